@@ -0,0 +1,107 @@
+//! Owned versus arena-backed parsing of a multi-megabyte document.
+//!
+//! Allocation counts of a single parse are printed before the timings.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use markdown_rs::{ast::arena::Arena, parser::Parser};
+
+/// Counts the allocations of the whole process.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const FIXTURE: &str = "# Heading with *emphasis* and `code`\n\n\
+    A paragraph with [a link](https://example.com \"title\"), ![an image](image.png) and a\n\
+    [reference][ref]. Some **strong** text follows.\n\n\
+    > A quote with a list:\n>\n> - one\n> - two\n\n\
+    ```rust\nfn main() {}\n```\n\n\
+    [ref]: https://example.com/ref\n\n";
+
+fn fixture() -> String {
+    // about 2.5MB.
+    FIXTURE.repeat(10_000)
+}
+
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+
+    drop(black_box(f()));
+
+    ALLOCATIONS.load(Ordering::Relaxed) - start
+}
+
+fn arena(c: &mut Criterion) {
+    let source = fixture();
+
+    let owned = allocations(|| Parser::new(source.as_str()).parse().unwrap());
+
+    let arena = allocations(|| {
+        let arena = Arena::new();
+
+        Parser::new(source.as_str())
+            .parse_in(&arena)
+            .unwrap()
+            .children
+            .len()
+    });
+
+    println!(
+        "allocations for {} bytes: owned {}, arena {} ({:.0}% fewer)",
+        source.len(),
+        owned,
+        arena,
+        100.0 - arena as f64 * 100.0 / owned as f64
+    );
+
+    let mut group = c.benchmark_group("arena");
+
+    group.throughput(Throughput::Bytes(source.len() as u64));
+    group.sample_size(20);
+
+    group.bench_function("owned", |b| {
+        b.iter(|| Parser::new(black_box(source.as_str())).parse().unwrap())
+    });
+
+    group.bench_function("arena", |b| {
+        b.iter(|| {
+            let arena = Arena::new();
+
+            Parser::new(black_box(source.as_str()))
+                .parse_in(&arena)
+                .unwrap()
+                .children
+                .len()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, arena);
+criterion_main!(benches);
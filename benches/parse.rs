@@ -0,0 +1,108 @@
+//! Lexing, parsing and HTML rendering throughput over representative fixtures.
+//!
+//! - `readme`: a real-world README, `benches/fixtures/readme.md`.
+//! - `emphasis`: `*a` repeated 10k times, where emphasis resolution can go quadratic.
+//! - `tables`: a large table-heavy document, `benches/fixtures/tables.md`.
+//! - `brackets`: about 100KB of nested, escaped, unbalanced brackets and parentheses, where
+//!   link matching can go quadratic.
+//!
+//! `parse+cancel` parses with an unset cancellation flag and a progress callback, to compare
+//! with `parse`: the hooks are checked per top-level block and must not show.
+//!
+//! Before measuring, the pathological fixtures are parsed once in release builds and must stay
+//! under [`CANARY`]: a quadratic regression fails `cargo bench` outright.
+
+use std::{
+    sync::{atomic::AtomicBool, Arc},
+    time::{Duration, Instant},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use markdown_rs::{
+    html::HtmlOptions,
+    lexer::Lexer,
+    parser::{Parser, ParserOptions},
+};
+
+/// Soft upper bound of parsing a pathological fixture, generous to absorb slow CI runners.
+const CANARY: Duration = Duration::from_millis(100);
+
+fn fixtures() -> [(&'static str, String); 4] {
+    [
+        ("readme", include_str!("fixtures/readme.md").to_owned()),
+        ("emphasis", "*a".repeat(10_000)),
+        ("tables", include_str!("fixtures/tables.md").to_owned()),
+        (
+            "brackets",
+            "[a [b] c](d (e) f) \\] [g](h ![i [j](k ".repeat(2_500),
+        ),
+    ]
+}
+
+fn canary(source: &str) {
+    let start = Instant::now();
+
+    black_box(Parser::new(source).parse().unwrap());
+
+    let elapsed = start.elapsed();
+
+    if cfg!(not(debug_assertions)) {
+        assert!(
+            elapsed < CANARY,
+            "parsing a pathological fixture took {:?}, over {:?}",
+            elapsed,
+            CANARY
+        );
+    }
+}
+
+fn parse(c: &mut Criterion) {
+    let fixtures = fixtures();
+
+    canary(&fixtures[1].1);
+    canary(&fixtures[3].1);
+
+    for (name, source) in &fixtures {
+        let mut group = c.benchmark_group(*name);
+
+        group.throughput(Throughput::Bytes(source.len() as u64));
+
+        group.bench_function("lex", |b| {
+            b.iter(|| Lexer::new(black_box(source.as_str())).count())
+        });
+
+        group.bench_function("parse", |b| {
+            b.iter(|| Parser::new(black_box(source.as_str())).parse().unwrap())
+        });
+
+        let options = ParserOptions {
+            cancel: Some(Arc::new(AtomicBool::new(false))),
+            progress: Some(Arc::new(|offset| {
+                black_box(offset);
+            })),
+            ..Default::default()
+        };
+
+        group.bench_function("parse+cancel", |b| {
+            b.iter(|| {
+                Parser::with_options(black_box(source.as_str()), options.clone())
+                    .parse()
+                    .unwrap()
+            })
+        });
+
+        group.bench_function("parse+html", |b| {
+            b.iter(|| {
+                Parser::new(black_box(source.as_str()))
+                    .parse()
+                    .unwrap()
+                    .to_html(&HtmlOptions::default())
+            })
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
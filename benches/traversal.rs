@@ -0,0 +1,65 @@
+//! Traversal throughput over a large parsed document.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use markdown_rs::{
+    ast::{walk, Document, Node, Text, Visitor},
+    parser::Parser,
+};
+
+const FIXTURE: &str = "# Heading with *emphasis* and `code`\n\n\
+    A paragraph with [a link](https://example.com \"title\"), ![an image](image.png) and a\n\
+    [reference][ref]. Some **strong** text follows.\n\n\
+    > A quote with a list:\n>\n> - one\n> - two\n\n\
+    ```rust\nfn main() {}\n```\n\n\
+    [ref]: https://example.com/ref\n\n";
+
+fn fixture() -> String {
+    FIXTURE.repeat(1000)
+}
+
+#[derive(Default)]
+struct TextLength(usize);
+
+impl Visitor for TextLength {
+    fn visit_text(&mut self, node: &Text) {
+        self.0 += node.value.len();
+    }
+}
+
+fn walk_document(document: &Document) -> usize {
+    let mut visitor = TextLength::default();
+
+    for child in &document.children {
+        walk(child, &mut visitor);
+    }
+
+    visitor.0
+}
+
+fn traversal(c: &mut Criterion) {
+    let source = fixture();
+
+    let document = Parser::new(source.as_str()).parse().unwrap();
+
+    let nodes = document.descendants().count() as u64;
+
+    let mut group = c.benchmark_group("traversal");
+
+    group.throughput(Throughput::Elements(nodes));
+
+    group.bench_function("walk", |b| b.iter(|| walk_document(black_box(&document))));
+
+    group.bench_function("descendants", |b| {
+        b.iter(|| {
+            black_box(&document)
+                .descendants()
+                .filter(|node| matches!(node, Node::Text(_)))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, traversal);
+criterion_main!(benches);
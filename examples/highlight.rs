@@ -0,0 +1,71 @@
+//! Colors a markdown file for the terminal, line by line, from the lexer tokens alone.
+//!
+//! ```sh
+//! cargo run --example highlight -- README.md
+//! ```
+//!
+//! Reads the standard input without a path.
+
+use std::{
+    env,
+    fs::read_to_string,
+    io::{self, Read, Write},
+};
+
+use markdown_rs::lexer::{Lexer, TokenKind};
+
+const RESET: &str = "\x1b[0m";
+
+/// Returns the ANSI escape sequence of `kind`, `None` for the default color.
+fn color(kind: TokenKind) -> Option<&'static str> {
+    match kind {
+        TokenKind::Pounds => Some("\x1b[1;34m"),
+        TokenKind::Asterisks | TokenKind::Underscores => Some("\x1b[35m"),
+        TokenKind::Backticks => Some("\x1b[33m"),
+        TokenKind::Dashes | TokenKind::Pluses | TokenKind::Align => Some("\x1b[36m"),
+        TokenKind::GreaterThans | TokenKind::KeyChar => Some("\x1b[32m"),
+        TokenKind::Comment
+        | TokenKind::Cdata
+        | TokenKind::Declaration
+        | TokenKind::ProcessingInstruction => Some("\x1b[90m"),
+        TokenKind::PlainText | TokenKind::WhiteSpaces | TokenKind::LineBreaks | TokenKind::Eof => {
+            None
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let source = match env::args().nth(1) {
+        Some(path) => read_to_string(path)?,
+        None => {
+            let mut source = String::new();
+
+            io::stdin().read_to_string(&mut source)?;
+
+            source
+        }
+    };
+
+    let mut lexer = Lexer::new(&source);
+
+    let mut out = io::stdout().lock();
+
+    loop {
+        let line = lexer.tokenize_line();
+
+        if line.is_empty() {
+            break;
+        }
+
+        for token in line {
+            let text = &source[token.to_range()];
+
+            match color(token.kind()) {
+                Some(color) => write!(out, "{}{}{}", color, text, RESET)?,
+                None => write!(out, "{}", text)?,
+            }
+        }
+    }
+
+    out.flush()
+}
@@ -0,0 +1,19 @@
+//! Lexes, parses and renders arbitrary input, run with `cargo +nightly fuzz run parse`.
+//!
+//! Seeds are in `corpus/parse`, crashing inputs are saved to `artifacts/parse`: add them to
+//! the corpus once fixed so regressions reproduce.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use markdown_rs::{html::HtmlOptions, lexer::Lexer, parser::Parser};
+
+fuzz_target!(|data: &[u8]| {
+    let source = String::from_utf8_lossy(data);
+
+    Lexer::new(&source).count();
+
+    if let Ok(document) = Parser::new(source.as_ref()).parse() {
+        document.to_html(&HtmlOptions::default());
+    }
+});
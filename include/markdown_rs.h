@@ -0,0 +1,85 @@
+#ifndef MARKDOWN_RS_H
+#define MARKDOWN_RS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+// Status codes of [`MdError`].
+typedef enum MdStatus {
+  MdStatus_Ok = 0,
+  // A required pointer argument is null.
+  MdStatus_NullArgument = 1,
+  // The source is not valid UTF-8, the offset is that of the first invalid byte.
+  MdStatus_InvalidUtf8 = 2,
+  // The parser reported an error.
+  MdStatus_ParseError = 3,
+  // The library panicked, this is a bug.
+  MdStatus_Panic = 4,
+} MdStatus;
+
+// Opaque handle of a parsed document, owning its tree.
+typedef struct MdDocument MdDocument;
+
+// Error out-parameter of [`md_parse`].
+typedef struct MdError {
+  MdStatus code;
+  // Byte offset in the source the error relates to, `0` if none.
+  size_t offset;
+} MdError;
+
+// UTF-8 buffer allocated by this library, free it with [`md_buffer_free`].
+//
+// `data` is followed by a NUL byte not counted in `len`, so it can be used as a C string
+// when the content has no interior NUL. `data` is null if the call failed.
+typedef struct MdBuffer {
+  char *data;
+  size_t len;
+} MdBuffer;
+
+// Parse the `len` bytes of UTF-8 markdown at `source`.
+//
+// Returns null and fills `error` on failure, `error` may be null. The returned document is
+// independent of `source` and must be freed with [`md_document_free`].
+//
+// # Safety
+//
+// `source` must point to `len` readable bytes, `error` must be null or point to a writable
+// [`MdError`].
+MdDocument *md_parse(const char *source, size_t len, MdError *error);
+
+// Render `document` to HTML with the default [`HtmlOptions`], raw HTML is escaped.
+//
+// Returns a null buffer if `document` is null.
+//
+// # Safety
+//
+// `document` must be null or a live handle returned by [`md_parse`].
+MdBuffer md_render_html(const MdDocument *document);
+
+// Serialize `document` to mdast JSON.
+//
+// Returns a null buffer if `document` is null.
+//
+// # Safety
+//
+// `document` must be null or a live handle returned by [`md_parse`].
+MdBuffer md_document_to_json(const MdDocument *document);
+
+// Free a document returned by [`md_parse`], null is ignored.
+//
+// # Safety
+//
+// `document` must be null or a live handle returned by [`md_parse`], it is dangling
+// afterwards.
+void md_document_free(MdDocument *document);
+
+// Free a buffer returned by this library, null buffers are ignored.
+//
+// # Safety
+//
+// `buffer` must be unchanged from its return by this library, and not freed yet.
+void md_buffer_free(MdBuffer buffer);
+
+#endif  /* MARKDOWN_RS_H */
@@ -1,31 +1,111 @@
-use std::borrow::Cow;
-use std::{fmt::Debug, slice::Iter};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    fmt::{Debug, Formatter},
+    hash::{Hash, Hasher},
+    ops::RangeInclusive,
+    slice::Iter,
+};
 
 use thiserror::Error;
 
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod blocks;
+pub mod builder;
+pub mod code;
+pub mod comments;
+pub mod diff;
+pub mod excerpt;
+pub mod index;
+pub mod inspect;
+pub mod intern;
+pub mod links;
+pub mod lint;
+#[cfg(all(feature = "json", feature = "std"))]
+pub mod ndjson;
+pub mod normalize;
+pub mod prose;
+pub mod replace;
+pub mod resolve;
+pub mod sanitize;
+pub mod slug;
+pub mod smart;
+pub mod spans;
+pub mod stats;
+pub mod style;
+pub mod text;
+pub mod toc;
+pub mod transform;
+pub mod validate;
+pub mod visitors;
+
+pub use excerpt::excerpt;
+pub use normalize::normalize;
+pub use prose::prose_spans;
+pub use replace::replace_text;
+pub use smart::smart_punctuation;
+pub use validate::validate;
+
 /// `mdast` associated error type.
 #[derive(Error, Debug)]
-pub enum AstError {}
+pub enum AstError {
+    #[error("expect {expected:?} node, found {found:?}")]
+    KindMismatch { expected: NodeKind, found: NodeKind },
+    #[error("child index {index} out of bounds, children count is {len}")]
+    IndexOutOfBounds { index: usize, len: usize },
+    #[error("heading depth must be in 1..=6, found {0}")]
+    InvalidDepth(u8),
+    #[error("unknown node type {0:?}")]
+    UnknownKind(String),
+    #[error("{child:?} node at index {index} is not allowed in {parent:?}")]
+    InvalidChild {
+        parent: NodeKind,
+        child: NodeKind,
+        index: usize,
+    },
+    #[error("{0:?} node has an empty identifier")]
+    EmptyIdentifier(NodeKind),
+    #[error("expect {expected}, found {found:?}")]
+    InvalidValue {
+        expected: &'static str,
+        found: String,
+    },
+    #[error("table row has {found} cells, the header has {expected}")]
+    RowArityMismatch { expected: usize, found: usize },
+}
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// Represents the explicitness of a reference.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 #[cfg_attr(
     feature = "serde",
     derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "referencetype")
+    serde(rename_all = "lowercase")
 )]
 #[repr(C)]
 pub enum ReferenceType {
+    /// The reference is implicit, its identifier inferred from its content.
     Shortcut,
+    /// The reference is explicit, its identifier inferred from its content.
     Collapsed,
+    /// The reference is explicit, its identifier explicitly set.
     Full,
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "aligntype")
-)]
+/// Represents how phrasing content is aligned in a table column.
+///
+/// Serialized as the mdast `alignType`: `"left"`, `"right"`, `"center"`, or `null` for
+/// [`AlignType::None`].
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
 #[repr(C)]
 pub enum AlignType {
     Left,
@@ -34,9 +114,159 @@ pub enum AlignType {
     None,
 }
 
+impl ReferenceType {
+    /// Returns the mdast `referenceType` of this type: `"shortcut"`, `"collapsed"` or
+    /// `"full"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceType::Shortcut => "shortcut",
+            ReferenceType::Collapsed => "collapsed",
+            ReferenceType::Full => "full",
+        }
+    }
+}
+
+impl core::fmt::Display for ReferenceType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses an mdast `referenceType`.
+impl core::str::FromStr for ReferenceType {
+    type Err = AstError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shortcut" => Ok(ReferenceType::Shortcut),
+            "collapsed" => Ok(ReferenceType::Collapsed),
+            "full" => Ok(ReferenceType::Full),
+            _ => Err(AstError::InvalidValue {
+                expected: "referenceType",
+                found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl AlignType {
+    /// Returns the mdast `alignType` of this alignment: `"left"`, `"right"` or `"center"`,
+    /// and `"none"` for [`AlignType::None`], which mdast writes as `null`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlignType::Left => "left",
+            AlignType::Right => "right",
+            AlignType::Center => "center",
+            AlignType::None => "none",
+        }
+    }
+}
+
+impl core::fmt::Display for AlignType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses an mdast `alignType` or `"none"`, or a cell of a table delimiter row such as
+/// `:-:`.
+impl core::str::FromStr for AlignType {
+    type Err = AstError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "left" => return Ok(AlignType::Left),
+            "right" => return Ok(AlignType::Right),
+            "center" => return Ok(AlignType::Center),
+            "none" => return Ok(AlignType::None),
+            _ => {}
+        }
+
+        let cell = s.trim();
+
+        let left = cell.starts_with(':');
+        let right = cell.len() > 1 && cell.ends_with(':');
+
+        let dashes = &cell[usize::from(left)..cell.len() - usize::from(right)];
+
+        if dashes.is_empty() || dashes.bytes().any(|c| c != b'-') {
+            return Err(AstError::InvalidValue {
+                expected: "alignType",
+                found: s.to_owned(),
+            });
+        }
+
+        match (left, right) {
+            (true, true) => Ok(AlignType::Center),
+            (true, false) => Ok(AlignType::Left),
+            (false, true) => Ok(AlignType::Right),
+            (false, false) => Ok(AlignType::None),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AlignType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            AlignType::None => serializer.serialize_none(),
+            align => serializer.serialize_str(align.as_str()),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AlignType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)?.as_deref() {
+            None => Ok(AlignType::None),
+            Some("left") => Ok(AlignType::Left),
+            Some("right") => Ok(AlignType::Right),
+            Some("center") => Ok(AlignType::Center),
+            Some(other) => Err(serde::de::Error::unknown_variant(
+                other,
+                &["left", "right", "center"],
+            )),
+        }
+    }
+}
+
 /// `mdast` associated [Result] type.
 pub type AstResult<T> = Result<T, AstError>;
 
+/// Normalize a reference label into an identifier: whitespace runs collapse into one space,
+/// leading/trailing whitespace is stripped and the result is case-folded to lowercase.
+pub fn normalize_identifier(label: &str) -> String {
+    label
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .to_uppercase()
+        .to_lowercase()
+}
+
+/// Returns true if `a` and `b` are the same tree wherever they come from in the source: node
+/// kinds, fields and children are compared, source positions are not.
+pub fn eq_ignoring_positions(a: &Node, b: &Node) -> bool {
+    if a.kind() != b.kind() || a.value() != b.value() || a.attributes() != b.attributes() {
+        return false;
+    }
+
+    match (a.children(), b.children()) {
+        (Some(a), Some(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| eq_ignoring_positions(a, b))
+        }
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
 /// Flow content represent the sections of document.
 pub trait FlowContent {}
 
@@ -52,743 +282,4076 @@ pub trait TableContent {}
 /// Row content represent the cells in a row.
 pub trait RowContent {}
 
-/// [mdast](https://github.com/syntax-tree/mdast#list) variant type.
-#[derive(Clone, Eq, PartialEq)]
+/// Fieldless [`Node`] variant type, serialized the same as the node `type` tags.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 #[cfg_attr(
     feature = "serde",
     derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "type")
+    serde(rename_all = "camelCase")
 )]
+pub enum NodeKind {
+    #[cfg_attr(feature = "serde", serde(rename = "root", alias = "document"))]
+    Document,
+    Heading,
+    ThematicBreak,
+    Blockquote,
+    List,
+    ListItem,
+    Code,
+    Definition,
+    Text,
+    Emphasis,
+    Strong,
+    InlineCode,
+    Break,
+    Link,
+    LinkReference,
+    Image,
+    ImageReference,
+    Paragraph,
+    Delete,
+    FootnoteDefinition,
+    FootnoteReference,
+    Table,
+    TableRow,
+    TableCell,
+    Html,
+    Custom,
+}
+
+impl NodeKind {
+    /// Every kind, in [`Node`] variant order.
+    pub const ALL: [NodeKind; 26] = [
+        NodeKind::Document,
+        NodeKind::Heading,
+        NodeKind::ThematicBreak,
+        NodeKind::Blockquote,
+        NodeKind::List,
+        NodeKind::ListItem,
+        NodeKind::Code,
+        NodeKind::Definition,
+        NodeKind::Text,
+        NodeKind::Emphasis,
+        NodeKind::Strong,
+        NodeKind::InlineCode,
+        NodeKind::Break,
+        NodeKind::Link,
+        NodeKind::LinkReference,
+        NodeKind::Image,
+        NodeKind::ImageReference,
+        NodeKind::Paragraph,
+        NodeKind::Delete,
+        NodeKind::FootnoteDefinition,
+        NodeKind::FootnoteReference,
+        NodeKind::Table,
+        NodeKind::TableRow,
+        NodeKind::TableCell,
+        NodeKind::Html,
+        NodeKind::Custom,
+    ];
+
+    /// Returns the mdast `type` of nodes of this kind, which is also their serde tag.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeKind::Document => "root",
+            NodeKind::Heading => "heading",
+            NodeKind::ThematicBreak => "thematicBreak",
+            NodeKind::Blockquote => "blockquote",
+            NodeKind::List => "list",
+            NodeKind::ListItem => "listItem",
+            NodeKind::Code => "code",
+            NodeKind::Definition => "definition",
+            NodeKind::Text => "text",
+            NodeKind::Emphasis => "emphasis",
+            NodeKind::Strong => "strong",
+            NodeKind::InlineCode => "inlineCode",
+            NodeKind::Break => "break",
+            NodeKind::Link => "link",
+            NodeKind::LinkReference => "linkReference",
+            NodeKind::Image => "image",
+            NodeKind::ImageReference => "imageReference",
+            NodeKind::Paragraph => "paragraph",
+            NodeKind::Delete => "delete",
+            NodeKind::FootnoteDefinition => "footnoteDefinition",
+            NodeKind::FootnoteReference => "footnoteReference",
+            NodeKind::Table => "table",
+            NodeKind::TableRow => "tableRow",
+            NodeKind::TableCell => "tableCell",
+            NodeKind::Html => "html",
+            NodeKind::Custom => "custom",
+        }
+    }
+}
+
+impl core::fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses an mdast `type`. The `document` tag of earlier releases is still read as a
+/// [`NodeKind::Document`].
+impl core::str::FromStr for NodeKind {
+    type Err = AstError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "document" {
+            return Ok(NodeKind::Document);
+        }
+
+        NodeKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| AstError::UnknownKind(s.to_owned()))
+    }
+}
+
+/// Value of a [`NodeData`] entry: any JSON value with the `json` feature, a string without.
+#[cfg(feature = "json")]
+pub type DataValue = serde_json::Value;
+
+/// Value of a [`NodeData`] entry: any JSON value with the `json` feature, a string without.
+#[cfg(not(feature = "json"))]
+pub type DataValue = String;
+
+/// Information attached to a node by tools and transforms, such as a slug or a resolved url,
+/// like the [unist `data`](https://github.com/syntax-tree/unist#data) field.
+///
+/// Nodes hold it boxed, as it is rarely set: the parser leaves it empty. It is part of node
+/// equality and hashing, and serializes under `"data"` when set. The HTML renderer reads its `id` and `class` entries into the attributes
+/// of the element of a node.
+pub type NodeData = BTreeMap<String, DataValue>;
+
+/// Deserializes the depth of a heading, failing outside of [`Heading::DEPTHS`].
+#[cfg(feature = "serde")]
+fn deserialize_depth<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let depth: u8 = serde::Deserialize::deserialize(deserializer)?;
+
+    if !Heading::DEPTHS.contains(&depth) {
+        return Err(serde::de::Error::custom(AstError::InvalidDepth(depth)));
+    }
+
+    Ok(depth)
+}
+
+/// Deserializes the data of a node. Without the `json` feature, entries that aren't strings are
+/// dropped, such as the `hProperties` object of remark.
+#[cfg(feature = "serde")]
+fn deserialize_data<'de, D>(deserializer: D) -> Result<Option<Box<NodeData>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[cfg(feature = "json")]
+    {
+        serde::Deserialize::deserialize(deserializer)
+    }
+
+    #[cfg(not(feature = "json"))]
+    {
+        #[derive(serde::Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            String(String),
+            Other(serde::de::IgnoredAny),
+        }
+
+        let data: Option<BTreeMap<String, Entry>> = serde::Deserialize::deserialize(deserializer)?;
+
+        Ok(data
+            .map(|data| {
+                data.into_iter()
+                    .filter_map(|(name, value)| match value {
+                        Entry::String(value) => Some((name, value)),
+                        Entry::Other(_) => None,
+                    })
+                    .collect::<NodeData>()
+            })
+            .filter(|data| !data.is_empty())
+            .map(Box::new))
+    }
+}
+
+/// [mdast](https://github.com/syntax-tree/mdast#list) variant type.
+///
+/// Dropping, cloning, comparing, hashing and debug formatting a node use an explicit stack
+/// rather than recursion, so that they work on trees of any depth.
+#[derive(Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(tag = "type"))]
 pub enum Node<'cx> {
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "root", alias = "document"))]
     Document(Document<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "heading"))]
     Heading(Heading<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "thematicBreak"))]
     ThematicBreak(ThematicBreak),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "blockquote"))]
     Blockquote(Blockquote<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "list"))]
     List(List<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "listItem"))]
     ListItem(ListItem<'cx>),
-    #[serde(borrow)]
-    Code(Code<'cx>),
-    #[serde(borrow)]
-    Definition(Definition<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "code"))]
+    Code(Box<Code<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "definition"))]
+    Definition(Box<Definition<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "text"))]
     Text(Text<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "emphasis"))]
     Emphasis(Emphasis<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "strong"))]
     Strong(Strong<'cx>),
-    #[serde(borrow)]
+    #[cfg_attr(feature = "serde", serde(rename = "inlineCode"))]
     InlineCode(InlineCode<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "break"))]
     Break(Break),
-    #[serde(borrow)]
-    Link(Link<'cx>),
-    #[serde(borrow)]
-    LinkReference(LinkReference<'cx>),
-    #[serde(borrow)]
-    Image(Image<'cx>),
-    #[serde(borrow)]
-    ImageReference(ImageReference<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "link"))]
+    Link(Box<Link<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "linkReference"))]
+    LinkReference(Box<LinkReference<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "image"))]
+    Image(Box<Image<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "imageReference"))]
+    ImageReference(Box<ImageReference<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "paragraph"))]
+    Paragraph(Paragraph<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "delete"))]
+    Delete(Delete<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "footnoteDefinition"))]
+    FootnoteDefinition(Box<FootnoteDefinition<'cx>>),
+    #[cfg_attr(feature = "serde", serde(rename = "footnoteReference"))]
+    FootnoteReference(FootnoteReference<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "table"))]
+    Table(Table<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "tableRow"))]
+    TableRow(TableRow<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "tableCell"))]
+    TableCell(TableCell<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "html"))]
+    Html(Html<'cx>),
+    #[cfg_attr(feature = "serde", serde(rename = "custom"))]
+    Custom(Box<Custom<'cx>>),
 }
 
-impl<'cx> Debug for Node<'cx> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+/// Each node struct serializes its own `type` tag, so [`Node`] serializes as its inner node.
+#[cfg(feature = "serde")]
+impl<'cx> serde::Serialize for Node<'cx> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
         match self {
-            Node::Document(x) => x.fmt(f),
-            Node::Heading(x) => x.fmt(f),
-            Node::ThematicBreak(x) => x.fmt(f),
-            Node::Blockquote(x) => x.fmt(f),
-            Node::List(x) => x.fmt(f),
-            Node::ListItem(x) => x.fmt(f),
-            Node::Code(x) => x.fmt(f),
-            Node::Definition(x) => x.fmt(f),
-            Node::Text(x) => x.fmt(f),
-            Node::Emphasis(x) => x.fmt(f),
-            Node::Strong(x) => x.fmt(f),
-            Node::InlineCode(x) => x.fmt(f),
-            Node::Break(x) => x.fmt(f),
-            Node::Link(x) => x.fmt(f),
-            Node::LinkReference(x) => x.fmt(f),
-            Node::Image(x) => x.fmt(f),
-            Node::ImageReference(x) => x.fmt(f),
+            Node::Document(x) => x.serialize(serializer),
+            Node::Heading(x) => x.serialize(serializer),
+            Node::ThematicBreak(x) => x.serialize(serializer),
+            Node::Blockquote(x) => x.serialize(serializer),
+            Node::List(x) => x.serialize(serializer),
+            Node::ListItem(x) => x.serialize(serializer),
+            Node::Code(x) => x.serialize(serializer),
+            Node::Definition(x) => x.serialize(serializer),
+            Node::Text(x) => x.serialize(serializer),
+            Node::Emphasis(x) => x.serialize(serializer),
+            Node::Strong(x) => x.serialize(serializer),
+            Node::InlineCode(x) => x.serialize(serializer),
+            Node::Break(x) => x.serialize(serializer),
+            Node::Link(x) => x.serialize(serializer),
+            Node::LinkReference(x) => x.serialize(serializer),
+            Node::Image(x) => x.serialize(serializer),
+            Node::ImageReference(x) => x.serialize(serializer),
+            Node::Paragraph(x) => x.serialize(serializer),
+            Node::Delete(x) => x.serialize(serializer),
+            Node::FootnoteDefinition(x) => x.serialize(serializer),
+            Node::FootnoteReference(x) => x.serialize(serializer),
+            Node::Table(x) => x.serialize(serializer),
+            Node::TableRow(x) => x.serialize(serializer),
+            Node::TableCell(x) => x.serialize(serializer),
+            Node::Html(x) => x.serialize(serializer),
+            Node::Custom(x) => x.serialize(serializer),
         }
     }
 }
 
-impl<'cx> Node<'cx> {
-    /// Accept new [`Visitor`] to visit this `mdast`
-    pub fn accept<V: Visitor>(&self, visitor: &mut V) {
-        match self {
-            Node::Document(x) => visitor.visit_document(x),
-            Node::Heading(x) => visitor.visit_heading(x),
-            Node::ThematicBreak(x) => visitor.visit_thematic_break(x),
-            Node::Blockquote(x) => visitor.visit_blockquote(x),
-            Node::List(x) => visitor.visit_list(x),
-            Node::ListItem(x) => visitor.visit_list_item(x),
-            Node::Code(x) => visitor.visit_code(x),
-            Node::Definition(x) => visitor.visit_definition(x),
-            Node::Text(x) => visitor.visit_text(x),
-            Node::Emphasis(x) => visitor.visit_emphasis(x),
-            Node::Strong(x) => visitor.visit_strong(x),
-            Node::InlineCode(x) => visitor.visit_inline_code(x),
-            Node::Break(x) => visitor.visit_break(x),
-            Node::Link(x) => visitor.visit_link(x),
-            Node::LinkReference(x) => visitor.visit_link_reference(x),
-            Node::Image(x) => visitor.visit_image(x),
-            Node::ImageReference(x) => visitor.visit_image_reference(x),
+impl<'cx> Drop for Node<'cx> {
+    fn drop(&mut self) {
+        let Some(children) = self.children_mut() else {
+            return;
+        };
+
+        if children
+            .iter()
+            .all(|child| child.children().is_none_or(<[_]>::is_empty))
+        {
+            return;
+        }
+
+        // descendants are detached into a worklist, each one dropped once childless.
+        let mut nodes = core::mem::take(children);
+
+        while let Some(mut node) = nodes.pop() {
+            if let Some(children) = node.children_mut() {
+                nodes.append(children);
+            }
         }
     }
 }
 
-/// [mdast](https://github.com/syntax-tree/mdast#list) visitor must implement this trait.
-#[allow(unused_variables)]
-pub trait Visitor {
-    fn visit_document(&mut self, document: &Document) {}
+impl<'cx> Clone for Node<'cx> {
+    fn clone(&self) -> Self {
+        fn shallow<'a, 'cx>(node: &'a Node<'cx>) -> (Iter<'a, Node<'cx>>, Node<'cx>) {
+            let children = node.children().unwrap_or_default();
 
-    fn visit_heading(&mut self, heading: &Heading) {}
+            let mut copy = node.clone_shallow();
 
-    fn visit_thematic_break(&mut self, thematic_break: &ThematicBreak) {}
+            if let Some(copies) = copy.children_mut() {
+                copies.reserve_exact(children.len());
+            }
 
-    fn visit_blockquote(&mut self, blockquote: &Blockquote) {}
+            (children.iter(), copy)
+        }
 
-    fn visit_list(&mut self, node: &List) {}
+        // copies of the nodes being cloned, with their children left to clone.
+        let mut stack = vec![shallow(self)];
 
-    fn visit_list_item(&mut self, node: &ListItem) {}
+        loop {
+            let (children, _) = stack.last_mut().unwrap();
 
-    fn visit_code(&mut self, node: &Code) {}
+            if let Some(child) = children.next() {
+                stack.push(shallow(child));
+                continue;
+            }
 
-    fn visit_definition(&mut self, node: &Definition) {}
+            let (_, copy) = stack.pop().unwrap();
 
-    fn visit_text(&mut self, node: &Text) {}
+            match stack.last_mut() {
+                Some((_, parent)) => parent.children_mut().unwrap().push(copy),
+                None => return copy,
+            }
+        }
+    }
+}
 
-    fn visit_emphasis(&mut self, node: &Emphasis) {}
+impl<'cx> PartialEq for Node<'cx> {
+    fn eq(&self, other: &Self) -> bool {
+        // same fields and number of children in pre-order is the same tree.
+        let same = |a: &Node, b: &Node| {
+            a.children().map(<[_]>::len) == b.children().map(<[_]>::len) && a.eq_shallow(b)
+        };
 
-    fn visit_strong(&mut self, node: &Strong) {}
+        if !same(self, other) {
+            return false;
+        }
 
-    fn visit_inline_code(&mut self, node: &InlineCode) {}
+        let mut others = other.descendants();
 
-    fn visit_break(&mut self, node: &Break) {}
+        self.descendants()
+            .all(|node| others.next().is_some_and(|other| same(node, other)))
+            && others.next().is_none()
+    }
+}
 
-    fn visit_link(&mut self, node: &Link) {}
+impl<'cx> Hash for Node<'cx> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for node in core::iter::once(self).chain(self.descendants()) {
+            node.hash_shallow(state);
+        }
+    }
+}
 
-    fn visit_link_reference(&mut self, node: &LinkReference) {}
+impl<'cx> Debug for Node<'cx> {
+    /// Same output as a derived implementation, where each node formats as its inner struct.
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        fn indent(f: &mut Formatter<'_>, level: usize) -> core::fmt::Result {
+            (0..level).try_for_each(|_| f.write_str("    "))
+        }
 
-    fn visit_image(&mut self, node: &Image) {}
+        // a value of a pretty printed node at `level`, its own lines indented.
+        fn value(f: &mut Formatter<'_>, value: &dyn Debug, level: usize) -> core::fmt::Result {
+            if !f.alternate() {
+                return write!(f, "{:?}", value);
+            }
 
-    fn visit_image_reference(&mut self, node: &ImageReference) {}
-}
+            for (index, line) in format!("{:#?}", value).split('\n').enumerate() {
+                if index > 0 {
+                    f.write_str("\n")?;
+                    indent(f, level)?;
+                }
 
-/// Parent (UnistParent) represents an abstract interface in
-/// mdast containing other nodes (said to be children).
-pub trait Parent<'cx, Child>
-where
-    Child: Into<Node<'cx>>,
-{
-    type Iter<'a>: Iterator<Item = &'a Node<'cx>>
-    where
-        Self: 'a,
-        'cx: 'a;
+                f.write_str(line)?;
+            }
 
-    /// Addd one child node.
-    fn add_child(&mut self, node: Child) -> AstResult<()>;
+            Ok(())
+        }
 
-    /// Removes and returns the child [Node] at position `index`
-    fn remove_at(&mut self, index: usize) -> Node;
+        enum Step<'a, 'cx> {
+            Enter(&'a Node<'cx>, usize, bool),
+            Leave(&'a Node<'cx>, usize),
+        }
 
-    /// Return an iterator over children slice.
-    fn iter<'a>(&'a self) -> Self::Iter<'a>;
-}
+        let pretty = f.alternate();
 
-pub trait ParentEx<'cx> {
-    fn add_child_node(&mut self, node: Node<'cx>) -> AstResult<()>;
-}
+        let mut stack = vec![Step::Enter(self, 0, true)];
 
-macro_rules! parent {
-    ($node_name:ident) => {
-        impl<'cx, Child> Parent<'cx, Child> for $node_name<'cx>
-        where
-            Child: Into<Node<'cx>>,
-        {
-            type Iter<'a> = Iter<'a, Node<'cx>> where   'cx: 'a;
+        while let Some(step) = stack.pop() {
+            let (node, depth) =
+                match step {
+                    Step::Enter(node, depth, first) => {
+                        // children are separated by commas, one per line when pretty printed.
+                        match (pretty, depth) {
+                            (_, 0) => {}
+                            (true, _) => indent(f, 2 * depth)?,
+                            (false, _) if !first => f.write_str(", ")?,
+                            (false, _) => {}
+                        }
 
-            fn add_child(&mut self, node: Child) -> AstResult<()> {
-                self.children.push(node.into());
-                Ok(())
-            }
+                        let Some((name, _)) = node.debug_fields() else {
+                            value(f, node.debug_inner(), 2 * depth)?;
 
-            fn iter<'a>(&'a self) -> Self::Iter<'a> {
-                self.children.iter()
+                            if pretty && depth > 0 {
+                                f.write_str(",\n")?;
+                            }
+
+                            continue;
+                        };
+
+                        f.write_str(name)?;
+                        f.write_str(if pretty { " {\n" } else { " { " })?;
+                        indent(f, if pretty { 2 * depth + 1 } else { 0 })?;
+                        f.write_str("children: [")?;
+
+                        let children = node.children().unwrap_or_default();
+
+                        if !children.is_empty() {
+                            if pretty {
+                                f.write_str("\n")?;
+                            }
+
+                            stack.push(Step::Leave(node, depth));
+                            stack.extend(
+                                children.iter().enumerate().rev().map(|(index, child)| {
+                                    Step::Enter(child, depth + 1, index == 0)
+                                }),
+                            );
+
+                            continue;
+                        }
+
+                        (node, depth)
+                    }
+                    Step::Leave(node, depth) => {
+                        indent(f, if pretty { 2 * depth + 1 } else { 0 })?;
+
+                        (node, depth)
+                    }
+                };
+
+            f.write_str("]")?;
+
+            let (_, fields) = node.debug_fields().unwrap_or_default();
+
+            for (name, field) in fields {
+                if pretty {
+                    f.write_str(",\n")?;
+                    indent(f, 2 * depth + 1)?;
+                } else {
+                    f.write_str(", ")?;
+                }
+
+                write!(f, "{}: ", name)?;
+                value(f, field, 2 * depth + 1)?;
             }
 
-            fn remove_at(&mut self, index: usize) -> Node {
-                self.children.remove(index)
+            if pretty {
+                f.write_str(",\n")?;
+                indent(f, 2 * depth)?;
+                f.write_str("}")?;
+
+                if depth > 0 {
+                    f.write_str(",\n")?;
+                }
+            } else {
+                f.write_str(" }")?;
             }
         }
 
-        impl<'cx> ParentEx<'cx> for $node_name<'cx> {
-            fn add_child_node(&mut self, node: Node<'cx>) -> AstResult<()> {
-                self.children.push(node);
+        Ok(())
+    }
+}
 
-                Ok(())
-            }
+impl<'cx> Node<'cx> {
+    /// Returns a copy of this node without its children.
+    fn clone_shallow(&self) -> Self {
+        match self {
+            Node::Document(x) => Node::Document(Document {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::Heading(x) => Node::Heading(Heading {
+                children: vec![],
+                depth: x.depth,
+                data: x.data.clone(),
+            }),
+            Node::ThematicBreak(x) => Node::ThematicBreak(x.clone()),
+            Node::Blockquote(x) => Node::Blockquote(Blockquote {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::List(x) => Node::List(List {
+                children: vec![],
+                ordered: x.ordered,
+                start: x.start,
+                spread: x.spread,
+                data: x.data.clone(),
+            }),
+            Node::ListItem(x) => Node::ListItem(ListItem {
+                children: vec![],
+                checked: x.checked,
+                spread: x.spread,
+                data: x.data.clone(),
+            }),
+            Node::Code(x) => Node::Code(x.clone()),
+            Node::Definition(x) => Node::Definition(x.clone()),
+            Node::Text(x) => Node::Text(x.clone()),
+            Node::Emphasis(x) => Node::Emphasis(Emphasis {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::Strong(x) => Node::Strong(Strong {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::InlineCode(x) => Node::InlineCode(x.clone()),
+            Node::Break(x) => Node::Break(x.clone()),
+            Node::Link(x) => Node::Link(Box::new(Link {
+                children: vec![],
+                url: x.url.clone(),
+                title: x.title.clone(),
+                data: x.data.clone(),
+            })),
+            Node::LinkReference(x) => Node::LinkReference(Box::new(LinkReference {
+                children: vec![],
+                identifier: x.identifier.clone(),
+                label: x.label.clone(),
+                reference_type: x.reference_type.clone(),
+                data: x.data.clone(),
+            })),
+            Node::Image(x) => Node::Image(x.clone()),
+            Node::ImageReference(x) => Node::ImageReference(x.clone()),
+            Node::Paragraph(x) => Node::Paragraph(Paragraph {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::Delete(x) => Node::Delete(Delete {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::FootnoteDefinition(x) => Node::FootnoteDefinition(Box::new(FootnoteDefinition {
+                children: vec![],
+                identifier: x.identifier.clone(),
+                label: x.label.clone(),
+                data: x.data.clone(),
+            })),
+            Node::FootnoteReference(x) => Node::FootnoteReference(x.clone()),
+            Node::Table(x) => Node::Table(Table {
+                children: vec![],
+                align: x.align.clone(),
+                data: x.data.clone(),
+            }),
+            Node::TableRow(x) => Node::TableRow(TableRow {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::TableCell(x) => Node::TableCell(TableCell {
+                children: vec![],
+                data: x.data.clone(),
+            }),
+            Node::Html(x) => Node::Html(x.clone()),
+            Node::Custom(x) => Node::Custom(Box::new(Custom {
+                children: vec![],
+                name: x.name.clone(),
+                attributes: x.attributes.clone(),
+                data: x.data.clone(),
+            })),
         }
-    };
-    ($node_name:ident,$content_type: ident) => {
-        impl<'cx, Child> Parent<'cx, Child> for $node_name<'cx>
-        where
-            Child: Into<Node<'cx>> + $content_type,
-        {
-            type Iter<'a> = Iter<'a, Node<'cx>> where   'cx: 'a;
+    }
 
-            fn add_child(&mut self, node: Child) -> AstResult<()> {
-                self.children.push(node.into());
-                Ok(())
-            }
+    /// Returns true if this node and `other` are of the same kind with the same fields, their
+    /// children aside.
+    fn eq_shallow(&self, other: &Node) -> bool {
+        if self.data() != other.data() {
+            return false;
+        }
 
-            fn iter<'a>(&'a self) -> Self::Iter<'a> {
-                self.children.iter()
+        match (self, other) {
+            (Node::Document(_), Node::Document(_))
+            | (Node::Blockquote(_), Node::Blockquote(_))
+            | (Node::Emphasis(_), Node::Emphasis(_))
+            | (Node::Strong(_), Node::Strong(_))
+            | (Node::Paragraph(_), Node::Paragraph(_))
+            | (Node::Delete(_), Node::Delete(_))
+            | (Node::TableRow(_), Node::TableRow(_))
+            | (Node::TableCell(_), Node::TableCell(_)) => true,
+            (Node::Heading(a), Node::Heading(b)) => a.depth == b.depth,
+            (Node::List(a), Node::List(b)) => {
+                (a.ordered, a.start, a.spread) == (b.ordered, b.start, b.spread)
             }
-
-            fn remove_at(&mut self, index: usize) -> Node {
-                self.children.remove(index)
+            (Node::ListItem(a), Node::ListItem(b)) => {
+                (a.checked, a.spread) == (b.checked, b.spread)
+            }
+            (Node::Link(a), Node::Link(b)) => (&a.url, &a.title) == (&b.url, &b.title),
+            (Node::LinkReference(a), Node::LinkReference(b)) => {
+                (&a.identifier, &a.label, &a.reference_type)
+                    == (&b.identifier, &b.label, &b.reference_type)
+            }
+            (Node::FootnoteDefinition(a), Node::FootnoteDefinition(b)) => {
+                (&a.identifier, &a.label) == (&b.identifier, &b.label)
+            }
+            (Node::Table(a), Node::Table(b)) => a.align == b.align,
+            (Node::Custom(a), Node::Custom(b)) => {
+                (&a.name, &a.attributes) == (&b.name, &b.attributes)
             }
+            (Node::ThematicBreak(a), Node::ThematicBreak(b)) => a == b,
+            (Node::Code(a), Node::Code(b)) => a == b,
+            (Node::Definition(a), Node::Definition(b)) => a == b,
+            (Node::Text(a), Node::Text(b)) => a == b,
+            (Node::InlineCode(a), Node::InlineCode(b)) => a == b,
+            (Node::Break(a), Node::Break(b)) => a == b,
+            (Node::Image(a), Node::Image(b)) => a == b,
+            (Node::ImageReference(a), Node::ImageReference(b)) => a == b,
+            (Node::FootnoteReference(a), Node::FootnoteReference(b)) => a == b,
+            (Node::Html(a), Node::Html(b)) => a == b,
+            _ => false,
         }
+    }
 
-        impl<'cx> ParentEx<'cx> for $node_name<'cx> {
-            fn add_child_node(&mut self, node: Node<'cx>) -> AstResult<()> {
-                self.children.push(node);
+    /// Hash the kind, fields and number of children of this node.
+    fn hash_shallow<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        self.children().map(<[_]>::len).hash(state);
+        self.data().hash(state);
 
-                Ok(())
-            }
+        match self {
+            Node::Document(_)
+            | Node::Blockquote(_)
+            | Node::Emphasis(_)
+            | Node::Strong(_)
+            | Node::Paragraph(_)
+            | Node::Delete(_)
+            | Node::TableRow(_)
+            | Node::TableCell(_) => {}
+            Node::Heading(x) => x.depth.hash(state),
+            Node::List(x) => (x.ordered, x.start, x.spread).hash(state),
+            Node::ListItem(x) => (x.checked, x.spread).hash(state),
+            Node::Link(x) => (&x.url, &x.title).hash(state),
+            Node::LinkReference(x) => (&x.identifier, &x.label, &x.reference_type).hash(state),
+            Node::FootnoteDefinition(x) => (&x.identifier, &x.label).hash(state),
+            Node::Table(x) => x.align.hash(state),
+            Node::Custom(x) => (&x.name, &x.attributes).hash(state),
+            Node::ThematicBreak(x) => x.hash(state),
+            Node::Code(x) => x.hash(state),
+            Node::Definition(x) => x.hash(state),
+            Node::Text(x) => x.hash(state),
+            Node::InlineCode(x) => x.hash(state),
+            Node::Break(x) => x.hash(state),
+            Node::Image(x) => x.hash(state),
+            Node::ImageReference(x) => x.hash(state),
+            Node::FootnoteReference(x) => x.hash(state),
+            Node::Html(x) => x.hash(state),
         }
-    };
+    }
+
+    /// Returns the struct name and fields other than children of a parent node, for debug
+    /// formatting.
+    #[allow(clippy::type_complexity)]
+    fn debug_fields(&self) -> Option<(&'static str, Vec<(&'static str, &dyn Debug)>)> {
+        Some(match self {
+            Node::Document(x) => ("Document", vec![("data", &x.data as &dyn Debug)]),
+            Node::Heading(x) => (
+                "Heading",
+                vec![("depth", &x.depth as &dyn Debug), ("data", &x.data)],
+            ),
+            Node::Blockquote(x) => ("Blockquote", vec![("data", &x.data as &dyn Debug)]),
+            Node::List(x) => (
+                "List",
+                vec![
+                    ("ordered", &x.ordered as &dyn Debug),
+                    ("start", &x.start),
+                    ("spread", &x.spread),
+                    ("data", &x.data),
+                ],
+            ),
+            Node::ListItem(x) => (
+                "ListItem",
+                vec![
+                    ("checked", &x.checked as &dyn Debug),
+                    ("spread", &x.spread),
+                    ("data", &x.data),
+                ],
+            ),
+            Node::Emphasis(x) => ("Emphasis", vec![("data", &x.data as &dyn Debug)]),
+            Node::Strong(x) => ("Strong", vec![("data", &x.data as &dyn Debug)]),
+            Node::Link(x) => (
+                "Link",
+                vec![
+                    ("url", &x.url as &dyn Debug),
+                    ("title", &x.title),
+                    ("data", &x.data),
+                ],
+            ),
+            Node::LinkReference(x) => (
+                "LinkReference",
+                vec![
+                    ("identifier", &x.identifier as &dyn Debug),
+                    ("label", &x.label),
+                    ("reference_type", &x.reference_type),
+                    ("data", &x.data),
+                ],
+            ),
+            Node::Paragraph(x) => ("Paragraph", vec![("data", &x.data as &dyn Debug)]),
+            Node::Delete(x) => ("Delete", vec![("data", &x.data as &dyn Debug)]),
+            Node::FootnoteDefinition(x) => (
+                "FootnoteDefinition",
+                vec![
+                    ("identifier", &x.identifier as &dyn Debug),
+                    ("label", &x.label),
+                    ("data", &x.data),
+                ],
+            ),
+            Node::Table(x) => (
+                "Table",
+                vec![("align", &x.align as &dyn Debug), ("data", &x.data)],
+            ),
+            Node::TableRow(x) => ("TableRow", vec![("data", &x.data as &dyn Debug)]),
+            Node::TableCell(x) => ("TableCell", vec![("data", &x.data as &dyn Debug)]),
+            Node::Custom(x) => (
+                "Custom",
+                vec![
+                    ("name", &x.name as &dyn Debug),
+                    ("attributes", &x.attributes),
+                    ("data", &x.data),
+                ],
+            ),
+            _ => return None,
+        })
+    }
+
+    /// Returns the inner struct of a node, for debug formatting.
+    fn debug_inner(&self) -> &dyn Debug {
+        match self {
+            Node::Document(x) => x,
+            Node::Heading(x) => x,
+            Node::ThematicBreak(x) => x,
+            Node::Blockquote(x) => x,
+            Node::List(x) => x,
+            Node::ListItem(x) => x,
+            Node::Code(x) => x,
+            Node::Definition(x) => x,
+            Node::Text(x) => x,
+            Node::Emphasis(x) => x,
+            Node::Strong(x) => x,
+            Node::InlineCode(x) => x,
+            Node::Break(x) => x,
+            Node::Link(x) => x,
+            Node::LinkReference(x) => x,
+            Node::Image(x) => x,
+            Node::ImageReference(x) => x,
+            Node::Paragraph(x) => x,
+            Node::Delete(x) => x,
+            Node::FootnoteDefinition(x) => x,
+            Node::FootnoteReference(x) => x,
+            Node::Table(x) => x,
+            Node::TableRow(x) => x,
+            Node::TableCell(x) => x,
+            Node::Html(x) => x,
+            Node::Custom(x) => x,
+        }
+    }
 }
 
-macro_rules! node_into {
+impl<'cx> Node<'cx> {
+    /// Accept new [`Visitor`] to visit this `mdast`
+    pub fn accept<V: Visitor>(&self, visitor: &mut V) {
+        match self {
+            Node::Document(x) => visitor.visit_document(x),
+            Node::Heading(x) => visitor.visit_heading(x),
+            Node::ThematicBreak(x) => visitor.visit_thematic_break(x),
+            Node::Blockquote(x) => visitor.visit_blockquote(x),
+            Node::List(x) => visitor.visit_list(x),
+            Node::ListItem(x) => visitor.visit_list_item(x),
+            Node::Code(x) => visitor.visit_code(x),
+            Node::Definition(x) => visitor.visit_definition(x),
+            Node::Text(x) => visitor.visit_text(x),
+            Node::Emphasis(x) => visitor.visit_emphasis(x),
+            Node::Strong(x) => visitor.visit_strong(x),
+            Node::InlineCode(x) => visitor.visit_inline_code(x),
+            Node::Break(x) => visitor.visit_break(x),
+            Node::Link(x) => visitor.visit_link(x),
+            Node::LinkReference(x) => visitor.visit_link_reference(x),
+            Node::Image(x) => visitor.visit_image(x),
+            Node::ImageReference(x) => visitor.visit_image_reference(x),
+            Node::Paragraph(x) => visitor.visit_paragraph(x),
+            Node::Delete(x) => visitor.visit_delete(x),
+            Node::FootnoteDefinition(x) => visitor.visit_footnote_definition(x),
+            Node::FootnoteReference(x) => visitor.visit_footnote_reference(x),
+            Node::Table(x) => visitor.visit_table(x),
+            Node::TableRow(x) => visitor.visit_table_row(x),
+            Node::TableCell(x) => visitor.visit_table_cell(x),
+            Node::Html(x) => visitor.visit_html(x),
+            Node::Custom(x) => visitor.visit_custom(x),
+        }
+    }
+
+    /// Accept new [`TryVisitor`] to visit this `mdast`
+    pub fn try_accept<V: TryVisitor>(&self, visitor: &mut V) -> Result<Flow, V::Error> {
+        match self {
+            Node::Document(x) => visitor.visit_document(x),
+            Node::Heading(x) => visitor.visit_heading(x),
+            Node::ThematicBreak(x) => visitor.visit_thematic_break(x),
+            Node::Blockquote(x) => visitor.visit_blockquote(x),
+            Node::List(x) => visitor.visit_list(x),
+            Node::ListItem(x) => visitor.visit_list_item(x),
+            Node::Code(x) => visitor.visit_code(x),
+            Node::Definition(x) => visitor.visit_definition(x),
+            Node::Text(x) => visitor.visit_text(x),
+            Node::Emphasis(x) => visitor.visit_emphasis(x),
+            Node::Strong(x) => visitor.visit_strong(x),
+            Node::InlineCode(x) => visitor.visit_inline_code(x),
+            Node::Break(x) => visitor.visit_break(x),
+            Node::Link(x) => visitor.visit_link(x),
+            Node::LinkReference(x) => visitor.visit_link_reference(x),
+            Node::Image(x) => visitor.visit_image(x),
+            Node::ImageReference(x) => visitor.visit_image_reference(x),
+            Node::Paragraph(x) => visitor.visit_paragraph(x),
+            Node::Delete(x) => visitor.visit_delete(x),
+            Node::FootnoteDefinition(x) => visitor.visit_footnote_definition(x),
+            Node::FootnoteReference(x) => visitor.visit_footnote_reference(x),
+            Node::Table(x) => visitor.visit_table(x),
+            Node::TableRow(x) => visitor.visit_table_row(x),
+            Node::TableCell(x) => visitor.visit_table_cell(x),
+            Node::Html(x) => visitor.visit_html(x),
+            Node::Custom(x) => visitor.visit_custom(x),
+        }
+    }
+
+    /// Accept new [`VisitorMut`] to visit and possibly transform this `mdast`
+    pub fn accept_mut<V: VisitorMut<'cx>>(&mut self, visitor: &mut V) -> Transform<'cx> {
+        match self {
+            Node::Document(x) => visitor.visit_document(x),
+            Node::Heading(x) => visitor.visit_heading(x),
+            Node::ThematicBreak(x) => visitor.visit_thematic_break(x),
+            Node::Blockquote(x) => visitor.visit_blockquote(x),
+            Node::List(x) => visitor.visit_list(x),
+            Node::ListItem(x) => visitor.visit_list_item(x),
+            Node::Code(x) => visitor.visit_code(x),
+            Node::Definition(x) => visitor.visit_definition(x),
+            Node::Text(x) => visitor.visit_text(x),
+            Node::Emphasis(x) => visitor.visit_emphasis(x),
+            Node::Strong(x) => visitor.visit_strong(x),
+            Node::InlineCode(x) => visitor.visit_inline_code(x),
+            Node::Break(x) => visitor.visit_break(x),
+            Node::Link(x) => visitor.visit_link(x),
+            Node::LinkReference(x) => visitor.visit_link_reference(x),
+            Node::Image(x) => visitor.visit_image(x),
+            Node::ImageReference(x) => visitor.visit_image_reference(x),
+            Node::Paragraph(x) => visitor.visit_paragraph(x),
+            Node::Delete(x) => visitor.visit_delete(x),
+            Node::FootnoteDefinition(x) => visitor.visit_footnote_definition(x),
+            Node::FootnoteReference(x) => visitor.visit_footnote_reference(x),
+            Node::Table(x) => visitor.visit_table(x),
+            Node::TableRow(x) => visitor.visit_table_row(x),
+            Node::TableCell(x) => visitor.visit_table_cell(x),
+            Node::Html(x) => visitor.visit_html(x),
+            Node::Custom(x) => visitor.visit_custom(x),
+        }
+    }
+
+    /// Returns the [`NodeKind`] of this node.
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            Node::Document(_) => NodeKind::Document,
+            Node::Heading(_) => NodeKind::Heading,
+            Node::ThematicBreak(_) => NodeKind::ThematicBreak,
+            Node::Blockquote(_) => NodeKind::Blockquote,
+            Node::List(_) => NodeKind::List,
+            Node::ListItem(_) => NodeKind::ListItem,
+            Node::Code(_) => NodeKind::Code,
+            Node::Definition(_) => NodeKind::Definition,
+            Node::Text(_) => NodeKind::Text,
+            Node::Emphasis(_) => NodeKind::Emphasis,
+            Node::Strong(_) => NodeKind::Strong,
+            Node::InlineCode(_) => NodeKind::InlineCode,
+            Node::Break(_) => NodeKind::Break,
+            Node::Link(_) => NodeKind::Link,
+            Node::LinkReference(_) => NodeKind::LinkReference,
+            Node::Image(_) => NodeKind::Image,
+            Node::ImageReference(_) => NodeKind::ImageReference,
+            Node::Paragraph(_) => NodeKind::Paragraph,
+            Node::Delete(_) => NodeKind::Delete,
+            Node::FootnoteDefinition(_) => NodeKind::FootnoteDefinition,
+            Node::FootnoteReference(_) => NodeKind::FootnoteReference,
+            Node::Table(_) => NodeKind::Table,
+            Node::TableRow(_) => NodeKind::TableRow,
+            Node::TableCell(_) => NodeKind::TableCell,
+            Node::Html(_) => NodeKind::Html,
+            Node::Custom(_) => NodeKind::Custom,
+        }
+    }
+
+    /// Returns children of a parent node, or [`None`] for literal and void nodes.
+    pub fn children(&self) -> Option<&[Node<'cx>]> {
+        match self {
+            Node::Document(x) => Some(&x.children),
+            Node::Paragraph(x) => Some(&x.children),
+            Node::Heading(x) => Some(&x.children),
+            Node::Blockquote(x) => Some(&x.children),
+            Node::List(x) => Some(&x.children),
+            Node::ListItem(x) => Some(&x.children),
+            Node::Emphasis(x) => Some(&x.children),
+            Node::Strong(x) => Some(&x.children),
+            Node::Link(x) => Some(&x.children),
+            Node::LinkReference(x) => Some(&x.children),
+            Node::Delete(x) => Some(&x.children),
+            Node::FootnoteDefinition(x) => Some(&x.children),
+            Node::Table(x) => Some(&x.children),
+            Node::TableRow(x) => Some(&x.children),
+            Node::TableCell(x) => Some(&x.children),
+            Node::Custom(x) => Some(&x.children),
+            Node::ThematicBreak(_)
+            | Node::Code(_)
+            | Node::Definition(_)
+            | Node::Text(_)
+            | Node::InlineCode(_)
+            | Node::Break(_)
+            | Node::Image(_)
+            | Node::ImageReference(_)
+            | Node::FootnoteReference(_)
+            | Node::Html(_) => None,
+        }
+    }
+
+    /// Returns mutable children of a parent node, or [`None`] for literal and void nodes.
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node<'cx>>> {
+        match self {
+            Node::Document(x) => Some(&mut x.children),
+            Node::Paragraph(x) => Some(&mut x.children),
+            Node::Heading(x) => Some(&mut x.children),
+            Node::Blockquote(x) => Some(&mut x.children),
+            Node::List(x) => Some(&mut x.children),
+            Node::ListItem(x) => Some(&mut x.children),
+            Node::Emphasis(x) => Some(&mut x.children),
+            Node::Strong(x) => Some(&mut x.children),
+            Node::Link(x) => Some(&mut x.children),
+            Node::LinkReference(x) => Some(&mut x.children),
+            Node::Delete(x) => Some(&mut x.children),
+            Node::FootnoteDefinition(x) => Some(&mut x.children),
+            Node::Table(x) => Some(&mut x.children),
+            Node::TableRow(x) => Some(&mut x.children),
+            Node::TableCell(x) => Some(&mut x.children),
+            Node::Custom(x) => Some(&mut x.children),
+            Node::ThematicBreak(_)
+            | Node::Code(_)
+            | Node::Definition(_)
+            | Node::Text(_)
+            | Node::InlineCode(_)
+            | Node::Break(_)
+            | Node::Image(_)
+            | Node::ImageReference(_)
+            | Node::FootnoteReference(_)
+            | Node::Html(_) => None,
+        }
+    }
+
+    /// Returns a pre-order depth-first iterator over all descendants of this node,
+    /// excluding the node itself.
+    pub fn descendants(&self) -> Descendants<'_, 'cx> {
+        Descendants::new(self.children().unwrap_or_default())
+    }
+
+    /// Same as [`descendants`](Self::descendants), also yields the depth of each node.
+    pub fn descendants_with_depth(&self) -> DescendantsWithDepth<'_, 'cx> {
+        self.descendants().with_depth()
+    }
+
+    /// Returns true if this node can contain other nodes.
+    pub fn is_parent(&self) -> bool {
+        self.children().is_some()
+    }
+
+    /// Returns the value of a literal node (text, inline code, code and html).
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            Node::Text(Text { value, .. })
+            | Node::InlineCode(InlineCode { value, .. })
+            | Node::Html(Html { value, .. }) => Some(value),
+            Node::Code(code) => Some(&code.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the fields of this node other than its children and literal value, in mdast
+    /// order, as `(name, value)` pairs. Absent optional fields are skipped, strings are quoted.
+    pub(crate) fn attributes(&self) -> Vec<(&'static str, String)> {
+        fn string(value: &str) -> String {
+            format!("{:?}", value)
+        }
+
+        let mut attributes = vec![];
+
+        let mut push = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                attributes.push((name, value));
+            }
+        };
+
+        match self {
+            Node::Heading(x) => push("depth", Some(x.depth.to_string())),
+            Node::List(x) => {
+                push("ordered", x.ordered.map(|x| x.to_string()));
+                push("start", x.start.map(|x| x.to_string()));
+                push("spread", x.spread.map(|x| x.to_string()));
+            }
+            Node::ListItem(x) => {
+                push("checked", x.checked.map(|x| x.to_string()));
+                push("spread", x.spread.map(|x| x.to_string()));
+            }
+            Node::Code(x) => {
+                push("lang", x.lang.as_deref().map(string));
+                push("meta", x.meta.as_deref().map(string));
+            }
+            Node::Definition(x) => {
+                push("identifier", Some(string(&x.identifier)));
+                push("label", x.label.as_deref().map(string));
+                push("url", Some(string(&x.url)));
+                push("title", x.title.as_deref().map(string));
+            }
+            Node::Link(x) => {
+                push("url", Some(string(&x.url)));
+                push("title", x.title.as_deref().map(string));
+            }
+            Node::Image(x) => {
+                push("url", Some(string(&x.url)));
+                push("title", x.title.as_deref().map(string));
+                push("alt", x.alt.as_deref().map(string));
+            }
+            Node::LinkReference(x) => {
+                push("identifier", Some(string(&x.identifier)));
+                push("label", x.label.as_deref().map(string));
+                push(
+                    "referenceType",
+                    Some(ReferenceType::to_string(&x.reference_type)),
+                );
+            }
+            Node::ImageReference(x) => {
+                push("alt", x.alt.as_deref().map(string));
+                push("identifier", Some(string(&x.identifier)));
+                push("label", x.label.as_deref().map(string));
+                push(
+                    "referenceType",
+                    Some(ReferenceType::to_string(&x.reference_type)),
+                );
+            }
+            Node::FootnoteDefinition(x) => {
+                push("identifier", Some(string(&x.identifier)));
+                push("label", x.label.as_deref().map(string));
+            }
+            Node::FootnoteReference(x) => {
+                push("identifier", Some(string(&x.identifier)));
+                push("label", x.label.as_deref().map(string));
+            }
+            Node::Table(x) => {
+                let align = x.align.iter().map(AlignType::as_str).collect::<Vec<_>>();
+
+                push("align", Some(format!("[{}]", align.join(", "))));
+            }
+            Node::Custom(x) => {
+                push("name", Some(string(&x.name)));
+
+                for (name, value) in &x.attributes {
+                    push(
+                        "attribute",
+                        Some(format!("{}={}", string(name), string(value))),
+                    );
+                }
+            }
+            Node::Document(_)
+            | Node::ThematicBreak(_)
+            | Node::Blockquote(_)
+            | Node::Text(_)
+            | Node::Emphasis(_)
+            | Node::Strong(_)
+            | Node::InlineCode(_)
+            | Node::Break(_)
+            | Node::Paragraph(_)
+            | Node::Delete(_)
+            | Node::TableRow(_)
+            | Node::TableCell(_)
+            | Node::Html(_) => {}
+        }
+
+        attributes
+    }
+
+    /// Returns the data attached to this node, see [`NodeData`].
+    pub fn data(&self) -> Option<&NodeData> {
+        let data = match self {
+            Node::Document(x) => &x.data,
+            Node::Heading(x) => &x.data,
+            Node::ThematicBreak(x) => &x.data,
+            Node::Blockquote(x) => &x.data,
+            Node::List(x) => &x.data,
+            Node::ListItem(x) => &x.data,
+            Node::Code(x) => &x.data,
+            Node::Definition(x) => &x.data,
+            Node::Text(x) => &x.data,
+            Node::Emphasis(x) => &x.data,
+            Node::Strong(x) => &x.data,
+            Node::InlineCode(x) => &x.data,
+            Node::Break(x) => &x.data,
+            Node::Link(x) => &x.data,
+            Node::LinkReference(x) => &x.data,
+            Node::Image(x) => &x.data,
+            Node::ImageReference(x) => &x.data,
+            Node::Paragraph(x) => &x.data,
+            Node::Delete(x) => &x.data,
+            Node::FootnoteDefinition(x) => &x.data,
+            Node::FootnoteReference(x) => &x.data,
+            Node::Table(x) => &x.data,
+            Node::TableRow(x) => &x.data,
+            Node::TableCell(x) => &x.data,
+            Node::Html(x) => &x.data,
+            Node::Custom(x) => &x.data,
+        };
+
+        data.as_deref()
+    }
+
+    /// Returns the data attached to this node, to set or change it.
+    pub fn data_mut(&mut self) -> &mut Option<Box<NodeData>> {
+        match self {
+            Node::Document(x) => &mut x.data,
+            Node::Heading(x) => &mut x.data,
+            Node::ThematicBreak(x) => &mut x.data,
+            Node::Blockquote(x) => &mut x.data,
+            Node::List(x) => &mut x.data,
+            Node::ListItem(x) => &mut x.data,
+            Node::Code(x) => &mut x.data,
+            Node::Definition(x) => &mut x.data,
+            Node::Text(x) => &mut x.data,
+            Node::Emphasis(x) => &mut x.data,
+            Node::Strong(x) => &mut x.data,
+            Node::InlineCode(x) => &mut x.data,
+            Node::Break(x) => &mut x.data,
+            Node::Link(x) => &mut x.data,
+            Node::LinkReference(x) => &mut x.data,
+            Node::Image(x) => &mut x.data,
+            Node::ImageReference(x) => &mut x.data,
+            Node::Paragraph(x) => &mut x.data,
+            Node::Delete(x) => &mut x.data,
+            Node::FootnoteDefinition(x) => &mut x.data,
+            Node::FootnoteReference(x) => &mut x.data,
+            Node::Table(x) => &mut x.data,
+            Node::TableRow(x) => &mut x.data,
+            Node::TableCell(x) => &mut x.data,
+            Node::Html(x) => &mut x.data,
+            Node::Custom(x) => &mut x.data,
+        }
+    }
+
+    /// Deep-convert all borrowed strings, returns a tree independent of the source text.
+    pub fn into_owned(self) -> Node<'static> {
+        self.rebind(&mut owned)
+    }
+
+    /// Converts every string of this tree with `f`.
+    fn rebind<'o>(mut self, f: &mut Rebind<'_, 'o>) -> Node<'o> {
+        match &mut self {
+            Node::Document(x) => Node::Document(x.take_rebind(f)),
+            Node::Heading(x) => Node::Heading(x.take_rebind(f)),
+            Node::ThematicBreak(x) => Node::ThematicBreak(x.clone()),
+            Node::Blockquote(x) => Node::Blockquote(x.take_rebind(f)),
+            Node::List(x) => Node::List(x.take_rebind(f)),
+            Node::ListItem(x) => Node::ListItem(x.take_rebind(f)),
+            Node::Code(x) => Node::Code(Box::new(x.take_rebind(f))),
+            Node::Definition(x) => Node::Definition(Box::new(x.take_rebind(f))),
+            Node::Text(x) => Node::Text(x.take_rebind(f)),
+            Node::Emphasis(x) => Node::Emphasis(x.take_rebind(f)),
+            Node::Strong(x) => Node::Strong(x.take_rebind(f)),
+            Node::InlineCode(x) => Node::InlineCode(x.take_rebind(f)),
+            Node::Break(x) => Node::Break(x.clone()),
+            Node::Link(x) => Node::Link(Box::new(x.take_rebind(f))),
+            Node::LinkReference(x) => Node::LinkReference(Box::new(x.take_rebind(f))),
+            Node::Image(x) => Node::Image(Box::new(x.take_rebind(f))),
+            Node::ImageReference(x) => Node::ImageReference(Box::new(x.take_rebind(f))),
+            Node::Paragraph(x) => Node::Paragraph(x.take_rebind(f)),
+            Node::Delete(x) => Node::Delete(x.take_rebind(f)),
+            Node::FootnoteDefinition(x) => Node::FootnoteDefinition(Box::new(x.take_rebind(f))),
+            Node::FootnoteReference(x) => Node::FootnoteReference(x.take_rebind(f)),
+            Node::Table(x) => Node::Table(x.take_rebind(f)),
+            Node::TableRow(x) => Node::TableRow(x.take_rebind(f)),
+            Node::TableCell(x) => Node::TableCell(x.take_rebind(f)),
+            Node::Html(x) => Node::Html(x.take_rebind(f)),
+            Node::Custom(x) => Node::Custom(Box::new(x.take_rebind(f))),
+        }
+    }
+}
+
+/// [mdast](https://github.com/syntax-tree/mdast#list) visitor must implement this trait.
+#[allow(unused_variables)]
+pub trait Visitor {
+    fn visit_document(&mut self, document: &Document) {}
+
+    fn visit_heading(&mut self, heading: &Heading) {}
+
+    fn visit_thematic_break(&mut self, thematic_break: &ThematicBreak) {}
+
+    fn visit_blockquote(&mut self, blockquote: &Blockquote) {}
+
+    fn visit_list(&mut self, node: &List) {}
+
+    fn visit_list_item(&mut self, node: &ListItem) {}
+
+    fn visit_code(&mut self, node: &Code) {}
+
+    fn visit_definition(&mut self, node: &Definition) {}
+
+    fn visit_text(&mut self, node: &Text) {}
+
+    fn visit_emphasis(&mut self, node: &Emphasis) {}
+
+    fn visit_strong(&mut self, node: &Strong) {}
+
+    fn visit_inline_code(&mut self, node: &InlineCode) {}
+
+    fn visit_break(&mut self, node: &Break) {}
+
+    fn visit_link(&mut self, node: &Link) {}
+
+    fn visit_link_reference(&mut self, node: &LinkReference) {}
+
+    fn visit_image(&mut self, node: &Image) {}
+
+    fn visit_image_reference(&mut self, node: &ImageReference) {}
+
+    fn visit_paragraph(&mut self, node: &Paragraph) {}
+
+    fn visit_delete(&mut self, node: &Delete) {}
+
+    fn visit_footnote_definition(&mut self, node: &FootnoteDefinition) {}
+
+    fn visit_footnote_reference(&mut self, node: &FootnoteReference) {}
+
+    fn visit_table(&mut self, node: &Table) {}
+
+    fn visit_table_row(&mut self, node: &TableRow) {}
+
+    fn visit_table_cell(&mut self, node: &TableCell) {}
+
+    fn visit_html(&mut self, node: &Html) {}
+
+    fn visit_custom(&mut self, node: &Custom) {}
+
+    /// Called by [`walk`] with any `node`, right before its `visit_*` method.
+    fn enter(&mut self, node: &Node) {}
+
+    /// Called by [`walk`] after `node` and all of its descendants have been visited.
+    fn leave(&mut self, node: &Node) {}
+}
+
+/// Tells [`try_walk`] whether to descend into the children of the visited node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Flow {
+    /// Descend into the children.
+    Continue,
+    /// Skip the children, continue with the next sibling.
+    SkipChildren,
+}
+
+/// [mdast](https://github.com/syntax-tree/mdast#list) fallible visitor must implement this trait.
+///
+/// Returning an error from any method aborts [`try_walk`].
+#[allow(unused_variables)]
+pub trait TryVisitor {
+    type Error;
+
+    fn visit_document(&mut self, node: &Document) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_heading(&mut self, node: &Heading) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_thematic_break(&mut self, node: &ThematicBreak) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_blockquote(&mut self, node: &Blockquote) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_list(&mut self, node: &List) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_list_item(&mut self, node: &ListItem) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_code(&mut self, node: &Code) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_definition(&mut self, node: &Definition) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_text(&mut self, node: &Text) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_emphasis(&mut self, node: &Emphasis) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_strong(&mut self, node: &Strong) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_inline_code(&mut self, node: &InlineCode) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_break(&mut self, node: &Break) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_link(&mut self, node: &Link) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_link_reference(&mut self, node: &LinkReference) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_image(&mut self, node: &Image) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_image_reference(&mut self, node: &ImageReference) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_paragraph(&mut self, node: &Paragraph) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_delete(&mut self, node: &Delete) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_footnote_definition(
+        &mut self,
+        node: &FootnoteDefinition,
+    ) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_footnote_reference(&mut self, node: &FootnoteReference) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_table(&mut self, node: &Table) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_table_row(&mut self, node: &TableRow) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_table_cell(&mut self, node: &TableCell) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_html(&mut self, node: &Html) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    fn visit_custom(&mut self, node: &Custom) -> Result<Flow, Self::Error> {
+        Ok(Flow::Continue)
+    }
+
+    /// Called by [`try_walk`] after `node` and all of its visited descendants.
+    fn leave(&mut self, node: &Node) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Outcome of a [`VisitorMut`] visit, applied by [`walk_mut`] to the visited node.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Transform<'cx> {
+    /// Keep the node and descend into its children.
+    Keep,
+    /// Replace the node, then descend into the replacement's children.
+    Replace(Node<'cx>),
+    /// Remove the node from its parent, its children are not visited.
+    Remove,
+}
+
+/// [mdast](https://github.com/syntax-tree/mdast#list) mutable visitor must implement this trait.
+#[allow(unused_variables)]
+pub trait VisitorMut<'cx> {
+    fn visit_document(&mut self, node: &mut Document<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_heading(&mut self, node: &mut Heading<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_thematic_break(&mut self, node: &mut ThematicBreak) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_blockquote(&mut self, node: &mut Blockquote<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_list(&mut self, node: &mut List<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_list_item(&mut self, node: &mut ListItem<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_code(&mut self, node: &mut Code<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_definition(&mut self, node: &mut Definition<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_text(&mut self, node: &mut Text<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_emphasis(&mut self, node: &mut Emphasis<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_strong(&mut self, node: &mut Strong<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_inline_code(&mut self, node: &mut InlineCode<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_break(&mut self, node: &mut Break) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_link(&mut self, node: &mut Link<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_link_reference(&mut self, node: &mut LinkReference<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_image(&mut self, node: &mut Image<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_image_reference(&mut self, node: &mut ImageReference<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_paragraph(&mut self, node: &mut Paragraph<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_delete(&mut self, node: &mut Delete<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_footnote_definition(&mut self, node: &mut FootnoteDefinition<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_footnote_reference(&mut self, node: &mut FootnoteReference<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_table(&mut self, node: &mut Table<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_table_row(&mut self, node: &mut TableRow<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_table_cell(&mut self, node: &mut TableCell<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_html(&mut self, node: &mut Html<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    fn visit_custom(&mut self, node: &mut Custom<'cx>) -> Transform<'cx> {
+        Transform::Keep
+    }
+
+    /// Called by [`walk_mut`] after `node` and all of its descendants have been visited.
+    fn leave(&mut self, node: &mut Node<'cx>) {}
+}
+
+/// Drive `visitor` over `node` and all of its descendants in pre-order depth-first order.
+///
+/// Every node is given to [`Visitor::enter`] and dispatched through [`Node::accept`] on the
+/// way down, and through [`Visitor::leave`] once its children are done.
+pub fn walk<V: Visitor>(node: &Node, visitor: &mut V) {
+    enum Step<'a, 'cx> {
+        Enter(&'a Node<'cx>),
+        Leave(&'a Node<'cx>),
+    }
+
+    let mut stack = vec![Step::Enter(node)];
+
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Enter(node) => {
+                visitor.enter(node);
+                node.accept(visitor);
+
+                stack.push(Step::Leave(node));
+                stack.extend(
+                    node.children()
+                        .unwrap_or_default()
+                        .iter()
+                        .rev()
+                        .map(Step::Enter),
+                );
+            }
+            Step::Leave(node) => visitor.leave(node),
+        }
+    }
+}
+
+/// Parent (UnistParent) represents an abstract interface in
+/// mdast containing other nodes (said to be children).
+pub trait Parent<'cx, Child>
+where
+    Child: Into<Node<'cx>>,
+{
+    type Iter<'a>: Iterator<Item = &'a Node<'cx>>
+    where
+        Self: 'a,
+        'cx: 'a;
+
+    /// Addd one child node.
+    fn add_child(&mut self, node: Child) -> AstResult<()>;
+
+    /// Inserts one child node at position `index`, shifting all children after it to the right.
+    ///
+    /// `index` may be equal to the children count, which appends the node.
+    fn insert_at(&mut self, index: usize, node: Child) -> AstResult<()>;
+
+    /// Replaces the child node at position `index`, returns the replaced node.
+    fn replace_at(&mut self, index: usize, node: Child) -> AstResult<Node<'cx>>;
+
+    /// Return an iterator over children slice.
+    fn iter<'a>(&'a self) -> Self::Iter<'a>;
+}
+
+/// Content model free counterpart of [`Parent`].
+pub trait ParentEx<'cx> {
+    /// Add one child node without content model checking.
+    fn add_child_node(&mut self, node: Node<'cx>) -> AstResult<()>;
+
+    /// Removes and returns the child [Node] at position `index`
+    fn remove_at(&mut self, index: usize) -> AstResult<Node<'cx>>;
+
+    /// Returns the number of children.
+    fn len(&self) -> usize;
+
+    /// Returns true if there are no children.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+macro_rules! parent {
+    ($node_name:ident $(, $content_type:ident)?) => {
+        impl<'cx, Child> Parent<'cx, Child> for $node_name<'cx>
+        where
+            Child: Into<Node<'cx>> $(+ $content_type)?,
+        {
+            type Iter<'a>
+                = Iter<'a, Node<'cx>>
+            where
+                'cx: 'a;
+
+            fn add_child(&mut self, node: Child) -> AstResult<()> {
+                self.children.push(node.into());
+                Ok(())
+            }
+
+            fn insert_at(&mut self, index: usize, node: Child) -> AstResult<()> {
+                let len = self.children.len();
+
+                if index > len {
+                    return Err(AstError::IndexOutOfBounds { index, len });
+                }
+
+                self.children.insert(index, node.into());
+
+                Ok(())
+            }
+
+            fn replace_at(&mut self, index: usize, node: Child) -> AstResult<Node<'cx>> {
+                let len = self.children.len();
+
+                match self.children.get_mut(index) {
+                    Some(child) => Ok(core::mem::replace(child, node.into())),
+                    None => Err(AstError::IndexOutOfBounds { index, len }),
+                }
+            }
+
+            fn iter<'a>(&'a self) -> Self::Iter<'a> {
+                self.children.iter()
+            }
+        }
+
+        impl<'cx> ParentEx<'cx> for $node_name<'cx> {
+            fn add_child_node(&mut self, node: Node<'cx>) -> AstResult<()> {
+                self.children.push(node);
+
+                Ok(())
+            }
+
+            fn remove_at(&mut self, index: usize) -> AstResult<Node<'cx>> {
+                let len = self.children.len();
+
+                if index >= len {
+                    return Err(AstError::IndexOutOfBounds { index, len });
+                }
+
+                Ok(self.children.remove(index))
+            }
+
+            fn len(&self) -> usize {
+                self.children.len()
+            }
+        }
+    };
+}
+
+macro_rules! node_into {
+    (box $node_name:ident<'a>) => {
+        impl<'a> From<$node_name<'a>> for Node<'a> {
+            fn from(value: $node_name<'a>) -> Self {
+                Node::$node_name(Box::new(value))
+            }
+        }
+
+        impl<'a> From<Box<$node_name<'a>>> for Node<'a> {
+            fn from(value: Box<$node_name<'a>>) -> Self {
+                Node::$node_name(value)
+            }
+        }
+
+        impl<'a, 'b> TryFrom<&'b Node<'a>> for &'b $node_name<'a> {
+            type Error = AstError;
+
+            fn try_from(value: &'b Node<'a>) -> Result<Self, Self::Error> {
+                match value {
+                    Node::$node_name(x) => Ok(x),
+                    node => Err(AstError::KindMismatch {
+                        expected: NodeKind::$node_name,
+                        found: node.kind(),
+                    }),
+                }
+            }
+        }
+    };
     ($node_name:ident<'a>) => {
         impl<'a> From<$node_name<'a>> for Node<'a> {
-            fn from(value: Text<'a>) -> Self {
+            fn from(value: $node_name<'a>) -> Self {
+                Node::$node_name(value)
+            }
+        }
+
+        impl<'a, 'b> TryFrom<&'b Node<'a>> for &'b $node_name<'a> {
+            type Error = AstError;
+
+            fn try_from(value: &'b Node<'a>) -> Result<Self, Self::Error> {
+                match value {
+                    Node::$node_name(x) => Ok(x),
+                    node => Err(AstError::KindMismatch {
+                        expected: NodeKind::$node_name,
+                        found: node.kind(),
+                    }),
+                }
+            }
+        }
+    };
+    ($node_name:ident) => {
+        impl<'a> From<$node_name> for Node<'a> {
+            fn from(value: $node_name) -> Self {
                 Node::$node_name(value)
             }
         }
-    };
-    ($node_name:ident) => {
-        impl<'a> From<$node_name> for Node<'a> {
-            fn from(value: Text<'a>) -> Self {
-                Node::$node_name(value)
+
+        impl<'a, 'b> TryFrom<&'b Node<'a>> for &'b $node_name {
+            type Error = AstError;
+
+            fn try_from(value: &'b Node<'a>) -> Result<Self, Self::Error> {
+                match value {
+                    Node::$node_name(x) => Ok(x),
+                    node => Err(AstError::KindMismatch {
+                        expected: NodeKind::$node_name,
+                        found: node.kind(),
+                    }),
+                }
+            }
+        }
+    };
+}
+
+node_into!(Document<'a>);
+node_into!(Heading<'a>);
+node_into!(ThematicBreak);
+node_into!(Blockquote<'a>);
+node_into!(List<'a>);
+node_into!(ListItem<'a>);
+node_into!(box Code<'a>);
+node_into!(box Definition<'a>);
+node_into!(Text<'a>);
+node_into!(Emphasis<'a>);
+node_into!(Strong<'a>);
+node_into!(InlineCode<'a>);
+node_into!(Break);
+node_into!(box Link<'a>);
+node_into!(box LinkReference<'a>);
+node_into!(box Image<'a>);
+node_into!(box ImageReference<'a>);
+node_into!(Paragraph<'a>);
+node_into!(Delete<'a>);
+node_into!(box FootnoteDefinition<'a>);
+node_into!(FootnoteReference<'a>);
+node_into!(Table<'a>);
+node_into!(TableRow<'a>);
+node_into!(TableCell<'a>);
+node_into!(Html<'a>);
+node_into!(box Custom<'a>);
+
+/// Function converting the strings of a tree, see [`IntoOwned`].
+type Rebind<'f, 'o> = dyn for<'s> FnMut(Cow<'s, str>) -> Cow<'o, str> + 'f;
+
+/// [`Rebind`] function detaching strings from the source lifetime.
+fn owned(value: Cow<'_, str>) -> Cow<'static, str> {
+    Cow::Owned(Cow::into_owned(value))
+}
+
+/// Convert the strings of a field value with a [`Rebind`] function, detaching it from the
+/// source lifetime.
+trait IntoOwned<'o> {
+    type Output;
+
+    fn rebind(self, f: &mut Rebind<'_, 'o>) -> Self::Output;
+
+    /// Same as [`rebind`](IntoOwned::rebind), leaving an empty value behind.
+    fn take_rebind(&mut self, f: &mut Rebind<'_, 'o>) -> Self::Output;
+}
+
+impl<'cx, 'o> IntoOwned<'o> for Cow<'cx, str> {
+    type Output = Cow<'o, str>;
+
+    fn rebind(self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        f(self)
+    }
+
+    fn take_rebind(&mut self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        f(core::mem::take(self))
+    }
+}
+
+impl<'cx, 'o> IntoOwned<'o> for Vec<Node<'cx>> {
+    type Output = Vec<Node<'o>>;
+
+    fn rebind(self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        self.into_iter().map(|node| node.rebind(f)).collect()
+    }
+
+    fn take_rebind(&mut self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        core::mem::take(self).rebind(f)
+    }
+}
+
+impl<'o, T: IntoOwned<'o>> IntoOwned<'o> for Option<T> {
+    type Output = Option<T::Output>;
+
+    fn rebind(self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        self.map(|value| value.rebind(f))
+    }
+
+    fn take_rebind(&mut self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        self.take().map(|value| value.rebind(f))
+    }
+}
+
+macro_rules! into_owned_copy {
+    ($($ty:ty),*) => {
+        $(
+            impl<'o> IntoOwned<'o> for $ty {
+                type Output = $ty;
+
+                fn rebind(self, _: &mut Rebind<'_, 'o>) -> Self::Output {
+                    self
+                }
+
+                fn take_rebind(&mut self, _: &mut Rebind<'_, 'o>) -> Self::Output {
+                    self.clone()
+                }
+            }
+        )*
+    };
+}
+
+into_owned_copy!(bool, u8, u32, usize, ReferenceType, Vec<AlignType>);
+
+impl<'cx, 'o> IntoOwned<'o> for BTreeMap<Cow<'cx, str>, Cow<'cx, str>> {
+    type Output = BTreeMap<Cow<'o, str>, Cow<'o, str>>;
+
+    fn rebind(self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        self.into_iter()
+            .map(|(name, value)| (f(name), f(value)))
+            .collect()
+    }
+
+    fn take_rebind(&mut self, f: &mut Rebind<'_, 'o>) -> Self::Output {
+        core::mem::take(self).rebind(f)
+    }
+}
+
+macro_rules! into_owned {
+    ($node_name:ident { $($field:ident),* }) => {
+        impl<'cx> $node_name<'cx> {
+            /// Deep-convert all borrowed strings, returns a tree independent of the source text.
+            pub fn into_owned(mut self) -> $node_name<'static> {
+                self.take_rebind(&mut owned)
+            }
+
+            /// Converts every string of this node and its descendants with `f`, leaving
+            /// empty fields behind.
+            fn take_rebind<'o>(&mut self, f: &mut Rebind<'_, 'o>) -> $node_name<'o> {
+                $node_name {
+                    $($field: IntoOwned::take_rebind(&mut self.$field, f),)*
+                    data: self.data.take(),
+                }
+            }
+        }
+    };
+}
+
+into_owned!(Document { children });
+into_owned!(Paragraph { children });
+into_owned!(Heading { children, depth });
+into_owned!(Blockquote { children });
+into_owned!(List {
+    children,
+    ordered,
+    start,
+    spread
+});
+into_owned!(ListItem {
+    children,
+    checked,
+    spread
+});
+into_owned!(Code { value, lang, meta });
+into_owned!(Definition {
+    identifier,
+    label,
+    url,
+    title
+});
+into_owned!(Text { value });
+into_owned!(Emphasis { children });
+into_owned!(Strong { children });
+into_owned!(InlineCode { value });
+into_owned!(Link {
+    children,
+    url,
+    title
+});
+into_owned!(LinkReference {
+    children,
+    identifier,
+    label,
+    reference_type
+});
+into_owned!(Image { url, title, alt });
+into_owned!(ImageReference {
+    alt,
+    identifier,
+    label,
+    reference_type
+});
+into_owned!(Delete { children });
+into_owned!(FootnoteDefinition {
+    children,
+    identifier,
+    label
+});
+into_owned!(FootnoteReference { identifier, label });
+into_owned!(Table { children, align });
+into_owned!(TableCell { children });
+into_owned!(Html { value });
+into_owned!(TableRow { children });
+into_owned!(Custom {
+    children,
+    name,
+    attributes
+});
+
+/// Document.
+///
+/// ```markdown
+/// > | a
+///     ^
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "root")
+)]
+pub struct Document<'cx> {
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> Document<'cx> {
+    /// Returns a pre-order depth-first iterator over all descendants of this document.
+    pub fn descendants(&self) -> Descendants<'_, 'cx> {
+        Descendants::new(&self.children)
+    }
+
+    /// Same as [`descendants`](Self::descendants), also yields the depth of each node.
+    pub fn descendants_with_depth(&self) -> DescendantsWithDepth<'_, 'cx> {
+        self.descendants().with_depth()
+    }
+
+    /// Returns all descendants matching `predicate`, in document order.
+    pub fn find_all<F>(&self, mut predicate: F) -> Vec<&Node<'cx>>
+    where
+        F: FnMut(&Node<'cx>) -> bool,
+    {
+        self.descendants().filter(|node| predicate(node)).collect()
+    }
+
+    /// Returns the first descendant matching `predicate`, in document order.
+    pub fn find_first<F>(&self, mut predicate: F) -> Option<&Node<'cx>>
+    where
+        F: FnMut(&Node<'cx>) -> bool,
+    {
+        self.descendants().find(|node| predicate(node))
+    }
+
+    /// Returns all descendant nodes of type `T`, e.g. `document.find_all_of::<Link>()`.
+    pub fn find_all_of<'a, T>(&'a self) -> Vec<&'a T>
+    where
+        &'a T: TryFrom<&'a Node<'cx>>,
+    {
+        self.descendants()
+            .filter_map(|node| node.try_into().ok())
+            .collect()
+    }
+
+    /// Returns the first descendant node of type `T`.
+    pub fn find_first_of<'a, T>(&'a self) -> Option<&'a T>
+    where
+        &'a T: TryFrom<&'a Node<'cx>>,
+    {
+        self.descendants().find_map(|node| node.try_into().ok())
+    }
+}
+
+parent!(Document);
+
+/// Paragraph (Parent) represents a unit of discourse dealing with a particular point or idea.
+/// For example, the following markdown:
+/// ```markdown
+/// Alpha bravo charlie.
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "paragraph")
+)]
+pub struct Paragraph<'cx> {
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(Paragraph, PhrasingContent);
+
+impl<'cx> FlowContent for Paragraph<'cx> {}
+
+/// Heading (Parent) represents a heading of a section.
+/// Heading can be used where flow content is expected.
+/// Its content model is phrasing content.
+///
+/// ```markdown
+/// # Alpha
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "heading")
+)]
+pub struct Heading<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// A depth field must be present.
+    /// A value of 1 is said to be the highest rank and 6 the lowest.
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "deserialize_depth"))]
+    pub depth: u8,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> Heading<'cx> {
+    /// Valid values of [`depth`](Self::depth).
+    pub const DEPTHS: RangeInclusive<u8> = 1..=6;
+
+    /// Create new [`Heading`] instance with provided `depth`, failing with
+    /// [`AstError::InvalidDepth`] if it is not in [`DEPTHS`](Self::DEPTHS).
+    pub fn new(depth: u8) -> AstResult<Self> {
+        if !Self::DEPTHS.contains(&depth) {
+            return Err(AstError::InvalidDepth(depth));
+        }
+
+        Ok(Heading {
+            children: Default::default(),
+            depth,
+            data: None,
+        })
+    }
+}
+
+parent!(Heading, PhrasingContent);
+
+impl<'cx> FlowContent for Heading<'cx> {}
+
+/// ThematicBreak (Node) represents a thematic break,
+/// such as a scene change in a story,
+/// a transition to another topic, or a new document.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "thematicBreak")
+)]
+pub struct ThematicBreak {
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl FlowContent for ThematicBreak {}
+
+/// Blockquote (Parent) represents a section quoted from somewhere else.
+/// ```markdown
+/// # Alpha
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "blockquote")
+)]
+pub struct Blockquote<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(Blockquote, PhrasingContent);
+
+impl<'cx> FlowContent for Blockquote<'cx> {}
+
+/// List (Parent) represents a list of items.
+///
+/// ```markdown
+/// 1. foo
+/// 2. bar
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "list")
+)]
+pub struct List<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// An ordered field can be present.
+    /// It represents that the items have been intentionally ordered (when true),
+    /// or that the order of items is not important (when false or not present).
+    pub ordered: Option<bool>,
+    /// A start field can be present.
+    /// It represents, when the ordered field is true, the starting number of the list.
+    pub start: Option<u32>,
+    /// A spread field can be present.
+    /// It represents that one or more of its children are separated with a blank line
+    /// from its siblings (when true), or not (when false or not present).
+    pub spread: Option<bool>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> List<'cx> {
+    /// Create new [`List`] instance, `start` is only kept for ordered lists.
+    pub fn new(ordered: bool, start: Option<u32>) -> Self {
+        List {
+            children: Default::default(),
+            ordered: Some(ordered),
+            start: if ordered { start } else { None },
+            spread: None,
+            data: None,
+        }
+    }
+}
+
+parent!(List, ListContent);
+
+impl<'cx> FlowContent for List<'cx> {}
+
+/// ListItem (Parent) represents an item in a List.
+///
+/// ```markdown
+/// - [x] foo
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "listItem")
+)]
+pub struct ListItem<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// A checked field can be present.
+    /// It represents whether the item is done (when true), not done (when false),
+    /// or indeterminate or not applicable (when null or not present).
+    pub checked: Option<bool>,
+    /// A spread field can be present.
+    /// It represents that the item contains two or more children separated
+    /// by a blank line (when true), or not (when false or not present).
+    pub spread: Option<bool>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> ListItem<'cx> {
+    /// Create new [`ListItem`] instance with provided task `checked` state.
+    pub fn new(checked: Option<bool>) -> Self {
+        ListItem {
+            children: Default::default(),
+            checked,
+            spread: None,
+            data: None,
+        }
+    }
+}
+
+parent!(ListItem, FlowContent);
+
+impl<'cx> ListContent for ListItem<'cx> {}
+
+/// Code (Literal) represents a block of preformatted text, such as ASCII art or computer code.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "code")
+)]
+pub struct Code<'cx> {
+    /// Literal data.
+    pub value: Cow<'cx, str>,
+    /// [`Option`] field to indicate code language.
+    pub lang: Option<Cow<'cx, str>>,
+    /// Meta data for code language.
+    pub meta: Option<Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> FlowContent for Code<'cx> {}
+
+/// Definition (Node) represents a resource.
+///
+/// ```markdown
+/// [alpha]: https://example.com "bravo"
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "definition")
+)]
+pub struct Definition<'cx> {
+    /// An identifier field must be present. It can match another node.
+    /// identifier is a source value: character escapes and character
+    /// references are not parsed. Its value must be normalized.
+    pub identifier: Cow<'cx, str>,
+    /// A label field can be present.
+    /// label is a string value: it works just like title on a link or a lang on
+    /// code: character escapes and character references are parsed.
+    pub label: Option<Cow<'cx, str>>,
+    /// A url field must be present. It represents a URL to the referenced resource.
+    pub url: Cow<'cx, str>,
+    /// A title field can be present.
+    /// It represents advisory information for the resource,
+    /// such as would be appropriate for a tooltip.
+    pub title: Option<Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> FlowContent for Definition<'cx> {}
+
+/// Text (Literal) represents everything that is just text.
+///
+/// Soft line breaks are kept as `\n` in the value, with the whitespace around them stripped;
+/// renderers decide how to display them. Hard line breaks are [`Break`] nodes.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "text")
+)]
+pub struct Text<'cx> {
+    /// Text literal value
+    pub value: Cow<'cx, str>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+/// Text can be used where phrasing content is expected.
+/// Its content is represented by its value field.
+impl<'cx> PhrasingContent for Text<'cx> {}
+
+/// Emphasis (Parent) represents stress emphasis of its contents.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "emphasis")
+)]
+pub struct Emphasis<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(Emphasis, PhrasingContent);
+
+/// Emphasis can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for Emphasis<'cx> {}
+
+/// Strong (Parent) represents strong importance, seriousness, or urgency for its contents.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "strong")
+)]
+pub struct Strong<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(Strong, PhrasingContent);
+
+/// Strong can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for Strong<'cx> {}
+
+/// InlineCode (Literal) represents a fragment of computer code, such as a file name,
+/// computer program, or anything a computer could parse.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "inlineCode")
+)]
+pub struct InlineCode<'cx> {
+    /// Text literal value
+    pub value: Cow<'cx, str>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+/// InlineCode can be used where phrasing content is expected.
+/// Its content is represented by its value field.
+impl<'cx> PhrasingContent for InlineCode<'cx> {}
+
+/// InlineCode (Literal) represents a fragment of computer code, such as a file name,
+/// computer program, or anything a computer could parse.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "break")
+)]
+pub struct Break {
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+/// Break can be used where phrasing content is expected.
+/// Its content is represented by its value field.
+impl PhrasingContent for Break {}
+
+/// Link (Parent) represents a hyperlink.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "link")
+)]
+pub struct Link<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// A url field must be present. It represents a URL to the referenced resource.
+    pub url: Cow<'cx, str>,
+    /// A title field can be present.
+    /// It represents advisory information for the resource,
+    /// such as would be appropriate for a tooltip.
+    pub title: Option<Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(Link, PhrasingContent);
+
+/// Link can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for Link<'cx> {}
+
+/// Link (Parent) represents a hyperlink.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "linkReference")
+)]
+pub struct LinkReference<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// An identifier field must be present. It can match another node.
+    /// identifier is a source value: character escapes and character
+    /// references are not parsed. Its value must be normalized.
+    pub identifier: Cow<'cx, str>,
+    /// A label field can be present.
+    /// label is a string value: it works just like title on a link or a lang on
+    /// code: character escapes and character references are parsed.
+    pub label: Option<Cow<'cx, str>>,
+    /// A referenceType field must be present. Its value must be a referenceType.
+    /// It represents the explicitness of the reference.
+    #[cfg_attr(feature = "serde", serde(rename = "referenceType"))]
+    pub reference_type: ReferenceType,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(LinkReference, PhrasingContent);
+
+/// LinkReference can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for LinkReference<'cx> {}
+
+/// Image (Node) represents an image.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "image")
+)]
+pub struct Image<'cx> {
+    /// A url field must be present. It represents a URL to the referenced resource.
+    pub url: Cow<'cx, str>,
+    /// A title field can be present.
+    /// It represents advisory information for the resource,
+    /// such as would be appropriate for a tooltip.
+    pub title: Option<Cow<'cx, str>>,
+    /// An alt field should be present.
+    /// It represents equivalent content for environments
+    /// that cannot represent the node as intended.
+    pub alt: Option<Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+/// Image can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for Image<'cx> {}
+
+/// ImageReference (Node) represents an image through association, or its original source if there is no association.
+///
+/// ```markdown
+/// ![alpha][bravo]
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "imageReference")
+)]
+pub struct ImageReference<'cx> {
+    /// An alt field should be present.
+    /// It represents equivalent content for environments
+    /// that cannot represent the node as intended.
+    pub alt: Option<Cow<'cx, str>>,
+    /// An identifier field must be present. It can match another node.
+    /// identifier is a source value: character escapes and character
+    /// references are not parsed. Its value must be normalized.
+    pub identifier: Cow<'cx, str>,
+    /// A label field can be present.
+    /// label is a string value: it works just like title on a link or a lang on
+    /// code: character escapes and character references are parsed.
+    pub label: Option<Cow<'cx, str>>,
+    /// A referenceType field must be present. Its value must be a referenceType.
+    /// It represents the explicitness of the reference.
+    #[cfg_attr(feature = "serde", serde(rename = "referenceType"))]
+    pub reference_type: ReferenceType,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+/// ImageReference can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for ImageReference<'cx> {}
+
+/// Delete (Parent) represents contents that are no longer accurate or no longer relevant.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "delete")
+)]
+pub struct Delete<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(Delete, PhrasingContent);
+
+/// ImageReference can be used where phrasing content is expected.
+/// Its content model is phrasing content.
+impl<'cx> PhrasingContent for Delete<'cx> {}
+
+/// FootnoteDefinition (Node) represents a marker through association.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "footnoteDefinition")
+)]
+pub struct FootnoteDefinition<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// An identifier field must be present. It can match another node.
+    /// identifier is a source value: character escapes and character
+    /// references are not parsed. Its value must be normalized.
+    pub identifier: Cow<'cx, str>,
+    /// A label field can be present.
+    /// label is a string value: it works just like title on a link or a lang on
+    /// code: character escapes and character references are parsed.
+    pub label: Option<Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(FootnoteDefinition, FlowContent);
+
+/// FootnoteDefinition can be used where flow content is expected.
+///  Its content model is also flow content.
+impl<'cx> FlowContent for FootnoteDefinition<'cx> {}
+
+/// FootnoteReference (Node) represents a marker through association.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "footnoteReference")
+)]
+pub struct FootnoteReference<'cx> {
+    /// An identifier field must be present. It can match another node.
+    /// identifier is a source value: character escapes and character
+    /// references are not parsed. Its value must be normalized.
+    pub identifier: Cow<'cx, str>,
+    /// A label field can be present.
+    /// label is a string value: it works just like title on a link or a lang on
+    /// code: character escapes and character references are parsed.
+    pub label: Option<Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+/// FootnoteReference can be used where phrasing content is expected.
+/// It has no content model.
+impl<'cx> PhrasingContent for FootnoteReference<'cx> {}
+
+/// Table (Parent) represents two-dimensional data.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "table")
+)]
+pub struct Table<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// An align field can be present. If present, it must be a list of alignTypes.
+    /// It represents how cells in columns are aligned.
+    pub align: Vec<AlignType>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> Table<'cx> {
+    /// Returns the header row, the first row of the table.
+    pub fn header(&self) -> Option<&TableRow<'cx>> {
+        match self.children.first()? {
+            Node::TableRow(row) => Some(row),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the body rows, every row but the header.
+    pub fn rows(&self) -> impl Iterator<Item = &TableRow<'cx>> + '_ {
+        self.children.iter().skip(1).filter_map(|node| match node {
+            Node::TableRow(row) => Some(row),
+            _ => None,
+        })
+    }
+
+    /// Returns the number of columns, the number of cells of the header row.
+    pub fn column_count(&self) -> usize {
+        self.header().map_or(0, |header| header.children.len())
+    }
+
+    /// Returns the alignment of column `column`, [`AlignType::None`] if `align` has no entry
+    /// for it.
+    pub fn aligned(&self, column: usize) -> AlignType {
+        self.align.get(column).cloned().unwrap_or(AlignType::None)
+    }
+
+    /// Appends `row` to the table, as its header if the table has no rows.
+    ///
+    /// Returns [`AstError::RowArityMismatch`] if `row` has not as many cells as the header.
+    pub fn add_row(&mut self, row: TableRow<'cx>) -> AstResult<()> {
+        if let Some(header) = self.header() {
+            if header.children.len() != row.children.len() {
+                return Err(AstError::RowArityMismatch {
+                    expected: header.children.len(),
+                    found: row.children.len(),
+                });
+            }
+        }
+
+        self.children.push(Node::TableRow(row));
+
+        Ok(())
+    }
+}
+
+parent!(Table, TableContent);
+
+/// FootnoteDefinition can be used where flow content is expected.
+///  Its content model is also flow content.
+impl<'cx> FlowContent for Table<'cx> {}
+
+/// TableCell (Parent) represents a header cell in a Table,
+///  if its parent is a head, or a data cell otherwise.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "tableCell")
+)]
+pub struct TableCell<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(TableCell, PhrasingContent);
+
+/// TableCell can be used where row content is expected.
+/// Its content model is phrasing content excluding Break nodes.
+impl<'cx> RowContent for TableCell<'cx> {}
+
+/// TableRow (Parent) represents a row of cells in a table.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "tableRow")
+)]
+pub struct TableRow<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+parent!(TableRow, RowContent);
+
+/// TableRow can be used where table content is expected. Its content model is row content.
+impl<'cx> TableContent for TableRow<'cx> {}
+
+/// Html (Literal) represents a fragment of raw HTML.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "html")
+)]
+pub struct Html<'cx> {
+    /// Raw HTML value
+    pub value: Cow<'cx, str>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+/// Html can be used where flow content is expected.
+impl<'cx> FlowContent for Html<'cx> {}
+
+/// Html can be used where phrasing content is expected.
+impl<'cx> PhrasingContent for Html<'cx> {}
+
+/// Custom (Parent) represents a node of a parser extension, such as a wiki link or
+/// highlighted text, see [`parser::extension`](crate::parser::extension).
+///
+/// Custom nodes serialize their children only, to HTML and markdown alike.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", rename = "custom")
+)]
+pub struct Custom<'cx> {
+    /// Children node list.
+    pub children: Vec<Node<'cx>>,
+    /// Name of the node, such as `"highlight"`.
+    pub name: Cow<'cx, str>,
+    /// Attributes of the node, such as the target of a wiki link.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub attributes: BTreeMap<Cow<'cx, str>, Cow<'cx, str>>,
+    /// Information of tools and transforms, see [`NodeData`].
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            deserialize_with = "deserialize_data"
+        )
+    )]
+    pub data: Option<Box<NodeData>>,
+}
+
+impl<'cx> Custom<'cx> {
+    /// Create new [`Custom`] node named `name`, without attributes.
+    pub fn new<N: Into<Cow<'cx, str>>>(name: N, children: Vec<Node<'cx>>) -> Self {
+        Custom {
+            children,
+            name: name.into(),
+            attributes: BTreeMap::new(),
+            data: None,
+        }
+    }
+}
+
+parent!(Custom);
+
+/// Custom can be used where flow content is expected.
+impl<'cx> FlowContent for Custom<'cx> {}
+
+/// Custom can be used where phrasing content is expected.
+impl<'cx> PhrasingContent for Custom<'cx> {}
+
+/// Drive fallible `visitor` over `node` and its descendants in pre-order depth-first order,
+/// stops at the first error and returns it.
+pub fn try_walk<V: TryVisitor>(node: &Node, visitor: &mut V) -> Result<(), V::Error> {
+    enum Step<'a, 'cx> {
+        Enter(&'a Node<'cx>),
+        Leave(&'a Node<'cx>),
+    }
+
+    let mut stack = vec![Step::Enter(node)];
+
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Enter(node) => {
+                let flow = node.try_accept(visitor)?;
+
+                stack.push(Step::Leave(node));
+
+                if flow == Flow::Continue {
+                    stack.extend(
+                        node.children()
+                            .unwrap_or_default()
+                            .iter()
+                            .rev()
+                            .map(Step::Enter),
+                    );
+                }
+            }
+            Step::Leave(node) => visitor.leave(node)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Drive mutable `visitor` over `node` and all of its descendants in pre-order depth-first order,
+/// applying the returned [`Transform`] of every visit.
+///
+/// The root node can be replaced but not removed, [`Transform::Remove`] on it is treated as
+/// [`Transform::Keep`].
+pub fn walk_mut<'cx, V: VisitorMut<'cx>>(node: &mut Node<'cx>, visitor: &mut V) {
+    if let Transform::Replace(replacement) = node.accept_mut(visitor) {
+        *node = replacement;
+    }
+
+    // the children being walked at each depth, taken out of their parent until it is left, so
+    // that deeply nested trees can't overflow the call stack.
+    let mut stack: Vec<(Vec<Node<'cx>>, usize)> = vec![];
+
+    if let Some(children) = node.children_mut() {
+        stack.push((core::mem::take(children), 0));
+    }
+
+    while let Some((children, index)) = stack.last_mut() {
+        if *index == children.len() {
+            let (children, _) = stack.pop().unwrap();
+
+            let Some((siblings, index)) = stack.last_mut() else {
+                *node.children_mut().unwrap() = children;
+                break;
+            };
+
+            let parent = &mut siblings[*index];
+
+            *parent.children_mut().unwrap() = children;
+
+            visitor.leave(parent);
+
+            *index += 1;
+
+            continue;
+        }
+
+        match children[*index].accept_mut(visitor) {
+            Transform::Keep => {}
+            Transform::Replace(replacement) => children[*index] = replacement,
+            Transform::Remove => {
+                // the next sibling shifts into `index`.
+                children.remove(*index);
+                continue;
+            }
+        }
+
+        let child = &mut children[*index];
+
+        match child.children_mut() {
+            Some(grandchildren) => {
+                let grandchildren = core::mem::take(grandchildren);
+
+                stack.push((grandchildren, 0));
+            }
+            None => {
+                visitor.leave(child);
+
+                *index += 1;
+            }
+        }
+    }
+
+    visitor.leave(node);
+}
+
+/// Pre-order depth-first iterator over the descendants of a node, see [`Node::descendants`].
+///
+/// Uses an explicit stack, so deeply nested trees can't overflow the call stack.
+#[derive(Clone, Debug)]
+pub struct Descendants<'a, 'cx> {
+    stack: Vec<(usize, &'a Node<'cx>)>,
+}
+
+impl<'a, 'cx> Descendants<'a, 'cx> {
+    fn new(children: &'a [Node<'cx>]) -> Self {
+        Descendants {
+            stack: children.iter().rev().map(|node| (1, node)).collect(),
+        }
+    }
+
+    /// Yields the depth along with each node, direct children have depth 1.
+    pub fn with_depth(self) -> DescendantsWithDepth<'a, 'cx> {
+        DescendantsWithDepth(self)
+    }
+
+    fn next_with_depth(&mut self) -> Option<(usize, &'a Node<'cx>)> {
+        let (depth, node) = self.stack.pop()?;
+
+        if let Some(children) = node.children() {
+            self.stack
+                .extend(children.iter().rev().map(|child| (depth + 1, child)));
+        }
+
+        Some((depth, node))
+    }
+}
+
+impl<'a, 'cx> Iterator for Descendants<'a, 'cx> {
+    type Item = &'a Node<'cx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_depth().map(|(_, node)| node)
+    }
+}
+
+/// [`Descendants`] iterator yielding `(depth, node)` pairs.
+#[derive(Clone, Debug)]
+pub struct DescendantsWithDepth<'a, 'cx>(Descendants<'a, 'cx>);
+
+impl<'a, 'cx> Iterator for DescendantsWithDepth<'a, 'cx> {
+    type Item = (usize, &'a Node<'cx>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_with_depth()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Counter {
+        kinds: BTreeMap<&'static str, usize>,
+        trace: Vec<String>,
+    }
+
+    impl Counter {
+        fn count(&mut self, kind: &'static str) {
+            *self.kinds.entry(kind).or_default() += 1;
+            self.trace.push(format!("enter {}", kind));
+        }
+    }
+
+    impl Visitor for Counter {
+        fn visit_document(&mut self, _: &Document) {
+            self.count("document");
+        }
+
+        fn visit_heading(&mut self, _: &Heading) {
+            self.count("heading");
+        }
+
+        fn visit_blockquote(&mut self, _: &Blockquote) {
+            self.count("blockquote");
+        }
+
+        fn visit_text(&mut self, _: &Text) {
+            self.count("text");
+        }
+
+        fn visit_emphasis(&mut self, _: &Emphasis) {
+            self.count("emphasis");
+        }
+
+        fn visit_thematic_break(&mut self, _: &ThematicBreak) {
+            self.count("thematicBreak");
+        }
+
+        fn leave(&mut self, node: &Node) {
+            self.trace
+                .push(format!("leave {:?}", core::mem::discriminant(node)));
+        }
+    }
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn test_walk() {
+        let mut heading = Heading::new(1).unwrap();
+
+        heading.add_child_node(text("a")).unwrap();
+        heading
+            .add_child_node(Node::Emphasis(Emphasis {
+                children: vec![text("b"), text("c")],
+                data: None,
+            }))
+            .unwrap();
+
+        let mut blockquote = Blockquote {
+            children: vec![],
+            data: None,
+        };
+
+        blockquote
+            .add_child_node(Node::Heading(heading.clone()))
+            .unwrap();
+
+        let document = Node::Document(Document {
+            children: vec![
+                Node::Heading(heading),
+                Node::ThematicBreak(ThematicBreak { data: None }),
+                Node::Blockquote(blockquote),
+            ],
+            data: None,
+        });
+
+        let mut counter = Counter::default();
+
+        walk(&document, &mut counter);
+
+        assert_eq!(
+            counter.kinds.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("blockquote", 1),
+                ("document", 1),
+                ("emphasis", 2),
+                ("heading", 2),
+                ("text", 6),
+                ("thematicBreak", 1),
+            ]
+        );
+
+        let enters = counter
+            .trace
+            .iter()
+            .filter_map(|step| step.strip_prefix("enter "))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            enters[..6],
+            ["document", "heading", "text", "emphasis", "text", "text"]
+        );
+
+        // every entered node is left exactly once, the root last.
+        assert_eq!(counter.trace.len(), 26);
+        assert_eq!(
+            counter.trace.last().unwrap(),
+            &format!("leave {:?}", core::mem::discriminant(&document))
+        );
+    }
+
+    #[test]
+    fn test_children() {
+        let mut row = TableRow {
+            children: vec![],
+            data: None,
+        };
+
+        row.add_child(TableCell {
+            children: vec![text("a")],
+            data: None,
+        })
+        .unwrap();
+
+        let mut document = Node::Document(Document {
+            children: vec![
+                Node::Paragraph(Paragraph {
+                    children: vec![
+                        text("b"),
+                        Node::Strong(Strong {
+                            children: vec![text("c")],
+                            data: None,
+                        }),
+                    ],
+                    data: None,
+                }),
+                Node::Table(Table {
+                    children: vec![Node::TableRow(row)],
+                    align: vec![AlignType::None],
+                    data: None,
+                }),
+                Node::ThematicBreak(ThematicBreak { data: None }),
+            ],
+            data: None,
+        });
+
+        fn count(node: &Node) -> (usize, usize) {
+            match node.children() {
+                Some(children) => children
+                    .iter()
+                    .map(count)
+                    .fold((1, 0), |acc, n| (acc.0 + n.0, acc.1 + n.1)),
+                None => (0, 1),
+            }
+        }
+
+        // (parents, leaves)
+        assert_eq!(count(&document), (6, 4));
+        assert!(document.is_parent());
+        assert!(!text("d").is_parent());
+        assert!(text("d").children().is_none());
+
+        fn prune_text(node: &mut Node) {
+            if let Some(children) = node.children_mut() {
+                children.retain(|child| !matches!(child, Node::Text(_)));
+                children.iter_mut().for_each(prune_text);
+            }
+        }
+
+        prune_text(&mut document);
+
+        assert_eq!(count(&document), (6, 1));
+    }
+
+    struct LinkRewriter;
+
+    impl<'cx> VisitorMut<'cx> for LinkRewriter {
+        fn visit_link(&mut self, node: &mut Link<'cx>) -> Transform<'cx> {
+            if let Some(path) = node.url.strip_prefix("./") {
+                node.url = format!("/docs/{}", path).into();
+            }
+
+            Transform::Keep
+        }
+    }
+
+    struct HeadingDemoter;
+
+    impl<'cx> VisitorMut<'cx> for HeadingDemoter {
+        fn visit_heading(&mut self, node: &mut Heading<'cx>) -> Transform<'cx> {
+            node.depth = (node.depth + 1).min(6);
+
+            Transform::Keep
+        }
+    }
+
+    fn link(url: &str) -> Node<'_> {
+        Node::from(Link {
+            children: vec![text(url)],
+            url: url.into(),
+            title: None,
+            data: None,
+        })
+    }
+
+    #[test]
+    fn test_walk_mut() {
+        let mut document = Node::Document(Document {
+            children: vec![
+                Node::Heading(Heading::new(1).unwrap()),
+                Node::Heading(Heading::new(6).unwrap()),
+                Node::Paragraph(Paragraph {
+                    children: vec![
+                        link("./a.md"),
+                        Node::Strong(Strong {
+                            children: vec![link("./b.md")],
+                            data: None,
+                        }),
+                        link("https://example.com"),
+                    ],
+                    data: None,
+                }),
+            ],
+            data: None,
+        });
+
+        walk_mut(&mut document, &mut LinkRewriter);
+        walk_mut(&mut document, &mut HeadingDemoter);
+
+        let mut urls = vec![];
+        let mut depths = vec![];
+
+        let mut stack = vec![&document];
+
+        while let Some(node) = stack.pop() {
+            match node {
+                Node::Link(link) => urls.push(link.url.to_string()),
+                Node::Heading(heading) => depths.push(heading.depth),
+                _ => {}
+            }
+
+            stack.extend(node.children().unwrap_or_default().iter().rev());
+        }
+
+        assert_eq!(depths, [2, 6]);
+        assert_eq!(urls, ["/docs/a.md", "/docs/b.md", "https://example.com"]);
+    }
+
+    struct TextRemover {
+        visited: Vec<String>,
+    }
+
+    impl<'cx> VisitorMut<'cx> for TextRemover {
+        fn visit_text(&mut self, node: &mut Text<'cx>) -> Transform<'cx> {
+            self.visited.push(node.value.to_string());
+
+            if node.value.starts_with('x') {
+                Transform::Remove
+            } else {
+                Transform::Keep
+            }
+        }
+
+        fn visit_emphasis(&mut self, node: &mut Emphasis<'cx>) -> Transform<'cx> {
+            Transform::Replace(Node::Strong(Strong {
+                children: core::mem::take(&mut node.children),
+                data: None,
+            }))
+        }
+    }
+
+    #[test]
+    fn test_walk_mut_remove_and_replace() {
+        let mut document = Node::Paragraph(Paragraph {
+            children: vec![
+                text("x1"),
+                text("x2"),
+                text("a"),
+                Node::Emphasis(Emphasis {
+                    children: vec![text("x3"), text("b")],
+                    data: None,
+                }),
+                text("x4"),
+            ],
+            data: None,
+        });
+
+        let mut remover = TextRemover { visited: vec![] };
+
+        walk_mut(&mut document, &mut remover);
+
+        assert_eq!(remover.visited, ["x1", "x2", "a", "x3", "b", "x4"]);
+
+        assert_eq!(
+            document,
+            Node::Paragraph(Paragraph {
+                children: vec![
+                    text("a"),
+                    Node::Strong(Strong {
+                        children: vec![text("b")],
+                        data: None,
+                    }),
+                ],
+                data: None,
+            })
+        );
+    }
+
+    struct Validator {
+        visited: usize,
+    }
+
+    impl TryVisitor for Validator {
+        type Error = String;
+
+        fn visit_text(&mut self, node: &Text) -> Result<Flow, Self::Error> {
+            self.visited += 1;
+
+            if node.value == "bad" {
+                return Err(format!("invalid text at {}", self.visited));
+            }
+
+            Ok(Flow::Continue)
+        }
+    }
+
+    #[test]
+    fn test_try_walk_abort() {
+        let document = Node::Paragraph(Paragraph {
+            children: vec![text("a"), text("b"), text("bad"), text("c")],
+            data: None,
+        });
+
+        let mut validator = Validator { visited: 0 };
+
+        assert_eq!(
+            try_walk(&document, &mut validator),
+            Err("invalid text at 3".to_owned())
+        );
+
+        assert_eq!(validator.visited, 3);
+    }
+
+    #[test]
+    fn test_try_walk_skip_children() {
+        struct SkipBlockquote {
+            texts: Vec<String>,
+            left: usize,
+        }
+
+        impl TryVisitor for SkipBlockquote {
+            type Error = ();
+
+            fn visit_blockquote(&mut self, _: &Blockquote) -> Result<Flow, ()> {
+                Ok(Flow::SkipChildren)
+            }
+
+            fn visit_text(&mut self, node: &Text) -> Result<Flow, ()> {
+                self.texts.push(node.value.to_string());
+
+                Ok(Flow::Continue)
+            }
+
+            fn leave(&mut self, _: &Node) -> Result<(), ()> {
+                self.left += 1;
+
+                Ok(())
             }
         }
-    };
-}
 
-node_into!(Text<'a>);
+        let document = Node::Document(Document {
+            children: vec![
+                Node::Paragraph(Paragraph {
+                    children: vec![text("a")],
+                    data: None,
+                }),
+                Node::Blockquote(Blockquote {
+                    children: vec![Node::Paragraph(Paragraph {
+                        children: vec![text("quoted")],
+                        data: None,
+                    })],
+                    data: None,
+                }),
+                Node::Paragraph(Paragraph {
+                    children: vec![text("b")],
+                    data: None,
+                }),
+            ],
+            data: None,
+        });
 
-/// Document.
-///
-/// ```markdown
-/// > | a
-///     ^
-/// ```
-#[derive(Clone, Debug, Eq, PartialEq, Default)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "document")
-)]
-pub struct Document<'cx> {
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        let mut visitor = SkipBlockquote {
+            texts: vec![],
+            left: 0,
+        };
 
-parent!(Document);
+        try_walk(&document, &mut visitor).unwrap();
 
-/// Paragraph (Parent) represents a unit of discourse dealing with a particular point or idea.
-/// For example, the following markdown:
-/// ```markdown
-/// Alpha bravo charlie.
-/// ```
-#[derive(Clone, Debug, Eq, PartialEq, Default)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "paragraph")
-)]
-pub struct Paragraph<'cx> {
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        assert_eq!(visitor.texts, ["a", "b"]);
+        assert_eq!(visitor.left, 6);
+    }
 
-parent!(Paragraph, PhrasingContent);
+    #[test]
+    fn test_descendants() {
+        let document = Document {
+            children: vec![
+                Node::Heading(Heading {
+                    children: vec![
+                        text("a"),
+                        Node::Emphasis(Emphasis {
+                            children: vec![text("b")],
+                            data: None,
+                        }),
+                    ],
+                    depth: 1,
+                    data: None,
+                }),
+                Node::Blockquote(Blockquote {
+                    children: vec![Node::Paragraph(Paragraph {
+                        children: vec![text("c")],
+                        data: None,
+                    })],
+                    data: None,
+                }),
+                text("d"),
+            ],
+            data: None,
+        };
 
-impl<'cx> FlowContent for Paragraph<'cx> {}
+        let order = document
+            .descendants_with_depth()
+            .map(|(depth, node)| {
+                let name = match node {
+                    Node::Text(text) => text.value.as_ref(),
+                    Node::Heading(_) => "heading",
+                    Node::Emphasis(_) => "emphasis",
+                    Node::Blockquote(_) => "blockquote",
+                    Node::Paragraph(_) => "paragraph",
+                    _ => unreachable!(),
+                };
 
-/// Heading (Parent) represents a heading of a section.
-/// Heading can be used where flow content is expected.
-/// Its content model is phrasing content.
-///
-/// ```markdown
-/// # Alpha
-/// ```
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "heading")
-)]
-pub struct Heading<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-    /// A depth field must be present.
-    /// A value of 1 is said to be the highest rank and 6 the lowest.
-    pub depth: usize,
-}
+                format!("{}:{}", depth, name)
+            })
+            .collect::<Vec<_>>();
 
-impl<'cx> Heading<'cx> {
-    /// Create new [`Heading`] instance with provided `depth`
-    pub fn new(depth: usize) -> Self {
-        assert!(
-            depth > 0 && depth < 7,
-            "A value of 1 is said to be the highest rank and 6 the lowest"
+        assert_eq!(
+            order,
+            [
+                "1:heading",
+                "2:a",
+                "2:emphasis",
+                "3:b",
+                "1:blockquote",
+                "2:paragraph",
+                "3:c",
+                "1:d"
+            ]
         );
 
-        Heading {
-            children: Default::default(),
-            depth,
+        let node = Node::Document(document);
+
+        assert_eq!(node.descendants().count(), 8);
+        assert_eq!(text("e").descendants().count(), 0);
+    }
+
+    #[test]
+    fn test_descendants_deep_nesting() {
+        const DEPTH: usize = 10_000;
+
+        let mut node = text("leaf");
+
+        for _ in 0..DEPTH {
+            node = Node::Blockquote(Blockquote {
+                children: vec![node],
+                data: None,
+            });
+        }
+
+        assert_eq!(node.descendants().count(), DEPTH);
+
+        let (depth, leaf) = node.descendants_with_depth().last().unwrap();
+
+        assert_eq!(depth, DEPTH);
+        assert_eq!(leaf, &text("leaf"));
+
+        // tear the chain down level by level, dropping it whole would recurse.
+        let mut children = node.children_mut().map(core::mem::take);
+
+        while let Some(mut nodes) = children {
+            children = nodes
+                .pop()
+                .and_then(|mut node| node.children_mut().map(core::mem::take));
         }
     }
-}
 
-parent!(Heading, PhrasingContent);
+    #[test]
+    fn test_find() {
+        let document = Document {
+            children: vec![
+                Node::Heading(Heading {
+                    children: vec![text("Index")],
+                    depth: 1,
+                    data: None,
+                }),
+                Node::Paragraph(Paragraph {
+                    children: vec![
+                        link("https://a.example"),
+                        Node::Emphasis(Emphasis {
+                            children: vec![link("./b.md")],
+                            data: None,
+                        }),
+                    ],
+                    data: None,
+                }),
+                Node::Heading(Heading {
+                    children: vec![link("#c")],
+                    depth: 3,
+                    data: None,
+                }),
+            ],
+            data: None,
+        };
 
-impl<'cx> FlowContent for Heading<'cx> {}
+        let urls = document
+            .find_all_of::<Link>()
+            .into_iter()
+            .map(|link| link.url.as_ref())
+            .collect::<Vec<_>>();
 
-/// ThematicBreak (Node) represents a thematic break,
-/// such as a scene change in a story,
-/// a transition to another topic, or a new document.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "thematicbreak")
-)]
-pub struct ThematicBreak {}
+        assert_eq!(urls, ["https://a.example", "./b.md", "#c"]);
 
-impl FlowContent for ThematicBreak {}
+        let depths = document
+            .find_all_of::<Heading>()
+            .into_iter()
+            .map(|heading| heading.depth)
+            .collect::<Vec<_>>();
 
-/// Blockquote (Parent) represents a section quoted from somewhere else.
-/// ```markdown
-/// # Alpha
-/// ```
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "blockquote")
-)]
-pub struct Blockquote<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        assert_eq!(depths, [1, 3]);
 
-parent!(Blockquote, PhrasingContent);
+        assert_eq!(
+            document
+                .find_all(|node| node.kind() == NodeKind::Link)
+                .len(),
+            3
+        );
 
-impl<'cx> FlowContent for Blockquote<'cx> {}
+        assert_eq!(
+            document.find_first(|node| matches!(node, Node::Emphasis(_))),
+            Some(&Node::Emphasis(Emphasis {
+                children: vec![link("./b.md")],
+                data: None,
+            }))
+        );
 
-/// List (Parent) represents a list of items.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "list")
-)]
-pub struct List<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        assert_eq!(document.find_first_of::<Heading>().unwrap().depth, 1);
+        assert!(document.find_first_of::<Code>().is_none());
 
-parent!(List, ListContent);
+        let error = <&Link>::try_from(&text("x")).unwrap_err();
 
-impl<'cx> FlowContent for List<'cx> {}
+        assert!(matches!(
+            error,
+            AstError::KindMismatch {
+                expected: NodeKind::Link,
+                found: NodeKind::Text
+            }
+        ));
+    }
 
-/// ListItem (Parent) represents an item in a List.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "list")
-)]
-pub struct ListItem<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+    #[test]
+    fn test_parent_editing() {
+        let mut paragraph = Paragraph::default();
 
-parent!(ListItem, FlowContent);
+        paragraph
+            .insert_at(
+                0,
+                Text {
+                    value: "b".into(),
+                    data: None,
+                },
+            )
+            .unwrap();
+        paragraph
+            .insert_at(
+                0,
+                Text {
+                    value: "a".into(),
+                    data: None,
+                },
+            )
+            .unwrap();
+        paragraph
+            .insert_at(
+                2,
+                Text {
+                    value: "d".into(),
+                    data: None,
+                },
+            )
+            .unwrap();
+        paragraph
+            .insert_at(
+                2,
+                Text {
+                    value: "c".into(),
+                    data: None,
+                },
+            )
+            .unwrap();
 
-impl<'cx> ListContent for ListItem<'cx> {}
+        assert_eq!(
+            paragraph.children,
+            [text("a"), text("b"), text("c"), text("d")]
+        );
 
-/// Code (Literal) represents a block of preformatted text, such as ASCII art or computer code.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "code")
-)]
-pub struct Code<'cx> {
-    /// Literal data.
-    pub value: Cow<'cx, str>,
-    /// [`Option`] field to indicate code language.
-    pub lang: Option<Cow<'cx, str>>,
-    /// Meta data for code language.
-    pub meta: Option<Cow<'cx, str>>,
-}
+        assert!(matches!(
+            paragraph.insert_at(
+                5,
+                Text {
+                    value: "e".into(),
+                    data: None
+                }
+            ),
+            Err(AstError::IndexOutOfBounds { index: 5, len: 4 })
+        ));
 
-impl<'cx> FlowContent for Code<'cx> {}
+        let replaced = paragraph
+            .replace_at(
+                1,
+                InlineCode {
+                    value: "B".into(),
+                    data: None,
+                },
+            )
+            .unwrap();
 
-/// Code (Literal) represents a block of preformatted text, such as ASCII art or computer code.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "definition")
-)]
-pub struct Definition<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-    /// An identifier field must be present. It can match another node.
-    /// identifier is a source value: character escapes and character
-    /// references are not parsed. Its value must be normalized.
-    pub identifier: Cow<'cx, str>,
-    /// A label field can be present.
-    /// label is a string value: it works just like title on a link or a lang on
-    /// code: character escapes and character references are parsed.
-    pub label: Option<Cow<'cx, str>>,
-    /// A url field must be present. It represents a URL to the referenced resource.
-    pub url: Cow<'cx, str>,
-    /// A title field can be present.
-    /// It represents advisory information for the resource,
-    /// such as would be appropriate for a tooltip.
-    pub title: Option<Cow<'cx, str>>,
-}
+        assert_eq!(replaced, text("b"));
+        assert_eq!(paragraph.children[0], text("a"));
+        assert_eq!(paragraph.children[2], text("c"));
+        assert_eq!(paragraph.len(), 4);
 
-impl<'cx> FlowContent for Definition<'cx> {}
+        assert!(matches!(
+            paragraph.replace_at(
+                4,
+                Text {
+                    value: "e".into(),
+                    data: None
+                }
+            ),
+            Err(AstError::IndexOutOfBounds { index: 4, len: 4 })
+        ));
 
-/// Text (Literal) represents everything that is just text.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "text")
-)]
-pub struct Text<'cx> {
-    /// Text literal value
-    pub value: Cow<'cx, str>,
-}
-/// Text can be used where phrasing content is expected.
-/// Its content is represented by its value field.
-impl<'cx> PhrasingContent for Text<'cx> {}
+        assert_eq!(paragraph.remove_at(3).unwrap(), text("d"));
+
+        assert!(matches!(
+            paragraph.remove_at(3),
+            Err(AstError::IndexOutOfBounds { index: 3, len: 3 })
+        ));
+
+        while !paragraph.is_empty() {
+            paragraph.remove_at(0).unwrap();
+        }
+
+        assert!(paragraph.remove_at(0).is_err());
+    }
+
+    #[test]
+    fn test_heading_new() {
+        for depth in Heading::DEPTHS {
+            assert_eq!(Heading::new(depth).unwrap().depth, depth);
+        }
+
+        assert!(matches!(Heading::new(0), Err(AstError::InvalidDepth(0))));
+        assert!(matches!(Heading::new(7), Err(AstError::InvalidDepth(7))));
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_node_size() {
+        // large variants are boxed, so children lists stay compact.
+        assert!(core::mem::size_of::<Node>() <= 64);
+    }
+
+    #[test]
+    fn test_node_kind_str() {
+        for kind in NodeKind::ALL {
+            assert_eq!(kind.as_str().parse::<NodeKind>().unwrap(), kind);
+            assert_eq!(kind.to_string(), kind.as_str());
+        }
+
+        let names = NodeKind::ALL
+            .iter()
+            .map(NodeKind::as_str)
+            .collect::<std::collections::BTreeSet<_>>();
+
+        assert_eq!(names.len(), NodeKind::ALL.len());
+
+        assert_eq!(NodeKind::Document.as_str(), "root");
+        assert_eq!("document".parse::<NodeKind>().unwrap(), NodeKind::Document);
+
+        assert!(matches!(
+            "ThematicBreak".parse::<NodeKind>(),
+            Err(AstError::UnknownKind(name)) if name == "ThematicBreak"
+        ));
+    }
+
+    #[test]
+    fn test_enum_str() {
+        for reference_type in [
+            ReferenceType::Shortcut,
+            ReferenceType::Collapsed,
+            ReferenceType::Full,
+        ] {
+            assert_eq!(
+                reference_type.to_string().parse::<ReferenceType>().unwrap(),
+                reference_type
+            );
+        }
+
+        for align in [
+            AlignType::Left,
+            AlignType::Right,
+            AlignType::Center,
+            AlignType::None,
+        ] {
+            assert_eq!(align.to_string().parse::<AlignType>().unwrap(), align);
+        }
+
+        assert_eq!(AlignType::Center.as_str(), "center");
+        assert_eq!(ReferenceType::Full.to_string(), "full");
+
+        assert!(matches!(
+            "Left".parse::<AlignType>(),
+            Err(AstError::InvalidValue { expected: "alignType", found }) if found == "Left"
+        ));
+        assert_eq!(
+            "".parse::<ReferenceType>().unwrap_err().to_string(),
+            "expect referenceType, found \"\""
+        );
+    }
+
+    #[test]
+    fn test_align_delimiter() {
+        for (delimiter, align) in [
+            (":-:", AlignType::Center),
+            (" :---", AlignType::Left),
+            ("--: ", AlignType::Right),
+            ("-", AlignType::None),
+        ] {
+            assert_eq!(delimiter.parse::<AlignType>().unwrap(), align);
+        }
+
+        for delimiter in [":", "::", "", ":-:-", "- -"] {
+            assert!(delimiter.parse::<AlignType>().is_err(), "{:?}", delimiter);
+        }
+    }
+
+    #[test]
+    fn test_table_helpers() {
+        use crate::{ast::text::to_text, parser::Parser};
+
+        let mut document =
+            Parser::new("| a | b | c |\n| :- | -: | - |\n| 1 | 2 | 3 |\n| 4 | 5 | 6 |")
+                .parse()
+                .unwrap();
+
+        let Node::Table(table) = &mut document.children[0] else {
+            panic!("{:?}", document.children[0]);
+        };
+
+        let text = |row: &TableRow| to_text(&Node::TableRow(row.clone()), &Default::default());
+
+        assert_eq!(table.column_count(), 3);
+        assert_eq!(text(table.header().unwrap()), "a b c");
+        assert_eq!(
+            table.rows().map(text).collect::<Vec<_>>(),
+            ["1 2 3", "4 5 6"]
+        );
+
+        assert_eq!(table.aligned(0), AlignType::Left);
+        assert_eq!(table.aligned(1), AlignType::Right);
+        assert_eq!(table.aligned(2), AlignType::None);
+        // columns past `align` are not aligned.
+        assert_eq!(table.aligned(3), AlignType::None);
+
+        let row = table.rows().next().unwrap().clone();
+
+        table.add_row(row.clone()).unwrap();
+
+        assert_eq!(table.rows().count(), 3);
+
+        let mut short = row.clone();
+
+        short.children.pop();
+
+        assert!(matches!(
+            table.add_row(short.clone()),
+            Err(AstError::RowArityMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert_eq!(table.rows().count(), 3);
+
+        // the first row of an empty table is its header.
+        let table = &mut Table {
+            children: vec![],
+            align: vec![],
+            data: None,
+        };
+
+        assert_eq!(table.column_count(), 0);
+        assert!(table.header().is_none());
+
+        table.add_row(short).unwrap();
+
+        assert_eq!(table.column_count(), 2);
+        assert!(table.add_row(row).is_err());
+    }
+
+    #[test]
+    fn test_structural_identity() {
+        use crate::parser::Parser;
+        use std::{
+            collections::{hash_map::DefaultHasher, HashSet},
+            hash::{Hash, Hasher},
+        };
 
-/// Emphasis (Parent) represents stress emphasis of its contents.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "emphasis")
-)]
-pub struct Emphasis<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        let source = "# Notes\n\n- [ ] *ship* `it`\n\n---\n\n- [ ] *ship* `it`\n";
 
-parent!(Emphasis, PhrasingContent);
+        let snippet = "- [ ] *ship* `it`\n";
 
-/// Emphasis can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for Emphasis<'cx> {}
+        let offsets = source
+            .match_indices(snippet)
+            .map(|(offset, _)| offset)
+            .collect::<Vec<_>>();
 
-/// Strong (Parent) represents strong importance, seriousness, or urgency for its contents.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "strong")
-)]
-pub struct Strong<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        assert_eq!(offsets.len(), 2);
 
-parent!(Strong, PhrasingContent);
+        let snippets = offsets
+            .iter()
+            .map(|offset| {
+                let document = Parser::new(&source[*offset..*offset + snippet.len()])
+                    .parse()
+                    .unwrap();
 
-/// Strong can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for Strong<'cx> {}
+                Node::Document(document)
+            })
+            .collect::<Vec<_>>();
 
-/// InlineCode (Literal) represents a fragment of computer code, such as a file name,
-/// computer program, or anything a computer could parse.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "inlinecode")
-)]
-pub struct InlineCode<'cx> {
-    /// Text literal value
-    pub value: Cow<'cx, str>,
-}
-/// InlineCode can be used where phrasing content is expected.
-/// Its content is represented by its value field.
-impl<'cx> PhrasingContent for InlineCode<'cx> {}
+        let hash = |node: &Node| {
+            let mut hasher = DefaultHasher::new();
 
-/// InlineCode (Literal) represents a fragment of computer code, such as a file name,
-/// computer program, or anything a computer could parse.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "break")
-)]
-pub struct Break {}
-/// Break can be used where phrasing content is expected.
-/// Its content is represented by its value field.
-impl PhrasingContent for Break {}
+            node.hash(&mut hasher);
 
-/// Link (Parent) represents a hyperlink.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "link")
-)]
-pub struct Link<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-    /// A url field must be present. It represents a URL to the referenced resource.
-    pub url: Cow<'cx, str>,
-    /// A title field can be present.
-    /// It represents advisory information for the resource,
-    /// such as would be appropriate for a tooltip.
-    pub title: Option<Cow<'cx, str>>,
-}
+            hasher.finish()
+        };
 
-parent!(Link, PhrasingContent);
+        assert!(eq_ignoring_positions(&snippets[0], &snippets[1]));
+        assert_eq!(hash(&snippets[0]), hash(&snippets[1]));
 
-/// Link can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for Link<'cx> {}
+        let unique = snippets.iter().collect::<HashSet<_>>();
 
-/// Link (Parent) represents a hyperlink.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "link")
-)]
-pub struct LinkReference<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-    /// An identifier field must be present. It can match another node.
-    /// identifier is a source value: character escapes and character
-    /// references are not parsed. Its value must be normalized.
-    pub identifier: Cow<'cx, str>,
-    /// A label field can be present.
-    /// label is a string value: it works just like title on a link or a lang on
-    /// code: character escapes and character references are parsed.
-    pub label: Option<Cow<'cx, str>>,
-    /// A referenceType field must be present. Its value must be a referenceType.
-    /// It represents the explicitness of the reference.
-    pub reference_type: ReferenceType,
-}
+        assert_eq!(unique.len(), 1);
 
-parent!(LinkReference, PhrasingContent);
+        let other = Node::Document(Parser::new("- [ ] *ship* `it`!").parse().unwrap());
 
-/// LinkReference can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for LinkReference<'cx> {}
+        assert!(!eq_ignoring_positions(&snippets[0], &other));
+        assert!(!eq_ignoring_positions(
+            &snippets[0],
+            &Node::Paragraph(Paragraph {
+                children: vec![],
+                data: None
+            })
+        ));
+    }
 
-/// Image (Node) represents an image.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "image")
-)]
-pub struct Image<'cx> {
-    /// A url field must be present. It represents a URL to the referenced resource.
-    pub url: Cow<'cx, str>,
-    /// A title field can be present.
-    /// It represents advisory information for the resource,
-    /// such as would be appropriate for a tooltip.
-    pub title: Option<Cow<'cx, str>>,
-    /// An alt field should be present.
-    /// It represents equivalent content for environments
-    /// that cannot represent the node as intended.
-    pub alt: Option<Cow<'cx, str>>,
-}
+    #[test]
+    fn test_deep_tree() {
+        use std::{collections::hash_map::DefaultHasher, hash::Hasher};
 
-/// Image can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for Image<'cx> {}
+        fn nested(depth: usize) -> Node<'static> {
+            let mut node = Node::Text(Text {
+                value: "a".into(),
+                data: None,
+            });
 
-/// Image (Node) represents an image.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "strong")
-)]
-pub struct ImageReference<'cx> {
-    /// A url field must be present. It represents a URL to the referenced resource.
-    pub url: Cow<'cx, str>,
-    /// A title field can be present.
-    /// It represents advisory information for the resource,
-    /// such as would be appropriate for a tooltip.
-    pub title: Option<Cow<'cx, str>>,
-    /// An alt field should be present.
-    /// It represents equivalent content for environments
-    /// that cannot represent the node as intended.
-    pub alt: Option<Cow<'cx, str>>,
-}
+            for _ in 0..depth {
+                node = Node::Blockquote(Blockquote {
+                    children: vec![node],
+                    data: None,
+                });
+            }
 
-/// ImageReference can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for ImageReference<'cx> {}
+            node
+        }
 
-/// Delete (Parent) represents contents that are no longer accurate or no longer relevant.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "delete")
-)]
-pub struct Delete<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        // recursion would overflow this stack long before the bottom of the tree.
+        let thread = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                let node = nested(200_000);
 
-parent!(Delete, PhrasingContent);
+                let copy = node.clone();
 
-/// ImageReference can be used where phrasing content is expected.
-/// Its content model is phrasing content.
-impl<'cx> PhrasingContent for Delete<'cx> {}
+                assert!(copy == node);
+                assert!(copy != nested(199_999));
 
-/// FootnoteDefinition (Node) represents a marker through association.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "footnotedefinition")
-)]
-pub struct FootnoteDefinition<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-    /// An identifier field must be present. It can match another node.
-    /// identifier is a source value: character escapes and character
-    /// references are not parsed. Its value must be normalized.
-    pub identifier: Cow<'cx, str>,
-    /// A label field can be present.
-    /// label is a string value: it works just like title on a link or a lang on
-    /// code: character escapes and character references are parsed.
-    pub label: Option<Cow<'cx, str>>,
-}
+                let hash = |node: &Node| {
+                    let mut hasher = DefaultHasher::new();
 
-parent!(FootnoteDefinition, FlowContent);
+                    node.hash(&mut hasher);
 
-/// FootnoteDefinition can be used where flow content is expected.
-///  Its content model is also flow content.
-impl<'cx> FlowContent for FootnoteDefinition<'cx> {}
+                    hasher.finish()
+                };
 
-/// FootnoteReference (Node) represents a marker through association.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "footnotedefinition")
-)]
-pub struct FootnoteReference<'cx> {
-    /// An identifier field must be present. It can match another node.
-    /// identifier is a source value: character escapes and character
-    /// references are not parsed. Its value must be normalized.
-    pub identifier: Cow<'cx, str>,
-    /// A label field can be present.
-    /// label is a string value: it works just like title on a link or a lang on
-    /// code: character escapes and character references are parsed.
-    pub label: Option<Cow<'cx, str>>,
-}
+                assert_eq!(hash(&copy), hash(&node));
 
-/// FootnoteReference can be used where phrasing content is expected.
-/// It has no content model.
-impl<'cx> PhrasingContent for FootnoteReference<'cx> {}
+                let debug = format!("{:?}", node);
 
-/// Table (Parent) represents two-dimensional data.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "table")
-)]
-pub struct Table<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-    /// An align field can be present. If present, it must be a list of alignTypes.
-    /// It represents how cells in columns are aligned.
-    pub align: Vec<AlignType>,
-}
+                assert_eq!(debug.matches("Blockquote").count(), 200_000);
+                assert_eq!(debug.matches("Text").count(), 1);
 
-parent!(Table, TableContent);
+                // pretty printing indents each level, keep the output reasonably small.
+                let debug = format!("{:#?}", nested(1_000));
 
-/// FootnoteDefinition can be used where flow content is expected.
-///  Its content model is also flow content.
-impl<'cx> FlowContent for Table<'cx> {}
+                assert_eq!(debug.matches("Blockquote").count(), 1_000);
 
-/// TableCell (Parent) represents a header cell in a Table,
-///  if its parent is a head, or a data cell otherwise.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "footnotedefinition")
-)]
-pub struct TableCell<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+                drop(node);
+                drop(copy);
+            })
+            .unwrap();
 
-parent!(TableCell, PhrasingContent);
+        thread.join().unwrap();
+    }
 
-/// TableCell can be used where row content is expected.
-/// Its content model is phrasing content excluding Break nodes.
-impl<'cx> RowContent for TableCell<'cx> {}
+    #[test]
+    fn test_debug() {
+        let document = Document {
+            children: vec![
+                Node::Heading(Heading {
+                    children: vec![
+                        Node::Text(Text {
+                            value: "a\nb".into(),
+                            data: None,
+                        }),
+                        Node::Emphasis(Emphasis {
+                            children: vec![],
+                            data: None,
+                        }),
+                    ],
+                    depth: 2,
+                    data: None,
+                }),
+                Node::List(List {
+                    children: vec![Node::ListItem(ListItem {
+                        children: vec![Node::Paragraph(Paragraph {
+                            children: vec![Node::Link(Box::new(Link {
+                                children: vec![Node::Text(Text {
+                                    value: "c".into(),
+                                    data: None,
+                                })],
+                                url: "/d".into(),
+                                title: Some("e".into()),
+                                data: None,
+                            }))],
+                            data: None,
+                        })],
+                        checked: Some(true),
+                        spread: Some(false),
+                        data: None,
+                    })],
+                    ordered: Some(true),
+                    start: Some(3),
+                    spread: Some(false),
+                    data: None,
+                }),
+                Node::ThematicBreak(ThematicBreak { data: None }),
+            ],
+            data: None,
+        };
 
-/// TableRow (Parent) represents a row of cells in a table.
-#[derive(Clone, Debug, Eq, PartialEq)]
-#[cfg_attr(
-    feature = "serde",
-    derive(serde::Serialize, serde::Deserialize),
-    serde(tag = "type", rename = "footnotedefinition")
-)]
-pub struct TableRow<'cx> {
-    /// Children node list.
-    #[serde(borrow)]
-    pub children: Vec<Node<'cx>>,
-}
+        // same as the derived implementation of the inner struct.
+        let expected = (format!("{:?}", document), format!("{:#?}", document));
 
-parent!(TableRow, RowContent);
+        let node = Node::Document(document);
 
-/// TableRow can be used where table content is expected. Its content model is row content.
-impl<'cx> TableContent for TableRow<'cx> {}
+        assert_eq!((format!("{:?}", node), format!("{:#?}", node)), expected);
+
+        for node in node.descendants() {
+            assert_eq!(format!("{:?}", node), format!("{:?}", node.debug_inner()));
+            assert_eq!(format!("{:#?}", node), format!("{:#?}", node.debug_inner()));
+        }
+    }
+}
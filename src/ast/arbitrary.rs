@@ -0,0 +1,411 @@
+//! Random documents respecting the mdast content model, for fuzzing transforms and serializers.
+//!
+//! Strings are borrowed from the fuzzer input, so the generated trees live as long as it.
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use markdown_rs::ast::{
+//!     arbitrary::{generate, GenerateOptions},
+//!     Document,
+//! };
+//!
+//! let bytes = (0..=255).cycle().take(4096).collect::<Vec<u8>>();
+//!
+//! let document = Document::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+//!
+//! // CommonMark node kinds only.
+//! let options = GenerateOptions {
+//!     gfm: false,
+//!     ..Default::default()
+//! };
+//!
+//! let commonmark = generate(&mut Unstructured::new(&bytes), &options).unwrap();
+//! ```
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use super::*;
+
+/// Options of [`generate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GenerateOptions {
+    /// Also generate GFM nodes: tables, delete, footnotes and task list items, defaults to
+    /// `true`.
+    pub gfm: bool,
+    /// Maximum nesting of parents below the document, defaults to `6`.
+    pub max_depth: usize,
+    /// Maximum number of children of one parent, defaults to `4`.
+    pub max_children: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            gfm: true,
+            max_depth: 6,
+            max_children: 4,
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for Document<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        generate(u, &GenerateOptions::default())
+    }
+}
+
+/// Returns a random document, whose every parent only holds children its content model
+/// allows.
+///
+/// Headings, paragraphs and table cells hold phrasing content, lists hold list items, tables
+/// hold rows of as many cells as `align` entries, links hold no links, cells hold no breaks,
+/// heading depths are in `1..=6` and identifiers are non-empty normalized labels: the
+/// result always passes [`validate`](super::validate()).
+pub fn generate<'a>(u: &mut Unstructured<'a>, options: &GenerateOptions) -> Result<Document<'a>> {
+    let mut generator = Generator {
+        options,
+        depth: 0,
+        in_link: false,
+        in_cell: false,
+    };
+
+    Ok(Document {
+        children: generator.flow(u)?,
+        data: None,
+    })
+}
+
+struct Generator<'o> {
+    options: &'o GenerateOptions,
+    depth: usize,
+    in_link: bool,
+    in_cell: bool,
+}
+
+impl<'o> Generator<'o> {
+    fn children<'a, F>(&mut self, u: &mut Unstructured<'a>, mut f: F) -> Result<Vec<Node<'a>>>
+    where
+        F: FnMut(&mut Self, &mut Unstructured<'a>) -> Result<Node<'a>>,
+    {
+        if self.depth >= self.options.max_depth {
+            return Ok(vec![]);
+        }
+
+        self.depth += 1;
+
+        let len = u.int_in_range(0..=self.options.max_children)?;
+
+        let children = (0..len).map(|_| f(self, u)).collect();
+
+        self.depth -= 1;
+
+        children
+    }
+
+    fn flow<'a>(&mut self, u: &mut Unstructured<'a>) -> Result<Vec<Node<'a>>> {
+        self.children(u, Self::flow_node)
+    }
+
+    fn phrasing<'a>(&mut self, u: &mut Unstructured<'a>) -> Result<Vec<Node<'a>>> {
+        self.children(u, Self::phrasing_node)
+    }
+
+    fn flow_node<'a>(&mut self, u: &mut Unstructured<'a>) -> Result<Node<'a>> {
+        let kinds = if self.options.gfm { 10 } else { 8 };
+
+        Ok(match u.choose_index(kinds)? {
+            0 => Node::Paragraph(Paragraph {
+                children: self.phrasing(u)?,
+                data: None,
+            }),
+            1 => Node::Heading(Heading {
+                children: self.phrasing(u)?,
+                depth: u.int_in_range(Heading::DEPTHS)?,
+                data: None,
+            }),
+            2 => Node::ThematicBreak(ThematicBreak { data: None }),
+            3 => Node::Blockquote(Blockquote {
+                children: self.flow(u)?,
+                data: None,
+            }),
+            4 => {
+                let ordered = u.arbitrary()?;
+
+                Node::List(List {
+                    children: self.children(u, Self::list_item)?,
+                    ordered: Some(ordered),
+                    start: if ordered {
+                        Some(u.int_in_range(0..=999_999_999)?)
+                    } else {
+                        None
+                    },
+                    spread: u.arbitrary()?,
+                    data: None,
+                })
+            }
+            5 => Node::from(Code {
+                value: string(u)?,
+                lang: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                meta: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                data: None,
+            }),
+            6 => {
+                let (identifier, label) = label(u)?;
+
+                Node::from(Definition {
+                    identifier,
+                    label,
+                    url: string(u)?,
+                    title: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                    data: None,
+                })
+            }
+            7 => Node::Html(Html {
+                value: string(u)?,
+                data: None,
+            }),
+            8 => self.table(u)?,
+            _ => {
+                let (identifier, label) = label(u)?;
+
+                Node::from(FootnoteDefinition {
+                    children: self.flow(u)?,
+                    identifier,
+                    label,
+                    data: None,
+                })
+            }
+        })
+    }
+
+    fn list_item<'a>(&mut self, u: &mut Unstructured<'a>) -> Result<Node<'a>> {
+        Ok(Node::ListItem(ListItem {
+            children: self.flow(u)?,
+            checked: if self.options.gfm {
+                u.arbitrary()?
+            } else {
+                None
+            },
+            spread: u.arbitrary()?,
+            data: None,
+        }))
+    }
+
+    fn table<'a>(&mut self, u: &mut Unstructured<'a>) -> Result<Node<'a>> {
+        let align = (0..u.int_in_range(1..=4)?)
+            .map(|_| {
+                Ok(match u.choose_index(4)? {
+                    0 => AlignType::Left,
+                    1 => AlignType::Right,
+                    2 => AlignType::Center,
+                    _ => AlignType::None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let columns = align.len();
+
+        let mut children = vec![];
+
+        self.in_cell = true;
+
+        for _ in 0..u.int_in_range(1..=3)? {
+            let mut cells = vec![];
+
+            for _ in 0..columns {
+                cells.push(Node::TableCell(TableCell {
+                    children: self.phrasing(u)?,
+                    data: None,
+                }));
+            }
+
+            children.push(Node::TableRow(TableRow {
+                children: cells,
+                data: None,
+            }));
+        }
+
+        self.in_cell = false;
+
+        Ok(Node::Table(Table {
+            children,
+            align,
+            data: None,
+        }))
+    }
+
+    fn phrasing_node<'a>(&mut self, u: &mut Unstructured<'a>) -> Result<Node<'a>> {
+        let kinds = if self.options.gfm { 12 } else { 10 };
+
+        Ok(match u.choose_index(kinds)? {
+            0 | 1 => Node::Text(Text {
+                value: string(u)?,
+                data: None,
+            }),
+            2 => Node::Emphasis(Emphasis {
+                children: self.phrasing(u)?,
+                data: None,
+            }),
+            3 => Node::Strong(Strong {
+                children: self.phrasing(u)?,
+                data: None,
+            }),
+            4 => Node::InlineCode(InlineCode {
+                value: string(u)?,
+                data: None,
+            }),
+            5 if !self.in_cell => Node::Break(Break { data: None }),
+            6 if !self.in_link => {
+                self.in_link = true;
+
+                let children = self.phrasing(u);
+
+                self.in_link = false;
+
+                if u.arbitrary()? {
+                    Node::from(Link {
+                        children: children?,
+                        url: string(u)?,
+                        title: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                        data: None,
+                    })
+                } else {
+                    let (identifier, label) = label(u)?;
+
+                    Node::from(LinkReference {
+                        children: children?,
+                        identifier,
+                        label,
+                        reference_type: reference_type(u)?,
+                        data: None,
+                    })
+                }
+            }
+            5..=7 => {
+                if u.arbitrary()? {
+                    Node::from(Image {
+                        url: string(u)?,
+                        title: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                        alt: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                        data: None,
+                    })
+                } else {
+                    let (identifier, label) = label(u)?;
+
+                    Node::from(ImageReference {
+                        alt: u.arbitrary::<Option<&str>>()?.map(Cow::Borrowed),
+                        identifier,
+                        label,
+                        reference_type: reference_type(u)?,
+                        data: None,
+                    })
+                }
+            }
+            8 | 9 => Node::Html(Html {
+                value: string(u)?,
+                data: None,
+            }),
+            10 => Node::Delete(Delete {
+                children: self.phrasing(u)?,
+                data: None,
+            }),
+            _ => {
+                let (identifier, label) = label(u)?;
+
+                Node::FootnoteReference(FootnoteReference {
+                    identifier,
+                    label,
+                    data: None,
+                })
+            }
+        })
+    }
+}
+
+fn string<'a>(u: &mut Unstructured<'a>) -> Result<Cow<'a, str>> {
+    Ok(Cow::Borrowed(u.arbitrary()?))
+}
+
+/// Returns a label and its identifier, never empty.
+fn label<'a>(u: &mut Unstructured<'a>) -> Result<(Cow<'a, str>, Option<Cow<'a, str>>)> {
+    let label: &str = u.arbitrary()?;
+
+    let identifier = normalize_identifier(label);
+
+    Ok(if identifier.is_empty() {
+        ("a".into(), Some("a".into()))
+    } else {
+        (identifier.into(), Some(label.into()))
+    })
+}
+
+fn reference_type(u: &mut Unstructured<'_>) -> Result<ReferenceType> {
+    Ok(match u.choose_index(3)? {
+        0 => ReferenceType::Shortcut,
+        1 => ReferenceType::Collapsed,
+        _ => ReferenceType::Full,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn documents(options: &GenerateOptions) -> impl Iterator<Item = Document<'static>> + '_ {
+        (1..=200u64).map(move |seed| {
+            // xorshift, the generated documents only depend on the seed.
+            let mut state = seed;
+
+            let bytes = (0..4096)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+
+                    state as u8
+                })
+                .collect::<Vec<_>>();
+
+            generate(&mut Unstructured::new(&bytes), options)
+                .unwrap()
+                .into_owned()
+        })
+    }
+
+    #[test]
+    fn test_content_model() {
+        let mut kinds = std::collections::BTreeSet::new();
+
+        for document in documents(&GenerateOptions::default()) {
+            assert!(validate(&document).is_ok());
+
+            for node in document.descendants() {
+                kinds.insert(node.kind());
+
+                if let Node::Table(table) = node {
+                    for row in &table.children {
+                        assert_eq!(row.children().unwrap().len(), table.align.len());
+                    }
+                }
+            }
+        }
+
+        // every kind but the document itself.
+        assert_eq!(kinds.len(), 24);
+
+        let options = GenerateOptions {
+            gfm: false,
+            ..Default::default()
+        };
+
+        for document in documents(&options) {
+            assert!(document.descendants().all(|node| !matches!(
+                node.kind(),
+                NodeKind::Table
+                    | NodeKind::Delete
+                    | NodeKind::FootnoteDefinition
+                    | NodeKind::FootnoteReference
+            )));
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Arena-backed trees, built by [`Parser::parse_in`](crate::parser::Parser::parse_in).
+//!
+//! Children lists of an [`ArenaNode`] are slices allocated in an [`Arena`] rather than one
+//! `Vec` per parent, which cuts the allocations of parsing large documents. The whole tree is
+//! freed at once when the arena is dropped.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{arena::Arena, NodeKind},
+//!     parser::Parser,
+//! };
+//!
+//! let arena = Arena::new();
+//!
+//! let document = Parser::new("# Hello *world*").parse_in(&arena).unwrap();
+//!
+//! assert_eq!(document.children[0].kind(), NodeKind::Heading);
+//! assert_eq!(document.children[0].children.len(), 2);
+//!
+//! assert_eq!(
+//!     document.to_document(),
+//!     Parser::new("# Hello *world*").parse().unwrap()
+//! );
+//! ```
+
+use super::*;
+
+/// Storage of the nodes of arena-backed trees.
+///
+/// `'a` is the lifetime of the arena borrow, `'cx` the one of the parsed source.
+pub struct Arena<'a, 'cx> {
+    nodes: typed_arena::Arena<ArenaNode<'a, 'cx>>,
+}
+
+impl<'a, 'cx> Arena<'a, 'cx> {
+    pub fn new() -> Self {
+        Arena {
+            nodes: typed_arena::Arena::new(),
+        }
+    }
+
+    /// Moves `nodes` into the arena, returns them as a contiguous slice.
+    pub(crate) fn alloc_extend<I>(&'a self, nodes: I) -> &'a [ArenaNode<'a, 'cx>]
+    where
+        I: IntoIterator<Item = ArenaNode<'a, 'cx>>,
+    {
+        self.nodes.alloc_extend(nodes)
+    }
+}
+
+impl<'a, 'cx> Default for Arena<'a, 'cx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A node whose children live in an [`Arena`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ArenaNode<'a, 'cx> {
+    /// The node itself, its own children list is always empty.
+    pub node: Node<'cx>,
+    /// Children of the node, empty for literal and void nodes.
+    pub children: &'a [ArenaNode<'a, 'cx>],
+}
+
+impl<'a, 'cx> ArenaNode<'a, 'cx> {
+    pub fn kind(&self) -> NodeKind {
+        self.node.kind()
+    }
+
+    /// Returns an iterator over the descendants of this node, in pre-order depth-first order.
+    pub fn descendants(&self) -> ArenaDescendants<'a, 'cx> {
+        ArenaDescendants::new(self.children)
+    }
+
+    /// Returns the equivalent owned [`Node`] tree.
+    pub fn to_node(&self) -> Node<'cx> {
+        let mut node = self.node.clone();
+
+        if let Some(children) = node.children_mut() {
+            *children = self.children.iter().map(ArenaNode::to_node).collect();
+        }
+
+        node
+    }
+
+    /// Appends the plain text of this node to `out`, as used for image alt text.
+    pub(crate) fn plain_text(&self, out: &mut String) {
+        match &self.node {
+            Node::Text(text) => out.push_str(&text.value),
+            Node::InlineCode(code) => out.push_str(&code.value),
+            Node::Image(image) => out.push_str(image.alt.as_deref().unwrap_or_default()),
+            Node::ImageReference(image) => out.push_str(image.alt.as_deref().unwrap_or_default()),
+            Node::Break(_) => out.push('\n'),
+            _ => {
+                for child in self.children {
+                    child.plain_text(out);
+                }
+            }
+        }
+    }
+}
+
+/// An arena-backed document, see [`Parser::parse_in`](crate::parser::Parser::parse_in).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ArenaDocument<'a, 'cx> {
+    pub children: &'a [ArenaNode<'a, 'cx>],
+}
+
+impl<'a, 'cx> ArenaDocument<'a, 'cx> {
+    /// Returns an iterator over all the nodes of the document, in pre-order depth-first order.
+    pub fn descendants(&self) -> ArenaDescendants<'a, 'cx> {
+        ArenaDescendants::new(self.children)
+    }
+
+    /// Returns the equivalent owned [`Document`].
+    pub fn to_document(&self) -> Document<'cx> {
+        Document {
+            children: self.children.iter().map(ArenaNode::to_node).collect(),
+            data: None,
+        }
+    }
+}
+
+/// Pre-order depth-first iterator over arena nodes, see [`ArenaNode::descendants`].
+#[derive(Clone, Debug)]
+pub struct ArenaDescendants<'a, 'cx> {
+    stack: Vec<&'a ArenaNode<'a, 'cx>>,
+}
+
+impl<'a, 'cx> ArenaDescendants<'a, 'cx> {
+    fn new(children: &'a [ArenaNode<'a, 'cx>]) -> Self {
+        ArenaDescendants {
+            stack: children.iter().rev().collect(),
+        }
+    }
+}
+
+impl<'a, 'cx> Iterator for ArenaDescendants<'a, 'cx> {
+    type Item = &'a ArenaNode<'a, 'cx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+
+        self.stack.extend(node.children.iter().rev());
+
+        Some(node)
+    }
+}
+
+/// Drive `visitor` over `node` and all of its descendants, like [`walk`].
+///
+/// The nodes given to the visitor, including to [`Visitor::enter`] and [`Visitor::leave`], have
+/// no children: these
+/// are only reachable through [`ArenaNode::children`].
+pub fn walk_arena<V: Visitor>(node: &ArenaNode, visitor: &mut V) {
+    enum Step<'n, 'a, 'cx> {
+        Enter(&'n ArenaNode<'a, 'cx>),
+        Leave(&'n ArenaNode<'a, 'cx>),
+    }
+
+    let mut stack = vec![Step::Enter(node)];
+
+    while let Some(step) = stack.pop() {
+        match step {
+            Step::Enter(node) => {
+                visitor.enter(&node.node);
+                node.node.accept(visitor);
+
+                stack.push(Step::Leave(node));
+                stack.extend(node.children.iter().rev().map(Step::Enter));
+            }
+            Step::Leave(node) => visitor.leave(&node.node),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "# Title\n\n\
+        > *a* **b** `c`  \nd [e](/e \"E\") [f][] ![g *h*](/g.png) <b>i</b>\n\n\
+        1. one\n\n   two\n\n\
+        ```rust\nfn main() {}\n```\n\n\
+        - \tindented *tab*\n\n\
+        [f]: /f\n";
+
+    #[test]
+    fn test_same_tree() {
+        let arena = Arena::new();
+
+        let document = Parser::new(MARKDOWN).parse_in(&arena).unwrap();
+
+        let owned = Parser::new(MARKDOWN).parse().unwrap();
+
+        assert_eq!(document.to_document(), owned);
+
+        assert!(document
+            .descendants()
+            .map(ArenaNode::kind)
+            .eq(owned.descendants().map(Node::kind)));
+
+        // children are only held by the arena nodes.
+        assert!(document.descendants().all(|node| node
+            .node
+            .children()
+            .unwrap_or_default()
+            .is_empty()));
+    }
+
+    #[test]
+    fn test_walk() {
+        #[derive(Default)]
+        struct Kinds(Vec<NodeKind>, usize);
+
+        impl Visitor for Kinds {
+            fn visit_text(&mut self, _: &Text) {
+                self.1 += 1;
+            }
+
+            fn leave(&mut self, node: &Node) {
+                self.0.push(node.kind());
+            }
+        }
+
+        let arena = Arena::new();
+
+        let document = Parser::new("> *a* b\n").parse_in(&arena).unwrap();
+
+        let mut kinds = Kinds::default();
+
+        walk_arena(&document.children[0], &mut kinds);
+
+        assert_eq!(
+            kinds.0,
+            [
+                NodeKind::Text,
+                NodeKind::Emphasis,
+                NodeKind::Text,
+                NodeKind::Paragraph,
+                NodeKind::Blockquote
+            ]
+        );
+        assert_eq!(kinds.1, 2);
+    }
+}
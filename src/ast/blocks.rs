@@ -0,0 +1,263 @@
+//! Top-level blocks and sections of a document, for tools that only need its outline.
+//!
+//! ```
+//! use markdown_rs::{ast::*, parser::Parser};
+//!
+//! let source = "# Changelog\n\n## Unreleased\n\n- Fix\n\n## 1.0.0\n\n- Initial\n";
+//!
+//! let (mut document, spans) = Parser::new(source).parse_with_spans().unwrap();
+//!
+//! let section = document
+//!     .section(|heading| heading.depth == 2 && heading.to_text() == "Unreleased")
+//!     .unwrap();
+//!
+//! let texts = document
+//!     .blocks(&spans)
+//!     .filter(|block| section.contains(&block.index))
+//!     .filter_map(|block| block.text(source))
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(texts, ["## Unreleased", "- Fix"]);
+//!
+//! // insert a paragraph at the end of the section.
+//! let paragraph = Paragraph {
+//!     children: vec![Node::Text(Text {
+//!         value: "Thanks!".into(),
+//!         data: None,
+//!     })],
+//!     data: None,
+//! };
+//!
+//! document.insert_at(section.end, paragraph).unwrap();
+//!
+//! assert!(matches!(document.children[3], Node::Paragraph(_)));
+//! ```
+
+use core::{iter::Enumerate, ops::Range, slice};
+
+use super::{slug::heading_text, spans::Spans, *};
+
+/// A top-level node of a document, see [`Document::blocks`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BlockView<'a, 'cx> {
+    /// The node.
+    pub node: &'a Node<'cx>,
+    /// Index of the node in the document children.
+    pub index: usize,
+    /// Source range of the node, `None` if it has none, e.g. added after parsing.
+    pub span: Option<Range<usize>>,
+}
+
+impl<'a, 'cx> BlockView<'a, 'cx> {
+    /// Returns the text of `source` the node was parsed from, `source` being the text of the
+    /// parse that produced the document.
+    pub fn text<'s>(&self, source: &'s str) -> Option<&'s str> {
+        source.get(self.span.clone()?)
+    }
+}
+
+/// Iterator over the top-level nodes of a document, see [`Document::blocks`].
+#[derive(Clone, Debug)]
+pub struct Blocks<'a, 'cx> {
+    children: Enumerate<slice::Iter<'a, Node<'cx>>>,
+    spans: &'a Spans,
+}
+
+impl<'a, 'cx> Iterator for Blocks<'a, 'cx> {
+    type Item = BlockView<'a, 'cx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, node) = self.children.next()?;
+
+        Some(BlockView {
+            node,
+            index,
+            span: self.spans.get(&[index]),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.children.size_hint()
+    }
+}
+
+impl<'a, 'cx> DoubleEndedIterator for Blocks<'a, 'cx> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (index, node) = self.children.next_back()?;
+
+        Some(BlockView {
+            node,
+            index,
+            span: self.spans.get(&[index]),
+        })
+    }
+}
+
+impl<'a, 'cx> ExactSizeIterator for Blocks<'a, 'cx> {}
+
+impl<'cx> Heading<'cx> {
+    /// Returns the plain text of the heading content, as used for its slug.
+    pub fn to_text(&self) -> String {
+        heading_text(self)
+    }
+}
+
+impl<'cx> Document<'cx> {
+    /// Returns an iterator over the top-level nodes of this document with their index and
+    /// source range, `spans` being those of the parse that produced it.
+    pub fn blocks<'a>(&'a self, spans: &'a Spans) -> Blocks<'a, 'cx> {
+        Blocks {
+            children: self.children.iter().enumerate(),
+            spans,
+        }
+    }
+
+    /// Returns the range of children of the section of the first top-level heading matching
+    /// `predicate`: the heading and everything up to the next top-level heading of the same
+    /// or a lower depth, or up to the end of the document.
+    ///
+    /// Returns `None` if no top-level heading matches.
+    pub fn section<F>(&self, mut predicate: F) -> Option<Range<usize>>
+    where
+        F: FnMut(&Heading<'cx>) -> bool,
+    {
+        let (start, depth) =
+            self.children
+                .iter()
+                .enumerate()
+                .find_map(|(index, node)| match node {
+                    Node::Heading(heading) if predicate(heading) => Some((index, heading.depth)),
+                    _ => None,
+                })?;
+
+        let end = self.children[start + 1..]
+            .iter()
+            .position(|node| matches!(node, Node::Heading(heading) if heading.depth <= depth))
+            .map_or(self.children.len(), |offset| start + 1 + offset);
+
+        Some(start..end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::Parser, to_markdown::SerializeOptions};
+
+    use super::*;
+
+    const CHANGELOG: &str = "\
+# Changelog
+
+Intro.
+
+## Unreleased
+
+### Added
+
+- Blocks.
+
+### Fixed
+
+- Sections.
+
+## 1.0.0
+
+- Initial release.
+";
+
+    fn unreleased(heading: &Heading) -> bool {
+        heading.to_text() == "Unreleased"
+    }
+
+    #[test]
+    fn test_blocks() {
+        let (document, spans) = Parser::new(CHANGELOG).parse_with_spans().unwrap();
+
+        let blocks = document.blocks(&spans).collect::<Vec<_>>();
+
+        assert_eq!(blocks.len(), document.children.len());
+        assert_eq!(document.blocks(&spans).len(), blocks.len());
+
+        for (index, block) in blocks.iter().enumerate() {
+            assert_eq!(block.index, index);
+            assert!(core::ptr::eq(block.node, &document.children[index]));
+        }
+
+        assert_eq!(blocks[1].text(CHANGELOG), Some("Intro."));
+        assert_eq!(blocks[2].span, Some(21..34));
+        assert_eq!(
+            document.blocks(&spans).next_back().unwrap().text(CHANGELOG),
+            Some("- Initial release.")
+        );
+
+        // built nodes have no span.
+        assert_eq!(
+            document
+                .blocks(&Spans::new())
+                .next()
+                .unwrap()
+                .text(CHANGELOG),
+            None
+        );
+    }
+
+    #[test]
+    fn test_section() {
+        let (document, spans) = Parser::new(CHANGELOG).parse_with_spans().unwrap();
+
+        let section = document.section(unreleased).unwrap();
+
+        assert_eq!(section, 2..7);
+
+        let start = spans.get(&[section.start]).unwrap().start;
+        let end = spans.get(&[section.end - 1]).unwrap().end;
+
+        assert_eq!(
+            &CHANGELOG[start..end],
+            "## Unreleased\n\n### Added\n\n- Blocks.\n\n### Fixed\n\n- Sections."
+        );
+
+        // sections of nested headings end at the next one of their depth.
+        assert_eq!(document.section(|heading| heading.depth == 3), Some(3..5));
+        // the last section runs to the end.
+        assert_eq!(
+            document.section(|heading| heading.to_text() == "1.0.0"),
+            Some(7..9)
+        );
+        assert_eq!(document.section(|heading| heading.depth == 1), Some(0..9));
+        assert_eq!(document.section(|heading| heading.depth == 4), None);
+    }
+
+    #[test]
+    fn test_insert_into_section() {
+        let mut document = Parser::new(CHANGELOG).parse().unwrap();
+
+        let section = document.section(unreleased).unwrap();
+
+        // the list of the `Fixed` subsection, the last list of the section.
+        let index = section
+            .clone()
+            .rev()
+            .find(|index| matches!(document.children[*index], Node::List(_)))
+            .unwrap();
+
+        let Node::List(list) = &mut document.children[index] else {
+            unreachable!();
+        };
+
+        let item = Parser::new("- Inserting.").parse().unwrap().children[0]
+            .children()
+            .unwrap()[0]
+            .clone();
+
+        list.add_child_node(item).unwrap();
+
+        let markdown = document.to_markdown(&SerializeOptions::default());
+
+        assert!(
+            markdown.contains("### Fixed\n\n* Sections.\n* Inserting.\n\n## 1.0.0"),
+            "{}",
+            markdown
+        );
+    }
+}
@@ -0,0 +1,457 @@
+//! Fluent API for constructing documents programmatically.
+//!
+//! ```
+//! use markdown_rs::ast::builder::DocumentBuilder;
+//!
+//! let document = DocumentBuilder::new()
+//!     .heading(1, |h| {
+//!         h.text("Title");
+//!     })
+//!     .paragraph(|p| {
+//!         p.text("See ").link("https://example.com", |l| {
+//!             l.text("example");
+//!         });
+//!     })
+//!     .finish()
+//!     .unwrap();
+//!
+//! assert_eq!(document.children.len(), 2);
+//! ```
+
+use alloc::borrow::Cow;
+
+use super::*;
+
+/// Builder of flow content, used for the document itself, blockquotes and list items.
+#[derive(Debug, Default)]
+pub struct DocumentBuilder<'cx> {
+    children: Vec<Node<'cx>>,
+    error: Option<AstError>,
+}
+
+impl<'cx> DocumentBuilder<'cx> {
+    /// Create new empty [`DocumentBuilder`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Append one flow content node.
+    pub fn flow<N>(&mut self, node: N) -> &mut Self
+    where
+        N: Into<Node<'cx>> + FlowContent,
+    {
+        self.children.push(node.into());
+        self
+    }
+
+    /// Append a heading, `depth` must be in [`Heading::DEPTHS`].
+    pub fn heading<F>(&mut self, depth: u8, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut InlineBuilder<'cx>),
+    {
+        let mut heading = match Heading::new(depth) {
+            Ok(heading) => heading,
+            Err(err) => {
+                self.fail(err);
+                return self;
+            }
+        };
+
+        heading.children = InlineBuilder::build(f);
+
+        self.flow(heading)
+    }
+
+    /// Append a paragraph.
+    pub fn paragraph<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut InlineBuilder<'cx>),
+    {
+        let children = InlineBuilder::build(f);
+
+        self.flow(Paragraph {
+            children,
+            data: None,
+        })
+    }
+
+    /// Append a code block.
+    pub fn code<V>(&mut self, lang: Option<&'cx str>, value: V) -> &mut Self
+    where
+        V: Into<Cow<'cx, str>>,
+    {
+        self.flow(Code {
+            value: value.into(),
+            lang: lang.map(Cow::Borrowed),
+            meta: None,
+            data: None,
+        })
+    }
+
+    /// Append a thematic break.
+    pub fn thematic_break(&mut self) -> &mut Self {
+        self.flow(ThematicBreak { data: None })
+    }
+
+    /// Append a blockquote containing flow content.
+    pub fn blockquote<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut DocumentBuilder<'cx>),
+    {
+        let mut builder = DocumentBuilder::new();
+
+        f(&mut builder);
+
+        match builder.error {
+            Some(error) => self.fail(error),
+            None => self.flow(Blockquote {
+                children: builder.children,
+                data: None,
+            }),
+        }
+    }
+
+    /// Append a list, items are added with [`ListBuilder::item`].
+    pub fn list<F>(&mut self, ordered: bool, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut ListBuilder<'cx>),
+    {
+        let mut builder = ListBuilder {
+            list: List::new(ordered, if ordered { Some(1) } else { None }),
+            error: None,
+        };
+
+        f(&mut builder);
+
+        match builder.error {
+            Some(error) => self.fail(error),
+            None => self.flow(builder.list),
+        }
+    }
+
+    /// Returns the built document, or the first error raised while building.
+    pub fn finish(&mut self) -> AstResult<Document<'cx>> {
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(Document {
+                children: core::mem::take(&mut self.children),
+                data: None,
+            }),
+        }
+    }
+
+    fn fail(&mut self, error: AstError) -> &mut Self {
+        if self.error.is_none() {
+            self.error = Some(error);
+        }
+
+        self
+    }
+}
+
+/// Builder of [`List`] items.
+#[derive(Debug)]
+pub struct ListBuilder<'cx> {
+    list: List<'cx>,
+    error: Option<AstError>,
+}
+
+impl<'cx> ListBuilder<'cx> {
+    /// Set the start number of an ordered list.
+    pub fn start(&mut self, start: u32) -> &mut Self {
+        if self.list.ordered == Some(true) {
+            self.list.start = Some(start);
+        }
+
+        self
+    }
+
+    /// Append one item containing flow content.
+    pub fn item<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut DocumentBuilder<'cx>),
+    {
+        self.add_item(None, f)
+    }
+
+    /// Append one GFM task item.
+    pub fn task<F>(&mut self, checked: bool, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut DocumentBuilder<'cx>),
+    {
+        self.add_item(Some(checked), f)
+    }
+
+    fn add_item<F>(&mut self, checked: Option<bool>, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut DocumentBuilder<'cx>),
+    {
+        let mut builder = DocumentBuilder::new();
+
+        f(&mut builder);
+
+        match builder.error {
+            Some(error) if self.error.is_none() => self.error = Some(error),
+            Some(_) => {}
+            None => {
+                let mut item = ListItem::new(checked);
+
+                item.children = builder.children;
+
+                self.list.children.push(item.into());
+            }
+        }
+
+        self
+    }
+}
+
+/// Builder of phrasing content, the content model of headings and paragraphs.
+#[derive(Debug, Default)]
+pub struct InlineBuilder<'cx> {
+    children: Vec<Node<'cx>>,
+}
+
+impl<'cx> InlineBuilder<'cx> {
+    fn build<F>(f: F) -> Vec<Node<'cx>>
+    where
+        F: FnOnce(&mut InlineBuilder<'cx>),
+    {
+        let mut builder = InlineBuilder::default();
+
+        f(&mut builder);
+
+        builder.children
+    }
+
+    /// Append one phrasing content node.
+    pub fn phrasing<N>(&mut self, node: N) -> &mut Self
+    where
+        N: Into<Node<'cx>> + PhrasingContent,
+    {
+        self.children.push(node.into());
+        self
+    }
+
+    /// Append a text.
+    pub fn text<V>(&mut self, value: V) -> &mut Self
+    where
+        V: Into<Cow<'cx, str>>,
+    {
+        self.phrasing(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    /// Append an inline code.
+    pub fn inline_code<V>(&mut self, value: V) -> &mut Self
+    where
+        V: Into<Cow<'cx, str>>,
+    {
+        self.phrasing(InlineCode {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    /// Append a hard line break.
+    pub fn hard_break(&mut self) -> &mut Self {
+        self.phrasing(Break { data: None })
+    }
+
+    /// Append an emphasis.
+    pub fn emphasis<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut InlineBuilder<'cx>),
+    {
+        let children = Self::build(f);
+
+        self.phrasing(Emphasis {
+            children,
+            data: None,
+        })
+    }
+
+    /// Append a strong.
+    pub fn strong<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnOnce(&mut InlineBuilder<'cx>),
+    {
+        let children = Self::build(f);
+
+        self.phrasing(Strong {
+            children,
+            data: None,
+        })
+    }
+
+    /// Append a link to `url`.
+    pub fn link<U, F>(&mut self, url: U, f: F) -> &mut Self
+    where
+        U: Into<Cow<'cx, str>>,
+        F: FnOnce(&mut InlineBuilder<'cx>),
+    {
+        let children = Self::build(f);
+
+        self.phrasing(Link {
+            children,
+            url: url.into(),
+            title: None,
+            data: None,
+        })
+    }
+
+    /// Append an image of `url` with `alt` text.
+    pub fn image<U, A>(&mut self, url: U, alt: A) -> &mut Self
+    where
+        U: Into<Cow<'cx, str>>,
+        A: Into<Cow<'cx, str>>,
+    {
+        self.phrasing(Image {
+            url: url.into(),
+            title: None,
+            alt: Some(alt.into()),
+            data: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn test_readme() {
+        let document = DocumentBuilder::new()
+            .heading(1, |h| {
+                h.text("markdown-rs");
+            })
+            .paragraph(|p| {
+                p.text("A ")
+                    .strong(|s| {
+                        s.text("fast");
+                    })
+                    .text(" parser, see ")
+                    .link("https://docs.rs", |l| {
+                        l.inline_code("docs");
+                    })
+                    .text(".");
+            })
+            .code(Some("sh"), "cargo add markdown-rs")
+            .list(true, |items| {
+                items
+                    .start(3)
+                    .item(|flow| {
+                        flow.paragraph(|p| {
+                            p.emphasis(|e| {
+                                e.text("lexer");
+                            });
+                        });
+                    })
+                    .task(true, |flow| {
+                        flow.blockquote(|quote| {
+                            quote.paragraph(|p| {
+                                p.image("logo.png", "logo");
+                            });
+                        });
+                    });
+            })
+            .thematic_break()
+            .finish()
+            .unwrap();
+
+        let mut first = ListItem::new(None);
+
+        first.children = vec![Node::Paragraph(Paragraph {
+            children: vec![Node::Emphasis(Emphasis {
+                children: vec![text("lexer")],
+                data: None,
+            })],
+            data: None,
+        })];
+
+        let mut second = ListItem::new(Some(true));
+
+        second.children = vec![Node::Blockquote(Blockquote {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![Node::from(Image {
+                    url: "logo.png".into(),
+                    title: None,
+                    alt: Some("logo".into()),
+                    data: None,
+                })],
+                data: None,
+            })],
+            data: None,
+        })];
+
+        let mut list = List::new(true, Some(3));
+
+        list.children = vec![first.into(), second.into()];
+
+        let expected = Document {
+            children: vec![
+                Node::Heading(Heading {
+                    children: vec![text("markdown-rs")],
+                    depth: 1,
+                    data: None,
+                }),
+                Node::Paragraph(Paragraph {
+                    children: vec![
+                        text("A "),
+                        Node::Strong(Strong {
+                            children: vec![text("fast")],
+                            data: None,
+                        }),
+                        text(" parser, see "),
+                        Node::from(Link {
+                            children: vec![Node::InlineCode(InlineCode {
+                                value: "docs".into(),
+                                data: None,
+                            })],
+                            url: "https://docs.rs".into(),
+                            title: None,
+                            data: None,
+                        }),
+                        text("."),
+                    ],
+                    data: None,
+                }),
+                Node::from(Code {
+                    value: "cargo add markdown-rs".into(),
+                    lang: Some("sh".into()),
+                    meta: None,
+                    data: None,
+                }),
+                Node::List(list),
+                Node::ThematicBreak(ThematicBreak { data: None }),
+            ],
+            data: None,
+        };
+
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn test_invalid_depth() {
+        let result = DocumentBuilder::new()
+            .list(false, |items| {
+                items.item(|flow| {
+                    flow.heading(7, |h| {
+                        h.text("too deep");
+                    });
+                });
+            })
+            .finish();
+
+        assert!(matches!(result, Err(AstError::InvalidDepth(7))));
+    }
+}
@@ -0,0 +1,214 @@
+//! Info strings of fenced code, split into the [`lang`](Code::lang) and [`meta`](Code::meta)
+//! of [`Code`], and the `key=value` and flag syntax most tools read from the meta.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{code::parse_meta, Code},
+//!     parser::Parser,
+//! };
+//!
+//! let document = Parser::new("```rust title=\"main.rs\" {1,3-5} no_run\nfn main() {}\n```")
+//!     .parse()
+//!     .unwrap();
+//!
+//! let code = &document.find_all_of::<Code>()[0];
+//!
+//! assert_eq!(code.lang.as_deref(), Some("rust"));
+//! assert_eq!(code.meta.as_deref(), Some("title=\"main.rs\" {1,3-5} no_run"));
+//!
+//! assert_eq!(
+//!     parse_meta(code.meta.as_deref().unwrap()),
+//!     [("title", Some("main.rs")), ("{1,3-5}", None), ("no_run", None)]
+//! );
+//! ```
+
+use crate::parser::unescape;
+
+use super::*;
+
+/// Splits the info string of a code fence into its language and meta.
+///
+/// The language is the first word of `info`, up to a space or a tab, and the meta is the rest
+/// with its leading and trailing spaces and tabs trimmed; both have their backslash escapes
+/// and character references processed. An info string of only spaces and tabs has neither,
+/// and a meta is `None` rather than empty.
+///
+/// ```
+/// use markdown_rs::ast::code::split_info;
+///
+/// assert_eq!(split_info("c\\+\\+ &amp; more"), (Some("c++".into()), Some("& more".into())));
+/// assert_eq!(split_info(" rust "), (Some("rust".into()), None));
+/// assert_eq!(split_info(" \t"), (None, None));
+/// ```
+pub fn split_info(info: &str) -> (Option<Cow<'_, str>>, Option<Cow<'_, str>>) {
+    let info = info.trim_matches([' ', '\t']);
+
+    if info.is_empty() {
+        return (None, None);
+    }
+
+    match info.split_once([' ', '\t']) {
+        Some((lang, meta)) => {
+            let meta = meta.trim_start_matches([' ', '\t']);
+
+            (
+                Some(unescape(lang)),
+                (!meta.is_empty()).then(|| unescape(meta)),
+            )
+        }
+        None => (Some(unescape(info)), None),
+    }
+}
+
+/// Parses a code meta, or a language such as `rust,no_run`, into `key=value` pairs and flags,
+/// in order.
+///
+/// Items are separated by whitespace or commas. A value is either bare, running to the next
+/// separator, or quoted with `"` or `'`, running to the matching quote and left unquoted; an
+/// unterminated quote runs to the end. Braces group a highlight range such as `{1,3-5}` into
+/// a single flag. Keys and values are not unescaped.
+///
+/// ```
+/// use markdown_rs::ast::code::parse_meta;
+///
+/// assert_eq!(
+///     parse_meta("rust,no_run title='a b' x="),
+///     [("rust", None), ("no_run", None), ("title", Some("a b")), ("x", Some(""))]
+/// );
+/// ```
+pub fn parse_meta(meta: &str) -> Vec<(&str, Option<&str>)> {
+    let is_separator = |c: char| c.is_whitespace() || c == ',';
+
+    let mut items = vec![];
+
+    let mut rest = meta;
+
+    loop {
+        rest = rest.trim_start_matches(is_separator);
+
+        if rest.is_empty() {
+            break;
+        }
+
+        // a group runs to its closing brace, commas included.
+        if rest.starts_with('{') {
+            let end = rest.find('}').map_or(rest.len(), |end| end + 1);
+
+            items.push((&rest[..end], None));
+            rest = &rest[end..];
+
+            continue;
+        }
+
+        let end = rest
+            .find(|c| is_separator(c) || c == '=')
+            .unwrap_or(rest.len());
+
+        let key = &rest[..end];
+
+        rest = &rest[end..];
+
+        let Some(value) = rest.strip_prefix('=') else {
+            items.push((key, None));
+
+            continue;
+        };
+
+        let (value, after) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value = &value[1..];
+
+                match value.find(quote) {
+                    Some(end) => (&value[..end], &value[end + 1..]),
+                    None => (value, ""),
+                }
+            }
+            _ => {
+                let end = value.find(is_separator).unwrap_or(value.len());
+
+                (&value[..end], &value[end..])
+            }
+        };
+
+        items.push((key, Some(value)));
+        rest = after;
+    }
+
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn info(md: &str) -> Vec<(Option<String>, Option<String>)> {
+        Parser::new(md)
+            .parse()
+            .unwrap()
+            .find_all_of::<Code>()
+            .into_iter()
+            .map(|code| {
+                (
+                    code.lang.as_deref().map(str::to_owned),
+                    code.meta.as_deref().map(str::to_owned),
+                )
+            })
+            .collect()
+    }
+
+    fn some(lang: &str, meta: &str) -> (Option<String>, Option<String>) {
+        (Some(lang.to_owned()), Some(meta.to_owned()))
+    }
+
+    #[test]
+    fn test_split_info() {
+        assert_eq!(info("```\na\n```"), [(None, None)]);
+        assert_eq!(info("```  \t\na\n```"), [(None, None)]);
+        assert_eq!(info("```js\na\n```"), [(Some("js".to_owned()), None)]);
+        assert_eq!(
+            info("```  js \t title=\"a  b\"  \t\na\n```"),
+            [some("js", "title=\"a  b\"")]
+        );
+
+        // escapes and references, in the language and the meta.
+        assert_eq!(
+            info("~~~ f\\#&#32;x &quot;y\\`\na\n~~~"),
+            [some("f# x", "\"y`")]
+        );
+
+        // a backtick is only allowed in the info string of a tilde fence.
+        assert_eq!(info("~~~ js `x` y\na\n~~~"), [some("js", "`x` y")]);
+        assert_eq!(info("~~~`js`\na\n~~~"), [(Some("`js`".to_owned()), None)]);
+        assert!(info("``` js `x`\na\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_meta() {
+        // quoted values with spaces.
+        assert_eq!(
+            parse_meta(r#"title="main file.rs" alt='a, b' {1,3-5}"#),
+            [
+                ("title", Some("main file.rs")),
+                ("alt", Some("a, b")),
+                ("{1,3-5}", None)
+            ]
+        );
+
+        // a lone flag.
+        assert_eq!(parse_meta("  no_run  "), [("no_run", None)]);
+
+        assert_eq!(
+            parse_meta("a=1,b=\"2\"c d='unterminated"),
+            [
+                ("a", Some("1")),
+                ("b", Some("2")),
+                ("c", None),
+                ("d", Some("unterminated"))
+            ]
+        );
+        assert_eq!(parse_meta("{1"), [("{1", None)]);
+        assert!(parse_meta(" , ").is_empty());
+    }
+}
@@ -0,0 +1,374 @@
+//! Html comments used as markers and directives, such as `<!-- toc -->` or
+//! `<!-- prettier-ignore -->`.
+//!
+//! Comments are found both in html blocks and in inline html, where the block holding them,
+//! a paragraph, heading or table cell, is reported in their place.
+//!
+//! ```
+//! use markdown_rs::{ast::comments, parser::Parser};
+//!
+//! let source = "# Post\n\nIntro.<!-- more -->\n\n<!-- toc -->\n\n<!-- layout: wide -->\n";
+//!
+//! let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+//!
+//! let found = comments::find(&document, &spans, source, "more");
+//!
+//! assert_eq!(found.len(), 1);
+//! assert_eq!(&source[found[0].1.clone()], "<!-- more -->");
+//!
+//! assert_eq!(comments::directives(&document), [("layout", "wide")]);
+//! ```
+
+use core::ops::Range;
+
+use super::spans::Spans;
+use super::validate::is_phrasing;
+use super::*;
+
+/// A comment of the tree, see [`for_each`].
+struct Comment<'p, 'd, 'cx> {
+    /// The block holding the comment: `html` itself, or the block of an inline comment.
+    block: &'d Node<'cx>,
+    /// Position of `html`, child indices from the document root.
+    path: &'p [usize],
+    /// Index of the comment among those of `html`.
+    index: usize,
+    /// Text between `<!--` and `-->`.
+    text: &'d str,
+}
+
+/// Returns the blocks holding the comments of `document` whose trimmed text is `pattern`,
+/// trimmed, with the source range of each comment.
+///
+/// A comment of an html block is reported with the html block, an inline comment with the
+/// paragraph, heading or table cell it is part of. `spans` and `source` are those of the
+/// parse that produced `document`, comments of nodes without a span are left out.
+pub fn find<'d, 'cx>(
+    document: &'d Document<'cx>,
+    spans: &Spans,
+    source: &str,
+    pattern: &str,
+) -> Vec<(&'d Node<'cx>, Range<usize>)> {
+    let pattern = pattern.trim();
+
+    let mut found = vec![];
+
+    for_each(document, |comment| {
+        if comment.text.trim() != pattern {
+            return;
+        }
+
+        let Some(span) = spans.get(comment.path) else {
+            return;
+        };
+
+        // the comments of the source of the html node are those of its value, the prefixes
+        // of its containers left out.
+        let Some(range) = source
+            .get(span.clone())
+            .and_then(|html| comment_ranges(html).nth(comment.index))
+        else {
+            return;
+        };
+
+        found.push((
+            comment.block,
+            span.start + range.start..span.start + range.end,
+        ));
+    });
+
+    found
+}
+
+/// Returns the `key: value` pairs of the directive comments of `document`, in document order,
+/// see [`directive`].
+pub fn directives<'d>(document: &'d Document) -> Vec<(&'d str, &'d str)> {
+    let mut directives = vec![];
+
+    for_each(document, |comment| {
+        directives.extend(directive(comment.text))
+    });
+
+    directives
+}
+
+/// Parses the text of a comment, between `<!--` and `-->`, as a `key: value` directive.
+///
+/// The key is made of ASCII alphanumerics, `-` and `_`, the value is the trimmed rest of the
+/// text and may be empty.
+///
+/// ```
+/// use markdown_rs::ast::comments::directive;
+///
+/// assert_eq!(directive(" layout:  wide "), Some(("layout", "wide")));
+/// assert_eq!(directive("toc"), None);
+/// assert_eq!(directive("see: http://a.b"), Some(("see", "http://a.b")));
+/// ```
+pub fn directive(text: &str) -> Option<(&str, &str)> {
+    let (key, value) = text.split_once(':')?;
+
+    let key = key.trim();
+
+    if key.is_empty()
+        || !key
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_'))
+    {
+        return None;
+    }
+
+    Some((key, value.trim()))
+}
+
+/// Removes the html nodes of `document` made of a single comment whose trimmed text is
+/// `pattern`, trimmed, returns the number of removed nodes.
+///
+/// Html blocks holding other content than the comment are kept.
+pub fn remove(document: &mut Document, pattern: &str) -> usize {
+    let mut remover = Remover {
+        pattern: pattern.trim(),
+        count: 0,
+    };
+
+    document.children.retain(|child| match child {
+        Node::Html(html) => !remover.matches(html),
+        _ => true,
+    });
+
+    for child in &mut document.children {
+        walk_mut(child, &mut remover);
+    }
+
+    remover.count
+}
+
+struct Remover<'p> {
+    pattern: &'p str,
+    count: usize,
+}
+
+impl<'p> Remover<'p> {
+    /// Returns true, and counts it, if `html` is a single comment matching the pattern.
+    fn matches(&mut self, html: &Html) -> bool {
+        let is_match = is_marker(html, self.pattern);
+
+        self.count += is_match as usize;
+
+        is_match
+    }
+}
+
+/// Returns true if `html` is made of a single comment whose trimmed text is `pattern`, which
+/// must be trimmed.
+pub(crate) fn is_marker(html: &Html, pattern: &str) -> bool {
+    let value = html.value.trim_end_matches(['\n', '\r']);
+
+    comment_ranges(value).next() == Some(0..value.len())
+        && comment_text(value).is_some_and(|text| text.trim() == pattern)
+}
+
+impl<'p, 'cx> VisitorMut<'cx> for Remover<'p> {
+    fn visit_html(&mut self, node: &mut Html<'cx>) -> Transform<'cx> {
+        if self.matches(node) {
+            Transform::Remove
+        } else {
+            Transform::Keep
+        }
+    }
+}
+
+/// Calls `f` with every comment of `document`, in document order.
+fn for_each<'d, 'cx, F>(document: &'d Document<'cx>, mut f: F)
+where
+    F: FnMut(Comment<'_, 'd, 'cx>),
+{
+    let mut ancestors: Vec<&'d Node<'cx>> = vec![];
+
+    let mut paths: Vec<(Vec<usize>, &'d Node<'cx>, &'d Node<'cx>)> = vec![];
+
+    super::links::for_each_with_path(document, |path, node| {
+        ancestors.truncate(path.len() - 1);
+
+        if let Node::Html(_) = node {
+            let block = ancestors
+                .iter()
+                .rev()
+                .take_while(|ancestor| is_inline_parent(ancestor))
+                .last()
+                .copied()
+                .unwrap_or(node);
+
+            paths.push((path.to_vec(), node, block));
+        }
+
+        ancestors.push(node);
+    });
+
+    for (path, html, block) in &paths {
+        let value = html.value().unwrap_or_default();
+
+        for (index, range) in comment_ranges(value).enumerate() {
+            let text = comment_text(&value[range]).unwrap_or_default();
+
+            f(Comment {
+                block,
+                path,
+                index,
+                text,
+            });
+        }
+    }
+}
+
+/// Returns true if the children of `node` are phrasing content.
+fn is_inline_parent(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::Paragraph(_) | Node::Heading(_) | Node::TableCell(_)
+    ) || is_phrasing(node.kind())
+}
+
+/// Iterate over the byte ranges of the comments of `html`, an unterminated one running to its
+/// end.
+fn comment_ranges(html: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut offset = 0;
+
+    core::iter::from_fn(move || {
+        let start = offset + html.get(offset..)?.find("<!--")?;
+
+        // `<!-->` and `<!--->` are comments.
+        let end = html[start + 2..]
+            .find("-->")
+            .map_or(html.len(), |end| start + 2 + end + 3);
+
+        offset = end;
+
+        Some(start..end)
+    })
+}
+
+/// Returns the text of `comment`, a comment from `<!--` to `-->`, or to its end if
+/// unterminated.
+fn comment_text(comment: &str) -> Option<&str> {
+    let inner = comment.strip_prefix("<!--")?;
+
+    // `<!-->` and `<!--->` are empty.
+    if matches!(inner, ">" | "->") {
+        return Some("");
+    }
+
+    Some(inner.strip_suffix("-->").unwrap_or(inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "\
+<!-- toc -->
+
+# Title <!--toc-->
+
+Some *text <!-- toc -->* here.
+
+> <!-- toc
+> -->
+> <!-- prettier-ignore --> <!--   toc   -->
+
+<!-- draft: true --><!-- author:  Ann Lee -->
+
+| a | b <!-- toc --> |
+| - | - |
+";
+
+    /// Returns the kind of each found block, and the source text of each comment.
+    fn found(pattern: &str) -> Vec<(NodeKind, &'static str)> {
+        let (document, spans) = Parser::new(MARKDOWN).parse_with_spans().unwrap();
+
+        find(&document, &spans, MARKDOWN, pattern)
+            .into_iter()
+            .map(|(node, range)| (node.kind(), &MARKDOWN[range]))
+            .collect()
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(
+            found(" toc "),
+            [
+                (NodeKind::Html, "<!-- toc -->"),
+                (NodeKind::Heading, "<!--toc-->"),
+                (NodeKind::Paragraph, "<!-- toc -->"),
+                (NodeKind::Html, "<!-- toc\n> -->"),
+                (NodeKind::Html, "<!--   toc   -->"),
+                (NodeKind::TableCell, "<!-- toc -->"),
+            ]
+        );
+        assert_eq!(
+            found("prettier-ignore"),
+            [(NodeKind::Html, "<!-- prettier-ignore -->")]
+        );
+        assert!(found("more").is_empty());
+    }
+
+    #[test]
+    fn test_multiline() {
+        // the source of a comment includes the prefixes of its containers, its text doesn't.
+        let source = "<!-- toc\n> -->\n\nA <!--\n  more\n--> b.\n\n> <!-- toc\n> -->\n";
+
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        let ranges = |pattern| {
+            find(&document, &spans, source, pattern)
+                .into_iter()
+                .map(|(node, range)| (node.kind(), &source[range]))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ranges("toc\n>"), [(NodeKind::Html, "<!-- toc\n> -->")]);
+        assert_eq!(ranges("more"), [(NodeKind::Paragraph, "<!--\n  more\n-->")]);
+        assert_eq!(ranges("toc"), [(NodeKind::Html, "<!-- toc\n> -->")]);
+    }
+
+    #[test]
+    fn test_directives() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        assert_eq!(
+            directives(&document),
+            [("draft", "true"), ("author", "Ann Lee")]
+        );
+
+        assert_eq!(directive("key:"), Some(("key", "")));
+        assert_eq!(directive(": value"), None);
+        assert_eq!(directive("two words: value"), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut document = Parser::new(MARKDOWN).parse().unwrap();
+
+        // the second html block of the blockquote holds another comment.
+        assert_eq!(remove(&mut document, "toc"), 5);
+
+        let mut document_without = Parser::new(MARKDOWN).parse().unwrap();
+
+        assert_eq!(remove(&mut document_without, "nothing"), 0);
+
+        assert_eq!(document.children.len(), document_without.children.len() - 1);
+        assert!(document
+            .descendants()
+            .filter_map(Node::value)
+            .all(|value| !value.contains("<!--toc-->") && value != "<!-- toc -->"));
+
+        let mut empty = Parser::new("<!---->\n\nA<!-->").parse().unwrap();
+
+        assert_eq!(remove(&mut empty, ""), 2);
+        assert_eq!(
+            empty.to_string(),
+            "root[1]\n  paragraph[1]\n    text \"A\"\n"
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! Structural diff of two documents, for change detection and incremental re-rendering.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::diff::{diff, Change},
+//!     parser::Parser,
+//! };
+//!
+//! let old = Parser::new("# Title\n\nBody").parse().unwrap();
+//! let new = Parser::new("# Title\n\nNew body").parse().unwrap();
+//!
+//! let entries = diff(&old, &new);
+//!
+//! assert_eq!(entries.len(), 1);
+//! assert_eq!(entries[0].path, [1, 0]);
+//! assert!(matches!(&entries[0].change, Change::TextChanged { new, .. } if new == "New body"));
+//! ```
+
+use super::*;
+
+/// Kind of a change found by [`diff()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    /// A node of the new document has no counterpart in the old one.
+    Inserted(NodeKind),
+    /// A node of the old document has no counterpart in the new one.
+    Removed(NodeKind),
+    /// The value of a literal node (text, inline code, code, html) changed.
+    TextChanged { old: String, new: String },
+    /// A field other than children or value changed, absent optional fields are `None`.
+    AttributeChanged {
+        name: &'static str,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+/// A change found by [`diff()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffEntry {
+    /// Child indices from the document root of the changed node: in the new document for
+    /// [`Change::Inserted`], in the old document otherwise.
+    pub path: Vec<usize>,
+    pub change: Change,
+}
+
+/// Returns the changes turning `old` into `new`, in document order.
+///
+/// Children lists are matched with a longest common subsequence of equal subtrees, unmatched
+/// children of the same kind at the same relative position are diffed recursively. The result
+/// is stable and correct but not necessarily minimal. Identical documents yield no entry.
+pub fn diff(old: &Document, new: &Document) -> Vec<DiffEntry> {
+    let mut entries = vec![];
+
+    diff_children(
+        &old.children,
+        &new.children,
+        &mut vec![],
+        &mut vec![],
+        &mut entries,
+    );
+
+    entries
+}
+
+fn diff_children(
+    old: &[Node],
+    new: &[Node],
+    old_path: &mut Vec<usize>,
+    new_path: &mut Vec<usize>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    let mut old_start = 0;
+    let mut new_start = 0;
+
+    // a trailing sentinel match flushes the last gap.
+    for (old_end, new_end) in lcs(old, new).into_iter().chain([(old.len(), new.len())]) {
+        let pairs = (old_end - old_start).min(new_end - new_start);
+
+        for offset in 0..pairs {
+            let (old_index, new_index) = (old_start + offset, new_start + offset);
+
+            let (old_node, new_node) = (&old[old_index], &new[new_index]);
+
+            old_path.push(old_index);
+            new_path.push(new_index);
+
+            if old_node.kind() == new_node.kind() {
+                diff_node(old_node, new_node, old_path, new_path, entries);
+            } else {
+                entries.push(DiffEntry {
+                    path: old_path.clone(),
+                    change: Change::Removed(old_node.kind()),
+                });
+                entries.push(DiffEntry {
+                    path: new_path.clone(),
+                    change: Change::Inserted(new_node.kind()),
+                });
+            }
+
+            old_path.pop();
+            new_path.pop();
+        }
+
+        for (index, node) in old.iter().enumerate().take(old_end).skip(old_start + pairs) {
+            entries.push(DiffEntry {
+                path: with_index(old_path, index),
+                change: Change::Removed(node.kind()),
+            });
+        }
+
+        for (index, node) in new.iter().enumerate().take(new_end).skip(new_start + pairs) {
+            entries.push(DiffEntry {
+                path: with_index(new_path, index),
+                change: Change::Inserted(node.kind()),
+            });
+        }
+
+        old_start = old_end + 1;
+        new_start = new_end + 1;
+    }
+}
+
+/// Diff two nodes of the same kind.
+fn diff_node(
+    old: &Node,
+    new: &Node,
+    old_path: &mut Vec<usize>,
+    new_path: &mut Vec<usize>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    if let (Some(old_value), Some(new_value)) = (old.value(), new.value()) {
+        if old_value != new_value {
+            entries.push(DiffEntry {
+                path: old_path.clone(),
+                change: Change::TextChanged {
+                    old: old_value.to_owned(),
+                    new: new_value.to_owned(),
+                },
+            });
+        }
+    }
+
+    let old_attributes = old.attributes();
+    let new_attributes = new.attributes();
+
+    let mut names: Vec<&'static str> = vec![];
+
+    for (name, _) in old_attributes.iter().chain(&new_attributes) {
+        if !names.contains(name) {
+            names.push(name);
+        }
+    }
+
+    let get = |attributes: &[(&'static str, String)], name| {
+        attributes
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    for name in names {
+        let (old_value, new_value) = (get(&old_attributes, name), get(&new_attributes, name));
+
+        if old_value != new_value {
+            entries.push(DiffEntry {
+                path: old_path.clone(),
+                change: Change::AttributeChanged {
+                    name,
+                    old: old_value,
+                    new: new_value,
+                },
+            });
+        }
+    }
+
+    if let (Some(old_children), Some(new_children)) = (old.children(), new.children()) {
+        diff_children(old_children, new_children, old_path, new_path, entries);
+    }
+}
+
+fn with_index(path: &[usize], index: usize) -> Vec<usize> {
+    let mut path = path.to_vec();
+
+    path.push(index);
+
+    path
+}
+
+/// Returns the index pairs of a longest common subsequence of equal nodes.
+fn lcs(old: &[Node], new: &[Node]) -> Vec<(usize, usize)> {
+    // the common prefix and suffix are matched directly, most edits are local.
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let (old_middle, new_middle) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let (n, m) = (old_middle.len(), new_middle.len());
+
+    // lengths[i][j] is the lcs length of old_middle[i..] and new_middle[j..].
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if old_middle[i] == new_middle[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = (0..prefix).map(|index| (index, index)).collect::<Vec<_>>();
+
+    let (mut i, mut j) = (0, 0);
+
+    while i < n && j < m {
+        if old_middle[i] == new_middle[j] {
+            pairs.push((prefix + i, prefix + j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    pairs.extend(
+        (0..suffix).map(|offset| (old.len() - suffix + offset, new.len() - suffix + offset)),
+    );
+
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn parse(markdown: &str) -> Document<'_> {
+        Parser::new(markdown).parse().unwrap()
+    }
+
+    #[test]
+    fn test_identical() {
+        let markdown = "# Title\n\n- a\n- b\n\n```rust\nfn main() {}\n```\n";
+
+        assert_eq!(diff(&parse(markdown), &parse(markdown)), []);
+    }
+
+    #[test]
+    fn test_changed_heading() {
+        let old = parse("# Intro\n\nBody\n");
+
+        assert_eq!(
+            diff(&old, &parse("# Introduction\n\nBody\n")),
+            [DiffEntry {
+                path: vec![0, 0],
+                change: Change::TextChanged {
+                    old: "Intro".to_owned(),
+                    new: "Introduction".to_owned(),
+                },
+            }]
+        );
+
+        assert_eq!(
+            diff(&old, &parse("## Intro\n\nBody\n")),
+            [DiffEntry {
+                path: vec![0],
+                change: Change::AttributeChanged {
+                    name: "depth",
+                    old: Some("1".to_owned()),
+                    new: Some("2".to_owned()),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn test_inserted_paragraph() {
+        let old = parse("# Title\n\nFirst\n\nLast\n");
+        let new = parse("# Title\n\nFirst\n\nMiddle\n\nLast\n");
+
+        assert_eq!(
+            diff(&old, &new),
+            [DiffEntry {
+                path: vec![2],
+                change: Change::Inserted(NodeKind::Paragraph),
+            }]
+        );
+
+        assert_eq!(
+            diff(&new, &old),
+            [DiffEntry {
+                path: vec![2],
+                change: Change::Removed(NodeKind::Paragraph),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_kind_changed() {
+        let old = parse("Text\n\n---\n");
+        let new = parse("Text\n\n> Quote\n");
+
+        assert_eq!(
+            diff(&old, &new),
+            [
+                DiffEntry {
+                    path: vec![1],
+                    change: Change::Removed(NodeKind::ThematicBreak),
+                },
+                DiffEntry {
+                    path: vec![1],
+                    change: Change::Inserted(NodeKind::Blockquote),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Excerpts of documents, for listing pages.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{excerpt, excerpt::ExcerptRule},
+//!     parser::Parser,
+//!     to_markdown::SerializeOptions,
+//! };
+//!
+//! let document = Parser::new("A *very long* post.\n\n<!--more-->\n\nThe rest.")
+//!     .parse()
+//!     .unwrap();
+//!
+//! let words = excerpt(&document, ExcerptRule::Words(2));
+//!
+//! assert_eq!(words.to_markdown(&SerializeOptions::default()), "A *very*\n");
+//!
+//! let marker = excerpt(&document, ExcerptRule::Marker("<!--more-->"));
+//!
+//! assert_eq!(marker.to_markdown(&SerializeOptions::default()), "A *very long* post.\n");
+//! ```
+
+use alloc::collections::BTreeSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::resolve::definitions;
+use super::*;
+
+/// Where [`excerpt()`] cuts a document.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExcerptRule<'a> {
+    /// Keep the given number of words, following Unicode word segmentation. The cut may fall
+    /// inside a text node.
+    Words(usize),
+    /// Keep the given number of top-level blocks, definitions aside.
+    Blocks(usize),
+    /// Keep everything before the first [`Html`] node whose trimmed value is the marker, such as
+    /// `<!--more-->`, or the whole document if there is none.
+    Marker(&'a str),
+}
+
+/// Returns the beginning of `document` according to `rule`, as a valid tree.
+///
+/// Parents around the cut keep the children before it, parents left empty are dropped. Code
+/// blocks and tables are never split: they are kept whole if they start before the cut, code
+/// counting no words. Definitions used by the kept references are appended to the excerpt.
+pub fn excerpt<'cx>(document: &Document<'cx>, rule: ExcerptRule) -> Document<'cx> {
+    let mut excerpt = match rule {
+        ExcerptRule::Blocks(blocks) => {
+            // right after the last kept block, definitions in between included.
+            let end = match blocks.checked_sub(1) {
+                Some(last) => document
+                    .children
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, child)| !matches!(child, Node::Definition(_)))
+                    .nth(last)
+                    .map_or(document.children.len(), |(index, _)| index + 1),
+                None => 0,
+            };
+
+            Document {
+                children: document.children[..end].to_vec(),
+                data: None,
+            }
+        }
+        ExcerptRule::Words(words) => {
+            let mut excerpt = document.clone();
+
+            let mut cutter = Cutter {
+                rule,
+                remaining: words,
+                done: words == 0,
+            };
+
+            cutter.cut(&mut excerpt.children);
+
+            excerpt
+        }
+        ExcerptRule::Marker(_) => {
+            let mut excerpt = document.clone();
+
+            let mut cutter = Cutter {
+                rule,
+                remaining: 0,
+                done: false,
+            };
+
+            cutter.cut(&mut excerpt.children);
+
+            excerpt
+        }
+    };
+
+    let definitions = definitions(document);
+
+    let mut defined = excerpt
+        .descendants()
+        .filter_map(|node| match node {
+            Node::Definition(definition) => Some(normalize_identifier(&definition.identifier)),
+            _ => None,
+        })
+        .collect::<BTreeSet<_>>();
+
+    let mut missing = vec![];
+
+    for node in excerpt.descendants() {
+        let identifier = match node {
+            Node::LinkReference(reference) => &reference.identifier,
+            Node::ImageReference(reference) => &reference.identifier,
+            _ => continue,
+        };
+
+        let identifier = normalize_identifier(identifier);
+
+        if let Some(definition) = definitions.get(&identifier) {
+            if defined.insert(identifier) {
+                missing.push(Node::from((*definition).clone()));
+            }
+        }
+    }
+
+    excerpt.children.extend(missing);
+
+    excerpt
+}
+
+struct Cutter<'r> {
+    rule: ExcerptRule<'r>,
+    /// Words left, for [`ExcerptRule::Words`].
+    remaining: usize,
+    done: bool,
+}
+
+impl<'r> Cutter<'r> {
+    /// Drop the nodes after the cut.
+    fn cut(&mut self, nodes: &mut Vec<Node>) {
+        let mut index = 0;
+
+        while index < nodes.len() && !self.done {
+            if !self.keep(&mut nodes[index]) {
+                break;
+            }
+
+            index += 1;
+        }
+
+        nodes.truncate(index);
+
+        // text right before the cut would end with whitespace, which breaks closing emphasis.
+        if self.done {
+            if let Some(Node::Text(text)) = nodes.last_mut() {
+                match &mut text.value {
+                    Cow::Borrowed(value) => *value = value.trim_end(),
+                    Cow::Owned(value) => value.truncate(value.trim_end().len()),
+                }
+
+                if text.value.is_empty() {
+                    nodes.pop();
+                }
+            }
+        }
+    }
+
+    /// Cut inside `node` if needed, returns `false` if it must be dropped.
+    fn keep(&mut self, node: &mut Node) -> bool {
+        match (self.rule, &mut *node) {
+            (ExcerptRule::Marker(marker), Node::Html(html)) if html.value.trim() == marker => {
+                self.done = true;
+
+                return false;
+            }
+            (ExcerptRule::Words(_), Node::Text(text)) => {
+                self.text(&mut text.value);
+
+                return true;
+            }
+            (ExcerptRule::Words(_), Node::InlineCode(InlineCode { value, .. })) => {
+                self.count(value.unicode_words().count());
+
+                return true;
+            }
+            (ExcerptRule::Words(_), Node::Table(_)) => {
+                self.count(node.to_text(&Default::default()).unicode_words().count());
+
+                return true;
+            }
+            (_, Node::Code(_) | Node::Table(_)) => return true,
+            _ => {}
+        }
+
+        let Some(children) = node.children_mut() else {
+            return true;
+        };
+
+        let empty = children.is_empty();
+
+        self.cut(children);
+
+        empty || !children.is_empty()
+    }
+
+    fn count(&mut self, words: usize) {
+        self.remaining = self.remaining.saturating_sub(words);
+
+        self.done = self.remaining == 0;
+    }
+
+    fn text(&mut self, value: &mut Cow<str>) {
+        let count = value.unicode_words().count();
+
+        if count < self.remaining {
+            self.remaining -= count;
+
+            return;
+        }
+
+        let (index, word) = value
+            .unicode_word_indices()
+            .nth(self.remaining - 1)
+            .expect("enough words");
+
+        let end = index + word.len();
+
+        match value {
+            Cow::Borrowed(value) => {
+                let borrowed: &str = value;
+
+                *value = &borrowed[..end];
+            }
+            Cow::Owned(value) => value.truncate(end),
+        }
+
+        self.remaining = 0;
+        self.done = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn markdown(source: &str, rule: ExcerptRule) -> String {
+        let document = Parser::new(source).parse().unwrap();
+
+        excerpt(&document, rule).to_markdown(&Default::default())
+    }
+
+    #[test]
+    fn test_words_mid_emphasis() {
+        assert_eq!(
+            markdown("Some **very *deeply* nested** text.", ExcerptRule::Words(3)),
+            "Some __very *deeply*__\n"
+        );
+    }
+
+    #[test]
+    fn test_words_mid_link() {
+        let document = Parser::new("See [the full docs](https://docs.rs) here.\n\nMore.")
+            .parse()
+            .unwrap();
+
+        let excerpt = excerpt(&document, ExcerptRule::Words(3));
+
+        assert_eq!(
+            excerpt.to_markdown(&Default::default()),
+            "See [the full](https://docs.rs)\n"
+        );
+        assert!(super::super::validate(&excerpt).is_ok());
+    }
+
+    #[test]
+    fn test_words_block_boundary() {
+        let source = "# One two\n\n* three\n* four\n\n```\nnot counted\n```\n\nfive six";
+
+        assert_eq!(
+            markdown(source, ExcerptRule::Words(4)),
+            "# One two\n\n* three\n* four\n"
+        );
+        assert_eq!(
+            markdown(source, ExcerptRule::Words(5)),
+            "# One two\n\n* three\n* four\n\n```\nnot counted\n```\n\nfive\n"
+        );
+        assert_eq!(markdown(source, ExcerptRule::Words(0)), "");
+    }
+
+    #[test]
+    fn test_shorter_than_limit() {
+        let document = Parser::new("# Title\n\nA [link][a] and `code`.\n\n[a]: https://a.example")
+            .parse()
+            .unwrap();
+
+        assert_eq!(excerpt(&document, ExcerptRule::Words(100)), document);
+        assert_eq!(excerpt(&document, ExcerptRule::Blocks(2)), document);
+        assert_eq!(
+            excerpt(&document, ExcerptRule::Marker("<!--more-->")),
+            document
+        );
+    }
+
+    #[test]
+    fn test_blocks() {
+        assert_eq!(
+            markdown(
+                "[a]: /a\n\n# Title\n\n[link][a]\n\n---\n\nrest",
+                ExcerptRule::Blocks(2)
+            ),
+            "[a]: /a\n\n# Title\n\n[link][a]\n"
+        );
+
+        // the definition of a kept reference comes along.
+        assert_eq!(
+            markdown(
+                "[link][a]\n\n[a]: /a\n\n[b]: /b\n\nrest",
+                ExcerptRule::Blocks(1)
+            ),
+            "[link][a]\n\n[a]: /a\n"
+        );
+    }
+
+    #[test]
+    fn test_marker() {
+        assert_eq!(
+            markdown(
+                "> quote *before <!--more--> after*\n\nrest",
+                ExcerptRule::Marker("<!--more-->")
+            ),
+            "> quote *before*\n"
+        );
+        assert_eq!(
+            markdown("<!--more-->\n\nrest", ExcerptRule::Marker("<!--more-->")),
+            ""
+        );
+    }
+}
@@ -0,0 +1,259 @@
+//! Indexes of the definitions, headings and footnote definitions of a document, for tools that
+//! look them up by identifier or list them without walking the tree again.
+//!
+//! [`Parser::parse_indexed`](crate::parser::Parser::parse_indexed) returns a [`DocumentIndex`]
+//! next to the document. Every entry holds the position of its node (see
+//! [`spans`]) and its source range.
+//!
+//! ```
+//! use markdown_rs::parser::Parser;
+//!
+//! let source = "# Intro\n\nSee [docs].\n\n[docs]: /docs \"Docs\"\n";
+//!
+//! let (_, index) = Parser::new(source).parse_indexed().unwrap();
+//!
+//! assert_eq!(index.definitions["docs"].url, "/docs");
+//! assert_eq!(index.headings[0].slug, "intro");
+//! assert_eq!(&source[index.headings[0].span.clone()], "# Intro");
+//! ```
+
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+use super::{links::for_each_with_path, slug::heading_text, slug::Slugger, spans::Spans, *};
+
+/// Side indexes of a document, see [`index`](self).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocumentIndex<'cx> {
+    /// Definitions keyed by their normalized identifier (see [`normalize_identifier`]). As per
+    /// CommonMark, the first definition of an identifier wins.
+    pub definitions: BTreeMap<String, DefinitionEntry<'cx>>,
+    /// Headings in document order.
+    pub headings: Vec<HeadingEntry>,
+    /// Footnote definitions keyed by their normalized identifier, the first one wins.
+    pub footnotes: BTreeMap<String, FootnoteEntry<'cx>>,
+}
+
+/// A [`Definition`] of a [`DocumentIndex`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DefinitionEntry<'cx> {
+    /// The URL of the definition.
+    pub url: Cow<'cx, str>,
+    /// The title of the definition.
+    pub title: Option<Cow<'cx, str>>,
+    /// Child indices from the document root to the definition.
+    pub path: Vec<usize>,
+    /// Source range of the definition.
+    pub span: Range<usize>,
+}
+
+/// A [`Heading`] of a [`DocumentIndex`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeadingEntry {
+    /// The depth of the heading, 1 to 6.
+    pub depth: u8,
+    /// Plain text of the heading content.
+    pub text: String,
+    /// The slug of the heading, unique within the document (see [`Slugger`]).
+    pub slug: String,
+    /// Child indices from the document root to the heading.
+    pub path: Vec<usize>,
+    /// Source range of the heading.
+    pub span: Range<usize>,
+}
+
+/// A [`FootnoteDefinition`] of a [`DocumentIndex`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FootnoteEntry<'cx> {
+    /// The label of the footnote definition.
+    pub label: Option<Cow<'cx, str>>,
+    /// Child indices from the document root to the footnote definition.
+    pub path: Vec<usize>,
+    /// Source range of the footnote definition.
+    pub span: Range<usize>,
+}
+
+impl<'cx> DocumentIndex<'cx> {
+    /// Create new [`DocumentIndex`] of `document` in a single walk, `spans` are those of the
+    /// parse that produced it.
+    ///
+    /// Nodes without a span, e.g. added after parsing, get an empty range at offset 0.
+    pub fn new(document: &Document<'cx>, spans: &Spans) -> Self {
+        let mut index = Self::default();
+
+        let mut slugger = Slugger::new();
+
+        for_each_with_path(document, |path, node| {
+            let span = spans.get(path).unwrap_or_default();
+
+            match node {
+                Node::Definition(definition) => {
+                    index
+                        .definitions
+                        .entry(normalize_identifier(&definition.identifier))
+                        .or_insert_with(|| DefinitionEntry {
+                            url: definition.url.clone(),
+                            title: definition.title.clone(),
+                            path: path.to_vec(),
+                            span,
+                        });
+                }
+                Node::Heading(heading) => {
+                    let text = heading_text(heading);
+
+                    index.headings.push(HeadingEntry {
+                        depth: heading.depth,
+                        slug: slugger.slug(&text),
+                        text,
+                        path: path.to_vec(),
+                        span,
+                    });
+                }
+                Node::FootnoteDefinition(definition) => {
+                    index
+                        .footnotes
+                        .entry(normalize_identifier(&definition.identifier))
+                        .or_insert_with(|| FootnoteEntry {
+                            label: definition.label.clone(),
+                            path: path.to_vec(),
+                            span,
+                        });
+                }
+                _ => {}
+            }
+        });
+
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{resolve::definitions, slug::slugger},
+        parser::Parser,
+    };
+
+    use super::*;
+
+    const SOURCE: &str = "\
+# Guide
+
+[a]: /first \"First\"
+
+## Setup *fast*
+
+> [A]: /second
+>
+> ### Guide
+
+Text and [a].
+
+#### Deep
+";
+
+    #[test]
+    fn test_index() {
+        let (document, index) = Parser::new(SOURCE).parse_indexed().unwrap();
+
+        // the first of the duplicate definitions wins.
+        assert_eq!(index.definitions.len(), 1);
+
+        let definition = &index.definitions["a"];
+
+        assert_eq!(definition.url, "/first");
+        assert_eq!(definition.title.as_deref(), Some("First"));
+        assert_eq!(definition.path, [1]);
+        assert_eq!(&SOURCE[definition.span.clone()], "[a]: /first \"First\"");
+
+        let headings = index
+            .headings
+            .iter()
+            .map(|heading| {
+                (
+                    heading.depth,
+                    heading.text.as_str(),
+                    heading.slug.as_str(),
+                    &SOURCE[heading.span.clone()],
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            headings,
+            [
+                (1, "Guide", "guide", "# Guide"),
+                (2, "Setup fast", "setup-fast", "## Setup *fast*"),
+                (3, "Guide", "guide-1", "### Guide"),
+                (4, "Deep", "deep", "#### Deep"),
+            ]
+        );
+        assert_eq!(index.headings[2].path, [3, 1]);
+        assert!(matches!(document.children[3], Node::Blockquote(_)));
+
+        assert!(index.footnotes.is_empty());
+    }
+
+    #[test]
+    fn test_footnotes() {
+        let footnote = |identifier: &'static str, label: &'static str| {
+            Node::from(FootnoteDefinition {
+                children: vec![],
+                identifier: identifier.into(),
+                label: Some(label.into()),
+                data: None,
+            })
+        };
+
+        let document = Document {
+            children: vec![
+                Node::Heading(Heading::new(1).unwrap()),
+                footnote("note", "Note"),
+                footnote("NOTE", "NOTE"),
+            ],
+            data: None,
+        };
+
+        // built documents have no spans.
+        let index = DocumentIndex::new(&document, &Spans::new());
+
+        assert_eq!(index.footnotes.len(), 1);
+        assert_eq!(index.footnotes["note"].label.as_deref(), Some("Note"));
+        assert_eq!(index.footnotes["note"].path, [1]);
+        assert_eq!(index.footnotes["note"].span, 0..0);
+        assert_eq!(index.headings[0].slug, "");
+    }
+
+    #[test]
+    fn test_consistent() {
+        let (document, spans) = Parser::new(SOURCE).parse_with_spans().unwrap();
+
+        let index = DocumentIndex::new(&document, &spans);
+
+        assert_eq!(Parser::new(SOURCE).parse_indexed().unwrap().1, index);
+
+        let definitions = definitions(&document);
+
+        assert_eq!(
+            definitions.keys().collect::<Vec<_>>(),
+            index.definitions.keys().collect::<Vec<_>>()
+        );
+
+        for (identifier, definition) in definitions {
+            assert_eq!(definition.url, index.definitions[&identifier].url);
+        }
+
+        let slugs = slugger(&document);
+
+        assert_eq!(slugs.len(), index.headings.len());
+
+        for ((slug, heading), entry) in slugs.iter().zip(&index.headings) {
+            assert_eq!(*slug, entry.slug);
+            assert_eq!(heading.depth, entry.depth);
+        }
+    }
+}
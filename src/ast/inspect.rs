@@ -0,0 +1,450 @@
+//! Compact tree inspector, in the indented format of `unist-util-inspect`.
+//!
+//! The format is stable, so that snapshots of it only change with the trees they show. Each
+//! node is a line of:
+//!
+//! - its type, followed by its child count in brackets for parents,
+//! - its other fields as `name=value` pairs, in declaration order, optional fields that are
+//!   `None` left out, and [`data`](Node::data) never shown,
+//! - its value last for literals: text, inline code, code and html.
+//!
+//! Strings are quoted, with newlines, quotes and control chars escaped like in Rust string
+//! literals. Children follow their parent, indented by two more spaces.
+//!
+//! ```
+//! use markdown_rs::parser::Parser;
+//!
+//! let document = Parser::new("# Hello *world*").parse().unwrap();
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "root[1]\n  heading[2] depth=1\n    text \"Hello \"\n    emphasis[1]\n      text \"world\"\n"
+//! );
+//! ```
+
+use core::fmt::{self, Display, Formatter, Write};
+
+use super::*;
+
+/// Returns the indented tree of `node`, one line per node.
+///
+/// Each line is the node type, its child count for parents, its fields as `name=value` pairs
+/// and its value for literals. Nesting is shown by two spaces of indentation per level.
+pub fn inspect(node: &Node) -> String {
+    let mut out = String::new();
+
+    write_node(&mut out, node, 0).unwrap();
+
+    for (depth, node) in node.descendants_with_depth() {
+        write_node(&mut out, node, depth).unwrap();
+    }
+
+    out
+}
+
+impl<'cx> Display for Document<'cx> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}[{}]", NodeKind::Document, self.children.len())?;
+
+        for (depth, node) in self.descendants_with_depth() {
+            write_node(f, node, depth)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'cx> Display for Node<'cx> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&inspect(self))
+    }
+}
+
+fn write_node<W: Write>(out: &mut W, node: &Node, depth: usize) -> fmt::Result {
+    write!(
+        out,
+        "{:indent$}{}",
+        "",
+        node.kind().as_str(),
+        indent = depth * 2
+    )?;
+
+    if let Some(children) = node.children() {
+        write!(out, "[{}]", children.len())?;
+    }
+
+    for (name, value) in node.attributes() {
+        write!(out, " {}={}", name, value)?;
+    }
+
+    if let Some(value) = node.value() {
+        write!(out, " {:?}", value)?;
+    }
+
+    writeln!(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "# Title\n\n\
+        ---\n\n\
+        > *a* **b** `c`  \nd [e](/e \"E\") [f][] ![g](/g.png) ![h][f] <b>i</b>\n\n\
+        1. [x] one\n\n   two\n\n\
+        ```rust meta\nfn main() {}\n```\n\n\
+        <div>\n\n\
+        [f]: /f\n";
+
+    const SNAPSHOT: &str = r#"root[9]
+  heading[1] depth=1
+    text "Title"
+  thematicBreak
+  blockquote[1]
+    paragraph[18]
+      emphasis[1]
+        text "a"
+      text " "
+      strong[1]
+        text "b"
+      text " "
+      inlineCode "c"
+      break
+      text "d "
+      link[1] url="/e" title="E"
+        text "e"
+      text " "
+      linkReference[1] identifier="f" label="f" referenceType=collapsed
+        text "f"
+      text " "
+      image url="/g.png" alt="g"
+      text " "
+      imageReference alt="h" identifier="f" label="f" referenceType=full
+      text " "
+      html "<b>"
+      text "i"
+      html "</b>"
+  list[1] ordered=true start=1 spread=true
+    listItem[2] spread=true
+      paragraph[1]
+        text "[x] one"
+      paragraph[1]
+        text "two"
+  code lang="rust" meta="meta" "fn main() {}"
+  html "<div>"
+  definition identifier="f" label="f" url="/f"
+  table[1] align=[left, none]
+    tableRow[2]
+      tableCell[1]
+        delete[1]
+          text "j"
+      tableCell[1]
+        footnoteReference identifier="k" label="k"
+  footnoteDefinition[1] identifier="k"
+    paragraph[1]
+      text "l"
+"#;
+
+    #[test]
+    fn test_snapshot() {
+        let mut document = Parser::new(MARKDOWN).parse().unwrap();
+
+        // not produced by the parser yet.
+        document.children.push(Node::Table(Table {
+            children: vec![Node::TableRow(TableRow {
+                children: vec![
+                    Node::TableCell(TableCell {
+                        children: vec![Node::Delete(Delete {
+                            children: vec![Node::Text(Text {
+                                value: "j".into(),
+                                data: None,
+                            })],
+                            data: None,
+                        })],
+                        data: None,
+                    }),
+                    Node::TableCell(TableCell {
+                        children: vec![Node::FootnoteReference(FootnoteReference {
+                            identifier: "k".into(),
+                            label: Some("k".into()),
+                            data: None,
+                        })],
+                        data: None,
+                    }),
+                ],
+                data: None,
+            })],
+            align: vec![AlignType::Left, AlignType::None],
+            data: None,
+        }));
+
+        document.children.push(Node::from(FootnoteDefinition {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![Node::Text(Text {
+                    value: "l".into(),
+                    data: None,
+                })],
+                data: None,
+            })],
+            identifier: "k".into(),
+            label: None,
+            data: None,
+        }));
+
+        let kinds = document
+            .descendants()
+            .map(|node| node.kind())
+            .collect::<std::collections::BTreeSet<_>>();
+
+        // every kind but the document itself.
+        assert_eq!(kinds.len(), 24);
+
+        assert_eq!(document.to_string(), SNAPSHOT);
+    }
+
+    #[test]
+    fn test_node() {
+        let document = Parser::new("- a\n").parse().unwrap();
+
+        assert_eq!(
+            inspect(&document.children[0]),
+            "list[1] ordered=false spread=false\n  listItem[1] spread=false\n    paragraph[1]\n      text \"a\"\n"
+        );
+    }
+
+    /// Every kind of node, with all optional fields `None`, then set.
+    fn every_kind() -> Vec<Node<'static>> {
+        fn text(value: &'static str) -> Vec<Node<'static>> {
+            vec![Node::from(Text {
+                value: value.into(),
+                data: None,
+            })]
+        }
+
+        let mut nodes = vec![];
+
+        for set in [false, true] {
+            let some = |value: &'static str| set.then(|| value.into());
+
+            nodes.extend([
+                Node::from(Document {
+                    children: text("a"),
+                    data: None,
+                }),
+                Node::from(Heading {
+                    children: text("a"),
+                    depth: 2,
+                    data: None,
+                }),
+                Node::from(ThematicBreak { data: None }),
+                Node::from(Blockquote {
+                    children: vec![],
+                    data: None,
+                }),
+                Node::from(List {
+                    children: vec![Node::from(ListItem {
+                        children: vec![],
+                        checked: set.then_some(false),
+                        spread: set.then_some(true),
+                        data: None,
+                    })],
+                    ordered: set.then_some(true),
+                    start: set.then_some(3),
+                    spread: set.then_some(false),
+                    data: None,
+                }),
+                Node::from(Code {
+                    value: "a\n\tb \"c\"".into(),
+                    lang: some("rust"),
+                    meta: some("x=\"1\""),
+                    data: None,
+                }),
+                Node::from(Definition {
+                    identifier: "a".into(),
+                    label: some("A"),
+                    url: "/a".into(),
+                    title: some("T\nU"),
+                    data: None,
+                }),
+                Node::from(Emphasis {
+                    children: text("a\nb"),
+                    data: None,
+                }),
+                Node::from(Strong {
+                    children: vec![],
+                    data: None,
+                }),
+                Node::from(InlineCode {
+                    value: "\u{7}".into(),
+                    data: None,
+                }),
+                Node::from(Break { data: None }),
+                Node::from(Link {
+                    children: vec![],
+                    url: "".into(),
+                    title: some(""),
+                    data: None,
+                }),
+                Node::from(LinkReference {
+                    children: vec![],
+                    identifier: "a".into(),
+                    label: some("A"),
+                    reference_type: ReferenceType::Full,
+                    data: None,
+                }),
+                Node::from(Image {
+                    url: "/i.png".into(),
+                    title: some("T"),
+                    alt: some("A"),
+                    data: None,
+                }),
+                Node::from(ImageReference {
+                    alt: some("A"),
+                    identifier: "a".into(),
+                    label: some("A"),
+                    reference_type: ReferenceType::Shortcut,
+                    data: None,
+                }),
+                Node::from(Paragraph {
+                    children: vec![],
+                    data: None,
+                }),
+                Node::from(Delete {
+                    children: vec![],
+                    data: None,
+                }),
+                Node::from(FootnoteDefinition {
+                    children: vec![],
+                    identifier: "n".into(),
+                    label: some("N"),
+                    data: None,
+                }),
+                Node::from(FootnoteReference {
+                    identifier: "n".into(),
+                    label: some("N"),
+                    data: None,
+                }),
+                Node::from(Table {
+                    children: vec![Node::from(TableRow {
+                        children: vec![Node::from(TableCell {
+                            children: vec![],
+                            data: None,
+                        })],
+                        data: None,
+                    })],
+                    align: if set {
+                        vec![AlignType::Center, AlignType::None]
+                    } else {
+                        vec![]
+                    },
+                    data: None,
+                }),
+                Node::from(Html {
+                    value: "<a>\n".into(),
+                    data: None,
+                }),
+                Node::from(Custom {
+                    children: vec![],
+                    name: "c".into(),
+                    attributes: if set {
+                        [("k".into(), "v\n".into()), ("a".into(), "".into())].into()
+                    } else {
+                        Default::default()
+                    },
+                    data: None,
+                }),
+            ]);
+        }
+
+        nodes
+    }
+
+    const EVERY_KIND: &str = r#"root[1]
+  text "a"
+heading[1] depth=2
+  text "a"
+thematicBreak
+blockquote[0]
+list[1]
+  listItem[0]
+code "a\n\tb \"c\""
+definition identifier="a" url="/a"
+emphasis[1]
+  text "a\nb"
+strong[0]
+inlineCode "\u{7}"
+break
+link[0] url=""
+linkReference[0] identifier="a" referenceType=full
+image url="/i.png"
+imageReference identifier="a" referenceType=shortcut
+paragraph[0]
+delete[0]
+footnoteDefinition[0] identifier="n"
+footnoteReference identifier="n"
+table[1] align=[]
+  tableRow[1]
+    tableCell[0]
+html "<a>\n"
+custom[0] name="c"
+root[1]
+  text "a"
+heading[1] depth=2
+  text "a"
+thematicBreak
+blockquote[0]
+list[1] ordered=true start=3 spread=false
+  listItem[0] checked=false spread=true
+code lang="rust" meta="x=\"1\"" "a\n\tb \"c\""
+definition identifier="a" label="A" url="/a" title="T\nU"
+emphasis[1]
+  text "a\nb"
+strong[0]
+inlineCode "\u{7}"
+break
+link[0] url="" title=""
+linkReference[0] identifier="a" label="A" referenceType=full
+image url="/i.png" title="T" alt="A"
+imageReference alt="A" identifier="a" label="A" referenceType=shortcut
+paragraph[0]
+delete[0]
+footnoteDefinition[0] identifier="n" label="N"
+footnoteReference identifier="n" label="N"
+table[1] align=[center, none]
+  tableRow[1]
+    tableCell[0]
+html "<a>\n"
+custom[0] name="c" attribute="a"="" attribute="k"="v\n"
+"#;
+
+    #[test]
+    fn test_every_kind() {
+        let nodes = every_kind();
+
+        let kinds = nodes
+            .iter()
+            .flat_map(|node| core::iter::once(node).chain(node.descendants()))
+            .map(Node::kind)
+            .collect::<std::collections::BTreeSet<_>>();
+
+        assert_eq!(kinds.len(), NodeKind::ALL.len());
+
+        assert_eq!(nodes.iter().map(inspect).collect::<String>(), EVERY_KIND);
+
+        // data is never shown.
+        let mut with_data = nodes.clone();
+
+        for node in &mut with_data {
+            node.data_mut()
+                .get_or_insert_default()
+                .insert("id".into(), DataValue::from("x"));
+        }
+
+        assert_eq!(
+            with_data.iter().map(inspect).collect::<String>(),
+            EVERY_KIND
+        );
+    }
+}
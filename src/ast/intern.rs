@@ -0,0 +1,246 @@
+//! Sharing of repeated strings between the nodes of owned trees.
+//!
+//! A tree detached from its source with [`into_owned`](Node::into_owned) owns every string,
+//! so a language set on thousands of code blocks is allocated thousands of times. An
+//! [`Interner`] keeps one copy of each distinct string, and
+//! [`into_interned`](Document::into_interned) detaches a tree by borrowing its strings from
+//! the interner instead.
+//!
+//! ```
+//! use markdown_rs::{ast::intern::Interner, parser::Parser};
+//!
+//! let source = "```rust\na\n```\n\n```rust\nb\n```\n";
+//!
+//! let document = Parser::new(source).parse().unwrap();
+//!
+//! let mut interner = Interner::new();
+//!
+//! interner.collect(&document);
+//!
+//! assert_eq!(interner.len(), 1);
+//!
+//! // the tree no longer borrows `source`, only the interner.
+//! let document = document.into_interned(&interner);
+//!
+//! assert_eq!(document.children.len(), 2);
+//! ```
+//!
+//! Trees deserialized into [`Document<'static>`](Document) are interned the same way.
+
+use alloc::collections::BTreeSet;
+
+use super::*;
+
+/// Set of distinct strings that trees can borrow from, see the [module](self) docs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Interner {
+    strings: BTreeSet<Box<str>>,
+}
+
+impl Interner {
+    /// Create new empty [`Interner`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds `value` if it is not interned yet, returns the interned copy.
+    pub fn intern(&mut self, value: &str) -> &str {
+        if !self.strings.contains(value) {
+            self.strings.insert(value.into());
+        }
+
+        self.get(value).unwrap_or_default()
+    }
+
+    /// Returns the interned copy of `value`, `None` if it is not interned.
+    pub fn get(&self, value: &str) -> Option<&str> {
+        self.strings.get(value).map(|value| &**value)
+    }
+
+    /// Returns the number of interned strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns true if no string is interned.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Interns the strings of `document` that tend to repeat: urls, titles, code languages
+    /// and meta, identifiers, labels, and the names and attributes of custom nodes.
+    ///
+    /// Text, code and html values are left out: they are seldom repeated, and interning them
+    /// would keep a second copy of the content.
+    pub fn collect(&mut self, document: &Document) {
+        for node in document.descendants() {
+            match node {
+                Node::Code(code) => {
+                    self.intern_all([&code.lang, &code.meta].into_iter().flatten());
+                }
+                Node::Definition(definition) => {
+                    self.intern_all([&definition.identifier, &definition.url]);
+                    self.intern_all([&definition.label, &definition.title].into_iter().flatten());
+                }
+                Node::Link(link) => {
+                    self.intern(&link.url);
+                    self.intern_all(&link.title);
+                }
+                Node::LinkReference(link) => {
+                    self.intern(&link.identifier);
+                    self.intern_all(&link.label);
+                }
+                Node::Image(image) => {
+                    self.intern(&image.url);
+                    self.intern_all(&image.title);
+                }
+                Node::ImageReference(image) => {
+                    self.intern(&image.identifier);
+                    self.intern_all(&image.label);
+                }
+                Node::FootnoteDefinition(footnote) => {
+                    self.intern(&footnote.identifier);
+                    self.intern_all(&footnote.label);
+                }
+                Node::FootnoteReference(footnote) => {
+                    self.intern(&footnote.identifier);
+                    self.intern_all(&footnote.label);
+                }
+                Node::Custom(custom) => {
+                    self.intern(&custom.name);
+                    self.intern_all(custom.attributes.iter().flat_map(|(k, v)| [k, v]));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn intern_all<'a, I>(&mut self, values: I)
+    where
+        I: IntoIterator<Item = &'a Cow<'a, str>>,
+    {
+        for value in values {
+            self.intern(value);
+        }
+    }
+
+    /// [`Rebind`] function borrowing interned strings, copying the others.
+    fn rebind<'i>(&'i self, value: Cow<'_, str>) -> Cow<'i, str> {
+        match self.get(&value) {
+            Some(interned) => Cow::Borrowed(interned),
+            None => Cow::Owned(value.into_owned()),
+        }
+    }
+}
+
+impl<'cx> Node<'cx> {
+    /// Deep-convert all borrowed strings like [`into_owned`](Self::into_owned), borrowing
+    /// those of `interner` rather than copying them.
+    ///
+    /// Strings that are not interned are copied if borrowed, and moved otherwise.
+    pub fn into_interned(self, interner: &Interner) -> Node<'_> {
+        self.rebind(&mut |value| interner.rebind(value))
+    }
+}
+
+impl<'cx> Document<'cx> {
+    /// Deep-convert all borrowed strings like [`into_owned`](Self::into_owned), borrowing
+    /// those of `interner` rather than copying them, see [`Node::into_interned`].
+    pub fn into_interned(mut self, interner: &Interner) -> Document<'_> {
+        self.take_rebind(&mut |value| interner.rebind(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "\
+[a](/u \"t\") [b](/u) ![c](/u \"t\") [d][x]
+
+```rust info
+y
+```
+
+```rust
+z
+```
+
+[x]: /u \"t\"
+";
+
+    #[test]
+    fn test_collect() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let mut interner = Interner::new();
+
+        interner.collect(&document);
+
+        let strings = interner
+            .strings
+            .iter()
+            .map(|value| &**value)
+            .collect::<Vec<_>>();
+
+        assert_eq!(strings, ["/u", "info", "rust", "t", "x"]);
+
+        assert_eq!(interner.get("rust"), Some("rust"));
+        assert_eq!(interner.get("a"), None);
+    }
+
+    #[test]
+    fn test_into_interned() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let mut interner = Interner::new();
+
+        interner.collect(&document);
+
+        let interned = document.clone().into_interned(&interner);
+
+        assert_eq!(interned, document);
+
+        let urls = interned
+            .descendants()
+            .filter_map(|node| match node {
+                Node::Link(link) => Some(&link.url),
+                Node::Image(image) => Some(&image.url),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(urls.len(), 3);
+
+        // the urls share the interned copy.
+        for url in urls {
+            assert!(matches!(url, Cow::Borrowed(_)));
+            assert!(core::ptr::eq(&**url, interner.get("/u").unwrap()));
+        }
+
+        // strings left out are owned.
+        let Node::Code(code) = &interned.children[1] else {
+            panic!("{:?}", interned.children[1]);
+        };
+
+        assert!(matches!(code.value, Cow::Owned(_)));
+        assert!(matches!(code.lang, Some(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn test_intern() {
+        let mut interner = Interner::new();
+
+        let first = interner.intern("rust") as *const str;
+
+        assert!(core::ptr::eq(first, interner.intern("rust")));
+        assert_eq!(interner.len(), 1);
+
+        interner.intern("toml");
+
+        assert_eq!(interner.len(), 2);
+        assert!(!interner.is_empty());
+    }
+}
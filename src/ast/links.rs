@@ -0,0 +1,575 @@
+//! Link, image and definition URL collection and rewriting.
+//!
+//! ```
+//! use markdown_rs::{ast::links, parser::Parser};
+//!
+//! let mut document = Parser::new("[guide](./guide.md) and <https://example.com>")
+//!     .parse()
+//!     .unwrap();
+//!
+//! links::rewrite(&mut document, |url| {
+//!     url.strip_prefix("./")
+//!         .and_then(|path| path.strip_suffix(".md"))
+//!         .map(|path| format!("/docs/{}/", path))
+//! });
+//!
+//! let urls = links::collect(&document)
+//!     .into_iter()
+//!     .map(|link| link.url)
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(urls, ["/docs/guide/", "https://example.com"]);
+//! ```
+
+use alloc::borrow::Cow;
+
+use super::*;
+
+/// A URL found by [`collect`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LinkInfo<'a> {
+    /// The destination URL.
+    pub url: &'a str,
+    /// The advisory title, if any.
+    pub title: Option<&'a str>,
+    /// Kind of the node carrying the URL: [`NodeKind::Link`], [`NodeKind::Image`]
+    /// or [`NodeKind::Definition`].
+    pub kind: NodeKind,
+    /// Position of the node in the tree, as child indices from the document root.
+    pub path: Vec<usize>,
+}
+
+/// Collect the URL of every link (including autolinks), image and definition of `document`,
+/// in document order.
+pub fn collect<'a>(document: &'a Document) -> Vec<LinkInfo<'a>> {
+    let mut links = vec![];
+
+    for_each_with_path(document, |path, node| {
+        let (url, title) = match node {
+            Node::Link(link) => (&link.url, &link.title),
+            Node::Image(image) => (&image.url, &image.title),
+            Node::Definition(definition) => (&definition.url, &definition.title),
+            _ => return,
+        };
+
+        links.push(LinkInfo {
+            url,
+            title: title.as_deref(),
+            kind: node.kind(),
+            path: path.to_vec(),
+        });
+    });
+
+    links
+}
+
+/// Call `f` with every descendant of `document` in document order, along with its position as
+/// child indices from the document root.
+pub(crate) fn for_each_with_path<'a, 'cx, F>(document: &'a Document<'cx>, mut f: F)
+where
+    F: FnMut(&[usize], &'a Node<'cx>),
+{
+    let mut path: Vec<usize> = vec![];
+
+    for (depth, node) in document.descendants_with_depth() {
+        // pre-order: a node either is the next sibling at `depth` or the first child.
+        if path.len() >= depth {
+            path.truncate(depth);
+            path[depth - 1] += 1;
+        } else {
+            path.push(0);
+        }
+
+        f(&path, node);
+    }
+}
+
+/// Replace the URL of every link, image and definition of `document` for which `f`
+/// returns `Some`, returns the number of replaced URLs.
+pub fn rewrite<F>(document: &mut Document, f: F) -> usize
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut rewriter = Rewriter { f, count: 0 };
+
+    for child in &mut document.children {
+        walk_mut(child, &mut rewriter);
+    }
+
+    rewriter.count
+}
+
+struct Rewriter<F> {
+    f: F,
+    count: usize,
+}
+
+impl<F> Rewriter<F>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn rewrite(&mut self, url: &mut Cow<'_, str>) {
+        if let Some(replacement) = (self.f)(url) {
+            *url = Cow::Owned(replacement);
+            self.count += 1;
+        }
+    }
+}
+
+impl<'cx, F> VisitorMut<'cx> for Rewriter<F>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    fn visit_link(&mut self, node: &mut Link<'cx>) -> Transform<'cx> {
+        self.rewrite(&mut node.url);
+
+        Transform::Keep
+    }
+
+    fn visit_image(&mut self, node: &mut Image<'cx>) -> Transform<'cx> {
+        self.rewrite(&mut node.url);
+
+        Transform::Keep
+    }
+
+    fn visit_definition(&mut self, node: &mut Definition<'cx>) -> Transform<'cx> {
+        self.rewrite(&mut node.url);
+
+        Transform::Keep
+    }
+}
+
+/// Resolve the relative URLs of the links, images and definitions of `document` against
+/// `base`, see [`resolve_url`], returns the number of changed URLs.
+///
+/// ```
+/// use markdown_rs::{ast::links, parser::Parser};
+///
+/// let mut document = Parser::new("[a](<../a b.md>) [b][] [c](#c)\n\n[b]: b.png")
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(links::resolve_base(&mut document, "https://example.com/docs/guide/"), 2);
+/// assert_eq!(
+///     document.to_markdown(&Default::default()),
+///     "[a](https://example.com/docs/a%20b.md) [b][] [c](#c)\n\n\
+///      [b]: https://example.com/docs/guide/b.png\n"
+/// );
+/// ```
+pub fn resolve_base(document: &mut Document, base: &str) -> usize {
+    rewrite(document, |url| {
+        let resolved = resolve_url(base, url);
+
+        (resolved != url).then_some(resolved)
+    })
+}
+
+/// Returns `url` resolved against `base` as per [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2),
+/// with the characters not allowed in URLs percent-encoded.
+///
+/// Empty and fragment-only URLs, such as `#usage`, are returned as they are. `base` should
+/// be an absolute URL or path: the `..` segments climbing above a relative one are dropped.
+///
+/// ```
+/// use markdown_rs::ast::links::resolve_url;
+///
+/// let base = "https://example.com/docs/guide/index.html?v=1";
+///
+/// assert_eq!(resolve_url(base, "../api/"), "https://example.com/docs/api/");
+/// assert_eq!(resolve_url(base, "/about"), "https://example.com/about");
+/// assert_eq!(resolve_url(base, "?v=2"), "https://example.com/docs/guide/index.html?v=2");
+/// assert_eq!(resolve_url(base, "#usage"), "#usage");
+/// assert_eq!(resolve_url(base, "mailto:a@example.com"), "mailto:a@example.com");
+/// ```
+pub fn resolve_url(base: &str, url: &str) -> String {
+    if url.is_empty() || url.starts_with('#') {
+        return url.to_owned();
+    }
+
+    let base = UrlParts::parse(base);
+    let url = UrlParts::parse(url);
+
+    // RFC 3986 section 5.2.2.
+    let (scheme, authority, path, query) = if url.scheme.is_some() {
+        (
+            url.scheme,
+            url.authority,
+            remove_dot_segments(url.path),
+            url.query,
+        )
+    } else if url.authority.is_some() {
+        (
+            base.scheme,
+            url.authority,
+            remove_dot_segments(url.path),
+            url.query,
+        )
+    } else if url.path.is_empty() {
+        (
+            base.scheme,
+            base.authority,
+            base.path.to_owned(),
+            url.query.or(base.query),
+        )
+    } else if url.path.starts_with('/') {
+        (
+            base.scheme,
+            base.authority,
+            remove_dot_segments(url.path),
+            url.query,
+        )
+    } else {
+        // RFC 3986 section 5.2.3.
+        let merged = if base.authority.is_some() && base.path.is_empty() {
+            format!("/{}", url.path)
+        } else {
+            let directory = base.path.rfind('/').map_or("", |end| &base.path[..=end]);
+
+            format!("{}{}", directory, url.path)
+        };
+
+        (
+            base.scheme,
+            base.authority,
+            remove_dot_segments(&merged),
+            url.query,
+        )
+    };
+
+    let mut out = String::with_capacity(url.path.len() + path.len());
+
+    if let Some(scheme) = scheme {
+        out.push_str(scheme);
+        out.push(':');
+    }
+
+    if let Some(authority) = authority {
+        out.push_str("//");
+        out.push_str(authority);
+    }
+
+    out.push_str(&path);
+
+    if let Some(query) = query {
+        out.push('?');
+        out.push_str(query);
+    }
+
+    if let Some(fragment) = url.fragment {
+        out.push('#');
+        out.push_str(fragment);
+    }
+
+    percent_encode(&out)
+}
+
+/// Returns `url` with its `.md` extension replaced by `replacement`, such as `".html"` or
+/// `"/"`, or `None` if it isn't a relative URL to a `.md` file. The query and fragment are
+/// kept.
+///
+/// ```
+/// use markdown_rs::ast::links::replace_md_extension;
+///
+/// assert_eq!(replace_md_extension("../guide.md#usage", ".html").unwrap(), "../guide.html#usage");
+/// assert_eq!(replace_md_extension("guide.md", "/").unwrap(), "guide/");
+/// assert_eq!(replace_md_extension("https://example.com/README.md", "/"), None);
+/// ```
+pub fn replace_md_extension(url: &str, replacement: &str) -> Option<String> {
+    let parts = UrlParts::parse(url);
+
+    if parts.scheme.is_some() || parts.authority.is_some() {
+        return None;
+    }
+
+    let stem = parts.path.strip_suffix(".md")?;
+
+    if stem.is_empty() || stem.ends_with('/') {
+        return None;
+    }
+
+    Some(format!(
+        "{}{}{}",
+        stem,
+        replacement,
+        &url[parts.path.len()..]
+    ))
+}
+
+/// The components of a URL, as per RFC 3986 section 3.
+struct UrlParts<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> UrlParts<'a> {
+    fn parse(url: &'a str) -> Self {
+        let (url, fragment) = match url.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (url, None),
+        };
+
+        let (url, query) = match url.split_once('?') {
+            Some((url, query)) => (url, Some(query)),
+            None => (url, None),
+        };
+
+        let scheme = url.split_once(':').and_then(|(scheme, _)| {
+            let valid = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+            valid.then_some(scheme)
+        });
+
+        let url = scheme.map_or(url, |scheme| &url[scheme.len() + 1..]);
+
+        let (authority, path) = match url.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+
+                (Some(&rest[..end]), &rest[end..])
+            }
+            None => (None, url),
+        };
+
+        UrlParts {
+            scheme,
+            authority,
+            path,
+            query,
+            fragment,
+        }
+    }
+}
+
+/// Removes the `.` and `..` segments of `path`, as per RFC 3986 section 5.2.4.
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = vec![];
+
+    let absolute = path.starts_with('/');
+
+    let mut parts = path.split('/').skip(usize::from(absolute)).peekable();
+
+    while let Some(segment) = parts.next() {
+        let last = parts.peek().is_none();
+
+        match segment {
+            "." | ".." => {
+                if segment == ".." {
+                    segments.pop();
+                }
+
+                // a trailing dot segment leaves a directory.
+                if last {
+                    segments.push("");
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut out = segments.join("/");
+
+    if absolute {
+        out.insert(0, '/');
+    }
+
+    out
+}
+
+/// Percent-encodes the chars of `url` that URLs can't hold, existing escapes are kept.
+fn percent_encode(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+
+    let bytes = url.as_bytes();
+
+    for (index, c) in url.char_indices() {
+        match c {
+            '%' if bytes.len() > index + 2
+                && bytes[index + 1].is_ascii_hexdigit()
+                && bytes[index + 2].is_ascii_hexdigit() =>
+            {
+                out.push('%')
+            }
+            c if c.is_ascii_graphic()
+                && !matches!(
+                    c,
+                    '"' | '<' | '>' | '\\' | '^' | '`' | '{' | '|' | '}' | '%'
+                ) =>
+            {
+                out.push(c)
+            }
+            c => {
+                let mut buf = [0; 4];
+
+                for byte in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "# [Home](/index.md)\n\n\
+        See [the guide](./guide.md \"Guide\"), <https://example.com> and [api][].\n\n\
+        - ![logo](images/logo.png)\n- ![badge](https://img.shields.io/badge.svg)\n\n\
+        [api]: ../api.md\n";
+
+    #[test]
+    fn test_collect() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let links = collect(&document);
+
+        let count = |kind: NodeKind| links.iter().filter(|link| link.kind == kind).count();
+
+        assert_eq!(count(NodeKind::Link), 3);
+        assert_eq!(count(NodeKind::Image), 2);
+        assert_eq!(count(NodeKind::Definition), 1);
+
+        assert_eq!(
+            links[1],
+            LinkInfo {
+                url: "./guide.md",
+                title: Some("Guide"),
+                kind: NodeKind::Link,
+                path: vec![1, 1],
+            }
+        );
+
+        assert_eq!(links[4].path, [2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_rewrite_relative() {
+        let mut document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let count = rewrite(&mut document, |url| {
+            (!url.contains("://") && !url.starts_with('/')).then(|| format!("/docs/{}", url))
+        });
+
+        assert_eq!(count, 3);
+
+        let urls = collect(&document)
+            .into_iter()
+            .map(|link| link.url)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            [
+                "/index.md",
+                "/docs/./guide.md",
+                "https://example.com",
+                "/docs/images/logo.png",
+                "https://img.shields.io/badge.svg",
+                "/docs/../api.md",
+            ]
+        );
+    }
+    #[test]
+    fn test_resolve_url() {
+        // RFC 3986 section 5.4.
+        let base = "http://a/b/c/d;p?q";
+
+        for (url, expected) in [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+            ("g;x=1/../y", "http://a/b/c/y"),
+            // fragment-only urls stay relative.
+            ("#s", "#s"),
+            ("", ""),
+        ] {
+            assert_eq!(resolve_url(base, url), expected, "{}", url);
+        }
+
+        // a base without scheme and a host without path.
+        assert_eq!(resolve_url("/docs/guide/", "../api.md"), "/docs/api.md");
+        assert_eq!(
+            resolve_url("https://example.com", "a"),
+            "https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn test_resolve_base() {
+        let mut document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let count = resolve_base(&mut document, "https://example.com/docs/");
+
+        assert_eq!(count, 4);
+
+        let urls = collect(&document)
+            .into_iter()
+            .map(|link| link.url)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            [
+                "https://example.com/index.md",
+                "https://example.com/docs/guide.md",
+                "https://example.com",
+                "https://example.com/docs/images/logo.png",
+                "https://img.shields.io/badge.svg",
+                "https://example.com/api.md",
+            ]
+        );
+
+        // encoded, without encoding escapes again.
+        assert_eq!(
+            resolve_url("https://example.com/", "a b/ü%20\"<c>\""),
+            "https://example.com/a%20b/%C3%BC%20%22%3Cc%3E%22"
+        );
+    }
+
+    #[test]
+    fn test_replace_md_extension() {
+        assert_eq!(
+            replace_md_extension("a.md?x#y", ".html").unwrap(),
+            "a.html?x#y"
+        );
+        assert_eq!(replace_md_extension("/docs/a.md", "/").unwrap(), "/docs/a/");
+        assert_eq!(replace_md_extension(".md", "/"), None);
+        assert_eq!(replace_md_extension("docs/.md", "/"), None);
+        assert_eq!(replace_md_extension("a.mdx", "/"), None);
+        assert_eq!(replace_md_extension("//host/a.md", "/"), None);
+        assert_eq!(replace_md_extension("#a.md", "/"), None);
+    }
+}
@@ -0,0 +1,374 @@
+//! Structural lints for documentation CI.
+//!
+//! Every lint returns [`LintWarning`]s, tagged with the [`LintRule`] that raised them and the
+//! position of the offending node. [`references`] checks definitions against the references
+//! using them instead, and returns a [`ReferenceReport`].
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::lint::{self, LintRule},
+//!     parser::Parser,
+//! };
+//!
+//! let document = Parser::new("# Guide\n\n## Install\n\n#### Linux\n\n# Usage")
+//!     .parse()
+//!     .unwrap();
+//!
+//! let rules = lint::headings(&document)
+//!     .into_iter()
+//!     .map(|warning| (warning.rule, warning.path))
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(
+//!     rules,
+//!     [
+//!         (LintRule::HeadingIncrement, vec![2]),
+//!         (LintRule::MultipleH1, vec![3])
+//!     ]
+//! );
+//! ```
+
+use alloc::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use core::fmt;
+
+use super::links::for_each_with_path;
+use super::text::{to_text, TextOptions};
+use super::*;
+
+/// Rule that raised a [`LintWarning`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum LintRule {
+    /// A level 1 heading after the first one.
+    MultipleH1,
+    /// A heading more than one level deeper than the heading before it.
+    HeadingIncrement,
+    /// A heading without any text.
+    EmptyHeading,
+}
+
+impl LintRule {
+    /// Returns the id of the rule, such as `"heading-increment"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LintRule::MultipleH1 => "multiple-h1",
+            LintRule::HeadingIncrement => "heading-increment",
+            LintRule::EmptyHeading => "empty-heading",
+        }
+    }
+}
+
+impl fmt::Display for LintRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A problem found by a lint.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintWarning {
+    /// The rule that was broken.
+    pub rule: LintRule,
+    /// Human readable description of the problem.
+    pub message: String,
+    /// Position of the offending node in the tree, as child indices from the document root.
+    pub path: Vec<usize>,
+}
+
+/// Checks the heading structure of `document`, returns warnings in document order.
+///
+/// Raises [`LintRule::MultipleH1`] for every level 1 heading after the first,
+/// [`LintRule::HeadingIncrement`] for a heading more than one level deeper than the previous
+/// heading and [`LintRule::EmptyHeading`] for a heading whose text is blank.
+pub fn headings(document: &Document) -> Vec<LintWarning> {
+    let mut warnings = vec![];
+
+    let mut previous = None;
+
+    let mut h1 = false;
+
+    for_each_with_path(document, |path, node| {
+        let Node::Heading(heading) = node else {
+            return;
+        };
+
+        let mut warn = |rule, message| {
+            warnings.push(LintWarning {
+                rule,
+                message,
+                path: path.to_vec(),
+            })
+        };
+
+        if heading.depth == 1 {
+            if h1 {
+                warn(
+                    LintRule::MultipleH1,
+                    "document already has a level 1 heading".into(),
+                );
+            }
+
+            h1 = true;
+        }
+
+        if let Some(previous) = previous.filter(|previous| heading.depth > previous + 1) {
+            warn(
+                LintRule::HeadingIncrement,
+                format!("heading level jumps from {} to {}", previous, heading.depth),
+            );
+        }
+
+        if to_text(node, &TextOptions::default()).trim().is_empty() {
+            warn(LintRule::EmptyHeading, "heading has no text".into());
+        }
+
+        previous = Some(heading.depth);
+    });
+
+    warnings
+}
+
+/// Namespace of a definition or reference in a [`ReferenceReport`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ReferenceKind {
+    /// A [`LinkReference`], or a [`Definition`]: links and images share definitions.
+    Link,
+    /// An [`ImageReference`].
+    Image,
+    /// A [`FootnoteReference`] or [`FootnoteDefinition`].
+    Footnote,
+}
+
+/// A definition or reference reported by [`references`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferenceEntry<'a> {
+    /// The identifier, as found in the node.
+    pub identifier: &'a str,
+    /// Which definitions the identifier belongs to.
+    pub kind: ReferenceKind,
+    /// Position of the node in the tree, as child indices from the document root.
+    pub path: Vec<usize>,
+}
+
+/// Result of [`references`], every list in document order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReferenceReport<'a> {
+    /// Definitions no reference uses.
+    pub unused_definitions: Vec<ReferenceEntry<'a>>,
+    /// References without a matching definition.
+    pub unresolved_references: Vec<ReferenceEntry<'a>>,
+    /// Definitions of an identifier that was already defined, and are thus ignored.
+    pub duplicate_definitions: Vec<ReferenceEntry<'a>>,
+}
+
+impl<'a> ReferenceReport<'a> {
+    /// Returns `true` if no problem was found.
+    pub fn is_empty(&self) -> bool {
+        self.unused_definitions.is_empty()
+            && self.unresolved_references.is_empty()
+            && self.duplicate_definitions.is_empty()
+    }
+}
+
+/// Matches the definitions of `document` with its link, image and footnote references.
+///
+/// Identifiers are compared after [`normalize_identifier`]. Link and image references both
+/// resolve to [`Definition`]s, footnote references to [`FootnoteDefinition`]s. As per
+/// CommonMark the first definition of an identifier wins: later ones are reported as duplicates
+/// only, never as unused.
+pub fn references<'a>(document: &'a Document) -> ReferenceReport<'a> {
+    let mut report = ReferenceReport::default();
+
+    // (is footnote, normalized identifier) -> first definition.
+    let mut definitions = BTreeMap::new();
+
+    let mut used = BTreeSet::new();
+
+    let mut references = vec![];
+
+    for_each_with_path(document, |path, node| {
+        let (identifier, kind, definition) = match node {
+            Node::Definition(definition) => (&definition.identifier, ReferenceKind::Link, true),
+            Node::FootnoteDefinition(definition) => {
+                (&definition.identifier, ReferenceKind::Footnote, true)
+            }
+            Node::LinkReference(reference) => (&reference.identifier, ReferenceKind::Link, false),
+            Node::ImageReference(reference) => (&reference.identifier, ReferenceKind::Image, false),
+            Node::FootnoteReference(reference) => {
+                (&reference.identifier, ReferenceKind::Footnote, false)
+            }
+            _ => return,
+        };
+
+        let key = (
+            kind == ReferenceKind::Footnote,
+            normalize_identifier(identifier),
+        );
+
+        let entry = ReferenceEntry {
+            identifier,
+            kind,
+            path: path.to_vec(),
+        };
+
+        if !definition {
+            used.insert(key.clone());
+            references.push((key, entry));
+        } else if let Entry::Vacant(vacant) = definitions.entry(key) {
+            vacant.insert(entry);
+        } else {
+            report.duplicate_definitions.push(entry);
+        }
+    });
+
+    report.unresolved_references = references
+        .into_iter()
+        .filter(|(key, _)| !definitions.contains_key(key))
+        .map(|(_, entry)| entry)
+        .collect();
+
+    report.unused_definitions = definitions
+        .into_iter()
+        .filter(|(key, _)| !used.contains(key))
+        .map(|(_, entry)| entry)
+        .collect();
+
+    report
+        .unused_definitions
+        .sort_by(|a, b| a.path.cmp(&b.path));
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn rules(source: &str) -> Vec<(LintRule, Vec<usize>)> {
+        let document = Parser::new(source).parse().unwrap();
+
+        headings(&document)
+            .into_iter()
+            .map(|warning| (warning.rule, warning.path))
+            .collect()
+    }
+
+    #[test]
+    fn test_multiple_h1() {
+        assert_eq!(rules("# a\n\n## b\n\n## c"), []);
+        assert_eq!(rules("## a\n\n# b"), []);
+        assert_eq!(
+            rules("# a\n\n> # b\n\nc\n=\n"),
+            [
+                (LintRule::MultipleH1, vec![1, 0]),
+                (LintRule::MultipleH1, vec![2])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_heading_increment() {
+        // going back up any number of levels is fine, and so is starting deep.
+        assert_eq!(rules("### a\n\n#### b\n\n# c\n\n## d"), []);
+
+        let document = Parser::new("# a\n\ntext\n\n### b\n\n#### c\n\n###### d")
+            .parse()
+            .unwrap();
+
+        let warnings = headings(&document);
+
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].rule, LintRule::HeadingIncrement);
+        assert_eq!(warnings[0].message, "heading level jumps from 1 to 3");
+        assert_eq!(warnings[0].path, [2]);
+        assert_eq!(warnings[1].message, "heading level jumps from 4 to 6");
+        assert_eq!(warnings[1].path, [4]);
+    }
+
+    #[test]
+    fn test_empty_heading() {
+        assert_eq!(rules("# ![alt](a.png)\n\n## `code`"), []);
+        assert_eq!(
+            rules("#\n\n## <br>\n\n## ![](a.png)"),
+            [
+                (LintRule::EmptyHeading, vec![0]),
+                (LintRule::EmptyHeading, vec![1]),
+                (LintRule::EmptyHeading, vec![2])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rule_ids() {
+        assert_eq!(LintRule::MultipleH1.to_string(), "multiple-h1");
+        assert_eq!(LintRule::HeadingIncrement.as_str(), "heading-increment");
+        assert_eq!(LintRule::EmptyHeading.as_str(), "empty-heading");
+    }
+
+    #[test]
+    fn test_references() {
+        let document = Parser::new("[a], [A][] and ![b]\n\n[a]: x\n\n> [B]: y\n")
+            .parse()
+            .unwrap();
+
+        assert!(references(&document).is_empty());
+
+        let mut document = Parser::new("[a]\n\n[a]: x\n[ A ]: y\n\n[unused]: z\n")
+            .parse()
+            .unwrap();
+
+        document.children.push(Node::Paragraph(Paragraph {
+            children: vec![
+                Node::from(ImageReference {
+                    alt: None,
+                    identifier: "missing".into(),
+                    label: None,
+                    reference_type: ReferenceType::Shortcut,
+                    data: None,
+                }),
+                // footnotes don't resolve to link definitions.
+                Node::FootnoteReference(FootnoteReference {
+                    identifier: "a".into(),
+                    label: None,
+                    data: None,
+                }),
+            ],
+            data: None,
+        }));
+
+        let report = references(&document);
+
+        assert_eq!(
+            report.unused_definitions,
+            [ReferenceEntry {
+                identifier: "unused",
+                kind: ReferenceKind::Link,
+                path: vec![3],
+            }]
+        );
+        assert_eq!(
+            report.unresolved_references,
+            [
+                ReferenceEntry {
+                    identifier: "missing",
+                    kind: ReferenceKind::Image,
+                    path: vec![4, 0],
+                },
+                ReferenceEntry {
+                    identifier: "a",
+                    kind: ReferenceKind::Footnote,
+                    path: vec![4, 1],
+                }
+            ]
+        );
+        assert_eq!(
+            report.duplicate_definitions,
+            [ReferenceEntry {
+                identifier: "a",
+                kind: ReferenceKind::Link,
+                path: vec![2],
+            }]
+        );
+    }
+}
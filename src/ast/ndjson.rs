@@ -0,0 +1,203 @@
+//! [JSON Lines](https://jsonlines.org) export of documents, one line per top-level block.
+//!
+//! A large document serialized as a single JSON value is written and read at once.
+//! [`to_ndjson`](Document::to_ndjson) streams it block by block instead, each line holding a
+//! block in the mdast representation of [`Node`] and its source range:
+//!
+//! ```text
+//! {"span":{"start":0,"end":7},"node":{"type":"heading","children":[…],"depth":1}}
+//! ```
+//!
+//! [`from_ndjson`](Document::from_ndjson) reads the blocks back one line at a time.
+//!
+//! ```
+//! use markdown_rs::{ast::Document, parser::Parser};
+//!
+//! let source = "# Title\n\nSome *text*.\n";
+//!
+//! let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+//!
+//! let mut out = Vec::new();
+//!
+//! document.to_ndjson(&spans, &mut out).unwrap();
+//!
+//! assert_eq!(out.iter().filter(|&&c| c == b'\n').count(), 2);
+//!
+//! let (read, read_spans) = Document::from_ndjson(out.as_slice()).unwrap();
+//!
+//! assert_eq!(read, document);
+//! assert_eq!(read_spans.get(&[1]), spans.get(&[1]));
+//! ```
+
+use core::ops::Range;
+use std::io::{self, BufRead, Write};
+
+use super::spans::Spans;
+use super::*;
+
+/// A line of the stream, borrowing the block it writes.
+#[derive(serde::Serialize)]
+struct BlockLine<'a, 'cx> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    span: Option<Range<usize>>,
+    node: &'a Node<'cx>,
+}
+
+/// A line of the stream, as read.
+#[derive(serde::Deserialize)]
+struct OwnedBlockLine {
+    #[serde(default)]
+    span: Option<Range<usize>>,
+    node: Node<'static>,
+}
+
+impl<'cx> Document<'cx> {
+    /// Writes the top-level blocks of this document to `out` as JSON Lines, see the
+    /// [module](self) docs.
+    ///
+    /// `spans` are those of the parse that produced this document; a block without a range
+    /// is written without `span`. Only top-level ranges are written, and the [`data`] of the
+    /// document itself is left out.
+    ///
+    /// [`data`]: Document::data
+    pub fn to_ndjson(&self, spans: &Spans, out: &mut impl Write) -> io::Result<()> {
+        for (index, node) in self.children.iter().enumerate() {
+            let line = BlockLine {
+                span: spans.get(&[index]),
+                node,
+            };
+
+            serde_json::to_writer(&mut *out, &line)?;
+
+            out.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Document<'static> {
+    /// Reads a document written by [`to_ndjson`](Document::to_ndjson) from `reader`, with the
+    /// source ranges of its top-level blocks.
+    ///
+    /// Blank lines are skipped. A line that isn't a complete block, such as the last line of
+    /// a truncated stream, fails with an [`InvalidData`](io::ErrorKind::InvalidData) error
+    /// naming its line number.
+    pub fn from_ndjson(reader: impl BufRead) -> io::Result<(Self, Spans)> {
+        let mut document = Document::default();
+
+        let mut spans = Spans::new();
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let block: OwnedBlockLine = serde_json::from_str(&line).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", number + 1, err),
+                )
+            })?;
+
+            if let Some(span) = block.span {
+                spans.insert(vec![document.children.len()], span);
+            }
+
+            document.children.push(block.node);
+        }
+
+        Ok((document, spans))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "\
+# Title
+
+Some *text* with a [link](/u \"t\").
+
+- a
+- b
+
+```rust
+fn main() {}
+```
+
+| a | b |
+| :- | -: |
+| 1 | 2 |
+";
+
+    fn write(document: &Document, spans: &Spans) -> String {
+        let mut out = Vec::new();
+
+        document.to_ndjson(spans, &mut out).unwrap();
+
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (document, spans) = Parser::new(MARKDOWN).parse_with_spans().unwrap();
+
+        let ndjson = write(&document, &spans);
+
+        assert_eq!(ndjson.lines().count(), document.children.len());
+
+        let (read, read_spans) = Document::from_ndjson(ndjson.as_bytes()).unwrap();
+
+        assert_eq!(read.children.len(), document.children.len());
+
+        for (index, (read, node)) in read.children.iter().zip(&document.children).enumerate() {
+            assert_eq!(read, node);
+            assert_eq!(read_spans.get(&[index]), spans.get(&[index]));
+            assert_eq!(
+                &MARKDOWN[read_spans.get(&[index]).unwrap()],
+                document.source_of(node, &spans, MARKDOWN).unwrap()
+            );
+        }
+
+        assert_eq!(read, document);
+    }
+
+    #[test]
+    fn test_line_shape() {
+        let (document, spans) = Parser::new("# a\n\n---\n").parse_with_spans().unwrap();
+
+        assert_eq!(
+            write(&document, &spans),
+            "{\"span\":{\"start\":0,\"end\":3},\"node\":{\"type\":\"heading\",\
+             \"children\":[{\"type\":\"text\",\"value\":\"a\"}],\"depth\":1}}\n\
+             {\"span\":{\"start\":5,\"end\":8},\"node\":{\"type\":\"thematicBreak\"}}\n"
+        );
+
+        // without spans.
+        assert_eq!(
+            write(&document, &Spans::new()).lines().nth(1),
+            Some("{\"node\":{\"type\":\"thematicBreak\"}}")
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let (document, spans) = Parser::new(MARKDOWN).parse_with_spans().unwrap();
+
+        let ndjson = write(&document, &spans);
+
+        // cut in the middle of the third block.
+        let end = ndjson.match_indices('\n').nth(1).unwrap().0 + 20;
+
+        let err = Document::from_ndjson(&ndjson.as_bytes()[..end]).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 3: "), "{}", err);
+    }
+}
@@ -0,0 +1,280 @@
+//! Normalization of fragmented trees, as built by transforms or by hand.
+//!
+//! ```
+//! use markdown_rs::ast::{normalize, Document, Emphasis, Node, Paragraph, Strong, Text};
+//!
+//! let text = |value: &'static str| Node::Text(Text {
+//!     value: value.into(),
+//!     data: None,
+//! });
+//!
+//! let mut document = Document {
+//!     children: vec![
+//!         Node::Paragraph(Paragraph {
+//!             children: vec![
+//!                 text("foo"),
+//!                 Node::Strong(Strong {
+//!                     children: vec![],
+//!                     data: None,
+//!                 }),
+//!                 text(" "),
+//!                 text("bar"),
+//!             ],
+//!             data: None,
+//!         }),
+//!         Node::Paragraph(Paragraph {
+//!             children: vec![Node::Emphasis(Emphasis {
+//!                 children: vec![],
+//!                 data: None,
+//!             })],
+//!             data: None,
+//!         }),
+//!     ],
+//!     data: None,
+//! };
+//!
+//! normalize(&mut document);
+//!
+//! assert_eq!(
+//!     document,
+//!     Document {
+//!         children: vec![Node::Paragraph(Paragraph {
+//!             children: vec![text("foo bar")],
+//!             data: None,
+//!         })],
+//!         data: None,
+//!     }
+//! );
+//! ```
+
+use super::*;
+
+/// Normalizes `document` in place, so that equivalent trees compare equal.
+///
+/// - adjacent text nodes are merged into one, empty text nodes are dropped.
+/// - emphasis, strong and delete nodes directly holding only a node of their own kind are
+///   replaced by it.
+/// - emphasis, strong and delete nodes and paragraphs left without children are dropped.
+///
+/// Normalizing a normalized tree doesn't change it.
+pub fn normalize(document: &mut Document) {
+    children(&mut document.children);
+}
+
+fn children(nodes: &mut Vec<Node>) {
+    for node in nodes.iter_mut() {
+        if let Some(children) = node.children_mut() {
+            self::children(children);
+        }
+
+        while let Some(child) = redundant_child(node) {
+            *node = child;
+        }
+    }
+
+    nodes.retain(|node| match node {
+        Node::Text(text) => !text.value.is_empty(),
+        Node::Emphasis(_) | Node::Strong(_) | Node::Delete(_) | Node::Paragraph(_) => {
+            node.children().is_some_and(|children| !children.is_empty())
+        }
+        _ => true,
+    });
+
+    merge_text(nodes);
+}
+
+/// Merge adjacent text nodes of `nodes` into one.
+pub(crate) fn merge_text(nodes: &mut Vec<Node>) {
+    if !nodes
+        .windows(2)
+        .any(|pair| matches!(pair, [Node::Text(_), Node::Text(_)]))
+    {
+        return;
+    }
+
+    let mut merged: Vec<Node> = Vec::with_capacity(nodes.len());
+
+    for node in nodes.drain(..) {
+        match (merged.last_mut(), &node) {
+            (Some(Node::Text(last)), Node::Text(text)) => last.value.to_mut().push_str(&text.value),
+            _ => merged.push(node),
+        }
+    }
+
+    *nodes = merged;
+}
+
+/// Returns the only child of `node` if it is of the same kind and `node` adds nothing to it.
+fn redundant_child<'cx>(node: &mut Node<'cx>) -> Option<Node<'cx>> {
+    if !matches!(node, Node::Emphasis(_) | Node::Strong(_) | Node::Delete(_)) {
+        return None;
+    }
+
+    let kind = node.kind();
+
+    let children = node.children_mut()?;
+
+    match children.as_slice() {
+        [child] if child.kind() == kind => children.pop(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    fn emphasis(children: Vec<Node<'_>>) -> Node<'_> {
+        Node::Emphasis(Emphasis {
+            children,
+            data: None,
+        })
+    }
+
+    fn paragraph(children: Vec<Node<'_>>) -> Node<'_> {
+        Node::Paragraph(Paragraph {
+            children,
+            data: None,
+        })
+    }
+
+    fn normalized(children: Vec<Node<'_>>) -> Vec<Node<'_>> {
+        let mut document = Document {
+            children,
+            data: None,
+        };
+
+        normalize(&mut document);
+
+        document.children
+    }
+
+    #[test]
+    fn test_merge_text() {
+        assert_eq!(
+            normalized(vec![paragraph(vec![
+                text("foo"),
+                text(" "),
+                text("bar"),
+                Node::Break(Break { data: None }),
+                text(""),
+                text("baz"),
+            ])]),
+            [paragraph(vec![
+                text("foo bar"),
+                Node::Break(Break { data: None }),
+                text("baz")
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_drop_empty() {
+        assert_eq!(
+            normalized(vec![
+                paragraph(vec![
+                    text("a"),
+                    emphasis(vec![Node::Strong(Strong {
+                        children: vec![text("")],
+                        data: None,
+                    })]),
+                    Node::Delete(Delete {
+                        children: vec![],
+                        data: None
+                    }),
+                    text("b"),
+                ]),
+                paragraph(vec![emphasis(vec![])]),
+                // other parents are meaningful without children.
+                Node::Heading(Heading::new(1).unwrap()),
+                Node::Blockquote(Blockquote {
+                    children: vec![],
+                    data: None
+                }),
+            ]),
+            [
+                paragraph(vec![text("ab")]),
+                Node::Heading(Heading::new(1).unwrap()),
+                Node::Blockquote(Blockquote {
+                    children: vec![],
+                    data: None
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unwrap_nesting() {
+        assert_eq!(
+            normalized(vec![paragraph(vec![
+                emphasis(vec![emphasis(vec![emphasis(vec![text("a")])])]),
+                Node::Strong(Strong {
+                    children: vec![Node::Strong(Strong {
+                        children: vec![text("b")],
+                        data: None,
+                    })],
+                    data: None,
+                }),
+                // emphasis in strong is not redundant, nor are siblings.
+                Node::Strong(Strong {
+                    children: vec![emphasis(vec![text("c")])],
+                    data: None,
+                }),
+                emphasis(vec![emphasis(vec![text("d")]), text("e")]),
+            ])]),
+            [paragraph(vec![
+                emphasis(vec![text("a")]),
+                Node::Strong(Strong {
+                    children: vec![text("b")],
+                    data: None,
+                }),
+                Node::Strong(Strong {
+                    children: vec![emphasis(vec![text("c")])],
+                    data: None,
+                }),
+                emphasis(vec![emphasis(vec![text("d")]), text("e")]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let mut document = Document {
+            children: vec![
+                paragraph(vec![
+                    text("a"),
+                    emphasis(vec![emphasis(vec![text(""), emphasis(vec![])])]),
+                    text("b"),
+                    emphasis(vec![emphasis(vec![text("c"), text("d")])]),
+                ]),
+                Node::List(List {
+                    children: vec![Node::ListItem(ListItem {
+                        children: vec![paragraph(vec![emphasis(vec![])])],
+                        ..ListItem::new(None)
+                    })],
+                    ..List::new(false, None)
+                }),
+            ],
+            data: None,
+        };
+
+        normalize(&mut document);
+
+        let once = document.clone();
+
+        normalize(&mut document);
+
+        assert_eq!(document, once);
+        assert_eq!(
+            once.children[0],
+            paragraph(vec![text("ab"), emphasis(vec![text("cd")])])
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Runs of prose of parsed documents with their source ranges, for spell checkers and other
+//! tools that only care about the text people read.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{prose::ProseContext, prose_spans},
+//!     parser::Parser,
+//! };
+//!
+//! let source = "# Setup\n\nRun `cargo build` and\nwait.\n";
+//!
+//! let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+//!
+//! let prose = prose_spans(&document, &spans, source);
+//!
+//! assert_eq!(prose[0].text, "Setup");
+//! assert_eq!(prose[0].context, ProseContext::Heading);
+//! assert_eq!(prose[1].text, "Run ");
+//! assert_eq!(prose[2].text, " and\nwait.");
+//! assert_eq!(&source[prose[2].range.clone()], " and\nwait.");
+//! ```
+
+use core::ops::Range;
+
+use super::spans::Spans;
+use super::*;
+
+/// Where a [`ProseSpan`] was found.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProseContext {
+    /// Text of a heading.
+    Heading,
+    /// Text of a paragraph outside of lists and tables.
+    Paragraph,
+    /// Text of a paragraph inside a list item, nested block quotes included.
+    ListItem,
+    /// Text of a table cell.
+    TableCell,
+    /// Alternative text of an image or image reference.
+    ImageAlt,
+    /// Title of a link or image.
+    Title,
+}
+
+/// A run of prose found by [`prose_spans`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProseSpan {
+    /// Byte range of the run in the source, escapes and character references included.
+    pub range: Range<usize>,
+    /// The text of the run, with escapes and character references decoded: it differs from
+    /// the source in `range` where those are used.
+    pub text: String,
+    /// Where the run was found.
+    pub context: ProseContext,
+}
+
+/// Returns the runs of prose of `document`, in document order.
+///
+/// `spans` and `source` are those of the parse that produced `document`, see
+/// [`Parser::parse_with_spans`](crate::parser::Parser::parse_with_spans). A run is a sequence
+/// of adjacent [`Text`] nodes, soft line breaks included: emphasis, links and any other node
+/// end it. Code, inline code, html, autolinks, definitions and footnote references are
+/// skipped, as are runs of only whitespace and nodes without a source range, inserted after
+/// parsing.
+///
+/// The alternative text of images and the titles of links and images are runs of their own.
+/// Their text is that of the node, alternative text of nested emphasis or images losing its
+/// markup, while their range covers the source between the brackets or the title delimiters.
+pub fn prose_spans(document: &Document, spans: &Spans, source: &str) -> Vec<ProseSpan> {
+    let mut collector = ProseCollector {
+        spans,
+        source,
+        path: vec![],
+        prose: vec![],
+    };
+
+    collector.collect(&document.children, ProseContext::Paragraph);
+
+    collector.prose
+}
+
+struct ProseCollector<'a> {
+    spans: &'a Spans,
+    source: &'a str,
+    path: Vec<usize>,
+    prose: Vec<ProseSpan>,
+}
+
+impl<'a> ProseCollector<'a> {
+    /// Collects the prose of `children`, the children of the node at `self.path`.
+    fn collect(&mut self, children: &[Node], context: ProseContext) {
+        let mut run: Option<(Range<usize>, String)> = None;
+
+        for (index, child) in children.iter().enumerate() {
+            self.path.push(index);
+
+            let span = self.spans.get(&self.path);
+
+            match (child, span) {
+                (Node::Text(text), Some(span)) => match &mut run {
+                    Some((range, value)) => {
+                        range.end = span.end;
+                        value.push_str(&text.value);
+                    }
+                    None => run = Some((span, text.value.to_string())),
+                },
+                (child, span) => {
+                    self.push_run(run.take(), context);
+
+                    if let Some(span) = span {
+                        self.collect_node(child, span, context);
+                    }
+                }
+            }
+
+            self.path.pop();
+        }
+
+        self.push_run(run, context);
+    }
+
+    /// Collects the prose of `node`, other than a text node, at `self.path`.
+    fn collect_node(&mut self, node: &Node, span: Range<usize>, context: ProseContext) {
+        let context = match node {
+            Node::Heading(_) => ProseContext::Heading,
+            Node::ListItem(_) => ProseContext::ListItem,
+            Node::TableCell(_) => ProseContext::TableCell,
+            Node::Image(image) => {
+                let alt = alt_range(self.source, &span);
+
+                self.push(alt, image.alt.as_deref(), ProseContext::ImageAlt);
+                self.push(
+                    title_range(self.source, &span),
+                    image.title.as_deref(),
+                    ProseContext::Title,
+                );
+
+                return;
+            }
+            Node::ImageReference(reference) => {
+                let alt = alt_range(self.source, &span);
+
+                self.push(alt, reference.alt.as_deref(), ProseContext::ImageAlt);
+
+                return;
+            }
+            Node::Link(link) => {
+                // autolinks, `<https://example.com>`, have their url as text.
+                if !self
+                    .source
+                    .get(span.clone())
+                    .is_some_and(|s| s.starts_with('['))
+                {
+                    return;
+                }
+
+                self.collect(&link.children, context);
+                self.push(
+                    title_range(self.source, &span),
+                    link.title.as_deref(),
+                    ProseContext::Title,
+                );
+
+                return;
+            }
+            Node::Code(_)
+            | Node::InlineCode(_)
+            | Node::Html(_)
+            | Node::Definition(_)
+            | Node::FootnoteReference(_)
+            | Node::Custom(_) => return,
+            _ => context,
+        };
+
+        self.collect(node.children().unwrap_or_default(), context);
+    }
+
+    fn push_run(&mut self, run: Option<(Range<usize>, String)>, context: ProseContext) {
+        if let Some((range, text)) = run {
+            self.push(Some(range), Some(&text), context);
+        }
+    }
+
+    fn push(&mut self, range: Option<Range<usize>>, text: Option<&str>, context: ProseContext) {
+        let (Some(range), Some(text)) = (range, text) else {
+            return;
+        };
+
+        if text.trim().is_empty() {
+            return;
+        }
+
+        self.prose.push(ProseSpan {
+            range,
+            text: text.to_owned(),
+            context,
+        });
+    }
+}
+
+/// Returns true if the byte at `index` of `source` is escaped by a backslash.
+fn is_escaped(source: &str, index: usize) -> bool {
+    let backslashes = source.as_bytes()[..index]
+        .iter()
+        .rev()
+        .take_while(|b| **b == b'\\')
+        .count();
+
+    backslashes % 2 == 1
+}
+
+/// Returns the range of the alternative text of the image at `span`, between `![` and the
+/// matching `]`.
+fn alt_range(source: &str, span: &Range<usize>) -> Option<Range<usize>> {
+    let start = span.start + 2;
+
+    let mut depth = 0usize;
+
+    for (index, b) in source.as_bytes().get(start..span.end)?.iter().enumerate() {
+        match b {
+            b'[' if !is_escaped(source, start + index) => depth += 1,
+            b']' if !is_escaped(source, start + index) => match depth.checked_sub(1) {
+                Some(outer) => depth = outer,
+                None => return Some(start..start + index),
+            },
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the range of the title of the inline link or image at `span`, between its
+/// delimiters at the end of the destination parentheses.
+fn title_range(source: &str, span: &Range<usize>) -> Option<Range<usize>> {
+    let inner = source.get(span.clone())?.strip_suffix(')')?;
+    let inner = inner.trim_end_matches([' ', '\t', '\n', '\r']);
+
+    let open = match inner.as_bytes().last()? {
+        b'"' => b'"',
+        b'\'' => b'\'',
+        b')' => b'(',
+        _ => return None,
+    };
+
+    let end = span.start + inner.len() - 1;
+
+    let start = (span.start..end)
+        .rev()
+        .find(|index| source.as_bytes()[*index] == open && !is_escaped(source, *index))?;
+
+    Some(start + 1..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn prose(source: &str) -> Vec<(&str, String, ProseContext)> {
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        prose_spans(&document, &spans, source)
+            .into_iter()
+            .map(|span| (&source[span.range], span.text, span.context))
+            .collect()
+    }
+
+    #[test]
+    fn test_code_spans() {
+        use ProseContext::*;
+
+        assert_eq!(
+            prose("Call `f()` then\n`g()`, see <https://example.com>.\n\n```\nno\n```\n"),
+            [
+                ("Call ", "Call ".to_owned(), Paragraph),
+                (" then\n", " then\n".to_owned(), Paragraph),
+                (", see ", ", see ".to_owned(), Paragraph),
+                (".", ".".to_owned(), Paragraph)
+            ]
+        );
+
+        // escapes and references keep their source range.
+        assert_eq!(
+            prose("- a \\*b\\* &amp; c  \nd"),
+            [
+                ("a \\*b\\* &amp; c", "a *b* & c".to_owned(), ListItem),
+                ("d", "d".to_owned(), ListItem)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_images_and_links() {
+        use ProseContext::*;
+
+        assert_eq!(
+            prose("# A ![big \\] *cat*](cat.png \"The \\\"cat\\\"\")\n\n[docs](/ 'Read (it)') [x]\n\n[x]: /x\n"),
+            [
+                ("A ", "A ".to_owned(), Heading),
+                ("big \\] *cat*", "big ] cat".to_owned(), ImageAlt),
+                ("The \\\"cat\\\"", "The \"cat\"".to_owned(), Title),
+                ("docs", "docs".to_owned(), Paragraph),
+                ("Read (it)", "Read (it)".to_owned(), Title),
+                ("x", "x".to_owned(), Paragraph)
+            ]
+        );
+
+        assert_eq!(
+            prose("| ![a][x] | b `c` |\n|---|---|\n| d\\|e | |\n\n[x]: /x (T)\n"),
+            [
+                ("a", "a".to_owned(), ImageAlt),
+                ("b ", "b ".to_owned(), TableCell),
+                ("d\\|e", "d|e".to_owned(), TableCell)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_without_spans() {
+        let document = Parser::new("a *b*").parse().unwrap();
+
+        assert!(prose_spans(&document, &Spans::new(), "a *b*").is_empty());
+    }
+}
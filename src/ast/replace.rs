@@ -0,0 +1,306 @@
+//! Literal find and replace over text values, leaving markup, URLs and code alone.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{replace::ReplaceOptions, replace_text},
+//!     parser::Parser,
+//! };
+//!
+//! let mut document = Parser::new("Use `foo()` to foo, see [foo](https://foo.example).")
+//!     .parse()
+//!     .unwrap();
+//!
+//! let replacements = replace_text(&mut document, "foo", "bar", &ReplaceOptions::default());
+//!
+//! assert_eq!(replacements.len(), 2);
+//! assert_eq!(
+//!     document.to_markdown(&Default::default()),
+//!     "Use `foo()` to bar, see [bar](https://foo.example).\n"
+//! );
+//! ```
+
+use super::normalize::merge_text;
+use super::*;
+
+/// Options of [`replace_text`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReplaceOptions {
+    /// Replace in [`Text`] values, defaults to `true`.
+    pub text: bool,
+    /// Replace in [`InlineCode`] values, defaults to `false`.
+    pub inline_code: bool,
+    /// Replace in [`Code`] values, defaults to `false`.
+    pub code: bool,
+    /// Match case, defaults to `true`. Otherwise characters match if their lowercase forms
+    /// are equal.
+    pub case_sensitive: bool,
+}
+
+impl Default for ReplaceOptions {
+    fn default() -> Self {
+        ReplaceOptions {
+            text: true,
+            inline_code: false,
+            code: false,
+            case_sensitive: true,
+        }
+    }
+}
+
+/// A replacement made by [`replace_text`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Replacement {
+    /// Position of the node in the tree, as child indices from the document root.
+    pub path: Vec<usize>,
+    /// Byte offset of the match in the value before replacement.
+    pub offset: usize,
+}
+
+/// Replace every non-overlapping occurrence of `pattern` in the values selected by `options`
+/// with `replacement`, returns the replacements made in document order.
+///
+/// Both strings are literal, no character has a special meaning. Adjacent text nodes are
+/// merged first, so a match may span them; paths refer to the merged tree. Urls, titles, alt
+/// texts, html and identifiers are never touched.
+pub fn replace_text(
+    document: &mut Document,
+    pattern: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> Vec<Replacement> {
+    let mut replacer = Replacer {
+        pattern,
+        replacement,
+        options,
+        path: vec![],
+        replacements: vec![],
+    };
+
+    if !pattern.is_empty() {
+        replacer.children(&mut document.children);
+    }
+
+    replacer.replacements
+}
+
+struct Replacer<'a> {
+    pattern: &'a str,
+    replacement: &'a str,
+    options: &'a ReplaceOptions,
+    path: Vec<usize>,
+    replacements: Vec<Replacement>,
+}
+
+impl<'a> Replacer<'a> {
+    fn children(&mut self, nodes: &mut Vec<Node>) {
+        if self.options.text {
+            merge_text(nodes);
+        }
+
+        for (index, node) in nodes.iter_mut().enumerate() {
+            self.path.push(index);
+
+            self.node(node);
+
+            self.path.pop();
+        }
+    }
+
+    fn node(&mut self, node: &mut Node) {
+        let value = match node {
+            Node::Text(text) if self.options.text => &mut text.value,
+            Node::InlineCode(code) if self.options.inline_code => &mut code.value,
+            Node::Code(code) if self.options.code => &mut code.value,
+            _ => {
+                if let Some(children) = node.children_mut() {
+                    self.children(children);
+                }
+
+                return;
+            }
+        };
+
+        let offsets = self.matches(value);
+
+        if offsets.is_empty() {
+            return;
+        }
+
+        let mut replaced = String::with_capacity(value.len());
+
+        let mut last = 0;
+
+        for (start, end) in offsets {
+            replaced.push_str(&value[last..start]);
+            replaced.push_str(self.replacement);
+
+            last = end;
+
+            self.replacements.push(Replacement {
+                path: self.path.clone(),
+                offset: start,
+            });
+        }
+
+        replaced.push_str(&value[last..]);
+
+        *value = Cow::Owned(replaced);
+    }
+
+    /// Returns the byte ranges of the non-overlapping matches in `value`, left to right.
+    fn matches(&self, value: &str) -> Vec<(usize, usize)> {
+        if self.options.case_sensitive {
+            return value
+                .match_indices(self.pattern)
+                .map(|(start, found)| (start, start + found.len()))
+                .collect();
+        }
+
+        let mut matches = vec![];
+
+        let mut from = 0;
+
+        while let Some((start, _)) = value[from..]
+            .char_indices()
+            .find(|(start, _)| self.match_end(&value[from + start..]).is_some())
+        {
+            let start = from + start;
+
+            let end = start + self.match_end(&value[start..]).unwrap_or_default();
+
+            matches.push((start, end));
+
+            from = end;
+        }
+
+        matches
+    }
+
+    /// Returns the length of the case-insensitive match at the start of `value`, if any.
+    fn match_end(&self, value: &str) -> Option<usize> {
+        let mut chars = value.char_indices();
+
+        for expected in self.pattern.chars() {
+            let (_, found) = chars.next()?;
+
+            if !found.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+        }
+
+        Some(chars.next().map_or(value.len(), |(end, _)| end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn replaced(
+        source: &str,
+        pattern: &str,
+        replacement: &str,
+        options: &ReplaceOptions,
+    ) -> String {
+        let mut document = Parser::new(source).parse().unwrap();
+
+        replace_text(&mut document, pattern, replacement, options);
+
+        document.to_markdown(&Default::default())
+    }
+
+    #[test]
+    fn test_skip_code() {
+        let source = "`old` old *old*\n\n```\nold\n```";
+
+        assert_eq!(
+            replaced(source, "old", "new", &ReplaceOptions::default()),
+            "`old` new *new*\n\n```\nold\n```\n"
+        );
+
+        let options = ReplaceOptions {
+            inline_code: true,
+            code: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            replaced(source, "old", "new", &options),
+            "`new` new *new*\n\n```\nnew\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_text_boundary() {
+        let mut document = Document {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![
+                    Node::Text(Text {
+                        value: "a fo".into(),
+                        data: None,
+                    }),
+                    Node::Text(Text {
+                        value: "o b".into(),
+                        data: None,
+                    }),
+                    Node::Emphasis(Emphasis {
+                        children: vec![Node::Text(Text {
+                            value: "foo".into(),
+                            data: None,
+                        })],
+                        data: None,
+                    }),
+                ],
+                data: None,
+            })],
+            data: None,
+        };
+
+        let replacements = replace_text(&mut document, "foo", "bar", &ReplaceOptions::default());
+
+        assert_eq!(
+            replacements,
+            [
+                Replacement {
+                    path: vec![0, 0],
+                    offset: 2,
+                },
+                Replacement {
+                    path: vec![0, 1, 0],
+                    offset: 0,
+                }
+            ]
+        );
+        assert_eq!(document.to_markdown(&Default::default()), "a bar b*bar*\n");
+    }
+
+    #[test]
+    fn test_literal() {
+        let options = ReplaceOptions::default();
+
+        assert_eq!(replaced("a.b axb", "a.b", "c", &options), "c axb\n");
+        assert_eq!(replaced("x (y)* x", "(y)*", "$1", &options), "x $1 x\n");
+        assert_eq!(replaced("aaaa", "aa", "b", &options), "bb\n");
+        assert_eq!(replaced("abc", "", "x", &options), "abc\n");
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let options = ReplaceOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            replaced("Rust, RUST and rust", "rust", "Rust", &options),
+            "Rust, Rust and Rust\n"
+        );
+        assert_eq!(replaced("ÉTÉ été", "été", "x", &options), "x x\n");
+        assert_eq!(
+            replaced("Rust, RUST", "rust", "x", &ReplaceOptions::default()),
+            "Rust, RUST\n"
+        );
+    }
+}
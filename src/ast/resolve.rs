@@ -0,0 +1,334 @@
+//! Reference resolution: associate [`LinkReference`] and [`ImageReference`] nodes with
+//! their [`Definition`].
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{resolve::resolve_references, Node},
+//!     parser::Parser,
+//! };
+//!
+//! let mut document = Parser::new("[docs][]\n\n[docs]: https://docs.rs").parse().unwrap();
+//!
+//! assert_eq!(resolve_references(&mut document), 1);
+//!
+//! let Node::Paragraph(paragraph) = &document.children[0] else {
+//!     unreachable!()
+//! };
+//!
+//! assert!(matches!(&paragraph.children[0], Node::Link(link) if link.url == "https://docs.rs"));
+//! ```
+
+use alloc::{borrow::Cow, collections::BTreeMap};
+
+use super::*;
+
+/// Options of [`resolve_references_with`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ResolveOptions {
+    /// Replace references without a matching definition by the literal text of the
+    /// reference (`[text][label]`), defaults to `false` which leaves them untouched.
+    pub dangling_as_text: bool,
+}
+
+/// Returns the definitions of `document` keyed by their normalized identifier
+/// (see [`normalize_identifier`]).
+///
+/// As per CommonMark, the first definition of an identifier wins, later duplicates are ignored.
+pub fn definitions<'a, 'cx>(document: &'a Document<'cx>) -> BTreeMap<String, &'a Definition<'cx>> {
+    let mut definitions = BTreeMap::new();
+
+    for node in document.descendants() {
+        if let Node::Definition(definition) = node {
+            definitions
+                .entry(normalize_identifier(&definition.identifier))
+                .or_insert(definition.as_ref());
+        }
+    }
+
+    definitions
+}
+
+/// Rewrite every [`LinkReference`] into a [`Link`] and every [`ImageReference`] into an
+/// [`Image`] using the definitions of `document`, returns the number of resolved references.
+///
+/// References without a matching definition are left untouched.
+pub fn resolve_references(document: &mut Document) -> usize {
+    resolve_references_with(document, &ResolveOptions::default())
+}
+
+/// Same as [`resolve_references`], with dangling references handled according to `options`.
+pub fn resolve_references_with(document: &mut Document, options: &ResolveOptions) -> usize {
+    let definitions = definitions(document)
+        .into_iter()
+        .map(|(identifier, definition)| {
+            (
+                identifier,
+                (definition.url.clone(), definition.title.clone()),
+            )
+        })
+        .collect();
+
+    let mut resolver = Resolver {
+        options,
+        definitions,
+        count: 0,
+    };
+
+    for child in &mut document.children {
+        walk_mut(child, &mut resolver);
+    }
+
+    resolver.count
+}
+
+struct Resolver<'o, 'cx> {
+    options: &'o ResolveOptions,
+    definitions: BTreeMap<String, (Cow<'cx, str>, Option<Cow<'cx, str>>)>,
+    count: usize,
+}
+
+impl<'o, 'cx> Resolver<'o, 'cx> {
+    fn lookup(&mut self, identifier: &str) -> Option<(Cow<'cx, str>, Option<Cow<'cx, str>>)> {
+        let resource = self
+            .definitions
+            .get(&normalize_identifier(identifier))
+            .cloned()?;
+
+        self.count += 1;
+
+        Some(resource)
+    }
+}
+
+impl<'o, 'cx> VisitorMut<'cx> for Resolver<'o, 'cx> {
+    fn visit_link_reference(&mut self, node: &mut LinkReference<'cx>) -> Transform<'cx> {
+        match self.lookup(&node.identifier) {
+            Some((url, title)) => Transform::Replace(Node::Link(Box::new(Link {
+                children: core::mem::take(&mut node.children),
+                url,
+                title,
+                data: None,
+            }))),
+            None => Transform::Keep,
+        }
+    }
+
+    fn visit_image_reference(&mut self, node: &mut ImageReference<'cx>) -> Transform<'cx> {
+        match self.lookup(&node.identifier) {
+            Some((url, title)) => Transform::Replace(Node::Image(Box::new(Image {
+                url,
+                title,
+                alt: node.alt.take(),
+                data: None,
+            }))),
+            None => Transform::Keep,
+        }
+    }
+
+    fn leave(&mut self, node: &mut Node<'cx>) {
+        if !self.options.dangling_as_text {
+            return;
+        }
+
+        let Some(children) = node.children_mut() else {
+            return;
+        };
+
+        // resolved references were already replaced, any one left is dangling.
+        if !children
+            .iter()
+            .any(|child| matches!(child, Node::LinkReference(_) | Node::ImageReference(_)))
+        {
+            return;
+        }
+
+        for mut child in core::mem::take(children) {
+            match &mut child {
+                Node::LinkReference(reference) => {
+                    children.push(text("["));
+                    children.extend(core::mem::take(&mut reference.children));
+                    children.push(text(suffix(
+                        &reference.identifier,
+                        reference.label.as_deref(),
+                        &reference.reference_type,
+                    )));
+                }
+                Node::ImageReference(reference) => {
+                    children.push(text(format!(
+                        "![{}{}",
+                        reference.alt.as_deref().unwrap_or_default(),
+                        suffix(
+                            &reference.identifier,
+                            reference.label.as_deref(),
+                            &reference.reference_type,
+                        )
+                    )));
+                }
+                _ => children.push(child),
+            }
+        }
+    }
+}
+
+fn text<'cx>(value: impl Into<Cow<'cx, str>>) -> Node<'cx> {
+    Node::Text(Text {
+        value: value.into(),
+        data: None,
+    })
+}
+
+/// Literal text following the reference content.
+fn suffix(identifier: &str, label: Option<&str>, kind: &ReferenceType) -> String {
+    match kind {
+        ReferenceType::Full => format!("][{}]", label.unwrap_or(identifier)),
+        ReferenceType::Collapsed => "][]".to_owned(),
+        ReferenceType::Shortcut => "]".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn phrasing<'a, 'cx>(document: &'a Document<'cx>) -> &'a [Node<'cx>] {
+        let Node::Paragraph(paragraph) = &document.children[0] else {
+            panic!("expect paragraph");
+        };
+
+        &paragraph.children
+    }
+
+    #[test]
+    fn test_full_collapsed_shortcut() {
+        let mut document = Parser::new(
+            "[full][Rust Lang], [Rust Lang][], [rust lang] and ![logo][]\n\n\
+             [rust lang]: https://rust-lang.org \"Rust\"\n\
+             [logo]: logo.png\n",
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(resolve_references(&mut document), 4);
+
+        let urls = phrasing(&document)
+            .iter()
+            .filter_map(|node| match node {
+                Node::Link(link) => {
+                    assert_eq!(link.title.as_deref(), Some("Rust"));
+                    assert!(!link.children.is_empty());
+
+                    Some(&*link.url)
+                }
+                Node::Image(image) => {
+                    assert_eq!(image.alt.as_deref(), Some("logo"));
+
+                    Some(&*image.url)
+                }
+                Node::LinkReference(_) | Node::ImageReference(_) => panic!("unresolved"),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            urls,
+            [
+                "https://rust-lang.org",
+                "https://rust-lang.org",
+                "https://rust-lang.org",
+                "logo.png"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dangling() {
+        let markdown = "[a][missing] and ![b][]\n\n[unused]: /unused\n";
+
+        let mut document = Parser::new(markdown).parse().unwrap();
+
+        let mut dangling = document
+            .descendants()
+            .filter(|node| matches!(node, Node::LinkReference(_) | Node::ImageReference(_)))
+            .count();
+
+        assert_eq!(dangling, 0);
+
+        // the parser itself only creates references that match a definition.
+        document.children[0] = Node::Paragraph(Paragraph {
+            children: vec![
+                Node::from(LinkReference {
+                    children: vec![text("a")],
+                    identifier: "missing".into(),
+                    label: Some("missing".into()),
+                    reference_type: ReferenceType::Full,
+                    data: None,
+                }),
+                text(" and "),
+                Node::from(ImageReference {
+                    alt: Some("b".into()),
+                    identifier: "b".into(),
+                    label: Some("b".into()),
+                    reference_type: ReferenceType::Collapsed,
+                    data: None,
+                }),
+            ],
+            data: None,
+        });
+
+        let mut untouched = document.clone();
+
+        assert_eq!(resolve_references(&mut untouched), 0);
+        assert_eq!(untouched, document);
+
+        assert_eq!(
+            resolve_references_with(
+                &mut document,
+                &ResolveOptions {
+                    dangling_as_text: true
+                }
+            ),
+            0
+        );
+
+        dangling = document
+            .descendants()
+            .filter(|node| matches!(node, Node::LinkReference(_) | Node::ImageReference(_)))
+            .count();
+
+        assert_eq!(dangling, 0);
+
+        let text = phrasing(&document)
+            .iter()
+            .map(|node| match node {
+                Node::Text(text) => &*text.value,
+                node => panic!("unexpected {:?}", node),
+            })
+            .collect::<String>();
+
+        assert_eq!(text, "[a][missing] and ![b][]");
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let mut document =
+            Parser::new("[one]\n\n[ONE]: /first \"First\"\n[one]: /second\n[two]: /two\n")
+                .parse()
+                .unwrap();
+
+        let definitions = definitions(&document);
+
+        assert_eq!(definitions.len(), 2);
+        assert_eq!(definitions["one"].url, "/first");
+
+        assert_eq!(resolve_references(&mut document), 1);
+
+        let [Node::Link(link)] = phrasing(&document) else {
+            panic!("expect a single link");
+        };
+
+        assert_eq!(link.url, "/first");
+        assert_eq!(link.title.as_deref(), Some("First"));
+    }
+}
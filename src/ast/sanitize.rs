@@ -0,0 +1,591 @@
+//! Sanitizing transform for untrusted markdown.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::sanitize::{sanitize, SanitizeOptions},
+//!     html::HtmlOptions,
+//!     parser::Parser,
+//! };
+//!
+//! let mut document = Parser::new("[click](javascript:alert(1)) <img src=x onerror=alert(1)>")
+//!     .parse()
+//!     .unwrap();
+//!
+//! let actions = sanitize(&mut document, &SanitizeOptions::default());
+//!
+//! assert_eq!(actions.len(), 2);
+//! assert_eq!(
+//!     document.to_html(&HtmlOptions::default()),
+//!     "<p><a href=\"#\">click</a> </p>\n"
+//! );
+//! ```
+
+use alloc::collections::BTreeMap;
+
+use super::links::for_each_with_path;
+use super::*;
+
+/// What [`sanitize()`] does with [`Html`] nodes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum HtmlPolicy {
+    /// Drop the node.
+    #[default]
+    Remove,
+    /// Turn the node into text, so the markup is displayed instead of interpreted.
+    Escape,
+}
+
+/// What [`sanitize()`] does with nodes whose url has a blocked scheme.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UrlPolicy {
+    /// Replace the url with `#`.
+    #[default]
+    Replace,
+    /// Drop the node, links are unwrapped keeping their content.
+    Remove,
+}
+
+/// Options of [`sanitize()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SanitizeOptions {
+    /// Handling of raw html, defaults to [`HtmlPolicy::Remove`].
+    pub html: HtmlPolicy,
+    /// Url schemes considered unsafe, compared case-insensitively.
+    ///
+    /// Defaults to `javascript`, `vbscript`, `data` and `file`.
+    pub blocked_schemes: Vec<String>,
+    /// Handling of links, images and definitions with a blocked scheme,
+    /// defaults to [`UrlPolicy::Replace`].
+    pub unsafe_urls: UrlPolicy,
+    /// Headings deeper than this are clamped to it, defaults to no limit.
+    pub max_heading_depth: Option<u8>,
+    /// Hosts images may be loaded from, compared case-insensitively. Images from any other
+    /// host are removed, relative urls are always allowed. Defaults to any host.
+    pub image_hosts: Option<Vec<String>>,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        SanitizeOptions {
+            html: HtmlPolicy::default(),
+            blocked_schemes: ["javascript", "vbscript", "data", "file"]
+                .map(String::from)
+                .to_vec(),
+            unsafe_urls: UrlPolicy::default(),
+            max_heading_depth: None,
+            image_hosts: None,
+        }
+    }
+}
+
+/// A change made by [`sanitize()`].
+///
+/// Paths are the child indices from the document root of the affected node, in the document
+/// as it was before sanitizing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SanitizeAction {
+    /// An html node was removed.
+    RemoveHtml { path: Vec<usize>, value: String },
+    /// An html node was turned into text.
+    EscapeHtml { path: Vec<usize>, value: String },
+    /// The url of a node was replaced with `#`.
+    ReplaceUrl {
+        path: Vec<usize>,
+        kind: NodeKind,
+        url: String,
+    },
+    /// A node was removed because of its url, links keep their content.
+    RemoveNode {
+        path: Vec<usize>,
+        kind: NodeKind,
+        url: String,
+    },
+    /// A heading was clamped to [`SanitizeOptions::max_heading_depth`].
+    ClampHeading { path: Vec<usize>, depth: u8 },
+}
+
+impl SanitizeAction {
+    /// Returns the position of the affected node.
+    pub fn path(&self) -> &[usize] {
+        match self {
+            SanitizeAction::RemoveHtml { path, .. }
+            | SanitizeAction::EscapeHtml { path, .. }
+            | SanitizeAction::ReplaceUrl { path, .. }
+            | SanitizeAction::RemoveNode { path, .. }
+            | SanitizeAction::ClampHeading { path, .. } => path,
+        }
+    }
+}
+
+/// Make `document` safe to render: handle raw html, urls with a blocked scheme, heading depth
+/// and image hosts according to `options`. Returns the changes made, in document order.
+pub fn sanitize(document: &mut Document, options: &SanitizeOptions) -> Vec<SanitizeAction> {
+    let policy = Policy::new(document, options);
+
+    let mut actions = vec![];
+
+    // decisions only depend on the node itself, report them against the untouched tree.
+    for_each_with_path(document, |path, node| {
+        let Some(verdict) = policy.verdict(node) else {
+            return;
+        };
+
+        let path = path.to_vec();
+
+        actions.push(match (verdict, node) {
+            (Verdict::RemoveHtml, Node::Html(html)) => SanitizeAction::RemoveHtml {
+                path,
+                value: html.value.to_string(),
+            },
+            (Verdict::EscapeHtml, Node::Html(html)) => SanitizeAction::EscapeHtml {
+                path,
+                value: html.value.to_string(),
+            },
+            (Verdict::ClampHeading(depth), _) => SanitizeAction::ClampHeading { path, depth },
+            (Verdict::ReplaceUrl, node) => SanitizeAction::ReplaceUrl {
+                path,
+                kind: node.kind(),
+                url: policy.url(node).unwrap_or_default().to_owned(),
+            },
+            (_, node) => SanitizeAction::RemoveNode {
+                path,
+                kind: node.kind(),
+                url: policy.url(node).unwrap_or_default().to_owned(),
+            },
+        });
+    });
+
+    for child in &mut document.children {
+        walk_mut(child, &mut Sanitizer(&policy));
+    }
+
+    policy.sanitize_children(&mut document.children, true);
+
+    actions
+}
+
+enum Verdict {
+    RemoveHtml,
+    EscapeHtml,
+    ReplaceUrl,
+    Remove,
+    Unwrap,
+    ClampHeading(u8),
+}
+
+struct Policy<'o> {
+    options: &'o SanitizeOptions,
+    /// Url of the first definition of each identifier, images may be references.
+    definitions: BTreeMap<String, String>,
+}
+
+impl<'o> Policy<'o> {
+    fn new(document: &Document, options: &'o SanitizeOptions) -> Self {
+        let definitions = super::resolve::definitions(document)
+            .into_iter()
+            .map(|(identifier, definition)| (identifier, definition.url.to_string()))
+            .collect();
+
+        Policy {
+            options,
+            definitions,
+        }
+    }
+
+    fn url<'a>(&'a self, node: &'a Node) -> Option<&'a str> {
+        match node {
+            Node::Link(link) => Some(&link.url),
+            Node::Image(image) => Some(&image.url),
+            Node::Definition(definition) => Some(&definition.url),
+            Node::ImageReference(reference) => self
+                .definitions
+                .get(&normalize_identifier(&reference.identifier))
+                .map(String::as_str),
+            _ => None,
+        }
+    }
+
+    fn verdict(&self, node: &Node) -> Option<Verdict> {
+        match node {
+            Node::Html(_) => Some(match self.options.html {
+                HtmlPolicy::Remove => Verdict::RemoveHtml,
+                HtmlPolicy::Escape => Verdict::EscapeHtml,
+            }),
+            Node::Heading(heading) => match self.options.max_heading_depth {
+                Some(max) if heading.depth > max => Some(Verdict::ClampHeading(heading.depth)),
+                _ => None,
+            },
+            Node::Link(_) | Node::Definition(_) => {
+                let url = self.url(node)?;
+
+                match self.options.unsafe_urls {
+                    _ if !self.is_blocked(url) => None,
+                    UrlPolicy::Replace => Some(Verdict::ReplaceUrl),
+                    UrlPolicy::Remove if matches!(node, Node::Link(_)) => Some(Verdict::Unwrap),
+                    UrlPolicy::Remove => Some(Verdict::Remove),
+                }
+            }
+            Node::Image(_) | Node::ImageReference(_) => {
+                let url = self.url(node)?;
+
+                if !self.is_allowed_image(url) {
+                    return Some(Verdict::Remove);
+                }
+
+                // references point to a definition, which is sanitized itself.
+                match self.options.unsafe_urls {
+                    _ if matches!(node, Node::ImageReference(_)) || !self.is_blocked(url) => None,
+                    UrlPolicy::Replace => Some(Verdict::ReplaceUrl),
+                    UrlPolicy::Remove => Some(Verdict::Remove),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_blocked(&self, url: &str) -> bool {
+        match scheme(url) {
+            Some(scheme) => self
+                .options
+                .blocked_schemes
+                .iter()
+                .any(|blocked| blocked.eq_ignore_ascii_case(&scheme)),
+            None => false,
+        }
+    }
+
+    fn is_allowed_image(&self, url: &str) -> bool {
+        let Some(hosts) = &self.options.image_hosts else {
+            return true;
+        };
+
+        match host(url) {
+            Some(host) => hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host)),
+            // relative urls stay on the same site, other schemes have no host to check.
+            None => scheme(url).is_none(),
+        }
+    }
+
+    /// Remove, unwrap or escape the direct children of a parent, `flow` tells whether its
+    /// content model is flow content.
+    fn sanitize_children(&self, children: &mut Vec<Node>, flow: bool) {
+        if !children.iter().any(|child| {
+            matches!(
+                self.verdict(child),
+                Some(Verdict::RemoveHtml | Verdict::EscapeHtml | Verdict::Remove | Verdict::Unwrap)
+            )
+        }) {
+            return;
+        }
+
+        for mut child in core::mem::take(children) {
+            match (self.verdict(&child), &mut child) {
+                (Some(Verdict::RemoveHtml | Verdict::Remove), _) => {}
+                (Some(Verdict::EscapeHtml), Node::Html(Html { value, .. })) => {
+                    let text = Node::Text(Text {
+                        value: core::mem::take(value),
+                        data: None,
+                    });
+
+                    children.push(match flow {
+                        true => Node::Paragraph(Paragraph {
+                            children: vec![text],
+                            data: None,
+                        }),
+                        false => text,
+                    });
+                }
+                (Some(Verdict::Unwrap), Node::Link(link)) => {
+                    children.extend(core::mem::take(&mut link.children))
+                }
+                _ => children.push(child),
+            }
+        }
+    }
+}
+
+struct Sanitizer<'a, 'o>(&'a Policy<'o>);
+
+impl<'a, 'o, 'cx> VisitorMut<'cx> for Sanitizer<'a, 'o> {
+    fn visit_heading(&mut self, node: &mut Heading<'cx>) -> Transform<'cx> {
+        if let Some(max) = self.0.options.max_heading_depth {
+            node.depth = node.depth.min(max);
+        }
+
+        Transform::Keep
+    }
+
+    fn visit_link(&mut self, node: &mut Link<'cx>) -> Transform<'cx> {
+        if self.0.options.unsafe_urls == UrlPolicy::Replace && self.0.is_blocked(&node.url) {
+            node.url = "#".into();
+        }
+
+        Transform::Keep
+    }
+
+    fn visit_image(&mut self, node: &mut Image<'cx>) -> Transform<'cx> {
+        if self.0.options.unsafe_urls == UrlPolicy::Replace
+            && self.0.is_allowed_image(&node.url)
+            && self.0.is_blocked(&node.url)
+        {
+            node.url = "#".into();
+        }
+
+        Transform::Keep
+    }
+
+    fn visit_definition(&mut self, node: &mut Definition<'cx>) -> Transform<'cx> {
+        if self.0.options.unsafe_urls == UrlPolicy::Replace && self.0.is_blocked(&node.url) {
+            node.url = "#".into();
+        }
+
+        Transform::Keep
+    }
+
+    fn leave(&mut self, node: &mut Node<'cx>) {
+        let flow = matches!(
+            node,
+            Node::Blockquote(_) | Node::ListItem(_) | Node::FootnoteDefinition(_)
+        );
+
+        if let Some(children) = node.children_mut() {
+            self.0.sanitize_children(children, flow);
+        }
+    }
+}
+
+/// Returns the lowercased scheme of `url`, if any.
+///
+/// Like browsers, leading whitespace and control characters as well as tabs and newlines
+/// within the scheme are ignored, so `" java\tscript:"` is a `javascript` url.
+fn scheme(url: &str) -> Option<String> {
+    let mut scheme = String::new();
+
+    for c in url.trim_start_matches(|c: char| c <= ' ').chars() {
+        match c {
+            ':' if !scheme.is_empty() => return Some(scheme),
+            '\t' | '\n' | '\r' => {}
+            c if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.') => {
+                scheme.push(c.to_ascii_lowercase())
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// Returns the host of an absolute or scheme relative `url`.
+fn host(url: &str) -> Option<&str> {
+    let url = url.trim();
+
+    let rest = match scheme(url) {
+        Some(_) => &url[url.find(':')? + 1..],
+        None => url,
+    };
+
+    let authority = rest.strip_prefix("//")?;
+
+    let authority = authority.split(['/', '?', '#']).next().unwrap_or_default();
+
+    let host = authority.rsplit('@').next().unwrap_or_default();
+
+    // strip the port, brackets of ipv6 addresses may contain colons.
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => host,
+        _ => host,
+    };
+
+    (!host.is_empty()).then_some(host)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn options() -> SanitizeOptions {
+        SanitizeOptions::default()
+    }
+
+    #[test]
+    fn test_javascript_link() {
+        let mut document = Parser::new(
+            "[a](javascript:alert(1)) [b](JaVaScRiPt:x) [c](https://example.com) <javascript:x>\n",
+        )
+        .parse()
+        .unwrap();
+
+        let mut removed = document.clone();
+
+        let actions = sanitize(&mut document, &options());
+
+        assert_eq!(
+            actions,
+            [0, 2, 6].map(|index| SanitizeAction::ReplaceUrl {
+                path: vec![0, index],
+                kind: NodeKind::Link,
+                url: match index {
+                    0 => "javascript:alert(1)",
+                    2 => "JaVaScRiPt:x",
+                    _ => "javascript:x",
+                }
+                .to_owned(),
+            })
+        );
+
+        let urls = super::super::links::collect(&document)
+            .into_iter()
+            .map(|link| link.url)
+            .collect::<Vec<_>>();
+
+        assert_eq!(urls, ["#", "#", "https://example.com", "#"]);
+
+        sanitize(
+            &mut removed,
+            &SanitizeOptions {
+                unsafe_urls: UrlPolicy::Remove,
+                ..options()
+            },
+        );
+
+        assert_eq!(removed.to_text(&Default::default()), "a b c javascript:x");
+        assert_eq!(
+            removed
+                .descendants()
+                .filter(|node| matches!(node, Node::Link(_)))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_html() {
+        let markdown = "<div onerror=\"alert(1)\">\n\n> hi <img src=x onerror=alert(1)>\n";
+
+        let mut document = Parser::new(markdown).parse().unwrap();
+
+        let actions = sanitize(&mut document, &options());
+
+        assert_eq!(
+            actions,
+            [
+                SanitizeAction::RemoveHtml {
+                    path: vec![0],
+                    value: "<div onerror=\"alert(1)\">".to_owned(),
+                },
+                SanitizeAction::RemoveHtml {
+                    path: vec![1, 0, 1],
+                    value: "<img src=x onerror=alert(1)>".to_owned(),
+                },
+            ]
+        );
+
+        assert!(!document
+            .descendants()
+            .any(|node| matches!(node, Node::Html(_))));
+
+        let mut document = Parser::new(markdown).parse().unwrap();
+
+        sanitize(
+            &mut document,
+            &SanitizeOptions {
+                html: HtmlPolicy::Escape,
+                ..options()
+            },
+        );
+
+        assert_eq!(
+            document.to_html(&Default::default()),
+            "<p>&lt;div onerror=&quot;alert(1)&quot;&gt;</p>\n\
+             <blockquote>\n<p>hi &lt;img src=x onerror=alert(1)&gt;</p>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn test_data_image() {
+        let mut document = Parser::new(
+            "![a](data:image/svg+xml;base64,PHN2Zz4=) ![b](https://cdn.example.com/b.png) \
+             ![c](https://evil.test/c.png) ![d](d.png) ![e][e]\n\n[e]: //evil.test/e.png\n",
+        )
+        .parse()
+        .unwrap();
+
+        let actions = sanitize(
+            &mut document,
+            &SanitizeOptions {
+                unsafe_urls: UrlPolicy::Remove,
+                image_hosts: Some(vec!["CDN.example.com".to_owned()]),
+                ..options()
+            },
+        );
+
+        let removed = actions
+            .iter()
+            .map(|action| match action {
+                SanitizeAction::RemoveNode { url, .. } => url.as_str(),
+                action => panic!("unexpected {:?}", action),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            removed,
+            [
+                "data:image/svg+xml;base64,PHN2Zz4=",
+                "https://evil.test/c.png",
+                "//evil.test/e.png"
+            ]
+        );
+
+        let images = document
+            .descendants()
+            .filter_map(|node| match node {
+                Node::Image(image) => Some(&*image.url),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(images, ["https://cdn.example.com/b.png", "d.png"]);
+    }
+
+    #[test]
+    fn test_heading_depth() {
+        let mut document = Parser::new("# a\n\n#### b\n").parse().unwrap();
+
+        let actions = sanitize(
+            &mut document,
+            &SanitizeOptions {
+                max_heading_depth: Some(3),
+                ..options()
+            },
+        );
+
+        assert_eq!(
+            actions,
+            [SanitizeAction::ClampHeading {
+                path: vec![1],
+                depth: 4
+            }]
+        );
+
+        assert!(matches!(&document.children[1], Node::Heading(heading) if heading.depth == 3));
+    }
+
+    #[test]
+    fn test_scheme_and_host() {
+        assert_eq!(
+            scheme(" java\tscript:alert(1)").as_deref(),
+            Some("javascript")
+        );
+        assert_eq!(scheme("/path:with-colon"), None);
+        assert_eq!(scheme("./a.md"), None);
+
+        assert_eq!(host("https://user@Example.com:8080/a"), Some("Example.com"));
+        assert_eq!(host("//cdn.test?x"), Some("cdn.test"));
+        assert_eq!(host("http://[::1]/"), Some("[::1]"));
+        assert_eq!(host("images/a.png"), None);
+    }
+}
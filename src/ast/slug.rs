@@ -0,0 +1,160 @@
+//! GitHub-style heading slugs, for anchors and tables of contents.
+//!
+//! ```
+//! use markdown_rs::{ast::slug::slugger, parser::Parser};
+//!
+//! let document = Parser::new("# My Heading!\n\n## My Heading!").parse().unwrap();
+//!
+//! let slugs = slugger(&document)
+//!     .into_iter()
+//!     .map(|(slug, _)| slug)
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(slugs, ["my-heading", "my-heading-1"]);
+//! ```
+
+use alloc::collections::BTreeMap;
+
+use super::*;
+
+use crate::unicode::is_unicode_punctuation;
+
+/// Generator of slugs unique within one document.
+///
+/// Slugs that were already handed out get a `-1`, `-2`, ... suffix.
+#[derive(Clone, Debug, Default)]
+pub struct Slugger {
+    occurrences: BTreeMap<String, usize>,
+}
+
+impl Slugger {
+    /// Create new [`Slugger`] with no slug handed out yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the unique slug of `text`.
+    pub fn slug(&mut self, text: &str) -> String {
+        let base = slug(text);
+
+        let mut result = base.clone();
+
+        while self.occurrences.contains_key(&result) {
+            let count = self.occurrences.entry(base.clone()).or_default();
+
+            *count += 1;
+
+            result = format!("{}-{}", base, count);
+        }
+
+        self.occurrences.insert(result.clone(), 0);
+
+        result
+    }
+
+    /// Forget all slugs handed out so far.
+    pub fn reset(&mut self) {
+        self.occurrences.clear();
+    }
+}
+
+/// Returns the slug of `text`: lowercased, punctuation and symbols stripped (except `-` and `_`),
+/// spaces replaced by `-`. Letters of any script are kept.
+pub fn slug(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            '-' | '_' => Some(c),
+            c if c.is_control() || is_unicode_punctuation(c) => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// Returns the unique slug of every heading of `document`, in document order.
+pub fn slugger<'a, 'cx>(document: &'a Document<'cx>) -> Vec<(String, &'a Heading<'cx>)> {
+    let mut slugger = Slugger::new();
+
+    document
+        .descendants()
+        .filter_map(|node| match node {
+            Node::Heading(heading) => Some((slugger.slug(&heading_text(heading)), heading)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Plain text of the phrasing content of `heading`.
+pub(crate) fn heading_text(heading: &Heading) -> String {
+    let mut text = String::new();
+
+    for node in Descendants::new(&heading.children) {
+        match node {
+            Node::Text(Text { value, .. }) | Node::InlineCode(InlineCode { value, .. }) => {
+                text.push_str(value)
+            }
+            Node::Image(image) => text.push_str(image.alt.as_deref().unwrap_or_default()),
+            Node::ImageReference(reference) => {
+                text.push_str(reference.alt.as_deref().unwrap_or_default())
+            }
+            Node::Break(_) => text.push(' '),
+            _ => {}
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn slugs(markdown: &str) -> Vec<String> {
+        let document = Parser::new(markdown).parse().unwrap();
+
+        slugger(&document)
+            .into_iter()
+            .map(|(slug, _)| slug)
+            .collect()
+    }
+
+    #[test]
+    fn test_duplicates() {
+        assert_eq!(
+            slugs("# Intro\n\n## Intro\n\n## Intro\n\n# Intro 1\n"),
+            ["intro", "intro-1", "intro-2", "intro-1-1"]
+        );
+    }
+
+    #[test]
+    fn test_phrasing() {
+        assert_eq!(
+            slugs("# Using `Node::kind()` with [links](https://example.com)!\n"),
+            ["using-nodekind-with-links"]
+        );
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(
+            slugs("# 你好，世界\n\n## Ünïcödé Tëxt\n"),
+            ["你好世界", "ünïcödé-tëxt"]
+        );
+    }
+
+    #[test]
+    fn test_setext_and_nested() {
+        let document = Parser::new("Title\n=====\n\n> ## Quoted\n")
+            .parse()
+            .unwrap();
+
+        let headings = slugger(&document);
+
+        assert_eq!(headings[0].0, "title");
+        assert_eq!(headings[1].0, "quoted");
+        assert_eq!(headings[1].1.depth, 2);
+    }
+}
@@ -0,0 +1,224 @@
+//! Smart punctuation: curly quotes, dashes and ellipses in text values.
+//!
+//! ```
+//! use markdown_rs::{ast::smart_punctuation, parser::Parser};
+//!
+//! let mut document = Parser::new("\"Don't\" -- she said... `\"code\"`").parse().unwrap();
+//!
+//! smart_punctuation(&mut document);
+//!
+//! assert_eq!(
+//!     document.to_markdown(&Default::default()),
+//!     "“Don’t” – she said… `\"code\"`\n"
+//! );
+//! ```
+
+use super::normalize::merge_text;
+use super::*;
+
+/// Stands for content without text, such as an image, when deciding the direction of a quote.
+const ATOM: char = '\u{fffc}';
+
+/// Replaces straight quotes with curly ones, `--` and `---` with en and em dashes and `...`
+/// with an ellipsis in the [`Text`] values of `document`.
+///
+/// Code, inline code, html, urls, titles and alt texts are left alone. The direction of a
+/// quote depends on the text around it, across the nodes of a paragraph, heading or table
+/// cell: adjacent text nodes are merged first. An apostrophe inside a word (`don't`) or
+/// before a decade (`'90s`) becomes a right single quote.
+///
+/// Runs of hyphens follow CommonMark's smart punctuation extension: em dashes if the count is
+/// a multiple of 3, otherwise en dashes if it is even, otherwise as many em dashes as possible
+/// with en dashes for the rest.
+pub fn smart_punctuation(document: &mut Document) {
+    flow(&mut document.children);
+}
+
+fn flow(nodes: &mut [Node]) {
+    for node in nodes {
+        match node {
+            Node::Paragraph(_) | Node::Heading(_) | Node::TableCell(_) => {
+                if let Some(children) = node.children_mut() {
+                    phrasing(children, &mut None, None);
+                }
+            }
+            _ => {
+                if let Some(children) = node.children_mut() {
+                    flow(children);
+                }
+            }
+        }
+    }
+}
+
+/// Converts the text of phrasing content `nodes`, `prev` being the char before them and
+/// `after` the one after them.
+fn phrasing(nodes: &mut Vec<Node>, prev: &mut Option<char>, after: Option<char>) {
+    merge_text(nodes);
+
+    for index in 0..nodes.len() {
+        let next = first_char(&nodes[index + 1..]).or(after);
+
+        match &mut nodes[index] {
+            Node::Text(text) => {
+                if let Some(value) = smarten(&text.value, prev, next) {
+                    text.value = value.into();
+                }
+            }
+            Node::InlineCode(code) => *prev = code.value.chars().last().or(*prev),
+            Node::Break(_) => *prev = Some('\n'),
+            Node::Html(_) => {}
+            node => match node.children_mut() {
+                Some(children) => phrasing(children, prev, next),
+                None => *prev = Some(ATOM),
+            },
+        }
+    }
+}
+
+/// Returns the first char of phrasing content `nodes`.
+fn first_char(nodes: &[Node]) -> Option<char> {
+    nodes.iter().find_map(|node| match node {
+        Node::Text(text) => text.value.chars().next(),
+        Node::InlineCode(code) => code.value.chars().next(),
+        Node::Break(_) => Some('\n'),
+        Node::Html(_) => None,
+        node => match node.children() {
+            Some(children) => first_char(children),
+            None => Some(ATOM),
+        },
+    })
+}
+
+/// Returns `value` with smart punctuation, or `None` if it has nothing to convert.
+fn smarten(value: &str, prev: &mut Option<char>, after: Option<char>) -> Option<String> {
+    if !value.contains(['"', '\'', '-', '.']) {
+        *prev = value.chars().last().or(*prev);
+
+        return None;
+    }
+
+    let mut out = String::with_capacity(value.len());
+
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied().or(after);
+
+        match c {
+            '"' if opens(*prev) && next.is_some_and(|next| !next.is_whitespace()) => out.push('“'),
+            '"' => out.push('”'),
+            '\'' if opens(*prev) && starts_decade(chars.clone()) => out.push('’'),
+            '\'' if opens(*prev) && next.is_some_and(|next| !next.is_whitespace()) => out.push('‘'),
+            '\'' => out.push('’'),
+            '-' if chars.peek() == Some(&'-') => {
+                let mut count = 1;
+
+                while chars.next_if_eq(&'-').is_some() {
+                    count += 1;
+                }
+
+                let (em, en) = match (count % 3, count % 2) {
+                    (0, _) => (count / 3, 0),
+                    (_, 0) => (0, count / 2),
+                    (2, _) => (count / 3, 1),
+                    _ => ((count - 4) / 3, 2),
+                };
+
+                out.extend(core::iter::repeat_n('—', em));
+                out.extend(core::iter::repeat_n('–', en));
+            }
+            '.' if chars.clone().take(2).eq(['.', '.']) => {
+                chars.nth(1);
+
+                out.push('…');
+            }
+            c => out.push(c),
+        }
+
+        *prev = out.chars().last();
+    }
+
+    Some(out)
+}
+
+/// Returns true if a quote after `prev` opens.
+fn opens(prev: Option<char>) -> bool {
+    prev.is_none_or(|prev| {
+        prev.is_whitespace() || matches!(prev, '(' | '[' | '{' | '-' | '–' | '—' | '“' | '‘')
+    })
+}
+
+/// Returns true if `chars` start with a decade, such as `90s`.
+fn starts_decade(mut chars: impl Iterator<Item = char>) -> bool {
+    chars.next().is_some_and(|c| c.is_ascii_digit())
+        && chars.next().is_some_and(|c| c.is_ascii_digit())
+        && chars.next() == Some('s')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn smart(source: &str) -> String {
+        let mut document = Parser::new(source).parse().unwrap();
+
+        smart_punctuation(&mut document);
+
+        document.to_markdown(&Default::default())
+    }
+
+    #[test]
+    fn test_quotes() {
+        assert_eq!(
+            smart("\"She said 'don't', then left.\""),
+            "“She said ‘don’t’, then left.”\n"
+        );
+        assert_eq!(smart("\"'Nested'\" (\"a\")"), "“‘Nested’” (“a”)\n");
+        assert_eq!(
+            smart("Back in the '90s, 'twas rock 'n' roll."),
+            "Back in the ’90s, ‘twas rock ‘n’ roll.\n"
+        );
+        assert_eq!(smart("5' 10\" tall"), "5’ 10” tall\n");
+    }
+
+    #[test]
+    fn test_across_nodes() {
+        // the context of a quote spans emphasis, links and breaks.
+        assert_eq!(
+            smart("\"*a*\" *\"b\"*'s [\"c\"](/u \"t\")\\\n\"d\""),
+            "“*a*” *“b”*’s [“c”](/u \"t\")\\\n“d”\n"
+        );
+        // but not blocks.
+        assert_eq!(smart("a\"\n\n\"b"), "a”\n\n“b\n");
+    }
+
+    #[test]
+    fn test_dashes() {
+        assert_eq!(
+            smart("a -- b --- c - d ---- e -----\n\n---\n\nf...."),
+            "a – b — c - d –– e —–\n\n***\n\nf….\n"
+        );
+
+        let mut document = Parser::new("Prose -- text\n\n---\n").parse().unwrap();
+
+        smart_punctuation(&mut document);
+
+        assert!(matches!(document.children[1], Node::ThematicBreak(_)));
+    }
+
+    #[test]
+    fn test_untouched() {
+        let source = "`\"a\" -- b...`\n\n```\n\"c\"\n```\n\n<p>\"d\"</p>\n\n[\"e\"]: /\"f\"--";
+
+        assert_eq!(
+            smart(source),
+            Parser::new(source)
+                .parse()
+                .unwrap()
+                .to_markdown(&Default::default())
+        );
+    }
+}
@@ -0,0 +1,550 @@
+//! Source ranges of parsed nodes, for mapping a tree back to the text it was parsed from.
+//!
+//! [`Parser::parse_with_spans`](crate::parser::Parser::parse_with_spans) returns a [`Spans`]
+//! next to the document, holding the byte range of every node in the source, markers
+//! included: a heading starts at its `#`, a list item at its bullet, emphasis at its first `*`.
+//!
+//! ```
+//! use markdown_rs::{ast::Node, parser::Parser};
+//!
+//! let source = "# Title\n\nSome *text*.\n";
+//!
+//! let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+//!
+//! assert_eq!(spans.get(&[0]), Some(0..7));
+//!
+//! let emphasis = document
+//!     .descendants()
+//!     .find(|node| matches!(node, Node::Emphasis(_)))
+//!     .unwrap();
+//!
+//! assert_eq!(document.source_of(emphasis, &spans, source), Some("*text*"));
+//! ```
+
+use alloc::collections::{btree_map, BTreeMap};
+use core::{iter, ops::Range, slice};
+
+use super::links::for_each_with_path;
+use super::*;
+
+/// Source byte ranges of the nodes of a document, keyed by node position: child indices from
+/// the document root.
+///
+/// Like [`Style`](super::style::Style), positions are those of the parsed document: inserting
+/// or removing nodes shifts the positions after them, and edited nodes keep the range of the
+/// text they were parsed from.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Spans(BTreeMap<Vec<usize>, Range<usize>>);
+
+impl Spans {
+    /// Create new empty [`Spans`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the source range of the node at `path`.
+    pub fn get(&self, path: &[usize]) -> Option<Range<usize>> {
+        self.0.get(path).cloned()
+    }
+
+    /// Returns the number of nodes with a source range.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no node has a source range.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the positions and their source ranges, in document order.
+    pub fn iter(&self) -> btree_map::Iter<'_, Vec<usize>, Range<usize>> {
+        self.0.iter()
+    }
+
+    /// Sets the source range of the node at `path`.
+    #[cfg(all(feature = "json", feature = "std"))]
+    pub(crate) fn insert(&mut self, path: Vec<usize>, span: Range<usize>) {
+        self.0.insert(path, span);
+    }
+
+    /// Returns the spans of `document` given the source ranges of all its nodes in document
+    /// order, as recorded by the parser.
+    pub(crate) fn from_document_order<I>(document: &Document, spans: I) -> Self
+    where
+        I: IntoIterator<Item = Range<usize>>,
+    {
+        let mut spans = spans.into_iter();
+
+        let mut map = BTreeMap::new();
+
+        for_each_with_path(document, |path, node| {
+            let span = spans.next();
+
+            debug_assert!(span.is_some(), "no span recorded for {:?}", node);
+
+            if let Some(span) = span {
+                map.insert(path.to_vec(), span);
+            }
+        });
+
+        debug_assert!(spans.next().is_none(), "spans left after the last node");
+
+        Spans(map)
+    }
+}
+
+impl<'cx> Document<'cx> {
+    /// Returns the text of `source` that `node`, a node of this document, was parsed from.
+    ///
+    /// `spans` and `source` are those of the parse that produced this document, returns
+    /// `None` if `node` isn't part of it.
+    pub fn source_of<'s>(&self, node: &Node, spans: &Spans, source: &'s str) -> Option<&'s str> {
+        let mut found = None;
+
+        for_each_with_path(self, |path, candidate| {
+            if found.is_none() && core::ptr::eq(candidate, node) {
+                found = spans.get(path);
+            }
+        });
+
+        source.get(found?)
+    }
+
+    /// Returns the deepest node whose source range contains `offset`, with its ancestors.
+    ///
+    /// `spans` are those of the parse that produced this document. Ranges are half-open, so an
+    /// offset on the boundary of two siblings is in the second one; offsets outside of every
+    /// top-level node, between blocks or past the end of the source, return `None`. Children
+    /// are found by binary search over their ranges, a lookup takes logarithmic time in the
+    /// number of siblings at each level.
+    ///
+    /// ```
+    /// use markdown_rs::{
+    ///     ast::{resolve::definitions, Node},
+    ///     parser::Parser,
+    /// };
+    ///
+    /// let source = "[docs]\n\n[docs]: https://example.com\n";
+    ///
+    /// let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+    ///
+    /// // the cursor on `docs` of the reference.
+    /// let path = document.node_at_offset(&spans, 2).unwrap();
+    ///
+    /// assert_eq!(path.indices(), [0, 0, 0]);
+    /// assert!(matches!(path.node(), Node::Text(_)));
+    ///
+    /// let Node::LinkReference(reference) = path.ancestors()[1] else {
+    ///     panic!("not in a link reference");
+    /// };
+    ///
+    /// let definitions = definitions(&document);
+    ///
+    /// assert_eq!(definitions[&*reference.identifier].url, "https://example.com");
+    /// ```
+    pub fn node_at_offset<'a>(&'a self, spans: &Spans, offset: usize) -> Option<NodePath<'a, 'cx>> {
+        let mut path = NodePath {
+            indices: vec![],
+            nodes: vec![],
+        };
+
+        let mut children = self.children.as_slice();
+
+        while !children.is_empty() {
+            let index = first_not_before(&mut path.indices, children.len(), spans, offset);
+
+            path.indices.push(index);
+
+            let contains = spans
+                .get(&path.indices)
+                .is_some_and(|span| span.contains(&offset));
+
+            if !contains {
+                path.indices.pop();
+                break;
+            }
+
+            let node = &children[index];
+
+            path.nodes.push(node);
+
+            children = node.children().unwrap_or_default();
+        }
+
+        (!path.nodes.is_empty()).then_some(path)
+    }
+}
+
+/// Returns the index of the first of the `len` children at `path` whose span doesn't end at
+/// or before `offset`, by binary search.
+fn first_not_before(path: &mut Vec<usize>, len: usize, spans: &Spans, offset: usize) -> usize {
+    let (mut low, mut high) = (0, len);
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        path.push(middle);
+
+        let before = spans.get(path).is_some_and(|span| span.end <= offset);
+
+        path.pop();
+
+        if before {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    low.min(len.saturating_sub(1))
+}
+
+/// The chain of nodes from a top-level node down to the deepest node containing a source
+/// offset, see [`Document::node_at_offset`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodePath<'a, 'cx> {
+    indices: Vec<usize>,
+    nodes: Vec<&'a Node<'cx>>,
+}
+
+impl<'a, 'cx> NodePath<'a, 'cx> {
+    /// Returns the child indices from the document root to the node, its position in
+    /// [`Spans`].
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Returns the nodes from the top-level node to the deepest one.
+    pub fn nodes(&self) -> &[&'a Node<'cx>] {
+        &self.nodes
+    }
+
+    /// Returns the deepest node containing the offset.
+    pub fn node(&self) -> &'a Node<'cx> {
+        self.nodes[self.nodes.len() - 1]
+    }
+
+    /// Returns the ancestors of [`NodePath::node`], from the top-level node down to its parent.
+    pub fn ancestors(&self) -> &[&'a Node<'cx>] {
+        &self.nodes[..self.nodes.len() - 1]
+    }
+
+    /// Iterate over the child indices and nodes from the top-level node to the deepest one.
+    pub fn iter(&self) -> NodePathIter<'_, 'a, 'cx> {
+        self.indices.iter().copied().zip(self.nodes.iter().copied())
+    }
+}
+
+/// Iterator over the child indices and nodes of a [`NodePath`].
+pub type NodePathIter<'p, 'a, 'cx> =
+    iter::Zip<iter::Copied<slice::Iter<'p, usize>>, iter::Copied<slice::Iter<'p, &'a Node<'cx>>>>;
+
+impl<'p, 'a, 'cx> IntoIterator for &'p NodePath<'a, 'cx> {
+    type Item = (usize, &'a Node<'cx>);
+    type IntoIter = NodePathIter<'p, 'a, 'cx>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    /// Returns the source of the first node matching `f`.
+    fn source_of(source: &str, f: impl Fn(&Node) -> bool) -> Option<&str> {
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        let node = document.descendants().find(|node| f(node))?;
+
+        document.source_of(node, &spans, source)
+    }
+
+    #[test]
+    fn test_heading() {
+        let source = "intro\n\n  ## Heading *one* ##  \n";
+
+        let (_, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        assert_eq!(spans.get(&[1]), Some(9..28));
+        assert_eq!(&source[9..28], "## Heading *one* ##");
+        assert_eq!(spans.get(&[1, 0]), Some(12..20));
+        assert_eq!(spans.get(&[1, 1]), Some(20..25));
+
+        assert_eq!(
+            source_of("Title\n=====\n", |node| matches!(node, Node::Heading(_))),
+            Some("Title\n=====")
+        );
+    }
+
+    #[test]
+    fn test_nested_list_item() {
+        let source = "- a\n- b\n  1. c\n  2. d\n     e\n";
+
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        // second item of the list in the second item.
+        let path = [0, 1, 1, 1];
+
+        let item = path[1..]
+            .iter()
+            .fold(&document.children[path[0]], |node, index| {
+                &node.children().unwrap()[*index]
+            });
+
+        assert!(matches!(item, Node::ListItem(_)));
+        assert_eq!(spans.get(&path), Some(17..28));
+        assert_eq!(&source[17..28], "2. d\n     e");
+        assert_eq!(spans.get(&[0, 1, 1]), Some(10..28));
+        assert_eq!(spans.get(&[0]), Some(0..28));
+    }
+
+    #[test]
+    fn test_inline() {
+        let source = "> a **b _c_** \\* `d`  \n> [e](f) ![g *h*](i)";
+
+        let (_, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        let spans = spans
+            .iter()
+            .map(|(path, span)| (path.as_slice(), &source[span.clone()]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            [
+                (&[0][..], source),
+                (&[0, 0], &source[2..]),
+                (&[0, 0, 0], "a "),
+                (&[0, 0, 1], "**b _c_**"),
+                (&[0, 0, 1, 0], "b "),
+                (&[0, 0, 1, 1], "_c_"),
+                (&[0, 0, 1, 1, 0], "c"),
+                (&[0, 0, 2], " \\* "),
+                (&[0, 0, 3], "`d`"),
+                (&[0, 0, 4], "  \n"),
+                (&[0, 0, 5], "[e](f)"),
+                (&[0, 0, 5, 0], "e"),
+                (&[0, 0, 6], " "),
+                (&[0, 0, 7], "![g *h*](i)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nul() {
+        // each NUL is read as U+FFFD, three bytes for one in the source.
+        let source = "a\0 *b\0\0* c\n\n# \0 *d*\n";
+
+        let (_, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        let spans = spans
+            .iter()
+            .map(|(path, span)| (path.as_slice(), &source[span.clone()]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            [
+                (&[0][..], "a\0 *b\0\0* c"),
+                (&[0, 0], "a\0 "),
+                (&[0, 1], "*b\0\0*"),
+                (&[0, 1, 0], "b\0\0"),
+                (&[0, 2], " c"),
+                (&[1], "# \0 *d*"),
+                (&[1, 0], "\0 "),
+                (&[1, 1], "*d*"),
+                (&[1, 1, 0], "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_node() {
+        let source = "[a]: /b\n\n\
+            | a | b\\|c |\n|---|---|\n| <x@y.z> |\n\n\
+            ```rust\ncode\n```\n\n    indented\n\n<div>\n\n***\n\n> - [a] &amp; \\\nb";
+
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        assert_eq!(spans.len(), document.descendants().count());
+
+        let sources = spans
+            .iter()
+            .map(|(_, span)| &source[span.clone()])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            sources,
+            [
+                "[a]: /b",
+                "| a | b\\|c |\n|---|---|\n| <x@y.z> |",
+                "| a | b\\|c |",
+                "a",
+                "a",
+                "b\\|c",
+                "b\\|c",
+                "| <x@y.z> |",
+                "<x@y.z>",
+                "<x@y.z>",
+                "x@y.z",
+                "",
+                "```rust\ncode\n```",
+                "indented",
+                "<div>",
+                "***",
+                "> - [a] &amp; \\\nb",
+                "- [a] &amp; \\\nb",
+                "- [a] &amp; \\\nb",
+                "[a] &amp; \\\nb",
+                "[a]",
+                "a",
+                " &amp; ",
+                "\\\n",
+                "b",
+            ]
+        );
+    }
+
+    /// Returns the kinds of the nodes at `offset`, top-level node first.
+    fn kinds_at(source: &str, offset: usize) -> Option<Vec<NodeKind>> {
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        let path = document.node_at_offset(&spans, offset)?;
+
+        for (depth, (index, node)) in path.iter().enumerate() {
+            assert_eq!(index, path.indices()[depth]);
+            assert!(core::ptr::eq(node, path.nodes()[depth]));
+        }
+
+        Some(path.nodes().iter().map(|node| node.kind()).collect())
+    }
+
+    #[test]
+    fn test_node_at_offset() {
+        let source = "# Title\n\n> a **b _c_ d**\n";
+
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        // `c`, in emphasis in strong.
+        let path = document.node_at_offset(&spans, 18).unwrap();
+
+        assert_eq!(path.indices(), [1, 0, 1, 1, 0]);
+        assert_eq!(path.node().value(), Some("c"));
+        assert_eq!(
+            path.ancestors()
+                .iter()
+                .map(|node| node.kind())
+                .collect::<Vec<_>>(),
+            [
+                NodeKind::Blockquote,
+                NodeKind::Paragraph,
+                NodeKind::Strong,
+                NodeKind::Emphasis
+            ]
+        );
+
+        // the markers of a node belong to it.
+        assert_eq!(
+            kinds_at(source, 17),
+            Some(vec![
+                NodeKind::Blockquote,
+                NodeKind::Paragraph,
+                NodeKind::Strong,
+                NodeKind::Emphasis
+            ])
+        );
+        assert_eq!(kinds_at(source, 9), Some(vec![NodeKind::Blockquote]));
+        assert_eq!(kinds_at(source, 0), Some(vec![NodeKind::Heading]));
+        assert_eq!(
+            kinds_at(source, 3),
+            Some(vec![NodeKind::Heading, NodeKind::Text])
+        );
+    }
+
+    #[test]
+    fn test_node_at_offset_boundaries() {
+        let source = "a *b*c\n\nd";
+
+        // `a ` ends where the emphasis starts.
+        assert_eq!(
+            kinds_at(source, 2),
+            Some(vec![NodeKind::Paragraph, NodeKind::Emphasis])
+        );
+        assert_eq!(
+            kinds_at(source, 5),
+            Some(vec![NodeKind::Paragraph, NodeKind::Text])
+        );
+
+        // between blocks, and past the end.
+        assert_eq!(kinds_at(source, 7), None);
+        assert_eq!(
+            kinds_at(source, 8),
+            Some(vec![NodeKind::Paragraph, NodeKind::Text])
+        );
+        assert_eq!(kinds_at(source, 9), None);
+        assert_eq!(kinds_at(source, 100), None);
+        assert_eq!(kinds_at("", 0), None);
+    }
+
+    #[test]
+    fn test_spans_nest() {
+        let sources = [
+            "# Title\n\n> a **b _c_ d**\n",
+            "- a\n- b\n  1. c\n  2. d\n     e\n",
+            "| a | *b* |\n|---|---|\n| [c](d) | `e` |\n",
+            "[a]: /b\n\n> - [a] &amp; \\\nb ![c *d*](e)\n\n```\nf\n```\n",
+            "Title\n=====\n\n<div>\n\n***\n",
+        ];
+
+        for source in sources {
+            let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+            for (path, span) in spans.iter() {
+                // inside the parent.
+                if let Some(parent) = spans.get(&path[..path.len() - 1]) {
+                    assert!(
+                        parent.start <= span.start && span.end <= parent.end,
+                        "{:?} {:?} outside of {:?} in {:?}",
+                        path,
+                        span,
+                        parent,
+                        source
+                    );
+                }
+
+                // after the previous sibling.
+                if let Some(index) = path.last().unwrap().checked_sub(1) {
+                    let mut previous = path.clone();
+
+                    *previous.last_mut().unwrap() = index;
+
+                    let previous = spans.get(&previous).unwrap();
+
+                    assert!(
+                        previous.end <= span.start,
+                        "{:?} overlaps in {:?}",
+                        path,
+                        source
+                    );
+                }
+
+                // found at every offset it doesn't leave to a child.
+                for offset in span.clone() {
+                    let found = document.node_at_offset(&spans, offset).unwrap();
+
+                    assert!(
+                        found.indices().starts_with(path),
+                        "{:?} at {}",
+                        path,
+                        offset
+                    );
+                }
+            }
+        }
+    }
+}
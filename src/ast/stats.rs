@@ -0,0 +1,216 @@
+//! Document statistics: word count, reading time and node counts.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::stats::{Stats, StatsOptions},
+//!     parser::Parser,
+//! };
+//!
+//! let document = Parser::new("# Hello\n\nA *short* post.").parse().unwrap();
+//!
+//! let stats = Stats::of(&document, &StatsOptions::default());
+//!
+//! assert_eq!(stats.words, 4);
+//! assert_eq!(stats.headings, [1, 0, 0, 0, 0, 0]);
+//! ```
+
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::*;
+
+/// Options of [`Stats::of`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StatsOptions {
+    /// Count the words of code blocks, defaults to `false`. Inline code is always counted.
+    pub include_code: bool,
+    /// Reading speed used to estimate [`Stats::reading_time`], defaults to 200.
+    pub words_per_minute: u32,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        StatsOptions {
+            include_code: false,
+            words_per_minute: 200,
+        }
+    }
+}
+
+/// Statistics of a [`Document`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Number of words, following Unicode word segmentation: each CJK ideograph is a word.
+    pub words: usize,
+    /// Number of non-whitespace characters of the counted text.
+    pub characters: usize,
+    /// Number of nodes of each kind, the document itself excluded.
+    pub nodes: BTreeMap<NodeKind, usize>,
+    /// Number of headings of each depth, `headings[0]` counts the h1.
+    pub headings: [usize; 6],
+    /// Number of links and link references.
+    pub links: usize,
+    /// Number of images and image references.
+    pub images: usize,
+    /// Estimated reading time, rounded up to the second.
+    pub reading_time: Duration,
+}
+
+impl Stats {
+    /// Compute the statistics of `document`.
+    pub fn of(document: &Document, options: &StatsOptions) -> Self {
+        let mut collector = StatsCollector {
+            options,
+            stats: Stats::default(),
+            text: String::new(),
+        };
+
+        for child in &document.children {
+            walk(child, &mut collector);
+        }
+
+        let mut stats = collector.stats;
+
+        stats.words = collector.text.unicode_words().count();
+        stats.characters = collector
+            .text
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .count();
+
+        let words_per_minute = options.words_per_minute.max(1) as u64;
+
+        stats.reading_time =
+            Duration::from_secs((stats.words as u64 * 60).div_ceil(words_per_minute));
+
+        stats
+    }
+
+    /// Returns the number of nodes of `kind`.
+    pub fn count(&self, kind: NodeKind) -> usize {
+        self.nodes.get(&kind).copied().unwrap_or_default()
+    }
+}
+
+struct StatsCollector<'o> {
+    options: &'o StatsOptions,
+    stats: Stats,
+    /// Counted text, blocks separated by a newline so words don't run across them.
+    text: String,
+}
+
+impl<'o> Visitor for StatsCollector<'o> {
+    fn visit_heading(&mut self, node: &Heading) {
+        if let Some(count) = self
+            .stats
+            .headings
+            .get_mut(usize::from(node.depth).wrapping_sub(1))
+        {
+            *count += 1;
+        }
+    }
+
+    fn visit_text(&mut self, node: &Text) {
+        self.text.push_str(&node.value);
+    }
+
+    fn visit_inline_code(&mut self, node: &InlineCode) {
+        self.text.push_str(&node.value);
+    }
+
+    fn visit_code(&mut self, node: &Code) {
+        if self.options.include_code {
+            self.text.push_str(&node.value);
+        }
+    }
+
+    fn visit_break(&mut self, _: &Break) {
+        self.text.push('\n');
+    }
+
+    fn visit_link(&mut self, _: &Link) {
+        self.stats.links += 1;
+    }
+
+    fn visit_link_reference(&mut self, _: &LinkReference) {
+        self.stats.links += 1;
+    }
+
+    fn visit_image(&mut self, _: &Image) {
+        self.stats.images += 1;
+    }
+
+    fn visit_image_reference(&mut self, _: &ImageReference) {
+        self.stats.images += 1;
+    }
+
+    fn leave(&mut self, node: &Node) {
+        *self.stats.nodes.entry(node.kind()).or_default() += 1;
+
+        if matches!(
+            node,
+            Node::Paragraph(_) | Node::Heading(_) | Node::Code(_) | Node::TableCell(_)
+        ) {
+            self.text.push('\n');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "# Getting started\n\n\
+        Install the *crate* with `cargo add`, then read [the docs][docs].\n\n\
+        ## Example\n\n\
+        ```rust\nlet document = parse(source);\n```\n\n\
+        ![diagram](diagram.png)\n\n\
+        [docs]: https://docs.rs\n";
+
+    #[test]
+    fn test_counts() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let stats = Stats::of(&document, &StatsOptions::default());
+
+        // getting started / install the crate with cargo add then read the docs / example
+        assert_eq!(stats.words, 13);
+        assert_eq!(stats.characters, 65);
+        assert_eq!(stats.headings, [1, 1, 0, 0, 0, 0]);
+        assert_eq!(stats.links, 1);
+        assert_eq!(stats.images, 1);
+        assert_eq!(stats.count(NodeKind::Paragraph), 2);
+        assert_eq!(stats.count(NodeKind::Text), 8);
+        assert_eq!(stats.count(NodeKind::Definition), 1);
+        assert_eq!(stats.count(NodeKind::Document), 0);
+        assert_eq!(stats.reading_time, Duration::from_secs(4));
+
+        let stats = Stats::of(
+            &document,
+            &StatsOptions {
+                include_code: true,
+                words_per_minute: 1,
+            },
+        );
+
+        assert_eq!(stats.words, 17);
+        assert_eq!(stats.reading_time, Duration::from_secs(17 * 60));
+    }
+
+    #[test]
+    fn test_cjk() {
+        let document = Parser::new("# 你好\n\n今天天气很好。Rust 很快！\n")
+            .parse()
+            .unwrap();
+
+        let stats = Stats::of(&document, &StatsOptions::default());
+
+        // 你 好 / 今 天 天 气 很 好 Rust 很 快
+        assert_eq!(stats.words, 11);
+        assert_eq!(stats.characters, 16);
+    }
+}
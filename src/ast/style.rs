@@ -0,0 +1,278 @@
+//! Markup choices the tree doesn't record, for serializing parsed documents as they were written.
+//!
+//! [`Parser::parse_with_style`](crate::parser::Parser::parse_with_style) returns a [`Style`]
+//! next to the document, which
+//! [`Document::to_markdown_with_style`](crate::ast::Document::to_markdown_with_style) follows
+//! where the serializer would otherwise use its own defaults.
+//!
+//! ```
+//! use markdown_rs::{ast::links, parser::Parser};
+//!
+//! let source = "Setext _title_\n==============\n\n- [old](/old)\n\n~~~~rust\nfn main() {}\n~~~~\n";
+//!
+//! let (mut document, style) = Parser::new(source).parse_with_style().unwrap();
+//!
+//! links::rewrite(&mut document, |url| (url == "/old").then(|| "/new".to_owned()));
+//!
+//! assert_eq!(
+//!     document.to_markdown_with_style(&Default::default(), &style),
+//!     source.replace("/old", "/new")
+//! );
+//! ```
+
+use alloc::collections::{btree_map, BTreeMap};
+
+use super::links::for_each_with_path;
+use super::*;
+use crate::to_markdown::{LineEnding, ListNumbering};
+
+/// Markup of one node, as written in the source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum NodeStyle {
+    /// Bullet of an unordered [`List`], `*`, `-` or `+`, or delimiter of an ordered one, `.`
+    /// or `)`.
+    List(char),
+    /// Marker of [`Emphasis`] or [`Strong`], `*` or `_`.
+    Attention(char),
+    /// Whether a [`Heading`] was written with an underline rather than `#` signs.
+    Heading {
+        /// Written with an underline.
+        setext: bool,
+    },
+    /// Fences of [`Code`], `None` for indented code.
+    Code(Option<Fence>),
+}
+
+/// Opening fence of fenced [`Code`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct Fence {
+    /// `` ` `` or `~`.
+    pub marker: char,
+    /// Number of markers, at least 3.
+    pub size: usize,
+    /// Spaces before the opening fence, at most 3.
+    pub indent: usize,
+}
+
+/// The [`NodeStyle`]s of a document, keyed by node position: child indices from the document
+/// root.
+///
+/// Positions are those of the parsed document: transforms that keep the structure, such as
+/// rewriting urls or text, keep the style valid, while inserting or removing nodes shifts the
+/// positions after them.
+///
+/// The style also holds the line ending of the source, which
+/// [`LineEnding::Preserve`] writes, and the numbering of its ordered lists, which
+/// [`ListNumbering::Preserve`] writes.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Style {
+    nodes: BTreeMap<Vec<usize>, NodeStyle>,
+    line_ending: Option<LineEnding>,
+    numberings: BTreeMap<Vec<usize>, ListNumbering>,
+}
+
+impl Style {
+    /// Create new empty [`Style`], the serializer then uses its defaults everywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the style of the node at `path`.
+    pub fn get(&self, path: &[usize]) -> Option<&NodeStyle> {
+        self.nodes.get(path)
+    }
+
+    /// Set the style of the node at `path`, returns the previous one.
+    pub fn insert(&mut self, path: Vec<usize>, style: NodeStyle) -> Option<NodeStyle> {
+        self.nodes.insert(path, style)
+    }
+
+    /// Forget the style of the node at `path`, returns it.
+    pub fn remove(&mut self, path: &[usize]) -> Option<NodeStyle> {
+        self.nodes.remove(path)
+    }
+
+    /// Returns the number of styled nodes.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if no node is styled, and no list numbered.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.numberings.is_empty()
+    }
+
+    /// Returns the line ending of the source, [`LineEnding::Lf`] or [`LineEnding::CrLf`], if
+    /// known.
+    pub fn line_ending(&self) -> Option<LineEnding> {
+        self.line_ending
+    }
+
+    /// Set the line ending of the source.
+    pub fn set_line_ending(&mut self, line_ending: Option<LineEnding>) {
+        self.line_ending = line_ending;
+    }
+
+    /// Returns the numbering of the ordered list at `path`: [`ListNumbering::One`] if its items
+    /// repeat the number of the first one, [`ListNumbering::Number`] otherwise, if known.
+    pub fn numbering(&self, path: &[usize]) -> Option<ListNumbering> {
+        self.numberings.get(path).copied()
+    }
+
+    /// Set the numbering of the ordered list at `path`, returns the previous one.
+    pub fn set_numbering(
+        &mut self,
+        path: Vec<usize>,
+        numbering: ListNumbering,
+    ) -> Option<ListNumbering> {
+        self.numberings.insert(path, numbering)
+    }
+
+    /// Iterate over the styled positions, in document order.
+    pub fn iter(&self) -> btree_map::Iter<'_, Vec<usize>, NodeStyle> {
+        self.nodes.iter()
+    }
+
+    /// Returns the style of `document` given the styles of its lists, attention, headings and
+    /// code, and the numberings of its ordered lists, in document order, as recorded by the
+    /// parser: `None` for nodes built by parser extensions, whose markup is unknown.
+    pub(crate) fn from_document_order<I, N>(document: &Document, styles: I, numberings: N) -> Self
+    where
+        I: IntoIterator<Item = Option<NodeStyle>>,
+        N: IntoIterator<Item = Option<ListNumbering>>,
+    {
+        let mut styles = styles.into_iter();
+
+        let mut numberings = numberings.into_iter();
+
+        let mut style = Style::new();
+
+        for_each_with_path(document, |path, node| {
+            if !is_styled(node) {
+                return;
+            }
+
+            if matches!(node, Node::List(list) if list.ordered == Some(true)) {
+                if let Some(numbering) = numberings.next().flatten() {
+                    style.set_numbering(path.to_vec(), numbering);
+                }
+            }
+
+            if let Some(next) = styles.next().flatten() {
+                debug_assert!(next.applies_to(node), "{:?} recorded for {:?}", next, node);
+
+                style.insert(path.to_vec(), next);
+            }
+        });
+
+        debug_assert!(styles.next().is_none(), "styles left after the last node");
+        debug_assert!(
+            numberings.next().is_none(),
+            "numberings left after the last list"
+        );
+
+        style
+    }
+}
+
+/// Returns true if `node` is of a kind with a [`NodeStyle`].
+pub(crate) fn is_styled(node: &Node) -> bool {
+    matches!(
+        node,
+        Node::List(_) | Node::Emphasis(_) | Node::Strong(_) | Node::Heading(_) | Node::Code(_)
+    )
+}
+
+impl NodeStyle {
+    /// Returns true if this style is one of `node`.
+    pub fn applies_to(&self, node: &Node) -> bool {
+        matches!(
+            (self, node),
+            (NodeStyle::List(_), Node::List(_))
+                | (NodeStyle::Attention(_), Node::Emphasis(_) | Node::Strong(_))
+                | (NodeStyle::Heading { .. }, Node::Heading(_))
+                | (NodeStyle::Code(_), Node::Code(_))
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn round_trip(source: &str) -> String {
+        let (document, style) = Parser::new(source).parse_with_style().unwrap();
+
+        document.to_markdown_with_style(&Default::default(), &style)
+    }
+
+    #[test]
+    fn test_paths() {
+        let (_, style) = Parser::new("# a _b_\n\n- ![*c*](d) __e__\n\n  |f|\n  |-|\n  |*g*|")
+            .parse_with_style()
+            .unwrap();
+
+        assert_eq!(
+            style.iter().collect::<Vec<_>>(),
+            [
+                (&vec![0], &NodeStyle::Heading { setext: false }),
+                (&vec![0, 1], &NodeStyle::Attention('_')),
+                (&vec![1], &NodeStyle::List('-')),
+                // emphasis in alt text is gone.
+                (&vec![1, 0, 0, 2], &NodeStyle::Attention('_')),
+                (&vec![1, 0, 1, 1, 0, 0], &NodeStyle::Attention('*')),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        assert_eq!(
+            round_trip("  ~~~\n  a\n b\n\n  ~~~\n"),
+            "  ~~~\n  a\n  b\n\n  ~~~\n"
+        );
+        assert_eq!(round_trip("`````\n```\n`````\n"), "`````\n```\n`````\n");
+
+        let (mut document, style) = Parser::new("    code\n").parse_with_style().unwrap();
+
+        // indented code can't start with a blank line, nor have an info string.
+        let Node::Code(code) = &mut document.children[0] else {
+            panic!("expect code");
+        };
+
+        code.value = "\na".into();
+
+        assert_eq!(
+            document.to_markdown_with_style(&Default::default(), &style),
+            "```\n\na\n```\n"
+        );
+    }
+
+    #[test]
+    fn test_changed_tree() {
+        let (mut document, mut style) = Parser::new("- a\n\n* b\n\n_c_\n")
+            .parse_with_style()
+            .unwrap();
+
+        // sibling lists with the same bullet would merge, emphasis now touches a word.
+        style.insert(vec![1], NodeStyle::List('-'));
+        document.children[2]
+            .children_mut()
+            .unwrap()
+            .push(Node::Text(Text {
+                value: "d".into(),
+                data: None,
+            }));
+
+        assert_eq!(
+            document.to_markdown_with_style(&Default::default(), &style),
+            "- a\n\n* b\n\n*c*d\n"
+        );
+        assert_eq!(
+            document.to_markdown_with_style(&Default::default(), &Style::new()),
+            document.to_markdown(&Default::default())
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Plain text extraction, for search indexing and excerpts.
+//!
+//! ```
+//! use markdown_rs::{ast::text::TextOptions, parser::Parser};
+//!
+//! let document = Parser::new("# Hello *world*\n\nSee [docs](https://example.com).")
+//!     .parse()
+//!     .unwrap();
+//!
+//! assert_eq!(document.to_text(&TextOptions::default()), "Hello world\nSee docs.");
+//! ```
+
+use super::*;
+
+/// Options of [`to_text`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextOptions {
+    /// Include the value of code blocks, defaults to `true`.
+    ///
+    /// Search indexes usually want code, excerpts usually don't.
+    pub include_code: bool,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        TextOptions { include_code: true }
+    }
+}
+
+impl<'cx> Node<'cx> {
+    /// Returns the plain text content of this node, see [`to_text`].
+    pub fn to_text(&self, options: &TextOptions) -> String {
+        to_text(self, options)
+    }
+}
+
+impl<'cx> Document<'cx> {
+    /// Returns the plain text content of this document, see [`to_text`].
+    pub fn to_text(&self, options: &TextOptions) -> String {
+        let mut collector = TextCollector::new(options);
+
+        for child in &self.children {
+            walk(child, &mut collector);
+        }
+
+        collector.out
+    }
+}
+
+/// Returns the textual content of `node` with all markup dropped.
+///
+/// Text, inline code and code values are concatenated, images contribute their alt text and
+/// html and definitions are skipped. Blocks are separated by a newline, table cells by a space,
+/// and soft and hard line breaks are kept as newlines.
+pub fn to_text(node: &Node, options: &TextOptions) -> String {
+    let mut collector = TextCollector::new(options);
+
+    walk(node, &mut collector);
+
+    collector.out
+}
+
+struct TextCollector<'o> {
+    options: &'o TextOptions,
+    out: String,
+    /// Separator to insert before the next text, set when leaving a block or cell.
+    separator: Option<char>,
+}
+
+impl<'o> TextCollector<'o> {
+    fn new(options: &'o TextOptions) -> Self {
+        TextCollector {
+            options,
+            out: String::new(),
+            separator: None,
+        }
+    }
+
+    fn push(&mut self, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+
+        if let Some(separator) = self.separator.take() {
+            if !self.out.is_empty() {
+                self.out.push(separator);
+            }
+        }
+
+        self.out.push_str(value);
+    }
+}
+
+impl<'o> Visitor for TextCollector<'o> {
+    fn visit_text(&mut self, node: &Text) {
+        self.push(&node.value);
+    }
+
+    fn visit_inline_code(&mut self, node: &InlineCode) {
+        self.push(&node.value);
+    }
+
+    fn visit_code(&mut self, node: &Code) {
+        if self.options.include_code {
+            self.push(&node.value);
+        }
+    }
+
+    fn visit_break(&mut self, _: &Break) {
+        self.push("\n");
+    }
+
+    fn visit_image(&mut self, node: &Image) {
+        self.push(node.alt.as_deref().unwrap_or_default());
+    }
+
+    fn visit_image_reference(&mut self, node: &ImageReference) {
+        self.push(node.alt.as_deref().unwrap_or_default());
+    }
+
+    fn leave(&mut self, node: &Node) {
+        match node {
+            Node::TableCell(_) => {
+                self.separator.get_or_insert(' ');
+            }
+            Node::Paragraph(_)
+            | Node::Heading(_)
+            | Node::Code(_)
+            | Node::ThematicBreak(_)
+            | Node::Blockquote(_)
+            | Node::List(_)
+            | Node::ListItem(_)
+            | Node::Table(_)
+            | Node::TableRow(_)
+            | Node::FootnoteDefinition(_) => self.separator = Some('\n'),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "# Read [the *fine* manual](https://example.com)\n\n\
+        Install it:\n\n\
+        ```sh\ncargo add markdown-rs\n```\n\n\
+        - ![logo](logo.png) and `code`\n- <b>html</b> is skipped\n\n\
+        [manual]: https://example.com\n";
+
+    #[test]
+    fn test_include_code() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        assert_eq!(
+            document.to_text(&TextOptions::default()),
+            "Read the fine manual\nInstall it:\ncargo add markdown-rs\nlogo and code\nhtml is skipped"
+        );
+    }
+
+    #[test]
+    fn test_exclude_code() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        assert_eq!(
+            document.to_text(&TextOptions {
+                include_code: false
+            }),
+            "Read the fine manual\nInstall it:\nlogo and code\nhtml is skipped"
+        );
+    }
+
+    #[test]
+    fn test_node() {
+        let document = Parser::new("a *b* c\nd").parse().unwrap();
+
+        assert_eq!(
+            document.children[0].to_text(&TextOptions::default()),
+            "a b c\nd"
+        );
+    }
+}
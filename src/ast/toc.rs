@@ -0,0 +1,240 @@
+//! Table of contents generation.
+//!
+//! ```
+//! use markdown_rs::{ast::toc, parser::Parser};
+//!
+//! let mut document = Parser::new("[TOC]\n\n# Alpha\n\n## Bravo").parse().unwrap();
+//!
+//! assert!(toc::replace_placeholder(&mut document, 6));
+//! ```
+
+use super::slug::{heading_text, slugger};
+use super::*;
+
+/// Text of the paragraph [`replace_placeholder`] replaces.
+pub const PLACEHOLDER: &str = "[TOC]";
+
+/// Build a table of contents of `document`: a nested [`List`] mirroring its heading hierarchy,
+/// each item linking to the heading slug (see [`slugger`]).
+///
+/// Headings deeper than `max_depth` are ignored. A heading skipping levels (an h4 directly
+/// under an h2) is nested one level below its closest shallower heading.
+pub fn build<'cx>(document: &Document<'cx>, max_depth: usize) -> List<'cx> {
+    let entries = slugger(document)
+        .into_iter()
+        .filter(|(_, heading)| usize::from(heading.depth) <= max_depth)
+        .collect::<Vec<_>>();
+
+    nest(&entries)
+}
+
+fn nest<'cx>(entries: &[(String, &Heading<'cx>)]) -> List<'cx> {
+    let mut list = List::new(false, None);
+
+    list.spread = Some(false);
+
+    let mut index = 0;
+
+    while index < entries.len() {
+        let (slug, heading) = &entries[index];
+
+        // deeper headings up to the next sibling are nested into this item.
+        let end = entries[index + 1..]
+            .iter()
+            .position(|(_, next)| next.depth <= heading.depth)
+            .map(|offset| index + 1 + offset)
+            .unwrap_or(entries.len());
+
+        let mut item = ListItem::new(None);
+
+        item.spread = Some(false);
+
+        item.children.push(Node::Paragraph(Paragraph {
+            children: vec![Node::Link(Box::new(Link {
+                children: link_children(heading),
+                url: format!("#{}", slug).into(),
+                title: None,
+                data: None,
+            }))],
+            data: None,
+        }));
+
+        if index + 1 < end {
+            item.children
+                .push(Node::List(nest(&entries[index + 1..end])));
+        }
+
+        list.children.push(Node::ListItem(item));
+
+        index = end;
+    }
+
+    list
+}
+
+/// Phrasing content of `heading` usable inside a link: links are unwrapped and
+/// footnote references dropped.
+fn link_children<'cx>(heading: &Heading<'cx>) -> Vec<Node<'cx>> {
+    fn strip<'cx>(nodes: &[Node<'cx>], out: &mut Vec<Node<'cx>>) {
+        for node in nodes {
+            match node {
+                Node::Link(link) => strip(&link.children, out),
+                Node::LinkReference(reference) => strip(&reference.children, out),
+                Node::FootnoteReference(_) => {}
+                node => out.push(node.clone()),
+            }
+        }
+    }
+
+    let mut children = vec![];
+
+    strip(&heading.children, &mut children);
+
+    if children.is_empty() {
+        children.push(Node::Text(Text {
+            value: heading_text(heading).into(),
+            data: None,
+        }));
+    }
+
+    children
+}
+
+/// Replace the first paragraph made of exactly [`PLACEHOLDER`] with the table of contents
+/// [`build`] generates, returns false if no placeholder was found.
+pub fn replace_placeholder(document: &mut Document<'_>, max_depth: usize) -> bool {
+    let mut replacer = Replacer {
+        list: Some(build(document, max_depth)),
+    };
+
+    for child in &mut document.children {
+        walk_mut(child, &mut replacer);
+    }
+
+    replacer.list.is_none()
+}
+
+struct Replacer<'cx> {
+    list: Option<List<'cx>>,
+}
+
+impl<'cx> VisitorMut<'cx> for Replacer<'cx> {
+    fn visit_paragraph(&mut self, node: &mut Paragraph<'cx>) -> Transform<'cx> {
+        let is_placeholder = matches!(
+            node.children.as_slice(),
+            [Node::Text(Text { value, .. })] if value.trim() == PLACEHOLDER
+        );
+
+        match self.list.take() {
+            Some(list) if is_placeholder => Transform::Replace(Node::List(list)),
+            list => {
+                self.list = list;
+
+                Transform::Keep
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    /// Flatten `list` into `(depth, url)` pairs.
+    fn outline(list: &List, depth: usize, out: &mut Vec<(usize, String)>) {
+        for item in &list.children {
+            let Node::ListItem(item) = item else {
+                panic!("expect list item");
+            };
+
+            for child in &item.children {
+                match child {
+                    Node::Paragraph(Paragraph { children, .. }) => {
+                        let [Node::Link(link)] = children.as_slice() else {
+                            panic!("expect a single link");
+                        };
+
+                        out.push((depth, link.url.to_string()));
+                    }
+                    Node::List(list) => outline(list, depth + 1, out),
+                    node => panic!("unexpected {:?}", node),
+                }
+            }
+        }
+    }
+
+    const MARKDOWN: &str =
+        "# Guide\n\n## Install\n\n#### Windows\n\n## Usage\n\n### Install\n\n##### Deep\n\n# FAQ\n";
+
+    #[test]
+    fn test_build() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let mut entries = vec![];
+
+        outline(&build(&document, 4), 0, &mut entries);
+
+        assert_eq!(
+            entries,
+            [
+                (0, "#guide".to_owned()),
+                (1, "#install".to_owned()),
+                (2, "#windows".to_owned()),
+                (1, "#usage".to_owned()),
+                (2, "#install-1".to_owned()),
+                (0, "#faq".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_link_children() {
+        let document = Parser::new("## Use [`walk`](https://docs.rs) *now*\n")
+            .parse()
+            .unwrap();
+
+        let list = build(&document, 6);
+
+        let Node::ListItem(item) = &list.children[0] else {
+            panic!("expect list item");
+        };
+
+        let Node::Paragraph(Paragraph { children, .. }) = &item.children[0] else {
+            panic!("expect paragraph");
+        };
+
+        let Node::Link(link) = &children[0] else {
+            panic!("expect link");
+        };
+
+        assert_eq!(link.url, "#use-walk-now");
+        assert_eq!(link.children.len(), 4);
+        assert!(matches!(link.children[1], Node::InlineCode(_)));
+        assert!(matches!(link.children[3], Node::Emphasis(_)));
+    }
+
+    #[test]
+    fn test_replace_placeholder() {
+        let markdown = format!("[TOC]\n\n{}", MARKDOWN);
+
+        let mut document = Parser::new(markdown.as_str()).parse().unwrap();
+
+        assert!(replace_placeholder(&mut document, 2));
+
+        let Node::List(list) = &document.children[0] else {
+            panic!("expect toc list");
+        };
+
+        let mut entries = vec![];
+
+        outline(list, 0, &mut entries);
+
+        assert_eq!(entries.len(), 4);
+
+        let mut document = Parser::new("# No placeholder\n").parse().unwrap();
+
+        assert!(!replace_placeholder(&mut document, 2));
+    }
+}
@@ -0,0 +1,489 @@
+//! Transforms for composing documents, heading level shifting and embedding, and for emoji
+//! shortcodes.
+//!
+//! ```
+//! use markdown_rs::parser::Parser;
+//!
+//! let mut book = Parser::new("# Book\n\n## Chapter\n\n[a]: /book").parse().unwrap();
+//!
+//! let chapter = Parser::new("# Intro\n\nSee [a].\n\n[a]: /chapter").parse().unwrap();
+//!
+//! // the h1 of the chapter becomes an h3 under its section.
+//! let collisions = book.embed(2, chapter, 2).unwrap();
+//!
+//! assert_eq!(collisions, ["a"]);
+//! assert_eq!(
+//!     book.to_string(),
+//!     "root[6]\n  heading[1] depth=1\n    text \"Book\"\n  heading[1] depth=2\n    \
+//!      text \"Chapter\"\n  heading[1] depth=3\n    text \"Intro\"\n  paragraph[3]\n    \
+//!      text \"See \"\n    linkReference[1] identifier=\"a\" label=\"a\" referenceType=shortcut\n      \
+//!      text \"a\"\n    text \".\"\n  definition identifier=\"a\" label=\"a\" url=\"/chapter\"\n  \
+//!      definition identifier=\"a\" label=\"a\" url=\"/book\"\n"
+//! );
+//! ```
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use super::*;
+
+#[cfg(feature = "emoji")]
+mod gemoji;
+
+/// What [`shift_headings`] does with headings shifted deeper than 6.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Overflow {
+    /// Clamp the depth to 6.
+    #[default]
+    Clamp,
+    /// Turn the heading into a paragraph holding its content in strong emphasis.
+    Strong,
+}
+
+/// Shifts the depth of every heading of `document` by `delta`, nested ones included.
+///
+/// Depths are clamped to 1 at the top, headings shifted deeper than 6 are handled according
+/// to `overflow`. Returns the number of headings changed.
+pub fn shift_headings(document: &mut Document, delta: i8, overflow: Overflow) -> usize {
+    if delta == 0 {
+        return 0;
+    }
+
+    let mut shifter = Shifter {
+        delta,
+        overflow,
+        count: 0,
+    };
+
+    for child in &mut document.children {
+        walk_mut(child, &mut shifter);
+    }
+
+    shifter.count
+}
+
+struct Shifter {
+    delta: i8,
+    overflow: Overflow,
+    count: usize,
+}
+
+impl<'cx> VisitorMut<'cx> for Shifter {
+    fn visit_heading(&mut self, node: &mut Heading<'cx>) -> Transform<'cx> {
+        let depth = node
+            .depth
+            .saturating_add_signed(self.delta)
+            .max(*Heading::DEPTHS.start());
+
+        if depth == node.depth {
+            return Transform::Keep;
+        }
+
+        self.count += 1;
+
+        if Heading::DEPTHS.contains(&depth) || self.overflow == Overflow::Clamp {
+            node.depth = depth.min(*Heading::DEPTHS.end());
+
+            return Transform::Keep;
+        }
+
+        Transform::Replace(Node::Paragraph(Paragraph {
+            children: vec![Node::Strong(Strong {
+                children: core::mem::take(&mut node.children),
+                data: None,
+            })],
+            data: node.data.take(),
+        }))
+    }
+}
+
+impl<'cx> Document<'cx> {
+    /// Splices the children of `other` into this document at `at_index`, with its headings
+    /// shifted by `shift` (see [`shift_headings`], overflowing depths are clamped).
+    ///
+    /// Definitions of `other` come along. Returns the normalized identifiers defined by both
+    /// documents, sorted: as the first definition of an identifier wins, references to them
+    /// may resolve to the other document's definition.
+    pub fn embed(
+        &mut self,
+        at_index: usize,
+        mut other: Document<'cx>,
+        shift: i8,
+    ) -> AstResult<Vec<String>> {
+        let len = self.children.len();
+
+        if at_index > len {
+            return Err(AstError::IndexOutOfBounds {
+                index: at_index,
+                len,
+            });
+        }
+
+        let ours = identifiers(self);
+
+        let collisions = identifiers(&other)
+            .intersection(&ours)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        shift_headings(&mut other, shift, Overflow::Clamp);
+
+        let children = core::mem::take(&mut other.children);
+
+        self.children.splice(at_index..at_index, children);
+
+        Ok(collisions)
+    }
+}
+
+/// Returns the normalized identifiers of the definitions of `document`.
+fn identifiers(document: &Document) -> BTreeSet<String> {
+    document
+        .descendants()
+        .filter_map(|node| match node {
+            Node::Definition(definition) => Some(normalize_identifier(&definition.identifier)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Emojis by shortcode, the name between the colons of `:tada:`, see [`emoji`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmojiMap {
+    emojis: BTreeMap<Cow<'static, str>, Cow<'static, str>>,
+}
+
+impl EmojiMap {
+    /// Create new empty [`EmojiMap`].
+    pub fn new() -> Self {
+        EmojiMap {
+            emojis: BTreeMap::new(),
+        }
+    }
+
+    /// Create new [`EmojiMap`] of the bundled table of the most common shortcodes of gemoji,
+    /// GitHub's emoji table.
+    #[cfg(feature = "emoji")]
+    pub fn gemoji() -> Self {
+        EmojiMap {
+            emojis: gemoji::GEMOJI
+                .iter()
+                .map(|(shortcode, emoji)| (Cow::Borrowed(*shortcode), Cow::Borrowed(*emoji)))
+                .collect(),
+        }
+    }
+
+    /// Maps `shortcode` to `emoji`, returns the emoji it was mapped to before, if any.
+    pub fn insert(
+        &mut self,
+        shortcode: impl Into<Cow<'static, str>>,
+        emoji: impl Into<Cow<'static, str>>,
+    ) -> Option<Cow<'static, str>> {
+        self.emojis.insert(shortcode.into(), emoji.into())
+    }
+
+    /// Returns the emoji of `shortcode`.
+    pub fn get(&self, shortcode: &str) -> Option<&str> {
+        self.emojis.get(shortcode).map(AsRef::as_ref)
+    }
+}
+
+impl Default for EmojiMap {
+    /// The bundled gemoji table with the `emoji` feature, an empty map otherwise.
+    fn default() -> Self {
+        #[cfg(feature = "emoji")]
+        return Self::gemoji();
+
+        #[cfg(not(feature = "emoji"))]
+        return Self::new();
+    }
+}
+
+/// Replaces the shortcodes of `map` in the [`Text`] values of `document` with their emoji,
+/// returns the number of replacements.
+///
+/// A shortcode is a name of ASCII alphanumerics, `_`, `+` and `-` between colons, within one
+/// text node: code, html and urls are left alone, and unknown shortcodes stay as they are.
+///
+/// ```
+/// use markdown_rs::{
+///     ast::transform::{emoji, EmojiMap},
+///     parser::Parser,
+/// };
+///
+/// let mut document = Parser::new("Shipped :ship:, see `:ship:`.").parse().unwrap();
+///
+/// let mut map = EmojiMap::new();
+///
+/// map.insert("ship", "🚢");
+///
+/// assert_eq!(emoji(&mut document, &map), 1);
+/// assert_eq!(
+///     document.to_markdown(&Default::default()),
+///     "Shipped 🚢, see `:ship:`.\n"
+/// );
+/// ```
+pub fn emoji(document: &mut Document, map: &EmojiMap) -> usize {
+    emoji_children(&mut document.children, map)
+}
+
+fn emoji_children(nodes: &mut [Node], map: &EmojiMap) -> usize {
+    let mut count = 0;
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => {
+                if let Some((value, replaced)) = replace_shortcodes(&text.value, map) {
+                    text.value = value.into();
+
+                    count += replaced;
+                }
+            }
+            // the text of an autolink is its url.
+            Node::Link(link)
+                if matches!(
+                    link.children.as_slice(),
+                    [Node::Text(text)] if text.value == link.url
+                        || link.url.strip_prefix("mailto:") == Some(&text.value)
+                ) => {}
+            node => {
+                if let Some(children) = node.children_mut() {
+                    count += emoji_children(children, map);
+                }
+            }
+        }
+    }
+
+    count
+}
+
+/// Returns `value` with the shortcodes of `map` replaced and the number of replacements, or
+/// `None` if it has none.
+fn replace_shortcodes(value: &str, map: &EmojiMap) -> Option<(String, usize)> {
+    let mut out = String::new();
+
+    let mut count = 0;
+
+    // end of the value copied to `out`, and where to look for the next opening colon.
+    let mut copied = 0;
+    let mut from = 0;
+
+    while let Some(start) = value[from..].find(':').map(|start| from + start) {
+        let name = &value[start + 1..];
+
+        let len = name
+            .bytes()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'+' | b'-'))
+            .count();
+
+        if len == 0 || name.as_bytes().get(len) != Some(&b':') {
+            from = start + 1 + len;
+
+            continue;
+        }
+
+        let end = start + 1 + len + 1;
+
+        // an unknown shortcode's closing colon may open the next one.
+        let Some(emoji) = map.get(&name[..len]) else {
+            from = end - 1;
+
+            continue;
+        };
+
+        out.push_str(&value[copied..start]);
+        out.push_str(emoji);
+
+        count += 1;
+
+        copied = end;
+        from = end;
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    out.push_str(&value[copied..]);
+
+    Some((out, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn depths(document: &Document) -> Vec<u8> {
+        document
+            .find_all_of::<Heading>()
+            .into_iter()
+            .map(|heading| heading.depth)
+            .collect()
+    }
+
+    #[test]
+    fn test_shift_headings() {
+        let source = "# a\n\n## b\n\n> ### c\n\n###### d\n";
+
+        let mut document = Parser::new(source).parse().unwrap();
+
+        assert_eq!(shift_headings(&mut document, 2, Overflow::Clamp), 4);
+        assert_eq!(depths(&document), [3, 4, 5, 6]);
+
+        assert_eq!(shift_headings(&mut document, -3, Overflow::Clamp), 4);
+        assert_eq!(depths(&document), [1, 1, 2, 3]);
+
+        assert_eq!(shift_headings(&mut document, -1, Overflow::Clamp), 2);
+        assert_eq!(shift_headings(&mut document, 0, Overflow::Clamp), 0);
+    }
+
+    #[test]
+    fn test_shift_headings_overflow() {
+        let mut document = Parser::new("#### a *b*\n\n##### c\n\n# d").parse().unwrap();
+
+        assert_eq!(shift_headings(&mut document, 2, Overflow::Strong), 3);
+        assert_eq!(depths(&document), [6, 3]);
+        assert_eq!(
+            document.children[1],
+            Parser::new("**c**").parse().unwrap().children[0]
+        );
+
+        let mut document = Parser::new("##### c").parse().unwrap();
+
+        shift_headings(&mut document, 100, Overflow::Clamp);
+
+        assert_eq!(depths(&document), [6]);
+    }
+
+    #[test]
+    fn test_embed() {
+        let mut document =
+            Parser::new("# Guide\n\n## Install\n\nText.\n\n## Usage\n\n[b]: /guide\n[c]: /guide")
+                .parse()
+                .unwrap();
+
+        let other = Parser::new(
+            "# Setup\n\n## Linux\n\n### Debian\n\n> [B]: /other\n\n[d]: /other\n\n[c]: /other",
+        )
+        .parse()
+        .unwrap();
+
+        // under the `Install` section, before `Usage`.
+        let section = document
+            .section(|heading| heading.to_text() == "Install")
+            .unwrap();
+
+        let collisions = document.embed(section.end, other, 2).unwrap();
+
+        assert_eq!(collisions, ["b", "c"]);
+        assert_eq!(depths(&document), [1, 2, 3, 4, 5, 2]);
+
+        let definitions = document.find_all_of::<Definition>();
+
+        assert_eq!(
+            definitions
+                .iter()
+                .map(|definition| definition.identifier.as_ref())
+                .collect::<Vec<_>>(),
+            ["b", "d", "c", "b", "c"]
+        );
+
+        assert_eq!(
+            document.section(|heading| heading.to_text() == "Install"),
+            Some(1..9)
+        );
+
+        assert!(matches!(
+            document.embed(100, Document::default(), 0),
+            Err(AstError::IndexOutOfBounds {
+                index: 100,
+                len: 12
+            })
+        ));
+    }
+    fn map() -> EmojiMap {
+        let mut map = EmojiMap::new();
+
+        map.insert("tada", "🎉");
+        map.insert("+1", "👍");
+
+        map
+    }
+
+    fn with_emojis(source: &str, map: &EmojiMap) -> String {
+        let mut document = Parser::new(source).parse().unwrap();
+
+        emoji(&mut document, map);
+
+        document.to_markdown(&Default::default())
+    }
+
+    #[test]
+    fn test_emoji() {
+        let map = map();
+
+        assert_eq!(with_emojis("Done :tada:", &map), "Done 🎉\n");
+        // unknown shortcodes and lone colons stay.
+        assert_eq!(
+            with_emojis("a :unknown: b :tada :: c:", &map),
+            "a :unknown: b :tada :: c:\n"
+        );
+        // punctuation around and between shortcodes.
+        assert_eq!(
+            with_emojis("(:+1:), :tada::tada:! :x:tada:", &map),
+            "(👍), 🎉🎉! :x🎉\n"
+        );
+        assert_eq!(with_emojis("*:tada:* # :tada:", &map), "*🎉* # 🎉\n");
+
+        let mut document = Parser::new(":tada: :+1: :tada").parse().unwrap();
+
+        assert_eq!(emoji(&mut document, &map), 2);
+        assert_eq!(emoji(&mut document, &EmojiMap::new()), 0);
+    }
+
+    #[test]
+    fn test_emoji_untouched() {
+        let source = "`:tada:` <https://a.example/:tada:> [:tada:](/:tada: \":tada:\")\n\n\
+            ```\n:tada:\n```\n\n<p>:tada:</p>\n";
+
+        let expected = source.replacen("[:tada:]", "[🎉]", 1);
+
+        assert_eq!(with_emojis(source, &map()), expected);
+
+        // the colons of a shortcode must be in the same text node.
+        let mut document = Document {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![
+                    Node::Text(Text {
+                        value: ":ta".into(),
+                        data: None,
+                    }),
+                    Node::Text(Text {
+                        value: "da:".into(),
+                        data: None,
+                    }),
+                ],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert_eq!(emoji(&mut document, &map()), 0);
+    }
+
+    #[cfg(feature = "emoji")]
+    #[test]
+    fn test_gemoji() {
+        let map = EmojiMap::default();
+
+        assert_eq!(map, EmojiMap::gemoji());
+        assert_eq!(map.get("tada"), Some("🎉"));
+        assert_eq!(map.get("nope"), None);
+        assert!(gemoji::GEMOJI.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(
+            with_emojis(":rocket: :+1: :warning:", &map),
+            "🚀 👍 ⚠\u{fe0f}\n"
+        );
+    }
+}
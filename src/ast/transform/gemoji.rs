@@ -0,0 +1,283 @@
+//! Shortcodes of the most common emojis of gemoji, GitHub's emoji table, sorted by shortcode.
+
+pub(super) const GEMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("1234", "🔢"),
+    ("1st_place_medal", "🥇"),
+    ("abc", "🔤"),
+    ("airplane", "✈\u{fe0f}"),
+    ("alarm_clock", "⏰"),
+    ("alien", "👽"),
+    ("anchor", "⚓"),
+    ("angry", "😠"),
+    ("apple", "🍎"),
+    ("arrow_down", "⬇\u{fe0f}"),
+    ("arrow_down_small", "🔽"),
+    ("arrow_left", "⬅\u{fe0f}"),
+    ("arrow_right", "➡\u{fe0f}"),
+    ("arrow_up", "⬆\u{fe0f}"),
+    ("arrow_up_small", "🔼"),
+    ("arrows_counterclockwise", "🔄"),
+    ("art", "🎨"),
+    ("balloon", "🎈"),
+    ("bar_chart", "📊"),
+    ("battery", "🔋"),
+    ("bee", "🐝"),
+    ("beer", "🍺"),
+    ("beers", "🍻"),
+    ("bell", "🔔"),
+    ("bike", "🚲"),
+    ("birthday", "🎂"),
+    ("black_circle", "⚫"),
+    ("black_flag", "🏴"),
+    ("blue_heart", "💙"),
+    ("blush", "😊"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("bookmark", "🔖"),
+    ("bookmark_tabs", "📑"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("broken_heart", "💔"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("butterfly", "🦋"),
+    ("cactus", "🌵"),
+    ("cake", "🍰"),
+    ("calendar", "📆"),
+    ("camera", "📷"),
+    ("car", "🚗"),
+    ("card_index", "📇"),
+    ("cat", "🐱"),
+    ("cd", "💿"),
+    ("chart_with_downwards_trend", "📉"),
+    ("chart_with_upwards_trend", "📈"),
+    ("checkered_flag", "🏁"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("closed_lock_with_key", "🔐"),
+    ("cloud", "☁\u{fe0f}"),
+    ("coffee", "☕"),
+    ("computer", "💻"),
+    ("confetti_ball", "🎊"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cookie", "🍪"),
+    ("cool", "🆒"),
+    ("copyright", "©\u{fe0f}"),
+    ("crab", "🦀"),
+    ("crescent_moon", "🌙"),
+    ("crossed_flags", "🎌"),
+    ("crown", "👑"),
+    ("cry", "😢"),
+    ("dagger", "🗡\u{fe0f}"),
+    ("dart", "🎯"),
+    ("date", "📅"),
+    ("disappointed", "😞"),
+    ("dizzy", "💫"),
+    ("dog", "🐶"),
+    ("dollar", "💵"),
+    ("droplet", "💧"),
+    ("earth_africa", "🌍"),
+    ("earth_americas", "🌎"),
+    ("electric_plug", "🔌"),
+    ("email", "✉"),
+    ("envelope", "✉"),
+    ("evergreen_tree", "🌲"),
+    ("exclamation", "❗"),
+    ("expressionless", "😑"),
+    ("eyeglasses", "👓"),
+    ("eyes", "👀"),
+    ("fast_forward", "⏩"),
+    ("file_folder", "📁"),
+    ("fire", "🔥"),
+    ("fist", "✊"),
+    ("floppy_disk", "💾"),
+    ("flushed", "😳"),
+    ("four_leaf_clover", "🍀"),
+    ("free", "🆓"),
+    ("gear", "⚙\u{fe0f}"),
+    ("gem", "💎"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("globe", "🌐"),
+    ("globe_with_meridians", "🌐"),
+    ("green_heart", "💚"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠\u{fe0f}"),
+    ("handshake", "🤝"),
+    ("hankey", "💩"),
+    ("hear_no_evil", "🙉"),
+    ("heart", "❤\u{fe0f}"),
+    ("heart_eyes", "😍"),
+    ("heavy_check_mark", "✔\u{fe0f}"),
+    ("heavy_dollar_sign", "💲"),
+    ("heavy_exclamation_mark", "❗"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("honeybee", "🐝"),
+    ("hospital", "🏥"),
+    ("hourglass", "⌛"),
+    ("house", "🏠"),
+    ("hugs", "🤗"),
+    ("id", "🆔"),
+    ("inbox_tray", "📥"),
+    ("information_source", "ℹ\u{fe0f}"),
+    ("innocent", "😇"),
+    ("iphone", "📱"),
+    ("joy", "😂"),
+    ("key", "🔑"),
+    ("keyboard", "⌨\u{fe0f}"),
+    ("label", "🏷\u{fe0f}"),
+    ("large_blue_circle", "🔵"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lipstick", "💄"),
+    ("lock", "🔒"),
+    ("lock_with_ink_pen", "🔏"),
+    ("loud_sound", "🔊"),
+    ("loudspeaker", "📢"),
+    ("mag", "🔍"),
+    ("mailbox", "📫"),
+    ("mask", "😷"),
+    ("medal_sports", "🏅"),
+    ("mega", "📣"),
+    ("memo", "📝"),
+    ("microscope", "🔬"),
+    ("moneybag", "💰"),
+    ("moon", "🌔"),
+    ("muscle", "💪"),
+    ("musical_note", "🎵"),
+    ("mute", "🔇"),
+    ("necktie", "👔"),
+    ("neutral_face", "😐"),
+    ("new", "🆕"),
+    ("newspaper", "📰"),
+    ("ng", "🆖"),
+    ("no_bell", "🔕"),
+    ("no_entry", "⛔"),
+    ("notes", "🎶"),
+    ("nut_and_bolt", "🔩"),
+    ("ocean", "🌊"),
+    ("octopus", "🐙"),
+    ("office", "🏢"),
+    ("ok", "🆗"),
+    ("ok_hand", "👌"),
+    ("open_file_folder", "📂"),
+    ("outbox_tray", "📤"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("paperclip", "📎"),
+    ("pencil", "📝"),
+    ("pencil2", "✏"),
+    ("penguin", "🐧"),
+    ("pill", "💊"),
+    ("pizza", "🍕"),
+    ("point_down", "👇"),
+    ("point_left", "👈"),
+    ("point_right", "👉"),
+    ("point_up", "☝\u{fe0f}"),
+    ("poop", "💩"),
+    ("postbox", "📮"),
+    ("pray", "🙏"),
+    ("purple_heart", "💜"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("rage", "😡"),
+    ("rainbow", "🌈"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻\u{fe0f}"),
+    ("red_circle", "🔴"),
+    ("registered", "®\u{fe0f}"),
+    ("relieved", "😌"),
+    ("repeat", "🔁"),
+    ("rewind", "⏪"),
+    ("ring", "💍"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("roll_eyes", "🙄"),
+    ("rose", "🌹"),
+    ("rotating_light", "🚨"),
+    ("satellite", "📡"),
+    ("satisfied", "😆"),
+    ("scissors", "✂\u{fe0f}"),
+    ("scream", "😱"),
+    ("scroll", "📜"),
+    ("see_no_evil", "🙈"),
+    ("seedling", "🌱"),
+    ("shield", "🛡\u{fe0f}"),
+    ("ship", "🚢"),
+    ("shirt", "👕"),
+    ("skull", "💀"),
+    ("sleeping", "😴"),
+    ("slightly_smiling_face", "🙂"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("snake", "🐍"),
+    ("snowflake", "❄\u{fe0f}"),
+    ("sob", "😭"),
+    ("sos", "🆘"),
+    ("sound", "🔉"),
+    ("sparkles", "✨"),
+    ("sparkling_heart", "💖"),
+    ("speak_no_evil", "🙊"),
+    ("speaker", "🔈"),
+    ("speech_balloon", "💬"),
+    ("star", "⭐"),
+    ("star2", "🌟"),
+    ("stop_sign", "🛑"),
+    ("stopwatch", "⏱\u{fe0f}"),
+    ("straight_ruler", "📏"),
+    ("stuck_out_tongue", "😛"),
+    ("sun_with_face", "🌞"),
+    ("sunglasses", "😎"),
+    ("sunny", "☀\u{fe0f}"),
+    ("sweat_drops", "💦"),
+    ("sweat_smile", "😅"),
+    ("syringe", "💉"),
+    ("tada", "🎉"),
+    ("telescope", "🔭"),
+    ("test_tube", "🧪"),
+    ("thinking", "🤔"),
+    ("thought_balloon", "💭"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("ticket", "🎫"),
+    ("tm", "™\u{fe0f}"),
+    ("toolbox", "🧰"),
+    ("tophat", "🎩"),
+    ("triangular_flag_on_post", "🚩"),
+    ("triangular_ruler", "📐"),
+    ("trophy", "🏆"),
+    ("truck", "🚚"),
+    ("turtle", "🐢"),
+    ("tv", "📺"),
+    ("twisted_rightwards_arrows", "🔀"),
+    ("umbrella", "☔"),
+    ("unicorn", "🦄"),
+    ("unlock", "🔓"),
+    ("up", "🆙"),
+    ("upside_down_face", "🙃"),
+    ("v", "✌\u{fe0f}"),
+    ("vertical_traffic_light", "🚦"),
+    ("video_game", "🎮"),
+    ("warning", "⚠\u{fe0f}"),
+    ("wastebasket", "🗑"),
+    ("wave", "👋"),
+    ("whale", "🐳"),
+    ("white_check_mark", "✅"),
+    ("white_circle", "⚪"),
+    ("white_flag", "🏳\u{fe0f}"),
+    ("wink", "😉"),
+    ("worried", "😟"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yellow_heart", "💛"),
+    ("yum", "😋"),
+    ("zap", "⚡"),
+    ("zzz", "💤"),
+];
@@ -0,0 +1,392 @@
+//! Content-model validation of trees built by hand, deserialized or rewritten by transforms.
+//!
+//! The bounds of [`Parent::add_child`] only hold for the generic API; [`ParentEx::add_child_node`],
+//! struct literals and deserialization accept any child, so [`validate()`] checks the mdast content
+//! model of the whole tree after the fact.
+//!
+//! ```
+//! use markdown_rs::ast::{validate, AstError, Break, Document, Node, NodeKind, Table, TableRow};
+//!
+//! let document = Document {
+//!     children: vec![Node::Table(Table {
+//!         children: vec![Node::Break(Break { data: None })],
+//!         align: vec![],
+//!         data: None,
+//!     })],
+//!     data: None,
+//! };
+//!
+//! let errors = validate(&document).unwrap_err();
+//!
+//! assert!(matches!(
+//!     errors[..],
+//!     [AstError::InvalidChild {
+//!         parent: NodeKind::Table,
+//!         child: NodeKind::Break,
+//!         index: 0
+//!     }]
+//! ));
+//! ```
+
+use super::*;
+
+/// Checks `document` against the mdast content model, returns every violation found in
+/// document order.
+///
+/// Documents, block quotes, list items and footnote definitions hold flow content; paragraphs,
+/// headings, emphasis, strong, delete, links, link references and table cells hold phrasing
+/// content; lists hold list items, tables hold rows and rows hold cells. Links never contain
+/// links and table cells never contain breaks, at any depth. Heading depths are in `1..=6` and
+/// identifiers of definitions, references and footnotes are non-empty.
+pub fn validate(document: &Document) -> Result<(), Vec<AstError>> {
+    let mut validator = Validator {
+        errors: vec![],
+        in_link: false,
+        in_cell: false,
+    };
+
+    validator.children(NodeKind::Document, &document.children);
+
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+struct Validator {
+    errors: Vec<AstError>,
+    in_link: bool,
+    in_cell: bool,
+}
+
+impl Validator {
+    fn children(&mut self, parent: NodeKind, children: &[Node]) {
+        for (index, child) in children.iter().enumerate() {
+            let kind = child.kind();
+
+            if !self.allows(parent, kind) {
+                self.errors.push(AstError::InvalidChild {
+                    parent,
+                    child: kind,
+                    index,
+                });
+            }
+
+            self.node(child);
+        }
+    }
+
+    fn allows(&self, parent: NodeKind, child: NodeKind) -> bool {
+        match child {
+            NodeKind::Link | NodeKind::LinkReference if self.in_link => false,
+            NodeKind::Break if self.in_cell => false,
+            _ => match parent {
+                NodeKind::Document
+                | NodeKind::Blockquote
+                | NodeKind::ListItem
+                | NodeKind::FootnoteDefinition => is_flow(child),
+                NodeKind::Paragraph
+                | NodeKind::Heading
+                | NodeKind::Emphasis
+                | NodeKind::Strong
+                | NodeKind::Delete
+                | NodeKind::Link
+                | NodeKind::LinkReference
+                | NodeKind::TableCell => is_phrasing(child),
+                NodeKind::List => child == NodeKind::ListItem,
+                NodeKind::Table => child == NodeKind::TableRow,
+                NodeKind::TableRow => child == NodeKind::TableCell,
+                NodeKind::Custom => is_flow(child) || is_phrasing(child),
+                _ => false,
+            },
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        let identifier = match node {
+            Node::Heading(heading) => {
+                if !Heading::DEPTHS.contains(&heading.depth) {
+                    self.errors.push(AstError::InvalidDepth(heading.depth));
+                }
+
+                None
+            }
+            Node::Definition(definition) => Some(&definition.identifier),
+            Node::LinkReference(reference) => Some(&reference.identifier),
+            Node::ImageReference(reference) => Some(&reference.identifier),
+            Node::FootnoteDefinition(definition) => Some(&definition.identifier),
+            Node::FootnoteReference(reference) => Some(&reference.identifier),
+            _ => None,
+        };
+
+        if identifier.is_some_and(|identifier| identifier.is_empty()) {
+            self.errors.push(AstError::EmptyIdentifier(node.kind()));
+        }
+
+        let Some(children) = node.children() else {
+            return;
+        };
+
+        let (in_link, in_cell) = (self.in_link, self.in_cell);
+
+        let kind = node.kind();
+
+        self.in_link |= matches!(kind, NodeKind::Link | NodeKind::LinkReference);
+        self.in_cell |= kind == NodeKind::TableCell;
+
+        self.children(kind, children);
+
+        self.in_link = in_link;
+        self.in_cell = in_cell;
+    }
+}
+
+/// Returns true if nodes of `kind` are flow content.
+pub(crate) fn is_flow(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Paragraph
+            | NodeKind::Heading
+            | NodeKind::ThematicBreak
+            | NodeKind::Blockquote
+            | NodeKind::List
+            | NodeKind::Code
+            | NodeKind::Definition
+            | NodeKind::FootnoteDefinition
+            | NodeKind::Table
+            | NodeKind::Html
+            | NodeKind::Custom
+    )
+}
+
+/// Returns true if nodes of `kind` are phrasing content.
+pub(crate) fn is_phrasing(kind: NodeKind) -> bool {
+    matches!(
+        kind,
+        NodeKind::Text
+            | NodeKind::Emphasis
+            | NodeKind::Strong
+            | NodeKind::InlineCode
+            | NodeKind::Break
+            | NodeKind::Link
+            | NodeKind::LinkReference
+            | NodeKind::Image
+            | NodeKind::ImageReference
+            | NodeKind::Delete
+            | NodeKind::FootnoteReference
+            | NodeKind::Html
+            | NodeKind::Custom
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    fn paragraph(children: Vec<Node<'_>>) -> Node<'_> {
+        Node::Paragraph(Paragraph {
+            children,
+            data: None,
+        })
+    }
+
+    #[test]
+    fn test_valid() {
+        let document = Parser::new(
+            "# Title\n\n> - a *b* [c](d)\n>\n>   ```\n>   e\n>   ```\n\n[f]: g\n\n[f]\n",
+        )
+        .parse()
+        .unwrap();
+
+        assert!(validate(&document).is_ok());
+
+        let document = Document {
+            children: vec![Node::Table(Table {
+                children: vec![Node::TableRow(TableRow {
+                    children: vec![Node::TableCell(TableCell {
+                        children: vec![Node::Delete(Delete {
+                            children: vec![text("a")],
+                            data: None,
+                        })],
+                        data: None,
+                    })],
+                    data: None,
+                })],
+                align: vec![AlignType::None],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert!(validate(&document).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_children() {
+        let document = Document {
+            children: vec![
+                text("loose"),
+                Node::Heading(Heading {
+                    children: vec![text("a"), paragraph(vec![])],
+                    depth: 1,
+                    data: None,
+                }),
+                Node::List(List {
+                    children: vec![Node::ListItem(ListItem::new(None)), paragraph(vec![])],
+                    ..List::new(false, None)
+                }),
+                Node::Table(Table {
+                    children: vec![Node::TableRow(TableRow {
+                        children: vec![text("cell")],
+                        data: None,
+                    })],
+                    align: vec![],
+                    data: None,
+                }),
+                paragraph(vec![Node::TableRow(TableRow {
+                    children: vec![],
+                    data: None,
+                })]),
+            ],
+            data: None,
+        };
+
+        let errors = validate(&document).unwrap_err();
+
+        let errors = errors
+            .iter()
+            .map(|error| match error {
+                AstError::InvalidChild {
+                    parent,
+                    child,
+                    index,
+                } => (*parent, *child, *index),
+                error => panic!("unexpected {:?}", error),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            errors,
+            [
+                (NodeKind::Document, NodeKind::Text, 0),
+                (NodeKind::Heading, NodeKind::Paragraph, 1),
+                (NodeKind::List, NodeKind::Paragraph, 1),
+                (NodeKind::TableRow, NodeKind::Text, 0),
+                (NodeKind::Paragraph, NodeKind::TableRow, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_constraints() {
+        let link = |children| {
+            Node::from(Link {
+                children,
+                url: "a".into(),
+                title: None,
+                data: None,
+            })
+        };
+
+        let document = Document {
+            children: vec![
+                paragraph(vec![link(vec![Node::Emphasis(Emphasis {
+                    children: vec![text("a"), link(vec![])],
+                    data: None,
+                })])]),
+                Node::Table(Table {
+                    children: vec![Node::TableRow(TableRow {
+                        children: vec![Node::TableCell(TableCell {
+                            children: vec![Node::Strong(Strong {
+                                children: vec![Node::Break(Break { data: None })],
+                                data: None,
+                            })],
+                            data: None,
+                        })],
+                        data: None,
+                    })],
+                    align: vec![AlignType::None],
+                    data: None,
+                }),
+                // breaks are fine outside of cells.
+                paragraph(vec![Node::Break(Break { data: None })]),
+            ],
+            data: None,
+        };
+
+        let errors = validate(&document).unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            errors[0],
+            AstError::InvalidChild {
+                parent: NodeKind::Emphasis,
+                child: NodeKind::Link,
+                index: 1
+            }
+        ));
+        assert!(matches!(
+            errors[1],
+            AstError::InvalidChild {
+                parent: NodeKind::Strong,
+                child: NodeKind::Break,
+                index: 0
+            }
+        ));
+    }
+
+    #[test]
+    fn test_depth_and_identifiers() {
+        let document = Document {
+            children: vec![
+                Node::Heading(Heading {
+                    children: vec![],
+                    depth: 0,
+                    data: None,
+                }),
+                Node::from(Definition {
+                    identifier: "".into(),
+                    label: None,
+                    url: "a".into(),
+                    title: None,
+                    data: None,
+                }),
+                paragraph(vec![Node::FootnoteReference(FootnoteReference {
+                    identifier: "".into(),
+                    label: None,
+                    data: None,
+                })]),
+                Node::Heading(Heading {
+                    children: vec![],
+                    depth: 7,
+                    data: None,
+                }),
+            ],
+            data: None,
+        };
+
+        let errors = validate(&document).unwrap_err();
+
+        assert_eq!(errors.len(), 4);
+        assert!(matches!(errors[0], AstError::InvalidDepth(0)));
+        assert!(matches!(
+            errors[1],
+            AstError::EmptyIdentifier(NodeKind::Definition)
+        ));
+        assert!(matches!(
+            errors[2],
+            AstError::EmptyIdentifier(NodeKind::FootnoteReference)
+        ));
+        assert!(matches!(errors[3], AstError::InvalidDepth(7)));
+    }
+}
@@ -0,0 +1,335 @@
+//! Ready-made [`Visitor`]s collecting the parts of a document most tools need.
+//!
+//! Each visitor is created in one call, driven by [`walk`] over a document or any of its
+//! nodes, and turned into its result by `finish`.
+//!
+//! ```
+//! use markdown_rs::{
+//!     ast::{visitors::HeadingCollector, walk, Node},
+//!     parser::Parser,
+//! };
+//!
+//! let source = "# Install\n\nRun it.\n\n## From *source*\n";
+//!
+//! let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+//!
+//! let mut headings = HeadingCollector::with_spans(&spans);
+//!
+//! walk(&Node::Document(document), &mut headings);
+//!
+//! assert_eq!(
+//!     headings.finish(),
+//!     [
+//!         (1, "Install".to_owned(), Some(0..9)),
+//!         (2, "From source".to_owned(), Some(20..36))
+//!     ]
+//! );
+//! ```
+
+use alloc::collections::BTreeMap;
+use core::ops::Range;
+
+use super::spans::Spans;
+use super::text::{to_text, TextOptions};
+use super::*;
+
+/// Position of the node being visited, tracked from [`Visitor::enter`] and
+/// [`Visitor::leave`].
+///
+/// The document node is the root of positions, and is not part of them: walking a document
+/// or each of its children in turn gives the same positions.
+#[derive(Debug, Default)]
+struct PathTracker {
+    path: Vec<usize>,
+    /// Index of the next child of each node on the path, the top-level one first.
+    next: Vec<usize>,
+}
+
+impl PathTracker {
+    fn enter(&mut self, node: &Node) {
+        if matches!(node, Node::Document(_)) {
+            return;
+        }
+
+        if self.next.len() == self.path.len() {
+            self.next.push(0);
+        }
+
+        let index = &mut self.next[self.path.len()];
+
+        self.path.push(*index);
+
+        *index += 1;
+    }
+
+    fn leave(&mut self, node: &Node) {
+        if matches!(node, Node::Document(_)) {
+            return;
+        }
+
+        // the children of the left node are done.
+        self.next.truncate(self.path.len());
+        self.path.pop();
+    }
+}
+
+/// Collects the headings of a document as `(depth, text, span)` triples, in document order.
+///
+/// The text is the [plain text](to_text) of the heading. Spans are only known to a collector
+/// created [`with_spans`](Self::with_spans), and walking a document from its root or its
+/// top-level nodes.
+#[derive(Debug, Default)]
+pub struct HeadingCollector<'s> {
+    spans: Option<&'s Spans>,
+    tracker: PathTracker,
+    headings: Vec<(u8, String, Option<Range<usize>>)>,
+}
+
+impl<'s> HeadingCollector<'s> {
+    /// Create new [`HeadingCollector`] without spans.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Create new [`HeadingCollector`] reading the span of each heading from `spans`, those
+    /// of the parse that produced the walked document.
+    pub fn with_spans(spans: &'s Spans) -> Self {
+        HeadingCollector {
+            spans: Some(spans),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the collected headings.
+    pub fn finish(self) -> Vec<(u8, String, Option<Range<usize>>)> {
+        self.headings
+    }
+}
+
+impl<'s> Visitor for HeadingCollector<'s> {
+    fn enter(&mut self, node: &Node) {
+        self.tracker.enter(node);
+
+        if let Node::Heading(heading) = node {
+            let span = self.spans.and_then(|spans| spans.get(&self.tracker.path));
+
+            self.headings
+                .push((heading.depth, to_text(node, &TextOptions::default()), span));
+        }
+    }
+
+    fn leave(&mut self, node: &Node) {
+        self.tracker.leave(node);
+    }
+}
+
+/// Collects the links of a document as `(url, text)` pairs, in document order.
+///
+/// The text is the [plain text](to_text) of the link. Link references are left out, see
+/// [`resolve`] to turn them into links first.
+#[derive(Debug, Default)]
+pub struct LinkCollector {
+    links: Vec<(String, String)>,
+}
+
+impl LinkCollector {
+    /// Create new [`LinkCollector`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the collected links.
+    pub fn finish(self) -> Vec<(String, String)> {
+        self.links
+    }
+}
+
+impl Visitor for LinkCollector {
+    fn enter(&mut self, node: &Node) {
+        if let Node::Link(link) = node {
+            self.links
+                .push((link.url.to_string(), to_text(node, &TextOptions::default())));
+        }
+    }
+}
+
+/// Collects the values of the code blocks of a document by language, in document order.
+///
+/// Code blocks without a language are collected under the empty string.
+#[derive(Debug, Default)]
+pub struct CodeBlockCollector {
+    blocks: BTreeMap<String, Vec<String>>,
+}
+
+impl CodeBlockCollector {
+    /// Create new [`CodeBlockCollector`].
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the collected code blocks.
+    pub fn finish(self) -> BTreeMap<String, Vec<String>> {
+        self.blocks
+    }
+}
+
+impl Visitor for CodeBlockCollector {
+    fn visit_code(&mut self, node: &Code) {
+        self.blocks
+            .entry(node.lang.as_deref().unwrap_or_default().to_owned())
+            .or_default()
+            .push(node.value.to_string());
+    }
+}
+
+/// Counts the chars of the text a reader sees: text and inline code, code blocks and html
+/// excluded.
+#[derive(Debug, Default)]
+pub struct TextLength {
+    chars: usize,
+}
+
+impl TextLength {
+    /// Create new [`TextLength`] counter.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the counted number of chars.
+    pub fn finish(self) -> usize {
+        self.chars
+    }
+}
+
+impl Visitor for TextLength {
+    fn visit_text(&mut self, node: &Text) {
+        self.chars += node.value.chars().count();
+    }
+
+    fn visit_inline_code(&mut self, node: &InlineCode) {
+        self.chars += node.value.chars().count();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    const MARKDOWN: &str = "\
+# Getting *started*
+
+Read [the docs](https://docs.rs \"Docs\") or [ask][forum].
+
+> ## Quoted
+>
+> See <https://example.com>.
+
+```rust
+fn main() {}
+```
+
+```
+plain
+```
+
+- ```rust
+  let a = 1;
+  ```
+
+Setext
+======
+
+[forum]: https://forum.example.com
+";
+
+    #[test]
+    fn test_headings() {
+        let (document, spans) = Parser::new(MARKDOWN).parse_with_spans().unwrap();
+
+        let mut collector = HeadingCollector::with_spans(&spans);
+
+        walk(&Node::Document(document.clone()), &mut collector);
+
+        let headings = collector.finish();
+
+        assert_eq!(
+            headings,
+            [
+                (1, "Getting started".to_owned(), Some(0..19)),
+                (2, "Quoted".to_owned(), Some(81..90)),
+                (1, "Setext".to_owned(), Some(194..207)),
+            ]
+        );
+
+        for (_, _, span) in &headings {
+            assert!(MARKDOWN[span.clone().unwrap()].contains(['#', '=']));
+        }
+
+        // the same positions walking the top-level nodes.
+        let mut collector = HeadingCollector::with_spans(&spans);
+
+        for child in &document.children {
+            walk(child, &mut collector);
+        }
+
+        assert_eq!(collector.finish(), headings);
+
+        // no spans.
+        let mut collector = HeadingCollector::new();
+
+        walk(&Node::Document(document), &mut collector);
+
+        assert_eq!(collector.finish()[1], (2, "Quoted".to_owned(), None));
+    }
+
+    #[test]
+    fn test_links() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let mut collector = LinkCollector::new();
+
+        walk(&Node::Document(document), &mut collector);
+
+        assert_eq!(
+            collector.finish(),
+            [
+                ("https://docs.rs".to_owned(), "the docs".to_owned()),
+                (
+                    "https://example.com".to_owned(),
+                    "https://example.com".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let document = Parser::new(MARKDOWN).parse().unwrap();
+
+        let mut collector = CodeBlockCollector::new();
+
+        walk(&Node::Document(document), &mut collector);
+
+        let blocks = collector.finish();
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks["rust"], ["fn main() {}", "let a = 1;"]);
+        assert_eq!(blocks[""], ["plain"]);
+    }
+
+    #[test]
+    fn test_text_length() {
+        let document = Parser::new("*Héllo* `wörld`\n\n```\nskipped\n```\n\n<div>\nno\n</div>")
+            .parse()
+            .unwrap();
+
+        let mut counter = TextLength::new();
+
+        walk(&Node::Document(document), &mut counter);
+
+        // "Héllo", " " and "wörld".
+        assert_eq!(counter.finish(), 11);
+    }
+}
@@ -0,0 +1,298 @@
+//! C API, declared in `include/markdown_rs.h`.
+//!
+//! ```c
+//! MdError error;
+//! MdDocument *document = md_parse(source, strlen(source), &error);
+//!
+//! if (document == NULL) {
+//!     fprintf(stderr, "error %d at byte %zu\n", error.code, error.offset);
+//!     return 1;
+//! }
+//!
+//! MdBuffer html = md_render_html(document);
+//! fwrite(html.data, 1, html.len, stdout);
+//!
+//! md_buffer_free(html);
+//! md_document_free(document);
+//! ```
+//!
+//! Every function catches panics, unwinding never crosses the C boundary.
+
+use std::{
+    os::raw::c_char,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use crate::{ast::Document, html::HtmlOptions, parser::Parser};
+
+/// Status codes of [`MdError`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MdStatus {
+    Ok = 0,
+    /// A required pointer argument is null.
+    NullArgument = 1,
+    /// The source is not valid UTF-8, the offset is that of the first invalid byte.
+    InvalidUtf8 = 2,
+    /// The parser reported an error.
+    ParseError = 3,
+    /// The library panicked, this is a bug.
+    Panic = 4,
+}
+
+/// Error out-parameter of [`md_parse`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MdError {
+    pub code: MdStatus,
+    /// Byte offset in the source the error relates to, `0` if none.
+    pub offset: usize,
+}
+
+/// Opaque handle of a parsed document, owning its tree.
+pub struct MdDocument {
+    document: Document<'static>,
+}
+
+/// UTF-8 buffer allocated by this library, free it with [`md_buffer_free`].
+///
+/// `data` is followed by a NUL byte not counted in `len`, so it can be used as a C string
+/// when the content has no interior NUL. `data` is null if the call failed.
+#[repr(C)]
+#[derive(Debug)]
+pub struct MdBuffer {
+    pub data: *mut c_char,
+    pub len: usize,
+}
+
+impl MdBuffer {
+    fn null() -> Self {
+        Self {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    fn new(value: String) -> Self {
+        let mut bytes = value.into_bytes();
+
+        let len = bytes.len();
+
+        bytes.push(0);
+
+        Self {
+            data: Box::into_raw(bytes.into_boxed_slice()) as *mut c_char,
+            len,
+        }
+    }
+}
+
+fn report(error: *mut MdError, code: MdStatus, offset: usize) {
+    if !error.is_null() {
+        // SAFETY: non-null `error` points to writable memory, per the contract of callers.
+        unsafe { error.write(MdError { code, offset }) };
+    }
+}
+
+/// Parse the `len` bytes of UTF-8 markdown at `source`.
+///
+/// Returns null and fills `error` on failure, `error` may be null. The returned document is
+/// independent of `source` and must be freed with [`md_document_free`].
+///
+/// # Safety
+///
+/// `source` must point to `len` readable bytes, `error` must be null or point to a writable
+/// [`MdError`].
+#[no_mangle]
+pub unsafe extern "C" fn md_parse(
+    source: *const c_char,
+    len: usize,
+    error: *mut MdError,
+) -> *mut MdDocument {
+    if source.is_null() {
+        report(error, MdStatus::NullArgument, 0);
+
+        return ptr::null_mut();
+    }
+
+    // SAFETY: guaranteed by the caller.
+    let bytes = unsafe { slice::from_raw_parts(source as *const u8, len) };
+
+    let source = match std::str::from_utf8(bytes) {
+        Ok(source) => source,
+        Err(err) => {
+            report(error, MdStatus::InvalidUtf8, err.valid_up_to());
+
+            return ptr::null_mut();
+        }
+    };
+
+    match catch_unwind(|| Parser::new(source).parse().map(Document::into_owned)) {
+        Ok(Ok(document)) => {
+            report(error, MdStatus::Ok, 0);
+
+            Box::into_raw(Box::new(MdDocument { document }))
+        }
+        Ok(Err(_)) => {
+            report(error, MdStatus::ParseError, 0);
+
+            ptr::null_mut()
+        }
+        Err(_) => {
+            report(error, MdStatus::Panic, 0);
+
+            ptr::null_mut()
+        }
+    }
+}
+
+fn render(document: *const MdDocument, f: impl FnOnce(&Document<'static>) -> String) -> MdBuffer {
+    // SAFETY: non-null `document` comes from `md_parse`, per the contract of callers.
+    let Some(document) = (unsafe { document.as_ref() }) else {
+        return MdBuffer::null();
+    };
+
+    match catch_unwind(AssertUnwindSafe(|| f(&document.document))) {
+        Ok(value) => MdBuffer::new(value),
+        Err(_) => MdBuffer::null(),
+    }
+}
+
+/// Render `document` to HTML with the default [`HtmlOptions`], raw HTML is escaped.
+///
+/// Returns a null buffer if `document` is null.
+///
+/// # Safety
+///
+/// `document` must be null or a live handle returned by [`md_parse`].
+#[no_mangle]
+pub unsafe extern "C" fn md_render_html(document: *const MdDocument) -> MdBuffer {
+    render(document, |document| {
+        document.to_html(&HtmlOptions::default())
+    })
+}
+
+/// Serialize `document` to mdast JSON.
+///
+/// Returns a null buffer if `document` is null.
+///
+/// # Safety
+///
+/// `document` must be null or a live handle returned by [`md_parse`].
+#[no_mangle]
+pub unsafe extern "C" fn md_document_to_json(document: *const MdDocument) -> MdBuffer {
+    render(document, |document| {
+        serde_json::to_string(document).expect("mdast trees always serialize to JSON")
+    })
+}
+
+/// Free a document returned by [`md_parse`], null is ignored.
+///
+/// # Safety
+///
+/// `document` must be null or a live handle returned by [`md_parse`], it is dangling
+/// afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn md_document_free(document: *mut MdDocument) {
+    if !document.is_null() {
+        // SAFETY: guaranteed by the caller.
+        drop(unsafe { Box::from_raw(document) });
+    }
+}
+
+/// Free a buffer returned by this library, null buffers are ignored.
+///
+/// # Safety
+///
+/// `buffer` must be unchanged from its return by this library, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn md_buffer_free(buffer: MdBuffer) {
+    if !buffer.data.is_null() {
+        // SAFETY: `data` is a boxed slice of `len` bytes plus the NUL byte, see `MdBuffer::new`.
+        drop(unsafe {
+            Box::from_raw(ptr::slice_from_raw_parts_mut(
+                buffer.data as *mut u8,
+                buffer.len + 1,
+            ))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+
+    use super::*;
+
+    fn parse(source: &[u8]) -> Result<*mut MdDocument, MdError> {
+        let mut error = MdError {
+            code: MdStatus::Panic,
+            offset: usize::MAX,
+        };
+
+        let document = unsafe { md_parse(source.as_ptr() as _, source.len(), &mut error) };
+
+        match error.code {
+            MdStatus::Ok => Ok(document),
+            _ => {
+                assert!(document.is_null());
+
+                Err(error)
+            }
+        }
+    }
+
+    fn take(buffer: MdBuffer) -> String {
+        let value = unsafe { CStr::from_ptr(buffer.data) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+
+        assert_eq!(value.len(), buffer.len);
+
+        unsafe { md_buffer_free(buffer) };
+
+        value
+    }
+
+    #[test]
+    fn test_parse() {
+        let document = parse(b"# a <b>").unwrap();
+
+        assert_eq!(
+            take(unsafe { md_render_html(document) }),
+            "<h1>a &lt;b&gt;</h1>\n"
+        );
+
+        assert!(take(unsafe { md_document_to_json(document) })
+            .starts_with(r#"{"type":"root","children":[{"type":"heading""#));
+
+        unsafe { md_document_free(document) };
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse(b"ok \xF0\x28"),
+            Err(MdError {
+                code: MdStatus::InvalidUtf8,
+                offset: 3
+            })
+        );
+
+        assert!(
+            unsafe { md_parse(ptr::null(), 0, ptr::null_mut()) }.is_null(),
+            "null error out-parameter is allowed"
+        );
+
+        let buffer = unsafe { md_render_html(ptr::null()) };
+
+        assert!(buffer.data.is_null());
+
+        unsafe {
+            md_buffer_free(buffer);
+            md_document_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,1726 @@
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt, mem, slice};
+
+use crate::{
+    ast::{
+        links::{replace_md_extension, resolve_url},
+        slug::{heading_text, Slugger},
+        *,
+    },
+    parser::has_scheme,
+};
+
+#[cfg(feature = "syntect")]
+pub mod syntect;
+
+/// Options of [`render`].
+///
+/// The defaults are safe for untrusted input: raw HTML is escaped and links and images with
+/// `javascript:`, `vbscript:` or `data:` urls are made inert.
+#[derive(Clone)]
+pub struct HtmlOptions {
+    /// Emit [`Html`] nodes verbatim instead of escaping them as text, defaults to `false`.
+    ///
+    /// Only enable this for trusted input, raw HTML can carry scripts.
+    pub allow_dangerous_html: bool,
+    /// Unless `allow_dangerous_html` is set, escape [`Html`] nodes as text, the default, or
+    /// replace them with a `<!-- raw HTML omitted -->` comment if `false`.
+    pub escape_html: bool,
+    /// Keep `javascript:`, `vbscript:` and `data:` urls of links and images, which otherwise
+    /// render as an empty `href` or `src`, defaults to `false`.
+    pub allow_dangerous_protocols: bool,
+    /// Renders [`Code`] and [`InlineCode`] nodes, defaults to [`DefaultCodeRenderer`].
+    pub code_renderer: Option<Arc<dyn CodeRenderer>>,
+    /// Render soft line breaks, the line endings kept in [`Text`] values, like [`Break`]
+    /// nodes, as chat applications do, defaults to `false`.
+    pub hard_breaks: bool,
+    /// `rel` attribute of links to external urls, such as `"nofollow noopener"`, defaults to
+    /// `None`. Urls are external if they have a scheme or start with `//`.
+    pub link_rel: Option<String>,
+    /// `target` attribute of links to external urls, such as `"_blank"`, defaults to `None`.
+    pub link_target: Option<String>,
+    /// Add `id` attributes to headings, the unique [slugs](crate::ast::slug) of their text,
+    /// defaults to `false`.
+    pub heading_ids: bool,
+    /// `class` attributes of the elements of some node kinds, such as `"highlight"` for
+    /// [`NodeKind::Code`], defaults to none. The class of code goes on the first element
+    /// returned by the [`CodeRenderer`].
+    pub classes: BTreeMap<NodeKind, String>,
+    /// Overrides the rendering of some nodes, defaults to `None`.
+    pub node_renderer: Option<Arc<dyn NodeRenderer>>,
+    /// Resolve the urls of links and images against this url, defaults to `None`. See
+    /// [`resolve_url`].
+    pub base_url: Option<String>,
+    /// Replace the `.md` extension of relative urls of links and images with this, such as
+    /// `".html"` or `"/"`, before resolving them, defaults to `None`. See
+    /// [`replace_md_extension`].
+    pub md_extension: Option<String>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        HtmlOptions {
+            allow_dangerous_html: false,
+            escape_html: true,
+            allow_dangerous_protocols: false,
+            code_renderer: None,
+            hard_breaks: false,
+            link_rel: None,
+            link_target: None,
+            heading_ids: false,
+            classes: BTreeMap::new(),
+            node_renderer: None,
+            base_url: None,
+            md_extension: None,
+        }
+    }
+}
+
+impl fmt::Debug for HtmlOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HtmlOptions")
+            .field("allow_dangerous_html", &self.allow_dangerous_html)
+            .field("escape_html", &self.escape_html)
+            .field("allow_dangerous_protocols", &self.allow_dangerous_protocols)
+            .field(
+                "code_renderer",
+                &self.code_renderer.as_ref().map(|_| "dyn CodeRenderer"),
+            )
+            .field("hard_breaks", &self.hard_breaks)
+            .field("link_rel", &self.link_rel)
+            .field("link_target", &self.link_target)
+            .field("heading_ids", &self.heading_ids)
+            .field("classes", &self.classes)
+            .field(
+                "node_renderer",
+                &self.node_renderer.as_ref().map(|_| "dyn NodeRenderer"),
+            )
+            .field("base_url", &self.base_url)
+            .field("md_extension", &self.md_extension)
+            .finish()
+    }
+}
+
+/// Code and node renderers are equal when they are the same instance.
+impl PartialEq for HtmlOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.allow_dangerous_html == other.allow_dangerous_html
+            && self.escape_html == other.escape_html
+            && self.allow_dangerous_protocols == other.allow_dangerous_protocols
+            && match (&self.code_renderer, &other.code_renderer) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+            && self.hard_breaks == other.hard_breaks
+            && self.link_rel == other.link_rel
+            && self.link_target == other.link_target
+            && self.heading_ids == other.heading_ids
+            && self.classes == other.classes
+            && match (&self.node_renderer, &other.node_renderer) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+            && self.base_url == other.base_url
+            && self.md_extension == other.md_extension
+    }
+}
+
+impl Eq for HtmlOptions {}
+
+/// Renders code to HTML, the extension point of syntax highlighters.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use markdown_rs::{
+///     ast::Code,
+///     html::{CodeRenderer, HtmlOptions},
+///     parser::Parser,
+/// };
+///
+/// struct Shouting;
+///
+/// impl CodeRenderer for Shouting {
+///     fn render_code(&self, code: &Code) -> String {
+///         format!("<pre>{}</pre>\n", code.value.to_uppercase())
+///     }
+/// }
+///
+/// let options = HtmlOptions {
+///     code_renderer: Some(Arc::new(Shouting)),
+///     ..Default::default()
+/// };
+///
+/// let document = Parser::new("```\nhello\n```").parse().unwrap();
+///
+/// assert_eq!(document.to_html(&options), "<pre>HELLO</pre>\n");
+/// ```
+pub trait CodeRenderer: Send + Sync {
+    /// Returns the HTML of a code block, the output is trusted and emitted verbatim.
+    fn render_code(&self, code: &Code) -> String;
+
+    /// Returns the HTML of inline code, defaults to an escaped `<code>` element.
+    fn render_inline_code(&self, code: &InlineCode) -> String {
+        let mut out = String::from("<code>");
+
+        escape_into(&mut out, &code.value);
+
+        out.push_str("</code>");
+
+        out
+    }
+}
+
+/// Escaped `<pre><code class="language-x">` output of the CommonMark reference implementation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DefaultCodeRenderer;
+
+impl CodeRenderer for DefaultCodeRenderer {
+    fn render_code(&self, code: &Code) -> String {
+        let mut out = String::from("<pre><code");
+
+        if let Some(lang) = &code.lang {
+            out.push_str(" class=\"language-");
+            escape_into(&mut out, lang);
+            out.push('"');
+        }
+
+        out.push('>');
+        escape_into(&mut out, &code.value);
+
+        if !code.value.is_empty() {
+            out.push('\n');
+        }
+
+        out.push_str("</code></pre>\n");
+
+        out
+    }
+}
+
+/// Whether a [`NodeRenderer`] rendered a node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Handled {
+    /// The node was rendered, children included.
+    Yes,
+    /// The node is left to the default rendering.
+    No,
+}
+
+/// Overrides the rendering of nodes, the extension point of themes.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use markdown_rs::{
+///     ast::Node,
+///     html::{Handled, HtmlOptions, NodeRenderer, Renderer},
+///     parser::Parser,
+/// };
+///
+/// struct Admonitions;
+///
+/// impl NodeRenderer for Admonitions {
+///     fn render_node(&self, node: &Node, out: &mut String, renderer: &dyn Renderer) -> Handled {
+///         let Node::Blockquote(blockquote) = node else {
+///             return Handled::No;
+///         };
+///
+///         let note = match blockquote.children.first() {
+///             Some(Node::Paragraph(paragraph)) => matches!(
+///                 paragraph.children.first(),
+///                 Some(Node::Text(text)) if text.value.starts_with("Note:")
+///             ),
+///             _ => false,
+///         };
+///
+///         if !note {
+///             return Handled::No;
+///         }
+///
+///         out.push_str("<aside class=\"admonition\">\n");
+///         renderer.render_children(node, out);
+///         out.push_str("</aside>\n");
+///
+///         Handled::Yes
+///     }
+/// }
+///
+/// let options = HtmlOptions {
+///     node_renderer: Some(Arc::new(Admonitions)),
+///     ..Default::default()
+/// };
+///
+/// let document = Parser::new("> Note: *careful*\n\n> Quote").parse().unwrap();
+///
+/// assert_eq!(
+///     document.to_html(&options),
+///     "<aside class=\"admonition\">\n<p>Note: <em>careful</em></p>\n</aside>\n\
+///      <blockquote>\n<p>Quote</p>\n</blockquote>\n"
+/// );
+/// ```
+pub trait NodeRenderer: Send + Sync {
+    /// Render `node` into `out` and return [`Handled::Yes`], or leave `out` alone and return
+    /// [`Handled::No`] for the default rendering. `renderer` renders the nodes delegated to it,
+    /// their descendants still going through this method.
+    fn render_node(&self, node: &Node, out: &mut String, renderer: &dyn Renderer) -> Handled;
+}
+
+/// The default rendering, as offered to a [`NodeRenderer`].
+pub trait Renderer {
+    /// Render `node` the default way.
+    fn render_default(&self, node: &Node, out: &mut String);
+
+    /// Render the children of `node`, as the default rendering of `node` would.
+    fn render_children(&self, node: &Node, out: &mut String);
+}
+
+impl<'cx> Document<'cx> {
+    /// Render this document to HTML, see [`render`].
+    pub fn to_html(&self, options: &HtmlOptions) -> String {
+        render(self, options)
+    }
+}
+
+/// Render `document` to HTML, following the output format of the CommonMark reference implementation.
+pub fn render(document: &Document, options: &HtmlOptions) -> String {
+    let mut definitions = Definitions::default();
+
+    for child in &document.children {
+        walk(child, &mut definitions);
+    }
+
+    let renderer = SharedRenderer(RefCell::new(HtmlRenderer {
+        options,
+        definitions: definitions.0,
+        footnotes: BTreeMap::new(),
+        slugger: Slugger::new(),
+        stack: vec![Context::Flow],
+        attributes: (None, None),
+        out: String::new(),
+    }));
+
+    let mut out = String::new();
+
+    renderer.render_nodes(&document.children, &mut out, true);
+
+    out
+}
+
+/// [`HtmlRenderer`] shared with the [`NodeRenderer`] of the options, which renders into its
+/// own `out` between two nodes.
+struct SharedRenderer<'a>(RefCell<HtmlRenderer<'a>>);
+
+impl<'a> SharedRenderer<'a> {
+    /// Render `nodes` into `out`, asking the node renderer first unless `roots` is `false`:
+    /// descendants always go through it.
+    fn render_nodes(&self, nodes: &[Node], out: &mut String, roots: bool) {
+        enum Step<'n, 'cx> {
+            Enter(&'n Node<'cx>, bool),
+            Leave(&'n Node<'cx>),
+        }
+
+        let node_renderer = self.0.borrow().options.node_renderer.as_deref();
+
+        mem::swap(out, &mut self.0.borrow_mut().out);
+
+        let mut stack = nodes
+            .iter()
+            .rev()
+            .map(|node| Step::Enter(node, roots))
+            .collect::<Vec<_>>();
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Enter(node, ask) => {
+                    if let Some(node_renderer) = node_renderer.filter(|_| ask) {
+                        let mut out = mem::take(&mut self.0.borrow_mut().out);
+
+                        let handled = node_renderer.render_node(node, &mut out, self);
+
+                        self.0.borrow_mut().out = out;
+
+                        if handled == Handled::Yes {
+                            continue;
+                        }
+                    }
+
+                    let mut renderer = self.0.borrow_mut();
+
+                    renderer.attributes = (data_str(node, "id"), data_str(node, "class"));
+
+                    node.accept(&mut *renderer);
+
+                    if node.is_parent() {
+                        let context = renderer.context(node);
+
+                        renderer.stack.push(context);
+                    }
+
+                    stack.push(Step::Leave(node));
+                    stack.extend(
+                        node.children()
+                            .unwrap_or_default()
+                            .iter()
+                            .rev()
+                            .map(|child| Step::Enter(child, true)),
+                    );
+                }
+                Step::Leave(node) => self.0.borrow_mut().leave(node),
+            }
+        }
+
+        mem::swap(out, &mut self.0.borrow_mut().out);
+    }
+}
+
+impl<'a> Renderer for SharedRenderer<'a> {
+    fn render_default(&self, node: &Node, out: &mut String) {
+        self.render_nodes(slice::from_ref(node), out, false);
+    }
+
+    fn render_children(&self, node: &Node, out: &mut String) {
+        let Some(children) = node.children() else {
+            return;
+        };
+
+        {
+            let mut renderer = self.0.borrow_mut();
+
+            let context = renderer.context(node);
+
+            renderer.stack.push(context);
+        }
+
+        self.render_nodes(children, out, true);
+
+        self.0.borrow_mut().stack.pop();
+    }
+}
+
+/// Collect the first definition of each identifier, used to resolve references.
+#[derive(Default)]
+struct Definitions(BTreeMap<String, (String, Option<String>)>);
+
+impl Visitor for Definitions {
+    fn visit_definition(&mut self, definition: &Definition) {
+        self.0
+            .entry(normalize_identifier(&definition.identifier))
+            .or_insert_with(|| {
+                (
+                    definition.url.to_string(),
+                    definition.title.as_ref().map(|title| title.to_string()),
+                )
+            });
+    }
+}
+
+/// Parent node context of the node being rendered.
+enum Context {
+    /// Content model is flow content.
+    Flow,
+    List {
+        tight: bool,
+    },
+    ListItem,
+    Table {
+        align: Vec<AlignType>,
+        rows: usize,
+    },
+    TableRow {
+        head: bool,
+        cells: usize,
+    },
+    /// Content model is phrasing content.
+    Phrasing,
+}
+
+struct HtmlRenderer<'a> {
+    options: &'a HtmlOptions,
+    definitions: BTreeMap<String, (String, Option<String>)>,
+    /// Footnote numbers in order of first appearance.
+    footnotes: BTreeMap<String, usize>,
+    /// Heading ids handed out so far.
+    slugger: Slugger,
+    stack: Vec<Context>,
+    /// `id` and `class` of the node being rendered from its data, taken by its element.
+    attributes: (Option<String>, Option<String>),
+    out: String,
+}
+
+impl<'a> HtmlRenderer<'a> {
+    /// Start a new line unless already at the start of one.
+    fn cr(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+    }
+
+    fn push(&mut self, value: &str) {
+        self.out.push_str(value);
+    }
+
+    fn push_escaped(&mut self, value: &str) {
+        escape_into(&mut self.out, value);
+    }
+
+    fn code_renderer(&self) -> &'a dyn CodeRenderer {
+        match &self.options.code_renderer {
+            Some(renderer) => renderer.as_ref(),
+            None => &DefaultCodeRenderer,
+        }
+    }
+
+    /// Returns the context of the children of parent `node`, counting it in its table or row.
+    fn context(&mut self, node: &Node) -> Context {
+        match node {
+            Node::Document(_) | Node::Blockquote(_) | Node::FootnoteDefinition(_) => Context::Flow,
+            Node::List(list) => Context::List {
+                tight: list.spread != Some(true)
+                    && list.children.iter().all(|item| match item {
+                        Node::ListItem(item) => item.spread != Some(true),
+                        _ => true,
+                    }),
+            },
+            Node::ListItem(_) => Context::ListItem,
+            Node::Table(table) => Context::Table {
+                align: table.align.clone(),
+                rows: 0,
+            },
+            Node::TableRow(_) => {
+                let head = match self.stack.last_mut() {
+                    Some(Context::Table { rows, .. }) => {
+                        *rows += 1;
+                        *rows == 1
+                    }
+                    _ => false,
+                };
+
+                Context::TableRow { head, cells: 0 }
+            }
+            Node::TableCell(_) => {
+                if let Some(Context::TableRow { cells, .. }) = self.stack.last_mut() {
+                    *cells += 1;
+                }
+
+                Context::Phrasing
+            }
+            // extension nodes only render their children, in the content model of their own.
+            Node::Custom(_) => match self.stack.last() {
+                Some(Context::Phrasing) => Context::Phrasing,
+                _ => Context::Flow,
+            },
+            _ => Context::Phrasing,
+        }
+    }
+
+    /// Push `<tag` and its `class` and `id` attributes: `base` classes followed by the class of
+    /// `kind` in the options and the class of the node data, then the id of the node data.
+    fn open_tag(&mut self, tag: &str, kind: NodeKind, base: &str) {
+        let (id, class) = self.attributes(kind, base);
+
+        self.push("<");
+        self.push(tag);
+
+        if !class.is_empty() {
+            self.push(" class=\"");
+            self.push_escaped(&class);
+            self.push("\"");
+        }
+
+        if let Some(id) = id {
+            self.push(" id=\"");
+            self.push_escaped(&id);
+            self.push("\"");
+        }
+    }
+
+    /// Takes the id of the node data, and returns it with the classes of the element of a
+    /// `kind` node, see [`open_tag`](Self::open_tag).
+    fn attributes(&mut self, kind: NodeKind, base: &str) -> (Option<String>, String) {
+        let (id, data) = mem::take(&mut self.attributes);
+
+        let classes = [
+            Some(base),
+            self.options.classes.get(&kind).map(String::as_str),
+        ]
+        .into_iter()
+        .chain([data.as_deref()])
+        .flatten()
+        .filter(|class| !class.is_empty())
+        .collect::<Vec<_>>();
+
+        (id, classes.join(" "))
+    }
+
+    fn in_tight_list_item(&self) -> bool {
+        matches!(
+            self.stack.as_slice(),
+            [.., Context::List { tight: true }, Context::ListItem]
+        )
+    }
+
+    fn footnote_number(&mut self, identifier: &str) -> usize {
+        let next = self.footnotes.len() + 1;
+
+        *self
+            .footnotes
+            .entry(normalize_identifier(identifier))
+            .or_insert(next)
+    }
+
+    /// Add the class of `kind` in the options and the class and id of the node data to the
+    /// first element of `html`.
+    fn with_class(&mut self, kind: NodeKind, html: String) -> String {
+        let (id, class) = self.attributes(kind, "");
+
+        let name = html.strip_prefix('<').map_or(0, |rest| {
+            rest.find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len())
+        });
+
+        if name == 0 || (class.is_empty() && id.is_none()) {
+            return html;
+        }
+
+        let mut out = String::with_capacity(html.len() + class.len() + 9);
+
+        out.push_str(&html[..1 + name]);
+
+        if !class.is_empty() {
+            out.push_str(" class=\"");
+            escape_into(&mut out, &class);
+            out.push('"');
+        }
+
+        if let Some(id) = id {
+            out.push_str(" id=\"");
+            escape_into(&mut out, &id);
+            out.push('"');
+        }
+
+        out.push_str(&html[1 + name..]);
+
+        out
+    }
+
+    /// Push `url` as an attribute value, nothing if its protocol isn't allowed.
+    fn push_url(&mut self, url: &str) {
+        let options = self.options;
+
+        let mut url = Cow::Borrowed(url);
+
+        if let Some(replacement) = &options.md_extension {
+            if let Some(replaced) = replace_md_extension(&url, replacement) {
+                url = Cow::Owned(replaced);
+            }
+        }
+
+        if let Some(base) = &options.base_url {
+            url = Cow::Owned(resolve_url(base, &url));
+        }
+
+        if options.allow_dangerous_protocols || !is_dangerous_protocol(&url) {
+            self.push(&normalize_url(&url));
+        }
+    }
+
+    fn open_link(&mut self, kind: NodeKind, url: &str, title: Option<&str>) {
+        let options = self.options;
+
+        self.open_tag("a", kind, "");
+        self.push(" href=\"");
+        self.push_url(url);
+        self.push("\"");
+
+        if let Some(title) = title {
+            self.push(" title=\"");
+            self.push_escaped(title);
+            self.push("\"");
+        }
+
+        if url.starts_with("//") || has_scheme(url) {
+            for (name, value) in [("rel", &options.link_rel), ("target", &options.link_target)] {
+                if let Some(value) = value {
+                    self.push(&format!(" {}=\"", name));
+                    self.push_escaped(value);
+                    self.push("\"");
+                }
+            }
+        }
+
+        self.push(">");
+    }
+
+    fn image(&mut self, kind: NodeKind, url: &str, alt: &str, title: Option<&str>) {
+        self.open_tag("img", kind, "");
+        self.push(" src=\"");
+        self.push_url(url);
+        self.push("\" alt=\"");
+        self.push_escaped(alt);
+        self.push("\"");
+
+        if let Some(title) = title {
+            self.push(" title=\"");
+            self.push_escaped(title);
+            self.push("\"");
+        }
+
+        self.push(" />");
+    }
+
+    /// Render the source text of a reference without definition.
+    fn reference_suffix(&mut self, identifier: &str, label: Option<&str>, kind: &ReferenceType) {
+        match kind {
+            ReferenceType::Full => {
+                self.push("][");
+                self.push_escaped(label.unwrap_or(identifier));
+                self.push("]");
+            }
+            ReferenceType::Collapsed => self.push("][]"),
+            ReferenceType::Shortcut => self.push("]"),
+        }
+    }
+}
+
+impl<'a> Visitor for HtmlRenderer<'a> {
+    fn visit_heading(&mut self, heading: &Heading) {
+        // an id in the node data replaces the generated one.
+        let has_id = self.attributes.0.is_some();
+
+        self.cr();
+        self.open_tag(&format!("h{}", heading.depth), NodeKind::Heading, "");
+
+        if self.options.heading_ids && !has_id {
+            let id = self.slugger.slug(&heading_text(heading));
+
+            self.push(" id=\"");
+            self.push_escaped(&id);
+            self.push("\"");
+        }
+
+        self.push(">");
+    }
+
+    fn visit_thematic_break(&mut self, _: &ThematicBreak) {
+        self.cr();
+        self.open_tag("hr", NodeKind::ThematicBreak, "");
+        self.push(" />\n");
+    }
+
+    fn visit_blockquote(&mut self, _: &Blockquote) {
+        self.cr();
+        self.open_tag("blockquote", NodeKind::Blockquote, "");
+        self.push(">\n");
+    }
+
+    fn visit_list(&mut self, list: &List) {
+        self.cr();
+
+        if list.ordered == Some(true) {
+            self.open_tag("ol", NodeKind::List, "");
+
+            match list.start {
+                Some(start) if start != 1 => self.push(&format!(" start=\"{}\">\n", start)),
+                _ => self.push(">\n"),
+            }
+        } else {
+            self.open_tag("ul", NodeKind::List, "");
+            self.push(">\n");
+        }
+    }
+
+    fn visit_list_item(&mut self, item: &ListItem) {
+        self.cr();
+        self.open_tag("li", NodeKind::ListItem, "");
+        self.push(">");
+
+        match item.checked {
+            Some(true) => self.push("<input type=\"checkbox\" checked=\"\" disabled=\"\" /> "),
+            Some(false) => self.push("<input type=\"checkbox\" disabled=\"\" /> "),
+            None => {}
+        }
+    }
+
+    fn visit_code(&mut self, code: &Code) {
+        let html = self.code_renderer().render_code(code);
+
+        let html = self.with_class(NodeKind::Code, html);
+
+        self.cr();
+        self.push(&html);
+        self.cr();
+    }
+
+    fn visit_text(&mut self, text: &Text) {
+        if !self.options.hard_breaks {
+            self.push_escaped(&text.value);
+            return;
+        }
+
+        for (index, line) in text.value.split('\n').enumerate() {
+            if index > 0 {
+                self.push("<br />\n");
+            }
+
+            self.push_escaped(line);
+        }
+    }
+
+    fn visit_emphasis(&mut self, _: &Emphasis) {
+        self.open_tag("em", NodeKind::Emphasis, "");
+        self.push(">");
+    }
+
+    fn visit_strong(&mut self, _: &Strong) {
+        self.open_tag("strong", NodeKind::Strong, "");
+        self.push(">");
+    }
+
+    fn visit_inline_code(&mut self, code: &InlineCode) {
+        let html = self.code_renderer().render_inline_code(code);
+
+        let html = self.with_class(NodeKind::InlineCode, html);
+
+        self.push(&html);
+    }
+
+    fn visit_break(&mut self, _: &Break) {
+        self.open_tag("br", NodeKind::Break, "");
+        self.push(" />\n");
+    }
+
+    fn visit_link(&mut self, link: &Link) {
+        self.open_link(NodeKind::Link, &link.url, link.title.as_deref());
+    }
+
+    fn visit_link_reference(&mut self, reference: &LinkReference) {
+        match self
+            .definitions
+            .get(&normalize_identifier(&reference.identifier))
+            .cloned()
+        {
+            Some((url, title)) => self.open_link(NodeKind::LinkReference, &url, title.as_deref()),
+            None => self.push("["),
+        }
+    }
+
+    fn visit_image(&mut self, image: &Image) {
+        self.image(
+            NodeKind::Image,
+            &image.url,
+            image.alt.as_deref().unwrap_or_default(),
+            image.title.as_deref(),
+        );
+    }
+
+    fn visit_image_reference(&mut self, reference: &ImageReference) {
+        let alt = reference.alt.as_deref().unwrap_or_default();
+
+        match self
+            .definitions
+            .get(&normalize_identifier(&reference.identifier))
+            .cloned()
+        {
+            Some((url, title)) => self.image(NodeKind::ImageReference, &url, alt, title.as_deref()),
+            None => {
+                self.push("![");
+                self.push_escaped(alt);
+                self.reference_suffix(
+                    &reference.identifier,
+                    reference.label.as_deref(),
+                    &reference.reference_type,
+                );
+            }
+        }
+    }
+
+    fn visit_paragraph(&mut self, _: &Paragraph) {
+        if !self.in_tight_list_item() {
+            self.cr();
+            self.open_tag("p", NodeKind::Paragraph, "");
+            self.push(">");
+        }
+    }
+
+    fn visit_delete(&mut self, _: &Delete) {
+        self.open_tag("del", NodeKind::Delete, "");
+        self.push(">");
+    }
+
+    fn visit_footnote_definition(&mut self, definition: &FootnoteDefinition) {
+        let number = self.footnote_number(&definition.identifier);
+
+        self.cr();
+        self.open_tag("div", NodeKind::FootnoteDefinition, "footnote-definition");
+        self.push(" id=\"fn-");
+        self.push_escaped(&normalize_identifier(&definition.identifier));
+        self.push(&format!(
+            "\"><sup class=\"footnote-definition-label\">{}</sup>\n",
+            number
+        ));
+    }
+
+    fn visit_footnote_reference(&mut self, reference: &FootnoteReference) {
+        let number = self.footnote_number(&reference.identifier);
+
+        self.open_tag("sup", NodeKind::FootnoteReference, "footnote-reference");
+        self.push("><a href=\"#fn-");
+        self.push_escaped(&normalize_identifier(&reference.identifier));
+        self.push(&format!("\">{}</a></sup>", number));
+    }
+
+    fn visit_table(&mut self, _: &Table) {
+        self.cr();
+        self.open_tag("table", NodeKind::Table, "");
+        self.push(">\n");
+    }
+
+    fn visit_table_row(&mut self, _: &TableRow) {
+        let index = match self.stack.last() {
+            Some(Context::Table { rows, .. }) => *rows,
+            _ => 1,
+        };
+
+        match index {
+            0 => self.push("<thead>\n"),
+            1 => self.push("<tbody>\n"),
+            _ => {}
+        }
+
+        self.open_tag("tr", NodeKind::TableRow, "");
+        self.push(">\n");
+    }
+
+    fn visit_table_cell(&mut self, _: &TableCell) {
+        let (head, column) = match self.stack.last() {
+            Some(Context::TableRow { head, cells }) => (*head, *cells),
+            _ => (false, 0),
+        };
+
+        let align = match self.stack.iter().rev().nth(1) {
+            Some(Context::Table { align, .. }) => match align.get(column) {
+                Some(AlignType::Left) => " align=\"left\"",
+                Some(AlignType::Right) => " align=\"right\"",
+                Some(AlignType::Center) => " align=\"center\"",
+                Some(AlignType::None) | None => "",
+            },
+            _ => "",
+        };
+
+        self.open_tag(if head { "th" } else { "td" }, NodeKind::TableCell, "");
+        self.push(align);
+        self.push(">");
+    }
+
+    fn visit_html(&mut self, html: &Html) {
+        let block = matches!(
+            self.stack.last(),
+            Some(Context::Flow | Context::ListItem) | None
+        );
+
+        if block {
+            self.cr();
+        }
+
+        if self.options.allow_dangerous_html {
+            self.push(&html.value);
+        } else if self.options.escape_html {
+            self.push_escaped(&html.value);
+        } else {
+            self.push("<!-- raw HTML omitted -->");
+        }
+
+        if block {
+            self.cr();
+        }
+    }
+
+    fn leave(&mut self, node: &Node) {
+        if node.is_parent() {
+            self.stack.pop();
+        }
+
+        match node {
+            Node::Heading(heading) => self.push(&format!("</h{}>\n", heading.depth)),
+            Node::Blockquote(_) => {
+                self.cr();
+                self.push("</blockquote>\n");
+            }
+            Node::List(list) => {
+                self.cr();
+
+                if list.ordered == Some(true) {
+                    self.push("</ol>\n");
+                } else {
+                    self.push("</ul>\n");
+                }
+            }
+            Node::ListItem(_) => self.push("</li>\n"),
+            Node::Paragraph(_) if !self.in_tight_list_item() => self.push("</p>\n"),
+            Node::Emphasis(_) => self.push("</em>"),
+            Node::Strong(_) => self.push("</strong>"),
+            Node::Delete(_) => self.push("</del>"),
+            Node::Link(_) => self.push("</a>"),
+            Node::LinkReference(reference) => {
+                if self
+                    .definitions
+                    .contains_key(&normalize_identifier(&reference.identifier))
+                {
+                    self.push("</a>");
+                } else {
+                    self.reference_suffix(
+                        &reference.identifier,
+                        reference.label.as_deref(),
+                        &reference.reference_type,
+                    );
+                }
+            }
+            Node::FootnoteDefinition(_) => {
+                self.cr();
+                self.push("</div>\n");
+            }
+            Node::Table(table) => {
+                if table.children.len() > 1 {
+                    self.push("</tbody>\n");
+                }
+
+                self.push("</table>\n");
+            }
+            Node::TableRow(_) => {
+                self.push("</tr>\n");
+
+                if let Some(Context::Table { rows: 1, .. }) = self.stack.last() {
+                    self.push("</thead>\n");
+                }
+            }
+            Node::TableCell(_) => {
+                let head = matches!(
+                    self.stack.last(),
+                    Some(Context::TableRow { head: true, .. })
+                );
+
+                self.push(if head { "</th>\n" } else { "</td>\n" });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Escape HTML special characters of text content and attribute values.
+/// Returns the `name` entry of the data of `node`, if a string.
+fn data_str(node: &Node, name: &str) -> Option<String> {
+    let value = node.data()?.get(name)?;
+
+    #[cfg(feature = "json")]
+    let value = value.as_str()?;
+
+    Some(value.to_string())
+}
+
+fn escape_into(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Returns true if `url` runs script or embeds content: `javascript:`, `vbscript:` and `data:`
+/// urls.
+fn is_dangerous_protocol(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return false;
+    };
+
+    // browsers skip whitespace and control characters in schemes.
+    let scheme = scheme
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    matches!(scheme.as_str(), "javascript" | "vbscript" | "data")
+}
+
+/// Percent-encode `url` for use in an `href` or `src` attribute, existing escapes are kept.
+fn normalize_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+
+    let bytes = url.as_bytes();
+
+    for (index, c) in url.char_indices() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '\'' => out.push_str("&#x27;"),
+            '%' if bytes.len() > index + 2
+                && bytes[index + 1].is_ascii_hexdigit()
+                && bytes[index + 2].is_ascii_hexdigit() =>
+            {
+                out.push('%')
+            }
+            c if c.is_ascii_alphanumeric() || ";/?:@=+$,-_.!~*()#".contains(c) => out.push(c),
+            c => {
+                let mut buf = [0; 4];
+
+                for byte in c.encode_utf8(&mut buf).bytes() {
+                    out.push_str(&format!("%{:02X}", byte));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::builder::DocumentBuilder, parser::Parser};
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    fn html(document: &Document) -> String {
+        render(document, &HtmlOptions::default())
+    }
+
+    #[test]
+    fn test_spec_headings() {
+        // CommonMark spec example 62
+        let document = Parser::new("# foo\n## foo\n### foo\n#### foo\n##### foo\n###### foo")
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            html(&document),
+            "<h1>foo</h1>\n<h2>foo</h2>\n<h3>foo</h3>\n<h4>foo</h4>\n<h5>foo</h5>\n<h6>foo</h6>\n"
+        );
+    }
+
+    #[test]
+    fn test_spec_phrasing() {
+        // CommonMark spec example 350: `*foo bar*`
+        let document = DocumentBuilder::new()
+            .paragraph(|p| {
+                p.emphasis(|e| {
+                    e.text("foo bar");
+                });
+            })
+            .finish()
+            .unwrap();
+
+        assert_eq!(html(&document), "<p><em>foo bar</em></p>\n");
+
+        // CommonMark spec example 482: `[link](/uri "title")`
+        let document = Document {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![Node::from(Link {
+                    children: vec![text("link")],
+                    url: "/uri".into(),
+                    title: Some("title".into()),
+                    data: None,
+                })],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert_eq!(
+            html(&document),
+            "<p><a href=\"/uri\" title=\"title\">link</a></p>\n"
+        );
+
+        // CommonMark spec example 571: `![foo](/url "title")`
+        let document = DocumentBuilder::new()
+            .paragraph(|p| {
+                p.image("/url", "foo");
+            })
+            .finish()
+            .unwrap();
+
+        assert_eq!(html(&document), "<p><img src=\"/url\" alt=\"foo\" /></p>\n");
+
+        // CommonMark spec example 502: `[link](foo%20b&auml;)`
+        let document = DocumentBuilder::new()
+            .paragraph(|p| {
+                p.link("foo%20bä", |l| {
+                    l.text("link");
+                });
+            })
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            html(&document),
+            "<p><a href=\"foo%20b%C3%A4\">link</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_spec_escape() {
+        // text that looks like markup is escaped, not interpreted.
+        let document = Document {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![text("<br/> not a tag\n&ouml; not a character entity")],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert_eq!(
+            html(&document),
+            "<p>&lt;br/&gt; not a tag\n&amp;ouml; not a character entity</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_spec_lists() {
+        // CommonMark spec example 306: tight list with a nested list
+        let document = DocumentBuilder::new()
+            .list(false, |items| {
+                items
+                    .item(|flow| {
+                        flow.paragraph(|p| {
+                            p.text("a");
+                        })
+                        .list(false, |items| {
+                            items.item(|flow| {
+                                flow.paragraph(|p| {
+                                    p.text("b");
+                                });
+                            });
+                        });
+                    })
+                    .item(|_| {});
+            })
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            html(&document),
+            "<ul>\n<li>a\n<ul>\n<li>b</li>\n</ul>\n</li>\n<li></li>\n</ul>\n"
+        );
+
+        // CommonMark spec example 301: loose ordered list starting at 3
+        let mut document = DocumentBuilder::new()
+            .list(true, |items| {
+                items
+                    .start(3)
+                    .item(|flow| {
+                        flow.code(None, "b");
+                    })
+                    .item(|flow| {
+                        flow.blockquote(|quote| {
+                            quote.paragraph(|p| {
+                                p.text("c");
+                            });
+                        });
+                    });
+            })
+            .finish()
+            .unwrap();
+
+        if let Node::List(list) = &mut document.children[0] {
+            list.spread = Some(true);
+        }
+
+        assert_eq!(
+            html(&document),
+            "<ol start=\"3\">\n<li>\n<pre><code>b\n</code></pre>\n</li>\n<li>\n<blockquote>\n<p>c</p>\n</blockquote>\n</li>\n</ol>\n"
+        );
+    }
+
+    #[test]
+    fn test_spec_code() {
+        // CommonMark spec example 142: fenced code with info string
+        let document = DocumentBuilder::new()
+            .code(Some("ruby"), "def foo(x)\n  return 3\nend")
+            .thematic_break()
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            html(&document),
+            "<pre><code class=\"language-ruby\">def foo(x)\n  return 3\nend\n</code></pre>\n<hr />\n"
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        let document = Document {
+            children: vec![
+                Node::Paragraph(Paragraph {
+                    children: vec![
+                        Node::from(LinkReference {
+                            children: vec![text("Foo")],
+                            identifier: "foo".into(),
+                            label: Some("Foo".into()),
+                            reference_type: ReferenceType::Shortcut,
+                            data: None,
+                        }),
+                        text(" "),
+                        Node::from(LinkReference {
+                            children: vec![text("bar")],
+                            identifier: "bar".into(),
+                            label: Some("bar".into()),
+                            reference_type: ReferenceType::Collapsed,
+                            data: None,
+                        }),
+                    ],
+                    data: None,
+                }),
+                Node::from(Definition {
+                    identifier: "FOO".into(),
+                    label: Some("FOO".into()),
+                    url: "/url".into(),
+                    title: Some("t\"".into()),
+                    data: None,
+                }),
+            ],
+            data: None,
+        };
+
+        assert_eq!(
+            html(&document),
+            "<p><a href=\"/url\" title=\"t&quot;\">Foo</a> [bar][]</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_soft_breaks() {
+        let document = Parser::new("one \n  *two*\nthree\\\nfour").parse().unwrap();
+
+        let Node::Paragraph(paragraph) = &document.children[0] else {
+            unreachable!()
+        };
+
+        assert_eq!(paragraph.children[0], text("one\n"));
+        assert_eq!(
+            document.to_markdown(&Default::default()),
+            "one\n*two*\nthree\\\nfour\n"
+        );
+        assert_eq!(
+            document.to_text(&Default::default()),
+            "one\ntwo\nthree\nfour"
+        );
+        assert_eq!(
+            html(&document),
+            "<p>one\n<em>two</em>\nthree<br />\nfour</p>\n"
+        );
+
+        let options = HtmlOptions {
+            hard_breaks: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&document, &options),
+            "<p>one<br />\n<em>two</em><br />\nthree<br />\nfour</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_raw_html() {
+        let document = Document {
+            children: vec![
+                Node::Html(Html {
+                    value: "<div>".into(),
+                    data: None,
+                }),
+                Node::Paragraph(Paragraph {
+                    children: vec![
+                        text("a "),
+                        Node::Html(Html {
+                            value: "<b>".into(),
+                            data: None,
+                        }),
+                    ],
+                    data: None,
+                }),
+            ],
+            data: None,
+        };
+
+        assert_eq!(html(&document), "&lt;div&gt;\n<p>a &lt;b&gt;</p>\n");
+
+        let options = HtmlOptions {
+            allow_dangerous_html: true,
+            ..Default::default()
+        };
+
+        assert_eq!(render(&document, &options), "<div>\n<p>a <b></p>\n");
+
+        let options = HtmlOptions {
+            escape_html: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&document, &options),
+            "<!-- raw HTML omitted -->\n<p>a <!-- raw HTML omitted --></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_dangerous_input() {
+        let document = Parser::new(
+            "<script>alert(1)</script>\n\n[a](javascript:alert(1)) [b](JavaScript:x) \
+             ![c](data:text/html,x) [d](vbscript:x) [e](java%73cript:x)",
+        )
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            html(&document),
+            "&lt;script&gt;alert(1)&lt;/script&gt;\n<p><a href=\"\">a</a> <a href=\"\">b</a> \
+             <img src=\"\" alt=\"c\" /> <a href=\"\">d</a> \
+             <a href=\"java%73cript:x\">e</a></p>\n"
+        );
+
+        let options = HtmlOptions {
+            allow_dangerous_protocols: true,
+            ..Default::default()
+        };
+
+        assert!(render(&document, &options).contains("<a href=\"javascript:alert(1)\">a</a>"));
+    }
+
+    #[test]
+    fn test_link_attributes() {
+        let document =
+            Parser::new("[a](https://a.example) [b](/b) [c](#c) [d](//d.example) <mailto:e@f>")
+                .parse()
+                .unwrap();
+
+        let options = HtmlOptions {
+            link_rel: Some("nofollow noopener".into()),
+            link_target: Some("_blank".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&document, &options),
+            "<p><a href=\"https://a.example\" rel=\"nofollow noopener\" target=\"_blank\">a</a> \
+             <a href=\"/b\">b</a> <a href=\"#c\">c</a> \
+             <a href=\"//d.example\" rel=\"nofollow noopener\" target=\"_blank\">d</a> \
+             <a href=\"mailto:e@f\" rel=\"nofollow noopener\" target=\"_blank\">mailto:e@f</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let row = |values: &[&'static str]| {
+            Node::TableRow(TableRow {
+                children: values
+                    .iter()
+                    .map(|value| {
+                        Node::TableCell(TableCell {
+                            children: vec![text(value)],
+                            data: None,
+                        })
+                    })
+                    .collect(),
+                data: None,
+            })
+        };
+
+        let document = Document {
+            children: vec![Node::Table(Table {
+                children: vec![row(&["a", "b"]), row(&["c", "d"])],
+                align: vec![AlignType::None, AlignType::Center],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert_eq!(
+            html(&document),
+            "<table>\n<thead>\n<tr>\n<th>a</th>\n<th align=\"center\">b</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>c</td>\n<td align=\"center\">d</td>\n</tr>\n</tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn test_default_code_renderer() {
+        let code = Code {
+            value: "a < b".into(),
+            lang: Some("x\"y".into()),
+            meta: None,
+            data: None,
+        };
+
+        assert_eq!(
+            DefaultCodeRenderer.render_code(&code),
+            "<pre><code class=\"language-x&quot;y\">a &lt; b\n</code></pre>\n"
+        );
+
+        let code = InlineCode {
+            value: "<b>".into(),
+            data: None,
+        };
+
+        assert_eq!(
+            DefaultCodeRenderer.render_inline_code(&code),
+            "<code>&lt;b&gt;</code>"
+        );
+    }
+
+    #[test]
+    fn test_code_renderer() {
+        use std::sync::Mutex;
+
+        /// Records what it is asked to render.
+        #[derive(Default)]
+        struct Mock(Mutex<Vec<(Option<String>, String)>>);
+
+        impl CodeRenderer for Mock {
+            fn render_code(&self, code: &Code) -> String {
+                self.0.lock().unwrap().push((
+                    code.lang.as_ref().map(|lang| lang.to_string()),
+                    code.value.to_string(),
+                ));
+
+                "<pre>block</pre>".to_owned()
+            }
+
+            fn render_inline_code(&self, code: &InlineCode) -> String {
+                self.0.lock().unwrap().push((None, code.value.to_string()));
+
+                "<kbd>inline</kbd>".to_owned()
+            }
+        }
+
+        let mock = Arc::new(Mock::default());
+
+        let options = HtmlOptions {
+            code_renderer: Some(mock.clone()),
+            ..Default::default()
+        };
+
+        let document = Parser::new("```rust title=\"a\"\nfn main() {}\n```\n\na `b` c\n")
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            render(&document, &options),
+            "<pre>block</pre>\n<p>a <kbd>inline</kbd> c</p>\n"
+        );
+
+        assert_eq!(
+            *mock.0.lock().unwrap(),
+            [
+                (Some("rust".to_owned()), "fn main() {}".to_owned()),
+                (None, "b".to_owned())
+            ]
+        );
+
+        assert_eq!(options, options.clone());
+        assert_ne!(options, HtmlOptions::default());
+    }
+
+    #[test]
+    fn test_classes() {
+        let options = HtmlOptions {
+            classes: [
+                (NodeKind::Code, "highlight".to_owned()),
+                (NodeKind::Table, "table table-striped".to_owned()),
+                (NodeKind::FootnoteReference, "note".to_owned()),
+                (NodeKind::Link, "external".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+
+        let document = Parser::new("```rust\nx\n```\n\n| a |\n| - |\n\n[b](/c)")
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            render(&document, &options),
+            "<pre class=\"highlight\"><code class=\"language-rust\">x\n</code></pre>\n\
+             <table class=\"table table-striped\">\n<thead>\n<tr>\n<th>a</th>\n</tr>\n</thead>\n\
+             </table>\n<p><a class=\"external\" href=\"/c\">b</a></p>\n"
+        );
+
+        let document = Document {
+            children: vec![Node::Paragraph(Paragraph {
+                children: vec![Node::FootnoteReference(FootnoteReference {
+                    identifier: "1".into(),
+                    label: None,
+                    data: None,
+                })],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert_eq!(
+            render(&document, &options),
+            "<p><sup class=\"footnote-reference note\"><a href=\"#fn-1\">1</a></sup></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let document = Parser::new("# Intro\n\n## Intro\n\n> # Intro!\n\n## `Use` *it*")
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            html(&document),
+            "<h1>Intro</h1>\n<h2>Intro</h2>\n<blockquote>\n<h1>Intro!</h1>\n</blockquote>\n\
+             <h2><code>Use</code> <em>it</em></h2>\n"
+        );
+
+        let options = HtmlOptions {
+            heading_ids: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&document, &options),
+            "<h1 id=\"intro\">Intro</h1>\n<h2 id=\"intro-1\">Intro</h2>\n<blockquote>\n\
+             <h1 id=\"intro-2\">Intro!</h1>\n</blockquote>\n<h2 id=\"use-it\"><code>Use</code> \
+             <em>it</em></h2>\n"
+        );
+    }
+
+    #[test]
+    fn test_node_data() {
+        /// Sets the id of headings and the class of paragraphs and inline code.
+        struct Annotate;
+
+        impl<'cx> VisitorMut<'cx> for Annotate {
+            fn visit_heading(&mut self, node: &mut Heading<'cx>) -> Transform<'cx> {
+                node.data = Some(Box::new([("id".into(), "top".into())].into()));
+
+                Transform::Keep
+            }
+
+            fn visit_paragraph(&mut self, node: &mut Paragraph<'cx>) -> Transform<'cx> {
+                node.data = Some(Box::new([("class".into(), "lead".into())].into()));
+
+                Transform::Keep
+            }
+
+            fn visit_inline_code(&mut self, node: &mut InlineCode<'cx>) -> Transform<'cx> {
+                node.data = Some(Box::new(
+                    [("class".into(), "x".into()), ("id".into(), "a\"b".into())].into(),
+                ));
+
+                Transform::Keep
+            }
+        }
+
+        let document = Parser::new("# Intro\n\nSome `code`.\n").parse().unwrap();
+
+        let mut node = Node::Document(document);
+
+        walk_mut(&mut node, &mut Annotate);
+
+        let Node::Document(document) = &node else {
+            unreachable!();
+        };
+
+        let options = HtmlOptions {
+            heading_ids: true,
+            classes: [(NodeKind::Paragraph, "text".to_owned())].into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(document, &options),
+            "<h1 id=\"top\">Intro</h1>\n<p class=\"text lead\">Some \
+             <code class=\"x\" id=\"a&quot;b\">code</code>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_node_renderer() {
+        /// Renders strong as `<b>` and asks for the default rendering of tables, wrapped.
+        struct Custom;
+
+        impl NodeRenderer for Custom {
+            fn render_node(
+                &self,
+                node: &Node,
+                out: &mut String,
+                renderer: &dyn Renderer,
+            ) -> Handled {
+                match node {
+                    Node::Strong(_) => {
+                        out.push_str("<b>");
+                        renderer.render_children(node, out);
+                        out.push_str("</b>");
+                    }
+                    Node::Table(_) => {
+                        out.push_str("<div class=\"scroll\">\n");
+                        renderer.render_default(node, out);
+                        out.push_str("</div>\n");
+                    }
+                    _ => return Handled::No,
+                }
+
+                Handled::Yes
+            }
+        }
+
+        let options = HtmlOptions {
+            node_renderer: Some(Arc::new(Custom)),
+            ..Default::default()
+        };
+
+        let document = Parser::new("- a **b *c* d**\n\n| **e** | f |\n| - | - |\n| g |")
+            .parse()
+            .unwrap();
+
+        // the descendants of overridden nodes go through the node renderer too.
+        assert_eq!(
+            render(&document, &options),
+            "<ul>\n<li>a <b>b <em>c</em> d</b></li>\n</ul>\n<div class=\"scroll\">\n<table>\n\
+             <thead>\n<tr>\n<th><b>e</b></th>\n<th>f</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n\
+             <td>g</td>\n<td></td>\n</tr>\n</tbody>\n</table>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn test_base_url() {
+        let document = Parser::new(
+            "[a](../a.md#usage) [b] [c](#c) [d](https://example.org/d.md) ![e](<e f.png>)\n\n\
+             [b]: ./b.md",
+        )
+        .parse()
+        .unwrap();
+
+        let options = HtmlOptions {
+            base_url: Some("https://example.com/docs/guide/".into()),
+            md_extension: Some(".html".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            render(&document, &options),
+            "<p><a href=\"https://example.com/docs/a.html#usage\">a</a> \
+             <a href=\"https://example.com/docs/guide/b.html\">b</a> <a href=\"#c\">c</a> \
+             <a href=\"https://example.org/d.md\">d</a> \
+             <img src=\"https://example.com/docs/guide/e%20f.png\" alt=\"e\" /></p>\n"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! [`CodeRenderer`] highlighting code blocks with [syntect](https://docs.rs/syntect), as inline
+//! styled spans.
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use markdown_rs::{
+//!     html::{syntect::SyntectCodeRenderer, HtmlOptions},
+//!     parser::Parser,
+//! };
+//!
+//! let options = HtmlOptions {
+//!     code_renderer: Some(Arc::new(SyntectCodeRenderer::default())),
+//!     ..Default::default()
+//! };
+//!
+//! let document = Parser::new("```rust\nfn main() {}\n```").parse().unwrap();
+//!
+//! assert!(document.to_html(&options).contains("<span style="));
+//! ```
+
+use ::syntect::{
+    highlighting::{Theme, ThemeSet},
+    html::highlighted_html_for_string,
+    parsing::SyntaxSet,
+};
+
+use super::*;
+
+/// Highlights code blocks whose language syntect knows, other code renders like
+/// [`DefaultCodeRenderer`].
+#[derive(Clone, Debug)]
+pub struct SyntectCodeRenderer {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+impl SyntectCodeRenderer {
+    /// Name of the theme used by [`Default`].
+    pub const DEFAULT_THEME: &'static str = "InspiredGitHub";
+
+    /// Create new [`SyntectCodeRenderer`] highlighting `syntaxes` with `theme`.
+    pub fn new(syntaxes: SyntaxSet, theme: Theme) -> Self {
+        Self { syntaxes, theme }
+    }
+
+    /// Create new [`SyntectCodeRenderer`] with the default syntaxes and the bundled theme
+    /// `name`, returns `None` if no such theme is bundled.
+    pub fn with_theme(name: &str) -> Option<Self> {
+        let theme = ThemeSet::load_defaults().themes.remove(name)?;
+
+        Some(Self::new(SyntaxSet::load_defaults_newlines(), theme))
+    }
+}
+
+impl Default for SyntectCodeRenderer {
+    fn default() -> Self {
+        Self::with_theme(Self::DEFAULT_THEME).expect("bundled theme")
+    }
+}
+
+impl CodeRenderer for SyntectCodeRenderer {
+    fn render_code(&self, code: &Code) -> String {
+        let syntax = code
+            .lang
+            .as_ref()
+            .and_then(|lang| self.syntaxes.find_syntax_by_token(lang));
+
+        let Some(syntax) = syntax else {
+            return DefaultCodeRenderer.render_code(code);
+        };
+
+        let mut value = code.value.to_string();
+
+        value.push('\n');
+
+        highlighted_html_for_string(&value, &self.syntaxes, syntax, &self.theme)
+            .unwrap_or_else(|_| DefaultCodeRenderer.render_code(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let options = HtmlOptions {
+            code_renderer: Some(Arc::new(SyntectCodeRenderer::default())),
+            ..Default::default()
+        };
+
+        let document = Parser::new("```rust\nlet a = \"<b>\";\n```\n\n```nope\n<b>\n```")
+            .parse()
+            .unwrap();
+
+        let html = document.to_html(&options);
+
+        assert!(html.starts_with("<pre style=\"background-color:"));
+        assert!(html.contains("<span style=\"color:"));
+        assert!(html.contains("&lt;b&gt;"));
+        assert!(!html.contains("<b>"));
+        assert!(html.ends_with("<pre><code class=\"language-nope\">&lt;b&gt;\n</code></pre>\n"));
+
+        assert!(SyntectCodeRenderer::with_theme("no such theme").is_none());
+    }
+}
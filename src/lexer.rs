@@ -1,4 +1,9 @@
-use std::{borrow::Cow, ops::Range, str::Chars};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::String,
+    vec::Vec,
+};
+use core::{ops::Range, str::Chars};
 
 use crate::ast::AlignType;
 
@@ -7,11 +12,66 @@ const KEYCHARS: &[char] = [
 ]
 .as_slice();
 
+/// Whitespace of indentation: other Unicode whitespace, such as U+00A0, is plain text, see
+/// [`crate::unicode`].
 const WHITESPACECHARS: &[char] = [' ', '\t'].as_slice();
 
 const LINEBREAKCHARS: &[char] = ['\r', '\n'].as_slice();
 
+/// Options of a [`Lexer`], the default lexes the markdown key chars only.
+///
+/// ```
+/// use markdown_rs::lexer::{Lexer, LexerOptions, Token};
+///
+/// let options = LexerOptions {
+///     extra_keychars: &['%'],
+///     ..Default::default()
+/// };
+///
+/// let tokens = Lexer::with_options("{% tag %}", options).collect::<Vec<_>>();
+///
+/// assert_eq!(tokens[..2], [Token::KeyChar(0..1), Token::KeyChar(1..2)]);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LexerOptions<'a> {
+    /// Chars emitted as [`Token::KeyChar`] on top of the markdown key chars, e.g. `{` and `%`
+    /// of template tags, so that later passes can pick them up.
+    pub extra_keychars: &'a [char],
+    /// Chars read as [`Token::PlainText`] rather than as key chars or their own tokens.
+    /// Whitespace and line breaks are never demoted.
+    pub plain_chars: &'a [char],
+}
+
 /// Transformer for markdown token stream.
+///
+/// The tokens tile the source: each one is non-empty and starts where the previous one ends,
+/// the first at offset 0, or after a leading byte order mark, and the last at the end of the
+/// source. Every byte of the source but the byte order mark belongs to exactly one token,
+/// whatever the [`LexerOptions`], so a highlighter can color the source token by token.
+///
+/// ```
+/// use markdown_rs::lexer::{Lexer, TokenKind};
+///
+/// let source = "# Title\n*text*";
+///
+/// let mut lexer = Lexer::new(source);
+///
+/// let line = lexer.tokenize_line();
+///
+/// assert_eq!(
+///     line.iter().map(|token| token.kind()).collect::<Vec<_>>(),
+///     [
+///         TokenKind::Pounds,
+///         TokenKind::WhiteSpaces,
+///         TokenKind::PlainText,
+///         TokenKind::LineBreaks
+///     ]
+/// );
+/// assert_eq!(&source[line[2].to_range()], "Title");
+///
+/// assert_eq!(lexer.tokenize_line().len(), 3);
+/// assert!(lexer.tokenize_line().is_empty());
+/// ```
 #[derive(Debug)]
 pub struct Lexer<'a> {
     /// Markdown source stream.
@@ -20,6 +80,8 @@ pub struct Lexer<'a> {
     _iter: Chars<'a>,
     /// Lookahead cached next token instance.
     _lookahead: Option<Token>,
+    /// Extra and demoted significant chars.
+    _options: LexerOptions<'a>,
 }
 
 impl<'a> From<&'a str> for Lexer<'a> {
@@ -31,21 +93,38 @@ impl<'a> From<&'a str> for Lexer<'a> {
 impl<'a> Lexer<'a> {
     /// Create new [`Lexer`] from source `S`
     pub fn new(source: &'a str) -> Self {
+        Self::with_options(source, LexerOptions::default())
+    }
+
+    /// Create new [`Lexer`] from source `S` with `options`.
+    ///
+    /// A leading byte order mark is skipped, token ranges are still offsets of `source`.
+    pub fn with_options(source: &'a str, options: LexerOptions<'a>) -> Self {
         Lexer {
             _source: source,
             _lookahead: None,
-            _iter: source.chars(),
+            _iter: source.strip_prefix('\u{feff}').unwrap_or(source).chars(),
+            _options: options,
         }
     }
 
-    /// Rollback lexer cursor to `token` start offset
+    /// Returns the options of this lexer.
+    pub fn options(&self) -> &LexerOptions<'a> {
+        &self._options
+    }
+
+    /// Rollback lexer cursor to `token` start offset, `token` is the next token returned.
+    ///
+    /// A token not within the source, e.g. from another lexer, moves the cursor to the end of
+    /// the source once returned.
     pub fn rollback_to<T: AsRef<Token> + ToOwned<Owned = Token>>(&mut self, token: T) {
         let range = token.as_ref().to_range();
 
-        assert!(range.start < self._source.len());
-        assert!(range.end < self._source.len());
-
-        self._iter = self._source[range.start..].chars();
+        // lexing resumes after the token returned as lookahead.
+        self._iter = match self._source.get(range.end..) {
+            Some(rest) => rest.chars(),
+            None => self._source[self._source.len()..].chars(),
+        };
         self._lookahead = Some(token.to_owned());
     }
 
@@ -58,44 +137,61 @@ impl<'a> Lexer<'a> {
         let start = self.offset();
 
         if let Some(c) = self._iter.next() {
+            if self.is_plain(c) {
+                return self.read_plaintext(start);
+            }
+
+            if self._options.extra_keychars.contains(&c) {
+                return Token::KeyChar(start..start + c.len_utf8());
+            }
+
             match c {
-                '#' => return self.read_pounds(start),
-                '*' => return self.read_asterisks(start),
-                '+' => return self.read_pluses(start),
-                '-' => return self.read_align_type_or_plaintext(start, false),
+                '#' => self.read_pounds(start),
+                '*' => self.read_asterisks(start),
+                '+' => self.read_pluses(start),
+                '-' => self.read_align_type_or_plaintext(start, false),
                 ':' => {
                     let token = self.read_align_type_or_plaintext(start, true);
 
-                    if let Token::PlainText(range) = &token {
-                        let next_token = self.lookahead();
+                    let Token::PlainText(range) = &token else {
+                        return token;
+                    };
 
-                        if let Token::PlainText(next_range) = next_token {
-                            self.next_token();
+                    // merge with the following plain text, rewinding otherwise: a lookahead
+                    // here would clobber the one set by a nested merge.
+                    let rewind = self._iter.clone();
 
-                            return Token::PlainText(range.start..next_range.end);
-                        } else {
-                            return token;
+                    match self.next_token() {
+                        Token::PlainText(next_range) => {
+                            Token::PlainText(range.start..next_range.end)
+                        }
+                        _ => {
+                            self._iter = rewind;
+
+                            token
                         }
-                    } else {
-                        return token;
                     }
                 }
-                '_' => return self.read_underscores(start),
-                '`' => return self.read_backticks(start),
-                ' ' | '\t' => return self.read_whitespaces(start),
-                '\r' | '\n' => return self.read_linebreaks(start),
+                '_' => self.read_underscores(start),
+                '`' => self.read_backticks(start),
+                ' ' | '\t' => self.read_whitespaces(start),
+                '\r' | '\n' => self.read_linebreaks(start),
+                '<' if !self.is_plain(c) => match self.read_html(start) {
+                    Some(token) => token,
+                    None => Token::KeyChar(start..start + 1),
+                },
                 _ => {
                     // if this is a key char
-                    if KEYCHARS.contains(&c) {
-                        return Token::KeyChar(start..start + 1);
+                    if self.is_keychar(c) {
+                        return Token::KeyChar(start..start + c.len_utf8());
                     }
 
-                    return self.read_plaintext(start);
+                    self.read_plaintext(start)
                 }
             }
         } else {
             let offset = self._source.len();
-            return Token::Eof(offset..offset);
+            Token::Eof(offset..offset)
         }
     }
 
@@ -103,7 +199,7 @@ impl<'a> Lexer<'a> {
     fn read_pounds(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| c == '#');
 
-        return Token::Pounds(start..range.end);
+        Token::Pounds(start..range.end)
     }
 
     fn read_align_type_or_plaintext(&mut self, start: usize, has_prefix: bool) -> Token {
@@ -118,7 +214,7 @@ impl<'a> Lexer<'a> {
                 return true;
             }
 
-            return false;
+            false
         });
 
         if has_prefix && range.len() > 1 {
@@ -137,50 +233,87 @@ impl<'a> Lexer<'a> {
             }
         }
 
-        return Token::PlainText(start..range.end);
+        Token::PlainText(start..range.end)
+    }
+
+    /// Reads an html comment, CDATA section, declaration or processing instruction starting
+    /// with the `<` at `start`, up to its end or to the end of the source if unterminated.
+    fn read_html(&mut self, start: usize) -> Option<Token> {
+        let rest = &self._source[start..];
+
+        let (open, close, token): (_, _, fn(Range<usize>) -> Token) = if rest.starts_with("<!--") {
+            // searching from the dashes, as `<!-->` and `<!--->` are complete comments.
+            (2, "-->", Token::Comment)
+        } else if rest.starts_with("<![CDATA[") {
+            (9, "]]>", Token::Cdata)
+        } else if rest[1..].starts_with('!')
+            && rest[2..].starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            (2, ">", Token::Declaration)
+        } else if rest.starts_with("<?") {
+            (2, "?>", Token::ProcessingInstruction)
+        } else {
+            return None;
+        };
+
+        let end = match rest[open..].find(close) {
+            Some(offset) => start + open + offset + close.len(),
+            None => self._source.len(),
+        };
+
+        self._iter = self._source[end..].chars();
+
+        Some(token(start..end))
     }
 
     fn read_asterisks(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| c == '*');
 
-        return Token::Asterisks(start..range.end);
+        Token::Asterisks(start..range.end)
     }
 
     fn read_underscores(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| c == '_');
 
-        return Token::Underscores(start..range.end);
+        Token::Underscores(start..range.end)
     }
 
     fn read_pluses(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| c == '+');
 
-        return Token::Pluses(start..range.end);
+        Token::Pluses(start..range.end)
     }
 
     fn read_backticks(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| c == '`');
 
-        return Token::Backticks(start..range.end);
+        Token::Backticks(start..range.end)
     }
 
     fn read_whitespaces(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| WHITESPACECHARS.contains(&c));
 
-        return Token::WhiteSpaces(start..range.end);
+        Token::WhiteSpaces(start..range.end)
     }
 
     fn read_linebreaks(&mut self, start: usize) -> Token {
         let range = self.read_until(|c| LINEBREAKCHARS.contains(&c));
 
-        return Token::LineBreaks(start..range.end);
+        Token::LineBreaks(start..range.end)
     }
 
     fn read_plaintext(&mut self, start: usize) -> Token {
         let mut escaping = false;
 
+        let options = self._options;
+
+        let is_keychar = |c| {
+            (KEYCHARS.contains(&c) || options.extra_keychars.contains(&c))
+                && !options.plain_chars.contains(&c)
+        };
+
         let range = self.read_until(|c| {
-            if KEYCHARS.contains(&c) {
+            if is_keychar(c) {
                 if c == '\\' && !escaping {
                     escaping = true;
                     return true;
@@ -197,10 +330,61 @@ impl<'a> Lexer<'a> {
                 return false;
             }
 
-            return true;
+            true
         });
 
-        return Token::PlainText(start..range.end);
+        Token::PlainText(start..range.end)
+    }
+
+    /// Returns true if `c` is read as a [`Token::KeyChar`].
+    fn is_keychar(&self, c: char) -> bool {
+        (KEYCHARS.contains(&c) || self._options.extra_keychars.contains(&c)) && !self.is_plain(c)
+    }
+
+    /// Returns true if `c` is demoted to plain text.
+    fn is_plain(&self, c: char) -> bool {
+        self._options.plain_chars.contains(&c)
+            && !WHITESPACECHARS.contains(&c)
+            && !LINEBREAKCHARS.contains(&c)
+    }
+
+    /// Returns the tokens up to the end of the current line, the last one being the
+    /// [`Token::LineBreaks`] ending it, unless the source ends first.
+    ///
+    /// Returns an empty list at the end of the source. Consecutive line breaks are a single
+    /// token, so the returned tokens may span blank lines after the current one, as well as
+    /// the lines of a multi-line token such as [`Token::Comment`].
+    pub fn tokenize_line(&mut self) -> Vec<Token> {
+        let mut tokens = Vec::new();
+
+        for token in self.by_ref() {
+            let end = matches!(token, Token::LineBreaks(_));
+
+            tokens.push(token);
+
+            if end {
+                break;
+            }
+        }
+
+        tokens
+    }
+
+    /// Returns the tokens of `source` with their text, owned, up to the end of the source.
+    ///
+    /// ```
+    /// use markdown_rs::lexer::{Lexer, TokenKind};
+    ///
+    /// let tokens = Lexer::to_token_dump("# a");
+    ///
+    /// assert_eq!(tokens.len(), 3);
+    /// assert_eq!(tokens[0].kind, TokenKind::Pounds);
+    /// assert_eq!(tokens[2].text, "a");
+    /// ```
+    pub fn to_token_dump(source: &str) -> Vec<TokenDump> {
+        Lexer::new(source)
+            .map(|token| TokenDump::new(&token, source))
+            .collect()
     }
 
     /// Parse next token but not moving lexer cursor.
@@ -234,17 +418,116 @@ impl<'a> Lexer<'a> {
 
         while let Some(c) = self._iter.next() {
             if !f(c) {
-                self._iter = self._source[(self.offset() - 1)..].chars();
+                self._iter = self._source[(self.offset() - c.len_utf8())..].chars();
                 return begin..self.offset();
             }
         }
 
-        return begin..self.offset();
+        begin..self.offset()
     }
 
     pub fn offset(&self) -> usize {
         self._source.len() - self._iter.as_str().len()
     }
+
+    /// Returns the whole markdown source of this lexer.
+    pub fn source(&self) -> &'a str {
+        self._source
+    }
+
+    /// Returns the offset of the `]` closing the `[` at `open_at`, without moving the cursor.
+    ///
+    /// Nested pairs are counted and backslash-escaped brackets skipped, so the `]` of
+    /// `[a [b] \] c]` is the last one. Returns `None` if there is no unescaped `[` at `open_at`
+    /// or it is never closed. Code spans and other inline constructs are not recognized, the
+    /// offset only bounds where the link text of the bracket can end.
+    ///
+    /// ```
+    /// use markdown_rs::lexer::Lexer;
+    ///
+    /// let lexer = Lexer::new("![a [b] c](d)");
+    ///
+    /// assert_eq!(lexer.find_matching_bracket(1), Some(9));
+    /// assert_eq!(lexer.find_matching_bracket(0), None);
+    /// ```
+    pub fn find_matching_bracket(&self, open_at: usize) -> Option<usize> {
+        find_matching(self._source, open_at, b'[', b']')
+    }
+
+    /// Returns the offset of the `)` closing the `(` at `open_at`, without moving the cursor.
+    ///
+    /// Parentheses are balanced like in link destinations: nested pairs are counted and
+    /// backslash-escaped ones skipped. Returns `None` if there is no unescaped `(` at `open_at`
+    /// or it is never closed.
+    pub fn find_matching_paren(&self, open_at: usize) -> Option<usize> {
+        find_matching(self._source, open_at, b'(', b')')
+    }
+}
+
+/// Returns the offset of the `close` byte balancing the unescaped `open` byte at `open_at`.
+fn find_matching(source: &str, open_at: usize, open: u8, close: u8) -> Option<usize> {
+    let bytes = source.as_bytes();
+
+    if bytes.get(open_at) != Some(&open) {
+        return None;
+    }
+
+    let escapes = bytes[..open_at]
+        .iter()
+        .rev()
+        .take_while(|c| **c == b'\\')
+        .count();
+
+    if escapes % 2 == 1 {
+        return None;
+    }
+
+    let (end, _) = scan_balanced(bytes, open_at + 1, (open, close), usize::MAX, |_| false)?;
+
+    (bytes.get(end) == Some(&close)).then_some(end)
+}
+
+/// Scans `bytes` from `pos` to the first `close` byte not balanced by an `open` byte after
+/// `pos`, skipping backslash escapes, or to the first byte `stop` returns true for.
+///
+/// Returns the offset scanning ended at, `bytes.len()` if neither was found, and the number of
+/// `open` bytes left unbalanced there. Returns `None` once more than `max_depth` are open.
+pub(crate) fn scan_balanced(
+    bytes: &[u8],
+    pos: usize,
+    (open, close): (u8, u8),
+    max_depth: usize,
+    stop: impl Fn(u8) -> bool,
+) -> Option<(usize, usize)> {
+    let mut end = pos;
+    let mut depth = 0usize;
+
+    while let Some(&c) = bytes.get(end) {
+        match c {
+            b'\\' if bytes.get(end + 1).is_some_and(u8::is_ascii_punctuation) => end += 2,
+            c if c == open => {
+                depth += 1;
+
+                if depth > max_depth {
+                    return None;
+                }
+
+                end += 1;
+            }
+            c if c == close => {
+                if depth == 0 {
+                    break;
+                }
+
+                depth -= 1;
+                end += 1;
+            }
+            c if stop(c) => break,
+            _ => end += 1,
+        }
+    }
+
+    Some((end, depth))
 }
 
 impl<'a> Iterator for Lexer<'a> {
@@ -254,9 +537,9 @@ impl<'a> Iterator for Lexer<'a> {
         let token = self.next_token();
 
         if let Token::Eof(_) = token {
-            return None;
+            None
         } else {
-            return Some(token);
+            Some(token)
         }
     }
 }
@@ -290,9 +573,86 @@ pub enum Token {
     KeyChar(Range<usize>),
     /// Plain text range
     PlainText(Range<usize>),
+    /// Html comment `<!-- ... -->`, to the end of the source if unterminated.
+    Comment(Range<usize>),
+    /// Html CDATA section `<![CDATA[ ... ]]>`, to the end of the source if unterminated.
+    Cdata(Range<usize>),
+    /// Html declaration such as `<!DOCTYPE html>`, to the end of the source if unterminated.
+    Declaration(Range<usize>),
+    /// Html processing instruction `<? ... ?>`, to the end of the source if unterminated.
+    ProcessingInstruction(Range<usize>),
+}
+
+/// Variant of a [`Token`], without its range.
+///
+/// Serialized as its camel-cased name, such as `"pounds"` or `"lineBreaks"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum TokenKind {
+    /// [`Token::Pounds`].
+    Pounds,
+    /// [`Token::Eof`].
+    Eof,
+    /// [`Token::Align`].
+    Align,
+    /// [`Token::GreaterThans`].
+    GreaterThans,
+    /// [`Token::Asterisks`].
+    Asterisks,
+    /// [`Token::Underscores`].
+    Underscores,
+    /// [`Token::Dashes`].
+    Dashes,
+    /// [`Token::Pluses`].
+    Pluses,
+    /// [`Token::Backticks`].
+    Backticks,
+    /// [`Token::LineBreaks`].
+    LineBreaks,
+    /// [`Token::WhiteSpaces`].
+    WhiteSpaces,
+    /// [`Token::KeyChar`].
+    KeyChar,
+    /// [`Token::PlainText`].
+    PlainText,
+    /// [`Token::Comment`].
+    Comment,
+    /// [`Token::Cdata`].
+    Cdata,
+    /// [`Token::Declaration`].
+    Declaration,
+    /// [`Token::ProcessingInstruction`].
+    ProcessingInstruction,
 }
 
 impl Token {
+    /// Returns the variant of this token.
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Pounds(_) => TokenKind::Pounds,
+            Token::Eof(_) => TokenKind::Eof,
+            Token::Align(..) => TokenKind::Align,
+            Token::GreaterThans(_) => TokenKind::GreaterThans,
+            Token::Asterisks(_) => TokenKind::Asterisks,
+            Token::Underscores(_) => TokenKind::Underscores,
+            Token::Dashes(_) => TokenKind::Dashes,
+            Token::Pluses(_) => TokenKind::Pluses,
+            Token::Backticks(_) => TokenKind::Backticks,
+            Token::LineBreaks(_) => TokenKind::LineBreaks,
+            Token::WhiteSpaces(_) => TokenKind::WhiteSpaces,
+            Token::KeyChar(_) => TokenKind::KeyChar,
+            Token::PlainText(_) => TokenKind::PlainText,
+            Token::Comment(_) => TokenKind::Comment,
+            Token::Cdata(_) => TokenKind::Cdata,
+            Token::Declaration(_) => TokenKind::Declaration,
+            Token::ProcessingInstruction(_) => TokenKind::ProcessingInstruction,
+        }
+    }
+
     /// Convert [`Token`] to the [`Range`] object of the source stream.
     pub fn to_range(&self) -> Range<usize> {
         let r = match self {
@@ -309,9 +669,83 @@ impl Token {
             Token::Pluses(r) => r,
             Token::KeyChar(r) => r,
             Token::PlainText(r) => r,
+            Token::Comment(r) => r,
+            Token::Cdata(r) => r,
+            Token::Declaration(r) => r,
+            Token::ProcessingInstruction(r) => r,
         };
 
-        return r.clone();
+        r.clone()
+    }
+}
+
+/// Serialized as an object with the `kind`, `start` and `end` of the token, and the `align`
+/// of a [`Token::Align`].
+#[cfg(feature = "serde")]
+impl serde::Serialize for Token {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+
+        let range = self.to_range();
+
+        let align = match self {
+            Token::Align(_, align) => Some(align),
+            _ => None,
+        };
+
+        let mut token = serializer.serialize_struct("Token", 3 + align.is_some() as usize)?;
+
+        token.serialize_field("kind", &self.kind())?;
+        token.serialize_field("start", &range.start)?;
+        token.serialize_field("end", &range.end)?;
+
+        if let Some(align) = align {
+            token.serialize_field("align", align)?;
+        }
+
+        token.end()
+    }
+}
+
+/// A [`Token`] with its source text, owned so that it can outlive the source, see
+/// [`Lexer::to_token_dump`].
+///
+/// Serialized like [`Token`], with the source text under `text`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenDump {
+    pub kind: TokenKind,
+    /// Byte offset of the token in the source.
+    pub start: usize,
+    /// Byte offset of the end of the token in the source, exclusive.
+    pub end: usize,
+    /// Alignment of a [`Token::Align`], `None` for other tokens.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub align: Option<AlignType>,
+    pub text: String,
+}
+
+impl TokenDump {
+    /// Create new [`TokenDump`] of `token`, a token of `source`.
+    pub fn new(token: &Token, source: &str) -> Self {
+        let range = token.to_range();
+
+        TokenDump {
+            kind: token.kind(),
+            start: range.start,
+            end: range.end,
+            align: match token {
+                Token::Align(_, align) => Some(align.clone()),
+                _ => None,
+            },
+            text: source[range].to_owned(),
+        }
     }
 }
 
@@ -325,7 +759,7 @@ impl AsRef<Token> for Token {
 mod tests {
     use crate::{ast::AlignType, lexer::Token};
 
-    use super::Lexer;
+    use super::{Lexer, LexerOptions, TokenKind};
 
     #[test]
     fn test_heading() {
@@ -340,6 +774,122 @@ mod tests {
         assert_eq!(lexer.next(), Some(Token::LineBreaks(9..10)));
     }
 
+    #[test]
+    fn test_byte_order_mark() {
+        let mut lexer = Lexer::new("\u{feff}# a");
+
+        assert_eq!(lexer.next(), Some(Token::Pounds(3..4)));
+        assert_eq!(lexer.next(), Some(Token::WhiteSpaces(4..5)));
+        assert_eq!(lexer.next(), Some(Token::PlainText(5..6)));
+        assert_eq!(lexer.next(), None);
+
+        // only at the start.
+        assert_eq!(
+            Lexer::new("a\u{feff}").collect::<Vec<_>>(),
+            [Token::PlainText(0..4)]
+        );
+    }
+
+    #[test]
+    fn test_html_comment() {
+        let source = "a <!-- # not *a* heading --> b";
+
+        assert_eq!(
+            Lexer::new(source).collect::<Vec<_>>(),
+            [
+                Token::PlainText(0..1),
+                Token::WhiteSpaces(1..2),
+                Token::Comment(2..28),
+                Token::WhiteSpaces(28..29),
+                Token::PlainText(29..30)
+            ]
+        );
+
+        // unterminated, across lines.
+        assert_eq!(
+            Lexer::new("<!-- a\n\n*b* -- >").collect::<Vec<_>>(),
+            [Token::Comment(0..16)]
+        );
+
+        assert_eq!(
+            Lexer::new("<!--><!--->").collect::<Vec<_>>(),
+            [Token::Comment(0..5), Token::Comment(5..11)]
+        );
+    }
+
+    #[test]
+    fn test_html_declarations() {
+        assert_eq!(
+            Lexer::new("<!DOCTYPE html><![CDATA[ <a> ]]><?php # ?>").collect::<Vec<_>>(),
+            [
+                Token::Declaration(0..15),
+                Token::Cdata(15..32),
+                Token::ProcessingInstruction(32..42)
+            ]
+        );
+
+        assert_eq!(
+            Lexer::new("<?x").collect::<Vec<_>>(),
+            [Token::ProcessingInstruction(0..3)]
+        );
+
+        // tags and other `<` are key chars.
+        assert_eq!(
+            Lexer::new("<a><!1").collect::<Vec<_>>(),
+            [
+                Token::KeyChar(0..1),
+                Token::PlainText(1..2),
+                Token::KeyChar(2..3),
+                Token::KeyChar(3..4),
+                Token::KeyChar(4..5),
+                Token::PlainText(5..6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_line() {
+        let source = "> *a*\r\n\n| :-: |\nend";
+
+        let mut lexer = Lexer::new(source);
+
+        let mut lines = vec![];
+
+        loop {
+            let line = lexer.tokenize_line();
+
+            if line.is_empty() {
+                break;
+            }
+
+            lines.push(line);
+        }
+
+        let text = |line: &[Token]| {
+            line.iter()
+                .map(|token| &source[token.to_range()])
+                .collect::<String>()
+        };
+
+        // blank lines belong to the line before them.
+        assert_eq!(
+            lines.iter().map(|line| text(line)).collect::<Vec<_>>(),
+            ["> *a*\r\n\n", "| :-: |\n", "end"]
+        );
+        assert_eq!(
+            lines[1].iter().map(Token::kind).collect::<Vec<_>>(),
+            [
+                TokenKind::KeyChar,
+                TokenKind::WhiteSpaces,
+                TokenKind::Align,
+                TokenKind::WhiteSpaces,
+                TokenKind::KeyChar,
+                TokenKind::LineBreaks
+            ]
+        );
+        assert_eq!(Token::Eof(3..3).kind(), TokenKind::Eof);
+    }
+
     #[test]
     fn test_thematic_break() {
         let md = "\n__________\n----------\n***";
@@ -410,4 +960,159 @@ mod tests {
         assert_eq!(lexer.next(), Some(Token::WhiteSpaces(5..6)));
         assert_eq!(lexer.next(), Some(Token::KeyChar(6..7)));
     }
+
+    #[test]
+    fn test_extra_keychars() {
+        let md = "{{ var }} {% tag %} [[Page]]";
+
+        let options = LexerOptions {
+            extra_keychars: &['%'],
+            ..Default::default()
+        };
+
+        let tokens = |lexer: Lexer| {
+            lexer
+                .filter(|token| matches!(token, Token::KeyChar(_)))
+                .map(|token| &md[token.to_range()])
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            tokens(Lexer::new(md)),
+            ["{", "{", "}", "}", "{", "}", "[", "[", "]", "]"]
+        );
+
+        assert_eq!(
+            tokens(Lexer::with_options(md, options)),
+            ["{", "{", "}", "}", "{", "%", "%", "}", "[", "[", "]", "]"]
+        );
+
+        let mut lexer = Lexer::new("a%b");
+
+        assert_eq!(lexer.next(), Some(Token::PlainText(0..3)));
+
+        let mut lexer = Lexer::with_options("a%b", options);
+
+        assert_eq!(lexer.next(), Some(Token::PlainText(0..1)));
+        assert_eq!(lexer.next(), Some(Token::KeyChar(1..2)));
+        assert_eq!(lexer.next(), Some(Token::PlainText(2..3)));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn test_plain_chars() {
+        let options = LexerOptions {
+            plain_chars: &['{', '}', '*', ' '],
+            ..Default::default()
+        };
+
+        let mut lexer = Lexer::new("{x}");
+
+        assert_eq!(lexer.next(), Some(Token::KeyChar(0..1)));
+        assert_eq!(lexer.next(), Some(Token::PlainText(1..2)));
+        assert_eq!(lexer.next(), Some(Token::KeyChar(2..3)));
+
+        let mut lexer = Lexer::with_options("{x}*y* [z]", options);
+
+        assert_eq!(lexer.next(), Some(Token::PlainText(0..6)));
+        assert_eq!(lexer.next(), Some(Token::WhiteSpaces(6..7)));
+        assert_eq!(lexer.next(), Some(Token::KeyChar(7..8)));
+        assert_eq!(lexer.next(), Some(Token::PlainText(8..9)));
+        assert_eq!(lexer.next(), Some(Token::KeyChar(9..10)));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn test_find_matching_bracket() {
+        let lexer = Lexer::new("[a [b] c] d");
+
+        assert_eq!(lexer.find_matching_bracket(0), Some(8));
+        assert_eq!(lexer.find_matching_bracket(3), Some(5));
+        assert_eq!(lexer.find_matching_bracket(1), None);
+
+        // escaped brackets neither open nor close.
+        let lexer = Lexer::new(r"[a \] b] \[c]");
+
+        assert_eq!(lexer.find_matching_bracket(0), Some(7));
+        assert_eq!(lexer.find_matching_bracket(10), None);
+
+        let lexer = Lexer::new(r"[a \\] b]");
+
+        assert_eq!(lexer.find_matching_bracket(0), Some(5));
+
+        // unbalanced opens.
+        let lexer = Lexer::new("[[a] [b");
+
+        assert_eq!(lexer.find_matching_bracket(0), None);
+        assert_eq!(lexer.find_matching_bracket(1), Some(3));
+        assert_eq!(lexer.find_matching_bracket(5), None);
+        assert_eq!(lexer.find_matching_bracket(100), None);
+    }
+
+    #[test]
+    fn test_find_matching_paren() {
+        let mut lexer = Lexer::new(r"[a](b(c)\)d) (e");
+
+        let token = lexer.next_token();
+
+        assert_eq!(lexer.find_matching_paren(3), Some(11));
+        assert_eq!(lexer.find_matching_paren(5), Some(7));
+        assert_eq!(lexer.find_matching_paren(13), None);
+
+        // the cursor doesn't move.
+        assert_eq!(token, Token::KeyChar(0..1));
+        assert_eq!(lexer.next_token(), Token::PlainText(1..2));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        assert_eq!(
+            serde_json::to_string(&Token::LineBreaks(3..5)).unwrap(),
+            r#"{"kind":"lineBreaks","start":3,"end":5}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Token::Align(0..5, AlignType::Center)).unwrap(),
+            r#"{"kind":"align","start":0,"end":5,"align":"center"}"#
+        );
+
+        let tokens = Lexer::to_token_dump("<!-- a -->\n| --: |");
+
+        assert_eq!(
+            serde_json::to_string(&tokens[..2]).unwrap(),
+            r#"[{"kind":"comment","start":0,"end":10,"text":"<!-- a -->"},"#.to_owned()
+                + r#"{"kind":"lineBreaks","start":10,"end":11,"text":"\n"}]"#
+        );
+        assert_eq!(tokens[4].align, Some(AlignType::Right));
+
+        // tokens and their dumps serialize alike, but for the text.
+        for (token, dump) in Lexer::new("# a\n| :- |").zip(Lexer::to_token_dump("# a\n| :- |")) {
+            let mut dump = serde_json::to_value(&dump).unwrap();
+
+            dump.as_object_mut().unwrap().remove("text");
+
+            assert_eq!(serde_json::to_value(&token).unwrap(), dump);
+        }
+    }
+
+    #[test]
+    fn test_replacement_char() {
+        let source = String::from_utf8_lossy(b"# \xFF*a\xFF*\n```\xC0\n| \xFF | --: |\n<!-\xFF");
+
+        let tokens = Lexer::new(&source).collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| &source[token.to_range()])
+                .collect::<String>(),
+            source
+        );
+        assert_eq!(
+            tokens[2],
+            Token::PlainText(2..5),
+            "{:?}",
+            &source[tokens[2].to_range()]
+        );
+    }
 }
@@ -1,8 +1,73 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("markdown-rs needs the `alloc` feature, `std` enables it");
+
+extern crate alloc;
+
 /// [mdast](https://github.com/syntax-tree/mdast#list) implementation
 pub mod ast;
 
 /// Transform markdown document stream to token stream.
 pub mod lexer;
 
+/// Render [mdast](https://github.com/syntax-tree/mdast#list) to HTML.
+pub mod html;
+
 /// Consume [`lexer`](lexer::Lexer) output and generate [mdast](https://github.com/syntax-tree/mdast#list)
 pub mod parser;
+
+/// Character classes of the CommonMark spec, such as Unicode whitespace and punctuation.
+pub mod unicode;
+
+/// Serialize [mdast](https://github.com/syntax-tree/mdast#list) back into markdown text.
+pub mod to_markdown;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "pulldown-cmark")]
+pub mod pulldown;
+
+#[cfg(feature = "capi")]
+pub mod ffi;
+
+use alloc::string::String;
+
+use html::HtmlOptions;
+use parser::{Parser, ParserError};
+
+/// Parse `source` and serialize its tree to mdast JSON.
+///
+/// Unlike [`Parser`], the result borrows nothing from `source`, which suits FFI and
+/// `wasm-bindgen` boundaries.
+///
+/// ```
+/// use markdown_rs::{parse_to_json, parser::ParserOptions};
+///
+/// let json = parse_to_json("# hi", &ParserOptions::default()).unwrap();
+///
+/// assert_eq!(
+///     json,
+///     r#"{"type":"root","children":[{"type":"heading","children":[{"type":"text","value":"hi"}],"depth":1}]}"#
+/// );
+/// ```
+#[cfg(feature = "json")]
+pub fn parse_to_json(source: &str, options: &parser::ParserOptions) -> Result<String, ParserError> {
+    let document = Parser::with_options(source, options.clone()).parse()?;
+
+    Ok(serde_json::to_string(&document).expect("mdast trees always serialize to JSON"))
+}
+
+/// Parse `source` and render it to HTML, see [`html::render`].
+///
+/// ```
+/// use markdown_rs::{html::HtmlOptions, parse_to_html};
+///
+/// let html = parse_to_html("*hi*", &HtmlOptions::default()).unwrap();
+///
+/// assert_eq!(html, "<p><em>hi</em></p>\n");
+/// ```
+pub fn parse_to_html(source: &str, options: &HtmlOptions) -> Result<String, ParserError> {
+    Ok(Parser::new(source).parse()?.to_html(options))
+}
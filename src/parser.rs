@@ -1,20 +1,155 @@
-use std::ops::Range;
-
-use crate::ast::*;
+#[cfg(feature = "arena")]
+use crate::ast::arena::{Arena, ArenaDocument};
+use crate::ast::{index::DocumentIndex, spans::Spans, style::Style, *};
 use crate::lexer::*;
 
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+use core::{
+    fmt,
+    ops::{ControlFlow, Range},
+    str::Utf8Error,
+    sync::atomic::AtomicBool,
+};
+
 use thiserror::Error;
 
+mod block;
+pub mod extension;
+mod inline;
+mod record;
+mod tree;
+
+use extension::{BlockRule, InlineRule};
+
+pub(crate) use block::starts_html_block;
+pub(crate) use inline::{has_scheme, unescape};
+
 /// `mdast` associated error type.
 #[derive(Error, Debug)]
 pub enum ParserError {
     #[error("mdast error {0}")]
     AstError(#[from] AstError),
+    #[error("extension error: {0}")]
+    Extension(String),
+    #[error("parsing cancelled at offset {offset}")]
+    Cancelled { offset: usize },
+}
+
+/// Options of [`Parser`].
+#[derive(Clone)]
+pub struct ParserOptions {
+    /// Maximum nesting depth of blockquotes, lists, list items, emphasis, strong, links and
+    /// images, all counted together. Deeper markup is kept as literal text, so that untrusted
+    /// input can't build a tree deep enough to exhaust the stack of recursive consumers.
+    ///
+    /// Defaults to 256.
+    pub max_nesting_depth: usize,
+    /// Extensions of phrasing content, tried in order before the built-in rules, defaults to
+    /// none.
+    pub inline_rules: Vec<Arc<dyn InlineRule>>,
+    /// Extensions of flow content, probed in order before the built-in block starts, defaults
+    /// to none.
+    pub block_rules: Vec<Arc<dyn BlockRule>>,
+    /// Cancels parsing once set, defaults to `None`.
+    ///
+    /// The flag is checked before each top-level block, as the block structure is built and as
+    /// its inline content is parsed, and parsing then fails with [`ParserError::Cancelled`].
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Called with the source offset of each top-level block as the block structure is built,
+    /// before the inline content of the blocks is parsed, defaults to `None`.
+    pub progress: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions {
+            max_nesting_depth: 256,
+            inline_rules: Vec::new(),
+            block_rules: Vec::new(),
+            cancel: None,
+            progress: None,
+        }
+    }
+}
+
+impl fmt::Debug for ParserOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParserOptions")
+            .field("max_nesting_depth", &self.max_nesting_depth)
+            .field(
+                "inline_rules",
+                &self
+                    .inline_rules
+                    .iter()
+                    .map(|_| "dyn InlineRule")
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "block_rules",
+                &self
+                    .block_rules
+                    .iter()
+                    .map(|_| "dyn BlockRule")
+                    .collect::<Vec<_>>(),
+            )
+            .field("cancel", &self.cancel)
+            .field("progress", &self.progress.as_ref().map(|_| "dyn Fn(usize)"))
+            .finish()
+    }
+}
+
+/// Rules, cancellation flags and progress callbacks are equal when they are the same
+/// instances.
+impl PartialEq for ParserOptions {
+    fn eq(&self, other: &Self) -> bool {
+        self.max_nesting_depth == other.max_nesting_depth
+            && self.inline_rules.len() == other.inline_rules.len()
+            && self
+                .inline_rules
+                .iter()
+                .zip(&other.inline_rules)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.block_rules.len() == other.block_rules.len()
+            && self
+                .block_rules
+                .iter()
+                .zip(&other.block_rules)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+            && match (&self.cancel, &other.cancel) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+            && match (&self.progress, &other.progress) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+    }
+}
+
+impl Eq for ParserOptions {}
+
+/// An edit of the source of a parsed document, see [`Parser::reparse`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextEdit {
+    /// Byte range of the old source that was replaced.
+    pub range: Range<usize>,
+    /// Byte length of the text that replaced it.
+    pub new_len: usize,
 }
 
 /// Markdown text stream parser.
 pub struct Parser<'a> {
     _lexer: Lexer<'a>,
+    options: ParserOptions,
+    /// Top-level blocks of the last parsed document.
+    blocks: Vec<block::BlockSpan>,
+    /// Number of top-level blocks parsed by the last call to `parse` or `reparse`.
+    parsed_blocks: usize,
 }
 
 impl<'a, L> From<L> for Parser<'a>
@@ -29,86 +164,306 @@ where
 impl<'a> Parser<'a> {
     /// Create new parser from lexer implementation
     pub fn new<L: Into<Lexer<'a>>>(l: L) -> Self {
-        Parser { _lexer: l.into() }
+        Self::with_options(l, ParserOptions::default())
+    }
+
+    /// Create new parser from lexer implementation and `options`.
+    pub fn with_options<L: Into<Lexer<'a>>>(l: L, options: ParserOptions) -> Self {
+        Parser {
+            _lexer: l.into(),
+            options,
+            blocks: Vec::new(),
+            parsed_blocks: 0,
+        }
+    }
+
+    /// Create new parser from `bytes`, fails if they aren't valid UTF-8.
+    ///
+    /// See [`from_bytes_lossy`](Self::from_bytes_lossy) to parse invalid input anyway.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, Utf8Error> {
+        core::str::from_utf8(bytes).map(Self::new)
+    }
+
+    /// Parse `bytes`, replacing invalid UTF-8 sequences with U+FFFD like
+    /// [`String::from_utf8_lossy`], returns the document and the number of replaced sequences.
+    ///
+    /// The document owns its strings, as the decoded source doesn't outlive the call.
+    ///
+    /// ```
+    /// use markdown_rs::{html::HtmlOptions, parser::Parser};
+    ///
+    /// let (document, replaced) = Parser::from_bytes_lossy(b"caf\xE9 *au lait*").unwrap();
+    ///
+    /// assert_eq!(replaced, 1);
+    /// assert_eq!(
+    ///     document.to_html(&HtmlOptions::default()),
+    ///     "<p>caf\u{FFFD} <em>au lait</em></p>\n"
+    /// );
+    /// ```
+    pub fn from_bytes_lossy(bytes: &[u8]) -> Result<(Document<'static>, usize), ParserError> {
+        let replaced = bytes
+            .utf8_chunks()
+            .filter(|chunk| !chunk.invalid().is_empty())
+            .count();
+
+        let source = String::from_utf8_lossy(bytes);
+
+        let document = Parser::new(&*source).parse()?.into_owned();
+
+        Ok((document, replaced))
+    }
+
+    /// Returns the options of this parser.
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
     }
 
     /// Parse input markdown text stream.
+    ///
+    /// Parsing follows the CommonMark block/inline strategy: the block structure is built
+    /// line by line first, then the phrasing content of paragraphs and headings is parsed.
     pub fn parse(&mut self) -> Result<Document<'a>, ParserError> {
-        let mut document = Document::default();
+        self.parse_all()
+    }
 
-        loop {
-            if let Some(node) = self.parse_flow_content()? {
-                document.add_child(node)?;
-            } else {
-                return Ok(document);
-            }
+    /// Parse `new_source`, the source of this parser after `edit`, reusing the top-level nodes
+    /// of `old_tree` that the edit can't have changed.
+    ///
+    /// `old_tree` must be the unchanged result of the last call to [`Parser::parse`] or
+    /// `reparse`. Top-level blocks are parsed again from the last one starting before the edit
+    /// after a blank line, up to the first block after the edit that also follows a blank
+    /// line ending the block before it for good: lists, code and HTML blocks may go on after a
+    /// blank line. The whole source is parsed again if the edit doesn't match the source,
+    /// `old_tree` doesn't match the last parse, or the edit changes which identifiers are
+    /// defined, as references anywhere may then read differently.
+    ///
+    /// Produces the same tree, or error, as parsing `new_source` with [`Parser::parse`], this
+    /// parser then parses `new_source`.
+    pub fn reparse(
+        &mut self,
+        old_tree: Document<'a>,
+        edit: TextEdit,
+        new_source: &'a str,
+    ) -> Result<Document<'a>, ParserError> {
+        let old_len = self._lexer.source().len();
+
+        let new_end = edit.range.start + edit.new_len;
+
+        let matches = edit.range.start <= edit.range.end
+            && edit.range.end <= old_len
+            && new_source.len() + edit.range.len() == old_len + edit.new_len
+            && new_source.is_char_boundary(edit.range.start)
+            && new_source.is_char_boundary(new_end)
+            && self.blocks.iter().map(|block| block.nodes).sum::<usize>()
+                == old_tree.children.len();
+
+        self._lexer = new_source.into();
+
+        let old = core::mem::take(&mut self.blocks);
+
+        if !matches {
+            return self.parse_all();
         }
-    }
-    /// Parse flow content:
-    /// Blockquote | Code | Heading | Html | List | ThematicBreak | Content
-    fn parse_flow_content(&mut self) -> Result<Option<Node<'a>>, ParserError> {
-        let token = self._lexer.next_token();
-
-        match token {
-            Token::GreaterThans(range) => return self.parse_block_quote(range).map(Some),
-            Token::Backticks(range) => return self.parse_code(range).map(Some),
-            Token::Pounds(range) => return self.parse_heading(range).map(Some),
-            Token::Eof(_) => return Ok(None),
-            _ => {
-                unimplemented!()
+
+        let mut children = old_tree.children;
+
+        // first dirty block and its first node.
+        let (mut first, mut first_node) = (0, 0);
+
+        let mut node = 0;
+
+        for (index, block) in old.iter().enumerate() {
+            if block.start > edit.range.start {
+                break;
+            }
+
+            if index > 0
+                && block::follows_blank_line(new_source, block.start)
+                && matches!(
+                    children[node - 1],
+                    Node::Paragraph(_)
+                        | Node::Heading(_)
+                        | Node::ThematicBreak(_)
+                        | Node::Blockquote(_)
+                        | Node::Table(_)
+                        | Node::Definition(_)
+                )
+            {
+                (first, first_node) = (index, node);
             }
+
+            node += block.nodes;
         }
+
+        let start = if first == 0 { 0 } else { old[first].start };
+
+        // index of the old block starting at `position` of the new source, after the edit.
+        let resumed = |position: usize| {
+            position.checked_sub(new_end).and_then(|after| {
+                old.binary_search_by_key(&(edit.range.end + after), |block| block.start)
+                    .ok()
+            })
+        };
+
+        let partial = block::PartialParse::new(new_source, &self.options, start, |position| {
+            resumed(position).is_some()
+        });
+
+        let last = resumed(partial.end).unwrap_or(old.len());
+
+        let last_node = first_node
+            + old[first..last]
+                .iter()
+                .map(|block| block.nodes)
+                .sum::<usize>();
+
+        let mut definitions = identifiers(&children[..first_node]);
+
+        definitions.extend(identifiers(&children[last_node..]));
+        definitions.extend(partial.definitions());
+
+        if definitions != identifiers(&children) {
+            return self.parse_all();
+        }
+
+        let (nodes, blocks) = partial.finish(&definitions)?;
+
+        children.splice(first_node..last_node, nodes);
+
+        self.parsed_blocks = blocks.len();
+
+        self.blocks = old[..first]
+            .iter()
+            .copied()
+            .chain(blocks)
+            .chain(old[last..].iter().map(|block| block::BlockSpan {
+                start: block.start - edit.range.end + new_end,
+                nodes: block.nodes,
+            }))
+            .collect();
+
+        Ok(Document {
+            children,
+            data: None,
+        })
     }
 
-    fn parse_block_quote(&mut self, _range: Range<usize>) -> Result<Node<'a>, ParserError> {
-        unimplemented!()
+    /// Returns the number of top-level blocks parsed by the last call to [`Parser::parse`] or
+    /// [`Parser::reparse`].
+    pub fn parsed_blocks(&self) -> usize {
+        self.parsed_blocks
     }
 
-    fn parse_code(&mut self, _range: Range<usize>) -> Result<Node<'a>, ParserError> {
-        unimplemented!()
+    fn parse_all(&mut self) -> Result<Document<'a>, ParserError> {
+        let (document, blocks) = block::parse_spans(self._lexer.source(), &self.options)?;
+
+        self.parsed_blocks = blocks.len();
+        self.blocks = blocks;
+
+        Ok(document)
     }
-    /// expect: #* ws plaintext
-    fn parse_heading(&mut self, pounds: Range<usize>) -> Result<Node<'a>, ParserError> {
-        let expect = self._lexer.lookahead();
 
-        match expect {
-            Token::WhiteSpaces(range) => {
-                let mut heading = Heading::new(pounds.len());
+    /// Parse input markdown text stream, along with the markup choices the tree doesn't
+    /// record: list markers, emphasis markers, heading and code block forms.
+    ///
+    /// Produces the same tree as [`Parser::parse`]. Serializing it with
+    /// [`Document::to_markdown_with_style`] keeps the markup of the source where the tree is
+    /// unchanged, see [`style`].
+    pub fn parse_with_style(&mut self) -> Result<(Document<'a>, Style), ParserError> {
+        block::parse_with_style(self._lexer.source(), &self.options)
+    }
 
-                if range.len() > 1 {
-                    let value = self._lexer.range_as_str(range.start + 1..range.end);
-                    heading.add_child(Text {
-                        value: value.into(),
-                    })?;
-                }
+    /// Parse input markdown text stream, along with the source range of every node, see
+    /// [`spans`].
+    ///
+    /// Produces the same tree as [`Parser::parse`].
+    pub fn parse_with_spans(&mut self) -> Result<(Document<'a>, Spans), ParserError> {
+        block::parse_with_spans(self._lexer.source(), &self.options)
+    }
 
-                self._lexer.next();
+    /// Parse input markdown text stream, along with an index of its definitions, headings and
+    /// footnote definitions, see [`index`].
+    ///
+    /// Produces the same tree as [`Parser::parse`].
+    pub fn parse_indexed(&mut self) -> Result<(Document<'a>, DocumentIndex<'a>), ParserError> {
+        let (document, spans) = self.parse_with_spans()?;
 
-                while let Some(content) = self.parse_phrasing_content()? {
-                    heading.add_child_node(content)?;
-                }
+        let index = DocumentIndex::new(&document, &spans);
 
-                Ok(Node::Heading(heading))
-            }
-            _ => {
-                // maybe this is a normal paragraph
-                self.parse_paragraph()
+        Ok((document, index))
+    }
+
+    /// Parse input markdown text stream block by block, without building a [`Document`].
+    ///
+    /// `f` is called with each top-level node (heading, paragraph, code, list, definition...)
+    /// as soon as its block is closed, and the block is then dropped: memory use depends on
+    /// the largest block rather than on the input size. Returning [`ControlFlow::Break`]
+    /// stops parsing.
+    ///
+    /// Returns the definitions found in the parsed blocks, nested ones included, for later
+    /// resolution. Unlike [`Parser::parse`], a reference is only recognized if its definition
+    /// comes first in the stream or in the same top-level block.
+    pub fn parse_blocks<F>(&mut self, mut f: F) -> Result<Vec<Definition<'a>>, ParserError>
+    where
+        F: FnMut(Node<'a>) -> ControlFlow<()>,
+    {
+        let mut stream = block::BlockStream::new(self._lexer.source(), &self.options);
+
+        for node in stream.by_ref() {
+            if f(node).is_break() {
+                break;
             }
         }
-    }
 
-    fn parse_phrasing_content(&mut self) -> Result<Option<Node<'a>>, ParserError> {
-        unimplemented!()
+        stream.into_definitions()
     }
 
-    fn parse_paragraph(&mut self) -> Result<Node<'a>, ParserError> {
-        unimplemented!()
+    /// Parse input markdown text stream into a tree allocated in `arena`.
+    ///
+    /// Produces the same tree as [`Parser::parse`], with far fewer allocations: children lists
+    /// are slices of the arena instead of one `Vec` per parent. [`Parser::parse`] remains the
+    /// default, owned trees are easier to transform.
+    #[cfg(feature = "arena")]
+    pub fn parse_in<'arena>(
+        &mut self,
+        arena: &'arena Arena<'arena, 'a>,
+    ) -> Result<ArenaDocument<'arena, 'a>, ParserError> {
+        let sink = tree::ArenaSink::new(arena);
+
+        let children = block::parse_into(self._lexer.source(), &self.options, &sink)?;
+
+        Ok(ArenaDocument {
+            children: sink.alloc(children),
+        })
     }
 }
 
+/// Returns the identifiers defined in `nodes`, nested definitions included.
+fn identifiers(nodes: &[Node]) -> BTreeSet<String> {
+    nodes
+        .iter()
+        .flat_map(|node| core::iter::once(node).chain(node.descendants()))
+        .filter_map(|node| match node {
+            Node::Definition(definition) => Some(definition.identifier.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Parser;
+    use core::ops::ControlFlow;
+
+    use core::ops::Range;
+
+    use crate::ast::{walk, walk_mut, Code, Document, Heading, Node, Text, Visitor, VisitorMut};
+
+    use std::sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use super::{Parser, ParserError, ParserOptions, TextEdit};
 
     #[test]
     fn test_heading() {
@@ -118,4 +473,533 @@ mod tests {
 
         parser.parse().unwrap();
     }
+
+    #[test]
+    fn test_from_bytes() {
+        let document = Parser::from_bytes(b"# a\n\nb").unwrap().parse().unwrap();
+
+        assert_eq!(document, Parser::new("# a\n\nb").parse().unwrap());
+
+        let err = Parser::from_bytes(b"# a\n\n\xFFb").err().unwrap();
+
+        assert_eq!(err.valid_up_to(), 5);
+    }
+
+    #[test]
+    fn test_from_bytes_lossy() {
+        // an invalid byte and a truncated sequence mid-paragraph.
+        let bytes = b"Some *text* \xFF with \xE2\x82 bytes.\n\nNext.\n";
+
+        let (document, replaced) = Parser::from_bytes_lossy(bytes).unwrap();
+
+        assert_eq!(replaced, 2);
+        assert_eq!(
+            document,
+            Parser::new("Some *text* \u{FFFD} with \u{FFFD} bytes.\n\nNext.\n")
+                .parse()
+                .unwrap()
+        );
+
+        // inside a code fence, and its info string.
+        let bytes = b"```r\xC0st\nlet a = \"\xF0\x9F\x98\";\n\xFF\n```\nafter\n";
+
+        let (document, replaced) = Parser::from_bytes_lossy(bytes).unwrap();
+
+        assert_eq!(replaced, 3);
+
+        let [Node::Code(code), Node::Paragraph(_)] = &document.children[..] else {
+            panic!("{:?}", document.children);
+        };
+
+        assert_eq!(code.lang.as_deref(), Some("r\u{FFFD}st"));
+        assert_eq!(code.value, "let a = \"\u{FFFD}\";\n\u{FFFD}");
+
+        // valid input replaces nothing.
+        let (document, replaced) = Parser::from_bytes_lossy("é *a*".as_bytes()).unwrap();
+
+        assert_eq!(replaced, 0);
+        assert_eq!(document, Parser::new("é *a*").parse().unwrap());
+    }
+
+    #[test]
+    fn test_heading_whitespace() {
+        fn heading_texts(md: &str) -> Vec<String> {
+            let document = Parser::new(md).parse().unwrap();
+
+            let Some(Node::Heading(heading)) = document.children.first() else {
+                panic!("{:?}", document.children);
+            };
+
+            heading
+                .children
+                .iter()
+                .map(|child| match child {
+                    Node::Text(text) => text.value.to_string(),
+                    child => format!("{:?}", child.kind()),
+                })
+                .collect()
+        }
+
+        // a single text, interior whitespace kept as is.
+        assert_eq!(heading_texts("# a  b"), ["a  b"]);
+        assert_eq!(
+            heading_texts("#   Multiple   spaces  "),
+            ["Multiple   spaces"]
+        );
+        assert_eq!(heading_texts("#\t\ta  \t b\t \n"), ["a  \t b"]);
+
+        // trailing whitespace, before and after a closing sequence.
+        assert_eq!(heading_texts("## a \\#  ##  \t"), ["a #"]);
+        assert_eq!(heading_texts("# a\t\n"), ["a"]);
+        assert_eq!(heading_texts("# *a*  b \t"), ["Emphasis", "  b"]);
+        assert_eq!(heading_texts("#\ta ##\tb #"), ["a ##\tb"]);
+        assert!(heading_texts("#  \t").is_empty());
+
+        // the text starts after the whitespace.
+        let md = "#\t  a  b  ";
+
+        let (document, spans) = Parser::new(md).parse_with_spans().unwrap();
+
+        assert_eq!(spans.get(&[0, 0]), Some(4..8));
+        assert_eq!(&md[spans.get(&[0, 0]).unwrap()], "a  b");
+        assert_eq!(document.children.len(), 1);
+    }
+
+    #[test]
+    fn test_heading_depth() {
+        fn depths(md: &str) -> Vec<u8> {
+            Parser::new(md)
+                .parse()
+                .unwrap()
+                .find_all_of::<Heading>()
+                .into_iter()
+                .map(|heading| heading.depth)
+                .collect()
+        }
+
+        // seven or more `#` are text, at the top level and in containers.
+        for count in 1..=300 {
+            let md = format!(
+                "{} a\n\n> {}\n\n- a\n  {}\n",
+                "#".repeat(count),
+                "#".repeat(count),
+                "#".repeat(count)
+            );
+
+            if count <= 6 {
+                assert_eq!(depths(&md), [count as u8; 3]);
+            } else {
+                assert!(depths(&md).is_empty(), "{}", md);
+            }
+        }
+
+        assert_eq!(depths("a\n=\n\n> b\n> ---\n"), [1, 2]);
+    }
+
+    #[test]
+    fn test_code_in_containers() {
+        fn values(md: &str) -> Vec<String> {
+            Parser::new(md)
+                .parse()
+                .unwrap()
+                .find_all_of::<Code>()
+                .into_iter()
+                .map(|code| code.value.to_string())
+                .collect()
+        }
+
+        // the prefixes of the blockquote and both items are stripped, the python indentation
+        // kept byte for byte, tabs and blank lines included.
+        let md = "\
+> - outer
+>   - inner
+>
+>     ```python
+>     def f(x):
+>         if x:
+>             return [
+>                 1,
+>               ]
+>
+>     \treturn 0
+>     ```
+";
+
+        assert_eq!(
+            values(md),
+            ["def f(x):\n    if x:\n        return [\n            1,\n          ]\n\n\treturn 0"]
+        );
+
+        // the indentation of the fence is stripped too, and no more.
+        assert_eq!(
+            values("> > - a\n> >   - ```yaml\n> >     key:\n> >       - a: 1\n> >         b: 2\n> >     ```\n"),
+            ["key:\n  - a: 1\n    b: 2"]
+        );
+        assert_eq!(
+            values("- a\n\n   ```\n   x\n      y\n  z\n   ```\n"),
+            ["x\n   y\nz"]
+        );
+
+        // tabs partly taken by a prefix leave their remaining columns as spaces.
+        assert_eq!(values("> ```\n>\ta\n>  b\n> ```\n"), ["  a\n b"]);
+        assert_eq!(values("- ```\n \tx\n  \ty\n  ```\n"), ["  x\n\ty"]);
+    }
+
+    #[test]
+    fn test_parse_blocks() {
+        let source = "[home]: /home\n\n# Title\n\n> [home]\n\n- a\n- b\n\n```\ncode\n```\n";
+
+        let document = Parser::new(source).parse().unwrap();
+
+        let mut nodes = vec![];
+
+        let definitions = Parser::new(source)
+            .parse_blocks(|node| {
+                nodes.push(node);
+
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        assert_eq!(nodes, document.children);
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].url, "/home");
+
+        let mut count = 0;
+
+        Parser::new(source)
+            .parse_blocks(|_| {
+                count += 1;
+
+                if count == 2 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            })
+            .unwrap();
+
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_parse_blocks_large() {
+        let source = (0..25_000)
+            .map(|i| {
+                let previous = i.max(1) - 1;
+
+                format!("# Section {i}\n\nSee [{previous}].\n- item\n\n[{i}]: /{i}\n")
+            })
+            .collect::<String>();
+
+        let mut blocks = 0;
+        let mut links = 0;
+
+        let definitions = Parser::new(source.as_str())
+            .parse_blocks(|node| {
+                blocks += 1;
+                links += node
+                    .descendants()
+                    .filter(|node| matches!(node, Node::LinkReference(_)))
+                    .count();
+
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+
+        // heading, paragraph, list and definition per section.
+        assert_eq!(blocks, 100_000);
+        assert_eq!(definitions.len(), 25_000);
+        // only the first section refers to a definition further down the stream.
+        assert_eq!(links, 24_999);
+    }
+
+    #[test]
+    fn test_into_owned() {
+        let document = {
+            let md = String::from("# heading");
+
+            let mut parser = Parser::new(md.as_str());
+
+            parser.parse().unwrap().into_owned()
+        };
+
+        let Some(Node::Heading(heading)) = document.children.first() else {
+            panic!("expect heading");
+        };
+
+        assert_eq!(heading.depth, 1);
+        assert_eq!(
+            heading.children,
+            vec![Node::Text(Text {
+                value: "heading".into(),
+                data: None
+            })]
+        );
+    }
+
+    fn reparse(source: &str, range: Range<usize>, text: &str) -> usize {
+        let new_source = format!("{}{}{}", &source[..range.start], text, &source[range.end..]);
+
+        let mut parser = Parser::new(source);
+
+        let old_tree = parser.parse().unwrap();
+
+        let edit = TextEdit {
+            range,
+            new_len: text.len(),
+        };
+
+        let document = parser.reparse(old_tree, edit, &new_source).unwrap();
+
+        assert_eq!(
+            document,
+            Parser::new(new_source.as_str()).parse().unwrap(),
+            "reparse of {:?}",
+            new_source
+        );
+
+        parser.parsed_blocks()
+    }
+
+    #[test]
+    fn test_reparse_one_block() {
+        let source = (0..1000)
+            .map(|i| format!("Paragraph *{i}* with [a] link.\n\n"))
+            .chain(["[a]: /a\n".to_owned()])
+            .collect::<String>();
+
+        let offset = source.find("Paragraph *500*").unwrap() + "Paragraph *50".len();
+
+        assert_eq!(reparse(&source, offset..offset + 1, "x_y_"), 1);
+        assert_eq!(reparse(&source, offset..offset + 1, "\n\n# z"), 2);
+        assert_eq!(reparse(&source, 0..0, "# "), 1);
+        assert_eq!(reparse(&source, source.len()..source.len(), "tail"), 1);
+    }
+
+    #[test]
+    fn test_reparse_spreading_edit() {
+        let source = "a\n\n- b\n\n  c\n\nd\n\n> e\n\nf\n";
+
+        // an edit changing the block structure is parsed up to the next block left unchanged.
+        assert_eq!(reparse(source, 1..3, ""), 2);
+        assert_eq!(reparse(source, 0..1, "```"), 1);
+        assert_eq!(reparse(source, 9..9, "\n- x\n\n  "), 2);
+
+        // a new definition changes references anywhere.
+        let source = "[a]\n\nb\n\nc\n";
+
+        assert_eq!(reparse(source, 8..9, "[a]: /a"), 3);
+    }
+
+    #[test]
+    fn test_reparse_every_offset() {
+        let source = "# a\n\npara *b*\nc\n\n- d\n- e\n\n    f\n\n```\ng\n```\n\n> h\n\n| i |\n| - |\n| j |\n\n<div>\n\nk\n";
+
+        for start in 0..=source.len() {
+            for text in [
+                "",
+                "x",
+                "\n",
+                "\n\n",
+                "```",
+                "- ",
+                "> ",
+                "|-\n",
+                "[a]: /a\n",
+            ] {
+                reparse(source, start..start, text);
+                reparse(source, start..(start + 2).min(source.len()), text);
+            }
+        }
+    }
+
+    fn depth(document: &Document) -> usize {
+        document
+            .descendants_with_depth()
+            .map(|(depth, _)| depth)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn parse_with_depth(source: &str, max_nesting_depth: usize) -> Document<'_> {
+        let options = ParserOptions {
+            max_nesting_depth,
+            ..Default::default()
+        };
+
+        Parser::with_options(source, options).parse().unwrap()
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        assert_eq!(
+            parse_with_depth("> > > a", 2),
+            parse_with_depth("> > \\> a", 256)
+        );
+        assert_eq!(
+            parse_with_depth("- - - a\n\n  b", 3),
+            parse_with_depth("- \\- - a\n\n  b", 256)
+        );
+        assert_eq!(
+            parse_with_depth("> *a **b [c _d_](e)** f*", 3),
+            parse_with_depth("> *a **b \\[c \\_d\\_\\](e)** f*", 256)
+        );
+        assert_eq!(
+            parse_with_depth("![a ![b ![c](d)](e)](f)", 2),
+            parse_with_depth("![a ![b !\\[c\\](d)](e)](f)", 256)
+        );
+    }
+
+    #[test]
+    fn test_deep_input() {
+        struct Count(usize);
+
+        impl Visitor for Count {
+            fn leave(&mut self, _: &Node) {
+                self.0 += 1;
+            }
+        }
+
+        struct Keep;
+
+        impl VisitorMut<'_> for Keep {}
+
+        let n = 100_000;
+
+        let sources = [
+            ">".repeat(n),
+            "- ".repeat(n),
+            format!("{}a{}", "*".repeat(n), "*".repeat(n)),
+            format!("{}a{}", "![".repeat(n), "](b)".repeat(n)),
+            format!("{}a{}", "[".repeat(n), "]".repeat(n)),
+        ];
+
+        for source in &sources {
+            let document = Parser::new(source.as_str()).parse().unwrap();
+
+            // containers, then a paragraph or table, and text.
+            assert!(depth(&document) <= 256 + 4);
+
+            let mut node = Node::Document(document.clone());
+
+            let mut count = Count(0);
+
+            walk(&node, &mut count);
+            walk_mut(&mut node, &mut Keep);
+
+            assert_eq!(count.0, document.descendants().count() + 1);
+            assert_eq!(node, Node::Document(document));
+        }
+    }
+
+    fn blocks(count: usize) -> String {
+        (0..count)
+            .map(|index| format!("block {:03}\n\n", index))
+            .collect()
+    }
+
+    #[test]
+    fn test_cancel() {
+        let source = blocks(100);
+
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let options = ParserOptions {
+            cancel: Some(cancel.clone()),
+            progress: Some(Arc::new({
+                let (cancel, calls) = (cancel.clone(), calls.clone());
+
+                move |_| {
+                    if calls.fetch_add(1, Ordering::Relaxed) + 1 == 10 {
+                        cancel.store(true, Ordering::Relaxed);
+                    }
+                }
+            })),
+            ..Default::default()
+        };
+
+        let Err(ParserError::Cancelled { offset }) =
+            Parser::with_options(source.as_str(), options.clone()).parse()
+        else {
+            panic!("parsing wasn't cancelled");
+        };
+
+        // right at the tenth block, the blocks before it are dropped.
+        assert_eq!(offset, 9 * "block 000\n\n".len());
+        assert!(source[offset..].starts_with("block 009"));
+        assert_eq!(calls.load(Ordering::Relaxed), 10);
+
+        // a set flag cancels every kind of parse.
+        assert!(matches!(
+            Parser::with_options(source.as_str(), options.clone()).parse_with_spans(),
+            Err(ParserError::Cancelled { offset: 0 })
+        ));
+        assert!(matches!(
+            Parser::with_options(source.as_str(), options.clone())
+                .parse_blocks(|_| { ControlFlow::Continue(()) }),
+            Err(ParserError::Cancelled { offset: 0 })
+        ));
+    }
+
+    #[test]
+    fn test_progress() {
+        let source = format!("{}> a\n> b\n\n- c\n\n  d\n", blocks(3));
+
+        let offsets = Arc::new(Mutex::new(vec![]));
+
+        let options = ParserOptions {
+            cancel: Some(Arc::new(AtomicBool::new(false))),
+            progress: Some(Arc::new({
+                let offsets = offsets.clone();
+
+                move |offset| offsets.lock().unwrap().push(offset)
+            })),
+            ..Default::default()
+        };
+
+        let document = Parser::with_options(source.as_str(), options)
+            .parse()
+            .unwrap();
+
+        assert_eq!(document, Parser::new(source.as_str()).parse().unwrap());
+
+        // one call per top-level block, the lines of the list and block quote continue them.
+        assert_eq!(*offsets.lock().unwrap(), [0, 11, 22, 33, 42]);
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        for source in [
+            "# Title\n\ntext\n",
+            // front matter isn't special, but reads the same.
+            "---\ntitle: a\n---\n# Title\n",
+            "   # Title",
+            "",
+        ] {
+            let bom = format!("\u{feff}{}", source);
+
+            let (document, spans) = Parser::new(bom.as_str()).parse_with_spans().unwrap();
+
+            let (expected, expected_spans) = Parser::new(source).parse_with_spans().unwrap();
+
+            assert_eq!(document, expected);
+
+            // spans are offsets of the source with its mark.
+            for (path, span) in expected_spans.iter() {
+                assert_eq!(spans.get(path), Some(span.start + 3..span.end + 3));
+            }
+
+            reparse(&bom, 3..3, "x");
+            reparse(&bom, 3..3, "# ");
+        }
+
+        let (_, spans) = Parser::new("\u{feff}   # Title")
+            .parse_with_spans()
+            .unwrap();
+
+        assert_eq!(spans.get(&[0]), Some(6..13));
+    }
 }
@@ -0,0 +1,2548 @@
+//! Block structure parsing, following the CommonMark parsing strategy: lines are first
+//! incorporated into a tree of container and leaf blocks, then the inline content of
+//! paragraphs and headings is parsed.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::{BTreeSet, VecDeque},
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    ops::Range,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{
+    ast::{
+        code::split_info,
+        spans::Spans,
+        style::{self, is_styled, NodeStyle, Style},
+        *,
+    },
+    to_markdown::{LineEnding, ListNumbering},
+};
+
+use super::{
+    extension::{BlockContext, BlockRule, InlineRule, LineInfo},
+    inline,
+    record::{Record, SourceMap},
+    tree::{Owned, TreeSink},
+    ParserError, ParserOptions,
+};
+
+const CODE_INDENT: usize = 4;
+
+/// Parse markdown `source` into the top-level nodes built by `sink`.
+#[cfg(feature = "arena")]
+pub(super) fn parse_into<'s, S: TreeSink<'s>>(
+    source: &'s str,
+    options: &ParserOptions,
+    sink: &S,
+) -> Result<S::Children, ParserError> {
+    BlockParser::new(source, options).parse(sink)
+}
+
+/// Parse markdown `source` into flow content, for a block rule.
+pub(super) fn parse_flow(
+    source: &str,
+    options: &ParserOptions,
+) -> Result<Vec<Node<'static>>, ParserError> {
+    let children = BlockParser::new(source, options).parse(&Owned)?;
+
+    Ok(children.into_iter().map(Node::into_owned).collect())
+}
+
+/// Parse markdown `source` into a [`Document`], along with the spans of its top-level blocks.
+pub(super) fn parse_spans<'s>(
+    source: &'s str,
+    options: &ParserOptions,
+) -> Result<(Document<'s>, Vec<BlockSpan>), ParserError> {
+    let partial = PartialParse::new(source, options, 0, |_| false);
+
+    let definitions = partial.definitions();
+
+    let (children, spans) = partial.finish(&definitions)?;
+
+    Ok((
+        Document {
+            children,
+            data: None,
+        },
+        spans,
+    ))
+}
+
+/// A top-level block: the source offset of its first line, and the number of top-level nodes
+/// it turned into, its definitions included.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct BlockSpan {
+    pub(super) start: usize,
+    pub(super) nodes: usize,
+}
+
+/// Top-level blocks of a part of a source, parsed but not converted yet.
+pub(super) struct PartialParse<'s> {
+    parser: BlockParser<'s>,
+    /// Source offset where parsing stopped.
+    pub(super) end: usize,
+}
+
+impl<'s> PartialParse<'s> {
+    /// Parse the blocks of `source` from `start`, a line start where no block is open, up to
+    /// the first line start `resume` returns true for where no block is open either.
+    pub(super) fn new<F>(
+        source: &'s str,
+        options: &ParserOptions,
+        start: usize,
+        mut resume: F,
+    ) -> Self
+    where
+        F: FnMut(usize) -> bool,
+    {
+        let mut parser = BlockParser::new(source, options);
+
+        let mut end = start.max(document_start(source));
+
+        while end < source.len() && parser.error.is_none() {
+            end = parser.incorporate_next_line(end);
+
+            if end < source.len() && resume(end) && parser.is_settled(end) {
+                break;
+            }
+        }
+
+        parser.close_all();
+
+        PartialParse { parser, end }
+    }
+
+    /// Returns the identifiers defined by the parsed blocks.
+    pub(super) fn definitions(&self) -> BTreeSet<String> {
+        self.parser.definitions()
+    }
+
+    /// Convert the parsed blocks, `definitions` being the identifiers defined by the whole
+    /// document, or returns the error of a block rule.
+    pub(super) fn finish(
+        mut self,
+        definitions: &BTreeSet<String>,
+    ) -> Result<(Vec<Node<'s>>, Vec<BlockSpan>), ParserError> {
+        let parser = &mut self.parser;
+
+        if let Some(error) = parser.error.take() {
+            return Err(error);
+        }
+
+        let spans = parser.blocks[0]
+            .children
+            .iter()
+            .map(|&child| BlockSpan {
+                start: parser.blocks[child].start,
+                nodes: parser.blocks[child].definitions.len()
+                    + usize::from(!parser.blocks[child].unlinked),
+            })
+            .collect();
+
+        let mut children = vec![];
+
+        parser.convert_children(0, definitions, &mut children, &Owned);
+
+        if let Some(error) = parser.error.take() {
+            return Err(error);
+        }
+
+        Ok((children, spans))
+    }
+}
+
+/// Parse markdown `source` into a [`Document`], along with the markup style of its nodes.
+pub(super) fn parse_with_style<'s>(
+    source: &'s str,
+    options: &ParserOptions,
+) -> Result<(Document<'s>, Style), ParserError> {
+    let mut parser = BlockParser::new(source, options);
+
+    parser.record = Some(RefCell::new(Record {
+        styles: Some(vec![]),
+        ..Default::default()
+    }));
+
+    let document = Document {
+        children: parser.parse(&Owned)?,
+        data: None,
+    };
+
+    let record = parser.record.take().unwrap_or_default().into_inner();
+
+    let mut style = Style::from_document_order(
+        &document,
+        record.styles.unwrap_or_default(),
+        record.numberings,
+    );
+
+    style.set_line_ending(LineEnding::detect(source));
+
+    Ok((document, style))
+}
+
+/// Parse markdown `source` into a [`Document`], along with the source spans of its nodes.
+pub(super) fn parse_with_spans<'s>(
+    source: &'s str,
+    options: &ParserOptions,
+) -> Result<(Document<'s>, Spans), ParserError> {
+    let mut parser = BlockParser::new(source, options);
+
+    parser.record = Some(RefCell::new(Record {
+        spans: Some(vec![]),
+        ..Default::default()
+    }));
+
+    let document = Document {
+        children: parser.parse(&Owned)?,
+        data: None,
+    };
+
+    let spans = parser.record.take().unwrap_or_default().into_inner().spans;
+
+    let spans = Spans::from_document_order(&document, spans.unwrap_or_default());
+
+    Ok((document, spans))
+}
+
+/// Iterator over the top-level nodes of markdown `source`, each one yielded as soon as its
+/// block is closed. Blocks already yielded are dropped from the block tree.
+///
+/// Inline content is parsed when its top-level block is closed, so only the definitions seen
+/// so far are known: references to a definition further down the stream are left as text.
+///
+/// The stream ends at the first error of a block rule.
+pub(super) struct BlockStream<'s> {
+    parser: BlockParser<'s>,
+    /// Source offset of the next line.
+    start: usize,
+    finished: bool,
+    identifiers: BTreeSet<String>,
+    definitions: Vec<Definition<'s>>,
+    ready: VecDeque<Node<'s>>,
+}
+
+impl<'s> BlockStream<'s> {
+    pub(super) fn new(source: &'s str, options: &ParserOptions) -> Self {
+        BlockStream {
+            parser: BlockParser::new(source, options),
+            start: document_start(source),
+            finished: false,
+            identifiers: BTreeSet::new(),
+            definitions: vec![],
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Returns the definitions of the blocks yielded so far, in document order, or the error
+    /// of a block rule.
+    pub(super) fn into_definitions(mut self) -> Result<Vec<Definition<'s>>, ParserError> {
+        match self.parser.error.take() {
+            Some(error) => Err(error),
+            None => Ok(self.definitions),
+        }
+    }
+
+    /// Converts the closed top-level blocks and removes them from the block tree.
+    fn drain_closed(&mut self) {
+        let parser = &mut self.parser;
+
+        let closed = parser.blocks[0]
+            .children
+            .iter()
+            .take_while(|child| !parser.blocks[**child].open)
+            .count();
+
+        if closed == 0 {
+            return;
+        }
+
+        let children = parser.blocks[0]
+            .children
+            .drain(..closed)
+            .collect::<Vec<_>>();
+
+        let end = parser.blocks[0]
+            .children
+            .first()
+            .copied()
+            .unwrap_or(parser.blocks.len());
+
+        for (position, &child) in children.iter().enumerate() {
+            // descendants are created after their block, before its next sibling.
+            let subtree = child..children.get(position + 1).copied().unwrap_or(end);
+
+            for block in &parser.blocks[subtree] {
+                for definition in &block.definitions {
+                    if let Node::Definition(definition) = definition {
+                        self.identifiers.insert(definition.identifier.to_string());
+                        self.definitions.push(definition.as_ref().clone());
+                    }
+                }
+            }
+
+            self.ready
+                .extend(core::mem::take(&mut parser.blocks[child].definitions));
+
+            if !parser.blocks[child].unlinked {
+                self.ready
+                    .push_back(parser.convert(child, &self.identifiers, &Owned));
+            }
+        }
+
+        parser.compact();
+
+        parser.top_level = parser.blocks[0].children.len();
+    }
+}
+
+impl<'s> Iterator for BlockStream<'s> {
+    type Item = Node<'s>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.parser.error.is_some() {
+                return None;
+            }
+
+            if let Some(node) = self.ready.pop_front() {
+                return Some(node);
+            }
+
+            if self.finished {
+                return None;
+            }
+
+            if self.start < self.parser.source.len() {
+                self.start = self.parser.incorporate_next_line(self.start);
+            } else {
+                self.parser.close_all();
+                self.finished = true;
+            }
+
+            self.drain_closed();
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ListData {
+    ordered: bool,
+    bullet_char: u8,
+    start: u32,
+    delimiter: u8,
+    padding: usize,
+    marker_offset: usize,
+}
+
+impl ListData {
+    /// Returns true if an item with `other` marker continues a list started with this one.
+    fn matches(&self, other: &ListData) -> bool {
+        self.ordered == other.ordered
+            && self.delimiter == other.delimiter
+            && self.bullet_char == other.bullet_char
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Fence {
+    char: u8,
+    length: usize,
+    offset: usize,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Document,
+    BlockQuote,
+    List {
+        data: ListData,
+        tight: bool,
+    },
+    Item(ListData),
+    Paragraph,
+    Heading {
+        depth: u8,
+        setext: bool,
+    },
+    ThematicBreak,
+    /// Fenced code when `Some`, indented code otherwise.
+    Code(Option<Fence>),
+    /// HTML block of the given start condition type (1-7).
+    Html(u8),
+    /// GFM table, its content is the header row followed by the body rows.
+    Table(Vec<AlignType>),
+    /// Block taken by a block rule, with the number of its lines left after the current one.
+    Extension(usize),
+}
+
+impl Kind {
+    fn can_contain(&self, kind: &Kind) -> bool {
+        match self {
+            Kind::Document | Kind::BlockQuote | Kind::Item(_) => !matches!(kind, Kind::Item(_)),
+            Kind::List { .. } => matches!(kind, Kind::Item(_)),
+            _ => false,
+        }
+    }
+
+    fn accepts_lines(&self) -> bool {
+        matches!(
+            self,
+            Kind::Paragraph | Kind::Code(_) | Kind::Html(_) | Kind::Table(_)
+        )
+    }
+}
+
+struct Block<'s> {
+    kind: Kind,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Number of containers this block is or is in, the document excluded.
+    depth: usize,
+    open: bool,
+    start_line: usize,
+    /// Source offset of the first line.
+    start: usize,
+    /// Source range of the block, from its marker to the end of its last line.
+    span: Range<usize>,
+    /// Source offsets of `content`.
+    map: SourceMap,
+    end_line: usize,
+    /// Text content of leaf blocks, one `\n` terminated line per added line.
+    content: String,
+    /// Source offset of `content`, only trusted after checking the source matches.
+    content_start: Option<usize>,
+    /// Link reference definitions extracted from the start of a paragraph.
+    definitions: Vec<Node<'s>>,
+    /// Source ranges of `definitions`.
+    definition_spans: Vec<Range<usize>>,
+    /// Set on paragraphs made only of definitions, they are no longer part of the block structure.
+    unlinked: bool,
+    /// Node built by the block rule of an extension block.
+    custom: Option<Node<'s>>,
+}
+
+enum Continue {
+    Matched,
+    Unmatched,
+    /// The line was consumed, e.g. by a closing code fence.
+    Done,
+}
+
+enum Start {
+    None,
+    Container,
+    Leaf,
+}
+
+struct BlockParser<'s> {
+    source: &'s str,
+    blocks: Vec<Block<'s>>,
+    tip: usize,
+    old_tip: usize,
+    last_matched_container: usize,
+    all_closed: bool,
+    line: Cow<'s, str>,
+    line_start: usize,
+    line_number: usize,
+    offset: usize,
+    column: usize,
+    next_nonspace: usize,
+    next_nonspace_column: usize,
+    indent: usize,
+    indented: bool,
+    blank: bool,
+    partially_consumed_tab: bool,
+    /// Side tables of the converted nodes, if recorded.
+    record: Option<RefCell<Record>>,
+    /// Maximum nesting depth of containers, inline ones included.
+    max_depth: usize,
+    /// Extensions of the inline parser.
+    inline_rules: Vec<Arc<dyn InlineRule>>,
+    /// Extensions of the block parser.
+    block_rules: Vec<Arc<dyn BlockRule>>,
+    /// First error of a block rule, or the cancellation, which ends parsing.
+    error: Option<ParserError>,
+    /// Cancels parsing once set.
+    cancel: Option<Arc<AtomicBool>>,
+    /// Called with the source offset of each top-level block.
+    progress: Option<Arc<dyn Fn(usize) + Send + Sync>>,
+    /// Number of children of the document when the last line was incorporated.
+    top_level: usize,
+}
+
+impl<'s> BlockParser<'s> {
+    fn new(source: &'s str, options: &ParserOptions) -> Self {
+        let mut parser = BlockParser {
+            source,
+            blocks: vec![],
+            tip: 0,
+            old_tip: 0,
+            last_matched_container: 0,
+            all_closed: true,
+            line: Cow::Borrowed(""),
+            line_start: 0,
+            line_number: 0,
+            offset: 0,
+            column: 0,
+            next_nonspace: 0,
+            next_nonspace_column: 0,
+            indent: 0,
+            indented: false,
+            blank: false,
+            partially_consumed_tab: false,
+            record: None,
+            max_depth: options.max_nesting_depth,
+            inline_rules: options.inline_rules.clone(),
+            block_rules: options.block_rules.clone(),
+            error: None,
+            cancel: options.cancel.clone(),
+            progress: options.progress.clone(),
+            top_level: 0,
+        };
+
+        parser.push_block(Kind::Document, None);
+
+        parser
+    }
+
+    fn push_block(&mut self, kind: Kind, parent: Option<usize>) -> usize {
+        let index = self.blocks.len();
+
+        let depth = parent.map_or(0, |parent| {
+            let container = matches!(kind, Kind::BlockQuote | Kind::List { .. } | Kind::Item(_));
+
+            self.blocks[parent].depth + usize::from(container)
+        });
+
+        self.blocks.push(Block {
+            kind,
+            parent,
+            children: vec![],
+            depth,
+            open: true,
+            start_line: self.line_number.max(1),
+            start: self.line_start,
+            span: self.line_start + self.next_nonspace..self.line_start + self.next_nonspace,
+            map: SourceMap::default(),
+            end_line: 0,
+            content: String::new(),
+            content_start: None,
+            definitions: vec![],
+            definition_spans: vec![],
+            unlinked: false,
+            custom: None,
+        });
+
+        if let Some(parent) = parent {
+            self.blocks[parent].children.push(index);
+        }
+
+        index
+    }
+
+    fn is_paragraph(&self, index: usize) -> bool {
+        matches!(self.blocks[index].kind, Kind::Paragraph)
+    }
+
+    /// Paragraphs and tables take their lines after trying block starts, which interrupt them.
+    fn is_interruptible(&self, index: usize) -> bool {
+        matches!(self.blocks[index].kind, Kind::Paragraph | Kind::Table(_))
+    }
+
+    /// Children still part of the block structure.
+    fn linked_children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks[index]
+            .children
+            .iter()
+            .copied()
+            .filter(|child| !self.blocks[*child].unlinked)
+    }
+
+    /// Returns true if a blank line separates block `index` from block `next`.
+    fn ends_with_blank_line(&self, index: usize, next: usize) -> bool {
+        self.blocks[index].end_line + 1 != self.blocks[next].start_line
+    }
+
+    fn peek(&self, pos: usize) -> Option<u8> {
+        self.line.as_bytes().get(pos).copied()
+    }
+
+    fn find_next_nonspace(&mut self) {
+        let bytes = self.line.as_bytes();
+
+        let mut pos = self.offset;
+        let mut column = self.column;
+
+        loop {
+            match bytes.get(pos) {
+                Some(b' ') => column += 1,
+                Some(b'\t') => column += 4 - column % 4,
+                _ => break,
+            }
+
+            pos += 1;
+        }
+
+        self.blank = pos >= bytes.len();
+        self.next_nonspace = pos;
+        self.next_nonspace_column = column;
+        self.indent = column - self.column;
+        self.indented = self.indent >= CODE_INDENT;
+    }
+
+    fn advance_next_nonspace(&mut self) {
+        self.offset = self.next_nonspace;
+        self.column = self.next_nonspace_column;
+        self.partially_consumed_tab = false;
+    }
+
+    /// Advance by `count` bytes, or by `count` columns if `columns` is set.
+    fn advance_offset(&mut self, mut count: usize, columns: bool) {
+        while count > 0 {
+            let Some(c) = self.peek(self.offset) else {
+                break;
+            };
+
+            if c == b'\t' {
+                let to_tab = 4 - self.column % 4;
+
+                if columns {
+                    self.partially_consumed_tab = to_tab > count;
+
+                    let advance = to_tab.min(count);
+
+                    self.column += advance;
+
+                    if !self.partially_consumed_tab {
+                        self.offset += 1;
+                    }
+
+                    count -= advance;
+                } else {
+                    self.partially_consumed_tab = false;
+                    self.column += to_tab;
+                    self.offset += 1;
+                    count -= 1;
+                }
+            } else {
+                self.partially_consumed_tab = false;
+                self.offset += 1;
+                self.column += 1;
+                count -= 1;
+            }
+        }
+    }
+
+    fn advance_to_end(&mut self) {
+        self.advance_offset(self.line.len() - self.offset, false);
+    }
+
+    /// Add the rest of the current line to the tip block.
+    fn add_line(&mut self) {
+        let tip = &mut self.blocks[self.tip];
+
+        if self.partially_consumed_tab {
+            // skip over the tab and add the remaining columns as spaces.
+            self.offset += 1;
+
+            let to_tab = 4 - self.column % 4;
+
+            tip.content.extend(core::iter::repeat_n(' ', to_tab));
+        }
+
+        if tip.content_start.is_none() {
+            tip.content_start = Some(self.line_start + self.offset);
+        }
+
+        let start = self.line_start + self.offset;
+
+        tip.map.push_line(
+            tip.content.len(),
+            start,
+            &self.source[start..line_bounds(self.source, start).0],
+        );
+
+        tip.content.push_str(&self.line[self.offset..]);
+        tip.content.push('\n');
+    }
+
+    fn add_child(&mut self, kind: Kind) -> usize {
+        while !self.blocks[self.tip].kind.can_contain(&kind) {
+            self.finalize(self.tip, self.line_number - 1);
+        }
+
+        self.tip = self.push_block(kind, Some(self.tip));
+
+        self.tip
+    }
+
+    fn close_unmatched_blocks(&mut self) {
+        if !self.all_closed {
+            while self.old_tip != self.last_matched_container {
+                let parent = self.blocks[self.old_tip].parent.unwrap_or_default();
+
+                self.finalize(self.old_tip, self.line_number - 1);
+
+                self.old_tip = parent;
+            }
+
+            self.all_closed = true;
+        }
+    }
+
+    fn finalize(&mut self, index: usize, line_number: usize) {
+        let block = &mut self.blocks[index];
+
+        block.open = false;
+        block.end_line = line_number;
+
+        self.tip = block.parent.unwrap_or(index);
+
+        match self.blocks[index].kind {
+            Kind::Paragraph => {
+                let has_definitions = self.extract_definitions(index);
+
+                // a paragraph made only of definitions is removed from the structure.
+                self.blocks[index].unlinked =
+                    has_definitions && is_blank(&self.blocks[index].content);
+            }
+            Kind::Item(_) => {
+                // an empty item ends on its first line.
+                self.blocks[index].end_line = self
+                    .last_child_end_line(index)
+                    .unwrap_or(self.blocks[index].start_line);
+            }
+            Kind::List { .. } => {
+                let items = self.linked_children(index).collect::<Vec<_>>();
+
+                let loose = items
+                    .windows(2)
+                    .any(|pair| self.ends_with_blank_line(pair[0], pair[1]))
+                    || items
+                        .iter()
+                        .any(|item| self.has_blank_line_between_children(*item));
+
+                if let Kind::List { tight, .. } = &mut self.blocks[index].kind {
+                    *tight = !loose;
+                }
+
+                if let Some(end_line) = self.last_child_end_line(index) {
+                    self.blocks[index].end_line = end_line;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn last_child_end_line(&self, index: usize) -> Option<usize> {
+        self.linked_children(index)
+            .last()
+            .map(|child| self.blocks[child].end_line)
+    }
+
+    fn has_blank_line_between_children(&self, index: usize) -> bool {
+        let children = self.linked_children(index).collect::<Vec<_>>();
+
+        children
+            .windows(2)
+            .any(|pair| self.ends_with_blank_line(pair[0], pair[1]))
+    }
+
+    /// Parse link reference definitions at the start of paragraph `index`,
+    /// returns true if any was found.
+    fn extract_definitions(&mut self, index: usize) -> bool {
+        let block = &self.blocks[index];
+
+        if !block.content.starts_with('[') {
+            return false;
+        }
+
+        let text = block.content.strip_suffix('\n').unwrap_or(&block.content);
+
+        let (definitions, consumed) = match self.source_slice(index, 0..text.len()) {
+            Some(text) => parse_definitions(text),
+            None => {
+                let (definitions, consumed) = parse_definitions(text);
+
+                (
+                    definitions
+                        .into_iter()
+                        .map(|(definition, range)| (owned(definition), range))
+                        .collect(),
+                    consumed,
+                )
+            }
+        };
+
+        if definitions.is_empty() {
+            return false;
+        }
+
+        let text_len = text.len();
+
+        let block = &mut self.blocks[index];
+
+        for (definition, range) in definitions {
+            block.definitions.push(definition);
+            block.definition_spans.push(block.map.range(range));
+        }
+
+        if consumed >= text_len {
+            block.content.clear();
+            block.content_start = None;
+        } else {
+            block.content.drain(..consumed);
+            block.content_start = block.content_start.map(|start| start + consumed);
+            block.map = block.map.from(consumed);
+            block.span.start = block.map.source(0);
+        }
+
+        true
+    }
+
+    fn incorporate_line(&mut self, start: usize, line: &'s str) {
+        let mut container = 0;
+
+        self.old_tip = self.tip;
+        self.offset = 0;
+        self.column = 0;
+        self.blank = false;
+        self.partially_consumed_tab = false;
+        self.line_number += 1;
+        self.line_start = start;
+        self.line = if line.contains('\0') {
+            Cow::Owned(line.replace('\0', "\u{FFFD}"))
+        } else {
+            Cow::Borrowed(line)
+        };
+
+        // for each open block, try to match the line start.
+        while let Some(&last) = self.blocks[container].children.last() {
+            if !self.blocks[last].open {
+                break;
+            }
+
+            self.find_next_nonspace();
+
+            match self.continue_block(last) {
+                Continue::Matched => container = last,
+                Continue::Unmatched => break,
+                Continue::Done => {
+                    self.extend_span(last, line);
+
+                    return;
+                }
+            }
+        }
+
+        self.all_closed = container == self.old_tip;
+        self.last_matched_container = container;
+
+        let mut matched_leaf =
+            !self.is_interruptible(container) && self.blocks[container].kind.accepts_lines();
+
+        // unless the last matched container is a code block, try new block starts.
+        while !matched_leaf {
+            self.find_next_nonspace();
+
+            match self.block_start(container) {
+                Start::Container => container = self.tip,
+                Start::Leaf => {
+                    container = self.tip;
+                    matched_leaf = true;
+                }
+                Start::None => {
+                    self.advance_next_nonspace();
+                    break;
+                }
+            }
+        }
+
+        // what remains at the offset is a text line, first check for a lazy paragraph continuation.
+        if !self.all_closed && !self.blank && self.is_paragraph(self.tip) {
+            self.add_line();
+            self.extend_span(self.tip, line);
+        } else {
+            self.close_unmatched_blocks();
+
+            // the delimiter row of a table is consumed by its start.
+            let delimiter_row = matches!(self.blocks[container].kind, Kind::Table(_))
+                && self.offset == self.line.len();
+
+            if self.blocks[container].kind.accepts_lines() && !delimiter_row {
+                self.add_line();
+                self.extend_span(container, line);
+
+                if let Kind::Html(html_type) = self.blocks[container].kind {
+                    if html_block_ends(html_type, &self.line[self.offset..]) {
+                        self.finalize(container, self.line_number);
+                    }
+                }
+            } else if self.offset < self.line.len() && !self.blank {
+                self.add_child(Kind::Paragraph);
+                self.advance_next_nonspace();
+                self.add_line();
+                self.extend_span(self.tip, line);
+            } else {
+                self.extend_span(container, line);
+            }
+        }
+    }
+
+    /// End the spans of block `index` and its ancestors at the end of the current `line`,
+    /// unless it is blank.
+    fn extend_span(&mut self, index: usize, line: &str) {
+        let content = line.trim_end_matches([' ', '\t', '\r', '\n']);
+
+        if content.trim_start_matches([' ', '\t']).is_empty() {
+            return;
+        }
+
+        let end = self.line_start + content.len();
+
+        let mut index = Some(index);
+
+        while let Some(block) = index.map(|index| &mut self.blocks[index]) {
+            block.span.end = block.span.end.max(end);
+            index = block.parent;
+        }
+    }
+
+    fn continue_block(&mut self, index: usize) -> Continue {
+        match &self.blocks[index].kind {
+            Kind::Document | Kind::List { .. } => {}
+            Kind::BlockQuote => {
+                if self.indented || self.peek(self.next_nonspace) != Some(b'>') {
+                    return Continue::Unmatched;
+                }
+
+                self.advance_next_nonspace();
+                self.advance_offset(1, false);
+
+                if matches!(self.peek(self.offset), Some(b' ' | b'\t')) {
+                    self.advance_offset(1, true);
+                }
+            }
+            Kind::Item(data) => {
+                let width = data.marker_offset + data.padding;
+
+                if self.blank {
+                    if self.linked_children(index).next().is_none() {
+                        // a blank line after an empty list item ends it.
+                        return Continue::Unmatched;
+                    }
+
+                    self.advance_next_nonspace();
+                } else if self.indent >= width {
+                    self.advance_offset(width, true);
+                } else {
+                    return Continue::Unmatched;
+                }
+            }
+            Kind::Heading { .. } | Kind::ThematicBreak => return Continue::Unmatched,
+            Kind::Code(Some(fence)) => {
+                let fence = *fence;
+
+                let line = self.line.clone();
+
+                if self.indent <= 3 && is_closing_fence(&line[self.next_nonspace..], fence) {
+                    self.finalize(index, self.line_number);
+
+                    return Continue::Done;
+                }
+
+                // skip optional spaces of the fence offset.
+                let mut skip = fence.offset;
+
+                while skip > 0 && matches!(self.peek(self.offset), Some(b' ' | b'\t')) {
+                    self.advance_offset(1, true);
+                    skip -= 1;
+                }
+            }
+            Kind::Code(None) => {
+                if self.indent >= CODE_INDENT {
+                    self.advance_offset(CODE_INDENT, true);
+                } else if self.blank {
+                    self.advance_next_nonspace();
+                } else {
+                    return Continue::Unmatched;
+                }
+            }
+            Kind::Html(html_type) => {
+                if self.blank && matches!(html_type, 6 | 7) {
+                    return Continue::Unmatched;
+                }
+            }
+            Kind::Paragraph | Kind::Table(_) => {
+                if self.blank {
+                    return Continue::Unmatched;
+                }
+            }
+            Kind::Extension(lines) => {
+                let Some(lines) = lines.checked_sub(1) else {
+                    return Continue::Unmatched;
+                };
+
+                self.blocks[index].kind = Kind::Extension(lines);
+                self.advance_to_end();
+
+                return Continue::Done;
+            }
+        }
+
+        Continue::Matched
+    }
+
+    fn block_start(&mut self, container: usize) -> Start {
+        let line = self.line.clone();
+
+        let rest = &line[self.next_nonspace..];
+
+        // containers nested too deep are left as text.
+        let depth = self.blocks[container].depth;
+
+        if !self.indented && !self.blank && depth < self.max_depth {
+            if let Some(start) = self.extension_start(container) {
+                return start;
+            }
+        }
+
+        if !self.indented {
+            if rest.starts_with('>') && depth < self.max_depth {
+                self.advance_next_nonspace();
+                self.advance_offset(1, false);
+
+                // optional following space.
+                if matches!(self.peek(self.offset), Some(b' ' | b'\t')) {
+                    self.advance_offset(1, true);
+                }
+
+                self.close_unmatched_blocks();
+                self.add_child(Kind::BlockQuote);
+
+                return Start::Container;
+            }
+
+            if let Some((depth, len)) = scan_atx_heading(rest) {
+                self.advance_next_nonspace();
+                self.advance_offset(len, false);
+                self.close_unmatched_blocks();
+
+                let index = self.add_child(Kind::Heading {
+                    depth,
+                    setext: false,
+                });
+
+                let start = self.line_start + self.offset;
+
+                let block = &mut self.blocks[index];
+
+                block.content = strip_closing_sequence(&line[self.offset..]).to_owned();
+                block.content_start = Some(start);
+                block.map = SourceMap::at(start);
+                block.map.push_line(
+                    0,
+                    start,
+                    &self.source[start..line_bounds(self.source, start).0],
+                );
+
+                self.advance_to_end();
+
+                return Start::Leaf;
+            }
+
+            if let Some((char, length)) = scan_open_fence(rest) {
+                let offset = self.indent;
+
+                self.close_unmatched_blocks();
+                self.add_child(Kind::Code(Some(Fence {
+                    char,
+                    length,
+                    offset,
+                })));
+                self.advance_next_nonspace();
+                self.advance_offset(length, false);
+
+                return Start::Leaf;
+            }
+
+            if rest.starts_with('<') {
+                let interrupts_paragraph = self.is_paragraph(container)
+                    || (!self.all_closed && !self.blank && self.is_paragraph(self.tip));
+
+                let html_type = (1..=7).find(|html_type| {
+                    html_block_starts(*html_type, rest) && (*html_type < 7 || !interrupts_paragraph)
+                });
+
+                if let Some(html_type) = html_type {
+                    self.close_unmatched_blocks();
+                    self.add_child(Kind::Html(html_type));
+
+                    // the opening line is kept as-is, including leading spaces.
+                    return Start::Leaf;
+                }
+            }
+
+            if self.is_paragraph(container) {
+                if let Some(align) = scan_delimiter_row(rest) {
+                    if self.start_table(container, align) {
+                        self.advance_to_end();
+
+                        return Start::Leaf;
+                    }
+                }
+
+                if let Some(depth) = scan_setext_underline(rest) {
+                    self.close_unmatched_blocks();
+                    self.extract_definitions(container);
+
+                    if !self.blocks[container].content.is_empty() {
+                        self.blocks[container].kind = Kind::Heading {
+                            depth,
+                            setext: true,
+                        };
+                        self.tip = container;
+                        self.advance_to_end();
+
+                        return Start::Leaf;
+                    }
+                }
+            }
+
+            if is_thematic_break(rest) {
+                self.close_unmatched_blocks();
+                self.add_child(Kind::ThematicBreak);
+                self.advance_to_end();
+
+                return Start::Leaf;
+            }
+        }
+
+        // a new item goes in the container list, or in a new list.
+        let item_depth = match self.blocks[container].kind {
+            Kind::List { .. } => depth + 1,
+            _ => depth + 2,
+        };
+
+        if (!self.indented || matches!(self.blocks[container].kind, Kind::List { .. }))
+            && item_depth <= self.max_depth
+        {
+            if let Some(data) = self.parse_list_marker(container) {
+                self.close_unmatched_blocks();
+
+                let continues = matches!(
+                    &self.blocks[self.tip].kind,
+                    Kind::List { data: list, .. } if list.matches(&data)
+                );
+
+                if !continues {
+                    self.add_child(Kind::List {
+                        data: data.clone(),
+                        tight: true,
+                    });
+                }
+
+                self.add_child(Kind::Item(data));
+
+                return Start::Container;
+            }
+        }
+
+        if self.indented && !self.is_paragraph(self.tip) && !self.blank {
+            self.advance_offset(CODE_INDENT, true);
+            self.close_unmatched_blocks();
+            self.add_child(Kind::Code(None));
+
+            return Start::Leaf;
+        }
+
+        Start::None
+    }
+
+    /// Probe the block rules on the current line, in `container`. A rule taking the line
+    /// starts an extension block with the lines it advanced over.
+    fn extension_start(&mut self, container: usize) -> Option<Start> {
+        if self.block_rules.is_empty() || self.error.is_some() {
+            return None;
+        }
+
+        let text = self.rest_of_line();
+
+        let info = LineInfo {
+            text: &text,
+            indent: self.indent,
+            number: self.line_number,
+            in_paragraph: self.is_paragraph(container)
+                || (!self.all_closed && self.is_paragraph(self.tip)),
+        };
+
+        let rules = self
+            .block_rules
+            .iter()
+            .filter(|rule| rule.start(&info))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if rules.is_empty() {
+            return None;
+        }
+
+        let prefix = match &self.line {
+            Cow::Borrowed(line) => Cow::Borrowed(&line[..self.offset]),
+            Cow::Owned(line) => Cow::Owned(line[..self.offset].to_owned()),
+        };
+
+        // the block is one more container for nested content.
+        let options = ParserOptions {
+            max_nesting_depth: self.max_depth - self.blocks[container].depth - 1,
+            inline_rules: self.inline_rules.clone(),
+            block_rules: self.block_rules.clone(),
+            cancel: self.cancel.clone(),
+            // offsets in the content of the block aren't offsets of the source.
+            progress: None,
+        };
+
+        for rule in rules {
+            let (_, mut next) = line_bounds(self.source, self.line_start);
+
+            let mut next_line = || {
+                if next >= self.source.len() {
+                    return None;
+                }
+
+                let line = self.continued_line(container, next)?;
+
+                next = line_bounds(self.source, next).1;
+
+                Some(line)
+            };
+
+            let mut cx = BlockContext::new(
+                prefix.clone(),
+                text.clone(),
+                &mut next_line,
+                options.clone(),
+            );
+
+            match rule.parse(&mut cx) {
+                Ok(Some(node)) => {
+                    let lines = cx.lines();
+
+                    self.close_unmatched_blocks();
+
+                    let index = self.add_child(Kind::Extension(lines));
+
+                    self.blocks[index].custom = Some(node);
+                    self.advance_to_end();
+
+                    return Some(Start::Leaf);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    self.error = Some(error);
+
+                    return None;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the rest of the current line from the offset, a partially consumed tab
+    /// expanded to spaces.
+    fn rest_of_line(&self) -> Cow<'s, str> {
+        let (rest, spaces) = match self.partially_consumed_tab {
+            true => (self.offset + 1, 4 - self.column % 4),
+            false => (self.offset, 0),
+        };
+
+        match &self.line {
+            Cow::Borrowed(line) if spaces == 0 => Cow::Borrowed(&line[rest..]),
+            line => Cow::Owned(" ".repeat(spaces) + &line[rest..]),
+        }
+    }
+
+    /// Returns the rest of the line starting at `start` after the prefixes of `container` and
+    /// its ancestors, if they all go on on it. The current line is left as it was.
+    fn continued_line(&mut self, container: usize, start: usize) -> Option<Cow<'s, str>> {
+        let mut chain = vec![];
+
+        let mut index = container;
+
+        while let Some(parent) = self.blocks[index].parent {
+            chain.push(index);
+            index = parent;
+        }
+
+        let saved = (
+            core::mem::take(&mut self.line),
+            self.line_start,
+            self.offset,
+            self.column,
+            self.partially_consumed_tab,
+        );
+
+        let line = &self.source[start..line_bounds(self.source, start).0];
+
+        self.line = if line.contains('\0') {
+            Cow::Owned(line.replace('\0', "\u{FFFD}"))
+        } else {
+            Cow::Borrowed(line)
+        };
+        self.line_start = start;
+        self.offset = 0;
+        self.column = 0;
+        self.partially_consumed_tab = false;
+
+        let matched = chain.iter().rev().all(|&index| {
+            self.find_next_nonspace();
+
+            matches!(self.continue_block(index), Continue::Matched)
+        });
+
+        let rest = matched.then(|| self.rest_of_line());
+
+        (
+            self.line,
+            self.line_start,
+            self.offset,
+            self.column,
+            self.partially_consumed_tab,
+        ) = saved;
+
+        // the line state derived from the offset.
+        self.find_next_nonspace();
+
+        rest
+    }
+
+    /// Turn the last line of paragraph `index` into the header row of a table with `align`
+    /// columns, the current line being the delimiter row. Returns false if the cell counts
+    /// differ.
+    fn start_table(&mut self, index: usize, align: Vec<AlignType>) -> bool {
+        self.extract_definitions(index);
+
+        let content = &self.blocks[index].content;
+
+        let Some(body) = content.strip_suffix('\n') else {
+            return false;
+        };
+
+        let header = body.rfind('\n').map_or(0, |newline| newline + 1);
+
+        if split_row(&body[header..]).len() != align.len() {
+            return false;
+        }
+
+        self.close_unmatched_blocks();
+
+        if header == 0 {
+            self.blocks[index].kind = Kind::Table(align);
+            self.tip = index;
+
+            return true;
+        }
+
+        // the lines before the header row stay a paragraph.
+        let block = &mut self.blocks[index];
+
+        let row = block.content.split_off(header);
+        let row_start = block.content_start.map(|start| start + header);
+        let row_map = block.map.from(header);
+
+        block.span.end = block.map.range(0..block.content.trim_end().len()).end;
+        block.map.truncate(header);
+
+        self.finalize(index, self.line_number - 2);
+
+        let table = self.add_child(Kind::Table(align));
+
+        let block = &mut self.blocks[table];
+
+        block.start_line = self.line_number - 1;
+        block.start = previous_line_start(self.source, self.line_start);
+        block.content = row;
+        block.content_start = row_start;
+        block.span.start = row_map.source(0);
+        block.map = row_map;
+
+        true
+    }
+
+    /// Parse a list marker at the next non-space position and advance past it and its padding.
+    fn parse_list_marker(&mut self, container: usize) -> Option<ListData> {
+        if self.indent >= CODE_INDENT {
+            return None;
+        }
+
+        let line = self.line.clone();
+
+        let rest = &line[self.next_nonspace..];
+
+        let bytes = rest.as_bytes();
+
+        let interrupts_paragraph = self.is_paragraph(container);
+
+        let mut data = ListData {
+            ordered: false,
+            bullet_char: 0,
+            start: 1,
+            delimiter: 0,
+            padding: 0,
+            marker_offset: self.indent,
+        };
+
+        let marker_len = match *bytes.first()? {
+            c @ (b'*' | b'+' | b'-') => {
+                data.bullet_char = c;
+
+                1
+            }
+            _ => {
+                let digits = bytes.iter().take_while(|c| c.is_ascii_digit()).count();
+
+                if !(1..=9).contains(&digits) {
+                    return None;
+                }
+
+                let delimiter = *bytes.get(digits)?;
+
+                if !matches!(delimiter, b'.' | b')') {
+                    return None;
+                }
+
+                let start = rest[..digits].parse().ok()?;
+
+                // only lists starting with 1 can interrupt a paragraph.
+                if interrupts_paragraph && start != 1 {
+                    return None;
+                }
+
+                data.ordered = true;
+                data.start = start;
+                data.delimiter = delimiter;
+
+                digits + 1
+            }
+        };
+
+        if !matches!(bytes.get(marker_len), None | Some(b' ' | b'\t')) {
+            return None;
+        }
+
+        // an empty list item can't interrupt a paragraph.
+        if interrupts_paragraph && is_blank(&rest[marker_len..]) {
+            return None;
+        }
+
+        self.advance_next_nonspace();
+        self.advance_offset(marker_len, true);
+
+        let spaces_start_column = self.column;
+        let spaces_start_offset = self.offset;
+
+        loop {
+            self.advance_offset(1, true);
+
+            if self.column - spaces_start_column >= 5
+                || !matches!(self.peek(self.offset), Some(b' ' | b'\t'))
+            {
+                break;
+            }
+        }
+
+        let blank_item = self.peek(self.offset).is_none();
+
+        let spaces_after_marker = self.column - spaces_start_column;
+
+        if !(1..5).contains(&spaces_after_marker) || blank_item {
+            // the item content starts one space after the marker.
+            data.padding = marker_len + 1;
+
+            self.column = spaces_start_column;
+            self.offset = spaces_start_offset;
+
+            if matches!(self.peek(self.offset), Some(b' ' | b'\t')) {
+                self.advance_offset(1, true);
+            }
+        } else {
+            data.padding = marker_len + spaces_after_marker;
+        }
+
+        Some(data)
+    }
+
+    /// Incorporates the line starting at `start`, returns the start of the next line.
+    fn incorporate_next_line(&mut self, start: usize) -> usize {
+        let (end, next) = line_bounds(self.source, start);
+
+        self.incorporate_line(start, &self.source[start..end]);
+
+        if (self.cancel.is_some() || self.progress.is_some())
+            && self.blocks[0].children.len() != self.top_level
+        {
+            self.top_level = self.blocks[0].children.len();
+
+            if let Some(progress) = &self.progress {
+                progress(start);
+            }
+
+            self.check_cancelled(start);
+        }
+
+        next
+    }
+
+    /// Ends parsing at `offset` if the cancellation flag is set, returns true if it is.
+    fn check_cancelled(&mut self, offset: usize) -> bool {
+        let cancelled = self
+            .cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed));
+
+        if cancelled && self.error.is_none() {
+            self.error = Some(ParserError::Cancelled { offset });
+        }
+
+        cancelled
+    }
+
+    /// Closes every open block, the document included.
+    fn close_all(&mut self) {
+        loop {
+            let tip = self.tip;
+
+            self.finalize(tip, self.line_number);
+
+            if tip == 0 {
+                break;
+            }
+        }
+    }
+
+    /// Drops the blocks between the document and its first remaining child, all of them
+    /// already converted, and renumbers the blocks that follow.
+    fn compact(&mut self) {
+        let keep = self.blocks[0]
+            .children
+            .first()
+            .copied()
+            .unwrap_or(self.blocks.len());
+
+        if keep <= 1 {
+            return;
+        }
+
+        self.blocks.drain(1..keep);
+
+        // indices of dropped blocks are only left in stale line state.
+        let shift = |index: usize| match index {
+            index if index < keep => 0,
+            index => index - (keep - 1),
+        };
+
+        for block in &mut self.blocks {
+            block.parent = block.parent.map(shift);
+
+            for child in &mut block.children {
+                *child = shift(*child);
+            }
+        }
+
+        self.tip = shift(self.tip);
+        self.old_tip = shift(self.old_tip);
+        self.last_matched_container = shift(self.last_matched_container);
+    }
+
+    /// Incorporate every line of the source, then convert the block tree.
+    fn parse<S: TreeSink<'s>>(&mut self, sink: &S) -> Result<S::Children, ParserError> {
+        let mut start = document_start(self.source);
+
+        while start < self.source.len() && self.error.is_none() {
+            start = self.incorporate_next_line(start);
+        }
+
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        let children = self.finish(sink);
+
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => Ok(children),
+        }
+    }
+
+    fn finish<S: TreeSink<'s>>(&mut self, sink: &S) -> S::Children {
+        self.close_all();
+
+        let definitions = self.definitions();
+
+        let mut children = sink.children();
+
+        self.convert_children(0, &definitions, &mut children, sink);
+
+        children
+    }
+
+    /// Returns the identifiers defined by the blocks, nested ones included.
+    fn definitions(&self) -> BTreeSet<String> {
+        self.blocks
+            .iter()
+            .flat_map(|block| &block.definitions)
+            .filter_map(|definition| match definition {
+                Node::Definition(definition) => Some(definition.identifier.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns true if no block is open at the line starting at `start`, nor can continue on
+    /// it: the line before is blank and closed the last top-level block for good. Lists, code
+    /// and HTML blocks may go on after a blank line.
+    fn is_settled(&self, start: usize) -> bool {
+        follows_blank_line(self.source, start)
+            && self.blocks[0].children.last().is_none_or(|&last| {
+                matches!(
+                    self.blocks[last].kind,
+                    Kind::Paragraph
+                        | Kind::Heading { .. }
+                        | Kind::ThematicBreak
+                        | Kind::BlockQuote
+                        | Kind::Table(_)
+                )
+            })
+    }
+
+    /// Source text of `range` of the content of block `index`, if the content is verbatim source.
+    fn source_slice(&self, index: usize, range: Range<usize>) -> Option<&'s str> {
+        let block = &self.blocks[index];
+
+        let start = block.content_start?;
+
+        self.source
+            .get(start + range.start..start + range.end)
+            .filter(|source| *source == &block.content[range])
+    }
+
+    fn text(&self, index: usize, range: Range<usize>) -> Cow<'s, str> {
+        match self.source_slice(index, range.clone()) {
+            Some(source) => Cow::Borrowed(source),
+            None => Cow::Owned(self.blocks[index].content[range].to_owned()),
+        }
+    }
+
+    fn convert_children<S: TreeSink<'s>>(
+        &mut self,
+        index: usize,
+        definitions: &BTreeSet<String>,
+        out: &mut S::Children,
+        sink: &S,
+    ) {
+        let children = core::mem::take(&mut self.blocks[index].children);
+
+        for child in children {
+            if index == 0 && self.check_cancelled(self.blocks[child].start) {
+                break;
+            }
+
+            let spans = core::mem::take(&mut self.blocks[child].definition_spans);
+
+            for (definition, span) in core::mem::take(&mut self.blocks[child].definitions)
+                .into_iter()
+                .zip(spans)
+            {
+                self.record_span(span);
+                sink.push(out, sink.node(definition));
+            }
+
+            if !self.blocks[child].unlinked {
+                let node = self.convert(child, definitions, sink);
+
+                sink.push(out, node);
+            }
+        }
+    }
+
+    fn convert<S: TreeSink<'s>>(
+        &mut self,
+        index: usize,
+        definitions: &BTreeSet<String>,
+        sink: &S,
+    ) -> S::Node {
+        self.record_span(self.blocks[index].span.clone());
+
+        if let Some(record) = self
+            .record
+            .as_mut()
+            .map(RefCell::get_mut)
+            .filter(|record| record.styles.is_some())
+        {
+            let blocks = &self.blocks;
+
+            let style = match &blocks[index].kind {
+                Kind::List { data, .. } if data.ordered => {
+                    let items = &blocks[index].children;
+
+                    // a single item repeats no number.
+                    let repeated = items.len() > 1
+                        && items.iter().all(|&item| {
+                            matches!(&blocks[item].kind, Kind::Item(item) if item.start == data.start)
+                        });
+
+                    record.numberings.push(Some(if repeated {
+                        ListNumbering::One
+                    } else {
+                        ListNumbering::Number
+                    }));
+
+                    Some(NodeStyle::List(data.delimiter as char))
+                }
+                Kind::List { data, .. } => Some(NodeStyle::List(data.bullet_char as char)),
+                Kind::Heading { setext, .. } => Some(NodeStyle::Heading { setext: *setext }),
+                Kind::Code(fence) => Some(NodeStyle::Code(fence.map(|fence| style::Fence {
+                    marker: fence.char as char,
+                    size: fence.length,
+                    indent: fence.offset,
+                }))),
+                _ => None,
+            };
+
+            if let Some(styles) = &mut record.styles {
+                styles.extend(style.map(Some));
+            }
+        }
+
+        match &self.blocks[index].kind {
+            Kind::Document => unreachable!("document is never a child"),
+            Kind::BlockQuote => {
+                let mut children = sink.children();
+
+                self.convert_children(index, definitions, &mut children, sink);
+
+                sink.parent(
+                    Node::Blockquote(Blockquote {
+                        children: vec![],
+                        data: None,
+                    }),
+                    children,
+                )
+            }
+            Kind::List { data, tight } => {
+                let mut list = List::new(data.ordered, Some(data.start));
+
+                list.spread = Some(!tight);
+
+                let mut children = sink.children();
+
+                self.convert_children(index, definitions, &mut children, sink);
+
+                sink.parent(Node::List(list), children)
+            }
+            Kind::Item(_) => {
+                let mut item = ListItem::new(None);
+
+                item.spread = Some(self.has_blank_line_between_children(index));
+
+                let mut children = sink.children();
+
+                self.convert_children(index, definitions, &mut children, sink);
+
+                sink.parent(Node::ListItem(item), children)
+            }
+            Kind::Paragraph => sink.parent(
+                Node::Paragraph(Paragraph {
+                    children: vec![],
+                    data: None,
+                }),
+                self.inline(index, definitions, sink),
+            ),
+            // atx depths are scanned within `Heading::DEPTHS`, setext ones are 1 or 2.
+            Kind::Heading { depth, .. } => sink.parent(
+                Node::Heading(Heading {
+                    children: vec![],
+                    depth: *depth,
+                    data: None,
+                }),
+                self.inline(index, definitions, sink),
+            ),
+            Kind::ThematicBreak => sink.node(Node::ThematicBreak(ThematicBreak { data: None })),
+            Kind::Extension(_) => {
+                let Some(node) = self.blocks[index].custom.take() else {
+                    unreachable!("extension blocks hold their node");
+                };
+
+                // the markup of nodes built by rules is unknown, they all span the block.
+                let nodes = || core::iter::once(&node).chain(node.descendants());
+
+                if let Some(record) = &self.record {
+                    let mut record = record.borrow_mut();
+
+                    if let Some(styles) = &mut record.styles {
+                        styles.extend(nodes().filter(|node| is_styled(node)).map(|_| None));
+
+                        record.numberings.extend(
+                            nodes()
+                                .filter(|node| matches!(node, Node::List(list) if list.ordered == Some(true)))
+                                .map(|_| None),
+                        );
+                    }
+
+                    if let Some(spans) = &mut record.spans {
+                        let span = self.blocks[index].span.clone();
+
+                        spans.extend(core::iter::repeat_n(span, node.descendants().count()));
+                    }
+                }
+
+                sink.node(node)
+            }
+            Kind::Code(fence) => {
+                let content = &self.blocks[index].content;
+
+                let (lang, meta, value) = match fence {
+                    Some(_) => {
+                        let newline = content.find('\n').unwrap_or(content.len());
+
+                        let info = trimmed_range(&content[..newline]);
+
+                        let value = (newline + 1).min(content.len())..content.len();
+
+                        let value = value.start..value.end.max(value.start + 1) - 1;
+
+                        let (lang, meta) = match self.text(index, info) {
+                            Cow::Borrowed(info) => split_info(info),
+                            Cow::Owned(info) => {
+                                let (lang, meta) = split_info(&info);
+
+                                (
+                                    lang.map(|lang| Cow::Owned(lang.into_owned())),
+                                    meta.map(|meta| Cow::Owned(meta.into_owned())),
+                                )
+                            }
+                        };
+
+                        (lang, meta, value)
+                    }
+                    None => {
+                        let mut lines = content.split('\n').collect::<Vec<_>>();
+
+                        while lines.last().is_some_and(|line| is_blank(line)) {
+                            lines.pop();
+                        }
+
+                        let len = lines.iter().map(|line| line.len() + 1).sum::<usize>();
+
+                        (None, None, 0..len.saturating_sub(1))
+                    }
+                };
+
+                sink.node(Node::Code(Box::new(Code {
+                    value: self.text(index, value),
+                    lang,
+                    meta,
+                    data: None,
+                })))
+            }
+            Kind::Table(align) => {
+                let align = align.clone();
+
+                let mut rows = sink.children();
+
+                let mut start = 0;
+
+                for line in self.blocks[index].content.split_terminator('\n') {
+                    let mut cells = sink.children();
+
+                    let ranges = split_row(line);
+
+                    let row = trimmed_range(line);
+
+                    let row = self.blocks[index]
+                        .map
+                        .range(start + row.start..start + row.end);
+
+                    self.record_span(row.clone());
+
+                    for column in 0..align.len() {
+                        let children = match ranges.get(column) {
+                            Some(range) => {
+                                let range = start + range.start..start + range.end;
+
+                                self.record_span(self.blocks[index].map.range(range.clone()));
+                                self.cell(index, range, definitions, sink)
+                            }
+                            None => {
+                                self.record_span(row.end..row.end);
+
+                                sink.children()
+                            }
+                        };
+
+                        sink.push(
+                            &mut cells,
+                            sink.parent(
+                                Node::TableCell(TableCell {
+                                    children: vec![],
+                                    data: None,
+                                }),
+                                children,
+                            ),
+                        );
+                    }
+
+                    sink.push(
+                        &mut rows,
+                        sink.parent(
+                            Node::TableRow(TableRow {
+                                children: vec![],
+                                data: None,
+                            }),
+                            cells,
+                        ),
+                    );
+
+                    start += line.len() + 1;
+                }
+
+                sink.parent(
+                    Node::Table(Table {
+                        children: vec![],
+                        align,
+                        data: None,
+                    }),
+                    rows,
+                )
+            }
+            Kind::Html(_) => {
+                let content = &self.blocks[index].content;
+
+                let mut end = content.len();
+
+                // strip trailing line endings, with the spaces following them.
+                loop {
+                    let trimmed = content[..end].trim_end_matches(' ');
+
+                    match trimmed.strip_suffix('\n') {
+                        Some(trimmed) => end = trimmed.len(),
+                        None => break,
+                    }
+                }
+
+                sink.node(Node::Html(Html {
+                    value: self.text(index, 0..end),
+                    data: None,
+                }))
+            }
+        }
+    }
+
+    /// Record the source span of the next converted node, if spans are recorded.
+    fn record_span(&self, span: Range<usize>) {
+        if let Some(record) = &self.record {
+            if let Some(spans) = &mut record.borrow_mut().spans {
+                spans.push(span);
+            }
+        }
+    }
+
+    /// The record to fill while parsing the content of block `index` from content offset
+    /// `offset` on, with the source offsets of that content.
+    fn recorder(&self, index: usize, offset: usize) -> Option<(&RefCell<Record>, SourceMap)> {
+        let record = self.record.as_ref()?;
+
+        let map = match record.borrow().spans {
+            Some(_) => self.blocks[index].map.from(offset),
+            None => SourceMap::default(),
+        };
+
+        Some((record, map))
+    }
+
+    /// Parse the content of paragraph or heading `index` into phrasing content.
+    fn inline<S: TreeSink<'s>>(
+        &self,
+        index: usize,
+        definitions: &BTreeSet<String>,
+        sink: &S,
+    ) -> S::Children {
+        let range = trimmed_range(&self.blocks[index].content);
+
+        let record = self.recorder(index, range.start);
+
+        let depth = self.inline_depth(index);
+
+        let rules = &self.inline_rules;
+
+        match self.source_slice(index, range.clone()) {
+            Some(subject) => inline::parse(subject, definitions, rules, depth, record, sink),
+            None => inline_owned(
+                &self.blocks[index].content[range],
+                definitions,
+                rules,
+                depth,
+                record,
+                sink,
+            ),
+        }
+    }
+
+    /// Parse the cell at `range` of the content of table `index` into phrasing content,
+    /// escaped pipes being unescaped first, even in code spans.
+    fn cell<S: TreeSink<'s>>(
+        &self,
+        index: usize,
+        range: Range<usize>,
+        definitions: &BTreeSet<String>,
+        sink: &S,
+    ) -> S::Children {
+        let content = &self.blocks[index].content[range.clone()];
+
+        let record = self.recorder(index, range.start);
+
+        let depth = self.inline_depth(index);
+
+        let rules = &self.inline_rules;
+
+        if content.contains("\\|") {
+            // each removed backslash shifts the offsets after it.
+            let record = record.map(|(record, map)| {
+                let mut unescaped = SourceMap::at(map.source(0));
+
+                for (removed, (pipe, _)) in content.match_indices("\\|").enumerate() {
+                    unescaped.push(pipe - removed, map.source(pipe + 1));
+                }
+
+                (record, unescaped)
+            });
+
+            let content = content.replace("\\|", "|");
+
+            return inline_owned(&content, definitions, rules, depth, record, sink);
+        }
+
+        match self.source_slice(index, range) {
+            Some(subject) => inline::parse(subject, definitions, rules, depth, record, sink),
+            None => inline_owned(content, definitions, rules, depth, record, sink),
+        }
+    }
+
+    /// Returns the maximum nesting depth of the inline containers of leaf block `index`.
+    fn inline_depth(&self, index: usize) -> usize {
+        self.max_depth.saturating_sub(self.blocks[index].depth)
+    }
+}
+
+/// Parse `subject`, which doesn't outlive the parser, into owned phrasing content.
+fn inline_owned<'s, S: TreeSink<'s>>(
+    subject: &str,
+    definitions: &BTreeSet<String>,
+    rules: &[Arc<dyn InlineRule>],
+    max_depth: usize,
+    record: Option<(&RefCell<Record>, SourceMap)>,
+    sink: &S,
+) -> S::Children {
+    let mut children = sink.children();
+
+    for node in inline::parse(subject, definitions, rules, max_depth, record, &Owned) {
+        sink.push(&mut children, sink.node(owned(node)));
+    }
+
+    children
+}
+
+fn owned<'s>(node: Node<'_>) -> Node<'s> {
+    node.into_owned()
+}
+
+/// Returns the offset of the first line of `source`, after its byte order mark if any.
+fn document_start(source: &str) -> usize {
+    if source.starts_with('\u{feff}') {
+        '\u{feff}'.len_utf8()
+    } else {
+        0
+    }
+}
+
+fn is_blank(s: &str) -> bool {
+    s.bytes().all(|c| matches!(c, b' ' | b'\t' | b'\n'))
+}
+
+/// Returns true if the line starting at `start` of `source` follows a blank line.
+/// Returns the end of the line starting at `start`, before its line ending, and the start of
+/// the next line.
+fn line_bounds(source: &str, start: usize) -> (usize, usize) {
+    let bytes = source.as_bytes();
+
+    let end = source[start..]
+        .find(['\r', '\n'])
+        .map(|offset| start + offset)
+        .unwrap_or(source.len());
+
+    let next = match bytes.get(end) {
+        Some(b'\r') if bytes.get(end + 1) == Some(&b'\n') => end + 2,
+        Some(_) => end + 1,
+        None => end,
+    };
+
+    (end, next)
+}
+
+pub(super) fn follows_blank_line(source: &str, start: usize) -> bool {
+    let previous = previous_line_start(source, start);
+
+    start > 0 && is_blank(source[previous..start].trim_end_matches(['\r', '\n']))
+}
+
+/// Returns the start of the line before the one starting at `start`, which must follow a line
+/// ending.
+fn previous_line_start(source: &str, start: usize) -> usize {
+    let before = &source[..start];
+
+    let before = before
+        .strip_suffix("\r\n")
+        .or_else(|| before.strip_suffix(['\n', '\r']))
+        .unwrap_or(before);
+
+    before.rfind(['\n', '\r']).map_or(0, |newline| newline + 1)
+}
+
+/// Range of `s` without leading and trailing spaces, tabs and line endings.
+fn trimmed_range(s: &str) -> Range<usize> {
+    let pattern = [' ', '\t', '\n'];
+
+    let start = s.len() - s.trim_start_matches(pattern).len();
+    let end = s.trim_end_matches(pattern).len().max(start);
+
+    start..end
+}
+
+/// Scan an ATX heading opening sequence, returns the depth and the length of the sequence
+/// including the following spaces.
+fn scan_atx_heading(s: &str) -> Option<(u8, usize)> {
+    let hashes = s.bytes().take_while(|c| *c == b'#').count();
+
+    // seven or more `#` are a paragraph.
+    let depth = u8::try_from(hashes)
+        .ok()
+        .filter(|depth| Heading::DEPTHS.contains(depth))?;
+
+    let spaces = s[hashes..]
+        .bytes()
+        .take_while(|c| matches!(c, b' ' | b'\t'))
+        .count();
+
+    if spaces == 0 && hashes < s.len() {
+        return None;
+    }
+
+    Some((depth, hashes + spaces))
+}
+
+/// Strip the optional closing sequence of an ATX heading.
+fn strip_closing_sequence(content: &str) -> &str {
+    let trimmed = content.trim_end_matches([' ', '\t']);
+
+    let without_hashes = trimmed.trim_end_matches('#');
+
+    if without_hashes.len() == trimmed.len() {
+        return content;
+    }
+
+    if is_blank(without_hashes) {
+        return "";
+    }
+
+    if without_hashes.ends_with([' ', '\t']) {
+        return without_hashes.trim_end_matches([' ', '\t']);
+    }
+
+    content
+}
+
+/// Scan an opening code fence, returns the fence char and length.
+fn scan_open_fence(s: &str) -> Option<(u8, usize)> {
+    let char = *s.as_bytes().first()?;
+
+    if !matches!(char, b'`' | b'~') {
+        return None;
+    }
+
+    let length = s.bytes().take_while(|c| *c == char).count();
+
+    if length < 3 || (char == b'`' && s[length..].contains('`')) {
+        return None;
+    }
+
+    Some((char, length))
+}
+
+fn is_closing_fence(s: &str, fence: Fence) -> bool {
+    let length = s.bytes().take_while(|c| *c == fence.char).count();
+
+    length >= 3.max(fence.length) && is_blank(&s[length..])
+}
+
+fn scan_setext_underline(s: &str) -> Option<u8> {
+    let depth = match s.as_bytes().first()? {
+        b'=' => 1,
+        b'-' => 2,
+        _ => return None,
+    };
+
+    let char = s.as_bytes()[0];
+
+    let length = s.bytes().take_while(|c| *c == char).count();
+
+    is_blank(&s[length..]).then_some(depth)
+}
+
+fn is_thematic_break(s: &str) -> bool {
+    let Some(&char) = s.as_bytes().first() else {
+        return false;
+    };
+
+    if !matches!(char, b'*' | b'_' | b'-') {
+        return false;
+    }
+
+    let mut count = 0;
+
+    for c in s.bytes() {
+        match c {
+            c if c == char => count += 1,
+            b' ' | b'\t' => {}
+            _ => return false,
+        }
+    }
+
+    count >= 3
+}
+
+/// Split a table row into the ranges of its cells, trimmed. Leading and trailing pipes are
+/// optional and escaped pipes don't split, backslashes escaping any character.
+fn split_row(row: &str) -> Vec<Range<usize>> {
+    let bytes = row.as_bytes();
+
+    let mut pos = row.len() - row.trim_start_matches([' ', '\t']).len();
+
+    if bytes.get(pos) == Some(&b'|') {
+        pos += 1;
+    }
+
+    let mut cells = vec![];
+
+    let mut start = pos;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'|' => {
+                cells.push(start..pos);
+                pos += 1;
+                start = pos;
+            }
+            _ => pos += 1,
+        }
+    }
+
+    if cells.is_empty() || !is_blank(&row[start..]) {
+        cells.push(start..row.len());
+    }
+
+    cells
+        .into_iter()
+        .map(|cell| {
+            let trimmed = trimmed_range(&row[cell.clone()]);
+
+            cell.start + trimmed.start..cell.start + trimmed.end
+        })
+        .collect()
+}
+
+/// Scan a table delimiter row, returns the alignment of its columns.
+fn scan_delimiter_row(s: &str) -> Option<Vec<AlignType>> {
+    if !s.contains('|') {
+        return None;
+    }
+
+    split_row(s)
+        .into_iter()
+        .map(|cell| {
+            let cell = &s[cell];
+
+            let left = cell.starts_with(':');
+            let right = cell.len() > 1 && cell.ends_with(':');
+
+            let dashes = &cell[usize::from(left)..cell.len() - usize::from(right)];
+
+            if dashes.is_empty() || dashes.bytes().any(|c| c != b'-') {
+                return None;
+            }
+
+            Some(match (left, right) {
+                (true, true) => AlignType::Center,
+                (true, false) => AlignType::Left,
+                (false, true) => AlignType::Right,
+                (false, false) => AlignType::None,
+            })
+        })
+        .collect()
+}
+
+/// Lowercase ASCII tag name at the start of `s`, with its length.
+fn tag_name(s: &str) -> (String, usize) {
+    let len = s
+        .bytes()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == b'-')
+        .count();
+
+    (s[..len].to_ascii_lowercase(), len)
+}
+
+const RAW_TAGS: &[&str] = &["pre", "script", "style", "textarea"];
+
+const BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
+
+/// Returns true if `s` satisfies the start condition of HTML block `html_type`.
+fn html_block_starts(html_type: u8, s: &str) -> bool {
+    match html_type {
+        1 => {
+            let (name, len) = tag_name(&s[1..]);
+
+            RAW_TAGS.contains(&name.as_str())
+                && matches!(s.as_bytes().get(1 + len), None | Some(b' ' | b'\t' | b'>'))
+        }
+        2 => s.starts_with("<!--"),
+        3 => s.starts_with("<?"),
+        4 => s.starts_with("<!") && s.as_bytes().get(2).is_some_and(u8::is_ascii_alphabetic),
+        5 => s.starts_with("<![CDATA["),
+        6 => {
+            let name_start = if s[1..].starts_with('/') { 2 } else { 1 };
+
+            let (name, len) = tag_name(&s[name_start..]);
+
+            let rest = &s[name_start + len..];
+
+            BLOCK_TAGS.contains(&name.as_str())
+                && (rest.is_empty() || rest.starts_with([' ', '\t', '>']) || rest.starts_with("/>"))
+        }
+        _ => inline::scan_open_tag(s)
+            .or_else(|| inline::scan_closing_tag(s))
+            .is_some_and(|len| is_blank(&s[len..])),
+    }
+}
+
+/// Returns true if a line starting with `s` would start an HTML block of any type.
+pub(crate) fn starts_html_block(s: &str) -> bool {
+    s.starts_with('<') && (1..=7).any(|html_type| html_block_starts(html_type, s))
+}
+
+/// Returns true if `s` satisfies the end condition of HTML block `html_type`.
+fn html_block_ends(html_type: u8, s: &str) -> bool {
+    match html_type {
+        1 => {
+            let s = s.to_ascii_lowercase();
+
+            RAW_TAGS
+                .iter()
+                .any(|tag| s.contains(&format!("</{}>", tag)))
+        }
+        2 => s.contains("-->"),
+        3 => s.contains("?>"),
+        4 => s.contains('>'),
+        5 => s.contains("]]>"),
+        _ => false,
+    }
+}
+
+/// Parse link reference definitions at the start of `text`, returns them with the consumed length.
+/// Parse the link reference definitions at the start of `text`, returns them with their
+/// ranges in `text`, and the length of text they take.
+fn parse_definitions(text: &str) -> (Vec<(Node<'_>, Range<usize>)>, usize) {
+    let mut definitions = vec![];
+
+    let mut consumed = 0;
+
+    while let Some((definition, len)) = parse_definition(&text[consumed..]) {
+        let end = consumed + text[consumed..consumed + len].trim_end().len();
+
+        definitions.push((Node::Definition(Box::new(definition)), consumed..end));
+        consumed += len;
+    }
+
+    (definitions, consumed)
+}
+
+fn parse_definition(s: &str) -> Option<(Definition<'_>, usize)> {
+    let label_len = inline::scan_link_label(s);
+
+    if label_len == 0 || s.as_bytes().get(label_len) != Some(&b':') {
+        return None;
+    }
+
+    let label = &s[1..label_len - 1];
+
+    let pos = inline::skip_spaces_and_newline(s, label_len + 1);
+
+    let (url, before_title) = inline::scan_link_destination(s, pos)?;
+
+    let mut title = None;
+
+    let pos = inline::skip_spaces_and_newline(s, before_title);
+
+    if pos != before_title {
+        title = inline::scan_link_title(s, pos);
+    }
+
+    // the definition must end the line, if the title doesn't it is dropped.
+    let end = match title.and_then(|(_, end)| space_at_end_of_line(s, end)) {
+        Some(end) => end,
+        None => {
+            title = None;
+
+            space_at_end_of_line(s, before_title)?
+        }
+    };
+
+    let identifier = normalize_identifier(label);
+
+    if identifier.is_empty() {
+        return None;
+    }
+
+    Some((
+        Definition {
+            identifier: identifier.into(),
+            label: Some(label.into()),
+            url: inline::unescape(url),
+            title: title.map(|(title, _)| inline::unescape(title)),
+            data: None,
+        },
+        end,
+    ))
+}
+
+/// Skip spaces or tabs up to the line end, returns the offset after the line ending.
+fn space_at_end_of_line(s: &str, pos: usize) -> Option<usize> {
+    let bytes = s.as_bytes();
+
+    let mut pos = pos;
+
+    while matches!(bytes.get(pos), Some(b' ' | b'\t')) {
+        pos += 1;
+    }
+
+    match bytes.get(pos) {
+        None => Some(pos),
+        Some(b'\n') => Some(pos + 1),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_compaction() {
+        let source = "> quote\n> - item\n\ntext\n".repeat(10_000);
+
+        let mut stream = BlockStream::new(&source, &ParserOptions::default());
+
+        let mut nodes = vec![];
+        let mut peak = 0;
+
+        while let Some(node) = stream.next() {
+            nodes.push(node);
+            peak = peak.max(stream.parser.blocks.len());
+        }
+
+        assert_eq!(nodes.len(), 20_000);
+        assert_eq!(
+            nodes,
+            parse_spans(&source, &ParserOptions::default())
+                .unwrap()
+                .0
+                .children
+        );
+        // the document, with at most a blockquote and its descendants still open.
+        assert!(peak <= 6, "{peak} blocks alive");
+    }
+}
@@ -0,0 +1,960 @@
+//! Parser extensions, for syntax CommonMark doesn't have.
+//!
+//! An [`InlineRule`] registered in [`ParserOptions::inline_rules`](super::ParserOptions) is
+//! tried at each of its trigger chars of phrasing content, before the built-in rules. It
+//! returns the node of the text it recognized, either an existing kind or a [`Custom`] node,
+//! or `None` to let the next rules try.
+//!
+//! A [`BlockRule`] registered in [`ParserOptions::block_rules`] is probed at each line where a
+//! block may start, before the built-in block starts. It takes the lines of the block it
+//! recognized, such as an admonition or a collapsible section, and returns its node, or `None`
+//! to let the next rules and the CommonMark block starts try.
+
+use alloc::{borrow::Cow, collections::BTreeSet, string::String, sync::Arc, vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{ast::*, lexer::Lexer};
+
+use super::{block, inline, tree::Owned, ParserError, ParserOptions};
+
+mod autolink;
+
+pub use autolink::LiteralAutolinks;
+
+/// Recognizes an inline syntax, such as `==highlight==`, `[[wiki links]]` or `@mentions`.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use markdown_rs::{
+///     ast::{Custom, Node},
+///     parser::{
+///         extension::{InlineContext, InlineRule},
+///         Parser, ParserOptions,
+///     },
+/// };
+///
+/// /// `==text==` as a "highlight" node.
+/// struct Highlight;
+///
+/// impl InlineRule for Highlight {
+///     fn triggers(&self) -> &[char] {
+///         &['=']
+///     }
+///
+///     fn try_parse<'s>(&self, cx: &mut InlineContext<'s, '_>) -> Option<Node<'s>> {
+///         if !cx.rest().starts_with("==") {
+///             return None;
+///         }
+///
+///         let start = cx.position() + 2;
+///         let end = start + cx.subject()[start..].find("==")?;
+///
+///         if end == start {
+///             return None;
+///         }
+///
+///         let children = cx.parse_phrasing(start..end);
+///
+///         cx.advance(end + 2 - cx.position());
+///
+///         Some(Custom::new("highlight", children).into())
+///     }
+/// }
+///
+/// let options = ParserOptions {
+///     inline_rules: vec![Arc::new(Highlight)],
+///     ..Default::default()
+/// };
+///
+/// let document = Parser::with_options("a ==*b*== c", options).parse().unwrap();
+///
+/// let Node::Paragraph(paragraph) = &document.children[0] else {
+///     panic!("a paragraph");
+/// };
+///
+/// let Node::Custom(highlight) = &paragraph.children[1] else {
+///     panic!("a highlight");
+/// };
+///
+/// assert_eq!(highlight.name, "highlight");
+/// assert!(matches!(highlight.children[..], [Node::Emphasis(_)]));
+/// ```
+pub trait InlineRule: Send + Sync {
+    /// Chars that text recognized by this rule starts with, the rule is only tried at them.
+    fn triggers(&self) -> &[char];
+
+    /// Returns the node of the text at the position of `cx`, having moved `cx` past it, or
+    /// `None` for the next rules to try, wherever `cx` was moved.
+    ///
+    /// A node is only taken if `cx` moved forward.
+    fn try_parse<'s>(&self, cx: &mut InlineContext<'s, '_>) -> Option<Node<'s>>;
+}
+
+/// A position in phrasing content, as offered to an [`InlineRule`].
+pub struct InlineContext<'s, 'p> {
+    /// Phrasing content being parsed.
+    subject: &'s str,
+    pos: usize,
+    /// Normalized identifiers of the link reference definitions of the document.
+    definitions: &'p BTreeSet<String>,
+    rules: &'p [Arc<dyn InlineRule>],
+    /// Nesting depth left for [`InlineContext::parse_phrasing`].
+    max_depth: usize,
+}
+
+/// Saved position of an [`InlineContext`], see [`InlineContext::checkpoint`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Checkpoint(usize);
+
+impl<'s, 'p> InlineContext<'s, 'p> {
+    pub(super) fn new(
+        subject: &'s str,
+        pos: usize,
+        definitions: &'p BTreeSet<String>,
+        rules: &'p [Arc<dyn InlineRule>],
+        max_depth: usize,
+    ) -> Self {
+        InlineContext {
+            subject,
+            pos,
+            definitions,
+            rules,
+            max_depth,
+        }
+    }
+
+    /// Returns the phrasing content being parsed, such as the text of a paragraph without
+    /// its indentation.
+    pub fn subject(&self) -> &'s str {
+        self.subject
+    }
+
+    /// Returns the offset of the current position in the [`subject`](Self::subject).
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns the subject from the current position on.
+    pub fn rest(&self) -> &'s str {
+        &self.subject[self.pos..]
+    }
+
+    /// Returns the char at the current position, `None` at the end of the subject.
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Returns true if the subject continues with `text` at the current position.
+    pub fn lookahead(&self, text: &str) -> bool {
+        self.rest().starts_with(text)
+    }
+
+    /// Move the current position forward by `len` bytes, to the end of the subject at most,
+    /// and on to the next char boundary.
+    pub fn advance(&mut self, len: usize) {
+        self.pos = self.subject.len().min(self.pos + len);
+
+        while !self.subject.is_char_boundary(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    /// Returns the current position, to [`restore`](Self::restore) it later.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.pos)
+    }
+
+    /// Move back, or forward, to a position saved by [`checkpoint`](Self::checkpoint).
+    pub fn restore(&mut self, checkpoint: Checkpoint) {
+        self.pos = checkpoint.0;
+    }
+
+    /// Parse `range` of the subject into phrasing content, with the built-in rules and those
+    /// of the parser, without moving the current position.
+    ///
+    /// Nested calls are bounded by the nesting depth of the parser, past which the text is
+    /// kept literal.
+    pub fn parse_phrasing(&self, range: Range<usize>) -> Vec<Node<'s>> {
+        let Some(subject) = self.subject.get(range) else {
+            return Vec::new();
+        };
+
+        let rules = match self.max_depth {
+            0 => &[],
+            _ => self.rules,
+        };
+
+        inline::parse(
+            subject,
+            self.definitions,
+            rules,
+            self.max_depth.saturating_sub(1),
+            None,
+            &Owned,
+        )
+    }
+}
+
+/// Recognizes a block syntax, such as `!!! note` admonitions.
+///
+/// Rules are only probed on lines that aren't blank nor indented as code, where nesting depth
+/// allows one more container. A block taken by a rule ends the paragraph before it, like a
+/// fenced code block does.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use markdown_rs::{
+///     ast::{Custom, Node},
+///     parser::{
+///         extension::{BlockContext, BlockRule, LineInfo},
+///         Parser, ParserError, ParserOptions,
+///     },
+/// };
+///
+/// /// `!!! kind` followed by content indented by 4 spaces, as an "admonition" node.
+/// struct Admonition;
+///
+/// impl BlockRule for Admonition {
+///     fn start(&self, line: &LineInfo) -> bool {
+///         line.text.trim_start().starts_with("!!! ")
+///     }
+///
+///     fn parse<'s>(
+///         &self,
+///         cx: &mut BlockContext<'s, '_>,
+///     ) -> Result<Option<Node<'s>>, ParserError> {
+///         let kind = cx.line().trim_start()[4..].trim().to_owned();
+///
+///         let mut content = String::new();
+///
+///         while let Some(line) = cx.peek() {
+///             let text = match line.strip_prefix("    ") {
+///                 Some(text) => text,
+///                 None if line.trim().is_empty() => "",
+///                 None => break,
+///             };
+///
+///             content.push_str(text);
+///             content.push('\n');
+///
+///             cx.advance();
+///         }
+///
+///         let mut node = Custom::new("admonition", cx.parse_flow(&content)?);
+///
+///         node.attributes.insert("kind".into(), kind.into());
+///
+///         Ok(Some(node.into()))
+///     }
+/// }
+///
+/// let options = ParserOptions {
+///     block_rules: vec![Arc::new(Admonition)],
+///     ..Default::default()
+/// };
+///
+/// let source = "!!! warning\n    Mind the *gap*.\n\nAfter.\n";
+///
+/// let document = Parser::with_options(source, options).parse().unwrap();
+///
+/// let Node::Custom(admonition) = &document.children[0] else {
+///     panic!("an admonition");
+/// };
+///
+/// assert_eq!(admonition.attributes["kind"], "warning");
+/// assert!(matches!(admonition.children[..], [Node::Paragraph(_)]));
+/// assert!(matches!(document.children[1], Node::Paragraph(_)));
+/// ```
+pub trait BlockRule: Send + Sync {
+    /// Returns true if a block of this rule may start on `line`, for [`parse`](Self::parse)
+    /// to tell.
+    fn start(&self, line: &LineInfo) -> bool;
+
+    /// Returns the node of the block starting on the current line of `cx`, having advanced
+    /// `cx` to its last line, or `None` for the next rules to try, wherever `cx` was advanced.
+    ///
+    /// Errors end parsing, and are returned by the parser.
+    fn parse<'s>(&self, cx: &mut BlockContext<'s, '_>) -> Result<Option<Node<'s>>, ParserError>;
+}
+
+/// A line where a block may start, as probed by [`BlockRule::start`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineInfo<'a> {
+    /// Text of the line after the prefixes of its enclosing containers, such as the `> ` of a
+    /// block quote, its leading spaces included.
+    pub text: &'a str,
+    /// Columns of leading spaces of `text`, tabs expanded.
+    pub indent: usize,
+    /// Line number, starting at 1.
+    pub number: usize,
+    /// True if a paragraph is open, which the block would end.
+    pub in_paragraph: bool,
+}
+
+/// The lines of a block, as offered to a [`BlockRule`].
+///
+/// Lines are seen without the prefixes of the containers the block is in, and only as long as
+/// those containers go on: in a block quote, the block can't take a line without `>`.
+pub struct BlockContext<'s, 'p> {
+    /// Text of the first line before the block, the prefixes of its containers.
+    prefix: Cow<'s, str>,
+    /// Lines seen so far, the first one and the current one included.
+    lines: Vec<Cow<'s, str>>,
+    current: usize,
+    /// Returns the line after the last one seen, if the containers go on.
+    next_line: &'p mut dyn FnMut() -> Option<Cow<'s, str>>,
+    /// Options of [`BlockContext::parse_flow`], nesting depth decreased.
+    options: ParserOptions,
+}
+
+impl<'s, 'p> BlockContext<'s, 'p> {
+    pub(super) fn new(
+        prefix: Cow<'s, str>,
+        line: Cow<'s, str>,
+        next_line: &'p mut dyn FnMut() -> Option<Cow<'s, str>>,
+        options: ParserOptions,
+    ) -> Self {
+        BlockContext {
+            prefix,
+            lines: vec![line],
+            current: 0,
+            next_line,
+            options,
+        }
+    }
+
+    /// Returns the text of the first line before the block, such as `> - ` for a block in a
+    /// list item in a block quote.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the current line, without the prefixes of the containers.
+    pub fn line(&self) -> &str {
+        &self.lines[self.current]
+    }
+
+    /// Returns a lexer of the current line.
+    pub fn lexer(&self) -> Lexer<'_> {
+        Lexer::new(self.line())
+    }
+
+    /// Returns the line after the current one, without moving to it. `None` at the end of the
+    /// source or of the containers of the block.
+    pub fn peek(&mut self) -> Option<&str> {
+        if self.current + 1 == self.lines.len() {
+            let line = (self.next_line)()?;
+
+            self.lines.push(line);
+        }
+
+        Some(&self.lines[self.current + 1])
+    }
+
+    /// Move to the next line, making it part of the block. Returns false if there is none,
+    /// see [`peek`](Self::peek).
+    pub fn advance(&mut self) -> bool {
+        let advanced = self.peek().is_some();
+
+        self.current += usize::from(advanced);
+
+        advanced
+    }
+
+    /// Returns the number of lines of the block after the first one, those advanced over.
+    pub fn lines(&self) -> usize {
+        self.current
+    }
+
+    /// Parse `text` into flow content, with the built-in rules and those of the parser.
+    ///
+    /// References in `text` are only resolved to the definitions in `text`. Nested calls are
+    /// bounded by the nesting depth of the parser, past which block rules are no longer
+    /// probed.
+    pub fn parse_flow(&self, text: &str) -> Result<Vec<Node<'s>>, ParserError> {
+        block::parse_flow(text, &self.options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{borrow::Cow, format, string::ToString, vec};
+    use core::ops::ControlFlow;
+
+    use crate::{html::HtmlOptions, parser::Parser};
+
+    use super::{super::ParserOptions, *};
+
+    /// `==text==` as a "highlight" node.
+    struct Highlight;
+
+    impl InlineRule for Highlight {
+        fn triggers(&self) -> &[char] {
+            &['=']
+        }
+
+        fn try_parse<'s>(&self, cx: &mut InlineContext<'s, '_>) -> Option<Node<'s>> {
+            if !cx.lookahead("==") {
+                return None;
+            }
+
+            cx.advance(2);
+
+            let start = cx.position();
+
+            // moves past the opening marker even when failing.
+            let end = start + cx.rest().find("==")?;
+
+            if end == start {
+                return None;
+            }
+
+            let children = cx.parse_phrasing(start..end);
+
+            cx.advance(end + 2 - start);
+
+            Some(Custom::new("highlight", children).into())
+        }
+    }
+
+    /// `[[Page]]` as a "wikilink" node, `@name` as a link to a profile.
+    struct Wiki;
+
+    impl InlineRule for Wiki {
+        fn triggers(&self) -> &[char] {
+            &['[', '@']
+        }
+
+        fn try_parse<'s>(&self, cx: &mut InlineContext<'s, '_>) -> Option<Node<'s>> {
+            let checkpoint = cx.checkpoint();
+
+            if cx.peek() == Some('@') {
+                cx.advance(1);
+
+                let name = cx.rest();
+                let len = name
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(name.len());
+
+                if len == 0 {
+                    cx.restore(checkpoint);
+
+                    return None;
+                }
+
+                cx.advance(len);
+
+                return Some(Node::from(Link {
+                    children: vec![Node::Text(Text {
+                        value: Cow::Borrowed(&name[..len]),
+                        data: None,
+                    })],
+                    url: format!("/users/{}", &name[..len]).into(),
+                    title: None,
+                    data: None,
+                }));
+            }
+
+            if !cx.lookahead("[[") {
+                return None;
+            }
+
+            let target = &cx.rest()[2..];
+            let len = target.find("]]")?;
+
+            cx.advance(len + 4);
+
+            let mut node = Custom::new("wikilink", vec![]);
+
+            node.attributes
+                .insert("target".into(), Cow::Borrowed(&target[..len]));
+
+            Some(node.into())
+        }
+    }
+
+    fn options(rules: Vec<Arc<dyn InlineRule>>) -> ParserOptions {
+        ParserOptions {
+            inline_rules: rules,
+            ..Default::default()
+        }
+    }
+
+    fn phrasing<'s>(source: &'s str, options: &ParserOptions) -> Vec<Node<'s>> {
+        let mut document = Parser::with_options(source, options.clone())
+            .parse()
+            .unwrap();
+
+        match document.children.first_mut() {
+            Some(Node::Paragraph(paragraph)) => core::mem::take(&mut paragraph.children),
+            node => panic!("not a paragraph: {:?}", node),
+        }
+    }
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    #[test]
+    fn test_highlight() {
+        let options = options(vec![Arc::new(Highlight)]);
+
+        assert_eq!(
+            phrasing("a ==b *c*== d", &options),
+            [
+                text("a "),
+                Custom::new(
+                    "highlight",
+                    vec![
+                        text("b "),
+                        Node::Emphasis(Emphasis {
+                            children: vec![text("c")],
+                            data: None
+                        })
+                    ]
+                )
+                .into(),
+                text(" d"),
+            ]
+        );
+
+        // unclosed, empty or lone markers are left to the built-in rules.
+        assert_eq!(
+            phrasing("==*a*", &options),
+            [
+                text("=="),
+                Node::Emphasis(Emphasis {
+                    children: vec![text("a")],
+                    data: None
+                })
+            ]
+        );
+        assert_eq!(phrasing("a ==== b = c", &options), [text("a ==== b = c")]);
+
+        // without the rule.
+        assert_eq!(
+            phrasing("a ==b== c", &ParserOptions::default()),
+            [text("a ==b== c")]
+        );
+
+        let document = Parser::with_options("==*a* `b`==", options)
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            document.to_html(&HtmlOptions::default()),
+            "<p><em>a</em> <code>b</code></p>\n"
+        );
+        assert_eq!(document.to_markdown(&Default::default()), "*a* `b`\n");
+    }
+
+    #[test]
+    fn test_fallback() {
+        let options = options(vec![Arc::new(Highlight), Arc::new(Wiki)]);
+
+        let mut wikilink = Custom::new("wikilink", vec![]);
+
+        wikilink.attributes.insert("target".into(), "Page".into());
+
+        assert_eq!(
+            phrasing("see [[Page]], [link](/url) or @bob.", &options),
+            [
+                text("see "),
+                wikilink.into(),
+                text(", "),
+                Node::from(Link {
+                    children: vec![text("link")],
+                    url: "/url".into(),
+                    title: None,
+                    data: None,
+                }),
+                text(" or "),
+                Node::from(Link {
+                    children: vec![text("bob")],
+                    url: "/users/bob".into(),
+                    title: None,
+                    data: None,
+                }),
+                text("."),
+            ]
+        );
+
+        // a failed rule leaves the built-in rules a clean position.
+        assert_eq!(phrasing("[a] @ [[b", &options), [text("[a] @ [[b")]);
+    }
+
+    #[test]
+    fn test_record() {
+        let options = options(vec![Arc::new(Highlight)]);
+
+        let source = "# ==*a* b==\n\n_c_ ==d==";
+
+        let (document, spans) = Parser::with_options(source, options.clone())
+            .parse_with_spans()
+            .unwrap();
+
+        assert_eq!(spans.len(), document.descendants().count());
+        assert_eq!(spans.get(&[0, 0]), Some(2..11));
+        assert_eq!(spans.get(&[0, 0, 0]), Some(2..11));
+        assert_eq!(spans.get(&[1, 0]), Some(13..16));
+
+        let (document, style) = Parser::with_options(source, options)
+            .parse_with_style()
+            .unwrap();
+
+        assert_eq!(
+            document.to_markdown_with_style(&Default::default(), &style),
+            "# *a* b\n\n_c_ d\n"
+        );
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        /// Nests the rest of the subject into itself.
+        struct Nest;
+
+        impl InlineRule for Nest {
+            fn triggers(&self) -> &[char] {
+                &['+']
+            }
+
+            fn try_parse<'s>(&self, cx: &mut InlineContext<'s, '_>) -> Option<Node<'s>> {
+                let children = cx.parse_phrasing(cx.position()..cx.subject().len());
+
+                cx.advance(cx.rest().len());
+
+                Some(Custom::new("nest", children).into())
+            }
+        }
+
+        let options = ParserOptions {
+            max_nesting_depth: 3,
+            inline_rules: vec![Arc::new(Nest)],
+            ..Default::default()
+        };
+
+        let phrasing = phrasing("a+", &options);
+
+        let nodes = phrasing[1].descendants().collect::<Vec<_>>();
+
+        assert_eq!(nodes.len(), 4);
+        assert_eq!(nodes[3], &text("+"));
+    }
+
+    /// `!!! kind` followed by content indented by 4 spaces, as an "admonition" node.
+    struct Admonition;
+
+    impl BlockRule for Admonition {
+        fn start(&self, line: &LineInfo) -> bool {
+            line.text.trim_start().starts_with("!!!")
+        }
+
+        fn parse<'s>(
+            &self,
+            cx: &mut BlockContext<'s, '_>,
+        ) -> Result<Option<Node<'s>>, ParserError> {
+            let kind = cx.line().trim_start()[3..].trim().to_string();
+
+            if kind.is_empty() {
+                return Err(ParserError::Extension("admonition without kind".into()));
+            }
+
+            let mut content = String::new();
+
+            while let Some(line) = cx.peek() {
+                let text = match line.strip_prefix("    ") {
+                    Some(text) => text,
+                    None if line.trim().is_empty() => "",
+                    None => break,
+                };
+
+                content.push_str(text);
+                content.push('\n');
+
+                cx.advance();
+            }
+
+            let mut node = Custom::new("admonition", cx.parse_flow(&content)?);
+
+            node.attributes.insert("kind".into(), kind.into());
+
+            Ok(Some(node.into()))
+        }
+    }
+
+    /// `::: details summary` up to a `:::` line, as a "details" node whose first child is a
+    /// "summary" node.
+    struct Details;
+
+    impl BlockRule for Details {
+        fn start(&self, line: &LineInfo) -> bool {
+            line.text.trim_start().starts_with("::: details ")
+        }
+
+        fn parse<'s>(
+            &self,
+            cx: &mut BlockContext<'s, '_>,
+        ) -> Result<Option<Node<'s>>, ParserError> {
+            let summary = cx.line().trim_start()[12..].to_string();
+
+            let mut content = String::new();
+
+            loop {
+                // unclosed, left to the built-in rules.
+                if !cx.advance() {
+                    return Ok(None);
+                }
+
+                if cx.line().trim() == ":::" {
+                    break;
+                }
+
+                content.push_str(cx.line());
+                content.push('\n');
+            }
+
+            let summary = match cx.parse_flow(&summary)?.as_mut_slice() {
+                [Node::Paragraph(paragraph)] => core::mem::take(&mut paragraph.children),
+                _ => vec![],
+            };
+
+            let mut children = vec![Custom::new("summary", summary).into()];
+
+            children.extend(cx.parse_flow(&content)?);
+
+            Ok(Some(Custom::new("details", children).into()))
+        }
+    }
+
+    fn block_options() -> ParserOptions {
+        ParserOptions {
+            block_rules: vec![Arc::new(Admonition), Arc::new(Details)],
+            ..Default::default()
+        }
+    }
+
+    fn custom<'a, 's>(node: &'a Node<'s>) -> &'a Custom<'s> {
+        match node {
+            Node::Custom(custom) => custom,
+            node => panic!("not a custom node: {:?}", node),
+        }
+    }
+
+    fn kinds(nodes: &[Node]) -> Vec<NodeKind> {
+        nodes.iter().map(Node::kind).collect()
+    }
+
+    #[test]
+    fn test_block_rules() {
+        let source = "# Title
+
+!!! warning
+    Mind the *gap*.
+
+    - a
+    - b
+
+Text
+!!! note
+    Interrupts.
+After
+
+::: details More *info*
+> quote
+
+text
+:::
+";
+
+        let document = Parser::with_options(source, block_options())
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            kinds(&document.children),
+            [
+                NodeKind::Heading,
+                NodeKind::Custom,
+                NodeKind::Paragraph,
+                NodeKind::Custom,
+                NodeKind::Paragraph,
+                NodeKind::Custom,
+            ]
+        );
+
+        let warning = custom(&document.children[1]);
+
+        assert_eq!(warning.name, "admonition");
+        assert_eq!(warning.attributes["kind"], "warning");
+        assert_eq!(
+            kinds(&warning.children),
+            [NodeKind::Paragraph, NodeKind::List]
+        );
+
+        let note = custom(&document.children[3]);
+
+        assert_eq!(note.attributes["kind"], "note");
+        assert_eq!(kinds(&note.children), [NodeKind::Paragraph]);
+
+        let details = custom(&document.children[5]);
+
+        assert_eq!(details.name, "details");
+        assert_eq!(
+            kinds(&details.children),
+            [NodeKind::Custom, NodeKind::Blockquote, NodeKind::Paragraph]
+        );
+        assert_eq!(
+            kinds(details.children[0].children().unwrap()),
+            [NodeKind::Text, NodeKind::Emphasis]
+        );
+
+        assert_eq!(
+            document.to_html(&HtmlOptions::default()),
+            "<h1>Title</h1>\n<p>Mind the <em>gap</em>.</p>\n<ul>\n<li>a</li>\n<li>b</li>\n</ul>\n\
+             <p>Text</p>\n<p>Interrupts.</p>\n<p>After</p>\n\
+             More <em>info</em>\n<blockquote>\n<p>quote</p>\n</blockquote>\n<p>text</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_block_rules_precedence() {
+        // sources the rules don't take parse as without them.
+        for source in [
+            "# a\n\n- b\n\n> c\n\n```\n!!! d\n```\n",
+            "    !!! indented code\n",
+            "::: details unclosed\n\n*text*\n",
+            "<div>\n!!! html\n</div>\n",
+            "text\n    !!! lazy\n",
+        ] {
+            assert_eq!(
+                Parser::with_options(source, block_options())
+                    .parse()
+                    .unwrap(),
+                Parser::new(source).parse().unwrap(),
+                "{:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_block_rules_in_containers() {
+        /// Records the prefix of the block, takes a single line.
+        struct Prefix;
+
+        impl BlockRule for Prefix {
+            fn start(&self, line: &LineInfo) -> bool {
+                line.text.starts_with('%')
+            }
+
+            fn parse<'s>(
+                &self,
+                cx: &mut BlockContext<'s, '_>,
+            ) -> Result<Option<Node<'s>>, ParserError> {
+                let mut node = Custom::new("prefix", vec![]);
+
+                node.attributes
+                    .insert("prefix".into(), cx.prefix().to_string().into());
+
+                Ok(Some(node.into()))
+            }
+        }
+
+        let options = ParserOptions {
+            block_rules: vec![Arc::new(Admonition), Arc::new(Prefix)],
+            ..Default::default()
+        };
+
+        let document =
+            Parser::with_options("> !!! note\n>     quoted\n> after\nlazy\n", options.clone())
+                .parse()
+                .unwrap();
+
+        let quote = document.children[0].children().unwrap();
+
+        assert_eq!(kinds(quote), [NodeKind::Custom, NodeKind::Paragraph]);
+        assert_eq!(kinds(quote[0].children().unwrap()), [NodeKind::Paragraph]);
+        assert_eq!(quote[1].children().unwrap(), [text("after\nlazy")]);
+
+        // lines of the block stop with its containers.
+        let document = Parser::with_options("> !!! note\n    code\n", options.clone())
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            kinds(&document.children),
+            [NodeKind::Blockquote, NodeKind::Code]
+        );
+
+        let document = Parser::with_options("- !!! note\n      item\n- > % a\n", options)
+            .parse()
+            .unwrap();
+
+        let items = document.children[0].children().unwrap();
+
+        assert_eq!(kinds(items[0].children().unwrap()), [NodeKind::Custom]);
+        assert_eq!(
+            custom(&items[0].children().unwrap()[0]).children,
+            [Node::Paragraph(Paragraph {
+                children: vec![text("item")],
+                data: None
+            })]
+        );
+        assert_eq!(
+            custom(&items[1].children().unwrap()[0].children().unwrap()[0]).attributes["prefix"],
+            "- > "
+        );
+    }
+
+    #[test]
+    fn test_block_rule_error() {
+        let source = "a\n\n!!!\n\nb\n";
+
+        assert!(matches!(
+            Parser::with_options(source, block_options()).parse(),
+            Err(ParserError::Extension(_))
+        ));
+        assert!(Parser::with_options(source, block_options())
+            .parse_with_spans()
+            .is_err());
+
+        let mut blocks = 0;
+
+        let result = Parser::with_options(source, block_options()).parse_blocks(|_| {
+            blocks += 1;
+
+            ControlFlow::Continue(())
+        });
+
+        assert!(result.is_err());
+        assert!(blocks <= 1);
+    }
+
+    #[test]
+    fn test_block_rules_record() {
+        let source = "- x\n\n!!! note\n    - *a*\n\n    b\n\n* y\n";
+
+        let (document, spans) = Parser::with_options(source, block_options())
+            .parse_with_spans()
+            .unwrap();
+
+        assert_eq!(spans.len(), document.descendants().count());
+        assert_eq!(spans.get(&[1]), Some(5..30));
+        assert_eq!(spans.get(&[1, 0, 0, 0]), Some(5..30));
+        assert_eq!(spans.get(&[2]), Some(32..35));
+
+        let (document, style) = Parser::with_options(source, block_options())
+            .parse_with_style()
+            .unwrap();
+
+        assert_eq!(
+            document.to_markdown_with_style(&Default::default(), &style),
+            "- x\n\n* *a*\n\nb\n\n* y\n"
+        );
+    }
+}
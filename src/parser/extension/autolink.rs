@@ -0,0 +1,344 @@
+//! GFM literal autolinks: `www.` links, `http://` and `https://` urls and email addresses
+//! written without angle brackets.
+
+use alloc::{borrow::Cow, format, vec};
+
+use crate::ast::*;
+
+use super::{InlineContext, InlineRule};
+
+/// Chars a literal autolink starts with.
+const TRIGGERS: &[char] = &[
+    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
+    't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L',
+    'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4',
+    '5', '6', '7', '8', '9',
+];
+
+/// Recognizes the [literal autolinks](https://github.github.com/gfm/#autolinks-extension-)
+/// of GitHub Flavored Markdown as [`Link`] nodes.
+///
+/// - `www.example.com` links to `http://www.example.com`,
+/// - `http://` and `https://` urls link to themselves,
+/// - `user@example.com` links to `mailto:user@example.com`,
+/// - `mailto:` and `xmpp:` addresses link to themselves.
+///
+/// Other schemes, such as `tel:` or `file:`, are only recognized in angle brackets. Urls keep
+/// the casing of their scheme, and trailing punctuation is left out of the link.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use markdown_rs::{
+///     html::HtmlOptions,
+///     parser::{extension::LiteralAutolinks, Parser, ParserOptions},
+/// };
+///
+/// let options = ParserOptions {
+///     inline_rules: vec![Arc::new(LiteralAutolinks)],
+///     ..Default::default()
+/// };
+///
+/// let document = Parser::with_options("See www.example.com.", options)
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(
+///     document.to_html(&HtmlOptions::default()),
+///     "<p>See <a href=\"http://www.example.com\">www.example.com</a>.</p>\n"
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LiteralAutolinks;
+
+impl InlineRule for LiteralAutolinks {
+    fn triggers(&self) -> &[char] {
+        TRIGGERS
+    }
+
+    fn try_parse<'s>(&self, cx: &mut InlineContext<'s, '_>) -> Option<Node<'s>> {
+        let prev = cx.subject()[..cx.position()].chars().next_back();
+
+        let rest = cx.rest();
+
+        // urls start a word or follow an emphasis marker or a parenthesis.
+        let url_start =
+            prev.is_none_or(|c| c.is_whitespace() || matches!(c, '*' | '_' | '~' | '('));
+
+        let (len, url) = if url_start && starts_with_ignore_case(rest, "www.") {
+            let len = link_end(rest, scan_domain(rest, false)?);
+
+            (len, Cow::Owned(format!("http://{}", &rest[..len])))
+        } else if let Some(scheme) = ["http://", "https://"]
+            .into_iter()
+            .find(|scheme| url_start && starts_with_ignore_case(rest, scheme))
+        {
+            let domain = scan_domain(&rest[scheme.len()..], true)?;
+
+            let len = link_end(rest, scheme.len() + domain);
+
+            (len, Cow::Borrowed(&rest[..len]))
+        } else if let Some(scheme) = ["mailto:", "xmpp:"]
+            .into_iter()
+            .find(|scheme| url_start && starts_with_ignore_case(rest, scheme))
+        {
+            let mut len = scheme.len() + scan_email(&rest[scheme.len()..])?;
+
+            // an xmpp resource.
+            if scheme == "xmpp:" && rest[len..].starts_with('/') {
+                let resource = rest[len + 1..]
+                    .bytes()
+                    .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, b'@' | b'.'))
+                    .count();
+
+                len += 1 + resource;
+
+                len -= rest[..len].len() - rest[..len].trim_end_matches('.').len();
+            }
+
+            (len, Cow::Borrowed(&rest[..len]))
+        } else if prev.is_none_or(|c| !is_local_part(c)) {
+            let len = scan_email(rest)?;
+
+            (len, Cow::Owned(format!("mailto:{}", &rest[..len])))
+        } else {
+            return None;
+        };
+
+        let text = &rest[..len];
+
+        cx.advance(len);
+
+        Some(Node::from(Link {
+            children: vec![Node::Text(Text {
+                value: Cow::Borrowed(text),
+                data: None,
+            })],
+            url,
+            title: None,
+            data: None,
+        }))
+    }
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+/// Returns true if `c` can be part of the local part of an email address.
+fn is_local_part(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-' | '_')
+}
+
+/// Scans a valid domain at the start of `s`, returns its length.
+///
+/// A valid domain is made of segments of alphanumeric chars, underscores and hyphens
+/// separated by periods, without underscores in its last two segments. It has at least one
+/// period unless `short` is true.
+fn scan_domain(s: &str, short: bool) -> Option<usize> {
+    let len = s
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '_' | '-')))
+        .unwrap_or(s.len());
+
+    let domain = s[..len].trim_end_matches('.');
+
+    if domain.is_empty() || (!short && !domain.contains('.')) {
+        return None;
+    }
+
+    if domain.split('.').any(str::is_empty)
+        || domain
+            .rsplit('.')
+            .take(2)
+            .any(|segment| segment.contains('_'))
+    {
+        return None;
+    }
+
+    Some(domain.len())
+}
+
+/// Returns the end of the link starting `s`, the domain ending at `domain`: up to the next
+/// whitespace or `<`, trailing punctuation, unbalanced parentheses and entity references left
+/// out.
+fn link_end(s: &str, domain: usize) -> usize {
+    let mut end = domain
+        + s[domain..]
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .unwrap_or(s.len() - domain);
+
+    loop {
+        let link = &s[..end];
+
+        match link.as_bytes()[end - 1] {
+            b'?' | b'!' | b'.' | b',' | b':' | b'*' | b'_' | b'~' => end -= 1,
+            b')' if link.matches(')').count() > link.matches('(').count() => end -= 1,
+            b';' => {
+                let name = link[..end - 1]
+                    .bytes()
+                    .rev()
+                    .take_while(u8::is_ascii_alphanumeric)
+                    .count();
+
+                if name == 0 || !link[..end - 1 - name].ends_with('&') {
+                    return end;
+                }
+
+                end -= name + 2;
+            }
+            _ => return end,
+        }
+
+        if end <= domain {
+            return domain;
+        }
+    }
+}
+
+/// Scans an email address at the start of `s`, returns its length.
+fn scan_email(s: &str) -> Option<usize> {
+    let local = s.find(|c| !is_local_part(c)).unwrap_or(s.len());
+
+    if local == 0 || !s[local..].starts_with('@') {
+        return None;
+    }
+
+    let domain = &s[local + 1..];
+
+    let len = domain
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+        .unwrap_or(domain.len());
+
+    let domain = domain[..len].trim_end_matches('.');
+
+    if !domain.contains('.') || domain.ends_with(['-', '_']) || domain.split('.').any(str::is_empty)
+    {
+        return None;
+    }
+
+    Some(local + 1 + domain.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{
+        string::{String, ToString},
+        sync::Arc,
+        vec::Vec,
+    };
+
+    use crate::parser::{Parser, ParserOptions};
+
+    use super::*;
+
+    /// Returns the links of `source` parsed with literal autolinks, as `(text, url)` pairs.
+    fn links(source: &str) -> Vec<(String, String)> {
+        let options = ParserOptions {
+            inline_rules: vec![Arc::new(LiteralAutolinks)],
+            ..Default::default()
+        };
+
+        let document = Parser::with_options(source, options).parse().unwrap();
+
+        document
+            .descendants()
+            .filter_map(|node| match node {
+                Node::Link(link) => Some((
+                    link.children
+                        .iter()
+                        .map(|child| match child {
+                            Node::Text(text) => text.value.to_string(),
+                            _ => String::new(),
+                        })
+                        .collect(),
+                    link.url.to_string(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn link(text: &str, url: &str) -> (String, String) {
+        (text.into(), url.into())
+    }
+
+    #[test]
+    fn test_www() {
+        assert_eq!(
+            links("Visit www.commonmark.org/help for more."),
+            [link(
+                "www.commonmark.org/help",
+                "http://www.commonmark.org/help"
+            )]
+        );
+        assert_eq!(
+            links("(www.a.com/x_(y)) www.a.com/q?a=1&copy; WWW.A.COM."),
+            [
+                link("www.a.com/x_(y)", "http://www.a.com/x_(y)"),
+                link("www.a.com/q?a=1", "http://www.a.com/q?a=1"),
+                link("WWW.A.COM", "http://WWW.A.COM")
+            ]
+        );
+        // no underscore in the last two segments, and not within a word.
+        assert_eq!(links("www.a_b.com xwww.a.com www."), []);
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(
+            links("*https://example.com/a?b=c*, HTTP://localhost:8080 ftp://x.y"),
+            [
+                link("https://example.com/a?b=c", "https://example.com/a?b=c"),
+                link("HTTP://localhost:8080", "HTTP://localhost:8080")
+            ]
+        );
+        assert_eq!(links("see http://"), []);
+    }
+
+    #[test]
+    fn test_emails() {
+        assert_eq!(
+            links("Mail foo.bar+baz@example.com. Not a@b, c@d.e- or x@y.z_."),
+            [link(
+                "foo.bar+baz@example.com",
+                "mailto:foo.bar+baz@example.com"
+            )]
+        );
+        assert_eq!(
+            links("mailto:a@b.c xmpp:a@b.c/res.1. MAILTO:d@e.f tel:+1-555-0100"),
+            [
+                link("mailto:a@b.c", "mailto:a@b.c"),
+                link("xmpp:a@b.c/res.1", "xmpp:a@b.c/res.1"),
+                link("MAILTO:d@e.f", "MAILTO:d@e.f")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_angle_brackets() {
+        // any scheme of 2 to 32 chars in angle brackets.
+        assert_eq!(
+            links("<mailto:user@example.com> <tel:+1-555-0100> <File:///C:/x.md> <xmpp:a@b.c>"),
+            [
+                link("mailto:user@example.com", "mailto:user@example.com"),
+                link("tel:+1-555-0100", "tel:+1-555-0100"),
+                link("File:///C:/x.md", "File:///C:/x.md"),
+                link("xmpp:a@b.c", "xmpp:a@b.c")
+            ]
+        );
+        assert_eq!(
+            links("<1http://x> <a:b> <abcdefghijklmnopqrstuvwxyz1234567:x>"),
+            []
+        );
+        assert_eq!(
+            links("<user@example.com>"),
+            [link("user@example.com", "mailto:user@example.com")]
+        );
+    }
+
+    #[test]
+    fn test_not_in_code() {
+        assert_eq!(links("`www.a.com` <b title=\"www.c.com\">"), []);
+    }
+}
@@ -0,0 +1,1680 @@
+//! Inline (phrasing content) parsing, following the CommonMark delimiter stack algorithm.
+
+use alloc::{
+    borrow::{Cow, ToOwned},
+    boxed::Box,
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::{cell::RefCell, ops::Range};
+
+use crate::{
+    ast::{
+        style::{is_styled, NodeStyle},
+        *,
+    },
+    lexer::scan_balanced,
+    unicode::{is_unicode_punctuation, is_unicode_whitespace},
+};
+
+use super::{
+    extension::{InlineContext, InlineRule},
+    record::{Record, SourceMap},
+    tree::TreeSink,
+};
+
+/// Parse `subject` into phrasing content.
+///
+/// `definitions` holds the normalized identifiers of all link reference definitions of the document,
+/// reference links are only recognized when their label is defined. `rules` are tried before
+/// the built-in rules at their trigger chars. Emphasis, strong, links and images nested deeper
+/// than `max_depth` are kept as literal text. The parsed nodes are
+/// appended to the side tables of `record` in document order, if given, with the source
+/// offsets of `subject`.
+///
+/// Constructs left unclosed at the end of `subject`, such as `**a`, `` `a ``, `[a` or
+/// `[a](b`, fall back to literal text as CommonMark specifies: their characters are kept in
+/// the merged text around them, and the constructs after them parse as if they weren't
+/// there.
+pub(super) fn parse<'s, S: TreeSink<'s>>(
+    subject: &'s str,
+    definitions: &BTreeSet<String>,
+    rules: &[Arc<dyn InlineRule>],
+    max_depth: usize,
+    record: Option<(&RefCell<Record>, SourceMap)>,
+    sink: &S,
+) -> S::Children {
+    let mut parser = InlineParser {
+        subject,
+        pos: 0,
+        items: vec![],
+        delimiters: vec![],
+        last_delimiter: None,
+        brackets: vec![],
+        last_close_bracket: subject.rfind(']'),
+        definitions,
+        rules,
+        triggers: rules
+            .iter()
+            .flat_map(|rule| rule.triggers())
+            .copied()
+            .collect(),
+        max_depth,
+    };
+
+    while parser.pos < subject.len() {
+        parser.parse_inline();
+    }
+
+    parser.process_emphasis(None);
+
+    match record {
+        Some((record, map)) => {
+            parser.build(sink, max_depth, Some((&mut record.borrow_mut(), &map)))
+        }
+        None => parser.build(sink, max_depth, None),
+    }
+}
+
+/// Flat inline parse result, turned into a tree by [`InlineParser::build`].
+enum Item<'s> {
+    /// Literal text, `start` is set if `value` borrows the subject at that offset, `span` is
+    /// the subject range it was parsed from.
+    Text {
+        value: Cow<'s, str>,
+        start: Option<usize>,
+        span: Range<usize>,
+    },
+    Node(Node<'s>, Range<usize>),
+    /// A node built by an [`InlineRule`] out of the subject range.
+    Extension(Node<'s>, Range<usize>),
+    /// A run of `*` or `_`, `opens`/`closes` record the sizes of matched emphasis in match order.
+    Delimiter {
+        start: usize,
+        len: usize,
+        opens: Vec<usize>,
+        closes: Vec<usize>,
+    },
+    /// `[` or `![`, `link` is set once the matching `]` formed a link or image.
+    Bracket {
+        start: usize,
+        image: bool,
+        link: Option<LinkData<'s>>,
+    },
+    /// The end of a link or image, from its `]` at subject offset `close` to `end`.
+    BracketEnd {
+        close: usize,
+        end: usize,
+    },
+}
+
+enum LinkData<'s> {
+    Resource {
+        url: Cow<'s, str>,
+        title: Option<Cow<'s, str>>,
+    },
+    Reference {
+        identifier: String,
+        label: Cow<'s, str>,
+        reference_type: ReferenceType,
+    },
+}
+
+struct Delimiter {
+    ch: u8,
+    item: usize,
+    count: usize,
+    orig: usize,
+    can_open: bool,
+    can_close: bool,
+    previous: Option<usize>,
+    next: Option<usize>,
+}
+
+struct Bracket {
+    item: usize,
+    start: usize,
+    image: bool,
+    active: bool,
+    bracket_after: bool,
+    previous_delimiter: Option<usize>,
+}
+
+struct InlineParser<'s, 'd> {
+    subject: &'s str,
+    pos: usize,
+    items: Vec<Item<'s>>,
+    /// Delimiter arena, linked into a stack by `previous`/`next`.
+    delimiters: Vec<Delimiter>,
+    last_delimiter: Option<usize>,
+    brackets: Vec<Bracket>,
+    /// Offset of the last `]` of the subject, openers after it never close.
+    last_close_bracket: Option<usize>,
+    definitions: &'d BTreeSet<String>,
+    rules: &'d [Arc<dyn InlineRule>],
+    /// Trigger chars of all rules.
+    triggers: Vec<char>,
+    max_depth: usize,
+}
+
+impl<'s, 'd> InlineParser<'s, 'd> {
+    fn peek(&self) -> Option<u8> {
+        self.subject.as_bytes().get(self.pos).copied()
+    }
+
+    fn push_text(&mut self, range: Range<usize>) {
+        self.push_escaped_text(range.clone(), range);
+    }
+
+    /// Push the text at `range`, written as `span`, e.g. with a backslash.
+    fn push_escaped_text(&mut self, range: Range<usize>, span: Range<usize>) {
+        self.items.push(Item::Text {
+            value: Cow::Borrowed(&self.subject[range.clone()]),
+            start: Some(range.start),
+            span,
+        });
+    }
+
+    fn push_owned_text(&mut self, value: String, span: Range<usize>) {
+        self.items.push(Item::Text {
+            value: Cow::Owned(value),
+            start: None,
+            span,
+        });
+    }
+
+    fn push_node(&mut self, node: Node<'s>, span: Range<usize>) {
+        self.items.push(Item::Node(node, span));
+    }
+
+    fn parse_inline(&mut self) {
+        if !self.triggers.is_empty() && self.parse_extension() {
+            return;
+        }
+
+        let handled = match self.peek() {
+            Some(b'\n') => self.parse_newline(),
+            Some(b'\\') => self.parse_backslash(),
+            Some(b'`') => self.parse_backticks(),
+            Some(c @ (b'*' | b'_')) => self.handle_delimiter(c),
+            Some(b'[') => self.parse_open_bracket(),
+            Some(b'!') => self.parse_bang(),
+            Some(b']') => self.parse_close_bracket(),
+            Some(b'<') => self.parse_autolink() || self.parse_html(),
+            Some(b'&') => self.parse_entity(),
+            _ => self.parse_string(),
+        };
+
+        if !handled {
+            let len = self.subject[self.pos..]
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or(1);
+
+            self.push_text(self.pos..self.pos + len);
+            self.pos += len;
+        }
+    }
+
+    /// Try the rules triggered by the char at the current position, in order.
+    fn parse_extension(&mut self) -> bool {
+        let Some(c) = self.subject[self.pos..].chars().next() else {
+            return false;
+        };
+
+        if !self.triggers.contains(&c) {
+            return false;
+        }
+
+        for rule in self.rules {
+            if !rule.triggers().contains(&c) {
+                continue;
+            }
+
+            let mut cx = InlineContext::new(
+                self.subject,
+                self.pos,
+                self.definitions,
+                self.rules,
+                self.max_depth,
+            );
+
+            let node = rule.try_parse(&mut cx);
+
+            let end = cx.position();
+
+            if let Some(node) = node.filter(|_| end > self.pos) {
+                self.items.push(Item::Extension(node, self.pos..end));
+                self.pos = end;
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn parse_newline(&mut self) -> bool {
+        let newline = self.pos;
+
+        self.pos += 1;
+
+        let mut spaces = 0;
+
+        if let Some(Item::Text { value, span, .. }) = self.items.last_mut() {
+            let trimmed = value.trim_end_matches(' ').len();
+
+            spaces = (value.len() - trimmed).min(span.len());
+            span.end -= spaces;
+
+            match value {
+                Cow::Borrowed(borrowed) => *borrowed = &borrowed[..trimmed],
+                Cow::Owned(owned) => owned.truncate(trimmed),
+            }
+        }
+
+        if spaces >= 2 {
+            self.push_node(
+                Node::Break(Break { data: None }),
+                newline - spaces..newline + 1,
+            );
+        } else {
+            self.push_text(newline..newline + 1);
+        }
+
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+
+        true
+    }
+
+    fn parse_backslash(&mut self) -> bool {
+        self.pos += 1;
+
+        match self.peek() {
+            Some(b'\n') => {
+                self.pos += 1;
+                self.push_node(Node::Break(Break { data: None }), self.pos - 2..self.pos);
+            }
+            Some(c) if c.is_ascii_punctuation() => {
+                self.push_escaped_text(self.pos..self.pos + 1, self.pos - 1..self.pos + 1);
+                self.pos += 1;
+            }
+            _ => self.push_text(self.pos - 1..self.pos),
+        }
+
+        true
+    }
+
+    fn parse_backticks(&mut self) -> bool {
+        let start = self.pos;
+
+        let ticks = run_length(self.subject, start, b'`');
+
+        let after_open = start + ticks;
+
+        let bytes = self.subject.as_bytes();
+
+        let mut pos = after_open;
+
+        while pos < bytes.len() {
+            if bytes[pos] != b'`' {
+                pos += 1;
+                continue;
+            }
+
+            let run = run_length(self.subject, pos, b'`');
+
+            if run == ticks {
+                let contents = &self.subject[after_open..pos];
+
+                let is_space = |c: &u8| matches!(c, b' ' | b'\n');
+
+                let bytes = contents.as_bytes();
+
+                // strip one space from both sides, unless the span is only spaces.
+                let strip = bytes.len() >= 2
+                    && is_space(&bytes[0])
+                    && is_space(&bytes[bytes.len() - 1])
+                    && !bytes.iter().all(is_space);
+
+                let contents = if strip {
+                    &contents[1..contents.len() - 1]
+                } else {
+                    contents
+                };
+
+                let value = if contents.contains('\n') {
+                    Cow::Owned(contents.replace('\n', " "))
+                } else {
+                    Cow::Borrowed(contents)
+                };
+
+                self.push_node(
+                    Node::InlineCode(InlineCode { value, data: None }),
+                    start..pos + run,
+                );
+
+                self.pos = pos + run;
+
+                return true;
+            }
+
+            pos += run;
+        }
+
+        // no closing run, the backticks are literal.
+        self.push_text(start..after_open);
+        self.pos = after_open;
+
+        true
+    }
+
+    fn scan_delimiters(&self, ch: u8) -> (usize, bool, bool) {
+        let count = run_length(self.subject, self.pos, ch);
+
+        let before = self.subject[..self.pos].chars().next_back().unwrap_or('\n');
+        let after = self.subject[self.pos + count..]
+            .chars()
+            .next()
+            .unwrap_or('\n');
+
+        let after_whitespace = is_unicode_whitespace(after);
+        let after_punctuation = is_unicode_punctuation(after);
+        let before_whitespace = is_unicode_whitespace(before);
+        let before_punctuation = is_unicode_punctuation(before);
+
+        let left_flanking =
+            !after_whitespace && (!after_punctuation || before_whitespace || before_punctuation);
+        let right_flanking =
+            !before_whitespace && (!before_punctuation || after_whitespace || after_punctuation);
+
+        if ch == b'_' {
+            (
+                count,
+                left_flanking && (!right_flanking || before_punctuation),
+                right_flanking && (!left_flanking || after_punctuation),
+            )
+        } else {
+            (count, left_flanking, right_flanking)
+        }
+    }
+
+    fn handle_delimiter(&mut self, ch: u8) -> bool {
+        let (count, can_open, can_close) = self.scan_delimiters(ch);
+
+        let start = self.pos;
+
+        self.pos += count;
+
+        self.items.push(Item::Delimiter {
+            start,
+            len: count,
+            opens: vec![],
+            closes: vec![],
+        });
+
+        if can_open || can_close {
+            let index = self.delimiters.len();
+
+            self.delimiters.push(Delimiter {
+                ch,
+                item: self.items.len() - 1,
+                count,
+                orig: count,
+                can_open,
+                can_close,
+                previous: self.last_delimiter,
+                next: None,
+            });
+
+            if let Some(previous) = self.last_delimiter {
+                self.delimiters[previous].next = Some(index);
+            }
+
+            self.last_delimiter = Some(index);
+        }
+
+        true
+    }
+
+    fn remove_delimiter(&mut self, index: usize) {
+        let Delimiter { previous, next, .. } = self.delimiters[index];
+
+        if let Some(previous) = previous {
+            self.delimiters[previous].next = next;
+        }
+
+        match next {
+            Some(next) => self.delimiters[next].previous = previous,
+            None => self.last_delimiter = previous,
+        }
+    }
+
+    fn process_emphasis(&mut self, stack_bottom: Option<usize>) {
+        let mut openers_bottom = [stack_bottom; 12];
+
+        // find the first closer above stack_bottom.
+        let mut closer = self
+            .last_delimiter
+            .filter(|last| Some(*last) != stack_bottom);
+
+        while let Some(index) = closer {
+            if self.delimiters[index].previous == stack_bottom {
+                break;
+            }
+
+            closer = self.delimiters[index].previous;
+        }
+
+        while let Some(closer_index) = closer {
+            if !self.delimiters[closer_index].can_close {
+                closer = self.delimiters[closer_index].next;
+                continue;
+            }
+
+            let (ch, closer_can_open, closer_orig) = {
+                let closer = &self.delimiters[closer_index];
+
+                (closer.ch, closer.can_open, closer.orig)
+            };
+
+            let bottom_index = if ch == b'_' { 0 } else { 6 }
+                + if closer_can_open { 3 } else { 0 }
+                + closer_orig % 3;
+
+            // look back for the first matching opener.
+            let mut opener = self.delimiters[closer_index].previous;
+
+            let mut found = false;
+
+            while let Some(opener_index) = opener {
+                if Some(opener_index) == stack_bottom
+                    || Some(opener_index) == openers_bottom[bottom_index]
+                {
+                    break;
+                }
+
+                let candidate = &self.delimiters[opener_index];
+
+                let odd_match = (closer_can_open || candidate.can_close)
+                    && closer_orig % 3 != 0
+                    && (candidate.orig + closer_orig).is_multiple_of(3);
+
+                if candidate.ch == ch && candidate.can_open && !odd_match {
+                    found = true;
+                    break;
+                }
+
+                opener = candidate.previous;
+            }
+
+            let old_closer = closer_index;
+
+            match opener {
+                Some(opener_index) if found => {
+                    let used = if self.delimiters[closer_index].count >= 2
+                        && self.delimiters[opener_index].count >= 2
+                    {
+                        2
+                    } else {
+                        1
+                    };
+
+                    self.delimiters[opener_index].count -= used;
+                    self.delimiters[closer_index].count -= used;
+
+                    let opener_item = self.delimiters[opener_index].item;
+                    let closer_item = self.delimiters[closer_index].item;
+
+                    if let Item::Delimiter { opens, .. } = &mut self.items[opener_item] {
+                        opens.push(used);
+                    }
+
+                    if let Item::Delimiter { closes, .. } = &mut self.items[closer_item] {
+                        closes.push(used);
+                    }
+
+                    // remove delimiters between opener and closer.
+                    self.delimiters[opener_index].next = Some(closer_index);
+                    self.delimiters[closer_index].previous = Some(opener_index);
+
+                    if self.delimiters[opener_index].count == 0 {
+                        self.remove_delimiter(opener_index);
+                    }
+
+                    if self.delimiters[closer_index].count == 0 {
+                        let next = self.delimiters[closer_index].next;
+
+                        self.remove_delimiter(closer_index);
+
+                        closer = next;
+                    }
+                }
+                _ => {
+                    closer = self.delimiters[closer_index].next;
+
+                    // set lower bound for future searches for openers.
+                    openers_bottom[bottom_index] = self.delimiters[old_closer].previous;
+
+                    if !self.delimiters[old_closer].can_open {
+                        // a closer that can't open is useless once it found no opener.
+                        self.remove_delimiter(old_closer);
+                    }
+                }
+            }
+        }
+
+        // remove all delimiters above stack_bottom.
+        while let Some(index) = self.last_delimiter {
+            if Some(index) == stack_bottom {
+                break;
+            }
+
+            self.remove_delimiter(index);
+        }
+    }
+
+    fn add_bracket(&mut self, start: usize, image: bool) {
+        if self.last_close_bracket.is_none_or(|close| close < start) {
+            self.push_text(start..start + if image { 2 } else { 1 });
+            return;
+        }
+
+        if let Some(last) = self.brackets.last_mut() {
+            last.bracket_after = true;
+        }
+
+        self.items.push(Item::Bracket {
+            start,
+            image,
+            link: None,
+        });
+
+        self.brackets.push(Bracket {
+            item: self.items.len() - 1,
+            start,
+            image,
+            active: true,
+            bracket_after: false,
+            previous_delimiter: self.last_delimiter,
+        });
+    }
+
+    fn parse_open_bracket(&mut self) -> bool {
+        self.add_bracket(self.pos, false);
+        self.pos += 1;
+
+        true
+    }
+
+    fn parse_bang(&mut self) -> bool {
+        if self.subject.as_bytes().get(self.pos + 1) == Some(&b'[') {
+            self.add_bracket(self.pos, true);
+            self.pos += 2;
+        } else {
+            self.push_text(self.pos..self.pos + 1);
+            self.pos += 1;
+        }
+
+        true
+    }
+
+    fn parse_close_bracket(&mut self) -> bool {
+        let close = self.pos;
+
+        self.pos += 1;
+
+        let start = self.pos;
+
+        let Some(opener) = self.brackets.last() else {
+            self.push_text(close..start);
+            return true;
+        };
+
+        if !opener.active {
+            self.brackets.pop();
+            self.push_text(close..start);
+            return true;
+        }
+
+        let image = opener.image;
+
+        let text_start = opener.start + if image { 2 } else { 1 };
+
+        let mut link = None;
+
+        // inline link?
+        if self.peek() == Some(b'(') {
+            self.pos += 1;
+
+            link = self.parse_link_resource();
+
+            if link.is_none() {
+                self.pos = start;
+            }
+        }
+
+        if link.is_none() {
+            let before_label = self.pos;
+
+            let label_len = scan_link_label(&self.subject[before_label..]);
+
+            let (label, reference_type) = if label_len > 2 {
+                (
+                    Some(&self.subject[before_label + 1..before_label + label_len - 1]),
+                    ReferenceType::Full,
+                )
+            } else if !self
+                .brackets
+                .last()
+                .map(|b| b.bracket_after)
+                .unwrap_or(false)
+            {
+                let reference_type = if label_len == 2 {
+                    ReferenceType::Collapsed
+                } else {
+                    ReferenceType::Shortcut
+                };
+
+                (Some(&self.subject[text_start..close]), reference_type)
+            } else {
+                (None, ReferenceType::Shortcut)
+            };
+
+            if label_len == 0 {
+                // shortcut reference, rewind.
+                self.pos = start;
+            } else {
+                self.pos = before_label + label_len;
+            }
+
+            if let Some(label) = label {
+                let identifier = normalize_identifier(label);
+
+                if !identifier.is_empty()
+                    && label.len() <= 999
+                    && self.definitions.contains(&identifier)
+                {
+                    link = Some(LinkData::Reference {
+                        identifier,
+                        label: Cow::Borrowed(label),
+                        reference_type,
+                    });
+                }
+            }
+        }
+
+        let opener = self.brackets.pop().expect("checked above");
+
+        match link {
+            Some(link) => {
+                if let Item::Bracket { link: slot, .. } = &mut self.items[opener.item] {
+                    *slot = Some(link);
+                }
+
+                self.items.push(Item::BracketEnd {
+                    close,
+                    end: self.pos,
+                });
+
+                self.process_emphasis(opener.previous_delimiter);
+
+                // links can't contain other links. Openers below an inactive one were
+                // deactivated by an earlier link already, stopping there keeps this linear.
+                if !image {
+                    for bracket in self.brackets.iter_mut().rev().filter(|b| !b.image) {
+                        if !bracket.active {
+                            break;
+                        }
+
+                        bracket.active = false;
+                    }
+                }
+            }
+            None => {
+                self.pos = start;
+                self.push_text(close..start);
+            }
+        }
+
+        true
+    }
+
+    /// Parse `dest "title")` after the `(` of an inline link.
+    fn parse_link_resource(&mut self) -> Option<LinkData<'s>> {
+        self.skip_spaces_and_newline();
+
+        let url = self.parse_link_destination()?;
+
+        let before_title = self.pos;
+
+        self.skip_spaces_and_newline();
+
+        let mut title = None;
+
+        if self.pos != before_title {
+            title = self.parse_link_title();
+        }
+
+        if title.is_none() {
+            self.pos = before_title;
+        }
+
+        self.skip_spaces_and_newline();
+
+        if self.peek() != Some(b')') {
+            return None;
+        }
+
+        self.pos += 1;
+
+        Some(LinkData::Resource { url, title })
+    }
+
+    fn skip_spaces_and_newline(&mut self) {
+        self.pos = skip_spaces_and_newline(self.subject, self.pos);
+    }
+
+    fn parse_link_destination(&mut self) -> Option<Cow<'s, str>> {
+        let (raw, end) = scan_link_destination(self.subject, self.pos)?;
+
+        self.pos = end;
+
+        Some(unescape(raw))
+    }
+
+    fn parse_link_title(&mut self) -> Option<Cow<'s, str>> {
+        let (raw, end) = scan_link_title(self.subject, self.pos)?;
+
+        self.pos = end;
+
+        Some(unescape(raw))
+    }
+
+    fn parse_autolink(&mut self) -> bool {
+        let rest = &self.subject[self.pos..];
+
+        let (len, url) = if let Some(len) = scan_uri_autolink(rest) {
+            (len, Cow::Borrowed(&rest[1..len - 1]))
+        } else if let Some(len) = scan_email_autolink(rest) {
+            (len, Cow::Owned(format!("mailto:{}", &rest[1..len - 1])))
+        } else {
+            return false;
+        };
+
+        let value = &rest[1..len - 1];
+
+        self.push_node(
+            Node::Link(Box::new(Link {
+                children: vec![Node::Text(Text {
+                    value: Cow::Borrowed(value),
+                    data: None,
+                })],
+                url,
+                title: None,
+                data: None,
+            })),
+            self.pos..self.pos + len,
+        );
+
+        self.pos += len;
+
+        true
+    }
+
+    fn parse_html(&mut self) -> bool {
+        let rest = &self.subject[self.pos..];
+
+        let Some(len) = scan_html_tag(rest) else {
+            return false;
+        };
+
+        self.push_node(
+            Node::Html(Html {
+                value: Cow::Borrowed(&rest[..len]),
+                data: None,
+            }),
+            self.pos..self.pos + len,
+        );
+
+        self.pos += len;
+
+        true
+    }
+
+    fn parse_entity(&mut self) -> bool {
+        let Some((decoded, len)) = decode_entity(&self.subject[self.pos..]) else {
+            return false;
+        };
+
+        self.push_owned_text(decoded, self.pos..self.pos + len);
+
+        self.pos += len;
+
+        true
+    }
+
+    fn parse_string(&mut self) -> bool {
+        let start = self.pos;
+
+        let end = self.subject[start..]
+            .find(|c| {
+                matches!(
+                    c,
+                    '\n' | '`' | '[' | ']' | '\\' | '!' | '<' | '&' | '*' | '_'
+                ) || self.triggers.contains(&c)
+            })
+            .map(|offset| start + offset)
+            .unwrap_or(self.subject.len());
+
+        if end == start {
+            return false;
+        }
+
+        self.push_text(start..end);
+        self.pos = end;
+
+        true
+    }
+
+    /// Turn the flat item list into nodes, recording them in the side tables of `record`.
+    ///
+    /// Frames nested deeper than `max_depth` are skipped, their markers kept as text.
+    fn build<S: TreeSink<'s>>(
+        self,
+        sink: &S,
+        max_depth: usize,
+        record: Option<(&mut Record, &SourceMap)>,
+    ) -> S::Children {
+        let subject = self.subject;
+
+        // skipped frames are the innermost ones, closed first.
+        let mut skipped = 0;
+
+        let text = |frames: &mut Vec<Frame<'s, S>>, range: Range<usize>| {
+            frames.last_mut().unwrap().text(
+                subject,
+                Cow::Borrowed(&subject[range.clone()]),
+                Some(range.start),
+                range,
+            )
+        };
+
+        let (mut styles, spans, map) = match record {
+            Some((Record { styles, spans, .. }, map)) => {
+                (styles.as_mut(), spans.as_mut(), Some(map))
+            }
+            None => (None, None, None),
+        };
+
+        let mut frames = vec![Frame::new(FrameKind::Root, 0, spans.is_some(), sink)];
+
+        for item in self.items {
+            match item {
+                Item::Text { value, start, span } => {
+                    frames.last_mut().unwrap().text(subject, value, start, span)
+                }
+                Item::Node(node, span) => {
+                    // the text of an autolink is the destination inside the angle brackets.
+                    let text = matches!(node, Node::Link(_)).then(|| span.start + 1..span.end - 1);
+
+                    frames.last_mut().unwrap().push(
+                        sink.node(node),
+                        sink,
+                        core::iter::once(span).chain(text),
+                    )
+                }
+                Item::Extension(node, span) => {
+                    // the markup of nodes built by rules is unknown, they all span the text
+                    // of the rule.
+                    let nodes = || core::iter::once(&node).chain(node.descendants());
+
+                    if let Some(styles) = styles.as_deref_mut() {
+                        styles.extend(nodes().filter(|node| is_styled(node)).map(|_| None));
+                    }
+
+                    let count = nodes().count();
+
+                    frames.last_mut().unwrap().push(
+                        sink.node(node),
+                        sink,
+                        core::iter::repeat_n(span, count),
+                    )
+                }
+                Item::Delimiter {
+                    start,
+                    len,
+                    opens,
+                    closes,
+                } => {
+                    let mut end = start;
+
+                    for size in &closes {
+                        end += size;
+
+                        if skipped > 0 {
+                            text(&mut frames, end - size..end);
+                            skipped -= 1;
+                        } else {
+                            close_frame(&mut frames, end, sink);
+                        }
+                    }
+
+                    let closed: usize = closes.iter().sum();
+                    let opened: usize = opens.iter().sum();
+
+                    if closed + opened < len {
+                        text(&mut frames, start + closed..start + len - opened);
+                    }
+
+                    let mut start = start + len - opened;
+
+                    for size in opens.iter().rev() {
+                        if frames.len() > max_depth {
+                            text(&mut frames, start..start + size);
+                            skipped += 1;
+                            start += size;
+                            continue;
+                        }
+
+                        let kind = if *size == 2 {
+                            FrameKind::Strong
+                        } else {
+                            FrameKind::Emphasis
+                        };
+
+                        if let Some(styles) = styles.as_deref_mut() {
+                            styles.push(Some(NodeStyle::Attention(
+                                subject.as_bytes()[start] as char,
+                            )));
+                        }
+
+                        frames.push(Frame::new(kind, start, spans.is_some(), sink));
+
+                        start += size;
+                    }
+                }
+                Item::Bracket { start, image, link } => match link {
+                    Some(_) if frames.len() > max_depth => {
+                        text(&mut frames, start..start + if image { 2 } else { 1 });
+                        skipped += 1;
+                    }
+                    Some(link) => {
+                        let mut frame = Frame::new(
+                            FrameKind::Link { image, link },
+                            start,
+                            spans.is_some(),
+                            sink,
+                        );
+
+                        frame.styles = styles.as_deref().map_or(0, Vec::len);
+
+                        frames.push(frame);
+                    }
+                    None => text(&mut frames, start..start + if image { 2 } else { 1 }),
+                },
+                Item::BracketEnd { close, end } if skipped > 0 => {
+                    text(&mut frames, close..end);
+                    skipped -= 1;
+                }
+                Item::BracketEnd { end, .. } => {
+                    let frame = frames.last().unwrap();
+
+                    // the content of images only survives as alt text.
+                    if let (Some(styles), FrameKind::Link { image: true, .. }) =
+                        (styles.as_deref_mut(), &frame.kind)
+                    {
+                        styles.truncate(frame.styles);
+                    }
+
+                    close_frame(&mut frames, end, sink)
+                }
+            }
+        }
+
+        while frames.len() > 1 {
+            close_frame(&mut frames, subject.len(), sink);
+        }
+
+        let mut root = frames.pop().unwrap();
+
+        root.flush(sink);
+
+        if let (Some(spans), Some(map)) = (spans, map) {
+            spans.extend(
+                root.spans
+                    .take()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|span| map.range(span)),
+            );
+        }
+
+        root.children
+    }
+}
+
+enum FrameKind<'s> {
+    Root,
+    Emphasis,
+    Strong,
+    Link { image: bool, link: LinkData<'s> },
+}
+
+/// Text of a frame, with its subject range while still borrowed, and the subject range it was
+/// parsed from.
+type PendingText<'s> = (Cow<'s, str>, Option<Range<usize>>, Range<usize>);
+
+struct Frame<'s, S: TreeSink<'s>> {
+    kind: FrameKind<'s>,
+    /// Subject offset of the opening marker.
+    start: usize,
+    children: S::Children,
+    /// Pending text.
+    text: Option<PendingText<'s>>,
+    /// Number of styles recorded before this frame opened.
+    styles: usize,
+    /// Subject ranges of the descendants in document order, if recorded.
+    spans: Option<Vec<Range<usize>>>,
+}
+
+impl<'s, S: TreeSink<'s>> Frame<'s, S> {
+    fn new(kind: FrameKind<'s>, start: usize, spans: bool, sink: &S) -> Self {
+        Frame {
+            kind,
+            start,
+            children: sink.children(),
+            text: None,
+            styles: 0,
+            spans: spans.then(Vec::new),
+        }
+    }
+
+    fn text(
+        &mut self,
+        subject: &'s str,
+        value: Cow<'s, str>,
+        start: Option<usize>,
+        span: Range<usize>,
+    ) {
+        if value.is_empty() {
+            return;
+        }
+
+        let range = start.map(|start| start..start + value.len());
+
+        self.text = match self.text.take() {
+            None => Some((value, range, span)),
+            Some((current, current_range, current_span)) => {
+                let span = current_span.start..span.end;
+
+                match (current_range, range) {
+                    (Some(current_range), Some(range)) if current_range.end == range.start => {
+                        let merged = current_range.start..range.end;
+
+                        Some((Cow::Borrowed(&subject[merged.clone()]), Some(merged), span))
+                    }
+                    _ => {
+                        let mut owned = current.into_owned();
+
+                        owned.push_str(&value);
+
+                        Some((Cow::Owned(owned), None, span))
+                    }
+                }
+            }
+        };
+    }
+
+    fn flush(&mut self, sink: &S) {
+        if let Some((value, _, span)) = self.text.take() {
+            self.record(core::iter::once(span));
+
+            sink.push(
+                &mut self.children,
+                sink.node(Node::Text(Text { value, data: None })),
+            );
+        }
+    }
+
+    /// Push `node`, which spans the first of `spans`, its descendants spanning the others.
+    fn push<I>(&mut self, node: S::Node, sink: &S, spans: I)
+    where
+        I: IntoIterator<Item = Range<usize>>,
+    {
+        self.flush(sink);
+        self.record(spans);
+
+        sink.push(&mut self.children, node);
+    }
+
+    fn record<I>(&mut self, spans: I)
+    where
+        I: IntoIterator<Item = Range<usize>>,
+    {
+        if let Some(recorded) = &mut self.spans {
+            recorded.extend(spans);
+        }
+    }
+}
+
+/// Pop the innermost frame, closed at subject offset `end`, and append its node to the parent
+/// frame.
+fn close_frame<'s, S: TreeSink<'s>>(frames: &mut Vec<Frame<'s, S>>, end: usize, sink: &S) {
+    if frames.len() < 2 {
+        return;
+    }
+
+    let mut frame = frames.pop().unwrap();
+
+    frame.flush(sink);
+
+    let span = frame.start..end;
+
+    // only the text of images is kept, as alt text.
+    let spans = match frame.kind {
+        FrameKind::Link { image: true, .. } => vec![],
+        _ => frame.spans.take().unwrap_or_default(),
+    };
+
+    let children = frame.children;
+
+    let node = match frame.kind {
+        FrameKind::Root => unreachable!("the root frame is never closed"),
+        FrameKind::Emphasis => sink.parent(
+            Node::Emphasis(Emphasis {
+                children: vec![],
+                data: None,
+            }),
+            children,
+        ),
+        FrameKind::Strong => sink.parent(
+            Node::Strong(Strong {
+                children: vec![],
+                data: None,
+            }),
+            children,
+        ),
+        FrameKind::Link {
+            image: false,
+            link: LinkData::Resource { url, title },
+        } => sink.parent(
+            Node::Link(Box::new(Link {
+                children: vec![],
+                url,
+                title,
+                data: None,
+            })),
+            children,
+        ),
+        FrameKind::Link {
+            image: true,
+            link: LinkData::Resource { url, title },
+        } => sink.node(Node::Image(Box::new(Image {
+            url,
+            title,
+            alt: Some(sink.plain_text(&children).into()),
+            data: None,
+        }))),
+        FrameKind::Link {
+            image: false,
+            link:
+                LinkData::Reference {
+                    identifier,
+                    label,
+                    reference_type,
+                },
+        } => sink.parent(
+            Node::LinkReference(Box::new(LinkReference {
+                children: vec![],
+                identifier: identifier.into(),
+                label: Some(label),
+                reference_type,
+                data: None,
+            })),
+            children,
+        ),
+        FrameKind::Link {
+            image: true,
+            link:
+                LinkData::Reference {
+                    identifier,
+                    label,
+                    reference_type,
+                },
+        } => sink.node(Node::ImageReference(Box::new(ImageReference {
+            alt: Some(sink.plain_text(&children).into()),
+            identifier: identifier.into(),
+            label: Some(label),
+            reference_type,
+            data: None,
+        }))),
+    };
+
+    frames
+        .last_mut()
+        .unwrap()
+        .push(node, sink, core::iter::once(span).chain(spans));
+}
+
+fn run_length(subject: &str, start: usize, ch: u8) -> usize {
+    subject.as_bytes()[start..]
+        .iter()
+        .take_while(|c| **c == ch)
+        .count()
+}
+
+/// Skip spaces or tabs, up to one line ending, then spaces or tabs again.
+pub(super) fn skip_spaces_and_newline(subject: &str, mut pos: usize) -> usize {
+    let bytes = subject.as_bytes();
+
+    while matches!(bytes.get(pos), Some(b' ' | b'\t')) {
+        pos += 1;
+    }
+
+    if bytes.get(pos) == Some(&b'\n') {
+        pos += 1;
+
+        while matches!(bytes.get(pos), Some(b' ' | b'\t')) {
+            pos += 1;
+        }
+    }
+
+    pos
+}
+
+/// Scan a link label `[...]` at the start of `s`, returns its length including brackets or 0.
+pub(super) fn scan_link_label(s: &str) -> usize {
+    let bytes = s.as_bytes();
+
+    if bytes.first() != Some(&b'[') {
+        return 0;
+    }
+
+    let mut pos = 1;
+
+    while pos < bytes.len() && pos <= 1000 {
+        match bytes[pos] {
+            b'\\' if pos + 1 < bytes.len() => pos += 2,
+            b'[' => return 0,
+            b']' => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+
+    0
+}
+
+/// Scan a link destination starting at `pos`, returns the raw destination and the end offset.
+pub(super) fn scan_link_destination(subject: &str, pos: usize) -> Option<(&str, usize)> {
+    let bytes = subject.as_bytes();
+
+    if bytes.get(pos) == Some(&b'<') {
+        let mut end = pos + 1;
+
+        loop {
+            match bytes.get(end)? {
+                b'>' => return Some((&subject[pos + 1..end], end + 1)),
+                b'<' | b'\n' => return None,
+                b'\\' if bytes.get(end + 1).is_some_and(u8::is_ascii_punctuation) => end += 2,
+                _ => end += 1,
+            }
+        }
+    }
+
+    let (end, parens) = scan_balanced(bytes, pos, (b'(', b')'), 32, |c| {
+        c.is_ascii_whitespace() || c.is_ascii_control()
+    })?;
+
+    if end == pos && bytes.get(end) != Some(&b')') {
+        return None;
+    }
+
+    if parens != 0 {
+        return None;
+    }
+
+    Some((&subject[pos..end], end))
+}
+
+/// Scan a link title starting at `pos`, returns the raw title and the end offset.
+pub(super) fn scan_link_title(subject: &str, pos: usize) -> Option<(&str, usize)> {
+    let bytes = subject.as_bytes();
+
+    let close = match bytes.get(pos)? {
+        b'"' => b'"',
+        b'\'' => b'\'',
+        b'(' => b')',
+        _ => return None,
+    };
+
+    let mut end = pos + 1;
+
+    loop {
+        match *bytes.get(end)? {
+            b'\\' if bytes.get(end + 1).is_some_and(u8::is_ascii_punctuation) => end += 2,
+            c if c == close => return Some((&subject[pos + 1..end], end + 1)),
+            b'(' if close == b')' => return None,
+            _ => end += 1,
+        }
+    }
+}
+
+/// Returns true if `url` starts with a scheme, as URI autolinks do.
+pub(crate) fn has_scheme(url: &str) -> bool {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return false;
+    };
+
+    (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+}
+
+/// Scan an URI autolink `<scheme:...>`, returns its length.
+fn scan_uri_autolink(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+
+    if bytes.first() != Some(&b'<') || !bytes.get(1)?.is_ascii_alphabetic() {
+        return None;
+    }
+
+    let scheme = bytes[1..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, b'+' | b'.' | b'-'))
+        .count();
+
+    if !(2..=32).contains(&scheme) || bytes.get(1 + scheme) != Some(&b':') {
+        return None;
+    }
+
+    let mut end = 2 + scheme;
+
+    loop {
+        match *bytes.get(end)? {
+            b'>' => return Some(end + 1),
+            b'<' => return None,
+            c if c <= b' ' => return None,
+            _ => end += 1,
+        }
+    }
+}
+
+/// Scan an email autolink `<user@host>`, returns its length.
+fn scan_email_autolink(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+
+    if bytes.first() != Some(&b'<') {
+        return None;
+    }
+
+    let local = bytes[1..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || b".!#$%&'*+/=?^_`{|}~-".contains(c))
+        .count();
+
+    if local == 0 || bytes.get(1 + local) != Some(&b'@') {
+        return None;
+    }
+
+    let mut pos = 2 + local;
+
+    loop {
+        // a domain label: alphanumeric at both ends, up to 63 chars.
+        let label = bytes[pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'-')
+            .count();
+
+        if label == 0 || label > 63 || bytes[pos] == b'-' || bytes[pos + label - 1] == b'-' {
+            return None;
+        }
+
+        pos += label;
+
+        match bytes.get(pos)? {
+            b'.' => pos += 1,
+            b'>' => return Some(pos + 1),
+            _ => return None,
+        }
+    }
+}
+
+fn scan_tag_name(bytes: &[u8], pos: usize) -> Option<usize> {
+    if !bytes.get(pos)?.is_ascii_alphabetic() {
+        return None;
+    }
+
+    Some(
+        pos + bytes[pos..]
+            .iter()
+            .take_while(|c| c.is_ascii_alphanumeric() || **c == b'-')
+            .count(),
+    )
+}
+
+fn skip_whitespace(bytes: &[u8], mut pos: usize) -> usize {
+    while bytes
+        .get(pos)
+        .is_some_and(|c| matches!(c, b' ' | b'\t' | b'\n' | b'\r' | b'\x0C'))
+    {
+        pos += 1;
+    }
+
+    pos
+}
+
+/// Scan an HTML open tag, returns its length.
+pub(super) fn scan_open_tag(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+
+    if bytes.first() != Some(&b'<') {
+        return None;
+    }
+
+    let mut pos = scan_tag_name(bytes, 1)?;
+
+    loop {
+        let after_whitespace = skip_whitespace(bytes, pos);
+
+        match bytes.get(after_whitespace)? {
+            b'>' => return Some(after_whitespace + 1),
+            b'/' => {
+                return (bytes.get(after_whitespace + 1) == Some(&b'>'))
+                    .then_some(after_whitespace + 2)
+            }
+            c if after_whitespace > pos
+                && (c.is_ascii_alphabetic() || matches!(c, b'_' | b':')) =>
+            {
+                // attribute name
+                pos = after_whitespace
+                    + bytes[after_whitespace..]
+                        .iter()
+                        .take_while(|c| {
+                            c.is_ascii_alphanumeric() || matches!(c, b'_' | b'.' | b':' | b'-')
+                        })
+                        .count();
+
+                // optional attribute value
+                let before_value = skip_whitespace(bytes, pos);
+
+                if bytes.get(before_value) == Some(&b'=') {
+                    let value = skip_whitespace(bytes, before_value + 1);
+
+                    pos = match *bytes.get(value)? {
+                        quote @ (b'"' | b'\'') => {
+                            let len = bytes[value + 1..].iter().position(|c| *c == quote)?;
+
+                            value + len + 2
+                        }
+                        _ => {
+                            let len = bytes[value..]
+                                .iter()
+                                .take_while(|c| !c.is_ascii_whitespace() && !b"\"'=<>`".contains(c))
+                                .count();
+
+                            if len == 0 {
+                                return None;
+                            }
+
+                            value + len
+                        }
+                    };
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Scan an HTML closing tag, returns its length.
+pub(super) fn scan_closing_tag(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+
+    if !bytes.starts_with(b"</") {
+        return None;
+    }
+
+    let pos = skip_whitespace(bytes, scan_tag_name(bytes, 2)?);
+
+    (bytes.get(pos) == Some(&b'>')).then_some(pos + 1)
+}
+
+/// Scan inline raw HTML: a tag, comment, processing instruction, declaration or CDATA section.
+fn scan_html_tag(s: &str) -> Option<usize> {
+    let find = |start: usize, end: &str| s.get(start..)?.find(end).map(|i| start + i + end.len());
+
+    if let Some(comment) = s.strip_prefix("<!--") {
+        if comment.starts_with('>') {
+            return Some(5);
+        }
+
+        if comment.starts_with("->") {
+            return Some(6);
+        }
+
+        return find(4, "-->");
+    }
+
+    if s.starts_with("<?") {
+        return find(2, "?>");
+    }
+
+    if s.starts_with("<![CDATA[") {
+        return find(9, "]]>");
+    }
+
+    if s.starts_with("<!") && s.as_bytes().get(2).is_some_and(u8::is_ascii_alphabetic) {
+        return find(2, ">");
+    }
+
+    scan_open_tag(s).or_else(|| scan_closing_tag(s))
+}
+
+/// Decode an entity or numeric character reference at the start of `s`,
+/// returns the decoded value and the length of the reference.
+pub(super) fn decode_entity(s: &str) -> Option<(String, usize)> {
+    let bytes = s.as_bytes();
+
+    if bytes.first() != Some(&b'&') {
+        return None;
+    }
+
+    if bytes.get(1) == Some(&b'#') {
+        let (hex, digits_start) = match bytes.get(2) {
+            Some(b'x' | b'X') => (true, 3),
+            _ => (false, 2),
+        };
+
+        let digits = bytes[digits_start..]
+            .iter()
+            .take_while(|c| {
+                if hex {
+                    c.is_ascii_hexdigit()
+                } else {
+                    c.is_ascii_digit()
+                }
+            })
+            .count();
+
+        let max = if hex { 6 } else { 7 };
+
+        if digits == 0 || digits > max || bytes.get(digits_start + digits) != Some(&b';') {
+            return None;
+        }
+
+        let value = u32::from_str_radix(
+            &s[digits_start..digits_start + digits],
+            if hex { 16 } else { 10 },
+        )
+        .ok()?;
+
+        let c = match value {
+            0 => '\u{FFFD}',
+            value => char::from_u32(value).unwrap_or('\u{FFFD}'),
+        };
+
+        return Some((c.to_string(), digits_start + digits + 1));
+    }
+
+    let name = bytes[1..]
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .count();
+
+    if name == 0 || name > 32 || bytes.get(1 + name) != Some(&b';') {
+        return None;
+    }
+
+    let reference = &s[..name + 2];
+
+    entities::ENTITIES
+        .iter()
+        .find(|entity| entity.entity == reference)
+        .map(|entity| (entity.characters.to_owned(), reference.len()))
+}
+
+/// Process backslash escapes and character references of `raw`, borrowing when nothing changes.
+pub(crate) fn unescape(raw: &str) -> Cow<'_, str> {
+    if !raw.contains(['\\', '&']) {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut out = String::with_capacity(raw.len());
+
+    let mut pos = 0;
+
+    while let Some(c) = raw[pos..].chars().next() {
+        match c {
+            '\\' if raw[pos + 1..].starts_with(|c: char| c.is_ascii_punctuation()) => {
+                out.push_str(&raw[pos + 1..pos + 2]);
+                pos += 2;
+            }
+            '&' => match decode_entity(&raw[pos..]) {
+                Some((decoded, len)) => {
+                    out.push_str(&decoded);
+                    pos += len;
+                }
+                None => {
+                    out.push('&');
+                    pos += 1;
+                }
+            },
+            c => {
+                out.push(c);
+                pos += c.len_utf8();
+            }
+        }
+    }
+
+    Cow::Owned(out)
+}
@@ -0,0 +1,101 @@
+//! Side tables filled in document order while blocks are converted into nodes.
+
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{ast::style::NodeStyle, to_markdown::ListNumbering};
+
+/// Side tables of a parse, each filled when enabled.
+#[derive(Default)]
+pub(super) struct Record {
+    /// Markup style of lists, attention, headings and code, `None` if unknown.
+    pub(super) styles: Option<Vec<Option<NodeStyle>>>,
+    /// Numbering of ordered lists, `None` if unknown, recorded along with `styles`.
+    pub(super) numberings: Vec<Option<ListNumbering>>,
+    /// Source span of every node.
+    pub(super) spans: Option<Vec<Range<usize>>>,
+}
+
+/// Maps offsets of parsed text, such as block content made of lines stripped of their
+/// container markers, to source offsets.
+#[derive(Clone, Debug, Default)]
+pub(super) struct SourceMap {
+    /// Text offset and source offset of the start of each piece of text, by text offset.
+    pieces: Vec<(usize, usize)>,
+}
+
+impl SourceMap {
+    /// Create a map of text starting at source offset `start`.
+    pub(super) fn at(start: usize) -> Self {
+        SourceMap {
+            pieces: vec![(0, start)],
+        }
+    }
+
+    /// Add a piece of text from text offset `offset`, found at source offset `source`.
+    pub(super) fn push(&mut self, offset: usize, source: usize) {
+        if self.pieces.last().is_some_and(|last| last.0 == offset) {
+            self.pieces.pop();
+        }
+
+        self.pieces.push((offset, source));
+    }
+
+    /// Add a piece of text from text offset `offset`, read from `line` at source offset
+    /// `source` with its NULs replaced by U+FFFD: the three bytes of a replacement map to its
+    /// NUL.
+    pub(super) fn push_line(&mut self, offset: usize, source: usize, line: &str) {
+        self.push(offset, source);
+
+        for (shift, (index, _)) in line.match_indices('\0').enumerate() {
+            let replacement = offset + index + 2 * shift;
+
+            self.push(replacement + 1, source + index);
+            self.push(replacement + 2, source + index);
+            self.push(replacement + 3, source + index + 1);
+        }
+    }
+
+    /// Returns the source offset of text offset `offset`.
+    pub(super) fn source(&self, offset: usize) -> usize {
+        let index = self.pieces.partition_point(|piece| piece.0 <= offset);
+
+        match index.checked_sub(1) {
+            Some(index) => self.pieces[index].1 + offset - self.pieces[index].0,
+            None => offset,
+        }
+    }
+
+    /// Returns the source range of text range `range`.
+    pub(super) fn range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.source(range.start);
+
+        // the end of a piece is not the start of the next one.
+        let end = match range.end.checked_sub(1) {
+            Some(last) if range.end > range.start => self.source(last) + 1,
+            _ => start,
+        };
+
+        start..end
+    }
+
+    /// Returns the map of the text from text offset `offset` on.
+    pub(super) fn from(&self, offset: usize) -> SourceMap {
+        let mut map = SourceMap::at(self.source(offset));
+
+        for &(start, source) in &self.pieces {
+            if start > offset {
+                map.push(start - offset, source);
+            }
+        }
+
+        map
+    }
+
+    /// Keep the text before text offset `offset` only.
+    pub(super) fn truncate(&mut self, offset: usize) {
+        let keep = self.pieces.partition_point(|piece| piece.0 < offset).max(1);
+
+        self.pieces.truncate(keep);
+    }
+}
@@ -0,0 +1,171 @@
+//! Destinations of the nodes built by the parser: owned [`Node`] trees, or arena nodes.
+
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::ast::*;
+
+/// Builds the tree out of the nodes produced by the block and inline parsers.
+///
+/// Parents are produced by [`TreeSink::parent`] from a node without children and the children
+/// list collected for it, so implementations decide where children are stored.
+pub(crate) trait TreeSink<'s> {
+    type Node;
+    type Children;
+
+    /// Returns a new empty children list.
+    fn children(&self) -> Self::Children;
+
+    fn push(&self, children: &mut Self::Children, node: Self::Node);
+
+    /// Returns `node`, whose own children are empty, with `children`.
+    fn parent(&self, node: Node<'s>, children: Self::Children) -> Self::Node;
+
+    /// Converts a complete `node` tree.
+    fn node(&self, node: Node<'s>) -> Self::Node;
+
+    /// Plain text content of `children`, used for image alt text.
+    fn plain_text(&self, children: &Self::Children) -> String;
+}
+
+/// Builds owned [`Node`] trees.
+pub(crate) struct Owned;
+
+impl<'s> TreeSink<'s> for Owned {
+    type Node = Node<'s>;
+    type Children = Vec<Node<'s>>;
+
+    fn children(&self) -> Self::Children {
+        vec![]
+    }
+
+    fn push(&self, children: &mut Self::Children, node: Self::Node) {
+        children.push(node);
+    }
+
+    fn parent(&self, mut node: Node<'s>, children: Self::Children) -> Self::Node {
+        if let Some(slot) = node.children_mut() {
+            *slot = children;
+        }
+
+        node
+    }
+
+    fn node(&self, node: Node<'s>) -> Self::Node {
+        node
+    }
+
+    fn plain_text(&self, children: &Self::Children) -> String {
+        let mut out = String::new();
+
+        for node in children {
+            plain_text(node, &mut out);
+        }
+
+        out
+    }
+}
+
+/// Appends the plain text of `node` to `out`: text and code values, image alt text and a
+/// newline per break.
+pub(crate) fn plain_text(node: &Node, out: &mut String) {
+    match node {
+        Node::Text(text) => out.push_str(&text.value),
+        Node::InlineCode(code) => out.push_str(&code.value),
+        Node::Image(image) => out.push_str(image.alt.as_deref().unwrap_or_default()),
+        Node::ImageReference(image) => out.push_str(image.alt.as_deref().unwrap_or_default()),
+        Node::Break(_) => out.push('\n'),
+        node => {
+            for child in node.children().unwrap_or_default() {
+                plain_text(child, out);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "arena")]
+pub(crate) use self::arena::ArenaSink;
+
+#[cfg(feature = "arena")]
+mod arena {
+    use core::cell::RefCell;
+
+    use crate::ast::arena::{Arena, ArenaNode};
+
+    use super::*;
+
+    /// Builds [`ArenaNode`] trees, children lists are collected in pooled buffers and then
+    /// moved into the arena.
+    pub(crate) struct ArenaSink<'a, 'cx> {
+        arena: &'a Arena<'a, 'cx>,
+        pool: RefCell<Vec<Vec<ArenaNode<'a, 'cx>>>>,
+    }
+
+    impl<'a, 'cx> ArenaSink<'a, 'cx> {
+        pub(crate) fn new(arena: &'a Arena<'a, 'cx>) -> Self {
+            ArenaSink {
+                arena,
+                pool: RefCell::new(vec![]),
+            }
+        }
+
+        /// Moves `children` into the arena, recycling the buffer.
+        pub(crate) fn alloc(
+            &self,
+            mut children: Vec<ArenaNode<'a, 'cx>>,
+        ) -> &'a [ArenaNode<'a, 'cx>] {
+            let slice = match children.is_empty() {
+                true => &[],
+                false => self.arena.alloc_extend(children.drain(..)),
+            };
+
+            self.pool.borrow_mut().push(children);
+
+            slice
+        }
+    }
+
+    impl<'a, 'cx> TreeSink<'cx> for ArenaSink<'a, 'cx> {
+        type Node = ArenaNode<'a, 'cx>;
+        type Children = Vec<ArenaNode<'a, 'cx>>;
+
+        fn children(&self) -> Self::Children {
+            self.pool.borrow_mut().pop().unwrap_or_default()
+        }
+
+        fn push(&self, children: &mut Self::Children, node: Self::Node) {
+            children.push(node);
+        }
+
+        fn parent(&self, node: Node<'cx>, children: Self::Children) -> Self::Node {
+            ArenaNode {
+                node,
+                children: self.alloc(children),
+            }
+        }
+
+        fn node(&self, mut node: Node<'cx>) -> Self::Node {
+            let Some(children) = node.children_mut().map(core::mem::take) else {
+                return ArenaNode {
+                    node,
+                    children: &[],
+                };
+            };
+
+            let mut list = self.children();
+
+            list.extend(children.into_iter().map(|child| self.node(child)));
+
+            self.parent(node, list)
+        }
+
+        fn plain_text(&self, children: &Self::Children) -> String {
+            let mut out = String::new();
+
+            for child in children {
+                child.plain_text(&mut out);
+            }
+
+            out
+        }
+    }
+}
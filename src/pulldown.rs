@@ -0,0 +1,843 @@
+//! Interop with [pulldown-cmark](https://docs.rs/pulldown-cmark) event streams, for the
+//! highlighters, table of contents builders and mdBook plugins consuming them.
+//!
+//! [`events`] walks a document as pulldown-cmark [`Event`]s and [`from_events`] builds a
+//! document from them:
+//!
+//! ```
+//! use markdown_rs::{parser::Parser, pulldown};
+//! use pulldown_cmark::{Event, Tag};
+//!
+//! let document = Parser::new("# Title\n\nSee [docs].\n\n[docs]: https://docs.rs")
+//!     .parse()
+//!     .unwrap();
+//!
+//! let links = pulldown::events(&document)
+//!     .filter(|event| matches!(event, Event::Start(Tag::Link { .. })))
+//!     .count();
+//!
+//! assert_eq!(links, 1);
+//!
+//! let events = pulldown_cmark::Parser::new("# Title\n\n- *a*\n- b");
+//!
+//! let document = pulldown::from_events(events);
+//!
+//! assert_eq!(document.children.len(), 2);
+//! ```
+//!
+//! The two models differ, conversions follow these rules:
+//!
+//! - References are resolved against the definitions of the document: they yield links and
+//!   images with a `Reference`, `Collapsed` or `Shortcut` link type, while references
+//!   without a definition yield their content only, as pulldown-cmark does. Definitions yield
+//!   no events, so [`from_events`] never builds definitions nor references.
+//! - Links whose text is their url, or their email address after `mailto:`, yield
+//!   autolinks.
+//! - Images yield their alt text as a single text event, the markup of alt texts is lost.
+//! - Code blocks always yield fenced code blocks.
+//! - Paragraphs of tight lists yield no paragraph tags, [`from_events`] wraps the phrasing
+//!   content of list items back in paragraphs. pulldown-cmark only tells whether whole lists
+//!   are loose, [`from_events`] builds list items that are not spread.
+//! - Raw html yields html blocks and inline html, footnotes yield footnote definitions and
+//!   references, and the other way around.
+//! - Task list markers follow the start of their item.
+//! - [`from_events`] turns math into inline code, keeps the content of superscripts,
+//!   subscripts and definition lists without the wrapper, builds paragraphs from definition
+//!   list titles and skips metadata blocks.
+
+use alloc::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    format,
+    string::String,
+    vec,
+    vec::Vec,
+};
+use core::{iter::Enumerate, ops::Range, slice::Iter};
+
+use pulldown_cmark::{
+    Alignment, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Tag, TagEnd,
+};
+
+use crate::ast::{
+    resolve::definitions,
+    text::{to_text, TextOptions},
+    *,
+};
+
+/// Returns the pulldown-cmark events of `document`, see the [module](self) documentation.
+pub fn events<'a, 'cx>(document: &'a Document<'cx>) -> Events<'a, 'cx> {
+    Events {
+        definitions: definitions(document),
+        stack: vec![Frame {
+            children: document.children.iter().enumerate(),
+            end: None,
+            context: Context::Flow,
+        }],
+        pending: VecDeque::new(),
+    }
+}
+
+/// Iterator over the pulldown-cmark events of a document, see [`events`].
+pub struct Events<'a, 'cx> {
+    definitions: BTreeMap<String, &'a Definition<'cx>>,
+    stack: Vec<Frame<'a, 'cx>>,
+    pending: VecDeque<Event<'a>>,
+}
+
+struct Frame<'a, 'cx> {
+    children: Enumerate<Iter<'a, Node<'cx>>>,
+    end: Option<TagEnd>,
+    context: Context,
+}
+
+/// What the children of a frame are.
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    Flow,
+    Phrasing,
+    /// Items of a tight list.
+    TightList,
+    /// Content of an item of a tight list, whose paragraphs yield no tags.
+    TightItem,
+    Table,
+}
+
+impl<'a, 'cx> Iterator for Events<'a, 'cx> {
+    type Item = Event<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let frame = self.stack.last_mut()?;
+
+            let context = frame.context;
+
+            match frame.children.next() {
+                Some((index, node)) => self.enter(node, index, context),
+                None => {
+                    if let Some(end) = self.stack.pop().and_then(|frame| frame.end) {
+                        return Some(Event::End(end));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, 'cx> Events<'a, 'cx> {
+    /// Queues the start of a parent, its children are walked next.
+    fn open(&mut self, tag: Option<Tag<'a>>, children: &'a [Node<'cx>], context: Context) {
+        let end = tag.as_ref().map(Tag::to_end);
+
+        self.pending.extend(tag.map(Event::Start));
+
+        self.stack.push(Frame {
+            children: children.iter().enumerate(),
+            end,
+            context,
+        });
+    }
+
+    fn leaf(&mut self, tag: Tag<'a>, events: impl IntoIterator<Item = Event<'a>>) {
+        let end = tag.to_end();
+
+        self.pending.push_back(Event::Start(tag));
+        self.pending.extend(events);
+        self.pending.push_back(Event::End(end));
+    }
+
+    fn enter(&mut self, node: &'a Node<'cx>, index: usize, context: Context) {
+        match node {
+            Node::Document(document) => self.open(None, &document.children, Context::Flow),
+            Node::Paragraph(paragraph) => {
+                let tag = (context != Context::TightItem).then_some(Tag::Paragraph);
+
+                self.open(tag, &paragraph.children, Context::Phrasing);
+            }
+            Node::Heading(heading) => {
+                let depth = heading
+                    .depth
+                    .clamp(*Heading::DEPTHS.start(), *Heading::DEPTHS.end());
+
+                let level = HeadingLevel::try_from(usize::from(depth))
+                    .expect("depths are clamped to heading levels");
+
+                let tag = Tag::Heading {
+                    level,
+                    id: None,
+                    classes: vec![],
+                    attrs: vec![],
+                };
+
+                self.open(Some(tag), &heading.children, Context::Phrasing);
+            }
+            Node::ThematicBreak(_) => self.pending.push_back(Event::Rule),
+            Node::Blockquote(blockquote) => {
+                self.open(
+                    Some(Tag::BlockQuote(None)),
+                    &blockquote.children,
+                    Context::Flow,
+                );
+            }
+            Node::List(list) => {
+                let start = match list.ordered {
+                    Some(true) => Some(list.start.unwrap_or(1).into()),
+                    _ => None,
+                };
+
+                let loose = list.spread == Some(true)
+                    || list.children.iter().any(
+                        |child| matches!(child, Node::ListItem(item) if item.spread == Some(true)),
+                    );
+
+                let context = if loose {
+                    Context::Flow
+                } else {
+                    Context::TightList
+                };
+
+                self.open(Some(Tag::List(start)), &list.children, context);
+            }
+            Node::ListItem(item) => {
+                let context = if context == Context::TightList {
+                    Context::TightItem
+                } else {
+                    Context::Flow
+                };
+
+                self.open(Some(Tag::Item), &item.children, context);
+
+                if let Some(checked) = item.checked {
+                    self.pending.push_back(Event::TaskListMarker(checked));
+                }
+            }
+            Node::Code(code) => {
+                let info = match (&code.lang, &code.meta) {
+                    (Some(lang), Some(meta)) => format!("{} {}", lang, meta).into(),
+                    (Some(lang), None) => borrowed(lang),
+                    (None, _) => CowStr::Borrowed(""),
+                };
+
+                let value = (!code.value.is_empty())
+                    .then(|| Event::Text(format!("{}\n", code.value).into()));
+
+                self.leaf(Tag::CodeBlock(CodeBlockKind::Fenced(info)), value);
+            }
+            Node::Definition(_) => {}
+            // pulldown-cmark has no events for extension nodes, only their content is kept.
+            Node::Custom(custom) => self.open(None, &custom.children, context),
+            Node::Text(text) => {
+                for (index, line) in text.value.split('\n').enumerate() {
+                    if index > 0 {
+                        self.pending.push_back(Event::SoftBreak);
+                    }
+
+                    if !line.is_empty() {
+                        self.pending.push_back(Event::Text(CowStr::Borrowed(line)));
+                    }
+                }
+            }
+            Node::Emphasis(emphasis) => {
+                self.open(Some(Tag::Emphasis), &emphasis.children, Context::Phrasing);
+            }
+            Node::Strong(strong) => {
+                self.open(Some(Tag::Strong), &strong.children, Context::Phrasing);
+            }
+            Node::Delete(delete) => {
+                self.open(
+                    Some(Tag::Strikethrough),
+                    &delete.children,
+                    Context::Phrasing,
+                );
+            }
+            Node::InlineCode(code) => self.pending.push_back(Event::Code(borrowed(&code.value))),
+            Node::Break(_) => self.pending.push_back(Event::HardBreak),
+            Node::Link(link) => {
+                let text = match link.children.as_slice() {
+                    [Node::Text(text)] => Some(text.value.as_ref()),
+                    _ => None,
+                };
+
+                let (link_type, dest_url) = match (text, link.url.strip_prefix("mailto:")) {
+                    (Some(text), _) if text == link.url => (LinkType::Autolink, link.url.as_ref()),
+                    (Some(text), Some(email)) if text == email => (LinkType::Email, email),
+                    _ => (LinkType::Inline, link.url.as_ref()),
+                };
+
+                let tag = Tag::Link {
+                    link_type,
+                    dest_url: CowStr::Borrowed(dest_url),
+                    title: title(&link.title),
+                    id: CowStr::Borrowed(""),
+                };
+
+                self.open(Some(tag), &link.children, Context::Phrasing);
+            }
+            Node::LinkReference(reference) => {
+                let tag = self
+                    .definition(&reference.identifier)
+                    .map(|definition| Tag::Link {
+                        link_type: link_type(&reference.reference_type),
+                        dest_url: borrowed(&definition.url),
+                        title: title(&definition.title),
+                        id: label(&reference.label, &reference.identifier),
+                    });
+
+                self.open(tag, &reference.children, Context::Phrasing);
+            }
+            Node::Image(image) => {
+                let tag = Tag::Image {
+                    link_type: LinkType::Inline,
+                    dest_url: borrowed(&image.url),
+                    title: title(&image.title),
+                    id: CowStr::Borrowed(""),
+                };
+
+                self.leaf(tag, alt(&image.alt));
+            }
+            Node::ImageReference(reference) => match self.definition(&reference.identifier) {
+                Some(definition) => {
+                    let tag = Tag::Image {
+                        link_type: link_type(&reference.reference_type),
+                        dest_url: borrowed(&definition.url),
+                        title: title(&definition.title),
+                        id: label(&reference.label, &reference.identifier),
+                    };
+
+                    self.leaf(tag, alt(&reference.alt));
+                }
+                None => self.pending.extend(alt(&reference.alt)),
+            },
+            Node::Html(html) if context == Context::Phrasing => {
+                self.pending
+                    .push_back(Event::InlineHtml(borrowed(&html.value)));
+            }
+            Node::Html(html) => {
+                let lines = html.value.split_inclusive('\n').map(|line| {
+                    Event::Html(if line.ends_with('\n') {
+                        CowStr::Borrowed(line)
+                    } else {
+                        format!("{}\n", line).into()
+                    })
+                });
+
+                self.leaf(Tag::HtmlBlock, lines.collect::<Vec<_>>());
+            }
+            Node::Table(table) => {
+                let align = table.align.iter().map(|align| match align {
+                    AlignType::Left => Alignment::Left,
+                    AlignType::Right => Alignment::Right,
+                    AlignType::Center => Alignment::Center,
+                    AlignType::None => Alignment::None,
+                });
+
+                self.open(
+                    Some(Tag::Table(align.collect())),
+                    &table.children,
+                    Context::Table,
+                );
+            }
+            Node::TableRow(row) => {
+                // the first row is the head, whose cells pulldown-cmark wraps in no row.
+                let tag = if context == Context::Table && index == 0 {
+                    Tag::TableHead
+                } else {
+                    Tag::TableRow
+                };
+
+                self.open(Some(tag), &row.children, Context::Flow);
+            }
+            Node::TableCell(cell) => {
+                self.open(Some(Tag::TableCell), &cell.children, Context::Phrasing);
+            }
+            Node::FootnoteDefinition(definition) => {
+                let tag = Tag::FootnoteDefinition(label(&definition.label, &definition.identifier));
+
+                self.open(Some(tag), &definition.children, Context::Flow);
+            }
+            Node::FootnoteReference(reference) => {
+                self.pending.push_back(Event::FootnoteReference(label(
+                    &reference.label,
+                    &reference.identifier,
+                )));
+            }
+        }
+    }
+
+    fn definition(&self, identifier: &str) -> Option<&'a Definition<'cx>> {
+        self.definitions
+            .get(&normalize_identifier(identifier))
+            .copied()
+    }
+}
+
+fn borrowed(value: &str) -> CowStr<'_> {
+    CowStr::Borrowed(value)
+}
+
+fn title<'a>(title: &'a Option<Cow<'_, str>>) -> CowStr<'a> {
+    CowStr::Borrowed(title.as_deref().unwrap_or_default())
+}
+
+fn label<'a>(label: &'a Option<Cow<'_, str>>, identifier: &'a str) -> CowStr<'a> {
+    CowStr::Borrowed(label.as_deref().unwrap_or(identifier))
+}
+
+fn alt<'a>(alt: &'a Option<Cow<'_, str>>) -> Option<Event<'a>> {
+    alt.as_deref()
+        .filter(|alt| !alt.is_empty())
+        .map(|alt| Event::Text(CowStr::Borrowed(alt)))
+}
+
+fn link_type(reference_type: &ReferenceType) -> LinkType {
+    match reference_type {
+        ReferenceType::Shortcut => LinkType::Shortcut,
+        ReferenceType::Collapsed => LinkType::Collapsed,
+        ReferenceType::Full => LinkType::Reference,
+    }
+}
+
+/// Builds a document from pulldown-cmark events, see the [module](self) documentation.
+///
+/// Unbalanced streams are tolerated: ends close the innermost open tag whatever their kind,
+/// and tags still open at the end of the stream are closed.
+pub fn from_events<'cx, I>(events: I) -> Document<'cx>
+where
+    I: IntoIterator<Item = Event<'cx>>,
+{
+    let mut builder = Builder {
+        stack: vec![(Open::Document, vec![])],
+    };
+
+    for event in events {
+        builder.event(event);
+    }
+
+    while builder.stack.len() > 1 {
+        builder.close();
+    }
+
+    let (_, children) = builder.stack.pop().expect("the document is never closed");
+
+    Document {
+        children,
+        data: None,
+    }
+}
+
+struct Builder<'cx> {
+    stack: Vec<(Open<'cx>, Vec<Node<'cx>>)>,
+}
+
+/// An open tag, and what its node needs beside children.
+enum Open<'cx> {
+    Document,
+    /// A paragraph of a tight list item, which has no tags.
+    ImplicitParagraph,
+    Paragraph,
+    Heading(HeadingLevel),
+    Blockquote,
+    Code(CodeBlockKind<'cx>),
+    Html,
+    List {
+        start: Option<u64>,
+        loose: bool,
+    },
+    Item {
+        checked: Option<bool>,
+        loose: bool,
+    },
+    FootnoteDefinition(CowStr<'cx>),
+    Table(Vec<Alignment>),
+    TableRow,
+    TableCell,
+    Emphasis,
+    Strong,
+    Delete,
+    Link {
+        link_type: LinkType,
+        url: CowStr<'cx>,
+        title: CowStr<'cx>,
+    },
+    Image {
+        url: CowStr<'cx>,
+        title: CowStr<'cx>,
+    },
+    /// A tag whose content is kept without the wrapper, `flow` if it holds flow content.
+    Transparent {
+        flow: bool,
+    },
+    /// A tag dropped with its content.
+    Skip,
+}
+
+impl<'cx> Open<'cx> {
+    fn is_flow(&self) -> bool {
+        matches!(
+            self,
+            Open::Document
+                | Open::Blockquote
+                | Open::Item { .. }
+                | Open::FootnoteDefinition(_)
+                | Open::Transparent { flow: true }
+        )
+    }
+
+    /// Whether the text of this tag is kept verbatim.
+    fn is_raw(&self) -> bool {
+        matches!(self, Open::Code(_) | Open::Html | Open::Skip)
+    }
+}
+
+impl<'cx> Builder<'cx> {
+    fn top(&mut self) -> &mut (Open<'cx>, Vec<Node<'cx>>) {
+        self.stack.last_mut().expect("the document is never closed")
+    }
+
+    fn event(&mut self, event: Event<'cx>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(_) => {
+                if matches!(self.top().0, Open::ImplicitParagraph) {
+                    self.close();
+                }
+
+                if self.stack.len() > 1 {
+                    self.close();
+                }
+            }
+            Event::Text(text) | Event::Html(text) if self.top().0.is_raw() => self.text(text),
+            Event::Text(text) => {
+                self.phrasing();
+                self.text(text);
+            }
+            Event::Code(value) | Event::InlineMath(value) | Event::DisplayMath(value) => {
+                self.push(Node::InlineCode(InlineCode {
+                    value: value.into(),
+                    data: None,
+                }));
+            }
+            Event::Html(value) => {
+                self.block();
+                self.top().1.push(Node::Html(Html {
+                    value: trim_newline(value.into()),
+                    data: None,
+                }));
+            }
+            Event::InlineHtml(value) => self.push(Node::Html(Html {
+                value: value.into(),
+                data: None,
+            })),
+            Event::FootnoteReference(label) => {
+                self.push(Node::FootnoteReference(FootnoteReference {
+                    identifier: normalize_identifier(&label).into(),
+                    label: Some(label.into()),
+                    data: None,
+                }));
+            }
+            Event::SoftBreak => {
+                self.phrasing();
+                self.text(CowStr::Borrowed("\n"));
+            }
+            Event::HardBreak => self.push(Node::Break(Break { data: None })),
+            Event::Rule => {
+                self.block();
+                self.top()
+                    .1
+                    .push(Node::ThematicBreak(ThematicBreak { data: None }));
+            }
+            Event::TaskListMarker(value) => {
+                let item = self
+                    .stack
+                    .iter_mut()
+                    .rev()
+                    .find_map(|(open, _)| match open {
+                        Open::Item { checked, .. } => Some(checked),
+                        _ => None,
+                    });
+
+                if let Some(checked) = item {
+                    *checked = Some(value);
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'cx>) {
+        let open = match tag {
+            Tag::Emphasis => Open::Emphasis,
+            Tag::Strong => Open::Strong,
+            Tag::Strikethrough => Open::Delete,
+            Tag::Superscript | Tag::Subscript => Open::Transparent { flow: false },
+            Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                ..
+            } => Open::Link {
+                link_type,
+                url: dest_url,
+                title,
+            },
+            Tag::Image {
+                dest_url, title, ..
+            } => Open::Image {
+                url: dest_url,
+                title,
+            },
+            tag => {
+                self.block();
+
+                let open = match tag {
+                    Tag::Paragraph | Tag::DefinitionListTitle => Open::Paragraph,
+                    Tag::Heading { level, .. } => Open::Heading(level),
+                    Tag::BlockQuote(_) => Open::Blockquote,
+                    Tag::CodeBlock(kind) => Open::Code(kind),
+                    Tag::HtmlBlock => Open::Html,
+                    Tag::List(start) => Open::List {
+                        start,
+                        loose: false,
+                    },
+                    Tag::Item => Open::Item {
+                        checked: None,
+                        loose: false,
+                    },
+                    Tag::FootnoteDefinition(label) => Open::FootnoteDefinition(label),
+                    Tag::DefinitionList | Tag::DefinitionListDefinition => {
+                        Open::Transparent { flow: true }
+                    }
+                    Tag::Table(align) => Open::Table(align),
+                    Tag::TableHead | Tag::TableRow => Open::TableRow,
+                    Tag::TableCell => Open::TableCell,
+                    Tag::MetadataBlock(_) => Open::Skip,
+                    _ => unreachable!("phrasing tags are handled above"),
+                };
+
+                // paragraph tags in items are what makes a list loose.
+                if let (Open::Paragraph, (Open::Item { loose, .. }, _)) = (&open, self.top()) {
+                    *loose = true;
+                }
+
+                self.stack.push((open, vec![]));
+
+                return;
+            }
+        };
+
+        self.phrasing();
+        self.stack.push((open, vec![]));
+    }
+
+    /// Closes a paragraph of a tight item, before a block.
+    fn block(&mut self) {
+        if matches!(self.top().0, Open::ImplicitParagraph) {
+            self.close();
+        }
+    }
+
+    /// Opens a paragraph for phrasing content in a flow container.
+    fn phrasing(&mut self) {
+        if self.top().0.is_flow() {
+            self.stack.push((Open::ImplicitParagraph, vec![]));
+        }
+    }
+
+    fn push(&mut self, node: Node<'cx>) {
+        self.phrasing();
+        self.top().1.push(node);
+    }
+
+    fn text(&mut self, value: CowStr<'cx>) {
+        let children = &mut self.top().1;
+
+        match children.last_mut() {
+            Some(Node::Text(text)) => text.value.to_mut().push_str(&value),
+            _ => children.push(Node::Text(Text {
+                value: value.into(),
+                data: None,
+            })),
+        }
+    }
+
+    fn close(&mut self) {
+        let (open, children) = self.stack.pop().expect("the document is never closed");
+
+        let node = match open {
+            Open::Document | Open::Transparent { .. } => {
+                for mut child in children {
+                    match &mut child {
+                        Node::Text(text) => {
+                            self.text(CowStr::from(core::mem::take(&mut text.value)))
+                        }
+                        _ => self.top().1.push(child),
+                    }
+                }
+
+                return;
+            }
+            Open::Skip => return,
+            Open::ImplicitParagraph | Open::Paragraph => Node::Paragraph(Paragraph {
+                children,
+                data: None,
+            }),
+            Open::Heading(level) => Node::Heading(Heading {
+                children,
+                depth: level as u8,
+                data: None,
+            }),
+            Open::Blockquote => Node::Blockquote(Blockquote {
+                children,
+                data: None,
+            }),
+            Open::Code(kind) => {
+                let (lang, meta) = match kind {
+                    CodeBlockKind::Fenced(info) => info_parts(info.into()),
+                    CodeBlockKind::Indented => (None, None),
+                };
+
+                Node::from(Code {
+                    value: trim_newline(literal(children)),
+                    lang,
+                    meta,
+                    data: None,
+                })
+            }
+            Open::Html => Node::Html(Html {
+                value: trim_newline(literal(children)),
+                data: None,
+            }),
+            Open::List { start, loose } => Node::List(List {
+                children,
+                ordered: Some(start.is_some()),
+                start: start.map(|start| u32::try_from(start).unwrap_or(u32::MAX)),
+                spread: Some(loose),
+                data: None,
+            }),
+            Open::Item { checked, loose } => {
+                if let (Open::List { loose: list, .. }, _) = self.top() {
+                    *list |= loose;
+                }
+
+                Node::ListItem(ListItem {
+                    spread: Some(false),
+                    children,
+                    checked,
+                    data: None,
+                })
+            }
+            Open::FootnoteDefinition(label) => Node::from(FootnoteDefinition {
+                children,
+                identifier: normalize_identifier(&label).into(),
+                label: Some(label.into()),
+                data: None,
+            }),
+            Open::Table(align) => Node::Table(Table {
+                children,
+                align: align
+                    .into_iter()
+                    .map(|align| match align {
+                        Alignment::Left => AlignType::Left,
+                        Alignment::Right => AlignType::Right,
+                        Alignment::Center => AlignType::Center,
+                        Alignment::None => AlignType::None,
+                    })
+                    .collect(),
+                data: None,
+            }),
+            Open::TableRow => Node::TableRow(TableRow {
+                children,
+                data: None,
+            }),
+            Open::TableCell => Node::TableCell(TableCell {
+                children,
+                data: None,
+            }),
+            Open::Emphasis => Node::Emphasis(Emphasis {
+                children,
+                data: None,
+            }),
+            Open::Strong => Node::Strong(Strong {
+                children,
+                data: None,
+            }),
+            Open::Delete => Node::Delete(Delete {
+                children,
+                data: None,
+            }),
+            Open::Link {
+                link_type,
+                url,
+                title,
+            } => Node::from(Link {
+                children,
+                url: match link_type {
+                    LinkType::Email => format!("mailto:{}", url).into(),
+                    _ => url.into(),
+                },
+                title: (!title.is_empty()).then(|| title.into()),
+                data: None,
+            }),
+            Open::Image { url, title } => {
+                let alt = children
+                    .iter()
+                    .map(|child| to_text(child, &TextOptions::default()))
+                    .collect::<String>();
+
+                Node::from(Image {
+                    url: url.into(),
+                    title: (!title.is_empty()).then(|| title.into()),
+                    alt: Some(alt.into()),
+                    data: None,
+                })
+            }
+        };
+
+        self.top().1.push(node);
+    }
+}
+
+/// Returns the text collected by a code or html block.
+fn literal(mut children: Vec<Node<'_>>) -> Cow<'_, str> {
+    match children.first_mut() {
+        Some(Node::Text(text)) => core::mem::take(&mut text.value),
+        _ => Cow::Borrowed(""),
+    }
+}
+
+fn trim_newline(value: Cow<'_, str>) -> Cow<'_, str> {
+    match value {
+        Cow::Borrowed(value) => Cow::Borrowed(value.strip_suffix('\n').unwrap_or(value)),
+        Cow::Owned(mut value) => {
+            if value.ends_with('\n') {
+                value.pop();
+            }
+
+            Cow::Owned(value)
+        }
+    }
+}
+
+/// Splits an info string into its language and meta.
+fn info_parts(info: Cow<'_, str>) -> (Option<Cow<'_, str>>, Option<Cow<'_, str>>) {
+    let part = |range: Range<usize>| {
+        (!range.is_empty()).then(|| match &info {
+            Cow::Borrowed(info) => Cow::Borrowed(&info[range]),
+            Cow::Owned(info) => Cow::Owned(info[range].into()),
+        })
+    };
+
+    let start = info.len() - info.trim_start().len();
+    let end = info.trim_end().len();
+
+    let lang = info[start..end]
+        .find([' ', '\t'])
+        .map_or(end, |index| start + index);
+
+    let meta = end - info[lang..end].trim_start().len();
+
+    (part(start..lang), part(meta..end))
+}
@@ -0,0 +1,2604 @@
+use alloc::{
+    borrow::ToOwned,
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use core::{
+    cell::{Cell, RefCell},
+    ops::Range,
+};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    ast::{
+        comments::is_marker,
+        links::for_each_with_path,
+        spans::Spans,
+        style::{NodeStyle, Style},
+        validate::is_phrasing,
+        *,
+    },
+    parser::{has_scheme, starts_html_block},
+    unicode::{is_unicode_punctuation, is_unicode_whitespace},
+};
+
+/// Options of [`to_markdown`].
+///
+/// Marker fields only accept the chars listed on each field,
+/// any other char falls back to the default.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SerializeOptions {
+    /// Marker of unordered list items: `*` (default), `-` or `+`.
+    pub bullet: char,
+    /// Marker of an unordered list following a sibling list with [`bullet`](Self::bullet),
+    /// which would otherwise continue it: `*`, `-` or `+`, other than `bullet`. Defaults to
+    /// `-`, or `*` when `bullet` is `-`.
+    pub bullet_other: char,
+    /// Delimiter of ordered list items: `.` (default) or `)`. An ordered list following a
+    /// sibling list with the same delimiter uses the other one.
+    pub delimiter: char,
+    /// Numbers of ordered list items, defaults to [`ListNumbering::Preserve`].
+    pub numbering: ListNumbering,
+    /// Marker of emphasis and strong: `*` (default) or `_`.
+    pub emphasis: char,
+    /// Marker of fenced code: `` ` `` (default) or `~`.
+    pub fence: char,
+    /// Use setext underlines for headings of depth 1 and 2 instead of ATX `#` prefixes.
+    pub setext: bool,
+    /// Write table cells without padding them to the width of their column, so that editing a
+    /// cell doesn't change the other lines of the table.
+    pub compact_tables: bool,
+    /// Line layout of paragraphs, defaults to [`Wrap::Preserve`].
+    pub wrap: Wrap,
+    /// Line ending of the output, defaults to [`LineEnding::Lf`].
+    pub line_ending: LineEnding,
+    /// Text of the comment marking the next block as left as written, such as `md-ignore` for
+    /// `<!-- md-ignore -->`, defaults to `None`, see [`to_markdown_with_source`].
+    pub ignore: Option<String>,
+}
+
+impl SerializeOptions {
+    /// Set the text of the comment marking the next block as left as written, see
+    /// [`ignore`](Self::ignore).
+    ///
+    /// ```
+    /// use markdown_rs::to_markdown::SerializeOptions;
+    ///
+    /// let options = SerializeOptions::default().ignore_directive("prettier-ignore");
+    ///
+    /// assert_eq!(options.ignore.as_deref(), Some("prettier-ignore"));
+    /// ```
+    pub fn ignore_directive(mut self, directive: &str) -> Self {
+        self.ignore = Some(directive.to_owned());
+        self
+    }
+}
+
+/// Line ending written by [`to_markdown`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum LineEnding {
+    /// `\n`.
+    #[default]
+    Lf,
+    /// `\r\n`.
+    CrLf,
+    /// The line ending of the source, as recorded in the [`Style`] given to
+    /// [`to_markdown_with_style`], `\n` if it isn't known.
+    Preserve,
+}
+
+impl LineEnding {
+    /// Returns the line ending of the first line of `source`: [`LineEnding::CrLf`] if it ends
+    /// with `\r\n`, [`LineEnding::Lf`] otherwise, including for bare `\r` line endings. `None`
+    /// if `source` has a single line.
+    pub fn detect(source: &str) -> Option<LineEnding> {
+        let end = source.find(['\n', '\r'])?;
+
+        if source[end..].starts_with("\r\n") {
+            Some(LineEnding::CrLf)
+        } else {
+            Some(LineEnding::Lf)
+        }
+    }
+}
+
+/// How [`to_markdown`] numbers the items of ordered lists, from the [`start`](List::start) of
+/// their list.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum ListNumbering {
+    /// Count up from the start: `1.`, `2.`, `3.`.
+    Number,
+    /// Repeat the start: `1.`, `1.`, `1.`.
+    One,
+    /// The numbering of the source, as recorded in the [`Style`] given to
+    /// [`to_markdown_with_style`], [`ListNumbering::Number`] if it isn't known.
+    #[default]
+    Preserve,
+}
+
+/// How [`to_markdown`] lays out the lines of paragraphs.
+///
+/// Only paragraphs are reflowed: headings, tables, code and html keep their lines. Line breaks
+/// only replace whitespace of text, so inline code, html, autolinks, link destinations and
+/// words are never broken.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Wrap {
+    /// Keep the line breaks of the text as they are.
+    #[default]
+    Preserve,
+    /// Write each paragraph on one line, up to its hard breaks.
+    NoWrap,
+    /// Reflow paragraphs to lines of at most this many chars, the prefixes of block quotes and
+    /// list items included. A word longer than the width gets a line of its own.
+    Width(usize),
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        SerializeOptions {
+            bullet: '*',
+            bullet_other: '-',
+            delimiter: '.',
+            numbering: ListNumbering::Preserve,
+            emphasis: '*',
+            fence: '`',
+            setext: false,
+            compact_tables: false,
+            wrap: Wrap::Preserve,
+            line_ending: LineEnding::Lf,
+            ignore: None,
+        }
+    }
+}
+
+impl<'cx> Document<'cx> {
+    /// Serialize this document to markdown text, see [`to_markdown`].
+    pub fn to_markdown(&self, options: &SerializeOptions) -> String {
+        to_markdown(self, options)
+    }
+
+    /// Serialize this document to markdown text following `style`, see
+    /// [`to_markdown_with_style`].
+    pub fn to_markdown_with_style(&self, options: &SerializeOptions, style: &Style) -> String {
+        to_markdown_with_style(self, options, style)
+    }
+
+    /// Serialize this document to markdown text following `style`, ignored blocks copied from
+    /// `source`, see [`to_markdown_with_source`].
+    pub fn to_markdown_with_source(
+        &self,
+        options: &SerializeOptions,
+        style: &Style,
+        spans: &Spans,
+        source: &str,
+    ) -> String {
+        to_markdown_with_source(self, options, style, spans, source)
+    }
+}
+
+/// Serialize `document` back into markdown text.
+///
+/// Characters of text values that would change meaning are escaped where they would, such as
+/// `1.` at the start of a line or `*` next to a word, ordered lists are renumbered from their
+/// `start`. Whitespace ending a line of text has no markdown form, it is lost. Empty emphasis, strong and delete nodes
+/// are skipped, as [`normalize`](crate::ast::normalize()) would drop them.
+pub fn to_markdown(document: &Document, options: &SerializeOptions) -> String {
+    to_markdown_with_style(document, options, &Style::new())
+}
+
+/// Serialize `document` back into markdown text, writing the nodes styled by `style` with the
+/// markup it records rather than the one of `options`.
+///
+/// A recorded marker is still changed where it would not read back as the same tree, such as
+/// sibling lists with the same bullet, and multi-line heading content still needs an
+/// underline. Fenced code keeps the indent of its opening fence, which is also added to its
+/// lines.
+pub fn to_markdown_with_style(
+    document: &Document,
+    options: &SerializeOptions,
+    style: &Style,
+) -> String {
+    serialize(document, options, style, None)
+}
+
+/// Serialize `document` back into markdown text following `style`, like
+/// [`to_markdown_with_style`], writing each top-level block that follows an
+/// [`ignore`](SerializeOptions::ignore) comment as it is in `source`.
+///
+/// An ignore comment is an html block made of a single comment whose trimmed text is the
+/// directive. The ignored block is still in the tree, for linters and other tools, only its
+/// output is the source: `spans` and `source` are those of the parse that produced `document`,
+/// and a block without a span is serialized as usual. Ignore comments only apply to top-level
+/// blocks, whose source holds no prefix of a container.
+///
+/// ```
+/// use markdown_rs::{ast::style::Style, parser::Parser, to_markdown::SerializeOptions};
+///
+/// let source = "<!-- md-ignore -->\n\n| a |  b |\n|-|-|\n\n| a |  b |\n|-|-|\n";
+///
+/// let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+///
+/// let options = SerializeOptions::default().ignore_directive("md-ignore");
+///
+/// assert_eq!(
+///     document.to_markdown_with_source(&options, &Style::new(), &spans, source),
+///     "<!-- md-ignore -->\n\n| a |  b |\n|-|-|\n\n| a   | b   |\n| --- | --- |\n"
+/// );
+/// ```
+pub fn to_markdown_with_source(
+    document: &Document,
+    options: &SerializeOptions,
+    style: &Style,
+    spans: &Spans,
+    source: &str,
+) -> String {
+    serialize(document, options, style, Some((spans, source)))
+}
+
+fn serialize(
+    document: &Document,
+    options: &SerializeOptions,
+    style: &Style,
+    source: Option<(&Spans, &str)>,
+) -> String {
+    let mut serializer = Serializer::new(options);
+
+    if let (Some((spans, source)), Some(directive)) = (source, &options.ignore) {
+        let directive = directive.trim();
+
+        for (index, pair) in document.children.windows(2).enumerate() {
+            let (Node::Html(html), node) = (&pair[0], &pair[1]) else {
+                continue;
+            };
+
+            let Some(text) = spans
+                .get(&[index + 1])
+                .and_then(|span| source.get(span))
+                .filter(|_| is_marker(html, directive))
+            else {
+                continue;
+            };
+
+            // the line endings of the output are set once it is written.
+            let text = text.trim_end_matches(['\n', '\r']);
+
+            serializer.verbatim.insert(
+                address(node),
+                text.replace("\r\n", "\n").replace('\r', "\n"),
+            );
+        }
+    }
+
+    if !style.is_empty() {
+        for_each_with_path(document, |path, node| {
+            if let Some(style) = style.get(path).filter(|style| style.applies_to(node)) {
+                serializer.styles.insert(address(node), *style);
+            }
+
+            if let (Node::List(_), Some(numbering)) = (node, style.numbering(path)) {
+                serializer.numberings.insert(address(node), numbering);
+            }
+        });
+    }
+
+    let mut markdown = serializer.flow(&document.children, false);
+
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+
+    let line_ending = match options.line_ending {
+        LineEnding::Preserve => style.line_ending().unwrap_or_default(),
+        line_ending => line_ending,
+    };
+
+    if line_ending == LineEnding::CrLf {
+        // values of built nodes may hold any line ending.
+        if markdown.contains('\r') {
+            markdown = markdown.replace("\r\n", "\n").replace('\r', "\n");
+        }
+
+        markdown = markdown.replace('\n', "\r\n");
+    }
+
+    markdown
+}
+
+struct Serializer {
+    bullet: char,
+    bullet_other: char,
+    delimiter: char,
+    numbering: ListNumbering,
+    emphasis: char,
+    emphasis_other: char,
+    fence: char,
+    setext: bool,
+    compact_tables: bool,
+    wrap: Wrap,
+    /// Styles of the serialized nodes, by node address.
+    styles: BTreeMap<usize, NodeStyle>,
+    /// Numberings of the serialized ordered lists, by node address.
+    numberings: BTreeMap<usize, ListNumbering>,
+    /// Width of the prefixes of the lines of the block being serialized.
+    indent: Cell<usize>,
+    /// Values of reflowed text nodes, by node address.
+    reflowed: RefCell<BTreeMap<usize, String>>,
+    /// Source of the ignored blocks, by node address.
+    verbatim: BTreeMap<usize, String>,
+}
+
+impl Serializer {
+    fn new(options: &SerializeOptions) -> Self {
+        let default = SerializeOptions::default();
+
+        let bullet = match options.bullet {
+            c @ ('*' | '-' | '+') => c,
+            _ => default.bullet,
+        };
+
+        let emphasis = match options.emphasis {
+            c @ ('*' | '_') => c,
+            _ => default.emphasis,
+        };
+
+        let bullet_other = match options.bullet_other {
+            c @ ('*' | '-' | '+') if c != bullet => c,
+            _ if bullet == '-' => '*',
+            _ => '-',
+        };
+
+        Serializer {
+            bullet,
+            bullet_other,
+            delimiter: match options.delimiter {
+                c @ ('.' | ')') => c,
+                _ => default.delimiter,
+            },
+            numbering: options.numbering,
+            emphasis,
+            emphasis_other: if emphasis == '_' { '*' } else { '_' },
+            fence: match options.fence {
+                c @ ('`' | '~') => c,
+                _ => default.fence,
+            },
+            setext: options.setext,
+            compact_tables: options.compact_tables,
+            wrap: options.wrap,
+            styles: BTreeMap::new(),
+            numberings: BTreeMap::new(),
+            indent: Cell::new(0),
+            reflowed: RefCell::default(),
+            verbatim: BTreeMap::new(),
+        }
+    }
+
+    fn style(&self, node: &Node) -> Option<NodeStyle> {
+        self.styles.get(&address(node)).copied()
+    }
+
+    /// Serialize flow content, blocks are separated by blank lines unless `tight`.
+    fn flow(&self, children: &[Node], tight: bool) -> String {
+        let mut out = String::new();
+
+        // marker of the previous sibling if it is a list.
+        let mut prev_marker = None;
+
+        for (index, node) in children.iter().enumerate() {
+            let prev = index.checked_sub(1).map(|index| &children[index]);
+
+            let marker = match node {
+                Node::List(list) => Some(self.list_marker(node, list, prev_marker)),
+                _ => None,
+            };
+
+            prev_marker = marker;
+
+            let block = match self.verbatim.get(&address(node)) {
+                Some(verbatim) => verbatim.clone(),
+                None => self.block(node, marker),
+            };
+
+            if let Some(prev) = prev {
+                // setext headings start like a paragraph, escaping keeps `#` off their first line.
+                // indented code starts with spaces only, as does a lazy paragraph line.
+                let continues = match node {
+                    Node::Heading(_) => !block.starts_with('#'),
+                    Node::Code(_) => block.starts_with("    "),
+                    _ => false,
+                };
+
+                if tight && can_interrupt_paragraph(prev, node, continues) {
+                    out.push('\n');
+                } else if tight
+                    && matches!(prev, Node::Blockquote(_))
+                    && !matches!(node, Node::Blockquote(_))
+                {
+                    // an empty quoted line ends the paragraph without loosening the list.
+                    out.push_str("\n>\n");
+                } else {
+                    out.push_str("\n\n");
+                }
+            }
+
+            out.push_str(&block);
+        }
+
+        out
+    }
+
+    /// Serialize flow content `node`, lists use `marker` if given.
+    fn block(&self, node: &Node, marker: Option<char>) -> String {
+        match node {
+            Node::Paragraph(paragraph) => {
+                let width = match self.wrap {
+                    Wrap::Preserve => None,
+                    Wrap::NoWrap => Some(usize::MAX),
+                    Wrap::Width(width) => Some(width.saturating_sub(self.indent.get()).max(1)),
+                };
+
+                if let Some(width) = width {
+                    self.reflow(&paragraph.children, width);
+                }
+
+                self.phrasing(&paragraph.children, Scope::Flow)
+            }
+            Node::Heading(heading) => self.heading(heading, self.style(node)),
+            Node::ThematicBreak(_) => "***".to_owned(),
+            Node::Blockquote(blockquote) => {
+                let content = self.indented(2, || self.flow(&blockquote.children, false));
+
+                prefix_lines(&content, "> ", "> ", ">")
+            }
+            Node::List(list) => self.list(
+                node,
+                list,
+                marker.unwrap_or_else(|| self.list_marker(node, list, None)),
+            ),
+            Node::ListItem(item) => self.list_item(item, &self.bullet.to_string()),
+            Node::Code(code) => self.code(code, self.style(node)),
+            Node::Definition(definition) => {
+                let mut out = format!(
+                    "[{}]: {}",
+                    escape_label(
+                        definition
+                            .label
+                            .as_deref()
+                            .unwrap_or(&definition.identifier)
+                    ),
+                    destination(&definition.url)
+                );
+
+                if let Some(title) = &definition.title {
+                    out.push(' ');
+                    out.push_str(&title_string(title));
+                }
+
+                out
+            }
+            Node::FootnoteDefinition(definition) => {
+                let content = self.indented(4, || self.flow(&definition.children, false));
+
+                let first = format!(
+                    "[^{}]: ",
+                    escape_label(
+                        definition
+                            .label
+                            .as_deref()
+                            .unwrap_or(&definition.identifier)
+                    )
+                );
+
+                prefix_lines(&content, &first, "    ", "")
+            }
+            Node::Table(table) => self.table(table),
+            Node::Html(html) => html.value.to_string(),
+            Node::Document(document) => self.flow(&document.children, false),
+            // extension nodes have no markdown form of their own, only their content is kept.
+            Node::Custom(custom)
+                if custom
+                    .children
+                    .iter()
+                    .all(|child| is_phrasing(child.kind())) =>
+            {
+                self.phrasing(&custom.children, Scope::Flow)
+            }
+            Node::Custom(custom) => self.flow(&custom.children, false),
+            // phrasing content misplaced in flow content.
+            node => self.phrasing(core::slice::from_ref(node), Scope::Flow),
+        }
+    }
+
+    /// Runs `f` with the lines of the serialized content prefixed by `width` more chars.
+    fn indented<T>(&self, width: usize, f: impl FnOnce() -> T) -> T {
+        let indent = self.indent.replace(self.indent.get() + width);
+
+        let out = f();
+
+        self.indent.set(indent);
+
+        out
+    }
+
+    fn heading(&self, heading: &Heading, style: Option<NodeStyle>) -> String {
+        // lines of setext content are read like paragraph lines.
+        let content = self.phrasing(&heading.children, Scope::Flow);
+
+        let setext = match style {
+            Some(NodeStyle::Heading { setext }) => setext,
+            _ => self.setext,
+        };
+
+        // raw html on the first line could start a block of its own instead of the heading,
+        // ATX headings keep it inline.
+        let first = content.split('\n').next().unwrap_or_default();
+
+        // ATX headings are one line, so multiline content needs an underline.
+        if (setext || content.contains('\n'))
+            && heading.depth < 3
+            && !content.is_empty()
+            && !starts_block(first)
+        {
+            let underline = if heading.depth == 1 { '=' } else { '-' };
+
+            let width = content
+                .lines()
+                .last()
+                .map(|line| line.chars().count())
+                .unwrap_or_default()
+                .max(3);
+
+            return format!("{}\n{}", content, underline.to_string().repeat(width));
+        }
+
+        let content = self
+            .phrasing(&heading.children, Scope::Heading)
+            .replace('\n', " ");
+
+        let depth = heading
+            .depth
+            .clamp(*Heading::DEPTHS.start(), *Heading::DEPTHS.end());
+
+        let pounds = "#".repeat(usize::from(depth));
+
+        if content.is_empty() {
+            return pounds;
+        }
+
+        // a trailing pound sequence would be read as the closing sequence.
+        let content = match content.strip_suffix('#') {
+            Some(rest) if rest.is_empty() || rest.ends_with([' ', '\t', '#']) => {
+                let trimmed = rest.trim_end_matches('#');
+
+                format!("{}\\{}", trimmed, &content[trimmed.len()..])
+            }
+            _ => content,
+        };
+
+        format!("{} {}", pounds, content)
+    }
+
+    /// Returns the bullet or delimiter of `list`: its styled one or the preferred one, unless
+    /// the previous sibling list used it already, they would then merge into one.
+    fn list_marker(&self, node: &Node, list: &List, prev: Option<char>) -> char {
+        let (preferred, other) = if list.ordered == Some(true) {
+            (
+                self.delimiter,
+                if self.delimiter == '.' { ')' } else { '.' },
+            )
+        } else {
+            (self.bullet, self.bullet_other)
+        };
+
+        let marker = match self.style(node) {
+            Some(NodeStyle::List(c)) if list.ordered == Some(true) && matches!(c, '.' | ')') => c,
+            Some(NodeStyle::List(c))
+                if list.ordered != Some(true) && matches!(c, '*' | '-' | '+') =>
+            {
+                c
+            }
+            _ => preferred,
+        };
+
+        match prev {
+            Some(prev) if prev == marker && marker == preferred => other,
+            Some(prev) if prev == marker => preferred,
+            _ => marker,
+        }
+    }
+
+    fn list(&self, node: &Node, list: &List, marker: char) -> String {
+        let ordered = list.ordered == Some(true);
+
+        let start = list.start.unwrap_or(1) as usize;
+
+        let numbering = match self.numbering {
+            ListNumbering::Preserve => self
+                .numberings
+                .get(&address(node))
+                .copied()
+                .unwrap_or(ListNumbering::Number),
+            numbering => numbering,
+        };
+
+        let separator = if list.spread == Some(true) {
+            "\n\n"
+        } else {
+            "\n"
+        };
+
+        let mut out = String::new();
+
+        for (index, item) in list.children.iter().enumerate() {
+            if index > 0 {
+                out.push_str(separator);
+            }
+
+            let marker = if ordered {
+                let number = match numbering {
+                    ListNumbering::One => start,
+                    _ => start + index,
+                };
+
+                format!("{}{}", number, marker)
+            } else {
+                marker.to_string()
+            };
+
+            match item {
+                Node::ListItem(item) => out.push_str(&self.list_item(item, &marker)),
+                node => {
+                    let content = self.block(node, None);
+
+                    out.push_str(&prefix_lines(&content, &format!("{} ", marker), "", ""));
+                }
+            }
+        }
+
+        out
+    }
+
+    fn list_item(&self, item: &ListItem, marker: &str) -> String {
+        let mut content = self.indented(marker.len() + 1, || {
+            self.flow(&item.children, item.spread != Some(true))
+        });
+
+        // `* ***` is a thematic break of its own rather than an item holding one.
+        if marker == "*" && content.starts_with("***") {
+            content.replace_range(..3, "___");
+        }
+
+        if let Some(checked) = item.checked {
+            let check = if checked { "[x]" } else { "[ ]" };
+
+            content = if content.is_empty() {
+                check.to_owned()
+            } else {
+                format!("{} {}", check, content)
+            };
+        }
+
+        let indent = " ".repeat(marker.len() + 1);
+
+        // `* * *`, an item holding an item holding an empty item, is a thematic break, and
+        // whitespace after the marker would move the content indent past leading whitespace of
+        // raw html: the content goes on the next line instead.
+        let first = content.split('\n').next().unwrap_or_default();
+
+        let leading = item.checked.is_none()
+            && matches!(item.children.first(), Some(Node::Html(html)) if html.value.starts_with([' ', '\t']));
+
+        if leading || is_thematic_break(&format!("{} {}", marker, first)) {
+            return format!(
+                "{}\n{}",
+                marker,
+                prefix_lines(&content, &indent, &indent, "")
+            );
+        }
+
+        prefix_lines(&content, &format!("{} ", marker), &indent, "")
+    }
+
+    fn code(&self, code: &Code, style: Option<NodeStyle>) -> String {
+        let fence = match style {
+            Some(NodeStyle::Code(fence)) => fence,
+            _ => None,
+        };
+
+        // indented code has no info string, and loses leading and trailing blank lines.
+        if matches!(style, Some(NodeStyle::Code(None)))
+            && code.lang.is_none()
+            && code
+                .value
+                .lines()
+                .next()
+                .is_some_and(|line| !line.trim().is_empty())
+            && code
+                .value
+                .lines()
+                .last()
+                .is_some_and(|line| !line.trim().is_empty())
+        {
+            return prefix_lines(&code.value, "    ", "    ", "");
+        }
+
+        let info = match (&code.lang, &code.meta) {
+            (Some(lang), Some(meta)) => format!("{} {}", lang, meta),
+            (Some(lang), None) => lang.to_string(),
+            // meta without lang can't be written without turning into lang.
+            (None, _) => String::new(),
+        };
+
+        let info = escape_info(&info);
+
+        let (marker, size, indent) = match fence {
+            Some(fence) => (fence.marker, fence.size, fence.indent.min(3)),
+            None => (self.fence, 3, 0),
+        };
+
+        // backtick fences can't have backticks in their info string.
+        let marker = if marker == '`' && info.contains('`') {
+            '~'
+        } else {
+            marker
+        };
+
+        let longest = longest_run(&code.value, marker);
+
+        let fence = format!(
+            "{}{}",
+            " ".repeat(indent),
+            marker.to_string().repeat(size.max(longest + 1).max(3))
+        );
+
+        // an info string starting with the marker would lengthen the opening fence.
+        let info = if info.starts_with(marker) {
+            format!(" {}", info)
+        } else {
+            info
+        };
+
+        if code.value.is_empty() {
+            format!("{}{}\n{}", fence, info, fence)
+        } else {
+            // the parser strips up to the indent of the opening fence from every line.
+            let indent = " ".repeat(indent);
+
+            let value = prefix_lines(&code.value, &indent, &indent, "");
+
+            format!("{}{}\n{}\n{}", fence, info, value, fence)
+        }
+    }
+
+    fn table(&self, table: &Table) -> String {
+        let rows = table
+            .children
+            .iter()
+            .map(|row| {
+                row.children()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|cell| {
+                        let children = cell.children().unwrap_or_default();
+
+                        // the parser unescapes pipes before reading cells, in code spans too.
+                        self.phrasing(children, Scope::TableCell)
+                            .replace('\n', " ")
+                            .replace('|', "\\|")
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let columns = rows
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or_default()
+            .max(table.align.len());
+
+        let align = |column| table.align.get(column).unwrap_or(&AlignType::None);
+
+        // user-perceived characters, wide ones count as one.
+        let widths = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|cells| cells.get(column))
+                    .map(|cell| cell.graphemes(true).count())
+                    .max()
+                    .unwrap_or_default()
+                    .max(3)
+            })
+            .collect::<Vec<_>>();
+
+        let row_string = |cells: &[String]| {
+            let mut out = String::from("|");
+
+            for (column, width) in widths.iter().enumerate() {
+                let cell = cells.get(column).map(String::as_str).unwrap_or_default();
+
+                let pad = if self.compact_tables {
+                    0
+                } else {
+                    width.saturating_sub(cell.graphemes(true).count())
+                };
+
+                let before = match align(column) {
+                    AlignType::Right => pad,
+                    AlignType::Center => pad / 2,
+                    AlignType::Left | AlignType::None => 0,
+                };
+
+                out.push(' ');
+                out.push_str(&" ".repeat(before));
+                out.push_str(cell);
+                out.push_str(&" ".repeat(pad - before));
+                out.push_str(" |");
+            }
+
+            out
+        };
+
+        let delimiters = widths
+            .iter()
+            .enumerate()
+            .map(|(column, width)| {
+                let width = if self.compact_tables { 3 } else { *width };
+
+                let (left, right) = match align(column) {
+                    AlignType::Left => (":", ""),
+                    AlignType::Right => ("", ":"),
+                    AlignType::Center => (":", ":"),
+                    AlignType::None => ("", ""),
+                };
+
+                format!(
+                    "{}{}{}",
+                    left,
+                    "-".repeat(width - left.len() - right.len()),
+                    right
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut lines = vec![
+            row_string(rows.first().map(Vec::as_slice).unwrap_or_default()),
+            row_string(&delimiters),
+        ];
+
+        lines.extend(rows.iter().skip(1).map(|row| row_string(row)));
+
+        lines.join("\n")
+    }
+
+    fn phrasing(&self, children: &[Node], scope: Scope) -> String {
+        let mut out = String::new();
+
+        for (index, node) in children.iter().enumerate() {
+            let at_line_start = out.is_empty() || out.ends_with('\n');
+
+            let around = (
+                out.chars().next_back(),
+                children.get(index + 1).and_then(leading_char),
+            );
+
+            let piece = self.inline(node, scope, at_line_start, around);
+
+            // raw html starting a continuation line would start an html block, indented it is
+            // paragraph text and loses the indent when parsed.
+            if scope == Scope::Flow
+                && out.ends_with('\n')
+                && matches!(node, Node::Html(_))
+                && starts_html_block(&piece)
+            {
+                out.push_str("    ");
+            }
+
+            // `]:` at the start of a paragraph would be read as a definition.
+            if piece.starts_with(':') && out.ends_with(']') {
+                out.push('\\');
+            }
+
+            // `!` followed by a bracket opens an image.
+            if piece.starts_with('[') && out.ends_with('!') && !out.ends_with("\\!") {
+                out.pop();
+                out.push_str("\\!");
+            }
+
+            out.push_str(&piece);
+        }
+
+        out
+    }
+
+    /// Lays out the lines of the phrasing content `children` of a paragraph in `width` chars:
+    /// records the values of its text nodes with their whitespace replaced by line endings or
+    /// spaces in [`Serializer::reflowed`].
+    fn reflow(&self, children: &[Node], width: usize) {
+        let mut layout = Layout {
+            width,
+            ..Default::default()
+        };
+
+        self.lay_out(children, &mut layout);
+
+        layout.commit();
+
+        let mut reflowed = self.reflowed.borrow_mut();
+
+        for (address, value, runs) in layout.texts {
+            let mut out = String::with_capacity(value.len());
+            let mut end = 0;
+
+            for (range, run) in runs {
+                out.push_str(&value[end..range.start]);
+
+                match run {
+                    Run::Break => out.push('\n'),
+                    Run::Drop => {}
+                    Run::Keep => out.push_str(&value[range.clone()].replace('\n', " ")),
+                }
+
+                end = range.end;
+            }
+
+            out.push_str(&value[end..]);
+
+            if out != value {
+                reflowed.insert(address, out);
+            }
+        }
+    }
+
+    /// Feeds the words, whitespace and line endings of `children` to `layout`.
+    fn lay_out<'a>(&self, children: &'a [Node], layout: &mut Layout<'a>) {
+        for node in children {
+            match node {
+                Node::Text(text) => {
+                    let value = text.value.as_ref();
+
+                    layout.texts.push((address(node), value, vec![]));
+
+                    let text = layout.texts.len() - 1;
+
+                    let mut start = 0;
+
+                    for (index, c) in value.char_indices() {
+                        if c != ' ' && c != '\n' {
+                            continue;
+                        }
+
+                        layout.word(&value[start..index]);
+
+                        // a run of whitespace is one break opportunity.
+                        match layout.spaces.last_mut() {
+                            Some((last, range)) if *last == text && range.end == index => {
+                                range.end = index + 1;
+                            }
+                            _ => layout.space(text, index..index + 1),
+                        }
+
+                        start = index + 1;
+                    }
+
+                    layout.word(&value[start..]);
+                }
+                Node::Emphasis(_) | Node::Strong(_) | Node::Delete(_) => {
+                    let children = node.children().unwrap_or_default();
+
+                    if self.phrasing(children, Scope::Flow).is_empty() {
+                        continue;
+                    }
+
+                    let marker = if matches!(node, Node::Emphasis(_)) {
+                        1
+                    } else {
+                        2
+                    };
+
+                    layout.glue(marker, marker);
+                    self.lay_out(children, layout);
+                    layout.glue(marker, marker);
+                }
+                Node::Link(link) if autolink(link).is_none() => {
+                    let full = self.inline(node, Scope::Flow, false, (None, None));
+                    let content = self.phrasing(&link.children, Scope::Flow);
+
+                    // `[`, then `](destination "title")` sticks to the last word.
+                    let suffix = full.graphemes(true).count() - content.graphemes(true).count() - 1;
+
+                    layout.glue(1, 1);
+                    self.lay_out(&link.children, layout);
+                    layout.glue(suffix, suffix);
+                }
+                Node::Custom(custom) => self.lay_out(&custom.children, layout),
+                Node::Break(_) => {
+                    layout.glue(1, 1);
+                    layout.line_ending(0);
+                }
+                node => {
+                    let piece = self.inline(node, Scope::Flow, false, (None, None));
+
+                    // html and code fences at the start of a line would start a block.
+                    if matches!(node, Node::Html(_)) || piece.starts_with("```") {
+                        layout.keep_together();
+                    }
+
+                    let first = piece.split('\n').next().unwrap_or_default();
+
+                    let first = first.graphemes(true).count();
+
+                    layout.glue(first, first);
+
+                    if let Some((_, last)) = piece.rsplit_once('\n') {
+                        layout.line_ending(last.graphemes(true).count());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the marker of emphasis or strong `node` around `content`, between the
+    /// characters `around` it: its styled marker or the preferred one, unless it would merge
+    /// with a run in or next to `content`, or an underscore would touch a word.
+    fn emphasis_marker(
+        &self,
+        node: &Node,
+        content: &str,
+        around: (Option<char>, Option<char>),
+    ) -> char {
+        let (before, after) = around;
+
+        let clashes = |marker: char| {
+            content.starts_with(marker)
+                || (content.ends_with(marker) && !content[..content.len() - 1].ends_with('\\'))
+                || before == Some(marker)
+                || after == Some(marker)
+                || (marker == '_' && (before.is_some_and(is_word) || after.is_some_and(is_word)))
+                || has_unescaped(content, marker)
+        };
+
+        let (preferred, other) = match self.style(node) {
+            Some(NodeStyle::Attention('*')) => ('*', '_'),
+            Some(NodeStyle::Attention('_')) => ('_', '*'),
+            _ => (self.emphasis, self.emphasis_other),
+        };
+
+        if clashes(preferred) && !clashes(other) {
+            other
+        } else {
+            preferred
+        }
+    }
+
+    fn inline(
+        &self,
+        node: &Node,
+        scope: Scope,
+        at_line_start: bool,
+        around: (Option<char>, Option<char>),
+    ) -> String {
+        match node {
+            Node::Text(text) => match self.reflowed.borrow().get(&address(node)) {
+                Some(value) => escape_text(value, scope, at_line_start),
+                None => escape_text(&text.value, scope, at_line_start),
+            },
+            Node::Emphasis(_) | Node::Strong(_) | Node::Delete(_) => {
+                let content = self.phrasing(node.children().unwrap_or_default(), scope);
+
+                // empty attention has no markdown form, markers alone are literal text.
+                if content.is_empty() {
+                    return content;
+                }
+
+                let marker = match node {
+                    Node::Delete(_) => "~~".to_owned(),
+                    Node::Strong(_) => self
+                        .emphasis_marker(node, &content, around)
+                        .to_string()
+                        .repeat(2),
+                    _ => self.emphasis_marker(node, &content, around).to_string(),
+                };
+
+                format!("{}{}{}", marker, content, marker)
+            }
+            Node::InlineCode(code) => inline_code(&code.value),
+            // indented continuation lines of raw html are paragraph text, whatever they start
+            // with, and lose the indent when parsed.
+            Node::Html(html) if scope == Scope::Flow => html.value.replace('\n', "\n    "),
+            Node::Html(html) => html.value.to_string(),
+            Node::Break(_) => match scope {
+                Scope::Flow => "\\\n".to_owned(),
+                Scope::Heading | Scope::TableCell => " ".to_owned(),
+            },
+            Node::Link(link) => {
+                if let Some(autolink) = autolink(link) {
+                    return autolink;
+                }
+
+                let mut out = format!(
+                    "[{}]({}",
+                    self.phrasing(&link.children, scope),
+                    destination(&link.url)
+                );
+
+                if let Some(title) = &link.title {
+                    out.push(' ');
+                    out.push_str(&title_string(title));
+                }
+
+                out.push(')');
+
+                out
+            }
+            Node::LinkReference(reference) => {
+                let content = self.phrasing(&reference.children, scope);
+
+                reference_string(
+                    &content,
+                    &reference.identifier,
+                    &reference.label,
+                    &reference.reference_type,
+                )
+            }
+            Node::Image(image) => {
+                let mut out = format!(
+                    "![{}]({}",
+                    escape_text(image.alt.as_deref().unwrap_or_default(), scope, false),
+                    destination(&image.url)
+                );
+
+                if let Some(title) = &image.title {
+                    out.push(' ');
+                    out.push_str(&title_string(title));
+                }
+
+                out.push(')');
+
+                out
+            }
+            Node::ImageReference(reference) => {
+                let alt = escape_text(reference.alt.as_deref().unwrap_or_default(), scope, false);
+
+                format!(
+                    "!{}",
+                    reference_string(
+                        &alt,
+                        &reference.identifier,
+                        &reference.label,
+                        &reference.reference_type,
+                    )
+                )
+            }
+            Node::FootnoteReference(reference) => format!(
+                "[^{}]",
+                escape_label(reference.label.as_deref().unwrap_or(&reference.identifier))
+            ),
+            Node::Custom(custom) => self.phrasing(&custom.children, scope),
+            // flow content misplaced in phrasing content.
+            node => self.block(node, None),
+        }
+    }
+}
+
+/// What becomes of a run of whitespace of a reflowed text node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Run {
+    /// A space, or the run itself if it holds no line ending.
+    Keep,
+    /// A line ending.
+    Break,
+    /// Nothing, another run next to it breaks the line.
+    Drop,
+}
+
+/// Whitespace runs of a text node, by byte range.
+type Runs = Vec<(Range<usize>, Run)>;
+
+/// Greedy line layout of a paragraph, see [`Serializer::reflow`].
+#[derive(Default)]
+struct Layout<'a> {
+    width: usize,
+    /// Address, value and whitespace runs of the text nodes.
+    texts: Vec<(usize, &'a str, Runs)>,
+    /// Runs between the last placed word and the current one, by text index.
+    spaces: Vec<(usize, Range<usize>)>,
+    /// False if the line can't break before the current word.
+    breakable: bool,
+    /// Width of the line up to the last placed word.
+    column: usize,
+    /// Width of the current word, and at the start of a line, where it may need an escape.
+    current: (usize, usize),
+}
+
+impl<'a> Layout<'a> {
+    /// Adds a word of text.
+    fn word(&mut self, word: &str) {
+        if word.is_empty() {
+            return;
+        }
+
+        let width = escape_text(word, Scope::Flow, false)
+            .graphemes(true)
+            .count();
+        let at_start = escape_text(word, Scope::Flow, true).graphemes(true).count();
+
+        self.glue(width, at_start);
+    }
+
+    /// Adds content without break opportunities, `at_start` wide at the start of a line.
+    fn glue(&mut self, width: usize, at_start: usize) {
+        let (current, current_at_start) = &mut self.current;
+
+        if *current == 0 {
+            *current_at_start = at_start;
+        } else {
+            *current_at_start += width;
+        }
+
+        *current += width;
+    }
+
+    /// Adds whitespace at `range` of text `text`, placing the current word.
+    fn space(&mut self, text: usize, range: Range<usize>) {
+        if self.current.0 > 0 || self.spaces.is_empty() {
+            self.commit();
+        }
+
+        self.spaces.push((text, range));
+    }
+
+    /// Forbids breaking the line right before the current word.
+    fn keep_together(&mut self) {
+        if self.current.0 == 0 {
+            self.breakable = false;
+        }
+    }
+
+    /// Adds a line ending, the next line starts `column` wide.
+    fn line_ending(&mut self, column: usize) {
+        self.commit();
+
+        self.column = column;
+    }
+
+    /// Places the current word on the line, or on the next one if it doesn't fit.
+    fn commit(&mut self) {
+        let (word, at_start) = core::mem::take(&mut self.current);
+
+        let spaces = core::mem::take(&mut self.spaces);
+
+        let fits = spaces.is_empty() || self.column + 1 + word <= self.width;
+
+        let runs = if self.breakable && self.column > 0 && !fits {
+            self.column = at_start;
+
+            let last = spaces.len() - 1;
+
+            spaces
+                .into_iter()
+                .enumerate()
+                .map(|(index, space)| (space, if index == last { Run::Break } else { Run::Drop }))
+                .collect::<Vec<_>>()
+        } else {
+            self.column += if self.column == 0 { at_start } else { word };
+
+            if !spaces.is_empty() && self.column > at_start {
+                self.column += 1;
+            }
+
+            spaces.into_iter().map(|space| (space, Run::Keep)).collect()
+        };
+
+        for ((text, range), run) in runs {
+            self.texts[text].2.push((range, run));
+        }
+
+        self.breakable = true;
+    }
+}
+
+/// Where phrasing content is serialized, which changes what must be escaped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scope {
+    Flow,
+    Heading,
+    TableCell,
+}
+
+/// Returns true if `next` can directly follow `prev` without a blank line and still start a
+/// new block, `continues` if `next` starts with a line that could continue a paragraph: a
+/// heading written with an underline, or indented code.
+fn can_interrupt_paragraph(prev: &Node, next: &Node, continues: bool) -> bool {
+    // a blank line is all that separates two block quotes, lists alternate markers instead.
+    if matches!((prev, next), (Node::Blockquote(_), Node::Blockquote(_))) {
+        return false;
+    }
+
+    // without a paragraph to continue lazily, any block starts on the next line.
+    if !ends_in_paragraph(prev) {
+        return true;
+    }
+
+    // a paragraph nested in `prev` only takes lazy lines of paragraph text.
+    if matches!(prev, Node::Blockquote(_) | Node::List(_)) {
+        return !continues
+            && !matches!(
+                next,
+                Node::Paragraph(_) | Node::Definition(_) | Node::Html(_) | Node::Table(_)
+            );
+    }
+
+    // a paragraph after a definition would be taken for its title if it starts like one.
+    if let (Node::Definition(_), Node::Paragraph(paragraph)) = (prev, next) {
+        return paragraph.children.first().is_some_and(|child| {
+            // emphasis starts with its marker.
+            leading_char(child).is_none_or(|c| !matches!(c, '"' | '\'' | '('))
+        });
+    }
+
+    match next {
+        Node::Heading(_) | Node::Code(_) => !continues,
+        Node::ThematicBreak(_) | Node::Blockquote(_) => true,
+        // the header row splits off the paragraph once the delimiter row follows it.
+        Node::Table(_) => matches!(prev, Node::Paragraph(_)),
+        Node::List(list) => {
+            (list.ordered != Some(true) || list.start.unwrap_or(1) == 1)
+                // empty items can't interrupt a paragraph.
+                && list
+                    .children
+                    .first()
+                    .and_then(Node::children)
+                    .is_some_and(|children| !children.is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// Whether the last line of `node` continues on the next one unless something interrupts
+/// it: paragraphs, and HTML and tables, which also end at a blank line.
+fn ends_in_paragraph(node: &Node) -> bool {
+    match node {
+        Node::Paragraph(_) | Node::Definition(_) | Node::Html(_) | Node::Table(_) => true,
+        Node::Blockquote(_) | Node::List(_) | Node::ListItem(_) => node
+            .children()
+            .and_then(|children| children.last())
+            .is_some_and(ends_in_paragraph),
+        _ => false,
+    }
+}
+
+/// Prefix the first line of `content` with `first` and the rest with `rest`,
+/// empty lines other than the first one get `empty` instead.
+/// Identity of `node` while the document is borrowed.
+fn address(node: &Node) -> usize {
+    node as *const Node as usize
+}
+
+fn prefix_lines(content: &str, first: &str, rest: &str, empty: &str) -> String {
+    if content.is_empty() {
+        return first.trim_end().to_owned();
+    }
+
+    let mut out = String::new();
+
+    for (index, line) in content.split('\n').enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+
+        if index == 0 {
+            out.push_str(first);
+        } else if line.is_empty() {
+            out.push_str(empty);
+        } else {
+            out.push_str(rest);
+        }
+
+        out.push_str(line);
+    }
+
+    out
+}
+
+/// Returns true if `line` is a thematic break: three or more of the same marker, and nothing
+/// else but spaces and tabs.
+fn is_thematic_break(line: &str) -> bool {
+    let Some(first) = line.chars().next().filter(|c| matches!(c, '*' | '-' | '_')) else {
+        return false;
+    };
+
+    line.chars().filter(|c| *c == first).count() >= 3
+        && line.chars().all(|c| c == first || matches!(c, ' ' | '\t'))
+}
+
+/// Returns true if `line`, at the start of a paragraph, would start another block instead:
+/// an html block, or a construct text would have been escaped for.
+fn starts_block(line: &str) -> bool {
+    let chars = line.chars().collect::<Vec<_>>();
+
+    starts_html_block(line) || (!chars.is_empty() && escape_line_start(&chars).is_some())
+}
+
+fn longest_run(value: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+
+    for ch in value.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+
+    longest
+}
+
+fn inline_code(value: &str) -> String {
+    // the shortest backtick run not present in `value`.
+    let mut size = 1;
+
+    while value.split(|c| c != '`').any(|run| run.len() == size) {
+        size += 1;
+    }
+
+    let fence = "`".repeat(size);
+
+    let pad = value.starts_with('`')
+        || value.ends_with('`')
+        || (value.starts_with(' ') && value.ends_with(' ') && !value.trim().is_empty());
+
+    if pad {
+        format!("{} {} {}", fence, value, fence)
+    } else {
+        format!("{}{}{}", fence, value, fence)
+    }
+}
+
+fn autolink(link: &Link) -> Option<String> {
+    if link.title.is_some() {
+        return None;
+    }
+
+    let [Node::Text(text)] = link.children.as_slice() else {
+        return None;
+    };
+
+    let url = link.url.as_ref();
+
+    if url.is_empty() || url.contains(|c: char| c.is_whitespace() || c == '<' || c == '>') {
+        return None;
+    }
+
+    if text.value == url && has_scheme(url) {
+        return Some(format!("<{}>", url));
+    }
+
+    if url.strip_prefix("mailto:") == Some(&text.value) && text.value.contains('@') {
+        return Some(format!("<{}>", text.value));
+    }
+
+    None
+}
+
+/// Escape what the info string of fenced code would unescape: backslashes and character
+/// references.
+fn escape_info(info: &str) -> String {
+    let chars = info.chars().collect::<Vec<_>>();
+
+    let mut out = String::with_capacity(info.len());
+
+    for (index, c) in chars.iter().enumerate() {
+        if *c == '\\' || (*c == '&' && looks_like_reference(&chars[index + 1..])) {
+            out.push('\\');
+        }
+
+        out.push(*c);
+    }
+
+    out
+}
+
+fn destination(url: &str) -> String {
+    let mut depth = 0i32;
+
+    let balanced = url.chars().all(|c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+
+        depth >= 0
+    }) && depth == 0;
+
+    let raw = !url.is_empty()
+        && balanced
+        && !url.starts_with('<')
+        && !url.contains(|c: char| c.is_whitespace() || c.is_control());
+
+    if raw {
+        url.replace('\\', "\\\\")
+    } else {
+        let mut out = String::from("<");
+
+        for c in url.chars() {
+            if matches!(c, '<' | '>' | '\\') {
+                out.push('\\');
+            }
+
+            out.push(c);
+        }
+
+        out.push('>');
+
+        out
+    }
+}
+
+fn title_string(title: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in title.chars() {
+        if matches!(c, '"' | '\\') {
+            out.push('\\');
+        }
+
+        out.push(c);
+    }
+
+    out.push('"');
+
+    out
+}
+
+/// Returns the first character `node` likely serializes to, enough to tell words from
+/// punctuation and whitespace.
+fn leading_char(node: &Node) -> Option<char> {
+    match node {
+        Node::Text(text) => text.value.chars().next(),
+        Node::Html(html) => html.value.chars().next(),
+        Node::Emphasis(_) | Node::Strong(_) => None,
+        _ => Some('\\'),
+    }
+}
+
+/// Whether `c` is neither whitespace nor punctuation, so delimiter runs next to it are
+/// intraword.
+fn is_word(c: char) -> bool {
+    !is_unicode_whitespace(c) && !is_unicode_punctuation(c)
+}
+
+/// Whether `content` holds `marker` not escaped by a backslash, e.g. of nested emphasis.
+fn has_unescaped(content: &str, marker: char) -> bool {
+    let mut escaped = false;
+
+    content.chars().any(|c| {
+        let found = c == marker && !escaped;
+
+        escaped = c == '\\' && !escaped;
+
+        found
+    })
+}
+
+/// Escape brackets of a raw `label`, its existing escapes are kept.
+fn escape_label(label: &str) -> String {
+    let mut out = String::new();
+
+    let mut chars = label.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) => {
+                    out.push(c);
+                    out.push(next);
+                }
+                None => out.push_str("\\\\"),
+            },
+            '[' | ']' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+fn reference_string(
+    content: &str,
+    identifier: &str,
+    label: &Option<alloc::borrow::Cow<'_, str>>,
+    reference_type: &ReferenceType,
+) -> String {
+    let label = escape_label(label.as_deref().unwrap_or(identifier));
+
+    // the text of collapsed and shortcut references is their label, escapes included.
+    let text = if normalize_identifier(content) == normalize_identifier(&label) {
+        content
+    } else {
+        &label
+    };
+
+    match reference_type {
+        ReferenceType::Full => format!("[{}][{}]", content, label),
+        ReferenceType::Collapsed => format!("[{}][]", text),
+        ReferenceType::Shortcut => format!("[{}]", text),
+    }
+}
+
+/// Escape characters of text `value` that would otherwise be read as markdown syntax.
+fn escape_text(value: &str, scope: Scope, at_line_start: bool) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+
+    let mut out = String::with_capacity(value.len());
+
+    let mut line_start = at_line_start && scope == Scope::Flow;
+
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+
+        if line_start {
+            // the parser strips the indentation of paragraph lines, a character reference
+            // keeps it.
+            if c == ' ' || c == '\t' {
+                out.push_str(whitespace_reference(c));
+                index += 1;
+                line_start = false;
+                continue;
+            }
+
+            line_start = false;
+
+            let rest = &chars[index..];
+
+            if let Some(escaped) = escape_line_start(rest) {
+                out.push_str(&escaped);
+                index += escaped.chars().filter(|c| *c != '\\').count();
+                continue;
+            }
+        }
+
+        let prev = index.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(index + 1).copied();
+
+        let escape = match c {
+            '*' => {
+                let (before, after) = run_bounds(&chars, index);
+
+                can_flank(before, after)
+            }
+            '`' | '[' | ']' => true,
+            // before a line ending, a backslash is a hard break.
+            '\\' => next.is_none_or(|c| c.is_ascii_punctuation() || c == '\n'),
+            '_' => {
+                let (before, after) = run_bounds(&chars, index);
+
+                can_flank(before, after)
+                    && !(before.is_some_and(char::is_alphanumeric)
+                        && after.is_some_and(char::is_alphanumeric))
+            }
+            '<' => next.is_some_and(|c| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')),
+            '&' => looks_like_reference(&chars[index + 1..]),
+            '~' => prev == Some('~') || next == Some('~'),
+            _ => false,
+        };
+
+        if escape {
+            out.push('\\');
+        }
+
+        out.push(c);
+
+        if c == '\n' {
+            line_start = scope == Scope::Flow;
+        }
+
+        index += 1;
+    }
+
+    out
+}
+
+/// Returns the character reference of whitespace `c`.
+fn whitespace_reference(c: char) -> &'static str {
+    match c {
+        '\t' => "&#9;",
+        _ => "&#32;",
+    }
+}
+
+/// Returns the chars before and after the run of the char at `index` of `chars`, `None` at
+/// the ends of `chars`.
+fn run_bounds(chars: &[char], index: usize) -> (Option<char>, Option<char>) {
+    let c = chars[index];
+
+    let start = chars[..index]
+        .iter()
+        .rposition(|prev| *prev != c)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let end = chars[index..]
+        .iter()
+        .position(|next| *next != c)
+        .map_or(chars.len(), |i| index + i);
+
+    (
+        start.checked_sub(1).map(|i| chars[i]),
+        chars.get(end).copied(),
+    )
+}
+
+/// Returns true if a delimiter run between `before` and `after` can be left- or
+/// right-flanking, and so open or close emphasis. The chars at the ends of a text value are
+/// unknown, they may belong to other nodes.
+fn can_flank(before: Option<char>, after: Option<char>) -> bool {
+    let (Some(before), Some(after)) = (before, after) else {
+        return true;
+    };
+
+    let left = !is_unicode_whitespace(after)
+        && (!is_unicode_punctuation(after)
+            || is_unicode_whitespace(before)
+            || is_unicode_punctuation(before));
+    let right = !is_unicode_whitespace(before)
+        && (!is_unicode_punctuation(before)
+            || is_unicode_whitespace(after)
+            || is_unicode_punctuation(after));
+
+    left || right
+}
+
+/// Escape block syntax at the start of a line, returns [`None`] if the line needs nothing.
+fn escape_line_start(rest: &[char]) -> Option<String> {
+    let first = rest[0];
+    let second = rest.get(1).copied();
+
+    let blank_or_end = |c: Option<char>| c.is_none_or(|c| matches!(c, ' ' | '\t' | '\n'));
+
+    // a line that could be a delimiter row would turn the line before it into a table header.
+    let mut line = rest.iter().take_while(|c| **c != '\n');
+
+    if line
+        .clone()
+        .all(|c| matches!(c, '|' | ':' | '-' | ' ' | '\t'))
+        && line.clone().any(|c| *c == '|')
+        && line.any(|c| *c == '-')
+    {
+        return Some(format!("\\{}", first));
+    }
+
+    // three or more of the same marker, and nothing else but whitespace, are a thematic break.
+    let line = rest.iter().take_while(|c| **c != '\n');
+
+    if matches!(first, '*' | '-' | '_')
+        && line.clone().filter(|c| **c == first).count() >= 3
+        && line.clone().all(|c| matches!(c, ' ' | '\t') || *c == first)
+    {
+        return Some(format!("\\{}", first));
+    }
+
+    match first {
+        '#' | '>' => Some(format!("\\{}", first)),
+        '*' if blank_or_end(second) => Some("\\*".to_owned()),
+        '-' | '+' if blank_or_end(second) || second == Some(first) => Some(format!("\\{}", first)),
+        '=' => {
+            let line = rest.iter().take_while(|c| **c != '\n');
+
+            if line.clone().all(|c| *c == '=' || *c == ' ' || *c == '\t') {
+                Some("\\=".to_owned())
+            } else {
+                None
+            }
+        }
+        '0'..='9' => {
+            let digits = rest.iter().take_while(|c| c.is_ascii_digit()).count();
+
+            match rest.get(digits) {
+                Some(delimiter @ ('.' | ')'))
+                    if digits < 10 && blank_or_end(rest.get(digits + 1).copied()) =>
+                {
+                    Some(format!(
+                        "{}\\{}",
+                        rest[..digits].iter().collect::<String>(),
+                        delimiter
+                    ))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if `rest` (the chars after `&`) starts like a character reference.
+fn looks_like_reference(rest: &[char]) -> bool {
+    let body = rest
+        .iter()
+        .take_while(|c| c.is_ascii_alphanumeric() || **c == '#')
+        .count();
+
+    body > 0 && rest.get(body) == Some(&';')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::builder::DocumentBuilder;
+
+    fn text(value: &str) -> Node<'_> {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    }
+
+    fn paragraph(children: Vec<Node>) -> Node {
+        Node::Paragraph(Paragraph {
+            children,
+            data: None,
+        })
+    }
+
+    fn serialize(children: Vec<Node>) -> String {
+        to_markdown(
+            &Document {
+                children,
+                data: None,
+            },
+            &SerializeOptions::default(),
+        )
+    }
+
+    #[test]
+    fn test_heading() {
+        let mut document = DocumentBuilder::new()
+            .heading(1, |h| {
+                h.text("Alpha");
+            })
+            .heading(2, |h| {
+                h.text("Bravo #");
+            })
+            .heading(3, |_| {})
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            document.to_markdown(&Default::default()),
+            "# Alpha\n\n## Bravo \\#\n\n###\n"
+        );
+
+        document.children.truncate(2);
+
+        let options = SerializeOptions {
+            setext: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "Alpha\n=====\n\nBravo #\n-------\n"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            serialize(vec![paragraph(vec![text(
+                "# not heading\n1. not list\n- nor this"
+            )])]),
+            "\\# not heading\n1\\. not list\n\\- nor this\n"
+        );
+
+        assert_eq!(
+            serialize(vec![paragraph(vec![text(
+                "*a* _b_ snake_case `c` [d] <e> &amp; a\\b"
+            )])]),
+            "\\*a\\* \\_b\\_ snake_case \\`c\\` \\[d\\] \\<e> \\&amp; a\\b\n"
+        );
+
+        assert_eq!(
+            serialize(vec![paragraph(vec![
+                text("see!"),
+                Node::from(Link {
+                    children: vec![text("x")],
+                    url: "y".into(),
+                    title: None,
+                    data: None,
+                }),
+            ])]),
+            "see\\![x](y)\n"
+        );
+
+        assert_eq!(
+            serialize(vec![paragraph(vec![text("a\n===")])]),
+            "a\n\\===\n"
+        );
+
+        assert_eq!(
+            serialize(vec![paragraph(vec![text("a | b\n:-- | --")])]),
+            "a | b\n\\:-- | --\n"
+        );
+
+        // only runs that could open or close emphasis.
+        assert_eq!(
+            serialize(vec![paragraph(vec![text("a * b ** c _ d 2*3 e_f")])]),
+            "a * b ** c _ d 2\\*3 e_f\n"
+        );
+
+        assert_eq!(
+            serialize(vec![paragraph(vec![text("* a\n_ _ _\n  b")])]),
+            "\\* a\n\\_ _ _\n&#32; b\n"
+        );
+    }
+
+    #[test]
+    fn test_phrasing() {
+        let document = DocumentBuilder::new()
+            .paragraph(|p| {
+                p.emphasis(|e| {
+                    e.text("a");
+                })
+                .text(" ")
+                .strong(|s| {
+                    s.text("b");
+                })
+                .text(" ")
+                .inline_code("c`d")
+                .hard_break()
+                .link("https://example.com", |l| {
+                    l.text("https://example.com");
+                })
+                .text(" ")
+                .link("a b", |l| {
+                    l.text("e");
+                })
+                .text(" ")
+                .image("f.png", "g");
+            })
+            .finish()
+            .unwrap();
+
+        let options = SerializeOptions {
+            emphasis: '_',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "_a_ __b__ ``c`d``\\\n<https://example.com> [e](<a b>) ![g](f.png)\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_attention() {
+        assert_eq!(
+            serialize(vec![paragraph(vec![
+                text("a"),
+                Node::Strong(Strong {
+                    children: vec![],
+                    data: None
+                }),
+                Node::Emphasis(Emphasis {
+                    children: vec![Node::Delete(Delete {
+                        children: vec![],
+                        data: None
+                    })],
+                    data: None,
+                }),
+                text("b"),
+            ])]),
+            "ab\n"
+        );
+    }
+
+    #[test]
+    fn test_references() {
+        let document = Document {
+            children: vec![
+                paragraph(vec![
+                    Node::from(LinkReference {
+                        children: vec![text("a")],
+                        identifier: "b".into(),
+                        label: Some("B".into()),
+                        reference_type: ReferenceType::Full,
+                        data: None,
+                    }),
+                    text(" "),
+                    Node::from(ImageReference {
+                        alt: Some("c".into()),
+                        identifier: "c".into(),
+                        label: Some("c".into()),
+                        reference_type: ReferenceType::Collapsed,
+                        data: None,
+                    }),
+                ]),
+                Node::from(Definition {
+                    identifier: "b".into(),
+                    label: Some("B".into()),
+                    url: "https://example.com".into(),
+                    title: Some("say \"hi\"".into()),
+                    data: None,
+                }),
+            ],
+            data: None,
+        };
+
+        assert_eq!(
+            document.to_markdown(&Default::default()),
+            "[a][B] ![c][]\n\n[B]: https://example.com \"say \\\"hi\\\"\"\n"
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        let document = DocumentBuilder::new()
+            .code(Some("rust"), "fn main() {}\n```")
+            .flow(Code {
+                value: "".into(),
+                lang: Some("js".into()),
+                meta: Some("title=a".into()),
+                data: None,
+            })
+            .finish()
+            .unwrap();
+
+        assert_eq!(
+            document.to_markdown(&Default::default()),
+            "````rust\nfn main() {}\n```\n````\n\n```js title=a\n```\n"
+        );
+
+        let options = SerializeOptions {
+            fence: '~',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "~~~rust\nfn main() {}\n```\n~~~\n\n~~~js title=a\n~~~\n"
+        );
+
+        // the line endings of built values are written as the others.
+        let document = DocumentBuilder::new()
+            .code(None, "a\r\nb\rc\nd")
+            .finish()
+            .unwrap();
+
+        let options = SerializeOptions {
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "```\r\na\r\nb\r\nc\r\nd\r\n```\r\n"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let document = DocumentBuilder::new()
+            .list(true, |items| {
+                items
+                    .start(9)
+                    .item(|flow| {
+                        flow.paragraph(|p| {
+                            p.text("a");
+                        })
+                        .list(false, |items| {
+                            items.item(|flow| {
+                                flow.paragraph(|p| {
+                                    p.text("b");
+                                });
+                            });
+                        });
+                    })
+                    .task(true, |flow| {
+                        flow.code(None, "c\n\nd");
+                    });
+            })
+            .list(true, |items| {
+                items.item(|_| {});
+            })
+            .blockquote(|quote| {
+                quote
+                    .paragraph(|p| {
+                        p.text("e");
+                    })
+                    .paragraph(|p| {
+                        p.text("f");
+                    });
+            })
+            .finish()
+            .unwrap();
+
+        let options = SerializeOptions {
+            bullet: '-',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "9. a\n   - b\n10. [x] ```\n    c\n\n    d\n    ```\n\n1)\n\n> e\n>\n> f\n"
+        );
+    }
+
+    #[test]
+    fn test_list_markers() {
+        let items = |ordered, start, texts: &'static [&'static str]| {
+            move |flow: &mut DocumentBuilder| {
+                flow.list(ordered, |items| {
+                    if let Some(start) = start {
+                        items.start(start);
+                    }
+
+                    for text in texts {
+                        items.item(|flow| {
+                            flow.paragraph(|p| {
+                                p.text(*text);
+                            });
+                        });
+                    }
+                });
+            }
+        };
+
+        // adjacent lists that would merge alternate their markers.
+        let mut builder = DocumentBuilder::new();
+
+        items(false, None, &["a", "b"])(&mut builder);
+        items(false, None, &["c"])(&mut builder);
+        items(false, None, &["d"])(&mut builder);
+        items(true, None, &["e"])(&mut builder);
+        items(true, None, &["f"])(&mut builder);
+
+        let document = builder.finish().unwrap();
+
+        let options = SerializeOptions {
+            bullet: '-',
+            bullet_other: '+',
+            delimiter: ')',
+            ..Default::default()
+        };
+
+        let markdown = document.to_markdown(&options);
+
+        assert_eq!(markdown, "- a\n- b\n\n+ c\n\n- d\n\n1) e\n\n1. f\n");
+
+        let reparsed = crate::parser::Parser::new(markdown.as_str())
+            .parse()
+            .unwrap();
+
+        assert_eq!(reparsed.find_all_of::<List>().len(), 5);
+        assert_eq!(reparsed.to_markdown(&options), markdown);
+
+        // invalid markers fall back to the defaults.
+        let options = SerializeOptions {
+            bullet: '-',
+            bullet_other: '-',
+            delimiter: ':',
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "- a\n- b\n\n* c\n\n- d\n\n1. e\n\n1) f\n"
+        );
+
+        // numbering from the start of the list.
+        let mut builder = DocumentBuilder::new();
+
+        items(true, Some(5), &["a", "b", "c"])(&mut builder);
+
+        let document = builder.finish().unwrap();
+
+        let numbered = |numbering| {
+            document.to_markdown(&SerializeOptions {
+                numbering,
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(numbered(ListNumbering::Number), "5. a\n6. b\n7. c\n");
+        assert_eq!(numbered(ListNumbering::Preserve), "5. a\n6. b\n7. c\n");
+
+        let one = numbered(ListNumbering::One);
+
+        assert_eq!(one, "5. a\n5. b\n5. c\n");
+        assert_eq!(
+            crate::parser::Parser::new(one.as_str()).parse().unwrap(),
+            crate::parser::Parser::new(numbered(ListNumbering::Number).as_str())
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_preserve_numbering() {
+        let source = "1. a\n1. b\n\n- c\n\n  3) d\n  4) e\n\n  7. f\n\n2. g\n2. h\n5. i\n";
+
+        let (document, style) = crate::parser::Parser::new(source)
+            .parse_with_style()
+            .unwrap();
+
+        assert_eq!(style.numbering(&[0]), Some(ListNumbering::One));
+        assert_eq!(style.numbering(&[1, 0, 1]), Some(ListNumbering::Number));
+        assert_eq!(style.numbering(&[1, 0, 2]), Some(ListNumbering::Number));
+        assert_eq!(style.numbering(&[2]), Some(ListNumbering::Number));
+        assert_eq!(style.numbering(&[1]), None);
+
+        let options = SerializeOptions::default();
+
+        assert_eq!(
+            document.to_markdown_with_style(&options, &style),
+            "1. a\n1. b\n\n- c\n\n  3) d\n  4) e\n\n  7. f\n\n2. g\n3. h\n4. i\n"
+        );
+
+        // without the style, or numbering otherwise, lists are numbered from their start.
+        assert!(document.to_markdown(&options).starts_with("1. a\n2. b\n"));
+
+        let options = SerializeOptions {
+            numbering: ListNumbering::Number,
+            ..Default::default()
+        };
+
+        assert!(document
+            .to_markdown_with_style(&options, &style)
+            .starts_with("1. a\n2. b\n"));
+    }
+
+    #[test]
+    fn test_table() {
+        let cell = |value| {
+            Node::TableCell(TableCell {
+                children: vec![text(value)],
+                data: None,
+            })
+        };
+
+        let document = Document {
+            children: vec![Node::Table(Table {
+                children: vec![
+                    Node::TableRow(TableRow {
+                        children: vec![cell("a"), cell("b|c")],
+                        data: None,
+                    }),
+                    Node::TableRow(TableRow {
+                        children: vec![cell("d")],
+                        data: None,
+                    }),
+                ],
+                align: vec![AlignType::Left, AlignType::None],
+                data: None,
+            })],
+            data: None,
+        };
+
+        assert_eq!(
+            document.to_markdown(&Default::default()),
+            "| a   | b\\|c |\n| :-- | ---- |\n| d   |      |\n"
+        );
+
+        let options = SerializeOptions {
+            compact_tables: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            document.to_markdown(&options),
+            "| a | b\\|c |\n| :-- | --- |\n| d |  |\n"
+        );
+    }
+
+    #[test]
+    fn test_table_padding() {
+        let source = "\
+| Name | 名前 | Count | Note |
+|:-:|:--|--:|---|
+| Rust | ラスト | 1 | `a\\|b` |
+| é | 한국어 텍스트 | 12345 | [x](a\\|b) |
+";
+
+        let document = crate::parser::Parser::new(source).parse().unwrap();
+
+        let markdown = document.to_markdown(&Default::default());
+
+        // wide characters count as one column, without east asian width support.
+        assert_eq!(
+            markdown,
+            "\
+| Name | 名前      | Count | Note      |
+| :--: | :------ | ----: | --------- |
+| Rust | ラスト     |     1 | `a\\|b`    |
+|  é   | 한국어 텍스트 | 12345 | [x](a\\|b) |
+"
+        );
+
+        assert_eq!(
+            crate::parser::Parser::new(markdown.as_str())
+                .parse()
+                .unwrap(),
+            document
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for source in [
+            // the label of a shortcut reference is written verbatim.
+            "[`]\n\n[`]: /u\n",
+            // a continuation line of inline html starting like a block quote.
+            "a <b\n    >\n",
+            // a setext heading after a block quote in a tight list item.
+            "* > [a]: <>\n  >\n  b\n  ===\n* c\n",
+            // strong text after a definition in a tight list item.
+            "* [a]: <>\n  **b**\n* c\n",
+            // a table right after a paragraph, and a paragraph line like a delimiter row.
+            "* a\n  | b |\n  | - |\n* c\n      | - |\n",
+        ] {
+            let document = crate::parser::Parser::new(source).parse().unwrap();
+
+            let markdown = document.to_markdown(&Default::default());
+
+            assert_eq!(
+                crate::parser::Parser::new(markdown.as_str())
+                    .parse()
+                    .unwrap(),
+                document,
+                "{:?} serialized to {:?}",
+                source,
+                markdown
+            );
+        }
+    }
+
+    /// Returns `document` with the whitespace runs of its text values collapsed to a space.
+    fn collapse_whitespace(mut document: Document) -> Document {
+        struct Collapse;
+
+        impl<'cx> VisitorMut<'cx> for Collapse {
+            fn visit_text(&mut self, node: &mut Text<'cx>) -> Transform<'cx> {
+                let mut value = String::new();
+
+                for c in node.value.chars() {
+                    if c != '\n' && c != ' ' {
+                        value.push(c);
+                    } else if !value.ends_with(' ') {
+                        value.push(' ');
+                    }
+                }
+
+                node.value = value.into();
+
+                Transform::Keep
+            }
+        }
+
+        for child in &mut document.children {
+            walk_mut(child, &mut Collapse);
+        }
+
+        document
+    }
+
+    #[test]
+    fn test_wrap() {
+        let source = "\
+> * item
+>   * nested item with a long paragraph of *emphasized words*, `inline code spans`, a \
+[link to somewhere](https://example.com/a/rather/long/destination \"Title\") and a \
+supercalifragilisticexpialidocious word that can't fit, then 1. a number and + a plus
+>
+>     | a | b |
+>     | - | - |
+>     | a rather long table cell that is never wrapped | b |
+";
+
+        let document = crate::parser::Parser::new(source).parse().unwrap();
+
+        let options = SerializeOptions {
+            wrap: Wrap::Width(40),
+            ..Default::default()
+        };
+
+        let markdown = document.to_markdown(&options);
+
+        assert_eq!(
+            collapse_whitespace(
+                crate::parser::Parser::new(markdown.as_str())
+                    .parse()
+                    .unwrap()
+            ),
+            collapse_whitespace(document.clone()),
+            "{}",
+            markdown
+        );
+
+        for line in markdown.lines() {
+            let width = line.graphemes(true).count();
+
+            // only the table and unbreakable tokens may overflow, the link destination and
+            // title being one with the word before it.
+            let unbreakable = || {
+                !line[">     ".len()..]
+                    .trim_end_matches(" \"Title\")")
+                    .contains(' ')
+            };
+
+            assert!(
+                width <= 40 || line.starts_with(">     |") || unbreakable(),
+                "{:?} is {} wide",
+                line,
+                width
+            );
+        }
+
+        assert!(markdown.contains("\n>     supercalifragilisticexpialidocious\n"));
+        assert!(markdown.contains("`inline code spans`"));
+        assert!(
+            markdown.contains("\n>     | a rather long table cell that is never wrapped | b   |\n")
+        );
+
+        // without wrapping, paragraphs are on one line.
+        let options = SerializeOptions {
+            wrap: Wrap::NoWrap,
+            ..Default::default()
+        };
+
+        let markdown = crate::parser::Parser::new("a\nb  \nc *d\ne*")
+            .parse()
+            .unwrap()
+            .to_markdown(&options);
+
+        assert_eq!(markdown, "a b\\\nc *d e*\n");
+
+        let source = "a\nb c\n";
+
+        assert_eq!(
+            crate::parser::Parser::new(source)
+                .parse()
+                .unwrap()
+                .to_markdown(&Default::default()),
+            source
+        );
+    }
+
+    #[test]
+    fn test_wrap_block_starts() {
+        // breaking before these words would start a block, or end the paragraph.
+        let source = "aaaa 1. bbbb - cccc # dddd > eeee <div> ffff ```g``` hhhh";
+
+        let document = crate::parser::Parser::new(source).parse().unwrap();
+
+        for width in 1..source.len() {
+            let options = SerializeOptions {
+                wrap: Wrap::Width(width),
+                ..Default::default()
+            };
+
+            let markdown = document.to_markdown(&options);
+
+            assert_eq!(
+                collapse_whitespace(
+                    crate::parser::Parser::new(markdown.as_str())
+                        .parse()
+                        .unwrap()
+                ),
+                collapse_whitespace(document.clone()),
+                "{}",
+                markdown
+            );
+        }
+    }
+
+    #[test]
+    fn test_ignore_directive() {
+        let source = "\
+| a |  b |
+|:-|-|
+| 1   | 22 |
+
+<!-- md-ignore -->
+
+| a |  b |
+|:-|-|
+| 1   | 22 |
+
+<!--md-ignore-->
+*  keep   *this*
+   as is
+
+> <!-- md-ignore -->
+>
+> *  not   nested
+";
+
+        let (document, spans) = crate::parser::Parser::new(source)
+            .parse_with_spans()
+            .unwrap();
+
+        // the ignored blocks are parsed as usual.
+        assert_eq!(document.find_all_of::<Table>().len(), 2);
+        assert_eq!(document.find_all_of::<List>().len(), 2);
+
+        let options = SerializeOptions::default().ignore_directive(" md-ignore ");
+
+        let markdown = document.to_markdown_with_source(&options, &Style::new(), &spans, source);
+
+        assert_eq!(
+            markdown,
+            "\
+| a   | b   |
+| :-- | --- |
+| 1   | 22  |
+
+<!-- md-ignore -->
+
+| a |  b |
+|:-|-|
+| 1   | 22 |
+
+<!--md-ignore-->
+
+*  keep   *this*
+   as is
+
+> <!-- md-ignore -->
+>
+> * not   nested
+"
+        );
+        assert_eq!(
+            crate::parser::Parser::new(markdown.as_str())
+                .parse()
+                .unwrap(),
+            document
+        );
+
+        // without the source, a directive or a matching one, everything is serialized.
+        let formatted = document.to_markdown(&Default::default());
+
+        assert_eq!(document.to_markdown(&options), formatted);
+        assert_eq!(
+            document.to_markdown_with_source(&Default::default(), &Style::new(), &spans, source),
+            formatted
+        );
+        assert_eq!(
+            document.to_markdown_with_source(
+                &SerializeOptions::default().ignore_directive("prettier-ignore"),
+                &Style::new(),
+                &spans,
+                source
+            ),
+            formatted
+        );
+
+        // the source line endings are those of the output.
+        let crlf = source.replace('\n', "\r\n");
+
+        let (document, spans) = crate::parser::Parser::new(crlf.as_str())
+            .parse_with_spans()
+            .unwrap();
+
+        let options = SerializeOptions {
+            line_ending: LineEnding::CrLf,
+            ..options
+        };
+
+        assert_eq!(
+            document.to_markdown_with_source(&options, &Style::new(), &spans, &crlf),
+            markdown.replace('\n', "\r\n")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Character classes of the CommonMark spec.
+//!
+//! Emphasis delimiter runs are [flanking](https://spec.commonmark.org/0.31.2/#left-flanking-delimiter-run)
+//! depending on whether the chars around them are Unicode whitespace or punctuation, where
+//! indentation only knows spaces and tabs: a no-break space (U+00A0) indents nothing, but
+//! keeps a `*` from opening emphasis.
+//!
+//! ```
+//! use markdown_rs::unicode::{is_unicode_punctuation, is_unicode_whitespace};
+//!
+//! assert!(is_unicode_whitespace('\u{A0}'));
+//! assert!(is_unicode_punctuation('€'));
+//! assert!(!is_unicode_punctuation('a'));
+//! ```
+
+use core::cmp::Ordering;
+
+/// Returns true for [Unicode whitespace](https://spec.commonmark.org/0.31.2/#unicode-whitespace-character):
+/// the chars of the `Zs` general category, tab, line feed, form feed and carriage return.
+pub fn is_unicode_whitespace(c: char) -> bool {
+    matches!(
+        c,
+        '\t' | '\n' | '\x0C' | '\r' | ' ' | '\u{A0}' | '\u{1680}' | '\u{2000}'
+            ..='\u{200A}' | '\u{202F}' | '\u{205F}' | '\u{3000}'
+    )
+}
+
+/// Returns true for [Unicode punctuation](https://spec.commonmark.org/0.31.2/#unicode-punctuation-character):
+/// the chars of the `P` (punctuation) and `S` (symbol) general categories.
+pub fn is_unicode_punctuation(c: char) -> bool {
+    if c.is_ascii() {
+        return c.is_ascii_punctuation();
+    }
+
+    const RANGES: &[(char, char)] = &[
+        ('\u{A1}', '\u{A9}'),
+        ('\u{AB}', '\u{AC}'),
+        ('\u{AE}', '\u{B1}'),
+        ('\u{B4}', '\u{B4}'),
+        ('\u{B6}', '\u{B8}'),
+        ('\u{BB}', '\u{BB}'),
+        ('\u{BF}', '\u{BF}'),
+        ('\u{D7}', '\u{D7}'),
+        ('\u{F7}', '\u{F7}'),
+        ('\u{2C2}', '\u{2C5}'),
+        ('\u{2D2}', '\u{2DF}'),
+        ('\u{2E5}', '\u{2EB}'),
+        ('\u{2ED}', '\u{2ED}'),
+        ('\u{2EF}', '\u{2FF}'),
+        ('\u{375}', '\u{375}'),
+        ('\u{37E}', '\u{37E}'),
+        ('\u{384}', '\u{385}'),
+        ('\u{387}', '\u{387}'),
+        ('\u{55A}', '\u{55F}'),
+        ('\u{589}', '\u{58A}'),
+        ('\u{58D}', '\u{58F}'),
+        ('\u{5BE}', '\u{5BE}'),
+        ('\u{5C0}', '\u{5C0}'),
+        ('\u{5C3}', '\u{5C3}'),
+        ('\u{5C6}', '\u{5C6}'),
+        ('\u{5F3}', '\u{5F4}'),
+        ('\u{606}', '\u{60F}'),
+        ('\u{61B}', '\u{61B}'),
+        ('\u{61D}', '\u{61F}'),
+        ('\u{66A}', '\u{66D}'),
+        ('\u{6D4}', '\u{6D4}'),
+        ('\u{6DE}', '\u{6DE}'),
+        ('\u{6E9}', '\u{6E9}'),
+        ('\u{6FD}', '\u{6FE}'),
+        ('\u{700}', '\u{70D}'),
+        ('\u{964}', '\u{965}'),
+        ('\u{970}', '\u{970}'),
+        ('\u{E3F}', '\u{E3F}'),
+        ('\u{E4F}', '\u{E4F}'),
+        ('\u{E5A}', '\u{E5B}'),
+        ('\u{10FB}', '\u{10FB}'),
+        ('\u{166D}', '\u{166E}'),
+        ('\u{169B}', '\u{169C}'),
+        ('\u{16EB}', '\u{16ED}'),
+        ('\u{2010}', '\u{2027}'),
+        ('\u{2030}', '\u{205E}'),
+        ('\u{207A}', '\u{207E}'),
+        ('\u{208A}', '\u{208E}'),
+        ('\u{20A0}', '\u{20C0}'),
+        ('\u{2100}', '\u{2101}'),
+        ('\u{2103}', '\u{2106}'),
+        ('\u{2108}', '\u{2109}'),
+        ('\u{2114}', '\u{2114}'),
+        ('\u{2116}', '\u{2118}'),
+        ('\u{211E}', '\u{2123}'),
+        ('\u{2125}', '\u{2125}'),
+        ('\u{2127}', '\u{2127}'),
+        ('\u{2129}', '\u{2129}'),
+        ('\u{212E}', '\u{212E}'),
+        ('\u{213A}', '\u{213B}'),
+        ('\u{2140}', '\u{2144}'),
+        ('\u{214A}', '\u{214D}'),
+        ('\u{214F}', '\u{214F}'),
+        ('\u{2190}', '\u{2426}'),
+        ('\u{2440}', '\u{244A}'),
+        ('\u{249C}', '\u{24E9}'),
+        ('\u{2500}', '\u{2775}'),
+        ('\u{2794}', '\u{2B73}'),
+        ('\u{2B76}', '\u{2BFF}'),
+        ('\u{2CE5}', '\u{2CEA}'),
+        ('\u{2CF9}', '\u{2CFC}'),
+        ('\u{2CFE}', '\u{2CFF}'),
+        ('\u{2D70}', '\u{2D70}'),
+        ('\u{2E00}', '\u{2E5D}'),
+        ('\u{2E80}', '\u{2FFB}'),
+        ('\u{3001}', '\u{3004}'),
+        ('\u{3008}', '\u{3020}'),
+        ('\u{3030}', '\u{3030}'),
+        ('\u{3036}', '\u{3037}'),
+        ('\u{303D}', '\u{303F}'),
+        ('\u{309B}', '\u{309C}'),
+        ('\u{30A0}', '\u{30A0}'),
+        ('\u{30FB}', '\u{30FB}'),
+        ('\u{3190}', '\u{3191}'),
+        ('\u{3196}', '\u{319F}'),
+        ('\u{31C0}', '\u{31E3}'),
+        ('\u{3200}', '\u{321E}'),
+        ('\u{322A}', '\u{3247}'),
+        ('\u{3250}', '\u{3250}'),
+        ('\u{3260}', '\u{327F}'),
+        ('\u{328A}', '\u{32B0}'),
+        ('\u{32C0}', '\u{33FF}'),
+        ('\u{4DC0}', '\u{4DFF}'),
+        ('\u{A490}', '\u{A4C6}'),
+        ('\u{A4FE}', '\u{A4FF}'),
+        ('\u{A60D}', '\u{A60F}'),
+        ('\u{A673}', '\u{A673}'),
+        ('\u{A67E}', '\u{A67E}'),
+        ('\u{A6F2}', '\u{A6F7}'),
+        ('\u{A700}', '\u{A716}'),
+        ('\u{A720}', '\u{A721}'),
+        ('\u{A789}', '\u{A78A}'),
+        ('\u{A828}', '\u{A82B}'),
+        ('\u{A836}', '\u{A839}'),
+        ('\u{A874}', '\u{A877}'),
+        ('\u{A8CE}', '\u{A8CF}'),
+        ('\u{A8F8}', '\u{A8FA}'),
+        ('\u{A8FC}', '\u{A8FC}'),
+        ('\u{A92E}', '\u{A92F}'),
+        ('\u{A95F}', '\u{A95F}'),
+        ('\u{A9C1}', '\u{A9CD}'),
+        ('\u{A9DE}', '\u{A9DF}'),
+        ('\u{AA5C}', '\u{AA5F}'),
+        ('\u{AA77}', '\u{AA79}'),
+        ('\u{AADE}', '\u{AADF}'),
+        ('\u{AAF0}', '\u{AAF1}'),
+        ('\u{AB5B}', '\u{AB5B}'),
+        ('\u{AB6A}', '\u{AB6B}'),
+        ('\u{ABEB}', '\u{ABEB}'),
+        ('\u{FB29}', '\u{FB29}'),
+        ('\u{FBB2}', '\u{FBC2}'),
+        ('\u{FD3E}', '\u{FD4F}'),
+        ('\u{FDCF}', '\u{FDCF}'),
+        ('\u{FDFC}', '\u{FDFF}'),
+        ('\u{FE10}', '\u{FE19}'),
+        ('\u{FE30}', '\u{FE52}'),
+        ('\u{FE54}', '\u{FE66}'),
+        ('\u{FE68}', '\u{FE6B}'),
+        ('\u{FF01}', '\u{FF0F}'),
+        ('\u{FF1A}', '\u{FF20}'),
+        ('\u{FF3B}', '\u{FF40}'),
+        ('\u{FF5B}', '\u{FF65}'),
+        ('\u{FFE0}', '\u{FFE6}'),
+        ('\u{FFE8}', '\u{FFEE}'),
+        ('\u{FFFC}', '\u{FFFD}'),
+        ('\u{1F000}', '\u{1FBFF}'),
+    ];
+
+    RANGES
+        .binary_search_by(|(start, end)| {
+            if *end < c {
+                Ordering::Less
+            } else if *start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{html::HtmlOptions, parse_to_html};
+
+    use super::*;
+
+    fn html(source: &str) -> String {
+        parse_to_html(source, &HtmlOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_classes() {
+        for c in [
+            '\t', '\n', '\x0C', '\r', ' ', '\u{A0}', '\u{2003}', '\u{3000}',
+        ] {
+            assert!(is_unicode_whitespace(c), "{:?}", c);
+            assert!(!is_unicode_punctuation(c), "{:?}", c);
+        }
+
+        // zero-width and line separators aren't in `Zs`.
+        for c in ['\u{200B}', '\u{2028}', '\x0B', 'a'] {
+            assert!(!is_unicode_whitespace(c), "{:?}", c);
+        }
+
+        for c in [
+            '!', '~', '$', '£', '€', '«', '»', '¿', '—', '、', '「', '＄',
+        ] {
+            assert!(is_unicode_punctuation(c), "{:?}", c);
+        }
+
+        for c in ['a', '5', 'é', 'п', '中', '\u{A0}'] {
+            assert!(!is_unicode_punctuation(c), "{:?}", c);
+        }
+    }
+
+    #[test]
+    fn test_flanking() {
+        // a no-break space keeps delimiters from flanking, like a space.
+        assert_eq!(html("*\u{A0}a\u{A0}*"), "<p>*\u{A0}a\u{A0}*</p>\n");
+        assert_eq!(html("*\u{3000}a*"), "<p>*\u{3000}a*</p>\n");
+        assert_eq!(
+            html("a\u{A0}*b*\u{A0}c"),
+            "<p>a\u{A0}<em>b</em>\u{A0}c</p>\n"
+        );
+        assert_eq!(
+            html("a\u{A0}__b__\u{A0}c"),
+            "<p>a\u{A0}<strong>b</strong>\u{A0}c</p>\n"
+        );
+
+        // currency symbols are punctuation.
+        assert_eq!(
+            html("*$*alpha.\n\n*£*bravo.\n\n*€*charlie."),
+            "<p>*$*alpha.</p>\n<p>*£*bravo.</p>\n<p>*€*charlie.</p>\n"
+        );
+
+        // non-ASCII punctuation and letters.
+        assert_eq!(html("*«a»*"), "<p><em>«a»</em></p>\n");
+        assert_eq!(html("a*«b»*c"), "<p>a*«b»*c</p>\n");
+        assert_eq!(
+            html("пристаням_стремятся_"),
+            "<p>пристаням_стремятся_</p>\n"
+        );
+        assert_eq!(
+            html("_пристаням_стремятся_"),
+            "<p><em>пристаням_стремятся</em></p>\n"
+        );
+        assert_eq!(html("foo-_(bar)_"), "<p>foo-<em>(bar)</em></p>\n");
+    }
+}
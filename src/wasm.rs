@@ -0,0 +1,31 @@
+//! [`wasm-bindgen`](https://docs.rs/wasm-bindgen) exports of [`parse_to_json`](crate::parse_to_json)
+//! and [`parse_to_html`](crate::parse_to_html), for use from JavaScript:
+//!
+//! ```js
+//! import { parseToHtml, parseToJson } from "markdown-rs";
+//!
+//! const tree = JSON.parse(parseToJson("# hi"));
+//! const html = parseToHtml("# hi", false);
+//! ```
+
+use wasm_bindgen::prelude::*;
+
+use crate::{html::HtmlOptions, parser::ParserOptions};
+
+/// Parse `source` into an mdast tree serialized as JSON.
+#[wasm_bindgen(js_name = parseToJson)]
+pub fn parse_to_json(source: &str) -> Result<String, JsError> {
+    Ok(crate::parse_to_json(source, &ParserOptions::default())?)
+}
+
+/// Parse `source` and render it to HTML, raw HTML is only emitted verbatim if
+/// `allow_dangerous_html` is set.
+#[wasm_bindgen(js_name = parseToHtml)]
+pub fn parse_to_html(source: &str, allow_dangerous_html: bool) -> Result<String, JsError> {
+    let options = HtmlOptions {
+        allow_dangerous_html,
+        ..Default::default()
+    };
+
+    Ok(crate::parse_to_html(source, &options)?)
+}
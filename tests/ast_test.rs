@@ -0,0 +1,146 @@
+#![cfg(feature = "serde")]
+
+use markdown_rs::ast::*;
+use serde_json::json;
+
+#[test]
+fn image_reference_remark_json() {
+    // remark-parse output for `![alpha][Bravo]`, positions stripped.
+    let remark = json!({
+        "type": "imageReference",
+        "alt": "alpha",
+        "label": "Bravo",
+        "identifier": "bravo",
+        "referenceType": "full"
+    });
+
+    let image_reference = ImageReference {
+        alt: Some("alpha".into()),
+        identifier: "bravo".into(),
+        label: Some("Bravo".into()),
+        reference_type: ReferenceType::Full,
+        data: None,
+    };
+
+    assert_eq!(serde_json::to_value(&image_reference).unwrap(), remark);
+
+    let source = remark.to_string();
+
+    let parsed: ImageReference = serde_json::from_str(&source).unwrap();
+
+    assert_eq!(parsed, image_reference);
+}
+
+#[test]
+fn definition_json_keys() {
+    let definition = Definition {
+        identifier: "alpha".into(),
+        label: Some("Alpha".into()),
+        url: "https://example.com".into(),
+        title: None,
+        data: None,
+    };
+
+    let value = serde_json::to_value(&definition).unwrap();
+
+    let mut keys = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+
+    keys.sort();
+
+    assert_eq!(keys, ["identifier", "label", "title", "type", "url"]);
+
+    assert_eq!(
+        value,
+        json!({
+            "type": "definition",
+            "identifier": "alpha",
+            "label": "Alpha",
+            "url": "https://example.com",
+            "title": null
+        })
+    );
+}
+
+#[test]
+fn ordered_list_round_trip() {
+    let mut item = ListItem::new(Some(true));
+
+    item.spread = Some(false);
+
+    item.add_child_node(Node::from(Code {
+        value: "done".into(),
+        lang: None,
+        meta: None,
+        data: None,
+    }))
+    .unwrap();
+
+    let mut list = List::new(true, Some(3));
+
+    list.spread = Some(true);
+
+    list.add_child(item).unwrap();
+    list.add_child(ListItem::new(Some(false))).unwrap();
+
+    let expected = json!({
+        "type": "list",
+        "ordered": true,
+        "start": 3,
+        "spread": true,
+        "children": [
+            {
+                "type": "listItem",
+                "checked": true,
+                "spread": false,
+                "children": [
+                    { "type": "code", "value": "done", "lang": null, "meta": null }
+                ]
+            },
+            {
+                "type": "listItem",
+                "checked": false,
+                "spread": null,
+                "children": []
+            }
+        ]
+    });
+
+    assert_eq!(serde_json::to_value(&list).unwrap(), expected);
+
+    let source = expected.to_string();
+
+    let parsed: List = serde_json::from_str(&source).unwrap();
+
+    assert_eq!(parsed, list);
+}
+
+#[test]
+fn node_kind_matches_type_tag() {
+    let nodes = [
+        Node::ThematicBreak(ThematicBreak { data: None }),
+        Node::InlineCode(InlineCode {
+            value: "a".into(),
+            data: None,
+        }),
+        Node::ListItem(ListItem::new(None)),
+        Node::from(ImageReference {
+            alt: None,
+            identifier: "a".into(),
+            label: None,
+            reference_type: ReferenceType::Shortcut,
+            data: None,
+        }),
+    ];
+
+    for node in nodes {
+        assert_eq!(
+            serde_json::to_value(&node).unwrap()["type"],
+            serde_json::to_value(node.kind()).unwrap()
+        );
+    }
+}
@@ -0,0 +1,51 @@
+/* Exercises the C API against the static library, see .github/workflows/capi.yml. */
+
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "markdown_rs.h"
+
+int main(void) {
+    const char *source = "# Title\n\nSome *emphasis* and <b>.\n";
+
+    MdError error = {MdStatus_Panic, 42};
+    MdDocument *document = md_parse(source, strlen(source), &error);
+
+    assert(document != NULL);
+    assert(error.code == MdStatus_Ok);
+
+    MdBuffer html = md_render_html(document);
+
+    assert(strcmp(html.data,
+                  "<h1>Title</h1>\n<p>Some <em>emphasis</em> and &lt;b&gt;.</p>\n") == 0);
+    assert(html.len == strlen(html.data));
+
+    md_buffer_free(html);
+
+    MdBuffer json = md_document_to_json(document);
+
+    assert(strncmp(json.data, "{\"type\":\"root\"", 14) == 0);
+
+    md_buffer_free(json);
+    md_document_free(document);
+
+    const char invalid[] = "ok \xF0\x28";
+
+    assert(md_parse(invalid, sizeof(invalid) - 1, &error) == NULL);
+    assert(error.code == MdStatus_InvalidUtf8);
+    assert(error.offset == 3);
+
+    assert(md_parse(NULL, 0, NULL) == NULL);
+
+    MdBuffer none = md_render_html(NULL);
+
+    assert(none.data == NULL);
+
+    md_buffer_free(none);
+    md_document_free(NULL);
+
+    puts("ok");
+
+    return 0;
+}
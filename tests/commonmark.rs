@@ -0,0 +1,185 @@
+//! CommonMark spec compliance harness.
+//!
+//! Every example of `tests/spec/commonmark-0.31.2.json` is parsed and rendered to HTML.
+//! `commonmark_baseline` checks that no example panics and that the examples listed in
+//! `tests/spec/commonmark-passing.txt` still pass, `commonmark_report` (ignored by default)
+//! prints the per-section compliance report:
+//!
+//! ```shell
+//! COMMONMARK_SECTION=links cargo test --test commonmark -- --ignored --nocapture
+//! ```
+//!
+//! `COMMONMARK_SECTION` filters examples by a case-insensitive substring of their section name.
+
+use std::{collections::BTreeMap, fs::read_to_string, path::PathBuf};
+
+use markdown_rs::{html::HtmlOptions, parser::Parser};
+use serde_json::Value;
+
+struct Example {
+    number: u64,
+    section: String,
+    markdown: String,
+    html: String,
+}
+
+fn spec_path(name: &str) -> PathBuf {
+    let dir: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+
+    dir.join("tests/spec").join(name)
+}
+
+fn load_examples() -> Vec<Example> {
+    let json = read_to_string(spec_path("commonmark-0.31.2.json")).unwrap();
+
+    let Value::Array(examples) = serde_json::from_str(&json).unwrap() else {
+        panic!("expect an array of examples");
+    };
+
+    let filter = std::env::var("COMMONMARK_SECTION")
+        .ok()
+        .map(|section| section.to_lowercase());
+
+    examples
+        .into_iter()
+        .map(|example| Example {
+            number: example["example"].as_u64().unwrap(),
+            section: example["section"].as_str().unwrap().to_owned(),
+            markdown: example["markdown"].as_str().unwrap().to_owned(),
+            html: example["html"].as_str().unwrap().to_owned(),
+        })
+        .filter(|example| match &filter {
+            Some(filter) => example.section.to_lowercase().contains(filter),
+            None => true,
+        })
+        .collect()
+}
+
+fn load_baseline() -> Vec<u64> {
+    read_to_string(spec_path("commonmark-passing.txt"))
+        .unwrap()
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.parse().unwrap())
+        .collect()
+}
+
+fn render(markdown: &str) -> String {
+    let document = Parser::new(markdown).parse().unwrap();
+
+    document.to_html(&HtmlOptions {
+        allow_dangerous_html: true,
+        allow_dangerous_protocols: true,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn commonmark_baseline() {
+    let baseline = load_baseline();
+
+    let mut regressions = vec![];
+
+    for example in load_examples() {
+        let html = render(&example.markdown);
+
+        if baseline.contains(&example.number) && html != example.html {
+            regressions.push(example.number);
+        }
+    }
+
+    assert!(
+        regressions.is_empty(),
+        "examples regressed: {:?}",
+        regressions
+    );
+}
+
+#[test]
+#[ignore]
+fn commonmark_report() {
+    let mut sections = BTreeMap::<u64, (String, usize, usize)>::new();
+
+    let mut failed = vec![];
+
+    for example in load_examples() {
+        let html = render(&example.markdown);
+
+        let passed = html == example.html;
+
+        // keyed by the first example number to keep spec order.
+        let entry = sections
+            .values_mut()
+            .find(|(section, _, _)| *section == example.section);
+
+        let (_, pass, total) = match entry {
+            Some(entry) => entry,
+            None => sections
+                .entry(example.number)
+                .or_insert((example.section.clone(), 0, 0)),
+        };
+
+        *total += 1;
+
+        if passed {
+            *pass += 1;
+        } else {
+            failed.push(example);
+        }
+    }
+
+    for example in &failed {
+        println!(
+            "example {} ({}) failed\n--- markdown\n{}--- expected\n{}--- actual\n{}",
+            example.number,
+            example.section,
+            example.markdown,
+            example.html,
+            render(&example.markdown)
+        );
+    }
+
+    let (mut pass, mut total) = (0, 0);
+
+    for (section, section_pass, section_total) in sections.values() {
+        println!("{:>4}/{:<4} {}", section_pass, section_total, section);
+
+        pass += section_pass;
+        total += section_total;
+    }
+
+    println!("{}/{} examples passed", pass, total);
+}
+
+#[test]
+fn commonmark_spans() {
+    for example in load_examples() {
+        let source = example.markdown.as_str();
+
+        let (document, spans) = Parser::new(source).parse_with_spans().unwrap();
+
+        assert_eq!(document, Parser::new(source).parse().unwrap());
+        assert_eq!(spans.len(), document.descendants().count());
+
+        for (path, span) in spans.iter() {
+            assert!(
+                source.get(span.clone()).is_some(),
+                "example {}: {:?} out of the source",
+                example.number,
+                path
+            );
+
+            // children are within their parent.
+            if let Some(parent) = spans.get(&path[..path.len() - 1]) {
+                assert!(
+                    parent.start <= span.start && span.end <= parent.end,
+                    "example {}: {:?} at {:?} out of its parent at {:?}",
+                    example.number,
+                    path,
+                    span,
+                    parent
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Memory of owned versus interned trees, measured with a counting allocator.
+//!
+//! The allocator counts the whole process, so this file holds a single test.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use markdown_rs::{
+    ast::{intern::Interner, Node},
+    parser::Parser,
+};
+
+/// Counts the bytes allocated by the whole process.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocated() -> usize {
+    ALLOCATED.load(Ordering::Relaxed)
+}
+
+#[test]
+fn interned_code_languages() {
+    const BLOCKS: usize = 10_000;
+
+    let source = "```typescript\nlet a = 1;\n```\n\n".repeat(BLOCKS);
+
+    let document = Parser::new(source.as_str()).parse().unwrap();
+
+    let start = allocated();
+
+    let owned = document.clone().into_owned();
+
+    let owned_bytes = allocated() - start;
+
+    let start = allocated();
+
+    let mut interner = Interner::new();
+
+    interner.collect(&document);
+
+    let interned = document.clone().into_interned(&interner);
+
+    let interned_bytes = allocated() - start;
+
+    assert_eq!(interner.len(), 1);
+    assert_eq!(owned, interned);
+    assert!(interned.children.iter().all(|node| match node {
+        Node::Code(code) => code.lang.as_deref() == Some("typescript"),
+        _ => false,
+    }));
+
+    // every owned block has its own copy of the language.
+    let saved = owned_bytes - interned_bytes;
+
+    assert!(
+        saved >= BLOCKS * "typescript".len() * 9 / 10,
+        "owned {} bytes, interned {} bytes",
+        owned_bytes,
+        interned_bytes
+    );
+}
@@ -1,16 +1,101 @@
 use markdown_rs::lexer::*;
+use proptest::prelude::*;
 
 mod utils;
 
+/// Checks the source slices of the tokens of `source` are non-empty, char-boundary aligned and
+/// concatenate back to `source`.
+fn check_tiling(source: &str) -> Result<(), String> {
+    check_tiling_with(source, LexerOptions::default())
+}
+
+/// Same as [`check_tiling`], lexing with `options`.
+fn check_tiling_with(source: &str, options: LexerOptions) -> Result<(), String> {
+    let mut end = 0;
+
+    for token in Lexer::with_options(source, options) {
+        log::debug!("{:?}", token);
+
+        let range = token.to_range();
+
+        if range.start != end || range.is_empty() || source.get(range.clone()).is_none() {
+            return Err(format!("{:?} after offset {} of {:?}", token, end, source));
+        }
+
+        end = range.end;
+    }
+
+    if end != source.len() {
+        return Err(format!("tokens end at {} of {:?}", end, source));
+    }
+
+    Ok(())
+}
+
 #[test]
 fn lexer_test_from_files() {
     pretty_env_logger::init();
 
-    utils::read_test_data(|s| {
-        let lexer = Lexer::new(s);
+    utils::read_test_data(|s| check_tiling(s).unwrap());
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(5_000))]
 
-        for token in lexer {
-            log::debug!("{:?}", token);
-        }
-    });
+    #[test]
+    fn lexer_test_tiling(source in "[-\\\\`*_{}\\[\\]()#+.!|><:aZ9µé你😀 \t\r\n]{0,64}") {
+        prop_assert_eq!(check_tiling(&source), Ok(()));
+    }
+
+    #[test]
+    fn lexer_test_tiling_with_options(source in "[-\\\\`*_{}\\[\\]()#+.!|><:%aZ9µé你😀 \t\r\n]{0,64}") {
+        let options = LexerOptions {
+            extra_keychars: &['%', 'µ', 'Z'],
+            plain_chars: &['*', '{', '\\', ' ', '\n'],
+        };
+
+        prop_assert_eq!(check_tiling_with(&source, options), Ok(()));
+    }
+}
+
+#[test]
+fn lexer_test_non_ascii() {
+    let source = "*µs* 你好\tend";
+
+    let lexer = Lexer::new(source);
+
+    let text = lexer
+        .map(|token| &source[token.to_range()])
+        .collect::<String>();
+
+    assert_eq!(text, source);
+}
+
+#[test]
+fn lexer_test_rollback() {
+    let mut lexer = Lexer::new("# title");
+
+    let pounds = lexer.next_token();
+
+    lexer.next_token();
+    lexer.rollback_to(pounds);
+
+    let tokens = lexer.collect::<Vec<_>>();
+
+    assert_eq!(
+        tokens,
+        [
+            Token::Pounds(0..1),
+            Token::WhiteSpaces(1..2),
+            Token::PlainText(2..7)
+        ]
+    );
+
+    // a token out of the source ends the stream.
+    let mut lexer = Lexer::new("a");
+
+    lexer.rollback_to(Token::Eof(10..10));
+
+    assert_eq!(lexer.next_token(), Token::Eof(10..10));
+    assert_eq!(lexer.next_token(), Token::Eof(1..1));
 }
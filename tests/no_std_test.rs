@@ -0,0 +1,53 @@
+//! Uses only `core` and `alloc`, as a `no_std` consumer would. Run it against the `no_std`
+//! build with `cargo test --no-default-features --features alloc --test no_std_test`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use markdown_rs::{
+    ast::{builder::DocumentBuilder, Node},
+    lexer::{Lexer, Token},
+    parser::Parser,
+};
+
+#[test]
+fn lex() {
+    let source = "# Title\n\n*a*";
+
+    let mut lexer = Lexer::new(source);
+
+    let mut tokens = Vec::new();
+
+    loop {
+        match lexer.next_token() {
+            Token::Eof(_) => break,
+            token => tokens.push(token),
+        }
+    }
+
+    assert!(matches!(tokens[0], Token::Pounds(_)));
+    assert_eq!(tokens.last().unwrap().to_range().end, source.len());
+}
+
+#[test]
+fn build_and_parse() {
+    let built = DocumentBuilder::new()
+        .heading(1, |h| {
+            h.text("Title");
+        })
+        .paragraph(|p| {
+            p.emphasis(|e| {
+                e.text("a");
+            });
+        })
+        .finish()
+        .unwrap();
+
+    let parsed = Parser::new("# Title\n\n*a*").parse().unwrap();
+
+    assert_eq!(parsed, built);
+    assert!(matches!(parsed.children[1], Node::Paragraph(_)));
+}
@@ -0,0 +1,129 @@
+use markdown_rs::{html::HtmlOptions, lexer::Lexer, parser::Parser};
+use proptest::prelude::*;
+
+/// Fragments of markdown syntax, random sequences of them reach far more parser states than
+/// random characters.
+const ALPHABET: &[&str] = &[
+    "a",
+    "b c",
+    "µ",
+    "你好",
+    "\u{0}",
+    "\u{FFFD}",
+    " ",
+    "  ",
+    "    ",
+    "\t",
+    "\n",
+    "\r\n",
+    "\r",
+    "*",
+    "**",
+    "_",
+    "__",
+    "`",
+    "```",
+    "~~~",
+    "#",
+    "# ",
+    "######",
+    "=",
+    "===",
+    "-",
+    "---",
+    "- ",
+    "+ ",
+    "1. ",
+    "1) ",
+    "> ",
+    ">",
+    "[",
+    "]",
+    "(",
+    ")",
+    "![",
+    "[a]: /url",
+    "\"",
+    "'",
+    "<",
+    ">",
+    "<div>",
+    "</div>",
+    "<!--",
+    "-->",
+    "<?",
+    "<!X",
+    "<![CDATA[",
+    "<pre>",
+    "<a href=\"x\">",
+    "&amp;",
+    "&#35;",
+    "&#x0;",
+    "&",
+    ";",
+    "\\",
+    "\\\n",
+    "http://a.b",
+    "<http://a.b>",
+    "<a@b.c>",
+    "|",
+    ":",
+    "|---|",
+    ":-:",
+];
+
+fn markdown() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(ALPHABET), 0..64).prop_map(|parts| parts.concat())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2_000))]
+
+    #[test]
+    fn parse_never_panics(source in markdown()) {
+        let (document, spans) = Parser::new(source.as_str()).parse_with_spans().unwrap();
+
+        // every node lies within the source, on char boundaries.
+        for (path, span) in spans.iter() {
+            prop_assert!(
+                span.start <= span.end && span.end <= source.len(),
+                "span {:?} of {:?} out of the source",
+                span,
+                path
+            );
+            prop_assert!(
+                source.is_char_boundary(span.start) && source.is_char_boundary(span.end),
+                "span {:?} of {:?} not on char boundaries",
+                span,
+                path
+            );
+        }
+
+        document.to_html(&HtmlOptions::default());
+    }
+
+    #[test]
+    fn arbitrary_text_never_panics(source in any::<String>()) {
+        Parser::new(source.as_str()).parse().unwrap();
+
+        Lexer::new(source.as_str()).count();
+    }
+}
+
+#[test]
+fn fuzz_corpus() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/parse");
+
+    for entry in dir.read_dir().unwrap() {
+        let bytes = std::fs::read(entry.unwrap().path()).unwrap();
+
+        let source = String::from_utf8_lossy(&bytes);
+
+        Lexer::new(&source).count();
+
+        Parser::new(source.as_ref())
+            .parse()
+            .unwrap()
+            .to_html(&HtmlOptions::default());
+    }
+}
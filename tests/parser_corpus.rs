@@ -0,0 +1,116 @@
+//! Every file of `tests/data` parses, into a tree that holds invariants cheap to check on any
+//! document.
+
+use std::ops::Range;
+
+use markdown_rs::{
+    ast::{spans::Spans, validate, Heading, Node},
+    parser::Parser,
+};
+
+mod utils;
+
+/// Checks the invariants of `node`, at `path` and spanning `parent` or less, and of its
+/// descendants.
+fn check(
+    name: &str,
+    source: &str,
+    spans: &Spans,
+    node: &Node,
+    path: &mut Vec<usize>,
+    parent: Range<usize>,
+) {
+    let at = || format!("{}: {:?} at {:?}", name, node.kind(), path);
+
+    let span = match spans.get(path) {
+        Some(span) => {
+            assert!(span.start <= span.end, "{}: reversed span {:?}", at(), span);
+            assert!(
+                parent.start <= span.start && span.end <= parent.end,
+                "{}: span {:?} out of its parent {:?}",
+                at(),
+                span,
+                parent
+            );
+            assert!(
+                source.get(span.clone()).is_some(),
+                "{}: span {:?} not on char boundaries",
+                at(),
+                span
+            );
+
+            span
+        }
+        None => parent,
+    };
+
+    match node {
+        Node::Text(text) => assert!(!text.value.is_empty(), "{}: empty text", at()),
+        Node::Heading(heading) => assert!(
+            Heading::DEPTHS.contains(&heading.depth),
+            "{}: depth {}",
+            at(),
+            heading.depth
+        ),
+        Node::List(list) => {
+            for child in &list.children {
+                assert!(
+                    matches!(child, Node::ListItem(_)),
+                    "{}: {:?} child",
+                    at(),
+                    child.kind()
+                );
+            }
+        }
+        _ => {}
+    }
+
+    for (index, child) in node.children().unwrap_or_default().iter().enumerate() {
+        path.push(index);
+
+        check(name, source, spans, child, path, span.clone());
+
+        path.pop();
+    }
+}
+
+#[test]
+fn corpus() {
+    let data = utils::test_data();
+
+    assert!(!data.is_empty());
+
+    for (name, source) in &data {
+        let (document, spans) = match Parser::new(source.as_str()).parse_with_spans() {
+            Ok(parsed) => parsed,
+            Err(err) => panic!("{}: {}", name, err),
+        };
+
+        assert!(!document.children.is_empty(), "{}: empty document", name);
+        assert!(!spans.is_empty(), "{}: no spans", name);
+
+        for (index, child) in document.children.iter().enumerate() {
+            check(
+                name,
+                source,
+                &spans,
+                child,
+                &mut vec![index],
+                0..source.len(),
+            );
+        }
+
+        if let Err(errors) = validate(&document) {
+            panic!("{}: {:?}", name, errors);
+        }
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&document).unwrap();
+
+            let parsed: markdown_rs::ast::Document = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(parsed, document, "{}: serde round trip", name);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Classic denial-of-service inputs of markdown parsers, after the cmark pathological tests.
+//!
+//! Each one must parse, and render, well within [`LIMIT`] even in debug builds: a quadratic
+//! delimiter or bracket algorithm takes minutes on them.
+
+use std::time::{Duration, Instant};
+
+use markdown_rs::{html::HtmlOptions, parser::Parser};
+
+/// Generous bound of parsing and rendering one input, absorbing debug builds and slow runners.
+const LIMIT: Duration = Duration::from_secs(1);
+
+const N: usize = 50_000;
+
+fn cases() -> Vec<(&'static str, String)> {
+    vec![
+        ("link openers", "[".repeat(N)),
+        ("link openers and text", "[a".repeat(N)),
+        ("link closers", "a]".repeat(N)),
+        (
+            "nested brackets",
+            format!("{}a{}", "[".repeat(N), "]".repeat(N)),
+        ),
+        (
+            "nested links",
+            format!("{}a{}", "[".repeat(N), "](b)".repeat(N)),
+        ),
+        ("image openers", "![".repeat(N)),
+        ("unclosed links", "[a](b".repeat(N)),
+        ("unclosed angle links", "[a](<b".repeat(N)),
+        ("unclosed titles", "[a](b \"c".repeat(N)),
+        (
+            "links after openers",
+            format!("{}{}", "[".repeat(N), "[a](b)".repeat(N)),
+        ),
+        ("stars", format!("a{}", "*".repeat(N))),
+        ("underscores", format!("a{}", "_".repeat(N))),
+        ("alternating delimiters", format!("a{}", "*_".repeat(N))),
+        ("emphasis openers", "_a ".repeat(N)),
+        ("emphasis closers", "a_ ".repeat(N)),
+        ("mismatched delimiters", "*a_ ".repeat(N)),
+        ("strong ladder", "a**b".repeat(N)),
+        ("multiple of 3 ladder", format!("a**b{}", "c* ".repeat(N))),
+        (
+            "nested strong emphasis",
+            format!("{}b{}", "*a **a ".repeat(N / 2), " a** a*".repeat(N / 2)),
+        ),
+        ("link openers and emphasis closers", "[ a_".repeat(N)),
+        ("backticks", "e`".repeat(N)),
+        (
+            "code span runs",
+            (1..300)
+                .map(|n| "`".repeat(n))
+                .collect::<Vec<_>>()
+                .join("a"),
+        ),
+        ("entities", "&a".repeat(N)),
+        ("autolinks", "<a".repeat(N)),
+        ("nested block quotes", format!("{}a", "> ".repeat(N))),
+        ("nested lists", format!("{}a", "- ".repeat(N))),
+        (
+            "table columns",
+            format!("{}\n{}", "|a".repeat(N), "|-".repeat(N)),
+        ),
+    ]
+}
+
+#[test]
+fn pathological_inputs() {
+    for (name, source) in cases() {
+        let start = Instant::now();
+
+        Parser::new(source.as_str())
+            .parse()
+            .unwrap()
+            .to_html(&HtmlOptions::default());
+
+        let elapsed = start.elapsed();
+
+        assert!(
+            elapsed < LIMIT,
+            "{} took {:?}, over {:?}",
+            name,
+            elapsed,
+            LIMIT
+        );
+    }
+}
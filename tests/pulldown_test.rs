@@ -0,0 +1,115 @@
+#![cfg(feature = "pulldown-cmark")]
+
+use markdown_rs::{
+    ast::{resolve::resolve_references, Node},
+    parser::Parser,
+    pulldown,
+};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Tag, TextMergeStream};
+
+const SOURCE: &str = r#"# Release *notes*
+
+Setext heading
+--------------
+
+A paragraph with *emphasis*, **strong**, `code` and a [link](https://example.com "Title"),
+spanning lines with a hard
+break and an entity &amp; an escape \*.
+
+See [the docs][docs], [docs][] and [docs], or <https://autolink.example>.
+
+![An image](/logo.png)
+
+> A quote
+>
+> - tight
+> - list
+
+1. loose
+
+2. ordered
+   - nested
+   - list
+
+3) other
+
+```rust ignore
+fn main() {}
+```
+
+    indented?
+
+<div>
+block html
+</div>
+
+Inline <span>html</span>.
+
+***
+
+[docs]: https://docs.rs "Docs"
+"#;
+
+/// Events of `source` parsed by pulldown-cmark, adjacent text merged.
+fn expected(source: &str) -> Vec<Event<'_>> {
+    TextMergeStream::new(pulldown_cmark::Parser::new(source)).collect()
+}
+
+#[test]
+fn events_match_pulldown_cmark() {
+    let document = Parser::new(SOURCE).parse().unwrap();
+
+    let actual = TextMergeStream::new(pulldown::events(&document)).collect::<Vec<_>>();
+
+    let mut expected = expected(SOURCE);
+
+    // code blocks always yield fenced code blocks.
+    for event in &mut expected {
+        if let Event::Start(Tag::CodeBlock(kind @ CodeBlockKind::Indented)) = event {
+            *kind = pulldown_cmark::CodeBlockKind::Fenced("".into());
+        }
+    }
+
+    assert_eq!(actual.len(), expected.len());
+
+    for (index, (actual, expected)) in actual.iter().zip(&expected).enumerate() {
+        assert_eq!(actual, expected, "event {}", index);
+    }
+}
+
+/// Building from events loses what pulldown-cmark resolves: the tree matches the parsed one
+/// with references resolved and definitions dropped.
+#[test]
+fn from_events_resolves_references() {
+    let mut document = Parser::new(SOURCE).parse().unwrap();
+
+    resolve_references(&mut document);
+
+    document
+        .children
+        .retain(|child| !matches!(child, Node::Definition(_)));
+
+    assert_eq!(pulldown::from_events(expected(SOURCE)), document);
+}
+
+/// GFM constructs, built from pulldown-cmark events as our parser only reads tables so far.
+#[test]
+fn gfm_events_round_trip() {
+    let source =
+        "| a | b |\n|:--|--:|\n| ~~c~~ | d[^1] |\n\n- [x] done\n- [ ] todo\n\n[^1]: A *note*.\n";
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS;
+
+    let expected =
+        TextMergeStream::new(pulldown_cmark::Parser::new_ext(source, options)).collect::<Vec<_>>();
+
+    let document = pulldown::from_events(expected.clone());
+
+    assert_eq!(
+        TextMergeStream::new(pulldown::events(&document)).collect::<Vec<_>>(),
+        expected
+    );
+}
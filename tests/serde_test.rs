@@ -0,0 +1,470 @@
+#![cfg(feature = "serde")]
+
+use std::{fs::read_to_string, path::PathBuf};
+
+use markdown_rs::{ast::*, parser::Parser};
+use serde_json::Value;
+
+/// A document with a node of every kind.
+fn every_kind() -> Document<'static> {
+    let text = |value: &'static str| {
+        Node::Text(Text {
+            value: value.into(),
+            data: None,
+        })
+    };
+
+    let mut heading = Heading::new(2).unwrap();
+
+    heading.children = vec![text("Title with \"quotes\"\n")];
+
+    let mut list = List::new(true, Some(3));
+
+    list.spread = Some(false);
+
+    let mut item = ListItem::new(Some(true));
+
+    item.spread = Some(false);
+    item.children = vec![Node::Paragraph(Paragraph {
+        children: vec![
+            Node::Emphasis(Emphasis {
+                children: vec![text("em")],
+                data: None,
+            }),
+            Node::Strong(Strong {
+                children: vec![text("strong")],
+                data: None,
+            }),
+            Node::Delete(Delete {
+                children: vec![text("gone")],
+                data: None,
+            }),
+            Node::InlineCode(InlineCode {
+                value: "x < y".into(),
+                data: None,
+            }),
+            Node::Break(Break { data: None }),
+            Node::from(Link {
+                children: vec![text("link")],
+                url: "https://example.com".into(),
+                title: None,
+                data: None,
+            }),
+            Node::from(LinkReference {
+                children: vec![text("ref")],
+                identifier: "ref".into(),
+                label: Some("Ref".into()),
+                reference_type: ReferenceType::Full,
+                data: None,
+            }),
+            Node::from(Image {
+                url: "/a.png".into(),
+                title: Some("A".into()),
+                alt: Some("a".into()),
+                data: None,
+            }),
+            Node::from(ImageReference {
+                alt: Some("b".into()),
+                identifier: "b".into(),
+                label: Some("b".into()),
+                reference_type: ReferenceType::Collapsed,
+                data: None,
+            }),
+            Node::FootnoteReference(FootnoteReference {
+                identifier: "1".into(),
+                label: Some("1".into()),
+                data: None,
+            }),
+            Node::Html(Html {
+                value: "<br>".into(),
+                data: None,
+            }),
+        ],
+        data: None,
+    })];
+
+    list.children = vec![Node::ListItem(item)];
+
+    Document {
+        children: vec![
+            Node::Heading(heading),
+            Node::ThematicBreak(ThematicBreak { data: None }),
+            Node::Blockquote(Blockquote {
+                children: vec![Node::List(list)],
+                data: None,
+            }),
+            Node::from(Code {
+                value: "fn main() {}\n\ttabbed".into(),
+                lang: Some("rust".into()),
+                meta: None,
+                data: None,
+            }),
+            Node::from(Definition {
+                identifier: "ref".into(),
+                label: Some("Ref".into()),
+                url: "/ref".into(),
+                title: None,
+                data: None,
+            }),
+            Node::Table(Table {
+                children: vec![Node::TableRow(TableRow {
+                    children: vec![
+                        Node::TableCell(TableCell {
+                            children: vec![text("a")],
+                            data: None,
+                        }),
+                        Node::TableCell(TableCell {
+                            children: vec![],
+                            data: None,
+                        }),
+                    ],
+                    data: None,
+                })],
+                align: vec![AlignType::Left, AlignType::None],
+                data: None,
+            }),
+            Node::from(FootnoteDefinition {
+                children: vec![Node::Paragraph(Paragraph {
+                    children: vec![text("note")],
+                    data: None,
+                })],
+                identifier: "1".into(),
+                label: Some("1".into()),
+                data: None,
+            }),
+            Node::from(Custom {
+                children: vec![text("page")],
+                name: "wikilink".into(),
+                attributes: [("target".into(), "Page".into())].into(),
+                data: None,
+            }),
+        ],
+        data: None,
+    }
+}
+
+#[test]
+fn round_trip_every_kind() {
+    let document = every_kind();
+
+    let kinds = document
+        .descendants()
+        .map(Node::kind)
+        .collect::<std::collections::BTreeSet<_>>();
+
+    // every kind but the document itself.
+    assert_eq!(kinds.len(), 25);
+
+    let json = serde_json::to_string(&document).unwrap();
+
+    let parsed: Document = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(parsed, document);
+
+    let value = serde_json::to_value(&document).unwrap();
+
+    assert_eq!(
+        value["children"][5]["align"],
+        serde_json::json!(["left", null])
+    );
+    assert_eq!(value["children"][2]["children"][0]["start"], 3);
+}
+
+/// `alignType` and `referenceType` values are mdast strings, `null` for no alignment.
+#[test]
+fn enum_values() {
+    let align = [
+        AlignType::Left,
+        AlignType::None,
+        AlignType::Center,
+        AlignType::Right,
+    ];
+
+    let value = serde_json::to_value(&align).unwrap();
+
+    assert_eq!(value, serde_json::json!(["left", null, "center", "right"]));
+    assert_eq!(
+        serde_json::from_value::<Vec<AlignType>>(value).unwrap(),
+        align
+    );
+
+    for (reference_type, name) in [
+        (ReferenceType::Shortcut, "shortcut"),
+        (ReferenceType::Collapsed, "collapsed"),
+        (ReferenceType::Full, "full"),
+    ] {
+        let value = serde_json::to_value(&reference_type).unwrap();
+
+        assert_eq!(value, name);
+        assert_eq!(
+            serde_json::from_value::<ReferenceType>(value).unwrap(),
+            reference_type
+        );
+    }
+
+    assert!(serde_json::from_value::<ReferenceType>(serde_json::json!("Full")).is_err());
+    assert!(serde_json::from_value::<AlignType>(serde_json::json!("none")).is_err());
+}
+
+/// Heading depths outside of `1..=6` fail to deserialize.
+#[test]
+fn heading_depth() {
+    let heading = |depth: u64| {
+        serde_json::from_value::<Node>(serde_json::json!({
+            "type": "heading",
+            "depth": depth,
+            "children": [{ "type": "text", "value": "a" }],
+        }))
+    };
+
+    assert!(matches!(&heading(6).unwrap(), Node::Heading(heading) if heading.depth == 6));
+
+    assert_eq!(
+        heading(9).unwrap_err().to_string(),
+        "heading depth must be in 1..=6, found 9"
+    );
+    assert!(heading(0).is_err());
+    assert!(heading(300).is_err());
+}
+
+/// Data set by a transform serializes under `data`, and only where set.
+#[test]
+fn round_trip_node_data() {
+    /// Records the resolved url of links.
+    struct Resolve;
+
+    impl<'cx> VisitorMut<'cx> for Resolve {
+        fn visit_link(&mut self, node: &mut Link<'cx>) -> Transform<'cx> {
+            let url = format!("https://example.com{}", node.url);
+
+            node.data = Some(Box::new([("resolved".into(), url.as_str().into())].into()));
+
+            Transform::Keep
+        }
+    }
+
+    let document = Parser::new("[a](/a) b").parse().unwrap();
+
+    let mut node = Node::Document(document);
+
+    walk_mut(&mut node, &mut Resolve);
+
+    let value = serde_json::to_value(&node).unwrap();
+
+    assert_eq!(
+        value["children"][0]["children"][0]["data"],
+        serde_json::json!({ "resolved": "https://example.com/a" })
+    );
+    assert!(value["children"][0].get("data").is_none());
+
+    let parsed: Node = serde_json::from_value(value).unwrap();
+
+    assert_eq!(parsed, node);
+}
+
+/// Indexes can be cached as JSON.
+#[test]
+fn round_trip_document_index() {
+    let source = "# A\n\n[b]: /c 'd'\n\n[^e]: f\n";
+
+    let (_, index) = Parser::new(source).parse_indexed().unwrap();
+
+    let value = serde_json::to_value(&index).unwrap();
+
+    assert_eq!(
+        value["headings"][0]["span"],
+        serde_json::json!({ "start": 0, "end": 3 })
+    );
+    assert_eq!(value["definitions"]["b"]["url"], "/c");
+
+    let parsed: index::DocumentIndex = serde_json::from_value(value).unwrap();
+
+    assert_eq!(parsed, index);
+}
+
+/// The `type` tag of every node, and of every kind, is its [`NodeKind::as_str`].
+#[test]
+fn tags_match_kinds() {
+    let document = every_kind();
+
+    let nodes = std::iter::once(Node::Document(document.clone()))
+        .chain(document.descendants().cloned())
+        .collect::<Vec<_>>();
+
+    let kinds = nodes
+        .iter()
+        .map(Node::kind)
+        .collect::<std::collections::BTreeSet<_>>();
+
+    // `NodeKind` orders like `ALL`.
+    assert_eq!(kinds.into_iter().collect::<Vec<_>>(), NodeKind::ALL);
+
+    for node in &nodes {
+        let value = serde_json::to_value(node).unwrap();
+
+        assert_eq!(value["type"], node.kind().as_str());
+
+        let parsed: Node = serde_json::from_value(value).unwrap();
+
+        assert_eq!(parsed.kind(), node.kind());
+    }
+
+    for kind in NodeKind::ALL {
+        assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+    }
+}
+
+/// Trees written with the `document` root tag of earlier releases still read.
+#[test]
+fn legacy_document_tag() {
+    let json = r#"{"type":"document","children":[{"type":"text","value":"a"}]}"#;
+
+    let document: Document = serde_json::from_str(json).unwrap();
+    let node: Node = serde_json::from_str(json).unwrap();
+
+    assert_eq!(node, Node::Document(document.clone()));
+    assert_eq!(document.children.len(), 1);
+
+    let kind: NodeKind = serde_json::from_str(r#""document""#).unwrap();
+
+    assert_eq!(kind, NodeKind::Document);
+
+    assert!(serde_json::to_string(&document)
+        .unwrap()
+        .starts_with(r#"{"type":"root","#));
+}
+
+/// Compares `actual` to `expected` field by field, reporting the path of the first difference.
+///
+/// Absent fields and `null` fields are different: mdast tools tell them apart.
+fn compare(path: &str, expected: &Value, actual: &Value) -> Result<(), String> {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let Some(other) = actual.get(key) else {
+                    return Err(format!("{}.{}: missing", path, key));
+                };
+
+                compare(&format!("{}.{}", path, key), value, other)?;
+            }
+
+            match actual.keys().find(|key| !expected.contains_key(*key)) {
+                Some(key) => Err(format!("{}.{}: unexpected field", path, key)),
+                None => Ok(()),
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                return Err(format!(
+                    "{}: expected {} children, found {}",
+                    path,
+                    expected.len(),
+                    actual.len()
+                ));
+            }
+
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                compare(&format!("{}[{}]", path, index), expected, actual)?;
+            }
+
+            Ok(())
+        }
+        (expected, actual) if expected == actual => Ok(()),
+        (expected, actual) => Err(format!("{}: expected {}, found {}", path, expected, actual)),
+    }
+}
+
+/// Golden files in `tests/golden` pair a markdown document with its remark-parse
+/// (mdast-util-from-markdown) tree, positions removed.
+#[test]
+fn remark_golden_files() {
+    let dir: PathBuf = env!("CARGO_MANIFEST_DIR").into();
+
+    let mut count = 0;
+
+    for entry in dir.join("tests/golden").read_dir().unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+
+        let markdown = read_to_string(&path).unwrap();
+
+        let expected: Value =
+            serde_json::from_str(&read_to_string(path.with_extension("json")).unwrap()).unwrap();
+
+        let document = Parser::new(markdown.as_str()).parse().unwrap();
+
+        let actual = serde_json::to_value(&document).unwrap();
+
+        assert_eq!(actual["type"], "root");
+
+        if let Err(difference) = compare("root", &expected, &actual) {
+            panic!("{}: {}", path.display(), difference);
+        }
+
+        let json = serde_json::to_string(&document).unwrap();
+
+        let parsed: Document = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, document);
+
+        count += 1;
+    }
+
+    assert_eq!(count, 3);
+}
+
+/// Strips what mdast tools add around the tree itself: unist `position`s, and `data` unless
+/// its values are kept.
+fn strip(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.remove("position");
+
+            if !cfg!(feature = "json") {
+                object.remove("data");
+            }
+
+            object.values_mut().for_each(strip);
+        }
+        Value::Array(array) => array.iter_mut().for_each(strip),
+        _ => {}
+    }
+}
+
+/// `tests/remark/gfm.json` is remark-parse with remark-gfm output for `tests/remark/gfm.md`,
+/// positions and `data` included.
+#[test]
+fn deserialize_remark_output() {
+    fn owned<T: serde::de::DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/remark/gfm.json");
+
+    let mut expected: Value = serde_json::from_str(&read_to_string(path).unwrap()).unwrap();
+
+    let document: Document<'static> = owned(expected.clone());
+
+    let node: Node<'static> = owned(expected.clone());
+
+    assert_eq!(node, Node::Document(document.clone()));
+
+    let kinds = document
+        .descendants()
+        .map(Node::kind)
+        .collect::<std::collections::BTreeSet<_>>();
+
+    assert!(kinds.contains(&NodeKind::TableCell));
+    assert!(kinds.contains(&NodeKind::Link));
+
+    let actual = serde_json::to_value(&document).unwrap();
+
+    strip(&mut expected);
+
+    if let Err(difference) = compare("root", &expected, &actual) {
+        panic!("{}", difference);
+    }
+}
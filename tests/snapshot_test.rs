@@ -0,0 +1,74 @@
+//! Snapshots of the tokens, tree and HTML of every file of `tests/data`, in `tests/snapshots`.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to write them anew after an intended change, and review the
+//! diff.
+
+use std::fmt::Write;
+
+use markdown_rs::{html::HtmlOptions, lexer::Lexer, parser::Parser};
+
+mod utils;
+
+/// One token per line, with its source text.
+fn tokens(source: &str) -> String {
+    let mut out = String::new();
+
+    for token in Lexer::new(source) {
+        writeln!(out, "{:?} {:?}", token, &source[token.to_range()]).unwrap();
+    }
+
+    out
+}
+
+/// The tokens of `tests/data/tables.md` as JSON, one token per line.
+#[cfg(feature = "serde")]
+#[test]
+fn token_dump() {
+    let source = std::fs::read_to_string(
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/tables.md"),
+    )
+    .unwrap();
+
+    let tokens = Lexer::to_token_dump(&source);
+
+    let lines = tokens
+        .iter()
+        .map(|token| serde_json::to_string(token).unwrap())
+        .collect::<Vec<_>>();
+
+    let json = format!("[\n{}\n]\n", lines.join(",\n"));
+
+    utils::check_snapshot("tables.tokens.json", &json).unwrap();
+
+    // the dump reads back, and its text tiles the source.
+    let read: Vec<markdown_rs::lexer::TokenDump> = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(read, tokens);
+    assert_eq!(
+        read.iter().map(|token| &*token.text).collect::<String>(),
+        source
+    );
+}
+
+#[test]
+fn snapshots() {
+    let mut failures = vec![];
+
+    for (name, source) in utils::test_data() {
+        let document = Parser::new(source.as_str()).parse().unwrap();
+
+        let outputs = [
+            ("tokens", tokens(&source)),
+            ("tree", document.to_string()),
+            ("html", document.to_html(&HtmlOptions::default())),
+        ];
+
+        for (kind, actual) in outputs {
+            if let Err(failure) = utils::check_snapshot(&format!("{}.{}", name, kind), &actual) {
+                failures.push(failure);
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
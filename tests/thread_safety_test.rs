@@ -0,0 +1,155 @@
+//! Public types are `Send` and `Sync`, and parsing on many threads matches parsing on one.
+//!
+//! A type losing either trait, such as an `Rc` in a tree or an interner, fails to compile here.
+
+use std::thread;
+
+use markdown_rs::{
+    ast::{
+        blocks::BlockView, builder::DocumentBuilder, index::DocumentIndex, intern::Interner,
+        spans::Spans, stats::Stats, style::Style, visitors::*, *,
+    },
+    html::HtmlOptions,
+    lexer::{Lexer, LexerOptions, Token, TokenDump, TokenKind},
+    parser::{extension::LiteralAutolinks, Parser, ParserError, ParserOptions, TextEdit},
+    to_markdown::SerializeOptions,
+};
+
+mod utils;
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn send_sync() {
+    assert_send_sync::<Lexer<'_>>();
+    assert_send_sync::<LexerOptions>();
+    assert_send_sync::<Token>();
+    assert_send_sync::<TokenKind>();
+    assert_send_sync::<TokenDump>();
+
+    assert_send_sync::<Parser<'_>>();
+    assert_send_sync::<ParserOptions>();
+    assert_send_sync::<ParserError>();
+    assert_send_sync::<TextEdit>();
+    assert_send_sync::<LiteralAutolinks>();
+
+    assert_send_sync::<Document<'_>>();
+    assert_send_sync::<Node<'_>>();
+    assert_send_sync::<NodeKind>();
+    assert_send_sync::<NodeData>();
+    assert_send_sync::<AstError>();
+    assert_send_sync::<AlignType>();
+    assert_send_sync::<ReferenceType>();
+    assert_send_sync::<Heading<'_>>();
+    assert_send_sync::<ThematicBreak>();
+    assert_send_sync::<Blockquote<'_>>();
+    assert_send_sync::<List<'_>>();
+    assert_send_sync::<ListItem<'_>>();
+    assert_send_sync::<Code<'_>>();
+    assert_send_sync::<Definition<'_>>();
+    assert_send_sync::<Text<'_>>();
+    assert_send_sync::<Emphasis<'_>>();
+    assert_send_sync::<Strong<'_>>();
+    assert_send_sync::<InlineCode<'_>>();
+    assert_send_sync::<Break>();
+    assert_send_sync::<Link<'_>>();
+    assert_send_sync::<LinkReference<'_>>();
+    assert_send_sync::<Image<'_>>();
+    assert_send_sync::<ImageReference<'_>>();
+    assert_send_sync::<Paragraph<'_>>();
+    assert_send_sync::<Delete<'_>>();
+    assert_send_sync::<FootnoteDefinition<'_>>();
+    assert_send_sync::<FootnoteReference<'_>>();
+    assert_send_sync::<Table<'_>>();
+    assert_send_sync::<TableRow<'_>>();
+    assert_send_sync::<TableCell<'_>>();
+    assert_send_sync::<Html<'_>>();
+    assert_send_sync::<Custom<'_>>();
+
+    assert_send_sync::<Spans>();
+    assert_send_sync::<Style>();
+    assert_send_sync::<DocumentIndex<'_>>();
+    assert_send_sync::<Interner>();
+    assert_send_sync::<Stats>();
+    assert_send_sync::<BlockView<'_, '_>>();
+    assert_send_sync::<DocumentBuilder<'_>>();
+    assert_send_sync::<HeadingCollector<'_>>();
+    assert_send_sync::<LinkCollector>();
+    assert_send_sync::<CodeBlockCollector>();
+    assert_send_sync::<TextLength>();
+
+    assert_send_sync::<HtmlOptions>();
+    assert_send_sync::<SerializeOptions>();
+}
+
+/// Distinct documents parsed on 8 threads at once give the trees and HTML of a serial parse.
+#[test]
+fn parallel_parsing() {
+    let data = utils::test_data();
+
+    let sources = (0..100)
+        .map(|i| {
+            let (name, source) = &data[i % data.len()];
+
+            format!(
+                "# {} {}\n\n[link {}][{}]\n\n[{}]: /{}\n\n{}",
+                name, i, i, i, i, i, source
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let parse = |source: &str| {
+        let document = Parser::new(source).parse().unwrap().into_owned();
+
+        let html = document.to_html(&HtmlOptions::default());
+
+        (document, html)
+    };
+
+    let serial = sources
+        .iter()
+        .map(|source| parse(source))
+        .collect::<Vec<_>>();
+
+    let parallel = thread::scope(|scope| {
+        let handles = (0..8)
+            .map(|thread| {
+                let sources = &sources;
+
+                scope.spawn(move || {
+                    sources
+                        .iter()
+                        .enumerate()
+                        .skip(thread)
+                        .step_by(8)
+                        .map(|(i, source)| (i, parse(source)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut parsed = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        parsed.sort_by_key(|(i, _)| *i);
+
+        parsed
+            .into_iter()
+            .map(|(_, parsed)| parsed)
+            .collect::<Vec<_>>()
+    });
+
+    assert_eq!(parallel.len(), 100);
+    assert_eq!(parallel, serial);
+
+    // a document parsed on one thread is used on another.
+    let (document, _) = serial.into_iter().next().unwrap();
+
+    let html = thread::spawn(move || document.to_html(&HtmlOptions::default()))
+        .join()
+        .unwrap();
+
+    assert!(html.contains("<a href=\"/0\">link 0</a>"), "{}", html);
+}
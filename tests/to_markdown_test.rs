@@ -0,0 +1,447 @@
+use markdown_rs::{
+    ast::{Document, Emphasis, Node, NodeKind, Paragraph, Text},
+    parser::Parser,
+    to_markdown::{LineEnding, SerializeOptions},
+};
+
+mod utils;
+
+/// Files of `tests/data` exercising markup that [`Style`](markdown_rs::ast::style::Style)
+/// doesn't record, e.g. table padding, entities or escapes, they only round trip to the same
+/// tree.
+const NOT_LOSSLESS: &[&str] = &[
+    "edge_cases",
+    "emphasis",
+    "empty",
+    "links",
+    "lists",
+    "tables",
+];
+
+#[test]
+fn to_markdown_round_trip() {
+    utils::read_test_data(|s| {
+        let document = Parser::new(s).parse().unwrap();
+
+        let markdown = document.to_markdown(&SerializeOptions::default());
+
+        let reparsed = Parser::new(markdown.as_str()).parse().unwrap();
+
+        assert_eq!(document, reparsed, "round trip of:\n{}", markdown);
+    });
+}
+
+/// Empty cells, items, headings and link texts are kept as nodes without children, and
+/// serialize without dropping columns or items.
+#[test]
+fn empty_structures() {
+    let source = utils::test_data()
+        .into_iter()
+        .find(|(name, _)| name == "empty")
+        .unwrap()
+        .1;
+
+    let document = Parser::new(source.as_str()).parse().unwrap();
+
+    let empty = |kind| {
+        document
+            .descendants()
+            .filter(|node| node.kind() == kind && node.children().is_some_and(<[_]>::is_empty))
+            .count()
+    };
+
+    assert_eq!(empty(NodeKind::Heading), 2);
+    assert_eq!(empty(NodeKind::TableCell), 8);
+    assert_eq!(empty(NodeKind::ListItem), 3);
+    assert_eq!(empty(NodeKind::Link), 2);
+
+    let markdown = document.to_markdown(&SerializeOptions::default());
+
+    let rows = markdown
+        .lines()
+        .filter(|line| line.starts_with('|'))
+        .collect::<Vec<_>>();
+
+    assert_eq!(rows.len(), 5);
+    assert!(
+        rows.iter().all(|row| row.matches('|').count() == 4),
+        "{}",
+        markdown
+    );
+
+    assert!(
+        markdown.contains("* one\n*\n* three\n\n1.\n2. two\n\n> *\n> * [link](<>)\n"),
+        "{}",
+        markdown
+    );
+
+    let reparsed = Parser::new(markdown.as_str()).parse().unwrap();
+
+    assert_eq!(reparsed, document);
+    assert_eq!(
+        reparsed.to_html(&Default::default()),
+        document.to_html(&Default::default())
+    );
+}
+
+#[test]
+fn to_markdown_with_style_lossless() {
+    for (name, s) in utils::test_data() {
+        if NOT_LOSSLESS.contains(&name.as_str()) {
+            continue;
+        }
+
+        let s = s.as_str();
+
+        let (document, style) = Parser::new(s).parse_with_style().unwrap();
+
+        let markdown = document.to_markdown_with_style(&SerializeOptions::default(), &style);
+
+        assert_eq!(markdown, s.replace("\r\n", "\n"));
+
+        let options = SerializeOptions {
+            line_ending: LineEnding::Preserve,
+            ..Default::default()
+        };
+
+        assert_eq!(document.to_markdown_with_style(&options, &style), s);
+        assert_eq!(document, Parser::new(s).parse().unwrap());
+    }
+}
+
+#[test]
+fn line_endings_parse_equal() {
+    for (name, s) in utils::test_data() {
+        let lf = s.replace("\r\n", "\n");
+
+        let document = Parser::new(lf.as_str()).parse().unwrap();
+
+        for line_ending in ["\r\n", "\r"] {
+            let source = lf.replace('\n', line_ending);
+
+            let other = Parser::new(source.as_str()).parse().unwrap();
+
+            assert!(
+                other == document,
+                "{} with {:?} line endings",
+                name,
+                line_ending
+            );
+        }
+
+        for node in document.descendants() {
+            let value = match node {
+                Node::Text(text) => &text.value,
+                Node::InlineCode(code) => &code.value,
+                Node::Code(code) => &code.value,
+                Node::Html(html) => &html.value,
+                _ => continue,
+            };
+
+            assert!(!value.contains('\r'), "{}: {:?}", name, value);
+        }
+    }
+}
+
+#[test]
+fn line_ending_serialization() {
+    let source = "# Title\r\n\r\nSome\r\ntext.\r\n\r\n```rust\r\nfn main() {\r\n}\r\n```\r\n";
+
+    let (document, style) = Parser::new(source).parse_with_style().unwrap();
+
+    let with = |line_ending: LineEnding| {
+        let options = SerializeOptions {
+            line_ending,
+            ..Default::default()
+        };
+
+        (
+            document.to_markdown(&options),
+            document.to_markdown_with_style(&options, &style),
+        )
+    };
+
+    let lf = source.replace("\r\n", "\n");
+
+    assert_eq!(with(LineEnding::Lf), (lf.clone(), lf.clone()));
+    assert_eq!(
+        with(LineEnding::CrLf),
+        (source.to_owned(), source.to_owned())
+    );
+    // the line ending of the source is only known from its style.
+    assert_eq!(with(LineEnding::Preserve), (lf, source.to_owned()));
+
+    assert_eq!(LineEnding::detect("a\rb\r\n"), Some(LineEnding::Lf));
+    assert_eq!(LineEnding::detect("a\r\nb\n"), Some(LineEnding::CrLf));
+    assert_eq!(LineEnding::detect("a"), None);
+}
+
+/// Text values that read as markdown syntax somewhere in a paragraph.
+const ADVERSARIAL: &[&str] = &[
+    "# a",
+    "## a ##",
+    "#a",
+    "> a",
+    ">",
+    "- a",
+    "-",
+    "--",
+    "---",
+    "- - -",
+    "+ a",
+    "+",
+    "* a",
+    "*",
+    "**",
+    "***",
+    "* * *",
+    "_ _ _",
+    "___",
+    "1. a",
+    "1.",
+    "1) a",
+    "123456789. a",
+    "1234567890. a",
+    "2.5",
+    "a\n===",
+    "a\n---",
+    "===",
+    "=",
+    "a | b\n--- | ---",
+    "|-|",
+    ":--",
+    "```",
+    "~~~",
+    "``a``",
+    "`a`",
+    "a`b",
+    "    a",
+    "\ta",
+    "*a*",
+    "**a**",
+    "a*b*c",
+    "a * b",
+    "a ** b",
+    "2*3*4",
+    "*.*",
+    "(*)",
+    "a*",
+    "*a",
+    "_a_",
+    "__a__",
+    "snake_case_name",
+    "_snake",
+    "snake_",
+    "a _ b",
+    "(_a_)",
+    "~a~",
+    "~~a~~",
+    "a~b",
+    "~",
+    "[a]",
+    "[a]: /u",
+    "[a](/u)",
+    "![a](/u)",
+    "[",
+    "]",
+    "a]b",
+    "[^a]",
+    "<a>",
+    "<http://a.com>",
+    "<a@b.c>",
+    "</a>",
+    "<!-- a -->",
+    "<?a?>",
+    "<",
+    "a<b",
+    "a < b",
+    "&amp;",
+    "&#35;",
+    "&#x23;",
+    "&",
+    "a & b",
+    "&a",
+    "\\",
+    "a\\",
+    "\\a",
+    "\\*",
+    "\\\\",
+    "a\\\nb",
+    "!",
+    "![",
+    "a!",
+    "<!",
+    "www.a.com",
+    "http://a.com",
+    "a@b.c",
+    "$",
+    "%",
+    ":",
+    "|",
+    "{a}",
+    "'\"",
+    "a\nb",
+    "a\n# b",
+    "a\n> b",
+    "a\n- b",
+    "a\n1. b",
+    "a\n2) b",
+    "a\n```",
+    "a\n    b",
+    "a\n<div>",
+    "<div>",
+    "</div>",
+    "<div\nb",
+    "a\n===\nb",
+    "a\n\\",
+    "😀*😀*",
+];
+
+/// Paragraphs of adversarial text read back as the same tree.
+#[test]
+fn escaped_text_round_trip() {
+    let mut failures = vec![];
+
+    for text in ADVERSARIAL {
+        for value in [
+            text.to_string(),
+            format!("x {} y", text),
+            format!("x{}y", text),
+            format!("x\n{}", text),
+            format!("{}\nx", text),
+            format!("{} {}", text, text),
+        ] {
+            let text = || {
+                Node::Text(Text {
+                    value: value.as_str().into(),
+                    data: None,
+                })
+            };
+
+            let emphasis = Node::Emphasis(Emphasis {
+                children: vec![Node::Text(Text {
+                    value: "e".into(),
+                    data: None,
+                })],
+                data: None,
+            });
+
+            // alone, and around other nodes.
+            for children in [vec![text()], vec![text(), emphasis, text()]] {
+                let document = Document {
+                    children: vec![Node::Paragraph(Paragraph {
+                        children,
+                        data: None,
+                    })],
+                    data: None,
+                };
+
+                let markdown = document.to_markdown(&SerializeOptions::default());
+
+                let reparsed = Parser::new(markdown.as_str()).parse().unwrap();
+
+                if reparsed != document {
+                    failures.push(format!("{:?} written as {:?}", document, markdown));
+                }
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+/// Serializes `source` with `options`, and checks the output reads back as the same tree.
+fn assert_round_trip(source: &str, options: &SerializeOptions) {
+    let document = Parser::new(source).parse().unwrap();
+
+    let markdown = document.to_markdown(options);
+
+    let reparsed = Parser::new(markdown.as_str()).parse().unwrap();
+
+    assert_eq!(
+        document, reparsed,
+        "round trip of {:?} written as {:?}",
+        source, markdown
+    );
+}
+
+/// Nested lists whose first lines would make a line of three bullets, a thematic break.
+#[test]
+fn nested_bullet_lists_round_trip() {
+    for source in [
+        "- - *",
+        "* - *",
+        "+ + +",
+        "- - - a",
+        "1. - *",
+        "- * - *",
+        "- [ ] - *",
+    ] {
+        for bullet in ['*', '-', '+'] {
+            let options = SerializeOptions {
+                bullet,
+                ..Default::default()
+            };
+
+            assert_round_trip(source, &options);
+        }
+    }
+}
+
+/// Raw html on a continuation line of a paragraph, which must not start an html block.
+#[test]
+fn html_continuation_round_trip() {
+    for source in [
+        "aa\n    <div>",
+        "a\n\t<div>",
+        "a\n  <!-- b -->",
+        "a\n   <script>b",
+        "> a\n>     <div> b",
+        "- a\n      <pre>",
+        "a\n <a>",
+    ] {
+        assert_round_trip(source, &SerializeOptions::default());
+    }
+}
+
+#[test]
+fn setext_html_round_trip() {
+    let options = SerializeOptions {
+        setext: true,
+        ..Default::default()
+    };
+
+    for source in [
+        "# <a>",
+        "- # <div>x",
+        "## <!-- a -->",
+        "# <pre>\n\nb",
+        "a\n<div>\n=",
+    ] {
+        assert_round_trip(source, &options);
+    }
+}
+
+#[test]
+fn tilde_fence_info_round_trip() {
+    let options = SerializeOptions {
+        fence: '~',
+        ..Default::default()
+    };
+
+    for source in ["``` ~2)x\ny\n```", "``` ~\n```", "~~~ ~`a\n~~~"] {
+        assert_round_trip(source, &options);
+        assert_round_trip(source, &SerializeOptions::default());
+    }
+}
+
+#[test]
+fn list_item_leading_whitespace_round_trip() {
+    for source in [
+        "1. \n    <!--x",
+        "- \n   <div>",
+        "* a\n*\n    <!-- b -->\n\n  c",
+    ] {
+        assert_round_trip(source, &SerializeOptions::default());
+    }
+}
@@ -0,0 +1,95 @@
+//! Recovery of inline constructs left unclosed at the end of their block: their opening
+//! characters are kept as literal text, nothing is dropped, and the constructs after them still
+//! parse.
+
+use markdown_rs::{ast::Node, parser::Parser};
+
+/// Renders phrasing content compactly: text as a quoted string, inline code between
+/// backticks, links with their url and other nodes with their kind, children in brackets.
+fn phrasing(nodes: &[Node]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Node::Text(text) => format!("{:?}", text.value),
+            Node::InlineCode(code) => format!("`{}`", code.value),
+            Node::Link(link) => format!("link({})[{}]", link.url, phrasing(&link.children)),
+            node => format!(
+                "{}[{}]",
+                node.kind(),
+                phrasing(node.children().unwrap_or_default())
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns the phrasing content of every paragraph, heading and table cell of `source`.
+fn blocks(source: &str) -> Vec<String> {
+    let document = Parser::new(source).parse().unwrap();
+
+    document
+        .descendants()
+        .filter(|node| {
+            matches!(
+                node,
+                Node::Paragraph(_) | Node::Heading(_) | Node::TableCell(_)
+            )
+        })
+        .map(|node| phrasing(node.children().unwrap_or_default()))
+        .collect()
+}
+
+#[test]
+fn literal() {
+    for source in [
+        "**bold without close",
+        "*emphasis without close",
+        "_emphasis without close",
+        "***both without close",
+        "~~delete without close",
+        "`code without close",
+        "``code with a shorter close`",
+        "[link without close",
+        "![image without close",
+        "[link](destination without close",
+        "[link](<angle destination without close)",
+        "[link](destination \"title without close",
+        "[^footnote without close",
+        "<span without close",
+        "a <!-- comment without close",
+        "a [b *c ![d `e",
+    ] {
+        assert_eq!(blocks(source), [format!("{:?}", source)], "{:?}", source);
+    }
+}
+
+#[test]
+fn later_constructs() {
+    assert_eq!(blocks("*a [b](c)"), [r#""*a ", link(c)["b"]"#]);
+    assert_eq!(blocks("a **b `c` d"), [r#""a **b ", `c`, " d""#]);
+    assert_eq!(blocks("**a *b* c"), [r#""**a ", emphasis["b"], " c""#]);
+    assert_eq!(blocks("*a **b** c"), [r#""*a ", strong["b"], " c""#]);
+    assert_eq!(blocks("*a* *b"), [r#"emphasis["a"], " *b""#]);
+    assert_eq!(blocks("``a `b`"), [r#""``a ", `b`"#]);
+    assert_eq!(blocks("[a [b](c)"), [r#""[a ", link(c)["b"]"#]);
+
+    // a delimiter closing nothing inside a link stays in it.
+    assert_eq!(blocks("[*a](b)*"), [r#"link(b)["*a"], "*""#]);
+    assert_eq!(blocks("[a *b](c)"), [r#"link(c)["a *b"]"#]);
+}
+
+#[test]
+fn block_boundaries() {
+    assert_eq!(blocks("x *a\n\ny*"), [r#""x *a""#, r#""y*""#]);
+    assert_eq!(blocks("# **a `b\nc**"), [r#""**a `b""#, r#""c**""#]);
+    assert_eq!(blocks("- *a\n- b*"), [r#""*a""#, r#""b*""#]);
+    assert_eq!(blocks("> [a\n\n> b](c)"), [r#""[a""#, r#""b](c)""#]);
+    assert_eq!(
+        blocks("| `a | b** |\n|---|---|\n| *c | [d |"),
+        [r#""`a""#, r#""b**""#, r#""*c""#, r#""[d""#]
+    );
+
+    // a soft line break doesn't end the block.
+    assert_eq!(blocks("`a\nb`"), ["`a b`"]);
+    assert_eq!(blocks("*a\nb"), [r#""*a\nb""#]);
+}
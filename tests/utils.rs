@@ -1,22 +1,85 @@
-use std::{fs::read_to_string, path::PathBuf};
+#![allow(dead_code)]
 
-pub fn read_test_data<F>(mut f: F)
-where
-    F: FnMut(&str),
-{
+use std::{
+    env,
+    fs::{read_to_string, write},
+    path::PathBuf,
+};
+
+/// Returns the name and content of every markdown file of `tests/data`, sorted by name.
+pub fn test_data() -> Vec<(String, String)> {
     let dir: PathBuf = env!("CARGO_MANIFEST_DIR").into();
 
     let dir = dir.join("tests/data");
 
-    let paths = dir.read_dir().unwrap();
+    let mut files = vec![];
 
-    for path in paths {
+    for path in dir.read_dir().unwrap() {
         let path = path.unwrap().path();
 
-        let md = read_to_string(path.clone()).unwrap();
+        if path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+
+        let name = path.file_stem().unwrap().to_string_lossy().into_owned();
 
-        log::debug!("load test markdown document: {}", path.display());
+        files.push((name, read_to_string(&path).unwrap()));
+    }
+
+    files.sort();
+
+    files
+}
+
+pub fn read_test_data<F>(mut f: F)
+where
+    F: FnMut(&str),
+{
+    for (name, md) in test_data() {
+        log::debug!("load test markdown document: {}", name);
 
         f(&md);
     }
 }
+
+/// Compares `actual` to the snapshot `tests/snapshots/{name}.snap`, returns a description of
+/// the mismatch if they differ.
+///
+/// With the `UPDATE_SNAPSHOTS` environment variable set, the snapshot is written instead.
+pub fn check_snapshot(name: &str, actual: &str) -> Result<(), String> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.snap", name));
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        write(&path, actual).unwrap();
+
+        return Ok(());
+    }
+
+    let Ok(expected) = read_to_string(&path) else {
+        return Err(format!(
+            "{}: missing, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        ));
+    };
+
+    if expected == actual {
+        return Ok(());
+    }
+
+    let line = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(expected, actual)| expected != actual)
+        .unwrap_or(expected.lines().count().min(actual.lines().count()));
+
+    Err(format!(
+        "{}: differs from line {}\n  expected: {:?}\n  actual:   {:?}\n\
+         run with UPDATE_SNAPSHOTS=1 to accept the new output",
+        path.display(),
+        line + 1,
+        expected.lines().nth(line).unwrap_or_default(),
+        actual.lines().nth(line).unwrap_or_default()
+    ))
+}
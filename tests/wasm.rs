@@ -0,0 +1,24 @@
+//! Run in a headless browser with `wasm-pack test --headless --firefox -- --features wasm`.
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use markdown_rs::wasm::{parse_to_html, parse_to_json};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn json() {
+    let json = parse_to_json("# hi").unwrap();
+
+    assert!(json.starts_with(r#"{"type":"root","children":[{"type":"heading""#));
+}
+
+#[wasm_bindgen_test]
+fn html() {
+    assert_eq!(
+        parse_to_html("a <b>", false).unwrap(),
+        "<p>a &lt;b&gt;</p>\n"
+    );
+    assert_eq!(parse_to_html("a <b>", true).unwrap(), "<p>a <b></p>\n");
+}